@@ -56,6 +56,7 @@ let currentSearchEngine = 'google'; // Track current search engine
 const TOOLBAR_HEIGHT = 48;
 const SIDEBAR_WIDTH = 240;
 const MAX_CLOSED_TABS = 20;
+const AI_NOTIFY_AFTER_MS = 10000; // AI results slower than this get a desktop notification when unfocused
 
 const windowRegistry = new Map(); // windowId -> WindowContext
 const wcToWindow = new Map(); // webContents.id -> WindowContext (for IPC routing)
@@ -2800,6 +2801,7 @@ function injectTranslatePopup(wc, opts) {
 async function runAiAction(wc, action, text) {
   // Show loading popup, pass original text for later replacement
   injectAiPopup(wc, null, true, text);
+  const startedAt = Date.now();
 
   try {
     const config = await getAiConfig();
@@ -2851,11 +2853,21 @@ async function runAiAction(wc, action, text) {
     }
 
     injectAiPopup(wc, { text: resultText }, false, text);
+    notifySlowAiResult(startedAt, resultText);
   } catch (err) {
     injectAiPopup(wc, { error: err.message || 'AI request failed' }, false, text);
   }
 }
 
+// Announce a long AI task's result through the OS when the user has moved on
+function notifySlowAiResult(startedAt, resultText) {
+  if (Date.now() - startedAt < AI_NOTIFY_AFTER_MS || BaseWindow.getFocusedWindow()) return;
+  rustBridge.call('notify.show', {
+    title: cmL('ai.result_ready', 'AI result ready'),
+    body: resultText.substring(0, 200),
+  }).catch(() => {});
+}
+
 // Inject a floating result popup into the page
 function injectAiPopup(wc, result, loading, originalText) {
   if (wc.isDestroyed()) return;
@@ -2997,6 +3009,7 @@ const aiChatHistories = new Map(); // sessionId -> messages[]
 ipcMain.handle('ai-chat', async (_e, { provider, apiKey, message, sessionId, model }) => {
   if (!sessionId) sessionId = 'default';
   if (!aiChatHistories.has(sessionId)) aiChatHistories.set(sessionId, []);
+  const startedAt = Date.now();
   const history = aiChatHistories.get(sessionId);
   history.push({ role: 'user', content: message });
 
@@ -3032,6 +3045,7 @@ ipcMain.handle('ai-chat', async (_e, { provider, apiKey, message, sessionId, mod
       assistantText = data.choices?.[0]?.message?.content || 'No response';
     }
     history.push({ role: 'assistant', content: assistantText });
+    notifySlowAiResult(startedAt, assistantText);
     return { text: assistantText };
   } catch (err) {
     return { error: err.message || 'Network error' };
@@ -61,7 +61,12 @@ class RustBridge {
       ? path.dirname(rpcBin)
       : path.join(__dirname, '..');
 
-    this.process = spawn(rpcBin, [], {
+    // Forward `--profile <name>` / `--profile=<name>` so the backend opens that user profile
+    const argv = process.argv.slice(1);
+    const profileAt = argv.findIndex((a) => a === '--profile' || a.startsWith('--profile='));
+    const rpcArgs = profileAt === -1 ? [] : argv.slice(profileAt, argv[profileAt] === '--profile' ? profileAt + 2 : profileAt + 1);
+
+    this.process = spawn(rpcBin, rpcArgs, {
       stdio: ['pipe', 'pipe', 'pipe'],
       cwd,
       env: { ...process.env, GITBROWSER_DATA_DIR: app.isPackaged ? app.getPath('userData') : cwd },
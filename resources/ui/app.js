@@ -204,7 +204,9 @@ function applySettingsData(data) {
   if (data.general) {
     sv('s-language', data.general.language);
     sv('s-startup', data.general.startup_behavior);
-    sv('s-search', data.general.default_search_engine);
+  }
+  if (data.search) {
+    sv('s-search', data.search.default_engine);
   }
   if (data.privacy) {
     st('s-trackers', data.privacy.tracker_blocking);
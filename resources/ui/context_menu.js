@@ -0,0 +1,132 @@
+(function(){
+if(window.__gb_contextMenu)return;
+
+var CSS = '#gb-ctxmenu{position:fixed;z-index:2147483647;display:none;min-width:200px;max-width:320px;padding:4px;background:#161b22;border:1px solid #30363d;border-radius:8px;box-shadow:0 8px 24px rgba(0,0,0,0.5);font-family:-apple-system,BlinkMacSystemFont,"Segoe UI","Noto Sans",Helvetica,Arial,sans-serif;font-size:13px;color:#e6edf3;user-select:none;-webkit-user-select:none}'
++'#gb-ctxmenu.gb-open{display:block}'
++'.gb-ctx-item{display:block;width:100%;padding:6px 10px;border:none;background:none;color:inherit;font:inherit;text-align:left;cursor:pointer;border-radius:6px;white-space:nowrap;overflow:hidden;text-overflow:ellipsis}'
++'.gb-ctx-item:hover,.gb-ctx-item:focus{background:#1f6feb;color:#fff;outline:none}'
++'.gb-ctx-item:disabled{color:#484f58;cursor:default;background:none}'
++'.gb-ctx-sep{height:1px;margin:4px 6px;background:#30363d}';
+
+var s = document.createElement('style');
+s.textContent = CSS;
+(document.head || document.documentElement).appendChild(s);
+
+var menu = document.createElement('div');
+menu.id = 'gb-ctxmenu';
+menu.setAttribute('role', 'menu');
+document.documentElement.appendChild(menu);
+
+var ipc = function(cmd, data) { if (window.__gb_ipc) window.__gb_ipc(cmd, data); };
+var toast = function(msg) { if (window.__gb_showToast) window.__gb_showToast(msg); };
+
+function isWebUrl(url) { return /^https?:\/\//i.test(url || ''); }
+
+function copyText(text) {
+  if (navigator.clipboard && navigator.clipboard.writeText) {
+    return navigator.clipboard.writeText(text);
+  }
+  // Pages without the async clipboard (plain http) fall back to a hidden field
+  var area = document.createElement('textarea');
+  area.value = text;
+  area.style.cssText = 'position:fixed;top:-1000px;opacity:0';
+  document.documentElement.appendChild(area);
+  area.select();
+  var ok = document.execCommand('copy');
+  area.remove();
+  return ok ? Promise.resolve() : Promise.reject();
+}
+
+// Items for what was right-clicked: a link, an image, selected text
+function itemsFor(target) {
+  var link = target.closest && target.closest('a[href]');
+  var img = target.closest && target.closest('img');
+  var src = img ? (img.currentSrc || img.src) : '';
+  var selection = String(window.getSelection ? window.getSelection() : '').trim();
+  var nav = window.__gb_navState || { back: history.length > 1, forward: true };
+  var items = [
+    { label: 'Back', disabled: !nav.back, run: function() { ipc('go_back', {}); } },
+    { label: 'Forward', disabled: !nav.forward, run: function() { ipc('go_forward', {}); } },
+    { label: 'Reload', run: function() { location.reload(); } }
+  ];
+  if (link && isWebUrl(link.href)) {
+    items.push(null);
+    items.push({ label: 'Open Link in New Tab', run: function() { ipc('open_link_in_new_tab', { url: link.href }); } });
+    items.push({ label: 'Copy Link', run: function() {
+      copyText(link.href).then(function() { toast('Link copied'); }, function() { toast('Could not copy the link'); });
+    }});
+  }
+  if (isWebUrl(src)) {
+    items.push(null);
+    items.push({ label: 'Save Image', run: function() { ipc('save_image', { url: src }); } });
+    items.push({ label: 'Save Image As…', run: function() { ipc('save_image', { url: src, ask: true }); } });
+  }
+  if (selection) {
+    var shown = selection.length > 24 ? selection.slice(0, 24) + '…' : selection;
+    items.push(null);
+    items.push({ label: 'Search for “' + shown + '”', run: function() { ipc('search_selection', { text: selection }); } });
+    items.push({ label: 'Ask AI about Selection', run: function() {
+      ipc('ask_ai', { text: selection, url: location.href });
+    }});
+  }
+  return items;
+}
+
+function closeMenu() {
+  menu.className = '';
+  menu.innerHTML = '';
+}
+
+function openMenu(items, x, y) {
+  menu.innerHTML = '';
+  items.forEach(function(item) {
+    if (!item) {
+      var sep = document.createElement('div');
+      sep.className = 'gb-ctx-sep';
+      menu.appendChild(sep);
+      return;
+    }
+    var b = document.createElement('button');
+    b.className = 'gb-ctx-item';
+    b.setAttribute('role', 'menuitem');
+    b.textContent = item.label;
+    b.disabled = !!item.disabled;
+    b.onclick = function(e) { e.stopPropagation(); closeMenu(); item.run(); };
+    menu.appendChild(b);
+  });
+  menu.className = 'gb-open';
+  // Keep the whole menu on screen
+  var w = menu.offsetWidth, h = menu.offsetHeight;
+  menu.style.left = Math.max(0, Math.min(x, window.innerWidth - w - 4)) + 'px';
+  menu.style.top = Math.max(0, Math.min(y, window.innerHeight - h - 4)) + 'px';
+  var first = menu.querySelector('.gb-ctx-item:not(:disabled)');
+  if (first) first.focus();
+}
+
+window.__gb_contextMenu = openMenu;
+
+document.addEventListener('contextmenu', function(e) {
+  // Shift+right-click still gets the webview's own menu, and the browser UI keeps its own
+  if (e.shiftKey) return;
+  if (e.target.closest && e.target.closest('#gb-toolbar,#gb-findbar,#gb-ctxmenu,input,textarea,[contenteditable=""],[contenteditable="true"]')) return;
+  e.preventDefault();
+  openMenu(itemsFor(e.target), e.clientX, e.clientY);
+}, true);
+
+document.addEventListener('mousedown', function(e) {
+  if (menu.className && !menu.contains(e.target)) closeMenu();
+}, true);
+document.addEventListener('keydown', function(e) {
+  if (!menu.className) return;
+  if (e.key === 'Escape') { e.preventDefault(); closeMenu(); return; }
+  if (e.key !== 'ArrowDown' && e.key !== 'ArrowUp') return;
+  e.preventDefault();
+  var items = Array.prototype.slice.call(menu.querySelectorAll('.gb-ctx-item:not(:disabled)'));
+  var i = items.indexOf(document.activeElement);
+  var next = e.key === 'ArrowDown' ? (i + 1) % items.length : (i <= 0 ? items.length - 1 : i - 1);
+  if (items[next]) items[next].focus();
+}, true);
+window.addEventListener('blur', closeMenu);
+window.addEventListener('scroll', closeMenu, true);
+window.addEventListener('resize', closeMenu);
+})();
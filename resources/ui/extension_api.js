@@ -0,0 +1,94 @@
+// Extension API shim — evaluated before content scripts and background pages.
+// Each extension gets its own `chrome.runtime`/`chrome.storage` objects from
+// __gb_ext_api(id); requests travel over the webview IPC and replies come
+// back from Rust.
+(function(){
+if (window.__gb_ext_api) return;
+
+var listeners = {};
+var pending = {};
+var seq = 0;
+
+function post(data) {
+  if (window.ipc) window.ipc.postMessage(JSON.stringify(data));
+}
+
+window.__gb_ext_api = function(extId) {
+  var onMessage = {
+    addListener: function(fn) { (listeners[extId] = listeners[extId] || []).push(fn); },
+    removeListener: function(fn) {
+      listeners[extId] = (listeners[extId] || []).filter(function(l) { return l !== fn; });
+    },
+    hasListener: function(fn) { return (listeners[extId] || []).indexOf(fn) !== -1; }
+  };
+  function request(data, callback) {
+    var id = ++seq;
+    return new Promise(function(resolve, reject) {
+      pending[id] = function(response, error) {
+        if (callback) callback(response);
+        if (error) reject(new Error(error)); else resolve(response);
+      };
+      data.ext = extId;
+      data.id = id;
+      post(data);
+    });
+  }
+  function sendMessage(message, callback) {
+    return request({ cmd: 'ext_message', message: message }, callback);
+  }
+  // storage.local.get accepts null, a key, a list of keys, or an object of defaults
+  function get(keys, callback) {
+    var defaults = {};
+    var list = null;
+    if (typeof keys === 'string') list = [keys];
+    else if (Array.isArray(keys)) list = keys;
+    else if (keys && typeof keys === 'object') { defaults = keys; list = Object.keys(keys); }
+    return request({ cmd: 'ext_storage', op: 'get', keys: list }).then(function(items) {
+      var result = Object.assign({}, defaults, items || {});
+      if (callback) callback(result);
+      return result;
+    });
+  }
+  function set(items, callback) {
+    return request({ cmd: 'ext_storage', op: 'set', items: items || {} }, callback);
+  }
+  function remove(keys, callback) {
+    return request({ cmd: 'ext_storage', op: 'remove', keys: [].concat(keys) }, callback);
+  }
+  return {
+    runtime: { id: extId, sendMessage: sendMessage, onMessage: onMessage },
+    storage: { local: { get: get, set: set, remove: remove } }
+  };
+};
+
+// Reply to a sendMessage call — called from Rust
+window.__gb_ext_reply = function(id, response, error) {
+  var cb = pending[id];
+  delete pending[id];
+  if (cb) cb(response, error);
+};
+
+// Message delivered to a background page — called from Rust. A listener
+// answers with sendResponse, or returns true to answer asynchronously;
+// otherwise the sender gets an empty response.
+window.__gb_ext_deliver = function(extId, messageId, message, sender) {
+  var done = false;
+  var async = false;
+  function sendResponse(response) {
+    if (done) return;
+    done = true;
+    post({ cmd: 'ext_reply', id: messageId, response: response === undefined ? null : response });
+  }
+  (listeners[extId] || []).forEach(function(fn) {
+    try { if (fn(message, sender, sendResponse) === true) async = true; } catch (e) { console.error(e); }
+  });
+  if (!async) sendResponse(null);
+};
+
+// Message pushed to an extension's content scripts — called from Rust
+window.__gb_ext_dispatch = function(extId, message) {
+  (listeners[extId] || []).forEach(function(fn) {
+    try { fn(message, { id: extId }, function() {}); } catch (e) { console.error(e); }
+  });
+};
+})();
@@ -0,0 +1,142 @@
+(function(){
+if(window.__gb_findInPage)return;
+
+var CSS = '#gb-findbar{position:fixed;top:80px;right:16px;z-index:2147483647;display:none;align-items:center;gap:4px;padding:6px 8px;background:#161b22;border:1px solid #30363d;border-radius:8px;box-shadow:0 4px 12px rgba(0,0,0,0.4);font-family:-apple-system,BlinkMacSystemFont,"Segoe UI","Noto Sans",Helvetica,Arial,sans-serif;font-size:12px;color:#e6edf3}'
++'#gb-findbar.gb-open{display:flex}'
++'#gb-find-input{width:200px;height:26px;border:1px solid #30363d;border-radius:6px;background:#0d1117;color:#e6edf3;padding:0 8px;font:inherit;outline:none}'
++'#gb-find-input:focus{border-color:#1f6feb}'
++'#gb-find-count{min-width:56px;text-align:center;color:#7d8590}'
++'#gb-findbar button{width:26px;height:26px;border:none;background:none;color:#7d8590;cursor:pointer;border-radius:6px;font-size:12px}'
++'#gb-findbar button:hover{background:#1c2128;color:#e6edf3}'
++'#gb-findbar button.gb-on{color:#58a6ff}'
++'mark.gb-find-hit{background:#bb800966;color:inherit;border-radius:2px}'
++'mark.gb-find-hit.gb-find-current{background:#f2cc60;color:#0d1117}';
+
+var s = document.createElement('style');
+s.textContent = CSS;
+(document.head || document.documentElement).appendChild(s);
+
+var bar = document.createElement('div');
+bar.id = 'gb-findbar';
+bar.innerHTML = '<input id="gb-find-input" type="text" placeholder="Find in page" spellcheck="false" autocomplete="off"/>'
++ '<span id="gb-find-count"></span>'
++ '<button id="gb-find-case" title="Match case">Aa</button>'
++ '<button id="gb-find-prev" title="Previous (Shift+Enter)">▲</button>'
++ '<button id="gb-find-next" title="Next (Enter)">▼</button>'
++ '<button id="gb-find-close" title="Close (Escape)">×</button>';
+document.documentElement.appendChild(bar);
+
+var input = document.getElementById('gb-find-input');
+var count = document.getElementById('gb-find-count');
+var caseBtn = document.getElementById('gb-find-case');
+var caseSensitive = false;
+var ipc = function(cmd, data) { if (window.__gb_ipc) window.__gb_ipc(cmd, data); };
+
+// Highlights of the current search, in document order
+var hits = [];
+var current = -1;
+var lastSearch = null;
+
+function clearHits() {
+  hits.forEach(function(m) {
+    var p = m.parentNode;
+    if (!p) return;
+    p.replaceChild(document.createTextNode(m.textContent), m);
+    p.normalize();
+  });
+  hits = [];
+  current = -1;
+}
+
+function markMatches(query) {
+  var walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT, { acceptNode: function(n) {
+    var p = n.parentNode;
+    if (!p || /^(SCRIPT|STYLE|NOSCRIPT|TEXTAREA)$/.test(p.nodeName)) return NodeFilter.FILTER_REJECT;
+    if (p.closest && p.closest('#gb-toolbar,#gb-status,#gb-findbar')) return NodeFilter.FILTER_REJECT;
+    return NodeFilter.FILTER_ACCEPT;
+  }});
+  var nodes = [], n;
+  while ((n = walker.nextNode())) nodes.push(n);
+  var needle = caseSensitive ? query : query.toLowerCase();
+  nodes.forEach(function(node) {
+    var src = node.nodeValue;
+    var text = caseSensitive ? src : src.toLowerCase();
+    var i = text.indexOf(needle);
+    if (i < 0) return;
+    var frag = document.createDocumentFragment(), last = 0;
+    while (i >= 0) {
+      frag.appendChild(document.createTextNode(src.slice(last, i)));
+      var m = document.createElement('mark');
+      m.className = 'gb-find-hit';
+      m.textContent = src.slice(i, i + needle.length);
+      frag.appendChild(m);
+      hits.push(m);
+      last = i + needle.length;
+      i = text.indexOf(needle, last);
+    }
+    frag.appendChild(document.createTextNode(src.slice(last)));
+    node.parentNode.replaceChild(frag, node);
+  });
+}
+
+// Moves to the next (or previous) match of `query`, searching afresh when
+// the query or case sensitivity changed — called from Rust
+window.__gb_findInPage = function(query, matchCase, forward) {
+  var search = query + '\u0000' + matchCase;
+  if (search !== lastSearch) {
+    clearHits();
+    lastSearch = search;
+    caseSensitive = matchCase;
+    if (query) markMatches(query);
+  }
+  if (hits.length) {
+    if (current >= 0) hits[current].classList.remove('gb-find-current');
+    current = forward ? (current + 1) % hits.length : (current <= 0 ? hits.length - 1 : current - 1);
+    hits[current].classList.add('gb-find-current');
+    hits[current].scrollIntoView({ block: 'center' });
+  }
+  var result = { query: query, matches: hits.length, active: hits.length ? current + 1 : 0 };
+  count.textContent = query ? (result.active + '/' + result.matches) : '';
+  ipc('find_result', result);
+};
+
+// Shows the find bar with the tab's last search — called from Rust
+window.__gb_openFind = function(last) {
+  bar.className = 'gb-open';
+  if (last && last.query && !input.value) {
+    input.value = last.query;
+    caseSensitive = !!last.case_sensitive;
+  }
+  caseBtn.className = caseSensitive ? 'gb-on' : '';
+  input.focus();
+  input.select();
+  if (input.value) find(true);
+};
+
+function find(forward) {
+  ipc('find', { query: input.value, case_sensitive: caseSensitive, forward: forward });
+}
+
+function closeFind() {
+  bar.className = '';
+  clearHits();
+  lastSearch = null;
+  count.textContent = '';
+  ipc('find_close', {});
+}
+
+input.addEventListener('input', function() { lastSearch = null; find(true); });
+input.addEventListener('keydown', function(e) {
+  if (e.key === 'Enter') { e.preventDefault(); find(!e.shiftKey); }
+  if (e.key === 'Escape') { e.preventDefault(); closeFind(); }
+});
+caseBtn.onclick = function() {
+  caseSensitive = !caseSensitive;
+  caseBtn.className = caseSensitive ? 'gb-on' : '';
+  find(true);
+  input.focus();
+};
+document.getElementById('gb-find-prev').onclick = function() { find(false); };
+document.getElementById('gb-find-next').onclick = function() { find(true); };
+document.getElementById('gb-find-close').onclick = closeFind;
+})();
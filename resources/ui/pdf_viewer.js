@@ -0,0 +1,158 @@
+(function(){
+// The PDF viewer page: pdf.js renders each page into a canvas as it scrolls
+// into view. Rust fetches the PDF and serves it from /pdf-data.
+var root = document.getElementById('pdf-viewer');
+var src = root.dataset.src;
+var pagesEl = document.getElementById('pdf-pages');
+var pageInput = document.getElementById('pdf-page');
+var countEl = document.getElementById('pdf-count');
+var zoomEl = document.getElementById('pdf-zoom');
+var ipc = function(cmd, data) { if (window.__gb_ipc) window.__gb_ipc(cmd, data); };
+
+var ZOOMS = [0.5, 0.67, 0.75, 0.9, 1, 1.1, 1.25, 1.5, 1.75, 2, 3, 4];
+var doc = null;
+var scale = 1;
+var fitWidth = true;
+var pages = [];
+var rendered = {};
+
+function status(text) {
+  var el = document.getElementById('pdf-status');
+  el.textContent = text || '';
+  el.style.display = text ? 'block' : 'none';
+}
+
+document.getElementById('pdf-download').onclick = function() { ipc('pdf_download', { src: src }); };
+if (!root.dataset.viewer) {
+  status('The PDF viewer is not installed. Download the file to open it.');
+  return;
+}
+
+function loadScript(url, done) {
+  var s = document.createElement('script');
+  s.src = url;
+  s.onload = done;
+  s.onerror = function() { status('The PDF viewer could not be loaded.'); };
+  document.head.appendChild(s);
+}
+
+// Called from Rust once the PDF is fetched, or failed to be
+window.__gb_pdfReady = function() {
+  loadScript('/pdfjs/pdf.min.js', function() {
+    var lib = window.pdfjsLib;
+    lib.GlobalWorkerOptions.workerSrc = '/pdfjs/pdf.worker.min.js';
+    lib.getDocument('/pdf-data?src=' + encodeURIComponent(src)).promise.then(open, function(e) {
+      status('This PDF could not be opened: ' + (e && e.message || e));
+    });
+  });
+};
+window.__gb_pdfFailed = function(message) { status('This PDF could not be loaded: ' + message); };
+
+function open(pdf) {
+  doc = pdf;
+  status('');
+  countEl.textContent = '/ ' + pdf.numPages;
+  pageInput.max = pdf.numPages;
+  doc.getMetadata().then(function(meta) {
+    if (meta.info && meta.info.Title) document.title = meta.info.Title;
+  }, function() {});
+  var sizes = [];
+  var next = function(n) {
+    if (n > pdf.numPages) { layout(sizes); return; }
+    pdf.getPage(n).then(function(page) {
+      var v = page.getViewport({ scale: 1 });
+      sizes.push({ width: v.width, height: v.height });
+      next(n + 1);
+    });
+  };
+  next(1);
+}
+
+function layout(sizes) {
+  pagesEl.innerHTML = '';
+  pages = sizes.map(function(size, i) {
+    var el = document.createElement('div');
+    el.className = 'pdf-page';
+    el.dataset.page = i + 1;
+    pagesEl.appendChild(el);
+    return { el: el, size: size };
+  });
+  applyZoom();
+}
+
+function applyZoom() {
+  if (fitWidth && pages.length) {
+    var widest = Math.max.apply(null, pages.map(function(p) { return p.size.width; }));
+    scale = Math.max(0.25, (pagesEl.clientWidth - 48) / widest);
+  }
+  zoomEl.textContent = Math.round(scale * 100) + '%';
+  rendered = {};
+  pages.forEach(function(p) {
+    p.el.innerHTML = '';
+    p.el.style.width = Math.floor(p.size.width * scale) + 'px';
+    p.el.style.height = Math.floor(p.size.height * scale) + 'px';
+  });
+  renderVisible();
+}
+
+function renderPage(n) {
+  if (rendered[n]) return;
+  rendered[n] = true;
+  var at = scale;
+  doc.getPage(n).then(function(page) {
+    if (at !== scale) return;
+    var ratio = window.devicePixelRatio || 1;
+    var v = page.getViewport({ scale: scale * ratio });
+    var canvas = document.createElement('canvas');
+    canvas.width = v.width;
+    canvas.height = v.height;
+    canvas.style.width = '100%';
+    canvas.style.height = '100%';
+    pages[n - 1].el.appendChild(canvas);
+    page.render({ canvasContext: canvas.getContext('2d'), viewport: v });
+  });
+}
+
+// Renders the pages in or near view, and shows which one is current
+function renderVisible() {
+  var view = pagesEl.getBoundingClientRect();
+  var current = 0;
+  pages.forEach(function(p, i) {
+    var r = p.el.getBoundingClientRect();
+    if (r.bottom > view.top - view.height && r.top < view.bottom + view.height) renderPage(i + 1);
+    if (!current && r.bottom > view.top + view.height / 3) current = i + 1;
+  });
+  if (current && document.activeElement !== pageInput) pageInput.value = current;
+}
+pagesEl.addEventListener('scroll', renderVisible);
+window.addEventListener('resize', function() { if (fitWidth) applyZoom(); });
+
+function goTo(n) {
+  if (!pages.length) return;
+  n = Math.min(Math.max(1, n), pages.length);
+  pages[n - 1].el.scrollIntoView();
+  pageInput.value = n;
+}
+document.getElementById('pdf-prev').onclick = function() { goTo((parseInt(pageInput.value, 10) || 1) - 1); };
+document.getElementById('pdf-next').onclick = function() { goTo((parseInt(pageInput.value, 10) || 1) + 1); };
+pageInput.addEventListener('change', function() { goTo(parseInt(pageInput.value, 10) || 1); });
+
+function zoomBy(dir) {
+  fitWidth = false;
+  var i = ZOOMS.findIndex(function(z) { return dir > 0 ? z > scale + 0.001 : z >= scale - 0.001; });
+  if (dir > 0) scale = i < 0 ? ZOOMS[ZOOMS.length - 1] : ZOOMS[i];
+  else scale = i < 0 ? ZOOMS[ZOOMS.length - 1] : ZOOMS[Math.max(0, i - 1)];
+  applyZoom();
+}
+document.getElementById('pdf-zoom-in').onclick = function() { zoomBy(1); };
+document.getElementById('pdf-zoom-out').onclick = function() { zoomBy(-1); };
+document.getElementById('pdf-fit').onclick = function() { fitWidth = true; applyZoom(); };
+document.addEventListener('keydown', function(e) {
+  if (e.target === pageInput) return;
+  if (e.key === 'ArrowRight' || (e.key === 'PageDown' && e.ctrlKey)) goTo((parseInt(pageInput.value, 10) || 1) + 1);
+  if (e.key === 'ArrowLeft' || (e.key === 'PageUp' && e.ctrlKey)) goTo((parseInt(pageInput.value, 10) || 1) - 1);
+});
+
+status('Loading…');
+ipc('pdf_load', { src: src });
+})();
@@ -0,0 +1,150 @@
+(function(){
+if(window.__gb_requestFilter)return;
+window.__gb_requestFilter = true;
+// Internal pages make no requests worth filtering
+if (!/^https?:$/.test(location.protocol) || location.hostname === 'gb.localhost') return;
+
+// Requests the page makes from script are held until Rust's privacy engine
+// decides on them: blocked, allowed, or upgraded to https. Resources in the
+// page's own HTML are fetched by the parser before any script runs, so only
+// top-level navigations (checked by Rust) and script-made requests pass here.
+var post = function(data) { if (window.ipc) window.ipc.postMessage(JSON.stringify(data)); };
+var verdicts = {};
+var waiting = {};
+var pending = {};
+var nextId = 1;
+
+function absolute(url) {
+  try { return new URL(String(url), document.baseURI).href; } catch (e) { return ''; }
+}
+
+// Calls done(verdict) once the request for url is decided, at once if it was before
+function check(url, type, done) {
+  var href = absolute(url);
+  if (!/^https?:\/\//i.test(href)) { done({ action: 'allow' }); return; }
+  var key = type + ' ' + href;
+  if (verdicts[key]) { done(verdicts[key]); return; }
+  if (waiting[key]) { waiting[key].push(done); return; }
+  waiting[key] = [done];
+  var id = nextId++;
+  pending[id] = key;
+  post({ cmd: 'check_request', id: id, url: href, type: type });
+}
+
+// Called from Rust with the decision on request id
+window.__gb_requestVerdict = function(id, verdict) {
+  var key = pending[id];
+  if (!key) return;
+  delete pending[id];
+  verdicts[key] = verdict;
+  var callbacks = waiting[key] || [];
+  delete waiting[key];
+  callbacks.forEach(function(done) { done(verdict); });
+};
+
+function urlFor(verdict, url) { return verdict.action === 'upgrade' ? verdict.url : url; }
+
+// fetch()
+var origFetch = window.fetch;
+if (origFetch) {
+  window.fetch = function(input, init) {
+    var url = (input && typeof input === 'object' && 'url' in input) ? input.url : String(input);
+    return new Promise(function(resolve, reject) {
+      check(url, 'xmlhttprequest', function(v) {
+        if (v.action === 'block') { reject(new TypeError('Failed to fetch')); return; }
+        if (v.action === 'upgrade') {
+          input = (input && typeof input === 'object' && 'url' in input) ? new Request(v.url, input) : v.url;
+        }
+        origFetch.call(window, input, init).then(resolve, reject);
+      });
+    });
+  };
+}
+
+// XMLHttpRequest: send() waits for the decision; synchronous requests cannot
+var xhrProto = XMLHttpRequest.prototype;
+var origOpen = xhrProto.open, origSend = xhrProto.send, origHeader = xhrProto.setRequestHeader;
+xhrProto.open = function(method, url, async) {
+  this.__gb_open = arguments.length > 2 && async === false ? null : Array.prototype.slice.call(arguments);
+  this.__gb_headers = [];
+  return origOpen.apply(this, arguments);
+};
+xhrProto.setRequestHeader = function(name, value) {
+  if (this.__gb_headers) this.__gb_headers.push([name, value]);
+  return origHeader.apply(this, arguments);
+};
+xhrProto.send = function(body) {
+  var xhr = this, open = xhr.__gb_open;
+  if (!open) return origSend.apply(xhr, arguments);
+  check(open[1], 'xmlhttprequest', function(v) {
+    if (v.action === 'block') {
+      xhr.abort();
+      xhr.dispatchEvent(new ProgressEvent('error'));
+      xhr.dispatchEvent(new ProgressEvent('loadend'));
+      return;
+    }
+    if (v.action === 'upgrade') {
+      var args = open.slice();
+      args[1] = v.url;
+      origOpen.apply(xhr, args);
+      xhr.__gb_headers.forEach(function(h) { origHeader.call(xhr, h[0], h[1]); });
+    }
+    origSend.call(xhr, body);
+  });
+};
+
+// navigator.sendBeacon() reports success at once; a blocked beacon is dropped
+if (navigator.sendBeacon) {
+  var origBeacon = navigator.sendBeacon;
+  navigator.sendBeacon = function(url, data) {
+    check(url, 'ping', function(v) {
+      if (v.action !== 'block') origBeacon.call(navigator, urlFor(v, url), data);
+    });
+    return true;
+  };
+}
+
+// src of scripts, images and frames set from script
+var ELEMENT_TYPES = [
+  [window.HTMLScriptElement, 'script'],
+  [window.HTMLImageElement, 'image'],
+  [window.HTMLIFrameElement, 'sub_frame']
+];
+function typeOf(el) {
+  for (var i = 0; i < ELEMENT_TYPES.length; i++) {
+    if (ELEMENT_TYPES[i][0] && el instanceof ELEMENT_TYPES[i][0]) return ELEMENT_TYPES[i][1];
+  }
+  return null;
+}
+function setSrc(el, type, url, apply) {
+  check(url, type, function(v) {
+    if (v.action === 'block') {
+      // Script loaders wait for one of load or error
+      el.dispatchEvent(new Event('error'));
+      return;
+    }
+    apply(urlFor(v, url));
+  });
+}
+ELEMENT_TYPES.forEach(function(entry) {
+  if (!entry[0]) return;
+  var desc = Object.getOwnPropertyDescriptor(entry[0].prototype, 'src');
+  if (!desc || !desc.set) return;
+  Object.defineProperty(entry[0].prototype, 'src', {
+    configurable: true,
+    enumerable: desc.enumerable,
+    get: desc.get,
+    set: function(url) {
+      var el = this;
+      setSrc(el, entry[1], url, function(final) { desc.set.call(el, final); });
+    }
+  });
+});
+var origSetAttribute = Element.prototype.setAttribute;
+Element.prototype.setAttribute = function(name, value) {
+  var type = String(name).toLowerCase() === 'src' ? typeOf(this) : null;
+  if (!type) return origSetAttribute.apply(this, arguments);
+  var el = this;
+  setSrc(el, type, value, function(final) { origSetAttribute.call(el, name, final); });
+};
+})();
@@ -112,7 +112,7 @@
     if (!data) return;
     var sv = function(id, v) { var e = document.getElementById(id); if (e) e.value = v; };
     var st = function(id, v) { var e = document.getElementById(id); if (e) { if (v) e.classList.add('on'); else e.classList.remove('on'); } };
-    if (data.general) { sv('s-language', data.general.language); sv('s-startup', data.general.startup_behavior); sv('s-search', data.general.default_search_engine); }
+    if (data.general) { sv('s-language', data.general.language); sv('s-startup', data.general.startup_behavior); } if (data.search) { sv('s-search', data.search.default_engine); }
     if (data.privacy) { st('s-trackers', data.privacy.tracker_blocking); st('s-ads', data.privacy.ad_blocking); st('s-https', data.privacy.https_enforcement); st('s-doh', data.privacy.dns_over_https); st('s-fingerprint', data.privacy.anti_fingerprinting); st('s-clearonexit', data.privacy.clear_data_on_exit); }
     if (data.appearance) { sv('s-theme', data.appearance.theme); sv('s-accent', data.appearance.accent_color); sv('s-fontsize', data.appearance.font_size); }
     if (data.performance) { sv('s-suspend', data.performance.tab_suspend_timeout_minutes); st('s-lazyimg', data.performance.lazy_load_images); }
@@ -9,6 +9,7 @@ var CSS = '#gb-toolbar{position:fixed;top:0;left:0;right:0;z-index:2147483647;fo
 +'.gb-tab:hover{background:#1c2128;color:#e6edf3}'
 +'.gb-tab.active{background:#0d1117;color:#e6edf3;border-color:#30363d}'
 +'.gb-tab.active::after{content:"";position:absolute;bottom:-1px;left:0;right:0;height:2px;background:#1f6feb;border-radius:2px 2px 0 0}'
++'.gb-tab-icon{width:16px;height:16px;flex-shrink:0;object-fit:contain}'
 +'.gb-tab-x{width:16px;height:16px;border:none;background:none;color:#484f58;cursor:pointer;border-radius:4px;display:flex;align-items:center;justify-content:center;font-size:14px;line-height:1;padding:0;opacity:0;transition:all .12s;flex-shrink:0}'
 +'.gb-tab:hover .gb-tab-x{opacity:1}'
 +'.gb-tab-x:hover{background:#da3633;color:#fff}'
@@ -19,6 +20,7 @@ var CSS = '#gb-toolbar{position:fixed;top:0;left:0;right:0;z-index:2147483647;fo
 +'#gb-nav button{width:28px;height:28px;border:none;background:none;color:#7d8590;cursor:pointer;border-radius:6px;display:flex;align-items:center;justify-content:center;font-size:14px;transition:all .12s}'
 +'#gb-nav button:hover{background:#1c2128;color:#e6edf3}'
 +'#gb-nav button:active{transform:scale(0.9)}'
++'#gb-nav button:disabled{color:#30363d;background:none;cursor:default;transform:none}'
 +'#gb-urlbox{flex:1;display:flex;align-items:center;gap:8px;background:#0d1117;border:1px solid #30363d;border-radius:8px;padding:0 10px;height:30px;transition:all .15s}'
 +'#gb-urlbox:focus-within{border-color:#1f6feb;box-shadow:0 0 0 3px rgba(31,111,235,0.3)}'
 +'#gb-url{flex:1;border:none;background:none;color:#e6edf3;font-family:inherit;font-size:13px;outline:none}'
@@ -27,7 +29,23 @@ var CSS = '#gb-toolbar{position:fixed;top:0;left:0;right:0;z-index:2147483647;fo
 +'#gb-tools button{width:28px;height:28px;border:none;background:none;color:#7d8590;cursor:pointer;border-radius:6px;display:flex;align-items:center;justify-content:center;transition:all .12s}'
 +'#gb-tools button:hover{background:#1c2128;color:#e6edf3}'
 +'#gb-tools button:active{transform:scale(0.9)}'
++'#gb-tools button.gb-on{color:#58a6ff}'
++'#gb-shield{position:relative}'
++'#gb-shield-count{position:absolute;top:0;right:0;min-width:14px;height:14px;padding:0 3px;box-sizing:border-box;border-radius:7px;background:#1f6feb;color:#fff;font-size:9px;line-height:14px;text-align:center;display:none}'
++'#gb-menu-list{position:absolute;top:100%;right:8px;min-width:180px;padding:4px 0;background:#161b22;border:1px solid #30363d;border-radius:8px;box-shadow:0 8px 24px rgba(0,0,0,0.5);display:none}'
++'#gb-menu-list.gb-on{display:block}'
++'.gb-menu-item{display:flex;justify-content:space-between;gap:16px;padding:6px 12px;color:#e6edf3;cursor:pointer;font-size:12px}'
++'.gb-menu-item:hover{background:#1f6feb}'
++'.gb-menu-item kbd{color:#7d8590;font:inherit}'
++'#gb-progress{position:absolute;left:0;bottom:0;height:2px;width:0;background:#1f6feb;opacity:0;transition:width .2s ease-out,opacity .3s;pointer-events:none}'
++'#gb-progress.gb-on{opacity:1}'
++'.gb-tab-spin{width:12px;height:12px;flex-shrink:0;box-sizing:border-box;border:2px solid #30363d;border-top-color:#58a6ff;border-radius:50%;animation:gb-spin .8s linear infinite}'
++'@keyframes gb-spin{to{transform:rotate(360deg)}}'
 +'#gb-status{position:fixed;bottom:0;left:0;right:0;height:22px;background:#161b22;border-top:1px solid #30363d;display:flex;align-items:center;padding:0 10px;font-size:11px;color:#7d8590;z-index:2147483647}'
++'html.gb-fullscreen #gb-toolbar,html.gb-fullscreen #gb-status{display:none}'
++'#gb-fs-hint{position:fixed;top:16px;left:50%;transform:translateX(-50%);padding:8px 16px;background:rgba(22,27,34,0.9);border:1px solid #30363d;border-radius:8px;color:#e6edf3;font-family:-apple-system,BlinkMacSystemFont,"Segoe UI","Noto Sans",Helvetica,Arial,sans-serif;font-size:13px;z-index:2147483647;pointer-events:none;opacity:0;transition:opacity .3s}'
++'#gb-fs-hint.gb-on{opacity:1}'
++'#gb-fs-hint kbd{padding:1px 6px;border:1px solid #30363d;border-radius:4px;background:#0d1117;font:inherit}'
 +'.gb-toast{position:fixed;bottom:32px;left:50%;transform:translateX(-50%);padding:6px 16px;background:#161b22;border:1px solid #30363d;border-radius:8px;color:#e6edf3;font-size:12px;z-index:2147483647;box-shadow:0 4px 12px rgba(0,0,0,0.4)}';
 
 var s = document.createElement('style');
@@ -45,9 +63,20 @@ tb.innerHTML = '<div id="gb-tabbar"><div id="gb-tabs"></div><button id="gb-newta
 + '<svg width="14" height="14" viewBox="0 0 16 16" fill="#7d8590"><path d="M4 4a4 4 0 0 1 8 0v2h.25c.966 0 1.75.784 1.75 1.75v5.5A1.75 1.75 0 0 1 12.25 15h-8.5A1.75 1.75 0 0 1 2 13.25v-5.5C2 6.784 2.784 6 3.75 6H4Zm8.25 3.5h-8.5a.25.25 0 0 0-.25.25v5.5c0 .138.112.25.25.25h8.5a.25.25 0 0 0 .25-.25v-5.5a.25.25 0 0 0-.25-.25ZM10.5 6V4a2.5 2.5 0 1 0-5 0v2Z"/></svg>'
 + '<input id="gb-url" type="text" placeholder="Search or enter URL" spellcheck="false" autocomplete="off"/>'
 + '</div><div id="gb-tools">'
++ '<button id="gb-shield" title="Privacy protections"><svg width="14" height="14" viewBox="0 0 16 16" fill="currentColor"><path d="M8 0 1.5 2.5v5C1.5 11.6 4.3 14.9 8 16c3.7-1.1 6.5-4.4 6.5-8.5v-5Zm0 1.6 5 1.9v4c0 3.2-2.1 5.9-5 6.9-2.9-1-5-3.7-5-6.9v-4Z"/></svg><span id="gb-shield-count"></span></button>'
++ '<button id="gb-reader" title="Reader view"><svg width="14" height="14" viewBox="0 0 16 16" fill="currentColor"><path d="M0 1.75A.75.75 0 0 1 .75 1h4.253c1.227 0 2.317.59 3 1.501A3.743 3.743 0 0 1 11.006 1h4.245a.75.75 0 0 1 .75.75v10.5a.75.75 0 0 1-.75.75h-4.507a2.25 2.25 0 0 0-1.591.659l-.622.621a.75.75 0 0 1-1.06 0l-.622-.621A2.25 2.25 0 0 0 5.258 13H.75a.75.75 0 0 1-.75-.75Zm7.251 10.324.004-5.073-.002-2.253A2.25 2.25 0 0 0 5.003 2.5H1.5v9h3.757a3.75 3.75 0 0 1 1.994.574ZM8.755 4.75l-.004 7.322a3.752 3.752 0 0 1 1.992-.572H14.5v-9h-3.495a2.25 2.25 0 0 0-2.25 2.25Z"/></svg></button>'
 + '<button id="gb-bmark" title="Bookmark">\u2606</button>'
++ '<button id="gb-readlater" title="Save to Reading List">\u2398</button>'
++ '<button id="gb-darkmode" title="Dark mode for this site">\u263E</button>'
 + '<button id="gb-settings" title="Settings">\u2699</button>'
-+ '</div></div>';
++ '<button id="gb-menu" title="Menu">\u22EE</button>'
++ '</div></div><div id="gb-menu-list">'
++ '<div class="gb-menu-item" data-action="print">Print\u2026<kbd>Ctrl+P</kbd></div>'
++ '<div class="gb-menu-item" data-action="print_pdf">Save as PDF</div>'
++ '<div class="gb-menu-item" data-action="capture_page">Capture page</div>'
++ '<div class="gb-menu-item" data-action="capture_visible">Capture visible area</div>'
++ '<div class="gb-menu-item" data-action="fullscreen">Full screen<kbd>F11</kbd></div>'
++ '</div><div id="gb-progress"></div>';
 document.documentElement.appendChild(tb);
 
 var st = document.createElement('div');
@@ -67,14 +96,57 @@ window.__gb_ipc = ipc;
 
 // Button handlers
 document.getElementById('gb-newtab').onclick = function() { ipc('new_tab', {}); };
-document.getElementById('gb-back').onclick = function() { history.back(); };
-document.getElementById('gb-fwd').onclick = function() { history.forward(); };
+document.getElementById('gb-back').onclick = function() { ipc('go_back', {}); };
+document.getElementById('gb-fwd').onclick = function() { ipc('go_forward', {}); };
 document.getElementById('gb-reload').onclick = function() { location.reload(); };
+document.getElementById('gb-shield').onclick = function() { ipc('open_privacy', {}); };
+// Reader view: the page's HTML goes to Rust, which shows its article instead
+var readerBtn = document.getElementById('gb-reader');
+var isReaderPage = (location.protocol === 'gb:' || location.hostname === 'gb.localhost') && location.pathname === '/reader';
+if (isReaderPage) {
+  readerBtn.className = 'gb-on';
+  readerBtn.title = 'Exit reader view';
+}
+readerBtn.onclick = function() {
+  if (isReaderPage) ipc('reader_exit', {});
+  else ipc('reader_open', { url: location.href, html: document.documentElement.outerHTML });
+};
 document.getElementById('gb-bmark').onclick = function() {
   ipc('add_bookmark', { url: location.href, title: document.title || location.href });
 };
+document.getElementById('gb-readlater').onclick = function() {
+  // Inline same-origin (untainted) images so the saved copy works offline
+  var images = {};
+  Array.prototype.forEach.call(document.images, function(img) {
+    var src = img.getAttribute('src');
+    if (!src || src.indexOf('data:') === 0 || !img.complete || !img.naturalWidth) return;
+    try {
+      var c = document.createElement('canvas');
+      c.width = img.naturalWidth; c.height = img.naturalHeight;
+      c.getContext('2d').drawImage(img, 0, 0);
+      images[src] = c.toDataURL('image/png');
+    } catch (e) {}
+  });
+  ipc('reading_list_save', { url: location.href, html: document.documentElement.outerHTML, images: images });
+};
+document.getElementById('gb-darkmode').onclick = function() {
+  ipc('toggle_force_dark', { url: location.href });
+};
 document.getElementById('gb-settings').onclick = function() { ipc('open_settings', {}); };
 
+// Page menu: items run the shortcut action named by data-action
+var menuList = document.getElementById('gb-menu-list');
+document.getElementById('gb-menu').onclick = function(e) {
+  e.stopPropagation();
+  menuList.classList.toggle('gb-on');
+};
+menuList.addEventListener('click', function(e) {
+  var item = e.target.closest('.gb-menu-item');
+  menuList.classList.remove('gb-on');
+  if (item && actions[item.dataset.action]) actions[item.dataset.action]();
+});
+document.addEventListener('click', function() { menuList.classList.remove('gb-on'); });
+
 // URL input
 var urlEl = document.getElementById('gb-url');
 urlEl.addEventListener('keydown', function(e) {
@@ -82,13 +154,69 @@ urlEl.addEventListener('keydown', function(e) {
 });
 urlEl.addEventListener('focus', function() { this.select(); });
 
-// Keyboard shortcuts
-document.addEventListener('keydown', function(e) {
-  if (e.ctrlKey && e.key === 't') { e.preventDefault(); ipc('new_tab', {}); }
-  if (e.ctrlKey && e.key === 'w') { e.preventDefault(); ipc('close_active_tab', {}); }
-  if (e.ctrlKey && e.key === 'l') { e.preventDefault(); urlEl.focus(); urlEl.select(); }
-  if (e.ctrlKey && e.key === ',') { e.preventDefault(); ipc('open_settings', {}); }
+// Keyboard shortcuts: bindings (action -> keys) come from Rust's ShortcutManager
+var MODIFIERS = ['Ctrl', 'Cmd', 'Alt', 'Shift'];
+var KEY_NAMES = { ',': 'Comma', '+': 'Plus', '=': 'Plus', '-': 'Minus', ' ': 'Space',
+  ArrowLeft: 'Left', ArrowRight: 'Right', ArrowUp: 'Up', ArrowDown: 'Down', Esc: 'Escape' };
+function normalizeKeys(keys) {
+  var parts = keys.split('+').filter(function(p) { return p; });
+  if (keys.slice(-2) === '++') parts.push('Plus');
+  var key = parts.pop() || '';
+  var mods = MODIFIERS.filter(function(m) { return parts.indexOf(m) >= 0; });
+  return mods.concat([key.length === 1 ? key.toUpperCase() : key]).join('+');
+}
+function eventKeys(e) {
+  var key = KEY_NAMES[e.key] || (e.key.length === 1 ? e.key.toUpperCase() : e.key);
+  var mods = [];
+  if (e.ctrlKey) mods.push('Ctrl');
+  if (e.metaKey) mods.push('Cmd');
+  if (e.altKey) mods.push('Alt');
+  // Shift is how most layouts type '+'
+  if (e.shiftKey && key !== 'Plus') mods.push('Shift');
+  return mods.concat([key]).join('+');
+}
+var bindings = {};
+window.__gb_setShortcuts = function(shortcuts) {
+  bindings = {};
+  Object.keys(shortcuts).forEach(function(action) { bindings[normalizeKeys(shortcuts[action])] = action; });
+};
+// Defaults until Rust sends the configured ones
+window.__gb_setShortcuts({
+  new_tab: 'Ctrl+T', close_tab: 'Ctrl+W', address_bar: 'Ctrl+L', settings: 'Ctrl+Comma', find: 'Ctrl+F',
+  back: 'Alt+Left', forward: 'Alt+Right', print: 'Ctrl+P', fullscreen: 'F11'
 });
+var actions = {
+  new_tab: function() { ipc('new_tab', {}); },
+  close_tab: function() { ipc('close_active_tab', {}); },
+  reload: function() { location.reload(); },
+  back: function() { ipc('go_back', {}); },
+  forward: function() { ipc('go_forward', {}); },
+  address_bar: function() { urlEl.focus(); urlEl.select(); },
+  settings: function() { ipc('open_settings', {}); },
+  downloads: function() { ipc('open_downloads', {}); },
+  find: function() { ipc('find_open', {}); },
+  zoom_in: function() { ipc('zoom', { step: 'in' }); },
+  zoom_out: function() { ipc('zoom', { step: 'out' }); },
+  zoom_reset: function() { ipc('zoom', { step: 'reset' }); },
+  print: function() { ipc('print', {}); },
+  print_pdf: function() { ipc('print', { pdf: true }); },
+  capture_page: function() { ipc('screenshot', { full_page: true }); },
+  capture_visible: function() { ipc('screenshot', { full_page: false }); },
+  fullscreen: function() { ipc('toggle_fullscreen', {}); }
+};
+window.__gb_shortcutActions = actions;
+document.addEventListener('keydown', function(e) {
+  // Escape leaves fullscreen, unless it is closing the find bar or a menu first
+  var inChrome = e.target.closest && e.target.closest('[id^="gb-"]');
+  if (e.key === 'Escape' && fullscreen && !inChrome) {
+    e.preventDefault();
+    if (fullscreen === 'element' && fullscreenElement()) exitElementFullscreen();
+    else ipc('exit_fullscreen', {});
+    return;
+  }
+  var action = bindings[eventKeys(e)];
+  if (action && actions[action]) { e.preventDefault(); actions[action](); }
+}, true);
 
 // Tab update function — called from Rust
 window.__gb_updateTabs = function(data) {
@@ -102,6 +230,17 @@ window.__gb_updateTabs = function(data) {
     d.className = 'gb-tab' + (t.id === aid ? ' active' : '');
     var title = t.title || 'New Tab';
     if (title.length > 25) title = title.substring(0, 25) + '...';
+    if (t.loading) {
+      var spin = document.createElement('span');
+      spin.className = 'gb-tab-spin';
+      d.appendChild(spin);
+    } else if (t.favicon) {
+      var icon = document.createElement('img');
+      icon.className = 'gb-tab-icon';
+      icon.src = t.favicon;
+      icon.alt = '';
+      d.appendChild(icon);
+    }
     var span = document.createElement('span');
     span.textContent = title;
     d.appendChild(span);
@@ -128,7 +267,144 @@ window.__gb_updateTabs = function(data) {
   }
   var stxt = document.getElementById('gb-status-text');
   if (stxt && active) stxt.textContent = (active.url && !active.url.startsWith('about:')) ? active.url : '';
+  if (active) window.__gb_setBlocked(active.blocked || 0);
+  // Back/forward follow the tab's history as Rust keeps it
+  window.__gb_navState = { back: !!data.canGoBack, forward: !!data.canGoForward };
+  document.getElementById('gb-back').disabled = !data.canGoBack;
+  document.getElementById('gb-fwd').disabled = !data.canGoForward;
+  // A page loaded while the window is fullscreen starts out that way
+  if ((data.fullscreen || null) !== fullscreen) window.__gb_setFullscreen(data.fullscreen || null);
+};
+
+// Requests blocked on this page, shown on the shield — called from Rust
+window.__gb_setBlocked = function(n) {
+  var c = document.getElementById('gb-shield-count');
+  if (!c) return;
+  c.textContent = n > 99 ? '99+' : String(n);
+  c.style.display = n > 0 ? 'block' : 'none';
+  document.getElementById('gb-shield').title = n > 0
+    ? n + ' request' + (n === 1 ? '' : 's') + ' blocked on this page'
+    : 'Privacy protections';
+};
+
+// Page load progress along the bottom of the toolbar — called from Rust
+var loadDone = null;
+window.__gb_setLoadProgress = function(p) {
+  var bar = document.getElementById('gb-progress');
+  if (!bar) return;
+  clearTimeout(loadDone);
+  bar.className = 'gb-on';
+  // Never shrinks within a load, and never looks finished before it is
+  var shown = parseFloat(bar.style.width) || 0;
+  bar.style.width = Math.max(shown, Math.min(p, 0.95) * 100) + '%';
+};
+window.__gb_setLoading = function(on) {
+  var bar = document.getElementById('gb-progress');
+  if (!bar) return;
+  clearTimeout(loadDone);
+  if (on) {
+    bar.style.width = '0';
+    window.__gb_setLoadProgress(0.1);
+    return;
+  }
+  bar.style.width = '100%';
+  loadDone = setTimeout(function() { bar.className = ''; bar.style.width = '0'; }, 300);
 };
+// A document still loading shows as such until its load event, on engines
+// that do not report progress too
+if (document.readyState !== 'complete') {
+  window.__gb_setLoadProgress(0.3);
+  window.addEventListener('load', function() { window.__gb_setLoading(false); });
+}
+
+// Hides the toolbar and status bar while the page is captured — called from Rust
+window.__gb_setChromeHidden = function(hidden) {
+  ['gb-toolbar', 'gb-status'].forEach(function(id) {
+    var el = document.getElementById(id);
+    if (el) el.style.visibility = hidden ? 'hidden' : '';
+  });
+};
+
+// Fullscreen: F11 ('window') or a page element ('element') — called from Rust
+// with the mode, or null once the window is back to normal
+var fullscreen = null;
+var hintDone = null;
+function fullscreenElement() { return document.fullscreenElement || document.webkitFullscreenElement || null; }
+function exitElementFullscreen() {
+  if (document.exitFullscreen) document.exitFullscreen().catch(function() {});
+  else if (document.webkitExitFullscreen) document.webkitExitFullscreen();
+}
+window.__gb_setFullscreen = function(mode) {
+  fullscreen = mode || null;
+  document.documentElement.classList.toggle('gb-fullscreen', !!fullscreen);
+  // The page gets the whole window; its layout comes back on exit
+  document.body.style.marginTop = fullscreen ? '' : '72px';
+  document.body.style.marginBottom = fullscreen ? '' : '22px';
+  var hint = document.getElementById('gb-fs-hint');
+  clearTimeout(hintDone);
+  if (!fullscreen) {
+    if (hint) hint.className = '';
+    // The window left fullscreen some other way than the page's own exit
+    if (fullscreenElement()) exitElementFullscreen();
+    return;
+  }
+  if (!hint) {
+    hint = document.createElement('div');
+    hint.id = 'gb-fs-hint';
+    hint.innerHTML = 'Press <kbd>Esc</kbd> to exit full screen';
+    document.documentElement.appendChild(hint);
+  }
+  hint.className = 'gb-on';
+  hintDone = setTimeout(function() { hint.className = ''; }, 3000);
+};
+// The page's own requestFullscreen/exitFullscreen, reported so the window follows
+function __gb_fullscreenChanged() { ipc('element_fullscreen', { on: !!fullscreenElement() }); }
+document.addEventListener('fullscreenchange', __gb_fullscreenChanged);
+document.addEventListener('webkitfullscreenchange', __gb_fullscreenChanged);
+
+// Puts a screenshot on the clipboard, then says so — called from Rust
+window.__gb_copyImage = function(dataUrl, toast) {
+  var done = function(copied) { window.__gb_showToast(toast + (copied ? ' and copied to the clipboard' : '')); };
+  if (!navigator.clipboard || !navigator.clipboard.write || !window.ClipboardItem) { done(false); return; }
+  fetch(dataUrl).then(function(r) { return r.blob(); }).then(function(blob) {
+    return navigator.clipboard.write([new ClipboardItem({ 'image/png': blob })]);
+  }).then(function() { done(true); }, function() { done(false); });
+};
+
+// Password autofill: a focused login field asks the browser to offer the
+// logins saved for this origin, which it does in its own prompt and fills
+// in only once the user picks one there. Submitted logins are offered for
+// saving — answered from Rust
+var afQueried = false;
+function afPasswordField(root) { return (root || document).querySelector('input[type=password]'); }
+// The visible text field closest before the password field
+function afUsernameField(pass) {
+  var inputs = Array.prototype.slice.call((pass.form || document).querySelectorAll('input'));
+  var before = inputs.slice(0, inputs.indexOf(pass)).filter(function(i) {
+    return /^(text|email|tel)$/.test(i.type) && i.offsetParent !== null;
+  });
+  return before[before.length - 1] || null;
+}
+function afIsLoginField(el) {
+  if (!el || el.tagName !== 'INPUT' || el.id.indexOf('gb-') === 0) return false;
+  if (el.type === 'password') return true;
+  var pass = afPasswordField(el.form);
+  return !!pass && afUsernameField(pass) === el;
+}
+function afQuery() {
+  if (afQueried) return;
+  afQueried = true;
+  ipc('autofill_query', {});
+}
+document.addEventListener('focusin', function(e) {
+  if (afIsLoginField(e.target)) afQuery();
+});
+document.addEventListener('submit', function(e) {
+  var pass = afPasswordField(e.target);
+  if (!pass || !pass.value) return;
+  var user = afUsernameField(pass);
+  ipc('autofill_capture', { username: user ? user.value : '', password: pass.value });
+}, true);
 
 // Toast
 window.__gb_showToast = function(msg) {
@@ -143,11 +419,29 @@ window.__gb_showToast = function(msg) {
   }, 2000);
 };
 
+// Forced dark mode button state — called from Rust
+window.__gb_setForceDark = function(on) {
+  var b = document.getElementById('gb-darkmode');
+  if (b) b.className = on ? 'gb-on' : '';
+};
+
+// Mirror the toolbar (and internal gb:// pages) for right-to-left locales — called from Rust
+window.__gb_setDirection = function(dir) {
+  tb.dir = dir;
+  if (location.protocol === 'gb:' || location.hostname === 'gb.localhost') document.documentElement.dir = dir;
+};
+window.addEventListener('gb-theme-changed', function(e) {
+  var dir = e.detail && e.detail['--gb-direction'];
+  if (dir) window.__gb_setDirection(dir);
+});
+
 // Signal ready
-ipc('ui_ready', {});
+ipc('ui_ready', { url: location.href });
 
-// Track URL changes for the address bar (in-page navigation, redirects)
-var __gb_lastUrl = location.href;
+// Track URL changes for the address bar (in-page navigation, redirects).
+// A web page also reports itself once, so the tab's URL, title and history follow link clicks.
+var __gb_isWebPage = /^https?:$/.test(location.protocol) && location.hostname !== 'gb.localhost';
+var __gb_lastUrl = __gb_isWebPage ? '' : location.href;
 function __gb_checkUrl() {
   if (location.href !== __gb_lastUrl) {
     __gb_lastUrl = location.href;
@@ -163,6 +457,25 @@ window.addEventListener('hashchange', __gb_checkUrl);
 // Also poll for SPA navigations (pushState doesn't fire events)
 setInterval(__gb_checkUrl, 500);
 
+// Report the page's icon links once it has loaded, for the tab strip
+function __gb_sendIcons() {
+  var links = document.querySelectorAll('link[rel~="icon" i],link[rel^="apple-touch-icon" i]');
+  ipc('favicon', { icons: Array.prototype.map.call(links, function(l) {
+    return { rel: l.rel || '', href: l.href || '', sizes: l.getAttribute('sizes') || '', type: l.type || '' };
+  })});
+}
+if (__gb_isWebPage) {
+  if (document.readyState === 'complete') __gb_sendIcons();
+  else window.addEventListener('load', __gb_sendIcons);
+}
+
+// Ask for the origin's logins once the page shows a login form
+function __gb_checkLoginForm() { if (afPasswordField(document)) afQuery(); }
+if (__gb_isWebPage) {
+  if (document.readyState === 'complete') __gb_checkLoginForm();
+  else window.addEventListener('load', __gb_checkLoginForm);
+}
+
 // Update URL bar with current page URL on load
 if (urlEl && location.href && location.protocol !== 'about:' && location.href !== 'about:blank') {
   urlEl.value = location.href;
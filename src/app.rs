@@ -2,25 +2,50 @@
 //!
 //! Central struct holding all managers and services, managing application lifecycle.
 
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::Arc;
+use std::time::Instant;
 
+use crate::database::backup::{BackupInfo, BackupRotation};
 use crate::database::connection::Database;
+use crate::database::maintenance::{self, MaintenanceReport};
+use crate::database::worker::{DbTask, DbWorker};
 use crate::managers::download_manager::DownloadManager;
 use crate::managers::permission_manager::PermissionManager;
+use crate::managers::profile_manager::{ProfileLock, ProfileManager, ProfileManagerTrait};
 use crate::managers::session_manager::SessionManager;
 use crate::managers::shortcut_manager::ShortcutManager;
 use crate::managers::tab_manager::TabManager;
+use crate::platform::PowerStatus;
 use crate::services::ai_assistant::AIAssistant;
 use crate::services::crash_recovery::CrashRecovery;
+use crate::services::event_bus::{AppEvent, EventBus};
+use crate::services::event_hub::{EventHub, EventTopic};
 use crate::services::extension_framework::ExtensionFramework;
-use crate::services::github_integration::GitHubIntegration;
+use crate::services::github_integration::{GitHubEndpoints, GitHubIntegration};
+use crate::services::github_notifications::GitHubNotifications;
+use crate::services::github_quick_open::GitHubQuickOpen;
 use crate::services::localization_engine::LocalizationEngine;
 use crate::services::password_manager::PasswordManager;
 use crate::services::privacy_engine::PrivacyEngine;
 use crate::services::reader_mode::ReaderMode;
 use crate::services::settings_engine::SettingsEngine;
+use crate::services::sync::SyncService;
 use crate::services::theme_engine::ThemeEngine;
+use crate::services::theme_scheduler::ThemeScheduler;
+use crate::services::update_installer::UpdateInstaller;
 use crate::services::update_manager::UpdateManager;
+use crate::types::ai::AIAnswerPart;
+use crate::types::errors::{CrashError, DbWorkerError, DownloadError, GitBrowserError, ProfileError, SessionError};
+use crate::types::profile::{ProfileContext, UserProfile};
+use crate::types::session::{SessionData, SessionTab, WindowBounds};
+use crate::types::shutdown::{ShutdownReport, ShutdownStep, StepReport, StepStatus};
+use crate::types::settings::SettingsChange;
+use crate::types::tab::ZoomStep;
+
+/// How many times longer background loops wait while saving power.
+pub const BATTERY_SAVER_SLOWDOWN: u32 = 4;
 
 /// Central application struct holding all managers and services.
 ///
@@ -28,14 +53,25 @@ use crate::services::update_manager::UpdateManager;
 /// because they borrow the connection with a lifetime parameter.
 pub struct App {
     pub db: Arc<Database>,
+    /// Runs long queries on a connection of its own, outside the app lock.
+    pub db_worker: DbWorker,
+    /// Rotating backups of `db`, kept next to it.
+    pub db_backups: BackupRotation,
+    /// Changes pushed to subscribed RPC clients.
+    pub events: EventHub,
+    /// Changes passed between the app's own components.
+    pub bus: EventBus,
     pub tab_manager: TabManager,
     pub session_manager: SessionManager,
     pub download_manager: DownloadManager,
     pub permission_manager: PermissionManager,
     pub shortcut_manager: ShortcutManager,
+    /// User profiles next to the one this app runs.
+    pub profile_manager: ProfileManager,
     pub settings_engine: SettingsEngine,
     pub localization_engine: LocalizationEngine,
     pub theme_engine: ThemeEngine,
+    pub theme_scheduler: ThemeScheduler,
     pub privacy_engine: PrivacyEngine,
     pub password_manager: PasswordManager,
     pub crash_recovery: CrashRecovery,
@@ -43,9 +79,33 @@ pub struct App {
     pub extension_framework: ExtensionFramework,
     pub ai_assistant: AIAssistant,
     pub update_manager: UpdateManager,
+    pub update_installer: UpdateInstaller,
     pub github_integration: GitHubIntegration,
+    pub github_notifications: GitHubNotifications,
+    pub github_quick_open: GitHubQuickOpen,
+    pub sync_service: SyncService,
+    settings_events: Receiver<SettingsChange>,
+    /// Asks from `ask_ai_for_answer` whose answer is still coming, by number.
+    ai_answers: HashMap<u64, Sender<AIAnswerPart>>,
+    next_ai_ask: u64,
+    /// Session offered for restore after a crash, until the user chooses.
+    crash_restore: Option<SessionData>,
+    /// The user profile opened with `open_profile`, locked while the app lives.
+    profile: Option<(UserProfile, ProfileLock)>,
+    /// Where this app keeps its data, and whether it is private.
+    context: ProfileContext,
+    /// Set once `shutdown` has started; background loops stop when they see it.
+    shutting_down: bool,
+    /// The machine's power state, once the OS has reported it.
+    power: Option<PowerStatus>,
 }
 
+// SAFETY: `Database` wraps a `rusqlite::Connection`, which is `Send` but not
+// `Sync`, so `Arc<Database>` is not `Send`. Every clone of `App::db` is held
+// by `App` or a service it owns, so the connection only moves between threads
+// together with the whole `App`, and the RPC server shares `App` behind a `Mutex`.
+unsafe impl Send for App {}
+
 impl App {
     /// Creates a new App, initializing all managers and services.
     ///
@@ -53,49 +113,140 @@ impl App {
     /// borrow `&Connection` with a lifetime. Use `db.connection()` to create them
     /// on demand via `BookmarkManager::new(app.db.connection())`.
     pub fn new(db_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let db = Arc::new(Database::open(db_path)?);
+        Self::open(db_path, None)
+    }
+
+    /// Like `new`, with the master password for a database encrypted with it
+    /// (`privacy.database_encryption`). Fails with
+    /// `EncryptionError::PasswordRequired` when one is needed but not given.
+    pub fn open(db_path: &str, master_password: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_context(ProfileManager::default_context(db_path), master_password)
+    }
+
+    /// A private app beside this one, e.g. for a private window: same
+    /// settings, extensions and themes, with nothing written to disk.
+    pub fn open_private(&self) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_context(self.context.private_of(), None)
+    }
+
+    /// Opens user profile `name` of `profiles` (`--profile <name>`), creating
+    /// it on first use: its own database, settings file and extensions. The
+    /// profile stays locked against other GitBrowser instances while the app
+    /// lives; fails with `ProfileError::InUse` if another one has it open.
+    pub fn open_profile(
+        profiles: &ProfileManager,
+        name: &str,
+        master_password: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut profile = match profiles.get_profile(name) {
+            Err(ProfileError::NotFound(_)) => profiles.create_profile(name)?,
+            other => other?,
+        };
+        let lock = profiles.lock_profile(name)?;
+        profile.in_use = true;
+        let mut app = Self::with_context(profiles.context(name), master_password)?;
+        app.profile_manager = profiles.clone();
+        app.profile = Some((profile, lock));
+        Ok(app)
+    }
 
-        let tab_manager = TabManager::new();
+    /// Opens an app on `context`, every manager and service using its paths.
+    /// Several apps can be open in one process as long as their contexts
+    /// differ, e.g. a profile and a private profile over it.
+    pub fn with_context(
+        context: ProfileContext,
+        master_password: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let paths = &context.paths;
+        let db_path = paths.database.to_string_lossy().into_owned();
+        let db_path = db_path.as_str();
+        // Settings live outside the database and say how it is encrypted
+        let mut settings_engine = SettingsEngine::new(Some(paths.settings.to_string_lossy().into_owned()));
+        if context.private {
+            settings_engine = settings_engine.in_memory();
+        }
+        let settings_events = {
+            use crate::services::settings_engine::SettingsEngineTrait;
+            let _ = settings_engine.load();
+            settings_engine.subscribe()
+        };
+        let encryption = {
+            use crate::services::settings_engine::SettingsEngineTrait;
+            settings_engine.get_settings().privacy.database_encryption
+        };
+        let db_backups = BackupRotation::new(paths.backups.clone());
+        let key = crate::database::encryption::prepare_with_backups(
+            std::path::Path::new(db_path),
+            Some(&db_backups),
+            encryption,
+            master_password,
+        )?;
+        // The worker's connection only sees an in-memory database that is named and shared
+        let db_path = match db_path {
+            ":memory:" => format!("file:gitbrowser-{}?mode=memory&cache=shared", uuid::Uuid::new_v4()),
+            path => path.to_string(),
+        };
+        let db = Arc::new(crate::database::encryption::open(&db_path, key.as_ref())?);
+        let db_worker = DbWorker::spawn(&db_path, key.as_ref())?;
+
+        let events = EventHub::new();
+        let bus = EventBus::new();
+        let tab_manager = TabManager::new().with_events(events.clone()).with_bus(bus.clone());
         let session_manager = SessionManager::new(db.clone())
             .map_err(|e| format!("SessionManager init failed: {}", e))?;
-        let download_manager = DownloadManager::new(db.clone());
-        let permission_manager = PermissionManager::new(db.clone());
+        let download_manager = DownloadManager::new(db.clone()).with_events(events.clone()).with_bus(bus.clone());
+        let permission_manager = PermissionManager::new(db.clone()).with_events(events.clone());
         let shortcut_manager = ShortcutManager::new();
-        let password_manager = PasswordManager::new(db.clone());
-        let crash_recovery = CrashRecovery::new(db.clone());
-        let extension_framework = ExtensionFramework::new(db.clone());
+        let password_manager = PasswordManager::new(db.clone()).with_bus(bus.clone());
+        let crash_recovery = CrashRecovery::new(db.clone()).with_reports_dir(paths.crashes.clone());
+        let extension_framework = ExtensionFramework::new(db.clone())
+            .with_extensions_dir(paths.extensions.clone());
         let ai_assistant = AIAssistant::new(db.clone())
             .map_err(|e| format!("AIAssistant init failed: {}", e))?;
         let github_integration = GitHubIntegration::new(db.clone())
             .map_err(|e| format!("GitHubIntegration init failed: {}", e))?;
+        let github_notifications = GitHubNotifications::new(db.clone());
+        let github_quick_open = GitHubQuickOpen::new(db.clone());
+        let sync_service = SyncService::new(db.clone());
 
-        let mut settings_engine = SettingsEngine::new(None);
-        {
-            use crate::services::settings_engine::SettingsEngineTrait;
-            let _ = settings_engine.load();
-        }
-
-        let mut localization_engine = LocalizationEngine::new("locales");
+        let mut localization_engine = LocalizationEngine::bundled()
+            .with_override_dir(crate::platform::get_config_dir().join("locales"));
         {
             use crate::services::localization_engine::LocalizationEngineTrait;
             let _ = localization_engine.initialize();
+            if std::env::var("GITBROWSER_I18N_DEBUG").is_ok_and(|v| v == "1") {
+                localization_engine.set_debug_mode(true);
+            }
         }
 
-        let theme_engine = ThemeEngine::new(crate::types::settings::ThemeMode::System);
-        let privacy_engine = PrivacyEngine::new();
+        let theme_engine = ThemeEngine::new(crate::types::settings::ThemeMode::System)
+            .with_themes_dir(paths.themes.clone());
+        let theme_scheduler = ThemeScheduler::new(Default::default());
+        let mut privacy_engine = PrivacyEngine::new();
+        if context.private {
+            use crate::services::privacy_engine::PrivacyEngineTrait;
+            privacy_engine.enable_private_mode();
+        }
         let reader_mode = ReaderMode::new();
         let update_manager = UpdateManager::new();
+        let update_installer = UpdateInstaller::for_current_exe();
 
-        Ok(Self {
+        let mut app = Self {
             db,
+            db_worker,
+            db_backups,
+            events,
+            bus,
             tab_manager,
             session_manager,
             download_manager,
             permission_manager,
             shortcut_manager,
+            profile_manager: ProfileManager::with_default_root(),
             settings_engine,
             localization_engine,
             theme_engine,
+            theme_scheduler,
             privacy_engine,
             password_manager,
             crash_recovery,
@@ -103,39 +254,1043 @@ impl App {
             extension_framework,
             ai_assistant,
             update_manager,
+            update_installer,
             github_integration,
-        })
+            github_notifications,
+            github_quick_open,
+            sync_service,
+            settings_events,
+            ai_answers: HashMap::new(),
+            next_ai_ask: 1,
+            crash_restore: None,
+            profile: None,
+            context,
+            shutting_down: false,
+            power: None,
+        };
+        app.apply_settings();
+        app.refresh_shield_exceptions();
+        Ok(app)
+    }
+
+    /// The user profile this app runs; `None` for the default data location.
+    pub fn profile(&self) -> Option<&UserProfile> {
+        self.profile.as_ref().map(|(profile, _)| profile)
+    }
+
+    /// Where this app keeps its data, and whether it is private.
+    pub fn context(&self) -> &ProfileContext {
+        &self.context
     }
 
     /// Startup sequence: load settings, detect locale, apply theme, check crash recovery.
     pub fn startup(&mut self) {
-        use crate::services::crash_recovery::CrashRecoveryTrait;
         use crate::services::localization_engine::LocalizationEngineTrait;
         use crate::services::privacy_engine::PrivacyEngineTrait;
         use crate::services::settings_engine::SettingsEngineTrait;
 
+        if let Some(offset) = crate::platform::local_utc_offset_minutes() {
+            use crate::services::theme_scheduler::ThemeSchedulerTrait;
+            self.theme_scheduler.set_utc_offset_minutes(offset);
+        }
+
         // Load settings
         let _ = self.settings_engine.load();
+        if let Some(notice) = self.settings_engine.recovery_notice() {
+            eprintln!("[SETTINGS] {}", notice);
+        }
+        self.apply_settings();
+        if let Some(dark) = crate::platform::system_prefers_dark() {
+            self.set_system_dark_mode(dark);
+        }
+        if let Some(power) = crate::platform::power_status() {
+            self.set_power_status(power);
+        }
 
         // Detect and set locale
         let locale = self.localization_engine.detect_system_locale();
         let _ = self.localization_engine.set_locale(&locale);
+        {
+            use crate::services::theme_engine::ThemeEngineTrait;
+            self.theme_engine.set_direction(self.localization_engine.get_direction());
+        }
 
         // Initialize privacy engine
         let _ = self.privacy_engine.initialize();
+        self.refresh_extension_rules();
 
-        // Check for crash recovery
-        if self.crash_recovery.has_unrecovered_crash() {
-            if let Ok(Some(_session)) = self.crash_recovery.get_last_session_for_recovery() {
-                // Session would be restored by the UI layer
+        self.check_crash_recovery();
+    }
+
+    /// Logs crashes captured since the last run. After a crash, the last
+    /// session is kept for `crash_restore_prompt` until the user answers it
+    /// through `resolve_crash_restore`.
+    pub fn check_crash_recovery(&mut self) {
+        use crate::services::crash_recovery::CrashRecoveryTrait;
+
+        // The reports belong to the profile a private app runs over
+        if self.context.private {
+            return;
+        }
+        if let Err(e) = self.crash_recovery.collect_reports() {
+            eprintln!("[CRASH] {}", e);
+        }
+        if !self.crash_recovery.has_unrecovered_crash() {
+            return;
+        }
+        match self.crash_recovery.get_last_session_for_recovery() {
+            Ok(Some(session)) if !session.tabs.is_empty() => self.crash_restore = Some(session),
+            // Nothing to offer, so there is nothing to ask
+            _ => {
+                let _ = self.crash_recovery.mark_crash_recovered();
+            }
+        }
+    }
+
+    /// The `crash.restore_prompt` event while a post-crash restore is
+    /// waiting for the user: the last session's tabs.
+    pub fn crash_restore_prompt(&self) -> Option<serde_json::Value> {
+        let session = self.crash_restore.as_ref()?;
+        let tabs: Vec<serde_json::Value> = session.tabs.iter()
+            .map(|t| serde_json::json!({"url": t.url, "title": t.title, "pinned": t.pinned}))
+            .collect();
+        let active = session.tabs.iter().position(|t| Some(&t.id) == session.active_tab_id.as_ref());
+        Some(serde_json::json!({
+            "event": "crash.restore_prompt",
+            "tabs": tabs,
+            "active_index": active,
+            "saved_at": session.timestamp,
+        }))
+    }
+
+    /// Answers the post-crash prompt. Restoring reopens the session's tabs
+    /// in place of the current ones and returns the session; either way the
+    /// crash counts as recovered.
+    pub fn resolve_crash_restore(&mut self, restore: bool) -> Result<Option<SessionData>, CrashError> {
+        use crate::managers::tab_manager::TabManagerTrait;
+        use crate::services::crash_recovery::CrashRecoveryTrait;
+
+        let session = self.crash_restore.take()
+            .ok_or_else(|| CrashError::RecoveryFailed("no session is waiting to be restored".to_string()))?;
+        self.crash_recovery.mark_crash_recovered()?;
+        if !restore {
+            return Ok(None);
+        }
+
+        let replaced: Vec<String> = self.tab_manager.get_tab_order().to_vec();
+        for tab in &session.tabs {
+            let active = session.active_tab_id.as_ref() == Some(&tab.id);
+            let id = self.tab_manager.create_tab(Some(&tab.url), active);
+            let _ = self.tab_manager.update_tab_title(&id, &tab.title);
+            if tab.pinned {
+                let _ = self.tab_manager.pin_tab(&id);
             }
-            let _ = self.crash_recovery.mark_crash_recovered();
         }
+        for id in replaced {
+            let _ = self.tab_manager.close_tab(&id);
+        }
+        Ok(Some(session))
     }
 
-    /// Shutdown sequence: save session, stop periodic save, flush state.
-    pub fn shutdown(&mut self) {
+    /// Saves the open tabs as the session to offer after a crash. The
+    /// crashed session is kept while its restore prompt is unanswered.
+    pub fn save_session(&self) -> Result<(), SessionError> {
+        use crate::managers::tab_manager::TabManagerTrait;
+        if self.crash_restore.is_some() {
+            return Ok(());
+        }
+        let tabs = self.tab_manager.get_all_tabs().into_iter()
+            .filter(|t| !t.url.starts_with("about:"))
+            .map(|t| SessionTab {
+                id: t.id.clone(),
+                url: t.url.clone(),
+                title: t.title.clone(),
+                pinned: t.pinned,
+                scroll_position: t.scroll_position.clone(),
+            })
+            .collect();
+        let active_tab_id = self.tab_manager.get_active_tab().map(|t| t.id.clone());
+        self.store_session(tabs, active_tab_id)
+    }
+
+    /// Replaces the stored session with `tabs`.
+    pub fn store_session(&self, tabs: Vec<SessionTab>, active_tab_id: Option<String>) -> Result<(), SessionError> {
         use crate::managers::session_manager::SessionManagerTrait;
-        self.session_manager.stop_periodic_save();
+        let session = SessionData {
+            tabs,
+            active_tab_id,
+            window_bounds: WindowBounds::default(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64,
+        };
+        // Only the latest session is ever restored
+        self.session_manager.clear_session()?;
+        self.session_manager.save_session(&session)
+    }
+
+    /// Pushes the full current settings into every service that mirrors them
+    /// (theme, privacy, tab suspension, reader mode) and discards pending
+    /// change events. Locale is left to `startup`, which prefers the system locale.
+    pub fn apply_settings(&mut self) {
+        use crate::managers::shortcut_manager::ShortcutManagerTrait;
+        use crate::managers::tab_manager::TabManagerTrait;
+        use crate::services::privacy_engine::PrivacyEngineTrait;
+        use crate::services::reader_mode::ReaderModeTrait;
+        use crate::services::settings_engine::SettingsEngineTrait;
+        use crate::services::theme_engine::ThemeEngineTrait;
+        use crate::services::theme_scheduler::ThemeSchedulerTrait;
+
+        let settings = self.settings_engine.get_settings().clone();
+        self.theme_scheduler.update_settings(settings.theme_schedule.clone());
+        self.apply_theme_mode();
+        self.apply_accent_color(crate::platform::system_accent_color());
+        let _ = self.theme_engine.apply_custom_theme(settings.appearance.custom_theme.as_deref());
+        self.privacy_engine.apply_settings(&settings.privacy);
+        self.tab_manager.set_suspend_timeout_minutes(settings.performance.tab_suspend_timeout_minutes);
+        self.apply_github_endpoints(&settings.github);
+        self.apply_update_settings(&settings.updates);
+        self.reader_mode.update_settings(settings.reader);
+        self.shortcut_manager.load_bindings(&settings.shortcuts);
+        self.settings_events.try_iter().for_each(drop);
+    }
+
+    /// Recompiles extension blocking rules into the privacy engine. Call
+    /// after installing, removing, enabling or disabling an extension.
+    pub fn refresh_extension_rules(&mut self) {
+        use crate::services::extension_framework::ExtensionFrameworkTrait;
+        use crate::services::privacy_engine::PrivacyEngineTrait;
+        let rules = self.extension_framework.compile_blocking_rules();
+        self.privacy_engine.set_extension_rules(rules);
+    }
+
+    /// Drains pending settings change events and routes each one to the
+    /// service that depends on it. Call after mutating `settings_engine`;
+    /// returns the changes so callers (RPC, webview) can forward them.
+    pub fn process_settings_changes(&mut self) -> Vec<SettingsChange> {
+        use crate::managers::shortcut_manager::ShortcutManagerTrait;
+        use crate::managers::tab_manager::TabManagerTrait;
+        use crate::services::localization_engine::LocalizationEngineTrait;
+        use crate::services::privacy_engine::PrivacyEngineTrait;
+        use crate::services::reader_mode::ReaderModeTrait;
+        use crate::services::settings_engine::SettingsEngineTrait;
+        use crate::services::theme_engine::ThemeEngineTrait;
+        use crate::services::theme_scheduler::ThemeSchedulerTrait;
+
+        let changes: Vec<SettingsChange> = self.settings_events.try_iter().collect();
+        if changes.is_empty() {
+            return changes;
+        }
+        self.events.publish(EventTopic::Settings, serde_json::json!({"changes": changes}));
+        for change in &changes {
+            self.bus.emit(AppEvent::SettingChanged(change.clone()));
+        }
+        let settings = self.settings_engine.get_settings().clone();
+        let variables = self.theme_engine.get_css_variables();
+        let touched = |prefix: &str| changes.iter().any(|c| c.key.starts_with(prefix));
+
+        if let Some(change) = changes.iter().find(|c| c.key == "general.language") {
+            if let Some(lang) = change.new_value.as_str() {
+                let _ = self.localization_engine.set_locale(lang);
+                self.theme_engine.set_direction(self.localization_engine.get_direction());
+            }
+        }
+        if touched("theme_schedule.") {
+            self.theme_scheduler.update_settings(settings.theme_schedule.clone());
+        }
+        if touched("appearance.theme") || touched("theme_schedule.") {
+            self.apply_theme_mode();
+        }
+        if touched("appearance.accent_color") {
+            self.apply_accent_color(crate::platform::system_accent_color());
+        }
+        if touched("appearance.custom_theme") {
+            let _ = self.theme_engine.apply_custom_theme(settings.appearance.custom_theme.as_deref());
+        }
+        if touched("privacy.") {
+            self.privacy_engine.apply_settings(&settings.privacy);
+        }
+        if touched("performance.tab_suspend_timeout_minutes") {
+            self.tab_manager.set_suspend_timeout_minutes(settings.performance.tab_suspend_timeout_minutes);
+        }
+        if touched("github.") {
+            self.apply_github_endpoints(&settings.github);
+        }
+        if touched("updates.") {
+            self.apply_update_settings(&settings.updates);
+        }
+        if touched("reader.") {
+            self.reader_mode.update_settings(settings.reader);
+        }
+        if touched("shortcuts") {
+            self.shortcut_manager.load_bindings(&settings.shortcuts);
+        }
+        let updated = self.theme_engine.get_css_variables();
+        if updated != variables {
+            self.publish_theme(&updated);
+        }
+        changes
+    }
+
+    /// Pushes the theme's current mode, accent and CSS variables to
+    /// `theme` subscribers, and emits `ThemeChanged`.
+    fn publish_theme(&self, variables: &HashMap<String, String>) {
+        use crate::services::theme_engine::ThemeEngineTrait;
+        self.events.publish(
+            EventTopic::Theme,
+            serde_json::json!({
+                "mode": self.theme_engine.get_theme(),
+                "accent": self.theme_engine.get_accent_color(),
+                "variables": variables,
+            }),
+        );
+        self.bus.emit(AppEvent::ThemeChanged { variables: variables.clone() });
+    }
+
+    fn apply_update_settings(&mut self, settings: &crate::types::update::UpdateSettings) {
+        use crate::services::update_manager::UpdateManagerTrait;
+        self.update_manager.set_channel(settings.channel);
+        self.update_manager.set_auto_check_enabled(settings.auto_check);
+    }
+
+    /// Points GitHub logins and API calls at github.com or the configured
+    /// enterprise server. Incomplete enterprise settings fall back to github.com.
+    fn apply_github_endpoints(&mut self, settings: &crate::types::github::GitHubSettings) {
+        use crate::services::github_quick_open::GitHubQuickOpenTrait;
+        let endpoints = GitHubEndpoints::from_settings(settings).unwrap_or_else(|_| GitHubEndpoints::github_com());
+        if &endpoints != self.github_integration.endpoints() {
+            // Cached repositories link to the previous host
+            let _ = self.github_quick_open.clear_cache();
+            self.github_integration.set_endpoints(endpoints);
+        }
+    }
+
+    /// Destination of a `gh ...` address bar command on the active GitHub
+    /// host. `None` for other input, or when a search engine already uses
+    /// `gh` as its keyword.
+    pub fn resolve_gh_command(&self, input: &str) -> Option<String> {
+        use crate::services::github_quick_open::{command_query, GitHubQuickOpenTrait, GH_COMMAND};
+        use crate::services::settings_engine::SettingsEngineTrait;
+        let query = command_query(input)?;
+        let keyword_taken = self.settings_engine.get_settings().search.engines.iter()
+            .any(|e| e.keyword.as_deref().is_some_and(|k| k.eq_ignore_ascii_case(GH_COMMAND)));
+        if keyword_taken {
+            return None;
+        }
+        self.github_quick_open.resolve(&self.github_integration.endpoints().web_url, query)
+    }
+
+    /// Sets the theme engine's mode: the scheduled mode while a theme
+    /// schedule is active, otherwise `appearance.theme`.
+    fn apply_theme_mode(&mut self) {
+        use crate::services::settings_engine::SettingsEngineTrait;
+        use crate::services::theme_engine::ThemeEngineTrait;
+        use crate::services::theme_scheduler::ThemeSchedulerTrait;
+
+        let mode = self
+            .theme_scheduler
+            .scheduled_mode(Self::unix_now())
+            .unwrap_or_else(|| self.settings_engine.get_settings().appearance.theme.clone());
+        self.theme_engine.set_theme(mode);
+    }
+
+    /// Advances the theme schedule. Returns the new CSS variables if a
+    /// scheduled transition changed the visible palette.
+    pub fn tick_theme_schedule(&mut self) -> Option<HashMap<String, String>> {
+        use crate::services::theme_engine::ThemeEngineTrait;
+        use crate::services::theme_scheduler::ThemeSchedulerTrait;
+
+        let mode = self.theme_scheduler.tick(Self::unix_now())?;
+        let before = self.theme_engine.get_css_variables();
+        self.theme_engine.set_theme(mode);
+        let after = self.theme_engine.get_css_variables();
+        if after == before {
+            return None;
+        }
+        self.publish_theme(&after);
+        Some(after)
+    }
+
+    fn unix_now() -> i64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    }
+
+    /// Sets the theme engine's accent from `appearance.accent_color`. In
+    /// `"system"` mode `system_accent` is used, or the default accent if the
+    /// OS does not report one.
+    fn apply_accent_color(&mut self, system_accent: Option<String>) {
+        use crate::services::settings_engine::SettingsEngineTrait;
+        use crate::services::theme_engine::{ThemeEngineTrait, DEFAULT_ACCENT, SYSTEM_ACCENT};
+
+        let configured = self.settings_engine.get_settings().appearance.accent_color.clone();
+        let color = if configured.eq_ignore_ascii_case(SYSTEM_ACCENT) {
+            system_accent.unwrap_or_else(|| DEFAULT_ACCENT.to_string())
+        } else {
+            configured
+        };
+        let _ = self.theme_engine.set_accent_color(&color);
+    }
+
+    /// Records an OS accent color change. Returns the new CSS variables if
+    /// the accent follows the OS and changed.
+    pub fn set_system_accent_color(&mut self, color: &str) -> Option<HashMap<String, String>> {
+        use crate::services::theme_engine::ThemeEngineTrait;
+        let before = self.theme_engine.get_accent_color().to_string();
+        self.apply_accent_color(Some(color.to_string()));
+        if self.theme_engine.get_accent_color() == before {
+            return None;
+        }
+        let variables = self.theme_engine.get_css_variables();
+        self.publish_theme(&variables);
+        Some(variables)
+    }
+
+    /// Records an OS dark/light change. Returns the new CSS variables if the
+    /// visible palette changed, so the caller can push them to open pages.
+    pub fn set_system_dark_mode(&mut self, dark: bool) -> Option<HashMap<String, String>> {
+        use crate::services::theme_engine::ThemeEngineTrait;
+        if !self.theme_engine.set_system_preference(dark) {
+            return None;
+        }
+        let variables = self.theme_engine.get_css_variables();
+        self.publish_theme(&variables);
+        Some(variables)
+    }
+
+    /// Whether forced dark mode applies to `url`: the site's override if it
+    /// has one, otherwise `appearance.force_dark`. Internal pages never force.
+    pub fn force_dark_for(&self, url: &str) -> bool {
+        use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, FORCE_DARK_KEY};
+        use crate::services::settings_engine::SettingsEngineTrait;
+
+        let Some(site) = SiteSettingsManager::site_for_url(url) else {
+            return false;
+        };
+        let mgr = SiteSettingsManager::new(self.db.connection());
+        match mgr.get_setting(&site, FORCE_DARK_KEY) {
+            Ok(Some(value)) => value.as_bool().unwrap_or(false),
+            _ => self.settings_engine.get_settings().appearance.force_dark,
+        }
+    }
+
+    /// Flips forced dark mode for the site of `url` and returns the new state.
+    /// An override matching the global setting is dropped instead of stored.
+    pub fn toggle_force_dark(&mut self, url: &str) -> Result<bool, crate::types::errors::SiteSettingsError> {
+        use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, FORCE_DARK_KEY};
+        use crate::services::settings_engine::SettingsEngineTrait;
+
+        let site = SiteSettingsManager::site_for_url(url)
+            .ok_or_else(|| crate::types::errors::SiteSettingsError::InvalidSite(url.to_string()))?;
+        let enabled = !self.force_dark_for(url);
+        let mut mgr = SiteSettingsManager::new(self.db.connection());
+        if enabled == self.settings_engine.get_settings().appearance.force_dark {
+            mgr.remove_setting(&site, FORCE_DARK_KEY)?;
+        } else {
+            mgr.set_setting(&site, FORCE_DARK_KEY, &serde_json::Value::Bool(enabled))?;
+        }
+        Ok(enabled)
+    }
+
+    /// Loads the sites with protections turned off into the privacy engine.
+    pub fn refresh_shield_exceptions(&mut self) {
+        use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, SHIELDS_KEY};
+        use crate::services::privacy_engine::PrivacyEngineTrait;
+
+        let sites = match SiteSettingsManager::new(self.db.connection()).list_sites(SHIELDS_KEY) {
+            Ok(sites) => sites,
+            Err(e) => return eprintln!("[PRIVACY] {}", e),
+        };
+        let disabled = sites
+            .into_iter()
+            .filter(|s| s.value == serde_json::Value::Bool(false))
+            .map(|s| s.site)
+            .collect();
+        self.privacy_engine.set_disabled_sites(disabled);
+    }
+
+    /// Turns privacy protections on or off for the site of `url` and
+    /// returns the site. Protections are on unless turned off, so turning
+    /// them back on drops the override.
+    pub fn set_site_shields(
+        &mut self,
+        url: &str,
+        enabled: bool,
+    ) -> Result<String, crate::types::errors::SiteSettingsError> {
+        use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, SHIELDS_KEY};
+
+        let site = SiteSettingsManager::site_for_url(url)
+            .ok_or_else(|| crate::types::errors::SiteSettingsError::InvalidSite(url.to_string()))?;
+        let mut mgr = SiteSettingsManager::new(self.db.connection());
+        if enabled {
+            mgr.remove_setting(&site, SHIELDS_KEY)?;
+        } else {
+            mgr.set_setting(&site, SHIELDS_KEY, &serde_json::Value::Bool(false))?;
+        }
+        self.refresh_shield_exceptions();
+        Ok(site)
+    }
+
+    /// Decides what becomes of a request for `url` that the page of tab
+    /// `tab_id` makes (`page_url`, its address; for `main_frame`, the page
+    /// about to load). Blocks trackers, ads and filtered URLs, counting
+    /// them for the site and the tab, and upgrades `http://` when HTTPS is
+    /// enforced (except on loopback hosts, which have no certificate). Sites
+    /// with protections off get neither.
+    pub fn filter_request(
+        &mut self,
+        tab_id: Option<&str>,
+        url: &str,
+        resource_type: &str,
+        page_url: Option<&str>,
+    ) -> crate::types::privacy::RequestDecision {
+        use crate::managers::site_settings_manager::SiteSettingsManager;
+        use crate::managers::tab_manager::TabManagerTrait;
+        use crate::services::privacy_engine::PrivacyEngineTrait;
+        use crate::types::privacy::RequestDecision;
+
+        let lower = url.to_lowercase();
+        if !lower.starts_with("http://") && !lower.starts_with("https://") {
+            return RequestDecision::Allow;
+        }
+        let site = page_url.and_then(SiteSettingsManager::site_for_url);
+        if self.privacy_engine.should_block_on_site(url, resource_type, site.as_deref()) {
+            self.privacy_engine.record_blocked_on_site(url, site.as_deref());
+            if let Some(tab_id) = tab_id {
+                let _ = self.tab_manager.count_blocked_request(tab_id);
+            }
+            return RequestDecision::Block;
+        }
+        let exempt = site.as_deref().is_some_and(|site| self.privacy_engine.is_site_disabled(site))
+            || SiteSettingsManager::site_for_url(url).is_some_and(|host| Self::is_loopback(&host));
+        if let Some(https_url) = self.privacy_engine.upgrade_to_https(url).filter(|_| !exempt) {
+            self.privacy_engine.record_https_upgrade();
+            return RequestDecision::Upgrade(https_url);
+        }
+        if resource_type == "main_frame" {
+            if let Some(tab_id) = tab_id {
+                let _ = self.tab_manager.reset_blocked_requests(tab_id);
+            }
+        }
+        RequestDecision::Allow
+    }
+
+    /// Whether `host` (as `site_for_url` gives it, maybe with a port) is
+    /// this machine.
+    fn is_loopback(host: &str) -> bool {
+        let name = match host.strip_prefix('[') {
+            Some(v6) => v6.split(']').next().unwrap_or(v6),
+            None => host.split(':').next().unwrap_or(host),
+        };
+        name == "localhost"
+            || name.ends_with(".localhost")
+            || name.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    }
+
+    /// The zoom factor remembered for the site of `url`, if any.
+    pub fn zoom_for(&self, url: &str) -> Option<f64> {
+        use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, ZOOM_KEY};
+
+        let site = SiteSettingsManager::site_for_url(url)?;
+        let mgr = SiteSettingsManager::new(self.db.connection());
+        mgr.get_setting(&site, ZOOM_KEY).ok().flatten().and_then(|v| v.as_f64())
+    }
+
+    /// Sets the zoom of tab `tab_id` for the page at `url` it just loaded:
+    /// the site's remembered zoom, or 100% for a site without one. Pages
+    /// without a site (internal pages, files) keep the tab's zoom. Returns
+    /// the tab's zoom.
+    pub fn apply_site_zoom(&mut self, tab_id: &str, url: &str) -> Result<f64, crate::types::errors::TabError> {
+        use crate::managers::site_settings_manager::SiteSettingsManager;
+        use crate::managers::tab_manager::TabManagerTrait;
+
+        if SiteSettingsManager::site_for_url(url).is_some() {
+            self.tab_manager.set_tab_zoom(tab_id, self.zoom_for(url).unwrap_or(1.0))?;
+        }
+        self.tab_manager
+            .get_tab(tab_id)
+            .map(|t| t.zoom)
+            .ok_or_else(|| crate::types::errors::TabError::NotFound(tab_id.to_string()))
+    }
+
+    /// The User-Agent to send to the page at `url`: its site's override, or
+    /// else `general.user_agent`. `None` keeps the webview's own.
+    pub fn user_agent_for(&self, url: &str) -> Option<String> {
+        use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, USER_AGENT_KEY};
+        use crate::services::settings_engine::SettingsEngineTrait;
+
+        let site_ua = SiteSettingsManager::site_for_url(url).and_then(|site| {
+            let mgr = SiteSettingsManager::new(self.db.connection());
+            mgr.get_setting(&site, USER_AGENT_KEY).ok().flatten()
+        });
+        let ua = match site_ua.as_ref().and_then(|v| v.as_str()) {
+            Some(ua) => ua.to_string(),
+            None => self.settings_engine.get_settings().general.user_agent.clone(),
+        };
+        SiteSettingsManager::is_valid_user_agent(&ua).then(|| ua.trim().to_string())
+    }
+
+    /// Sets the User-Agent sent to the site of `url`, or with `None` drops
+    /// the override so the global one applies again. Returns the site.
+    pub fn set_site_user_agent(
+        &mut self,
+        url: &str,
+        user_agent: Option<&str>,
+    ) -> Result<String, crate::types::errors::SiteSettingsError> {
+        use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, USER_AGENT_KEY};
+
+        let site = SiteSettingsManager::site_for_url(url)
+            .ok_or_else(|| crate::types::errors::SiteSettingsError::InvalidSite(url.to_string()))?;
+        let mut mgr = SiteSettingsManager::new(self.db.connection());
+        match user_agent {
+            Some(ua) => mgr.set_setting(&site, USER_AGENT_KEY, &serde_json::json!(ua.trim()))?,
+            None => {
+                mgr.remove_setting(&site, USER_AGENT_KEY)?;
+            }
+        }
+        Ok(site)
+    }
+
+    /// Whether links with `scheme` open in their application without
+    /// asking first.
+    pub fn is_protocol_allowed(&self, scheme: &str) -> bool {
+        use crate::services::settings_engine::SettingsEngineTrait;
+        let scheme = scheme.to_ascii_lowercase();
+        self.settings_engine.get_settings().general.allowed_protocols.contains(&scheme)
+    }
+
+    /// Adds `scheme` to `general.allowed_protocols`, or with `allowed`
+    /// false takes it out so its links are asked about again.
+    pub fn set_protocol_allowed(&mut self, scheme: &str, allowed: bool) -> Result<(), crate::types::errors::SettingsError> {
+        use crate::services::settings_engine::SettingsEngineTrait;
+        let scheme = scheme.to_ascii_lowercase();
+        let mut schemes = self.settings_engine.get_settings().general.allowed_protocols.clone();
+        if schemes.contains(&scheme) == allowed {
+            return Ok(());
+        }
+        if allowed {
+            schemes.push(scheme);
+        } else {
+            schemes.retain(|s| *s != scheme);
+        }
+        self.settings_engine.set_value("general.allowed_protocols", serde_json::json!(schemes))
+    }
+
+    /// Prints tab `tab_id`'s page: through the print dialog, or with `to_pdf`
+    /// straight to a PDF in the downloads folder, recorded as a download
+    /// whose ID is returned. The tab's webview does the printing.
+    pub fn print_tab(&mut self, tab_id: &str, to_pdf: bool) -> Result<Option<String>, GitBrowserError> {
+        use crate::managers::tab_manager::TabManagerTrait;
+        use crate::types::tab::PageAction;
+
+        let (url, title) = self
+            .tab_manager
+            .get_tab(tab_id)
+            .map(|t| (t.url.clone(), t.title.clone()))
+            .ok_or_else(|| crate::types::errors::TabError::NotFound(tab_id.to_string()))?;
+        let (action, download_id) = if to_pdf {
+            let (id, path) = self.start_page_download(&url, &title, "pdf")?;
+            (PageAction::PrintToPdf { download_id: id.clone(), path }, Some(id))
+        } else {
+            (PageAction::Print, None)
+        };
+        self.bus.emit(AppEvent::PageActionRequested { tab_id: tab_id.to_string(), action });
+        Ok(download_id)
+    }
+
+    /// Captures tab `tab_id`'s page as a PNG in the downloads folder, the
+    /// whole page with `full_page` or else what is in view, and copies it to
+    /// the clipboard. Returns the ID of the download recording the file.
+    pub fn capture_tab(&mut self, tab_id: &str, full_page: bool) -> Result<String, GitBrowserError> {
+        use crate::managers::tab_manager::TabManagerTrait;
+        use crate::types::tab::PageAction;
+
+        let (url, title) = self
+            .tab_manager
+            .get_tab(tab_id)
+            .map(|t| (t.url.clone(), t.title.clone()))
+            .ok_or_else(|| crate::types::errors::TabError::NotFound(tab_id.to_string()))?;
+        let (id, path) = self.start_page_download(&url, &format!("Screenshot {}", title), "png")?;
+        let action = PageAction::Screenshot { download_id: id.clone(), path, full_page };
+        self.bus.emit(AppEvent::PageActionRequested { tab_id: tab_id.to_string(), action });
+        Ok(id)
+    }
+
+    /// Records a file made from page `url` (named after `title`) as a
+    /// download into the downloads folder. Returns its ID and path.
+    fn start_page_download(&mut self, url: &str, title: &str, ext: &str) -> Result<(String, String), DownloadError> {
+        use crate::managers::download_manager::DownloadManagerTrait;
+        let path = self.free_download_path(&DownloadManager::file_name_from_title(title, "page", ext));
+        let id = self.download_manager.start_download(url, &path)?;
+        Ok((id, path))
+    }
+
+    /// Records a download a page in tab `tab_id` started, saved into the
+    /// downloads folder under the name it suggested. Without a referrer
+    /// the tab's page is the referrer. Returns its ID and path.
+    pub fn start_link_download(
+        &mut self,
+        tab_id: &str,
+        mut request: crate::types::download::DownloadRequest,
+    ) -> Result<(String, String), DownloadError> {
+        use crate::managers::download_manager::DownloadManagerTrait;
+        use crate::managers::tab_manager::TabManagerTrait;
+        if request.referrer.is_none() {
+            request.referrer = self.tab_manager.get_tab(tab_id).map(|t| t.url.clone()).filter(|url| url.starts_with("http"));
+        }
+        let path = self.free_download_path(&DownloadManager::file_name_for(&request, "download"));
+        let id = self.download_manager.start_request(&request, &path)?;
+        Ok((id, path))
+    }
+
+    /// A path in the downloads folder for a file called `name`, numbered
+    /// when a file or an unfinished download already has it.
+    fn free_download_path(&self, name: &str) -> String {
+        use crate::managers::download_manager::DownloadManagerTrait;
+        let dir = crate::platform::get_downloads_dir();
+        // Files still being made are not on disk yet, but have their names taken
+        let taken = |path: &str| self.download_manager.list_downloads().iter().any(|d| d.filepath == path);
+        let mut path = DownloadManager::free_path(&dir, name).to_string_lossy().into_owned();
+        let (stem, ext) = match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+            _ => (name, String::new()),
+        };
+        let mut n = 1;
+        while taken(&path) {
+            let numbered = format!("{} ({}){}", stem, n, ext);
+            path = DownloadManager::free_path(&dir, &numbered).to_string_lossy().into_owned();
+            n += 1;
+        }
+        path
+    }
+
+    /// Zooms tab `tab_id` in, out or back to 100% and remembers the new zoom
+    /// for the tab's site, so its pages open at that zoom from then on.
+    /// 100% is the default and drops the site's entry. Returns the new zoom.
+    pub fn zoom_tab(&mut self, tab_id: &str, step: ZoomStep) -> Result<f64, crate::types::errors::TabError> {
+        use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, ZOOM_KEY};
+        use crate::managers::tab_manager::TabManagerTrait;
+
+        let (url, current) = self
+            .tab_manager
+            .get_tab(tab_id)
+            .map(|t| (t.url.clone(), t.zoom))
+            .ok_or_else(|| crate::types::errors::TabError::NotFound(tab_id.to_string()))?;
+        self.tab_manager.set_tab_zoom(tab_id, step.apply(current))?;
+        let zoom = self.tab_manager.get_tab(tab_id).map_or(current, |t| t.zoom);
+
+        if let Some(site) = SiteSettingsManager::site_for_url(&url) {
+            let mut mgr = SiteSettingsManager::new(self.db.connection());
+            let saved = if zoom == 1.0 {
+                mgr.remove_setting(&site, ZOOM_KEY).map(|_| ())
+            } else {
+                mgr.set_setting(&site, ZOOM_KEY, &serde_json::json!(zoom))
+            };
+            if let Err(e) = saved {
+                eprintln!("[ZOOM] {}", e);
+            }
+        }
+        Ok(zoom)
+    }
+
+    /// Suspends background tabs left idle, sooner under memory pressure.
+    /// Returns the suspended tabs' IDs.
+    pub fn suspend_idle_tabs(&mut self, memory_pressure: bool) -> Vec<String> {
+        use crate::managers::tab_manager::TabManagerTrait;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        self.tab_manager.suspend_idle_tabs(now, memory_pressure)
+    }
+
+    /// Records an OS power state change. Returns whether `saving_power`
+    /// changed with it.
+    pub fn set_power_status(&mut self, power: PowerStatus) -> bool {
+        let before = self.saving_power();
+        self.power = Some(power);
+        self.saving_power() != before
+    }
+
+    /// Whether background work is held back: `performance.battery_saver` is
+    /// on and the machine runs on battery or in the OS power saver.
+    pub fn saving_power(&self) -> bool {
+        use crate::services::settings_engine::SettingsEngineTrait;
+        self.settings_engine.get_settings().performance.battery_saver
+            && self.power.is_some_and(|p| p.is_saving())
+    }
+
+    /// How long a background loop that normally runs every `interval` waits
+    /// between runs; [`BATTERY_SAVER_SLOWDOWN`] times longer while saving power.
+    pub fn background_interval(&self, interval: std::time::Duration) -> std::time::Duration {
+        if self.saving_power() {
+            interval * BATTERY_SAVER_SLOWDOWN
+        } else {
+            interval
+        }
+    }
+
+    /// Unlocks the password vault and re-keys the GitHub and AI secrets with
+    /// the master key. Returns whether the password was right.
+    pub fn unlock_vault(&mut self, master_password: &str) -> Result<bool, crate::types::errors::CryptoError> {
+        use crate::services::password_manager::PasswordManagerTrait;
+
+        let ok = self.password_manager.unlock(master_password)?;
+        if ok {
+            self.rekey_with_vault();
+        }
+        Ok(ok)
+    }
+
+    /// Unlocks the vault with the master key kept in the OS keystore (see
+    /// `PasswordManager::enable_quick_unlock`), like [`App::unlock_vault`].
+    pub fn quick_unlock_vault(&mut self) -> Result<bool, crate::types::errors::CryptoError> {
+        let ok = self.password_manager.unlock_from_keystore()?;
+        if ok {
+            self.rekey_with_vault();
+        }
+        Ok(ok)
+    }
+
+    /// Copies the password of login `id` to the clipboard, cleared again
+    /// after `privacy.clipboard_clear_seconds`. Returns whether the
+    /// clipboard took it.
+    pub fn copy_password(&self, id: &str) -> Result<bool, crate::types::errors::CryptoError> {
+        use crate::services::settings_engine::SettingsEngineTrait;
+        let seconds = self.settings_engine.get_settings().privacy.clipboard_clear_seconds;
+        self.password_manager.copy_password(id, std::time::Duration::from_secs(seconds.into()))
+    }
+
+    /// Moves GitHub tokens and AI API keys onto the unlocked master key.
+    fn rekey_with_vault(&mut self) {
+        use crate::services::ai_assistant::AIAssistantTrait;
+        use crate::services::github_integration::GitHubIntegrationTrait;
+
+        if let Some(master_key) = self.password_manager.get_derived_key() {
+            let _ = self.github_integration.rekey_with_master(&master_key);
+            let _ = self.ai_assistant.rekey_with_master(&master_key);
+        }
+    }
+
+    /// Hands `context` to the AI assistant panel of whichever client is
+    /// showing one, as an `ai` event. Returns whether any client listens.
+    pub fn ask_ai(&self, context: &crate::types::ai::AIContext) -> bool {
+        if !self.events.has_subscribers(EventTopic::Ai) {
+            return false;
+        }
+        self.events.publish(EventTopic::Ai, serde_json::json!({"action": "ask", "context": context}));
+        true
+    }
+
+    /// Like `ask_ai`, for a caller waiting for the answer: the event also
+    /// carries an `ask` number, and what the panel sends with `answer_ai`
+    /// arrives on the returned receiver. `None` if no client listens.
+    pub fn ask_ai_for_answer(&mut self, context: &crate::types::ai::AIContext) -> Option<(u64, Receiver<AIAnswerPart>)> {
+        if !self.events.has_subscribers(EventTopic::Ai) {
+            return None;
+        }
+        let ask = self.next_ai_ask;
+        self.next_ai_ask += 1;
+        let (tx, rx) = mpsc::channel();
+        self.ai_answers.insert(ask, tx);
+        self.events.publish(EventTopic::Ai, serde_json::json!({"action": "ask", "ask": ask, "context": context}));
+        Some((ask, rx))
+    }
+
+    /// Passes `part` of the answer to `ask` on; the last part ends the ask.
+    /// Returns whether anyone was still waiting for it.
+    pub fn answer_ai(&mut self, ask: u64, part: AIAnswerPart) -> bool {
+        let Some(tx) = self.ai_answers.get(&ask) else { return false };
+        let done = part.done;
+        let delivered = tx.send(part).is_ok();
+        if done || !delivered {
+            self.ai_answers.remove(&ask);
+        }
+        delivered
+    }
+
+    /// Stops waiting for the answer to `ask`.
+    pub fn cancel_ai_ask(&mut self, ask: u64) {
+        self.ai_answers.remove(&ask);
+    }
+
+    /// Handles the renderer of the active tab dying: marks the tab crashed
+    /// and logs the crash with its URL. Returns the crashed tab's ID.
+    pub fn handle_renderer_crash(&mut self, reason: &str) -> Option<String> {
+        use crate::managers::tab_manager::TabManagerTrait;
+        let id = self.tab_manager.get_active_tab().map(|t| t.id.clone())?;
+        self.handle_tab_renderer_crash(&id, reason).then_some(id)
+    }
+
+    /// Like `handle_renderer_crash`, for the renderer of tab `tab_id`, which
+    /// need not be the active one. Returns whether the tab exists.
+    pub fn handle_tab_renderer_crash(&mut self, tab_id: &str, reason: &str) -> bool {
+        use crate::managers::tab_manager::TabManagerTrait;
+        use crate::services::crash_recovery::{crash_entry, CrashRecoveryTrait};
+
+        let Some(url) = self.tab_manager.get_tab(tab_id).map(|t| t.url.clone()) else {
+            return false;
+        };
+        let _ = self.tab_manager.mark_tab_crashed(tab_id);
+        let entry = crash_entry("renderer_crash", reason, None, Some(url));
+        if let Err(e) = self.crash_recovery.log_crash(entry) {
+            eprintln!("[CRASH] {}", e);
+        }
+        true
+    }
+
+    /// Logs the active tab's page as hung for `unresponsive_for`. Returns
+    /// its URL.
+    pub fn record_hang(&mut self, unresponsive_for: std::time::Duration) -> Option<String> {
+        use crate::managers::tab_manager::TabManagerTrait;
+        use crate::services::crash_recovery::{crash_entry, CrashRecoveryTrait};
+
+        let url = self.tab_manager.get_active_tab().map(|t| t.url.clone());
+        let message = format!("Page unresponsive for {} s", unresponsive_for.as_secs());
+        if let Err(e) = self.crash_recovery.log_crash(crash_entry("hang", &message, None, url.clone())) {
+            eprintln!("[CRASH] {}", e);
+        }
+        url
+    }
+
+    /// Backs the database up when the newest backup is older than the
+    /// backup interval. Returns the new backup; private apps keep none.
+    pub fn run_scheduled_backup(&self) -> Option<BackupInfo> {
+        if self.context.private {
+            return None;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        match self.db_backups.run_if_due(&self.db, now) {
+            Ok(backup) => backup,
+            Err(e) => {
+                eprintln!("[DB] scheduled backup failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Queues database maintenance on the worker when the last run is older
+    /// than [`maintenance::DEFAULT_INTERVAL`]. Wait on the task without
+    /// holding the app lock.
+    pub fn run_scheduled_maintenance(&self) -> DbTask<Option<MaintenanceReport>> {
+        self.db_worker.run(|conn| {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            match maintenance::run_if_due(conn, now, maintenance::DEFAULT_INTERVAL) {
+                Ok(report) => report,
+                Err(e) => {
+                    eprintln!("[DB] scheduled maintenance failed: {}", e);
+                    None
+                }
+            }
+        })
+    }
+
+    /// Switches the active settings profile and reloads every dependent
+    /// service from it. Returns the settings that differ from the old profile.
+    pub fn switch_settings_profile(&mut self, name: &str) -> Result<Vec<SettingsChange>, crate::types::errors::SettingsError> {
+        use crate::services::settings_engine::SettingsEngineTrait;
+        self.settings_engine.switch_profile(name)?;
+        let changes = self.process_settings_changes();
+        self.apply_settings();
+        Ok(changes)
+    }
+
+    /// Whether `shutdown` has started.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down
+    }
+
+    /// Shutdown sequence: runs every `ShutdownStep` in order, logging how
+    /// each one went. Database steps are given up on after their timeout so
+    /// a stuck query cannot keep the window from closing. Only the first
+    /// call does anything; later ones return an empty report.
+    pub fn shutdown(&mut self) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
+        if std::mem::replace(&mut self.shutting_down, true) {
+            return report;
+        }
+        for step in ShutdownStep::ALL {
+            let started = Instant::now();
+            let status = self.run_shutdown_step(step);
+            let elapsed = started.elapsed();
+            match &status {
+                StepStatus::Failed(e) => eprintln!("[SHUTDOWN] {} failed: {}", step.as_str(), e),
+                StepStatus::TimedOut => eprintln!("[SHUTDOWN] {} timed out after {:?}", step.as_str(), elapsed),
+                _ if elapsed > step.timeout() => eprintln!("[SHUTDOWN] {} took {:?}", step.as_str(), elapsed),
+                _ => {}
+            }
+            report.steps.push(StepReport { step, status, elapsed_ms: elapsed.as_millis() as u64 });
+        }
+        report
+    }
+
+    fn run_shutdown_step(&mut self, step: ShutdownStep) -> StepStatus {
+        use crate::managers::history_manager::{HistoryManager, HistoryManagerTrait};
+        use crate::managers::session_manager::SessionManagerTrait;
+        use crate::services::crash_recovery::CrashRecoveryTrait;
+        use crate::services::password_manager::PasswordManagerTrait;
+        use crate::services::privacy_engine::PrivacyEngineTrait;
+        use crate::services::settings_engine::SettingsEngineTrait;
+
+        let clear_data = self.settings_engine.get_settings().privacy.clear_data_on_exit;
+        let result = match step {
+            ShutdownStep::SaveSession if self.context.private || clear_data => return StepStatus::Skipped,
+            ShutdownStep::SaveSession => self.save_session().map_err(|e| e.to_string()),
+            ShutdownStep::StopSchedulers => {
+                self.session_manager.stop_periodic_save();
+                Ok(())
+            }
+            ShutdownStep::ClearOnExit if !self.context.private && !clear_data => return StepStatus::Skipped,
+            ShutdownStep::ClearOnExit => {
+                let _ = self.privacy_engine.clear_private_data();
+                if clear_data {
+                    if let Err(e) = self.session_manager.clear_session() {
+                        return StepStatus::Failed(e.to_string());
+                    }
+                    let history = self.db_worker.run(|conn| HistoryManager::new(conn).clear_all());
+                    return Self::wait_for_step(history, step);
+                }
+                Ok(())
+            }
+            ShutdownStep::MarkCleanExit | ShutdownStep::CheckpointWal if self.context.private => {
+                return StepStatus::Skipped
+            }
+            ShutdownStep::MarkCleanExit => {
+                self.crash_recovery.mark_clean_exit();
+                Ok(())
+            }
+            ShutdownStep::CheckpointWal => {
+                let checkpoint = self.db_worker.run(|conn| {
+                    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+                });
+                return Self::wait_for_step(checkpoint, step);
+            }
+            ShutdownStep::ZeroizeKeys => {
+                self.password_manager.lock();
+                Ok(())
+            }
+        };
+        match result {
+            Ok(()) => StepStatus::Done,
+            Err(e) => StepStatus::Failed(e),
+        }
+    }
+
+    /// Waits up to `step`'s timeout for its work on the database thread.
+    fn wait_for_step<E: std::fmt::Display>(task: DbTask<Result<(), E>>, step: ShutdownStep) -> StepStatus {
+        match task.wait_timeout(step.timeout()) {
+            Ok(Ok(())) => StepStatus::Done,
+            Ok(Err(e)) => StepStatus::Failed(e.to_string()),
+            Err(DbWorkerError::TimedOut) => StepStatus::TimedOut,
+            Err(e) => StepStatus::Failed(e.to_string()),
+        }
     }
 }
@@ -0,0 +1,163 @@
+//! Command-line arguments shared by `gitbrowser` and `gitbrowser-rpc`.
+//!
+//! ```text
+//! gitbrowser [OPTIONS] [URL...]
+//! ```
+//!
+//! URLs (or anything typed in the address bar) open in new tabs; so do
+//! `gitbrowser://` links, which the OS passes as arguments. When the
+//! profile is already open they go to that browser instead.
+//! `--settings` may be repeated; values are JSON where they parse as JSON
+//! (`privacy.ad_blocking=false`) and strings otherwise
+//! (`general.language=de`). They change the settings as the settings page
+//! would, so they stay after the browser closes unless `--private` is given.
+
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use crate::app::App;
+use crate::managers::profile_manager::{self, ProfileManager};
+use crate::types::errors::{CliError, SettingsError};
+
+/// Printed for `--help` and after an argument error.
+pub const USAGE: &str = "\
+Usage: gitbrowser [OPTIONS] [URL...]
+
+Options:
+  --profile <NAME>         Open user profile NAME, creating it on first use
+  --private                Keep nothing on disk: in-memory database and settings
+  --settings <KEY=VALUE>   Change a setting before the browser starts (repeatable)
+  --headless               Run without a window, serving RPC clients on the local socket
+  --rpc                    Run as the JSON-RPC backend on stdin/stdout
+  -h, --help               Print this help
+  -V, --version            Print the version";
+
+/// Parsed command-line arguments.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliArgs {
+    /// Opened in new tabs, in order.
+    pub urls: Vec<String>,
+    pub profile: Option<String>,
+    pub private: bool,
+    /// `--settings` overrides, in the order given.
+    pub settings: Vec<(String, Value)>,
+    pub headless: bool,
+    pub rpc: bool,
+    pub help: bool,
+    pub version: bool,
+}
+
+impl CliArgs {
+    /// Parses the arguments after the program name. `--name value` and
+    /// `--name=value` are both accepted; everything after `--` is a URL.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, CliError> {
+        let mut parsed = CliArgs::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline.clone().or_else(|| args.next()).ok_or_else(|| CliError::MissingValue(name.clone()))
+            };
+            match name.as_str() {
+                "--profile" => {
+                    // The name becomes a directory under the profiles root
+                    let name = value()?;
+                    if profile_manager::validate_name(&name).is_err() {
+                        return Err(CliError::InvalidProfile(name));
+                    }
+                    parsed.profile = Some(name);
+                }
+                "--settings" => parsed.settings.push(parse_setting(&value()?)?),
+                "--private" => parsed.private = true,
+                "--headless" => parsed.headless = true,
+                "--rpc" => parsed.rpc = true,
+                "-h" | "--help" => parsed.help = true,
+                "-V" | "--version" => parsed.version = true,
+                "--" => parsed.urls.extend(args.by_ref()),
+                other if other.starts_with('-') => return Err(CliError::UnknownOption(other.to_string())),
+                _ => parsed.urls.push(arg),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Whether the arguments ask for the RPC backend instead of a window.
+    pub fn wants_backend(&self) -> bool {
+        self.rpc || self.headless
+    }
+
+    /// Opens the app the arguments describe: the `--profile` (locked while
+    /// open) or the database at `db_path`, or a private app over either.
+    /// A private app has nothing to unlock, so `master_password` is unused.
+    pub fn open_app(&self, db_path: &str, master_password: Option<&str>) -> Result<App, Box<dyn std::error::Error>> {
+        let profiles = ProfileManager::with_default_root();
+        match (&self.profile, self.private) {
+            (Some(name), false) => App::open_profile(&profiles, name, master_password),
+            (Some(name), true) => App::with_context(profiles.context(name).private_of(), None),
+            (None, false) => App::open(db_path, master_password),
+            (None, true) => App::with_context(ProfileManager::default_context(db_path).private_of(), None),
+        }
+    }
+
+    /// Applies the `--settings` overrides to `app`, stopping at the first
+    /// one it rejects.
+    pub fn apply_settings(&self, app: &mut App) -> Result<(), SettingsError> {
+        use crate::services::settings_engine::SettingsEngineTrait;
+        for (key, value) in &self.settings {
+            app.settings_engine.set_value(key, value.clone())?;
+        }
+        app.process_settings_changes();
+        Ok(())
+    }
+
+    /// Opens the URL arguments in new tabs, the first one active. Returns
+    /// the ids of the new tabs.
+    pub fn open_urls(&self, app: &mut App) -> Vec<String> {
+        open_urls(app, &self.urls)
+    }
+
+    /// Where the browser already running for these arguments listens for
+    /// their URLs (see `single_instance`): the profile's directory, or the
+    /// data directory for the default database. Private windows never hand
+    /// their URLs over.
+    pub fn instance_dir(&self, db_path: &str) -> Option<PathBuf> {
+        if self.private {
+            return None;
+        }
+        Some(match &self.profile {
+            Some(name) => ProfileManager::with_default_root().paths(name).dir,
+            None => ProfileManager::default_context(db_path).paths.dir,
+        })
+    }
+}
+
+/// Opens `inputs` (URLs, `gitbrowser://` links or anything typed in the
+/// address bar) in new tabs, the first one active. Returns the ids of the
+/// new tabs.
+pub fn open_urls(app: &mut App, inputs: &[String]) -> Vec<String> {
+    use crate::managers::tab_manager::TabManagerTrait;
+    use crate::services::search_engine_registry::url_for_input;
+    use crate::services::settings_engine::SettingsEngineTrait;
+
+    let search = app.settings_engine.get_settings().search.clone();
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| app.tab_manager.create_tab(Some(&url_for_input(input, &search)), i == 0))
+        .collect()
+}
+
+/// Splits a `--settings` value into its key and JSON value.
+fn parse_setting(arg: &str) -> Result<(String, Value), CliError> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+            Ok((key.trim().to_string(), value))
+        }
+        _ => Err(CliError::InvalidSetting(arg.to_string())),
+    }
+}
@@ -0,0 +1,172 @@
+//! Rotating database backups.
+//!
+//! Backups are written with [`Database::backup_to`] as
+//! `gitbrowser-<unix millis>.db` into one directory (`backups/` next to the
+//! database). Only the newest `keep` are kept. The scheduler makes a new one
+//! once the newest is older than `interval`. Backups are keyed like the
+//! database; `rekey` moves them along when its encryption changes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::encryption::{self, DatabaseKey};
+use super::Database;
+use crate::types::errors::BackupError;
+
+/// Backups kept by default.
+pub const DEFAULT_KEEP: usize = 5;
+
+/// Time between scheduled backups by default.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const PREFIX: &str = "gitbrowser-";
+const SUFFIX: &str = ".db";
+
+/// A backup on disk.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BackupInfo {
+    /// File name; what `db.restore` takes.
+    pub name: String,
+    pub path: PathBuf,
+    /// Unix seconds.
+    pub created_at: i64,
+    pub size: u64,
+}
+
+/// The backup directory and its rotation policy.
+pub struct BackupRotation {
+    dir: PathBuf,
+    keep: usize,
+    interval: Duration,
+}
+
+impl BackupRotation {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, keep: DEFAULT_KEEP, interval: DEFAULT_INTERVAL }
+    }
+
+    /// Backups kept; at least one.
+    pub fn with_keep(mut self, keep: usize) -> Self {
+        self.keep = keep.max(1);
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Backups in the directory, newest first.
+    pub fn list(&self) -> Result<Vec<BackupInfo>, BackupError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(BackupError::IoError(e.to_string())),
+        };
+        let mut backups: Vec<(i64, BackupInfo)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let millis = backup_millis(&name)?;
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                Some((millis, BackupInfo { name, path: entry.path(), created_at: millis / 1000, size }))
+            })
+            .collect();
+        backups.sort_by_key(|b| std::cmp::Reverse(b.0));
+        Ok(backups.into_iter().map(|(_, info)| info).collect())
+    }
+
+    /// The backup named `name`. Only names from `list` are accepted.
+    pub fn find(&self, name: &str) -> Result<BackupInfo, BackupError> {
+        self.list()?
+            .into_iter()
+            .find(|b| b.name == name)
+            .ok_or_else(|| BackupError::NotFound(name.to_string()))
+    }
+
+    /// Backs `db` up now (`now_millis` names the file) and drops the oldest
+    /// backups beyond `keep`.
+    pub fn backup_now(&self, db: &Database, now_millis: i64) -> Result<BackupInfo, BackupError> {
+        let name = self.write_backup(db, now_millis)?;
+        self.prune()?;
+        self.find(&name)
+    }
+
+    /// Writes a backup and returns its name.
+    fn write_backup(&self, db: &Database, now_millis: i64) -> Result<String, BackupError> {
+        fs::create_dir_all(&self.dir).map_err(|e| BackupError::IoError(e.to_string()))?;
+        // A later millisecond than every existing backup keeps names unique and ordered
+        let newest = self.list()?.first().and_then(|b| backup_millis(&b.name)).unwrap_or(0);
+        let millis = now_millis.max(newest + 1);
+        let name = format!("{}{}{}", PREFIX, millis, SUFFIX);
+        let path = self.dir.join(&name);
+        // Written under another name first so a cut-short backup is never listed
+        let partial = self.dir.join(format!("{}.partial", name));
+        db.backup_to(&partial).map_err(|e| {
+            let _ = fs::remove_file(&partial);
+            BackupError::DatabaseError(e.to_string())
+        })?;
+        fs::rename(&partial, &path).map_err(|e| BackupError::IoError(e.to_string()))?;
+        Ok(name)
+    }
+
+    /// Whether a scheduled backup is due at `now` (unix seconds).
+    pub fn is_due(&self, now: i64) -> Result<bool, BackupError> {
+        Ok(match self.list()?.first() {
+            Some(newest) => now - newest.created_at >= self.interval.as_secs() as i64,
+            None => true,
+        })
+    }
+
+    /// Backs up `db` if one is due.
+    pub fn run_if_due(&self, db: &Database, now_millis: i64) -> Result<Option<BackupInfo>, BackupError> {
+        if !self.is_due(now_millis / 1000)? {
+            return Ok(None);
+        }
+        self.backup_now(db, now_millis).map(Some)
+    }
+
+    /// Restores `name` into `db`, backing the current contents up first;
+    /// returns that safety backup.
+    pub fn restore(&self, db: &Database, name: &str, now_millis: i64) -> Result<BackupInfo, BackupError> {
+        let backup = self.find(name)?;
+        // Pruned only afterwards, so the backup being restored is still there
+        let safety = self.write_backup(db, now_millis)?;
+        db.restore_from(&backup.path).map_err(|e| BackupError::DatabaseError(e.to_string()))?;
+        let safety = self.find(&safety)?;
+        self.prune()?;
+        Ok(safety)
+    }
+
+    /// Moves every backup from key `from` to key `to` (`None` is
+    /// unencrypted), after the database itself was. A backup that cannot be
+    /// moved is deleted: it could not be restored into the database anymore.
+    pub fn rekey(&self, from: Option<&DatabaseKey>, to: Option<&DatabaseKey>) -> Result<(), BackupError> {
+        for backup in self.list()? {
+            if let Err(e) = encryption::rekey_file(&backup.path, from, to) {
+                eprintln!("[DB] deleting backup {}: {}", backup.name, e);
+                fs::remove_file(&backup.path).map_err(|e| BackupError::IoError(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn prune(&self) -> Result<(), BackupError> {
+        for old in self.list()?.iter().skip(self.keep) {
+            fs::remove_file(&old.path).map_err(|e| BackupError::IoError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Creation time encoded in a backup file name.
+fn backup_millis(name: &str) -> Option<i64> {
+    name.strip_prefix(PREFIX)?.strip_suffix(SUFFIX)?.parse().ok()
+}
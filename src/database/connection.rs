@@ -2,18 +2,34 @@
 //!
 //! Provides the [`Database`] struct that wraps a `rusqlite::Connection`
 //! and automatically runs schema migrations on open.
+//!
+//! File databases use WAL journaling so the RPC server, autosave and the UI
+//! can read while another connection writes; writers wait up to
+//! [`BUSY_TIMEOUT`] for a lock instead of failing with `SQLITE_BUSY`.
 
+use rusqlite::backup::Backup;
 use rusqlite::Connection;
 use std::path::Path;
+use std::time::Duration;
 
+use super::encryption::{self, DatabaseKey};
 use super::migrations;
 
+/// How long a statement waits for a lock held by another connection.
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pages copied per backup step; other connections get the lock in between.
+const BACKUP_PAGES_PER_STEP: i32 = 256;
+const BACKUP_STEP_PAUSE: Duration = Duration::from_millis(5);
+
 /// Core database wrapper providing SQLite connection management.
 ///
 /// The `Database` struct owns a `rusqlite::Connection` and ensures that
 /// all required tables and indexes are created when the database is opened.
 pub struct Database {
     conn: Connection,
+    /// SQLCipher key, also used for backups.
+    key: Option<DatabaseKey>,
 }
 
 impl Database {
@@ -26,7 +42,22 @@ impl Database {
     /// Returns `rusqlite::Error` if the connection cannot be established or migrations fail.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, rusqlite::Error> {
         let conn = Connection::open(path)?;
-        let db = Self { conn };
+        configure(&conn)?;
+        let db = Self { conn, key: None };
+        db.run_migrations()?;
+        Ok(db)
+    }
+
+    /// Opens a SQLCipher database encrypted with `key` and runs migrations.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` with `ErrorCode::NotADatabase` if `key` does
+    /// not decrypt the file, or if the connection or migrations fail.
+    pub fn open_encrypted<P: AsRef<Path>>(path: P, key: &DatabaseKey) -> Result<Self, rusqlite::Error> {
+        let conn = Connection::open(path)?;
+        encryption::apply_key(&conn, key)?;
+        configure(&conn)?;
+        let db = Self { conn, key: Some(key.clone()) };
         db.run_migrations()?;
         Ok(db)
     }
@@ -39,15 +70,15 @@ impl Database {
     /// Returns `rusqlite::Error` if the connection cannot be established or migrations fail.
     pub fn open_in_memory() -> Result<Self, rusqlite::Error> {
         let conn = Connection::open_in_memory()?;
-        let db = Self { conn };
+        configure(&conn)?;
+        let db = Self { conn, key: None };
         db.run_migrations()?;
         Ok(db)
     }
 
-    /// Runs all schema migrations, creating tables and indexes if they do not exist.
+    /// Runs the pending schema migrations from `migrations::MIGRATIONS`.
     ///
-    /// Uses `CREATE TABLE IF NOT EXISTS` and `CREATE INDEX IF NOT EXISTS` so the
-    /// method is idempotent and safe to call on every startup.
+    /// Applied steps are recorded, so this is safe to call on every startup.
     fn run_migrations(&self) -> Result<(), rusqlite::Error> {
         migrations::run_all(&self.conn)
     }
@@ -59,4 +90,77 @@ impl Database {
     pub fn connection(&self) -> &Connection {
         &self.conn
     }
+
+    /// Writes a consistent copy of the database to `path` with SQLite's
+    /// online backup API; other connections keep working meanwhile. An
+    /// encrypted database is backed up with its key.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if `path` cannot be written or the copy fails.
+    pub fn backup_to<P: AsRef<Path>>(&self, path: P) -> Result<(), rusqlite::Error> {
+        let mut target = self.keyed_connection(path.as_ref())?;
+        let backup = Backup::new(&self.conn, &mut target)?;
+        backup.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)
+    }
+
+    /// Replaces the database contents with the backup at `path`, then runs
+    /// migrations so an older backup is brought up to date.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the backup cannot be read, fails its
+    /// integrity check, or the database is in memory.
+    pub fn restore_from<P: AsRef<Path>>(&self, path: P) -> Result<(), rusqlite::Error> {
+        let source = self.keyed_connection(path.as_ref())?;
+        let check: String = source.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+        if check != "ok" {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+                Some(format!("backup failed its integrity check: {}", check)),
+            ));
+        }
+        let live = match self.conn.path() {
+            Some(live) if !live.is_empty() => live.to_string(),
+            _ => return Err(rusqlite::Error::InvalidPath(path.as_ref().to_path_buf())),
+        };
+        let mut target = self.keyed_connection(Path::new(&live))?;
+        Backup::new(&source, &mut target)?.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)?;
+        drop(target);
+        self.run_migrations()
+    }
+
+    /// A plain connection to `path`, keyed like this database.
+    fn keyed_connection(&self, path: &Path) -> Result<Connection, rusqlite::Error> {
+        let conn = Connection::open(path)?;
+        if let Some(key) = &self.key {
+            encryption::apply_key(&conn, key)?;
+        }
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(conn)
+    }
+
+    /// Copies the write-ahead log into the database file and truncates it.
+    ///
+    /// Called on shutdown so the database file is complete on its own.
+    /// A no-op for databases not in WAL mode.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the checkpoint cannot run.
+    pub fn checkpoint(&self) -> Result<(), rusqlite::Error> {
+        self.conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+    }
+}
+
+/// Connection settings applied before migrations: WAL journaling (in-memory
+/// databases keep their `memory` journal), `synchronous = NORMAL`, which is
+/// durable under WAL except on power loss, foreign keys and the busy timeout.
+/// New databases are created with incremental auto-vacuum for
+/// [`maintenance`](super::maintenance) to use.
+pub(crate) fn configure(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // Only takes effect before the first table exists, or on the next VACUUM
+    conn.pragma_update(None, "auto_vacuum", "INCREMENTAL")?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.pragma_update(None, "foreign_keys", "ON")?;
+    Ok(())
 }
@@ -0,0 +1,330 @@
+//! At-rest encryption of the database file with SQLCipher.
+//!
+//! `privacy.database_encryption` says how the database should be keyed; the
+//! key file next to it (`gitbrowser.db.key`) records how it is keyed now.
+//! When the two differ, [`prepare`] rewrites the database under the new key
+//! (or none) with `sqlcipher_export` before it is opened. Keystore keys are
+//! random and kept in the OS keystore; master-password keys are derived
+//! from the password and the salt stored in the key file.
+//!
+//! Encryption needs a build with the `sqlcipher` feature. Without it an
+//! unencrypted database stays unencrypted and an encrypted one cannot be
+//! opened.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rusqlite::{Connection, ErrorCode};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use super::backup::BackupRotation;
+use super::Database;
+use crate::services::crypto_service::{CryptoService, CryptoServiceTrait};
+use crate::types::errors::EncryptionError;
+use crate::types::settings::DatabaseEncryption;
+
+/// Length of a SQLCipher raw key.
+pub const KEY_LENGTH: usize = 32;
+
+/// Keystore entry holding the keystore key.
+#[cfg(feature = "sqlcipher")]
+const KEYSTORE_ENTRY: &str = "database-key";
+
+/// First bytes of every unencrypted SQLite file.
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// A raw SQLCipher key, wiped on drop.
+#[derive(Clone)]
+pub struct DatabaseKey(Vec<u8>);
+
+impl DatabaseKey {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, EncryptionError> {
+        if bytes.len() != KEY_LENGTH {
+            return Err(EncryptionError::KeystoreError(format!("key must be {} bytes", KEY_LENGTH)));
+        }
+        Ok(Self(bytes))
+    }
+
+    /// The key as a raw-key literal for `PRAGMA key` and `ATTACH ... KEY`.
+    fn sql_literal(&self) -> String {
+        let hex: String = self.0.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("\"x'{}'\"", hex)
+    }
+}
+
+impl Drop for DatabaseKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Contents of the key file: how the database is keyed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct KeyFile {
+    encryption: DatabaseEncryption,
+    /// Base64 PBKDF2 salt of a master-password key.
+    #[serde(default)]
+    salt: Option<String>,
+}
+
+/// Path of the key file recording how `db_path` is keyed.
+pub fn key_file_path(db_path: &Path) -> PathBuf {
+    with_suffix(db_path, ".key")
+}
+
+/// Whether `db_path` is missing, empty or an unencrypted SQLite file.
+pub fn is_plaintext(db_path: &Path) -> bool {
+    match fs::read(db_path) {
+        Ok(bytes) => bytes.is_empty() || bytes.starts_with(SQLITE_HEADER),
+        Err(_) => true,
+    }
+}
+
+/// How `db_path` is keyed now, finishing a migration cut short first.
+pub fn current_encryption(db_path: &Path) -> Result<DatabaseEncryption, EncryptionError> {
+    finish_migration(db_path)?;
+    Ok(read_key_file(&key_file_path(db_path))?.map_or(DatabaseEncryption::Off, |k| k.encryption))
+}
+
+/// Master-password key for the salt in the key file.
+pub fn derive_password_key(password: &str, salt: &[u8]) -> Result<DatabaseKey, EncryptionError> {
+    let key = CryptoService::new()
+        .derive_key(password, salt)
+        .map_err(|e| EncryptionError::MigrationFailed(e.to_string()))?;
+    DatabaseKey::from_bytes(key)
+}
+
+/// Brings `db_path` to the `wanted` encryption and returns the key to open
+/// it with (`None` for an unencrypted database). Switching to or from
+/// `MasterPassword` needs `master_password`.
+pub fn prepare(
+    db_path: &Path,
+    wanted: DatabaseEncryption,
+    master_password: Option<&str>,
+) -> Result<Option<DatabaseKey>, EncryptionError> {
+    prepare_with_backups(db_path, None, wanted, master_password)
+}
+
+/// Like [`prepare`], and moves the backups in `backups` to the new key along
+/// with the database, so they stay restorable and none stays unencrypted.
+pub fn prepare_with_backups(
+    db_path: &Path,
+    backups: Option<&BackupRotation>,
+    wanted: DatabaseEncryption,
+    master_password: Option<&str>,
+) -> Result<Option<DatabaseKey>, EncryptionError> {
+    if db_path == Path::new(":memory:") {
+        return Ok(None);
+    }
+    finish_migration(db_path)?;
+    let current = read_key_file(&key_file_path(db_path))?;
+    let current_mode = current.as_ref().map_or(DatabaseEncryption::Off, |k| k.encryption);
+    let current_key = key_for(current.as_ref(), master_password, false)?;
+    if current_mode == wanted {
+        return Ok(current_key);
+    }
+    if !cfg!(feature = "sqlcipher") {
+        if current_mode == DatabaseEncryption::Off {
+            eprintln!("[DB] privacy.database_encryption needs a build with the sqlcipher feature; the database stays unencrypted");
+            return Ok(None);
+        }
+        return Err(EncryptionError::Unsupported);
+    }
+
+    let next = match wanted {
+        DatabaseEncryption::Off => None,
+        DatabaseEncryption::Keystore => Some(KeyFile { encryption: wanted, salt: None }),
+        DatabaseEncryption::MasterPassword => Some(KeyFile {
+            encryption: wanted,
+            salt: Some(BASE64.encode(CryptoService::new().generate_salt())),
+        }),
+    };
+    let next_key = key_for(next.as_ref(), master_password, true)?;
+    migrate(db_path, current_key.as_ref(), next_key.as_ref(), next.as_ref())?;
+    if let Some(backups) = backups {
+        backups
+            .rekey(current_key.as_ref(), next_key.as_ref())
+            .map_err(|e| EncryptionError::MigrationFailed(e.to_string()))?;
+    }
+    Ok(next_key)
+}
+
+/// Rewrites the database file at `path` from key `from` to key `to` in place.
+pub fn rekey_file(path: &Path, from: Option<&DatabaseKey>, to: Option<&DatabaseKey>) -> Result<(), EncryptionError> {
+    let tmp = with_suffix(path, ".tmp");
+    if let Err(e) = export(path, from, to, &tmp) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    fs::rename(&tmp, path).map_err(|e| EncryptionError::MigrationFailed(e.to_string()))
+}
+
+/// Opens `db_path` with `key`, or unencrypted without one.
+pub fn open(db_path: &str, key: Option<&DatabaseKey>) -> Result<Database, EncryptionError> {
+    let result = match key {
+        Some(key) => Database::open_encrypted(db_path, key),
+        None => Database::open(db_path),
+    };
+    result.map_err(|e| match e.sqlite_error_code() {
+        Some(ErrorCode::NotADatabase) => EncryptionError::WrongKey,
+        _ => EncryptionError::DatabaseError(e.to_string()),
+    })
+}
+
+/// Keys `conn`; it must be the first statement on the connection.
+pub(crate) fn apply_key(conn: &Connection, key: &DatabaseKey) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(&format!("PRAGMA key = {};", key.sql_literal()))
+}
+
+/// Rewrites the database under `to` and records `next` as its key file.
+///
+/// The copy is written to `<db>.tmp` and the new key file to `<db>.key.new`
+/// before the copy replaces the database, so `finish_migration` can tell
+/// after a crash whether the replace happened.
+fn migrate(
+    db_path: &Path,
+    from: Option<&DatabaseKey>,
+    to: Option<&DatabaseKey>,
+    next: Option<&KeyFile>,
+) -> Result<(), EncryptionError> {
+    let tmp = with_suffix(db_path, ".tmp");
+    let pending = with_suffix(db_path, ".key.new");
+    if db_path.exists() {
+        export(db_path, from, to, &tmp)?;
+    }
+    write_key_file(&pending, next)?;
+    if tmp.exists() {
+        for suffix in ["-wal", "-shm"] {
+            let _ = fs::remove_file(with_suffix(db_path, suffix));
+        }
+        fs::rename(&tmp, db_path).map_err(|e| EncryptionError::MigrationFailed(e.to_string()))?;
+    }
+    finish_migration(db_path)
+}
+
+/// Installs the pending key file once the database was replaced; drops a
+/// migration that never got that far.
+fn finish_migration(db_path: &Path) -> Result<(), EncryptionError> {
+    let tmp = with_suffix(db_path, ".tmp");
+    let pending = with_suffix(db_path, ".key.new");
+    if !pending.exists() {
+        return Ok(());
+    }
+    if tmp.exists() {
+        let _ = fs::remove_file(&tmp);
+        let _ = fs::remove_file(&pending);
+        return Ok(());
+    }
+    let next = read_key_file(&pending)?;
+    let key_file = key_file_path(db_path);
+    match next {
+        Some(next) if next.encryption != DatabaseEncryption::Off => {
+            fs::rename(&pending, &key_file).map_err(|e| EncryptionError::MigrationFailed(e.to_string()))
+        }
+        _ => {
+            let _ = fs::remove_file(&key_file);
+            let _ = fs::remove_file(&pending);
+            Ok(())
+        }
+    }
+}
+
+/// Copies the database into `target` keyed with `to` (unencrypted without one).
+#[cfg(feature = "sqlcipher")]
+fn export(db_path: &Path, from: Option<&DatabaseKey>, to: Option<&DatabaseKey>, target: &Path) -> Result<(), EncryptionError> {
+    let failed = |e: rusqlite::Error| match e.sqlite_error_code() {
+        Some(ErrorCode::NotADatabase) => EncryptionError::WrongKey,
+        _ => EncryptionError::MigrationFailed(e.to_string()),
+    };
+    let _ = fs::remove_file(target);
+    let conn = Connection::open(db_path).map_err(failed)?;
+    if let Some(key) = from {
+        apply_key(&conn, key).map_err(failed)?;
+    }
+    // Also checks the key: the first read of an encrypted page fails with a wrong one
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(())).map_err(failed)?;
+    let target_key = to.map_or_else(|| "''".to_string(), DatabaseKey::sql_literal);
+    conn.execute(
+        &format!("ATTACH DATABASE ?1 AS export KEY {}", target_key),
+        [target.to_string_lossy()],
+    )
+    .map_err(failed)?;
+    conn.query_row("SELECT sqlcipher_export('export')", [], |_| Ok(())).map_err(failed)?;
+    conn.execute("DETACH DATABASE export", []).map_err(failed)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+fn export(_db_path: &Path, _from: Option<&DatabaseKey>, _to: Option<&DatabaseKey>, _target: &Path) -> Result<(), EncryptionError> {
+    Err(EncryptionError::Unsupported)
+}
+
+/// Key described by a key file; `create` makes a keystore key if none exists.
+fn key_for(
+    key_file: Option<&KeyFile>,
+    master_password: Option<&str>,
+    create: bool,
+) -> Result<Option<DatabaseKey>, EncryptionError> {
+    let Some(key_file) = key_file else {
+        return Ok(None);
+    };
+    match key_file.encryption {
+        DatabaseEncryption::Off => Ok(None),
+        DatabaseEncryption::Keystore => keystore_key(create).map(Some),
+        DatabaseEncryption::MasterPassword => {
+            let password = master_password.ok_or(EncryptionError::PasswordRequired)?;
+            let salt = key_file
+                .salt
+                .as_deref()
+                .and_then(|s| BASE64.decode(s).ok())
+                .ok_or_else(|| EncryptionError::MigrationFailed("key file has no salt".to_string()))?;
+            derive_password_key(password, &salt).map(Some)
+        }
+    }
+}
+
+#[cfg(feature = "sqlcipher")]
+fn keystore_key(create: bool) -> Result<DatabaseKey, EncryptionError> {
+    use crate::platform::keystore;
+
+    let keystore_error = |e: crate::types::errors::KeystoreError| EncryptionError::KeystoreError(e.to_string());
+    let bytes = if create {
+        keystore::get_or_create(KEYSTORE_ENTRY, KEY_LENGTH).map_err(keystore_error)?
+    } else {
+        keystore::retrieve(KEYSTORE_ENTRY)
+            .map_err(keystore_error)?
+            .ok_or_else(|| EncryptionError::KeystoreError("no database key in the keystore".to_string()))?
+    };
+    DatabaseKey::from_bytes(bytes)
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+fn keystore_key(_create: bool) -> Result<DatabaseKey, EncryptionError> {
+    Err(EncryptionError::Unsupported)
+}
+
+fn read_key_file(path: &Path) -> Result<Option<KeyFile>, EncryptionError> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| EncryptionError::MigrationFailed(format!("invalid key file: {}", e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(EncryptionError::MigrationFailed(e.to_string())),
+    }
+}
+
+/// Writes the key file, or an `Off` entry without one.
+fn write_key_file(path: &Path, key_file: Option<&KeyFile>) -> Result<(), EncryptionError> {
+    let off = KeyFile { encryption: DatabaseEncryption::Off, salt: None };
+    let json = serde_json::to_string_pretty(key_file.unwrap_or(&off))
+        .map_err(|e| EncryptionError::MigrationFailed(e.to_string()))?;
+    fs::write(path, json).map_err(|e| EncryptionError::MigrationFailed(e.to_string()))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
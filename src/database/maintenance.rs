@@ -0,0 +1,143 @@
+//! Periodic database upkeep.
+//!
+//! [`run`] refreshes the query planner statistics (`PRAGMA optimize`), hands
+//! free pages back to the file system with an incremental vacuum and runs
+//! `PRAGMA integrity_check`; each run is recorded in `maintenance_runs`.
+//! [`stats`] reports the space the database takes and [`compact`] rewrites it
+//! with a full `VACUUM`. Everything takes a plain connection so it can run on
+//! the [`DbWorker`](super::DbWorker) thread.
+
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+/// Time between scheduled maintenance runs by default.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Runs kept in `maintenance_runs`.
+const KEEP_RUNS: i64 = 30;
+
+/// Space taken by the database.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DbStats {
+    /// Bytes in the database file.
+    pub size: u64,
+    /// Bytes in unused pages; what compacting gives back.
+    pub free: u64,
+    /// Bytes in the write-ahead log.
+    pub wal_size: u64,
+    pub page_size: u64,
+    pub page_count: u64,
+    /// Whether free pages are handed back by scheduled maintenance. Off for
+    /// databases created before it was enabled until they are compacted.
+    pub incremental_vacuum: bool,
+}
+
+/// Outcome of one maintenance run.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MaintenanceReport {
+    /// Unix seconds.
+    pub ran_at: i64,
+    /// Problems found by the integrity check; empty for a sound database.
+    pub integrity_errors: Vec<String>,
+    /// Bytes handed back by the incremental vacuum.
+    pub reclaimed: u64,
+}
+
+/// Current size of the database behind `conn`.
+pub fn stats(conn: &Connection) -> Result<DbStats, rusqlite::Error> {
+    let pragma = |name: &str| conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0));
+    let page_size = pragma("page_size")? as u64;
+    let page_count = pragma("page_count")? as u64;
+    let wal_size = match conn.path() {
+        Some(path) if !path.is_empty() => std::fs::metadata(format!("{}-wal", path)).map(|m| m.len()).unwrap_or(0),
+        _ => 0,
+    };
+    Ok(DbStats {
+        size: page_size * page_count,
+        free: page_size * pragma("freelist_count")? as u64,
+        wal_size,
+        page_size,
+        page_count,
+        // 2 = INCREMENTAL
+        incremental_vacuum: pragma("auto_vacuum")? == 2,
+    })
+}
+
+/// Problems reported by `PRAGMA integrity_check`; empty if there are none.
+pub fn integrity_check(conn: &Connection) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let problems: Vec<String> = rows.collect::<Result<_, _>>()?;
+    Ok(problems.into_iter().filter(|p| p != "ok").collect())
+}
+
+/// Optimizes, vacuums and checks the database, and records the run at `now`
+/// (unix seconds). Integrity problems are reported, not fixed.
+pub fn run(conn: &Connection, now: i64) -> Result<MaintenanceReport, rusqlite::Error> {
+    conn.execute_batch("PRAGMA optimize;")?;
+    let before = stats(conn)?.free;
+    // Frees nothing unless auto_vacuum is INCREMENTAL. Each step frees one
+    // page, so the pragma is stepped until it is done.
+    {
+        let mut vacuum = conn.prepare("PRAGMA incremental_vacuum")?;
+        let mut rows = vacuum.query([])?;
+        while rows.next()?.is_some() {}
+    }
+    let reclaimed = before.saturating_sub(stats(conn)?.free);
+    let integrity_errors = integrity_check(conn)?;
+    if !integrity_errors.is_empty() {
+        eprintln!("[DB] integrity check found {} problem(s): {}", integrity_errors.len(), integrity_errors.join("; "));
+    }
+
+    conn.execute(
+        "INSERT INTO maintenance_runs (ran_at, integrity_errors, reclaimed) VALUES (?1, ?2, ?3)",
+        params![now, serde_json::to_string(&integrity_errors).unwrap_or_default(), reclaimed as i64],
+    )?;
+    conn.execute(
+        "DELETE FROM maintenance_runs WHERE id NOT IN (SELECT id FROM maintenance_runs ORDER BY ran_at DESC, id DESC LIMIT ?1)",
+        params![KEEP_RUNS],
+    )?;
+    Ok(MaintenanceReport { ran_at: now, integrity_errors, reclaimed })
+}
+
+/// The most recent recorded run.
+pub fn last_run(conn: &Connection) -> Result<Option<MaintenanceReport>, rusqlite::Error> {
+    conn.query_row(
+        "SELECT ran_at, integrity_errors, reclaimed FROM maintenance_runs ORDER BY ran_at DESC, id DESC LIMIT 1",
+        [],
+        |row| {
+            let errors: String = row.get(1)?;
+            Ok(MaintenanceReport {
+                ran_at: row.get(0)?,
+                integrity_errors: serde_json::from_str(&errors).unwrap_or_default(),
+                reclaimed: row.get::<_, i64>(2)? as u64,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Runs maintenance if the last run is at least `interval` before `now`
+/// (unix seconds). Returns the new run.
+pub fn run_if_due(conn: &Connection, now: i64, interval: Duration) -> Result<Option<MaintenanceReport>, rusqlite::Error> {
+    if let Some(last) = last_run(conn)? {
+        if now - last.ran_at < interval.as_secs() as i64 {
+            return Ok(None);
+        }
+    }
+    run(conn, now).map(Some)
+}
+
+/// Rewrites the database without free pages and switches it to incremental
+/// vacuuming, so later maintenance keeps it compact. Returns the new size.
+///
+/// Needs free disk space about the size of the database and blocks other
+/// writers until done.
+pub fn compact(conn: &Connection) -> Result<DbStats, rusqlite::Error> {
+    conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")?;
+    // VACUUM goes through the WAL; fold it back so the file size is final
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    stats(conn)
+}
@@ -1,12 +1,60 @@
 //! Schema migrations for the GitBrowser SQLite database.
 //!
-//! Uses a `schema_version` table to track which migrations have been applied.
-//! Each migration runs exactly once and is recorded with a timestamp.
+//! Every schema change is a numbered step in [`MIGRATIONS`]; new tables and
+//! columns are added there and nowhere else. A `schema_version` table records
+//! each applied step. On open, pending steps run in order, each in its own
+//! transaction together with its version record, and a database written by a
+//! newer build is refused instead of being used with a schema this build
+//! does not know.
 
 use rusqlite::Connection;
 
-/// Current schema version. Bump this when adding a new migration.
-pub const CURRENT_SCHEMA_VERSION: i32 = 2;
+/// One schema change.
+#[derive(Clone, Copy)]
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> Result<(), rusqlite::Error>,
+}
+
+/// Every migration, in order. Append new ones with the next version.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "Initial schema: all core tables", up: migration_v1 },
+    Migration {
+        version: 2,
+        description: "Add content_scripts to extensions, uses_master to secure_store",
+        up: migration_v2,
+    },
+    Migration { version: 3, description: "Add reading_list table", up: migration_v3 },
+    Migration { version: 4, description: "Add annotations table", up: migration_v4 },
+    Migration { version: 5, description: "Add site_settings table", up: migration_v5 },
+    Migration {
+        version: 6,
+        description: "Add background to extensions, extension_storage table",
+        up: migration_v6,
+    },
+    Migration { version: 7, description: "Add sync_state and sync_device tables", up: migration_v7 },
+    Migration { version: 8, description: "Add github_notifications table", up: migration_v8 },
+    Migration { version: 9, description: "Add github_repos cache table", up: migration_v9 },
+    Migration {
+        version: 10,
+        description: "Add sync_conflicts table and sync_state.modified_at",
+        up: migration_v10,
+    },
+    Migration {
+        version: 11,
+        description: "Add backtrace, os, app_version and recovered to crash_logs",
+        up: migration_v11,
+    },
+    Migration { version: 12, description: "Add uploaded_at to crash_logs", up: migration_v12 },
+    Migration { version: 13, description: "Add maintenance_runs table", up: migration_v13 },
+    Migration { version: 14, description: "Add favicons table", up: migration_v14 },
+    Migration { version: 15, description: "Add referrer to downloads", up: migration_v15 },
+    Migration { version: 16, description: "Add page_count to reading_list", up: migration_v16 },
+];
+
+/// Current schema version: that of the last migration.
+pub const CURRENT_SCHEMA_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
 
 /// Returns the current schema version from the database (0 if table doesn't exist).
 pub fn get_schema_version(conn: &Connection) -> i32 {
@@ -20,17 +68,25 @@ pub fn get_schema_version(conn: &Connection) -> i32 {
 
 /// Runs all pending schema migrations against the provided connection.
 ///
-/// Migrations are versioned — each runs exactly once and is recorded in
-/// the `schema_version` table. Safe to call on every startup.
+/// Safe to call on every startup.
 ///
 /// # Errors
-/// Returns `rusqlite::Error` if any SQL statement fails.
+/// Returns `rusqlite::Error` if any SQL statement fails, or if the database
+/// was migrated past `CURRENT_SCHEMA_VERSION` by a newer build.
 pub fn run_all(conn: &Connection) -> Result<(), rusqlite::Error> {
-    // Enable WAL and foreign keys (always, not versioned)
+    apply(conn, MIGRATIONS)
+}
+
+/// Runs the steps of `migrations` newer than the database's version. A
+/// failing step is rolled back with its version record, leaving the
+/// database at the previous version.
+///
+/// # Errors
+/// Returns `rusqlite::Error` if a step fails or the database is newer than
+/// the last step.
+pub fn apply(conn: &Connection, migrations: &[Migration]) -> Result<(), rusqlite::Error> {
     conn.execute_batch(
-        "PRAGMA journal_mode = WAL;
-         PRAGMA foreign_keys = ON;
-         CREATE TABLE IF NOT EXISTS schema_version (
+        "CREATE TABLE IF NOT EXISTS schema_version (
              version INTEGER PRIMARY KEY,
              applied_at INTEGER NOT NULL,
              description TEXT NOT NULL
@@ -38,17 +94,23 @@ pub fn run_all(conn: &Connection) -> Result<(), rusqlite::Error> {
     )?;
 
     let current = get_schema_version(conn);
-
-    if current < 1 {
-        migration_v1(conn)?;
-        record_version(conn, 1, "Initial schema: all core tables")?;
+    let latest = migrations.last().map_or(0, |m| m.version);
+    if current > latest {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISMATCH),
+            Some(format!(
+                "database schema v{} is newer than this build supports (v{}); update GitBrowser",
+                current, latest
+            )),
+        ));
     }
 
-    if current < 2 {
-        migration_v2(conn)?;
-        record_version(conn, 2, "Add content_scripts to extensions, uses_master to secure_store")?;
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx)?;
+        record_version(&tx, migration.version, migration.description)?;
+        tx.commit()?;
     }
-
     Ok(())
 }
 
@@ -218,3 +280,191 @@ fn migration_v2(conn: &Connection) -> Result<(), rusqlite::Error> {
     }
     Ok(())
 }
+
+/// V3: Add the offline reading list (read-later articles extracted by reader mode).
+fn migration_v3(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS reading_list (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL UNIQUE,
+            title TEXT NOT NULL,
+            content_html TEXT NOT NULL,
+            text_content TEXT NOT NULL,
+            author TEXT,
+            site_name TEXT,
+            estimated_read_time_minutes INTEGER NOT NULL DEFAULT 0,
+            images_inlined INTEGER NOT NULL DEFAULT 0,
+            saved_at INTEGER NOT NULL,
+            read_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_reading_list_saved_at ON reading_list(saved_at);"
+    )
+}
+
+/// V4: Add reader annotations (highlights + notes keyed by URL and text anchor).
+fn migration_v4(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS annotations (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            anchor_text TEXT NOT NULL,
+            prefix TEXT NOT NULL DEFAULT '',
+            suffix TEXT NOT NULL DEFAULT '',
+            note TEXT,
+            color TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_annotations_url ON annotations(url);"
+    )
+}
+
+/// V5: Add per-site setting overrides (JSON values keyed by host and setting key).
+fn migration_v5(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS site_settings (
+            site TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (site, key)
+        );
+        CREATE INDEX IF NOT EXISTS idx_site_settings_key ON site_settings(key);"
+    )
+}
+
+/// V6: Record each extension's background script and give extensions a
+/// key-value store (JSON values keyed by extension ID and key).
+fn migration_v6(conn: &Connection) -> Result<(), rusqlite::Error> {
+    if conn.prepare("SELECT background FROM extensions LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE extensions ADD COLUMN background TEXT;")?;
+    }
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS extension_storage (
+            extension_id TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (extension_id, key)
+        );"
+    )
+}
+
+/// V7: Track per-collection sync version vectors and content digests, and
+/// give this installation a stable device ID for them.
+fn migration_v7(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sync_state (
+            collection TEXT PRIMARY KEY,
+            versions TEXT NOT NULL,
+            digest TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS sync_device (
+            id TEXT PRIMARY KEY,
+            created_at INTEGER NOT NULL
+        );"
+    )
+}
+
+/// V8: Keep fetched GitHub notifications, remembering which ones already
+/// raised a desktop notification.
+fn migration_v8(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS github_notifications (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            repo_full_name TEXT NOT NULL,
+            notification_type TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            unread INTEGER NOT NULL DEFAULT 1,
+            updated_at TEXT NOT NULL,
+            url TEXT NOT NULL,
+            alerted INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_github_notifications_updated ON github_notifications(updated_at);"
+    )
+}
+
+/// V9: Cache the user's own and starred repositories for `gh` omnibox
+/// suggestions.
+fn migration_v9(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS github_repos (
+            full_name TEXT PRIMARY KEY,
+            description TEXT,
+            html_url TEXT NOT NULL,
+            stargazers_count INTEGER NOT NULL DEFAULT 0,
+            language TEXT,
+            updated_at TEXT NOT NULL,
+            owned INTEGER NOT NULL DEFAULT 0,
+            starred INTEGER NOT NULL DEFAULT 0,
+            cached_at INTEGER NOT NULL
+        );"
+    )
+}
+
+/// V10: Keep sync conflicts for the user to resolve, and the time of each
+/// collection's last local change for last-writer-wins.
+fn migration_v10(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sync_conflicts (
+            collection TEXT PRIMARY KEY,
+            local_data TEXT NOT NULL,
+            remote_data TEXT NOT NULL,
+            remote_versions TEXT NOT NULL,
+            detected_at INTEGER NOT NULL
+        );
+        ALTER TABLE sync_state ADD COLUMN modified_at INTEGER NOT NULL DEFAULT 0;"
+    )
+}
+
+/// V11: Keep what automatic crash capture records with each crash, and mark
+/// crashes recovered instead of deleting them.
+fn migration_v11(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE crash_logs ADD COLUMN backtrace TEXT;
+        ALTER TABLE crash_logs ADD COLUMN os TEXT;
+        ALTER TABLE crash_logs ADD COLUMN app_version TEXT;
+        ALTER TABLE crash_logs ADD COLUMN recovered INTEGER NOT NULL DEFAULT 0;"
+    )
+}
+
+/// V12: Remember which crash logs were uploaded so they are sent once.
+fn migration_v12(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch("ALTER TABLE crash_logs ADD COLUMN uploaded_at INTEGER;")
+}
+
+/// V13: Record each maintenance run (integrity errors, pages reclaimed).
+fn migration_v13(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS maintenance_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            ran_at INTEGER NOT NULL,
+            integrity_errors TEXT NOT NULL DEFAULT '[]',
+            reclaimed INTEGER NOT NULL DEFAULT 0
+        );",
+    )
+}
+
+/// V14: Cache each site's icon for tabs, bookmarks and history.
+fn migration_v14(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS favicons (
+            site TEXT PRIMARY KEY,
+            icon_url TEXT NOT NULL,
+            data_url TEXT NOT NULL,
+            fetched_at INTEGER NOT NULL
+        );",
+    )
+}
+
+/// V15: Remember the page a download was started from.
+fn migration_v15(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch("ALTER TABLE downloads ADD COLUMN referrer TEXT;")
+}
+
+/// V16: Record how many source pages a saved article was stitched from.
+fn migration_v16(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch("ALTER TABLE reading_list ADD COLUMN page_count INTEGER NOT NULL DEFAULT 1;")
+}
@@ -17,7 +17,12 @@
 //! let conn = db.connection();
 //! ```
 
+pub mod backup;
 pub mod connection;
+pub mod encryption;
+pub mod maintenance;
 pub mod migrations;
+pub mod worker;
 
 pub use connection::Database;
+pub use worker::DbWorker;
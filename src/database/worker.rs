@@ -0,0 +1,108 @@
+//! Database work off the caller's thread.
+//!
+//! A [`DbWorker`] owns a second connection to the database on a thread of
+//! its own and runs queued jobs in order. Under WAL journaling its reads do
+//! not wait for the main connection's writes, so long queries (history
+//! search, listing pages of history) run without holding `Mutex<App>` or
+//! blocking the UI event loop. Jobs are answered through a [`DbTask`], which
+//! can be `.await`ed or waited on from synchronous code.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+use super::connection::configure;
+use super::encryption::{self, DatabaseKey};
+use crate::types::errors::DbWorkerError;
+
+type Job = Box<dyn FnOnce(&Connection) + Send>;
+
+/// How often `DbTask::wait_timeout` checks for the answer.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Handle to the database thread; clones share the thread.
+#[derive(Clone)]
+pub struct DbWorker {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl DbWorker {
+    /// Opens a connection to the database at `path` (already migrated by
+    /// [`super::Database`]) and starts the thread running jobs on it. The
+    /// thread ends when the last handle is dropped.
+    ///
+    /// # Errors
+    /// Returns `rusqlite::Error` if the connection cannot be opened or keyed.
+    pub fn spawn(path: &str, key: Option<&DatabaseKey>) -> Result<Self, rusqlite::Error> {
+        let conn = Connection::open(path)?;
+        if let Some(key) = key {
+            encryption::apply_key(&conn, key)?;
+        }
+        configure(&conn)?;
+
+        let (jobs, queue) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("gitbrowser-db".to_string())
+            .spawn(move || {
+                for job in queue {
+                    // A panicking job drops its answer; the caller sees `Stopped`
+                    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| job(&conn)));
+                }
+            })
+            .map_err(|e| {
+                rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN), Some(e.to_string()))
+            })?;
+        Ok(Self { jobs })
+    }
+
+    /// Queues `job` behind the ones already sent.
+    pub fn run<T, F>(&self, job: F) -> DbTask<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> T + Send + 'static,
+    {
+        let (answer, result) = oneshot::channel();
+        let _ = self.jobs.send(Box::new(move |conn| {
+            let _ = answer.send(job(conn));
+        }));
+        DbTask(result)
+    }
+}
+
+/// Result of a job queued on a [`DbWorker`].
+pub struct DbTask<T>(oneshot::Receiver<T>);
+
+impl<T> DbTask<T> {
+    /// Blocks until the job has run. Must not be called from async code;
+    /// `.await` the task there instead.
+    pub fn wait(self) -> Result<T, DbWorkerError> {
+        self.0.blocking_recv().map_err(|_| DbWorkerError::Stopped)
+    }
+
+    /// Like `wait`, giving up with `DbWorkerError::TimedOut` after
+    /// `timeout`. The job still runs; only its answer is dropped.
+    pub fn wait_timeout(mut self, timeout: Duration) -> Result<T, DbWorkerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.0.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Closed) => return Err(DbWorkerError::Stopped),
+                Err(TryRecvError::Empty) if Instant::now() >= deadline => return Err(DbWorkerError::TimedOut),
+                Err(TryRecvError::Empty) => std::thread::sleep(POLL_INTERVAL),
+            }
+        }
+    }
+}
+
+impl<T> Future for DbTask<T> {
+    type Output = Result<T, DbWorkerError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx).map_err(|_| DbWorkerError::Stopped)
+    }
+}
@@ -3,11 +3,19 @@
 //! This library crate exposes all modules for use by the binary and integration tests.
 
 pub mod app;
+pub mod cli;
 pub mod database;
 pub mod managers;
 pub mod platform;
 pub mod services;
+pub mod rpc_auth;
 pub mod rpc_handler;
+pub mod rpc_limits;
+pub mod rpc_methods;
+pub mod rpc_protocol;
+pub mod rpc_socket;
+pub mod rpc_stream;
+pub mod single_instance;
 pub mod types;
 
 #[cfg(feature = "gui")]
@@ -2,14 +2,58 @@
 //!
 //! Entry point: initializes a GTK4 application and displays the main browser window.
 //! When built without the `gui` feature, runs an interactive console demo.
+//! `--rpc` and `--headless` (see `gitbrowser::cli`) run the `gitbrowser-rpc`
+//! backend next to this binary instead.
+
+use gitbrowser::cli::{CliArgs, USAGE};
+
+/// The JSON-RPC backend, installed next to this binary.
+const RPC_BINARY: &str = "gitbrowser-rpc";
+
+/// Parses the command line, answering `--help` and `--version` and handing
+/// the backend flags over to `RPC_BINARY` with the same arguments.
+fn parse_args() -> CliArgs {
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("gitbrowser: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        std::process::exit(0);
+    }
+    if args.version {
+        println!("gitbrowser {}", env!("CARGO_PKG_VERSION"));
+        std::process::exit(0);
+    }
+    if args.wants_backend() {
+        let name = format!("{}{}", RPC_BINARY, std::env::consts::EXE_SUFFIX);
+        let backend = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(&name)))
+            .unwrap_or_else(|| name.into());
+        match std::process::Command::new(&backend).args(std::env::args_os().skip(1)).status() {
+            Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+            Err(e) => {
+                eprintln!("gitbrowser: cannot start {}: {}", backend.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    args
+}
 
 #[cfg(feature = "gui")]
 fn main() {
-    gitbrowser::ui::webview_app::run();
+    gitbrowser::ui::webview_app::run(parse_args());
 }
 
 #[cfg(not(feature = "gui"))]
 fn main() {
+    // The demo opens its own in-memory app; only the informational and backend flags apply
+    parse_args();
     println!();
     println!("╔══════════════════════════════════════════════════════════════╗");
     println!("║              GitBrowser v{} — Demo Mode              ║", env!("CARGO_PKG_VERSION"));
@@ -131,7 +175,7 @@ fn demo_localization() {
     use gitbrowser::services::localization_engine::{LocalizationEngine, LocalizationEngineTrait};
     section("Localization Engine (RU/EN)");
 
-    let mut engine = LocalizationEngine::new("locales");
+    let mut engine = LocalizationEngine::bundled();
     engine.initialize().unwrap();
 
     // Russian
@@ -151,6 +195,9 @@ fn demo_localization() {
     let mut params = HashMap::new();
     params.insert("count".to_string(), "42".to_string());
     println!("  [EN] {}", engine.t("ai.tokens_used", Some(&params)));
+    println!("  [EN] {} · {} · {}", engine.format_bytes(1_572_864), engine.format_relative_time(0, 7_200), engine.format_cost(0.0123));
+    engine.set_locale("ru").unwrap();
+    println!("  [RU] {} · {} · {}", engine.format_bytes(1_572_864), engine.format_relative_time(0, 7_200), engine.format_cost(0.0123));
     println!("  Available locales: {:?}", engine.get_available_locales());
     println!("  ✓ LocalizationEngine OK");
     println!();
@@ -518,6 +565,11 @@ fn demo_crash_recovery() {
         error_type: "WebProcessCrashed".to_string(),
         error_message: Some("Segmentation fault in renderer".to_string()),
         timestamp: 0,
+        backtrace: None,
+        os: None,
+        app_version: None,
+        recovered: false,
+        uploaded_at: None,
     }).unwrap();
     println!("  Logged crash for crashy-site.com");
     println!("  Has unrecovered crash: {}", recovery.has_unrecovered_crash());
@@ -690,7 +742,8 @@ fn demo_app_core() {
     app.startup();
     println!("  Startup sequence: settings → locale → theme → privacy → crash check");
 
-    app.shutdown();
-    println!("  Shutdown sequence: stop periodic save");
+    let report = app.shutdown();
+    let steps: Vec<&str> = report.steps.iter().map(|s| s.step.as_str()).collect();
+    println!("  Shutdown sequence: {} (clean={})", steps.join(" → "), report.is_clean());
     println!("  ✓ App Core OK");
 }
@@ -0,0 +1,273 @@
+//! Annotation Manager for GitBrowser.
+//!
+//! Implements `AnnotationManagerTrait` — highlights and notes on reader-mode
+//! articles, backed by SQLite via `rusqlite`. Annotations are keyed by URL and
+//! anchored by text (with prefix/suffix context), so they survive re-extraction
+//! of the article and can be re-applied with `apply_annotations`.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+use uuid::Uuid;
+
+use crate::types::annotation::Annotation;
+use crate::types::errors::AnnotationError;
+
+/// Default highlight color (soft yellow).
+pub const DEFAULT_HIGHLIGHT_COLOR: &str = "#fff3a0";
+
+/// Maximum length of a highlighted passage, in bytes.
+const MAX_ANCHOR_LEN: usize = 10_000;
+
+/// Maximum length of the prefix/suffix context, in bytes.
+const MAX_CONTEXT_LEN: usize = 64;
+
+/// Trait defining annotation operations.
+pub trait AnnotationManagerTrait {
+    /// Adds a highlight for `anchor_text` on the page at `url`. Returns the annotation ID.
+    fn add_annotation(&mut self, url: &str, anchor_text: &str, prefix: &str, suffix: &str, note: Option<&str>, color: Option<&str>) -> Result<String, AnnotationError>;
+    fn update_annotation(&mut self, id: &str, note: Option<&str>, color: Option<&str>) -> Result<(), AnnotationError>;
+    fn remove_annotation(&mut self, id: &str) -> Result<(), AnnotationError>;
+    /// Lists annotations for a URL in creation order.
+    fn list_annotations(&self, url: &str) -> Result<Vec<Annotation>, AnnotationError>;
+}
+
+/// Annotation manager backed by a SQLite connection.
+pub struct AnnotationManager<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> AnnotationManager<'a> {
+    /// Creates a new `AnnotationManager` using the provided database connection.
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Returns the current UNIX timestamp in seconds.
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    }
+
+    /// Annotations are keyed by URL without its `#fragment`.
+    fn url_key(url: &str) -> &str {
+        url.split('#').next().unwrap_or(url)
+    }
+
+    fn validate_color(color: &str) -> Result<(), AnnotationError> {
+        let hex = color.strip_prefix('#').unwrap_or("");
+        if (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            Ok(())
+        } else {
+            Err(AnnotationError::InvalidColor(color.to_string()))
+        }
+    }
+
+    /// Keeps at most `max` bytes of `s`, from the end if `from_end` is set,
+    /// without splitting a UTF-8 character.
+    fn clamp_context(s: &str, max: usize, from_end: bool) -> String {
+        if s.len() <= max {
+            return s.to_string();
+        }
+        if from_end {
+            let mut start = s.len() - max;
+            while !s.is_char_boundary(start) {
+                start += 1;
+            }
+            s[start..].to_string()
+        } else {
+            let mut end = max;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            s[..end].to_string()
+        }
+    }
+
+    /// Escapes text the way it appears inside HTML text nodes.
+    fn escape_text(input: &str) -> String {
+        input.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+    }
+
+    fn escape_attr(input: &str) -> String {
+        Self::escape_text(input).replace('"', "&quot;").replace('\'', "&#x27;")
+    }
+
+    /// Byte offsets of every occurrence of `needle` that lies entirely within
+    /// a single text node (i.e. not inside a tag).
+    fn text_positions(html: &str, needle: &str) -> Vec<usize> {
+        let mut positions = Vec::new();
+        let mut segment_start = 0;
+        let mut in_tag = false;
+        for (i, ch) in html.char_indices().chain(std::iter::once((html.len(), '<'))) {
+            match ch {
+                '<' if !in_tag => {
+                    let segment = &html[segment_start..i];
+                    let mut from = 0;
+                    while let Some(found) = segment[from..].find(needle) {
+                        positions.push(segment_start + from + found);
+                        from += found + needle.len();
+                    }
+                    in_tag = true;
+                }
+                '>' if in_tag => {
+                    in_tag = false;
+                    segment_start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        positions
+    }
+
+    /// Wraps each annotation's anchor text in `html` with a
+    /// `<mark class="gb-annotation">` element. When a passage occurs more than
+    /// once, the occurrence whose surrounding text matches the stored
+    /// prefix/suffix wins. Anchors that span element boundaries are skipped.
+    pub fn apply_annotations(html: &str, annotations: &[Annotation]) -> String {
+        let mut out = html.to_string();
+        for a in annotations {
+            if a.anchor_text.trim().is_empty() {
+                continue;
+            }
+            // Sloppy markup may carry a bare `&`; fall back to the raw text
+            let (needle, prefix, suffix, candidates) = {
+                let escaped = Self::escape_text(&a.anchor_text);
+                let found = Self::text_positions(&out, &escaped);
+                if found.is_empty() && escaped != a.anchor_text {
+                    let raw = Self::text_positions(&out, &a.anchor_text);
+                    (a.anchor_text.clone(), a.prefix.clone(), a.suffix.clone(), raw)
+                } else {
+                    (escaped, Self::escape_text(&a.prefix), Self::escape_text(&a.suffix), found)
+                }
+            };
+
+            let score = |pos: usize| {
+                let before = !prefix.is_empty() && out[..pos].ends_with(&prefix);
+                let after = !suffix.is_empty() && out[pos + needle.len()..].starts_with(&suffix);
+                before as u8 + after as u8
+            };
+            let best = candidates.iter().copied().max_by_key(|&p| (score(p), std::cmp::Reverse(p)));
+
+            if let Some(pos) = best {
+                let title = a
+                    .note
+                    .as_deref()
+                    .map(|n| format!(" title=\"{}\"", Self::escape_attr(n)))
+                    .unwrap_or_default();
+                let open = format!(
+                    "<mark class=\"gb-annotation\" data-annotation-id=\"{}\" style=\"background:{}\"{}>",
+                    Self::escape_attr(&a.id),
+                    Self::escape_attr(&a.color),
+                    title
+                );
+                out.insert_str(pos + needle.len(), "</mark>");
+                out.insert_str(pos, &open);
+            }
+        }
+        out
+    }
+
+    /// Reads a single `Annotation` row into a struct.
+    fn row_to_annotation(row: &rusqlite::Row) -> rusqlite::Result<Annotation> {
+        Ok(Annotation {
+            id: row.get(0)?,
+            url: row.get(1)?,
+            anchor_text: row.get(2)?,
+            prefix: row.get(3)?,
+            suffix: row.get(4)?,
+            note: row.get(5)?,
+            color: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+}
+
+impl<'a> AnnotationManagerTrait for AnnotationManager<'a> {
+    fn add_annotation(&mut self, url: &str, anchor_text: &str, prefix: &str, suffix: &str, note: Option<&str>, color: Option<&str>) -> Result<String, AnnotationError> {
+        if anchor_text.trim().is_empty() {
+            return Err(AnnotationError::InvalidAnchor("empty anchor text".to_string()));
+        }
+        if anchor_text.len() > MAX_ANCHOR_LEN {
+            return Err(AnnotationError::InvalidAnchor(format!(
+                "anchor text exceeds {} bytes",
+                MAX_ANCHOR_LEN
+            )));
+        }
+        let color = color.unwrap_or(DEFAULT_HIGHLIGHT_COLOR);
+        Self::validate_color(color)?;
+
+        let id = Uuid::new_v4().to_string();
+        let now = Self::now();
+        self.conn
+            .execute(
+                "INSERT INTO annotations (id, url, anchor_text, prefix, suffix, note, color, created_at, updated_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+                params![
+                    id,
+                    Self::url_key(url),
+                    anchor_text,
+                    Self::clamp_context(prefix, MAX_CONTEXT_LEN, true),
+                    Self::clamp_context(suffix, MAX_CONTEXT_LEN, false),
+                    note,
+                    color,
+                    now
+                ],
+            )
+            .map_err(|e| AnnotationError::DatabaseError(e.to_string()))?;
+        Ok(id)
+    }
+
+    fn update_annotation(&mut self, id: &str, note: Option<&str>, color: Option<&str>) -> Result<(), AnnotationError> {
+        if let Some(c) = color {
+            Self::validate_color(c)?;
+        }
+        let affected = self
+            .conn
+            .execute(
+                "UPDATE annotations SET note = COALESCE(?1, note), color = COALESCE(?2, color), updated_at = ?3 WHERE id = ?4",
+                params![note, color, Self::now(), id],
+            )
+            .map_err(|e| AnnotationError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(AnnotationError::NotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn remove_annotation(&mut self, id: &str) -> Result<(), AnnotationError> {
+        let affected = self
+            .conn
+            .execute("DELETE FROM annotations WHERE id = ?1", params![id])
+            .map_err(|e| AnnotationError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(AnnotationError::NotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn list_annotations(&self, url: &str) -> Result<Vec<Annotation>, AnnotationError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, url, anchor_text, prefix, suffix, note, color, created_at, updated_at \
+                 FROM annotations WHERE url = ?1 ORDER BY created_at ASC, rowid ASC",
+            )
+            .map_err(|e| AnnotationError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![Self::url_key(url)], Self::row_to_annotation)
+            .map_err(|e| AnnotationError::DatabaseError(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| AnnotationError::DatabaseError(e.to_string()))?);
+        }
+        Ok(results)
+    }
+}
@@ -7,7 +7,7 @@ use rusqlite::{params, Connection};
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
-use crate::types::bookmark::Bookmark;
+use crate::types::bookmark::{Bookmark, BookmarkFolder};
 use crate::types::errors::BookmarkError;
 
 /// Trait defining bookmark management operations.
@@ -22,6 +22,10 @@ pub trait BookmarkManagerTrait {
     fn list_bookmarks_paginated(&self, folder_id: Option<&str>, limit: i64, offset: i64) -> Result<(Vec<Bookmark>, i64), BookmarkError>;
     fn create_folder(&mut self, name: &str, parent_id: Option<&str>) -> Result<String, BookmarkError>;
     fn delete_folder(&mut self, id: &str) -> Result<(), BookmarkError>;
+    /// Every bookmark in every folder, ordered by folder and position.
+    fn list_all_bookmarks(&self) -> Result<Vec<Bookmark>, BookmarkError>;
+    /// Every folder, ordered by parent and position.
+    fn list_folders(&self) -> Result<Vec<BookmarkFolder>, BookmarkError>;
 }
 
 /// Bookmark manager backed by a SQLite connection.
@@ -353,4 +357,48 @@ impl<'a> BookmarkManagerTrait for BookmarkManager<'a> {
         }
         Ok((results, total))
     }
+
+    fn list_all_bookmarks(&self) -> Result<Vec<Bookmark>, BookmarkError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, url, title, folder_id, position, created_at, updated_at \
+                 FROM bookmarks ORDER BY folder_id, position",
+            )
+            .map_err(|e| BookmarkError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], Self::row_to_bookmark)
+            .map_err(|e| BookmarkError::DatabaseError(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| BookmarkError::DatabaseError(e.to_string()))?);
+        }
+        Ok(results)
+    }
+
+    fn list_folders(&self) -> Result<Vec<BookmarkFolder>, BookmarkError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, parent_id, position FROM bookmark_folders ORDER BY parent_id, position")
+            .map_err(|e| BookmarkError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(BookmarkFolder {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    parent_id: row.get(2)?,
+                    position: row.get(3)?,
+                })
+            })
+            .map_err(|e| BookmarkError::DatabaseError(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| BookmarkError::DatabaseError(e.to_string()))?);
+        }
+        Ok(results)
+    }
 }
@@ -3,6 +3,7 @@
 //! Manages file downloads with pause/resume/cancel support,
 //! backed by SQLite for persistence.
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -10,16 +11,25 @@ use rusqlite::params;
 use uuid::Uuid;
 
 use crate::database::connection::Database;
-use crate::types::download::{DownloadItem, DownloadStatus};
+use crate::services::event_bus::{AppEvent, EventBus};
+use crate::services::event_hub::{EventHub, EventTopic};
+use crate::types::download::{DownloadItem, DownloadRequest, DownloadStatus};
 use crate::types::errors::DownloadError;
 
 /// Trait defining download management operations.
 pub trait DownloadManagerTrait {
     fn start_download(&mut self, url: &str, filepath: &str) -> Result<String, DownloadError>;
+    /// Like `start_download`, keeping what the webview knew of the download.
+    fn start_request(&mut self, request: &DownloadRequest, filepath: &str) -> Result<String, DownloadError>;
     fn pause_download(&mut self, id: &str) -> Result<(), DownloadError>;
     fn resume_download(&mut self, id: &str) -> Result<(), DownloadError>;
     fn cancel_download(&mut self, id: &str) -> Result<(), DownloadError>;
     fn retry_download(&mut self, id: &str) -> Result<(), DownloadError>;
+    /// Records bytes received so far (and the total once known); the
+    /// download completes when they add up.
+    fn update_progress(&mut self, id: &str, downloaded: u64, size: Option<u64>) -> Result<(), DownloadError>;
+    /// Marks a download failed for `reason`, unless it already finished.
+    fn fail_download(&mut self, id: &str, reason: &str) -> Result<(), DownloadError>;
     fn list_downloads(&self) -> Vec<&DownloadItem>;
     fn get_download(&self, id: &str) -> Option<&DownloadItem>;
 }
@@ -49,6 +59,8 @@ fn str_to_status(s: &str) -> DownloadStatus {
 pub struct DownloadManager {
     db: Arc<Database>,
     downloads: Vec<DownloadItem>,
+    events: EventHub,
+    bus: EventBus,
 }
 
 impl DownloadManager {
@@ -56,15 +68,29 @@ impl DownloadManager {
         let mut mgr = Self {
             db,
             downloads: Vec::new(),
+            events: EventHub::new(),
+            bus: EventBus::new(),
         };
         mgr.load_from_db();
         mgr
     }
 
+    /// Publishes every change to a download to `events`.
+    pub fn with_events(mut self, events: EventHub) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Emits the progress of every download on `bus`.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
     fn load_from_db(&mut self) {
         let conn = self.db.connection();
         let mut stmt = conn.prepare(
-            "SELECT id, url, filename, filepath, size, downloaded, status, mime_type, started_at, completed_at FROM downloads ORDER BY started_at DESC"
+            "SELECT id, url, filename, filepath, size, downloaded, status, mime_type, started_at, completed_at, referrer FROM downloads ORDER BY started_at DESC"
         ).unwrap();
 
         self.downloads = stmt.query_map([], |row| {
@@ -80,10 +106,64 @@ impl DownloadManager {
                 mime_type: row.get(7)?,
                 started_at: row.get(8)?,
                 completed_at: row.get(9)?,
+                referrer: row.get(10)?,
             })
         }).unwrap().filter_map(|r| r.ok()).collect();
     }
 
+    /// `dir/name`, or `dir/stem (N).ext` for the first N not already taken.
+    pub fn free_path(dir: &Path, name: &str) -> PathBuf {
+        let candidate = dir.join(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        let (stem, ext) = match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+            _ => (name, String::new()),
+        };
+        (1..)
+            .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+            .find(|p| !p.exists())
+            .unwrap_or(candidate)
+    }
+
+    /// A file name from a page title: characters no file system takes
+    /// replaced, cut to a sane length, `fallback` when nothing is left.
+    pub fn file_name_from_title(title: &str, fallback: &str, ext: &str) -> String {
+        let stem = Self::clean_file_name(title);
+        format!("{}.{}", if stem.is_empty() { fallback } else { &stem }, ext)
+    }
+
+    /// A file name for what a page downloads: the suggested name, else the
+    /// last segment of its URL's path, else `fallback`. Cleaned like
+    /// `file_name_from_title`, keeping the extension.
+    pub fn file_name_for(request: &DownloadRequest, fallback: &str) -> String {
+        let from_url = request.url.split(['?', '#']).next().unwrap_or("").rsplit('/').next().unwrap_or("");
+        // Suggestions can carry directories; only the last component names the file
+        let name = [request.suggested_name.as_str(), from_url]
+            .into_iter()
+            .map(|name| name.rsplit(['/', '\\']).next().unwrap_or(""))
+            .find(|name| !Self::clean_file_name(name).is_empty())
+            .unwrap_or(fallback);
+        match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() && !Self::clean_file_name(ext).is_empty() => {
+                Self::file_name_from_title(stem, fallback, &Self::clean_file_name(ext))
+            }
+            _ => Self::clean_file_name(name),
+        }
+    }
+
+    /// `name` with characters no file system takes replaced, cut to a sane
+    /// length and trimmed of spaces and dots.
+    fn clean_file_name(name: &str) -> String {
+        let name: String = name
+            .chars()
+            .map(|c| if c.is_control() || "\\/:*?\"<>|".contains(c) { '_' } else { c })
+            .take(100)
+            .collect();
+        name.trim().trim_matches('.').to_string()
+    }
+
     fn now_ts() -> i64 {
         SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
     }
@@ -95,19 +175,26 @@ impl DownloadManager {
 
     fn persist(&self, item: &DownloadItem) -> Result<(), DownloadError> {
         self.db.connection().execute(
-            "INSERT OR REPLACE INTO downloads (id, url, filename, filepath, size, downloaded, status, mime_type, started_at, completed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            "INSERT OR REPLACE INTO downloads (id, url, filename, filepath, size, downloaded, status, mime_type, started_at, completed_at, referrer) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 item.id, item.url, item.filename, item.filepath,
                 item.size, item.downloaded as i64, status_to_str(&item.status),
-                item.mime_type, item.started_at, item.completed_at
+                item.mime_type, item.started_at, item.completed_at, item.referrer
             ],
         ).map_err(|e| DownloadError::FileSystemError(e.to_string()))?;
+        self.events.publish(EventTopic::Downloads, serde_json::json!({"download": item}));
+        self.bus.emit(AppEvent::DownloadProgress { id: item.id.clone(), downloaded: item.downloaded, size: item.size });
         Ok(())
     }
 }
 
 impl DownloadManagerTrait for DownloadManager {
     fn start_download(&mut self, url: &str, filepath: &str) -> Result<String, DownloadError> {
+        let request = DownloadRequest { url: url.to_string(), ..Default::default() };
+        self.start_request(&request, filepath)
+    }
+
+    fn start_request(&mut self, request: &DownloadRequest, filepath: &str) -> Result<String, DownloadError> {
         let id = Uuid::new_v4().to_string();
         let filename = filepath.rsplit('/').next()
             .or_else(|| filepath.rsplit('\\').next())
@@ -116,15 +203,16 @@ impl DownloadManagerTrait for DownloadManager {
 
         let item = DownloadItem {
             id: id.clone(),
-            url: url.to_string(),
+            url: request.url.clone(),
             filename,
             filepath: filepath.to_string(),
-            size: None,
+            size: request.size,
             downloaded: 0,
             status: DownloadStatus::Pending,
-            mime_type: None,
+            mime_type: request.mime_type.clone(),
             started_at: Self::now_ts(),
             completed_at: None,
+            referrer: request.referrer.clone(),
         };
 
         self.persist(&item)?;
@@ -178,6 +266,37 @@ impl DownloadManagerTrait for DownloadManager {
         }
     }
 
+    fn update_progress(&mut self, id: &str, downloaded: u64, size: Option<u64>) -> Result<(), DownloadError> {
+        let idx = self.find_index(id)?;
+        let item = &mut self.downloads[idx];
+        match item.status {
+            DownloadStatus::Completed => return Err(DownloadError::AlreadyCompleted(id.to_string())),
+            // Late chunks after a pause or cancel do not revive the download
+            DownloadStatus::Paused | DownloadStatus::Failed(_) => return Ok(()),
+            DownloadStatus::Pending | DownloadStatus::InProgress => {}
+        }
+        item.downloaded = downloaded;
+        if size.is_some() {
+            item.size = size;
+        }
+        if item.size.is_some_and(|total| downloaded >= total) {
+            item.status = DownloadStatus::Completed;
+            item.completed_at = Some(Self::now_ts());
+        } else {
+            item.status = DownloadStatus::InProgress;
+        }
+        self.persist(&self.downloads[idx].clone())
+    }
+
+    fn fail_download(&mut self, id: &str, reason: &str) -> Result<(), DownloadError> {
+        let idx = self.find_index(id)?;
+        if matches!(self.downloads[idx].status, DownloadStatus::Completed | DownloadStatus::Failed(_)) {
+            return Ok(());
+        }
+        self.downloads[idx].status = DownloadStatus::Failed(reason.to_string());
+        self.persist(&self.downloads[idx].clone())
+    }
+
     fn list_downloads(&self) -> Vec<&DownloadItem> {
         self.downloads.iter().collect()
     }
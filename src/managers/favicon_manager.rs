@@ -0,0 +1,231 @@
+//! Favicon Manager for GitBrowser.
+//!
+//! Implements `FaviconManagerTrait` — a cache of each site's icon as a
+//! `data:` URL in SQLite, so tabs, bookmarks and history show icons without
+//! fetching them again. Also picks which of a page's icon links to fetch.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use rusqlite::{params, Connection};
+
+use crate::managers::site_settings_manager::SiteSettingsManager;
+use crate::types::errors::FaviconError;
+use crate::types::favicon::{Favicon, IconLink};
+
+/// Icons larger than this are not kept (bytes of the `data:` URL).
+pub const MAX_FAVICON_DATA_URL_LEN: usize = 128 * 1024;
+
+/// A cached icon older than this (seconds) is fetched again.
+pub const FAVICON_MAX_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Icon size, in pixels, best suited to the tab strip on a high-DPI screen.
+const PREFERRED_ICON_SIZE: u32 = 32;
+
+/// Trait defining favicon cache operations.
+pub trait FaviconManagerTrait {
+    fn get_favicon(&self, site: &str) -> Result<Option<Favicon>, FaviconError>;
+    /// Creates or replaces the icon of `site`. `data_url` must be an image
+    /// `data:` URL no longer than `MAX_FAVICON_DATA_URL_LEN`.
+    fn save_favicon(&mut self, site: &str, icon_url: &str, data_url: &str) -> Result<(), FaviconError>;
+    fn remove_favicon(&mut self, site: &str) -> Result<bool, FaviconError>;
+    /// The cached icons of the sites of `urls`, by site.
+    fn favicons_for_urls(&self, urls: &[&str]) -> Result<HashMap<String, String>, FaviconError>;
+    fn clear(&mut self) -> Result<(), FaviconError>;
+}
+
+/// Favicon manager backed by a SQLite connection.
+pub struct FaviconManager<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> FaviconManager<'a> {
+    /// Creates a new `FaviconManager` using the provided database connection.
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Returns the current UNIX timestamp in seconds.
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    }
+
+    /// Whether an icon fetched at `fetched_at` is due to be fetched again.
+    pub fn is_stale(fetched_at: i64) -> bool {
+        Self::now() - fetched_at >= FAVICON_MAX_AGE_SECS
+    }
+
+    /// The icon URLs worth trying for the page at `page_url`, best first:
+    /// its `icon` links, nearest `PREFERRED_ICON_SIZE` (scalable ones
+    /// next, then those of unknown size), its `apple-touch-icon` links, and
+    /// last `/favicon.ico` at the site's root. `mask-icon` links are
+    /// single-color templates and are left out.
+    pub fn icon_candidates(links: &[IconLink], page_url: &str) -> Vec<String> {
+        let mut ranked: Vec<((u8, u32), &str)> = links
+            .iter()
+            .filter(|l| l.href.starts_with("http://") || l.href.starts_with("https://"))
+            .filter_map(|l| {
+                let rel = l.rel.to_lowercase();
+                let tokens: Vec<&str> = rel.split_whitespace().collect();
+                let touch = tokens.iter().any(|t| t.starts_with("apple-touch-icon"));
+                if !touch && !tokens.contains(&"icon") {
+                    return None;
+                }
+                let rank = if touch {
+                    (3, 0)
+                } else if let Some(size) = Self::largest_size(&l.sizes) {
+                    (0, size.abs_diff(PREFERRED_ICON_SIZE))
+                } else if l.sizes.eq_ignore_ascii_case("any") || l.mime.eq_ignore_ascii_case("image/svg+xml") {
+                    (1, 0)
+                } else {
+                    (2, 0)
+                };
+                Some((rank, l.href.as_str()))
+            })
+            .collect();
+        // Stable, so equally good links keep the page's order
+        ranked.sort_by_key(|(rank, _)| *rank);
+        let mut urls: Vec<String> = Vec::new();
+        for (_, href) in ranked {
+            if !urls.iter().any(|u| u == href) {
+                urls.push(href.to_string());
+            }
+        }
+        if let Some(root) = Self::site_root(page_url) {
+            let fallback = format!("{}/favicon.ico", root);
+            if !urls.contains(&fallback) {
+                urls.push(fallback);
+            }
+        }
+        urls
+    }
+
+    /// `scheme://host[:port]` of an http(s) URL.
+    fn site_root(url: &str) -> Option<String> {
+        let (scheme, rest) = url.split_once("://")?;
+        if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+            return None;
+        }
+        let authority = rest.split(['/', '?', '#']).next().filter(|a| !a.is_empty())?;
+        Some(format!("{}://{}", scheme.to_lowercase(), authority))
+    }
+
+    /// Largest width of a `sizes` attribute such as `16x16 32x32`.
+    fn largest_size(sizes: &str) -> Option<u32> {
+        sizes
+            .split_whitespace()
+            .filter_map(|s| s.to_lowercase().split_once('x').and_then(|(w, _)| w.parse::<u32>().ok()))
+            .max()
+    }
+
+    /// A `data:` URL of an icon fetched with content type `content_type`,
+    /// or `None` if the bytes are not an image. Servers often send icons as
+    /// `application/octet-stream`, so the type is also sniffed.
+    pub fn data_url(bytes: &[u8], content_type: Option<&str>) -> Option<String> {
+        let declared = content_type
+            .map(|t| t.split(';').next().unwrap_or(t).trim().to_lowercase())
+            .filter(|t| t.starts_with("image/"));
+        let mime = declared.or_else(|| Self::sniff_image(bytes).map(String::from))?;
+        Some(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes)))
+    }
+
+    fn sniff_image(bytes: &[u8]) -> Option<&'static str> {
+        let head = &bytes[..bytes.len().min(256)];
+        if head.starts_with(b"\x89PNG") {
+            Some("image/png")
+        } else if head.starts_with(&[0, 0, 1, 0]) {
+            Some("image/x-icon")
+        } else if head.starts_with(b"GIF8") {
+            Some("image/gif")
+        } else if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some("image/jpeg")
+        } else if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP") {
+            Some("image/webp")
+        } else if String::from_utf8_lossy(head).contains("<svg") {
+            Some("image/svg+xml")
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> FaviconManagerTrait for FaviconManager<'a> {
+    fn get_favicon(&self, site: &str) -> Result<Option<Favicon>, FaviconError> {
+        let result = self.conn.query_row(
+            "SELECT site, icon_url, data_url, fetched_at FROM favicons WHERE site = ?1",
+            params![site.to_lowercase()],
+            |row| {
+                Ok(Favicon {
+                    site: row.get(0)?,
+                    icon_url: row.get(1)?,
+                    data_url: row.get(2)?,
+                    fetched_at: row.get(3)?,
+                })
+            },
+        );
+        match result {
+            Ok(icon) => Ok(Some(icon)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(FaviconError::DatabaseError(e.to_string())),
+        }
+    }
+
+    fn save_favicon(&mut self, site: &str, icon_url: &str, data_url: &str) -> Result<(), FaviconError> {
+        if !data_url.starts_with("data:image/") {
+            return Err(FaviconError::InvalidIcon("not an image data: URL".to_string()));
+        }
+        if data_url.len() > MAX_FAVICON_DATA_URL_LEN {
+            return Err(FaviconError::InvalidIcon(format!("{} bytes is too large", data_url.len())));
+        }
+        self.conn
+            .execute(
+                "INSERT INTO favicons (site, icon_url, data_url, fetched_at) VALUES (?1, ?2, ?3, ?4) \
+                 ON CONFLICT(site) DO UPDATE SET icon_url = excluded.icon_url, \
+                 data_url = excluded.data_url, fetched_at = excluded.fetched_at",
+                params![site.to_lowercase(), icon_url, data_url, Self::now()],
+            )
+            .map_err(|e| FaviconError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn remove_favicon(&mut self, site: &str) -> Result<bool, FaviconError> {
+        let affected = self
+            .conn
+            .execute("DELETE FROM favicons WHERE site = ?1", params![site.to_lowercase()])
+            .map_err(|e| FaviconError::DatabaseError(e.to_string()))?;
+        Ok(affected > 0)
+    }
+
+    fn favicons_for_urls(&self, urls: &[&str]) -> Result<HashMap<String, String>, FaviconError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data_url FROM favicons WHERE site = ?1")
+            .map_err(|e| FaviconError::DatabaseError(e.to_string()))?;
+        let mut icons = HashMap::new();
+        for site in urls.iter().filter_map(|url| SiteSettingsManager::site_for_url(url)) {
+            if icons.contains_key(&site) {
+                continue;
+            }
+            let found = stmt.query_row(params![site], |row| row.get::<_, String>(0));
+            match found {
+                Ok(data_url) => {
+                    icons.insert(site, data_url);
+                }
+                Err(rusqlite::Error::QueryReturnedNoRows) => {}
+                Err(e) => return Err(FaviconError::DatabaseError(e.to_string())),
+            }
+        }
+        Ok(icons)
+    }
+
+    fn clear(&mut self) -> Result<(), FaviconError> {
+        self.conn
+            .execute("DELETE FROM favicons", [])
+            .map_err(|e| FaviconError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -167,7 +167,7 @@ impl<'a> HistoryManagerTrait for HistoryManager<'a> {
             Some(d) => {
                 // Parse date string to get start/end timestamps for the day
                 let start = Self::parse_date_to_timestamp(d)
-                    .map_err(|e| HistoryError::DatabaseError(e))?;
+                    .map_err(HistoryError::DatabaseError)?;
                 let end = start + 86400; // +24 hours
 
                 let mut stmt = self
@@ -246,7 +246,7 @@ impl<'a> HistoryManagerTrait for HistoryManager<'a> {
         let (entries, total) = match date {
             Some(d) => {
                 let start = Self::parse_date_to_timestamp(d)
-                    .map_err(|e| HistoryError::DatabaseError(e))?;
+                    .map_err(HistoryError::DatabaseError)?;
                 let end = start + 86400;
 
                 let total: i64 = self.conn.query_row(
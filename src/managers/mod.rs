@@ -1,10 +1,15 @@
 // GitBrowser state managers
-// Managers handle stateful operations: tabs, sessions, bookmarks, history, downloads, permissions, shortcuts.
+// Managers handle stateful operations: tabs, sessions, bookmarks, history, reading list, annotations, downloads, favicons, permissions, site settings, shortcuts, user profiles.
 
+pub mod annotation_manager;
 pub mod bookmark_manager;
 pub mod download_manager;
+pub mod favicon_manager;
 pub mod history_manager;
 pub mod permission_manager;
+pub mod profile_manager;
+pub mod reading_list_manager;
 pub mod session_manager;
 pub mod shortcut_manager;
+pub mod site_settings_manager;
 pub mod tab_manager;
@@ -10,6 +10,7 @@ use rusqlite::params;
 use uuid::Uuid;
 
 use crate::database::connection::Database;
+use crate::services::event_hub::{EventHub, EventTopic};
 use crate::types::errors::PermissionError;
 use crate::types::permission::{PermissionType, PermissionValue, SitePermission};
 
@@ -17,6 +18,18 @@ use crate::types::permission::{PermissionType, PermissionValue, SitePermission};
 pub trait PermissionManagerTrait {
     fn set_permission(&mut self, origin: &str, perm_type: PermissionType, value: PermissionValue) -> Result<(), PermissionError>;
     fn get_permission(&self, origin: &str, perm_type: &PermissionType) -> PermissionValue;
+    /// A site asks for a permission: returns the stored decision, and when
+    /// that is `Ask` announces the prompt so a frontend can ask the user.
+    fn request_permission(&self, origin: &str, perm_type: &PermissionType) -> PermissionValue;
+    /// The user's answer to a prompt; stored as the site's decision when
+    /// `remember` is set, and announced so other frontends close the prompt.
+    fn answer_prompt(
+        &mut self,
+        origin: &str,
+        perm_type: PermissionType,
+        value: PermissionValue,
+        remember: bool,
+    ) -> Result<(), PermissionError>;
     fn get_site_permissions(&self, origin: &str) -> Result<Vec<SitePermission>, PermissionError>;
     fn list_all_permissions(&self) -> Result<Vec<SitePermission>, PermissionError>;
     fn revoke_permission(&mut self, origin: &str, perm_type: &PermissionType) -> Result<(), PermissionError>;
@@ -63,11 +76,25 @@ fn str_to_perm_value(s: &str) -> PermissionValue {
 /// Permission manager backed by SQLite.
 pub struct PermissionManager {
     db: Arc<Database>,
+    events: EventHub,
 }
 
 impl PermissionManager {
     pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
+        Self { db, events: EventHub::new() }
+    }
+
+    /// Publishes prompts and changed decisions to `events`.
+    pub fn with_events(mut self, events: EventHub) -> Self {
+        self.events = events;
+        self
+    }
+
+    fn publish(&self, action: &str, origin: &str, perm_type: Option<&PermissionType>, value: Option<&PermissionValue>) {
+        self.events.publish(
+            EventTopic::Permissions,
+            serde_json::json!({"action": action, "origin": origin, "permission": perm_type, "value": value}),
+        );
     }
 
     fn now_ts() -> i64 {
@@ -96,6 +123,7 @@ impl PermissionManagerTrait for PermissionManager {
             ).map_err(|e| PermissionError::DatabaseError(e.to_string()))?;
         }
 
+        self.publish("changed", origin, Some(&perm_type), Some(&value));
         Ok(())
     }
 
@@ -161,6 +189,28 @@ impl PermissionManagerTrait for PermissionManager {
         Ok(result)
     }
 
+    fn request_permission(&self, origin: &str, perm_type: &PermissionType) -> PermissionValue {
+        let value = self.get_permission(origin, perm_type);
+        if value == PermissionValue::Ask {
+            self.publish("prompt", origin, Some(perm_type), None);
+        }
+        value
+    }
+
+    fn answer_prompt(
+        &mut self,
+        origin: &str,
+        perm_type: PermissionType,
+        value: PermissionValue,
+        remember: bool,
+    ) -> Result<(), PermissionError> {
+        if remember {
+            self.set_permission(origin, perm_type.clone(), value.clone())?;
+        }
+        self.publish("answered", origin, Some(&perm_type), Some(&value));
+        Ok(())
+    }
+
     fn revoke_permission(&mut self, origin: &str, perm_type: &PermissionType) -> Result<(), PermissionError> {
         self.set_permission(origin, perm_type.clone(), PermissionValue::Ask)
     }
@@ -170,6 +220,7 @@ impl PermissionManagerTrait for PermissionManager {
             "DELETE FROM site_permissions WHERE origin = ?1",
             params![origin],
         ).map_err(|e| PermissionError::DatabaseError(e.to_string()))?;
+        self.publish("reset", origin, None, None);
         Ok(())
     }
 }
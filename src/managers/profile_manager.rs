@@ -0,0 +1,181 @@
+//! Profile Manager for GitBrowser.
+//!
+//! A user profile is a directory under `<data_dir>/profiles/<name>` holding
+//! its own database, settings file, web cache, extensions, themes, crash
+//! reports and backups, so profiles share nothing. A running GitBrowser
+//! holds an OS lock on the profile's `lock` file; a second one opening the
+//! same profile is refused instead of writing to the same database. The OS
+//! drops the lock if the process dies, so a crash never leaves a profile
+//! locked.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::PathBuf;
+
+use crate::types::errors::ProfileError;
+use crate::types::profile::{ProfileContext, ProfilePaths, UserProfile};
+
+/// Longest accepted profile name.
+const MAX_NAME_LEN: usize = 64;
+
+/// Name of the context for data outside any profile (`default_context`).
+pub const DEFAULT_CONTEXT_NAME: &str = "default";
+
+const LOCK_FILE: &str = "lock";
+
+/// Trait defining user profile operations.
+pub trait ProfileManagerTrait {
+    fn list_profiles(&self) -> Result<Vec<UserProfile>, ProfileError>;
+    fn create_profile(&self, name: &str) -> Result<UserProfile, ProfileError>;
+    fn delete_profile(&self, name: &str) -> Result<(), ProfileError>;
+    fn get_profile(&self, name: &str) -> Result<UserProfile, ProfileError>;
+    fn lock_profile(&self, name: &str) -> Result<ProfileLock, ProfileError>;
+}
+
+/// Held while a profile is open; dropping it releases the profile.
+#[derive(Debug)]
+pub struct ProfileLock {
+    _file: File,
+}
+
+/// User profiles under one directory.
+#[derive(Clone)]
+pub struct ProfileManager {
+    root: PathBuf,
+}
+
+impl ProfileManager {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Profiles in the platform data directory.
+    pub fn with_default_root() -> Self {
+        Self::new(crate::platform::get_data_dir().join("profiles"))
+    }
+
+    /// Where profile `name` keeps its data; the name is not checked.
+    pub fn paths(&self, name: &str) -> ProfilePaths {
+        let dir = self.root.join(name);
+        ProfilePaths {
+            database: dir.join("gitbrowser.db"),
+            settings: dir.join("settings.json"),
+            cache: dir.join("cache"),
+            extensions: dir.join("extensions"),
+            themes: dir.join("themes"),
+            crashes: dir.join("crashes"),
+            backups: dir.join("backups"),
+            dir,
+        }
+    }
+
+    /// The context an app opens profile `name` with.
+    pub fn context(&self, name: &str) -> ProfileContext {
+        ProfileContext::new(name, self.paths(name))
+    }
+
+    /// The context for a database at `db_path` outside any profile: settings
+    /// in the platform config directory, other data in the data directory,
+    /// and backups beside the database.
+    pub fn default_context(db_path: &str) -> ProfileContext {
+        let data_dir = crate::platform::get_data_dir();
+        let database = PathBuf::from(db_path);
+        let backups = match database.parent() {
+            Some(dir) if db_path != ":memory:" => dir.join("backups"),
+            _ => data_dir.join("backups"),
+        };
+        let paths = ProfilePaths {
+            dir: data_dir.clone(),
+            database,
+            settings: crate::platform::get_config_dir().join("settings.json"),
+            cache: data_dir.join("cache"),
+            extensions: data_dir.join("extensions"),
+            themes: data_dir.join("themes"),
+            crashes: data_dir.join("crashes"),
+            backups,
+        };
+        ProfileContext::new(DEFAULT_CONTEXT_NAME, paths)
+    }
+
+    fn profile(&self, name: &str) -> UserProfile {
+        UserProfile { name: name.to_string(), paths: self.paths(name), in_use: self.try_lock(name).is_err() }
+    }
+
+    fn try_lock(&self, name: &str) -> Result<ProfileLock, ProfileError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.paths(name).dir.join(LOCK_FILE))
+            .map_err(|e| ProfileError::IoError(e.to_string()))?;
+        match file.try_lock() {
+            Ok(()) => Ok(ProfileLock { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(ProfileError::InUse(name.to_string())),
+            Err(TryLockError::Error(e)) => Err(ProfileError::IoError(e.to_string())),
+        }
+    }
+}
+
+impl ProfileManagerTrait for ProfileManager {
+    /// Profiles sorted by name.
+    fn list_profiles(&self) -> Result<Vec<UserProfile>, ProfileError> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(ProfileError::IoError(e.to_string())),
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| validate_name(name).is_ok())
+            .collect();
+        names.sort();
+        Ok(names.iter().map(|name| self.profile(name)).collect())
+    }
+
+    fn create_profile(&self, name: &str) -> Result<UserProfile, ProfileError> {
+        validate_name(name)?;
+        let paths = self.paths(name);
+        if paths.dir.exists() {
+            return Err(ProfileError::AlreadyExists(name.to_string()));
+        }
+        for dir in [&paths.dir, &paths.cache, &paths.extensions] {
+            fs::create_dir_all(dir).map_err(|e| ProfileError::IoError(e.to_string()))?;
+        }
+        Ok(self.profile(name))
+    }
+
+    /// Deletes the profile and all its data; refused while it is open.
+    fn delete_profile(&self, name: &str) -> Result<(), ProfileError> {
+        self.get_profile(name)?;
+        // Released before removing, as Windows cannot delete an open file
+        drop(self.try_lock(name)?);
+        fs::remove_dir_all(self.paths(name).dir).map_err(|e| ProfileError::IoError(e.to_string()))
+    }
+
+    fn get_profile(&self, name: &str) -> Result<UserProfile, ProfileError> {
+        validate_name(name)?;
+        if !self.paths(name).dir.is_dir() {
+            return Err(ProfileError::NotFound(name.to_string()));
+        }
+        Ok(self.profile(name))
+    }
+
+    /// Locks the profile for this process until the lock is dropped.
+    fn lock_profile(&self, name: &str) -> Result<ProfileLock, ProfileError> {
+        self.get_profile(name)?;
+        self.try_lock(name)
+    }
+}
+
+/// Profile names become directory names, so only a safe subset is allowed.
+pub fn validate_name(name: &str) -> Result<(), ProfileError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ProfileError::InvalidName(name.to_string()))
+    }
+}
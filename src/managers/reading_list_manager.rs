@@ -0,0 +1,254 @@
+//! Reading List Manager for GitBrowser.
+//!
+//! Implements `ReadingListManagerTrait` — saves reader-mode extractions for
+//! offline reading, backed by SQLite via `rusqlite`. Images can optionally be
+//! inlined as `data:` URIs so saved articles render without network access.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+use uuid::Uuid;
+
+use crate::types::errors::ReadingListError;
+use crate::types::reader::ReaderContent;
+use crate::types::reading_list::ReadingListItem;
+
+/// Trait defining reading list operations.
+pub trait ReadingListManagerTrait {
+    /// Saves an article. Re-saving an existing URL refreshes its content and
+    /// marks it unread again. Returns the item ID.
+    fn save_article(&mut self, url: &str, content: &ReaderContent, images: Option<&HashMap<String, String>>) -> Result<String, ReadingListError>;
+    fn get_item(&self, id: &str) -> Result<ReadingListItem, ReadingListError>;
+    /// Paginated listing, newest first. Returns (items, total_count).
+    fn list_items(&self, unread_only: bool, limit: i64, offset: i64) -> Result<(Vec<ReadingListItem>, i64), ReadingListError>;
+    fn mark_read(&mut self, id: &str, read: bool) -> Result<(), ReadingListError>;
+    fn remove_item(&mut self, id: &str) -> Result<(), ReadingListError>;
+    /// Stores a complete item (e.g. received through sync), replacing the item
+    /// with the same URL but keeping its local ID. Returns the item ID.
+    fn import_item(&mut self, item: &ReadingListItem) -> Result<String, ReadingListError>;
+}
+
+/// Reading list manager backed by a SQLite connection.
+pub struct ReadingListManager<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> ReadingListManager<'a> {
+    /// Creates a new `ReadingListManager` using the provided database connection.
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Returns the current UNIX timestamp in seconds.
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    }
+
+    /// Replaces `src` attributes whose value is a key in `images` with the
+    /// mapped `data:image/...` URI. Non-image data URIs are ignored.
+    /// Returns the rewritten HTML and whether any image was inlined.
+    pub fn inline_images(html: &str, images: &HashMap<String, String>) -> (String, bool) {
+        let mut out = String::with_capacity(html.len());
+        let mut inlined = false;
+        let mut rest = html;
+
+        while let Some(pos) = rest.find("src=") {
+            let (before, after) = rest.split_at(pos + 4);
+            out.push_str(before);
+            let quote = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => {
+                    rest = after;
+                    continue;
+                }
+            };
+            let Some(end) = after[1..].find(quote) else {
+                rest = after;
+                continue;
+            };
+            let src = &after[1..1 + end];
+            let replacement = images
+                .get(src)
+                .filter(|data| data.starts_with("data:image/") && !data.contains(quote));
+            out.push(quote);
+            match replacement {
+                Some(data) => {
+                    out.push_str(data);
+                    inlined = true;
+                }
+                None => out.push_str(src),
+            }
+            out.push(quote);
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+        (out, inlined)
+    }
+
+    /// Reads a single `ReadingListItem` row into a struct.
+    fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<ReadingListItem> {
+        Ok(ReadingListItem {
+            id: row.get(0)?,
+            url: row.get(1)?,
+            title: row.get(2)?,
+            content_html: row.get(3)?,
+            text_content: row.get(4)?,
+            author: row.get(5)?,
+            site_name: row.get(6)?,
+            estimated_read_time_minutes: row.get(7)?,
+            images_inlined: row.get::<_, i32>(8)? != 0,
+            saved_at: row.get(9)?,
+            read_at: row.get(10)?,
+            page_count: row.get(11)?,
+        })
+    }
+}
+
+const ITEM_COLUMNS: &str = "id, url, title, content_html, text_content, author, site_name, \
+     estimated_read_time_minutes, images_inlined, saved_at, read_at, page_count";
+
+impl<'a> ReadingListManagerTrait for ReadingListManager<'a> {
+    fn save_article(&mut self, url: &str, content: &ReaderContent, images: Option<&HashMap<String, String>>) -> Result<String, ReadingListError> {
+        let (content_html, images_inlined) = match images {
+            Some(map) if !map.is_empty() => Self::inline_images(&content.content, map),
+            _ => (content.content.clone(), false),
+        };
+        let now = Self::now();
+
+        let existing: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT id FROM reading_list WHERE url = ?1",
+                params![url],
+                |row| row.get(0),
+            )
+            .ok();
+
+        match existing {
+            Some(id) => {
+                self.conn
+                    .execute(
+                        "UPDATE reading_list SET title = ?1, content_html = ?2, text_content = ?3, author = ?4, \
+                         site_name = ?5, estimated_read_time_minutes = ?6, images_inlined = ?7, saved_at = ?8, read_at = NULL, \
+                         page_count = ?9 WHERE id = ?10",
+                        params![
+                            content.title, content_html, content.text_content, content.author,
+                            content.site_name, content.estimated_read_time_minutes, images_inlined as i32, now,
+                            content.page_count, id
+                        ],
+                    )
+                    .map_err(|e| ReadingListError::DatabaseError(e.to_string()))?;
+                Ok(id)
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                self.conn
+                    .execute(
+                        "INSERT INTO reading_list (id, url, title, content_html, text_content, author, site_name, \
+                         estimated_read_time_minutes, images_inlined, saved_at, read_at, page_count) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, NULL, ?11)",
+                        params![
+                            id, url, content.title, content_html, content.text_content, content.author,
+                            content.site_name, content.estimated_read_time_minutes, images_inlined as i32, now,
+                            content.page_count
+                        ],
+                    )
+                    .map_err(|e| ReadingListError::DatabaseError(e.to_string()))?;
+                Ok(id)
+            }
+        }
+    }
+
+    fn get_item(&self, id: &str) -> Result<ReadingListItem, ReadingListError> {
+        let sql = format!("SELECT {} FROM reading_list WHERE id = ?1", ITEM_COLUMNS);
+        match self.conn.query_row(&sql, params![id], Self::row_to_item) {
+            Ok(item) => Ok(item),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Err(ReadingListError::NotFound(id.to_string())),
+            Err(e) => Err(ReadingListError::DatabaseError(e.to_string())),
+        }
+    }
+
+    fn list_items(&self, unread_only: bool, limit: i64, offset: i64) -> Result<(Vec<ReadingListItem>, i64), ReadingListError> {
+        let filter = if unread_only { "WHERE read_at IS NULL" } else { "" };
+
+        let total: i64 = self
+            .conn
+            .query_row(&format!("SELECT COUNT(*) FROM reading_list {}", filter), [], |row| row.get(0))
+            .map_err(|e| ReadingListError::DatabaseError(e.to_string()))?;
+
+        let sql = format!(
+            "SELECT {} FROM reading_list {} ORDER BY saved_at DESC LIMIT ?1 OFFSET ?2",
+            ITEM_COLUMNS, filter
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .map_err(|e| ReadingListError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![limit, offset], Self::row_to_item)
+            .map_err(|e| ReadingListError::DatabaseError(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| ReadingListError::DatabaseError(e.to_string()))?);
+        }
+        Ok((results, total))
+    }
+
+    fn mark_read(&mut self, id: &str, read: bool) -> Result<(), ReadingListError> {
+        let read_at = if read { Some(Self::now()) } else { None };
+        let affected = self
+            .conn
+            .execute(
+                "UPDATE reading_list SET read_at = ?1 WHERE id = ?2",
+                params![read_at, id],
+            )
+            .map_err(|e| ReadingListError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(ReadingListError::NotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn remove_item(&mut self, id: &str) -> Result<(), ReadingListError> {
+        let affected = self
+            .conn
+            .execute("DELETE FROM reading_list WHERE id = ?1", params![id])
+            .map_err(|e| ReadingListError::DatabaseError(e.to_string()))?;
+
+        if affected == 0 {
+            return Err(ReadingListError::NotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn import_item(&mut self, item: &ReadingListItem) -> Result<String, ReadingListError> {
+        let id: String = self
+            .conn
+            .query_row(
+                "SELECT id FROM reading_list WHERE url = ?1",
+                params![item.url],
+                |row| row.get(0),
+            )
+            .unwrap_or_else(|_| Uuid::new_v4().to_string());
+
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO reading_list (id, url, title, content_html, text_content, author, site_name, \
+                 estimated_read_time_minutes, images_inlined, saved_at, read_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    id, item.url, item.title, item.content_html, item.text_content, item.author, item.site_name,
+                    item.estimated_read_time_minutes, item.images_inlined as i32, item.saved_at, item.read_at
+                ],
+            )
+            .map_err(|e| ReadingListError::DatabaseError(e.to_string()))?;
+        Ok(id)
+    }
+}
@@ -16,6 +16,8 @@ pub trait ShortcutManagerTrait {
     fn reset_to_defaults(&mut self) -> Result<(), ShortcutError>;
     fn has_conflict(&self, keys: &str, exclude_action: Option<&str>) -> Option<String>;
     fn get_default_shortcuts(&self) -> HashMap<String, String>;
+    /// Replaces all bindings with the defaults, overridden by `bindings`.
+    fn load_bindings(&mut self, bindings: &HashMap<String, String>);
 }
 
 /// Shortcut manager with in-memory storage and platform adaptation.
@@ -138,4 +140,11 @@ impl ShortcutManagerTrait for ShortcutManager {
             .map(|(a, k)| (a.to_string(), Self::adapt_for_platform(k)))
             .collect()
     }
+
+    fn load_bindings(&mut self, bindings: &HashMap<String, String>) {
+        self.shortcuts = self.get_default_shortcuts();
+        for (action, keys) in bindings.iter().filter(|(_, k)| !k.is_empty()) {
+            self.shortcuts.insert(action.clone(), Self::adapt_for_platform(keys));
+        }
+    }
 }
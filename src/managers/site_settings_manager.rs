@@ -0,0 +1,191 @@
+//! Site Settings Manager for GitBrowser.
+//!
+//! Implements `SiteSettingsManagerTrait` — per-site overrides of browser
+//! behaviors (such as forced dark mode or the User-Agent), keyed by host and
+//! stored as JSON values in SQLite. A missing override means the global
+//! setting applies.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+use crate::types::errors::SiteSettingsError;
+use crate::types::site_settings::SiteSetting;
+
+/// Site setting key for the forced dark mode override (boolean).
+pub const FORCE_DARK_KEY: &str = "force_dark";
+
+/// Site setting key turning privacy protections off for a site (`false`).
+pub const SHIELDS_KEY: &str = "shields";
+
+/// Site setting key for the page zoom factor remembered for a site (number).
+pub const ZOOM_KEY: &str = "zoom";
+
+/// Site setting key for the User-Agent string sent to a site (string).
+pub const USER_AGENT_KEY: &str = "user_agent";
+
+/// Trait defining site settings operations.
+pub trait SiteSettingsManagerTrait {
+    fn get_setting(&self, site: &str, key: &str) -> Result<Option<Value>, SiteSettingsError>;
+    /// Creates or replaces the override for `key` on `site`.
+    fn set_setting(&mut self, site: &str, key: &str, value: &Value) -> Result<(), SiteSettingsError>;
+    /// Removes an override. Returns false if none was set.
+    fn remove_setting(&mut self, site: &str, key: &str) -> Result<bool, SiteSettingsError>;
+    /// All overrides for one site, sorted by key.
+    fn get_site_settings(&self, site: &str) -> Result<Vec<SiteSetting>, SiteSettingsError>;
+    /// Every site overriding `key`, sorted by site.
+    fn list_sites(&self, key: &str) -> Result<Vec<SiteSetting>, SiteSettingsError>;
+    fn clear_site(&mut self, site: &str) -> Result<(), SiteSettingsError>;
+}
+
+/// Site settings manager backed by a SQLite connection.
+pub struct SiteSettingsManager<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> SiteSettingsManager<'a> {
+    /// Creates a new `SiteSettingsManager` using the provided database connection.
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Returns the site (lowercase `host[:port]`) of an http(s) URL.
+    pub fn site_for_url(url: &str) -> Option<String> {
+        let lower = url.trim().to_lowercase();
+        let rest = lower
+            .strip_prefix("https://")
+            .or_else(|| lower.strip_prefix("http://"))?;
+        let authority = rest.split(['/', '?', '#']).next()?;
+        let host = authority.rsplit('@').next()?;
+        if host.is_empty() {
+            return None;
+        }
+        Some(host.to_string())
+    }
+
+    /// Whether `user_agent` can be sent as a User-Agent header: not blank,
+    /// printable ASCII, and of a sane length.
+    pub fn is_valid_user_agent(user_agent: &str) -> bool {
+        !user_agent.trim().is_empty()
+            && user_agent.len() <= 512
+            && user_agent.chars().all(|c| c.is_ascii() && !c.is_ascii_control())
+    }
+
+    /// Returns the current UNIX timestamp in seconds.
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    }
+
+    fn validate(site: &str, key: &str) -> Result<(), SiteSettingsError> {
+        if site.is_empty() || site.len() > 260 || site.chars().any(|c| c.is_whitespace() || c == '/') {
+            return Err(SiteSettingsError::InvalidSite(site.to_string()));
+        }
+        let key_ok = !key.is_empty()
+            && key.len() <= 64
+            && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.');
+        if !key_ok {
+            return Err(SiteSettingsError::InvalidKey(key.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Checks the value of the keys the browser reads back with a type.
+    fn validate_value(key: &str, value: &Value) -> Result<(), SiteSettingsError> {
+        match key {
+            USER_AGENT_KEY if !value.as_str().is_some_and(Self::is_valid_user_agent) => Err(
+                SiteSettingsError::InvalidValue(format!("{} must be a non-empty printable string", key)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn row_to_setting(row: &rusqlite::Row) -> rusqlite::Result<SiteSetting> {
+        let raw: String = row.get(2)?;
+        Ok(SiteSetting {
+            site: row.get(0)?,
+            key: row.get(1)?,
+            value: serde_json::from_str(&raw).unwrap_or(Value::Null),
+            updated_at: row.get(3)?,
+        })
+    }
+
+    fn query(&self, sql: &str, param: &str) -> Result<Vec<SiteSetting>, SiteSettingsError> {
+        let mut stmt = self
+            .conn
+            .prepare(sql)
+            .map_err(|e| SiteSettingsError::DatabaseError(e.to_string()))?;
+        let rows = stmt
+            .query_map(params![param], Self::row_to_setting)
+            .map_err(|e| SiteSettingsError::DatabaseError(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row.map_err(|e| SiteSettingsError::DatabaseError(e.to_string()))?);
+        }
+        Ok(results)
+    }
+}
+
+impl<'a> SiteSettingsManagerTrait for SiteSettingsManager<'a> {
+    fn get_setting(&self, site: &str, key: &str) -> Result<Option<Value>, SiteSettingsError> {
+        let result = self.conn.query_row(
+            "SELECT value FROM site_settings WHERE site = ?1 AND key = ?2",
+            params![site, key],
+            |row| row.get::<_, String>(0),
+        );
+        match result {
+            Ok(raw) => Ok(serde_json::from_str(&raw).ok()),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(SiteSettingsError::DatabaseError(e.to_string())),
+        }
+    }
+
+    fn set_setting(&mut self, site: &str, key: &str, value: &Value) -> Result<(), SiteSettingsError> {
+        Self::validate(site, key)?;
+        Self::validate_value(key, value)?;
+        self.conn
+            .execute(
+                "INSERT INTO site_settings (site, key, value, updated_at) VALUES (?1, ?2, ?3, ?4) \
+                 ON CONFLICT(site, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                params![site, key, value.to_string(), Self::now()],
+            )
+            .map_err(|e| SiteSettingsError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn remove_setting(&mut self, site: &str, key: &str) -> Result<bool, SiteSettingsError> {
+        let affected = self
+            .conn
+            .execute(
+                "DELETE FROM site_settings WHERE site = ?1 AND key = ?2",
+                params![site, key],
+            )
+            .map_err(|e| SiteSettingsError::DatabaseError(e.to_string()))?;
+        Ok(affected > 0)
+    }
+
+    fn get_site_settings(&self, site: &str) -> Result<Vec<SiteSetting>, SiteSettingsError> {
+        self.query(
+            "SELECT site, key, value, updated_at FROM site_settings WHERE site = ?1 ORDER BY key",
+            site,
+        )
+    }
+
+    fn list_sites(&self, key: &str) -> Result<Vec<SiteSetting>, SiteSettingsError> {
+        self.query(
+            "SELECT site, key, value, updated_at FROM site_settings WHERE key = ?1 ORDER BY site",
+            key,
+        )
+    }
+
+    fn clear_site(&mut self, site: &str) -> Result<(), SiteSettingsError> {
+        self.conn
+            .execute("DELETE FROM site_settings WHERE site = ?1", params![site])
+            .map_err(|e| SiteSettingsError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use uuid::Uuid;
 
+use crate::services::event_bus::{AppEvent, EventBus};
+use crate::services::event_hub::{EventHub, EventTopic};
 use crate::types::errors::TabError;
-use crate::types::tab::{ScrollPosition, Tab};
+use crate::types::tab::{HistoryStep, LoadError, ScrollPosition, Tab, TabHistory, ZOOM_LEVELS};
 
 /// Trait defining the tab management interface.
 pub trait TabManagerTrait {
@@ -23,18 +25,79 @@ pub trait TabManagerTrait {
     fn get_active_tab(&self) -> Option<&Tab>;
     fn suspend_tab(&mut self, tab_id: &str) -> Result<(), TabError>;
     fn resume_tab(&mut self, tab_id: &str) -> Result<(), TabError>;
+    fn is_suspended(&self, tab_id: &str) -> bool;
     fn tab_count(&self) -> usize;
     fn get_tab_order(&self) -> &[String];
     fn update_tab_url(&mut self, tab_id: &str, url: &str) -> Result<(), TabError>;
     fn update_tab_title(&mut self, tab_id: &str, title: &str) -> Result<(), TabError>;
+    /// Sets the tab's icon (a `data:` URL), or clears it.
+    fn update_tab_favicon(&mut self, tab_id: &str, favicon: Option<&str>) -> Result<(), TabError>;
+    /// Sets a tab's zoom factor, kept within `ZOOM_LEVELS`.
+    fn set_tab_zoom(&mut self, tab_id: &str, zoom: f64) -> Result<(), TabError>;
+    /// Marks a tab whose renderer process died; it shows the crashed page
+    /// until reloaded or navigated.
+    fn mark_tab_crashed(&mut self, tab_id: &str) -> Result<(), TabError>;
+    /// Clears the crashed state so the tab's page loads again.
+    fn reload_crashed_tab(&mut self, tab_id: &str) -> Result<(), TabError>;
+    /// Idle time after which background tabs may be suspended (0 disables).
+    fn set_suspend_timeout_minutes(&mut self, minutes: u32);
+    fn suspend_timeout_minutes(&self) -> u32;
+    /// Suspends the background tabs idle at `now` (seconds since the epoch):
+    /// unpinned tabs past the suspend timeout, or under memory pressure any
+    /// tab idle for `MEMORY_PRESSURE_IDLE_SECS`. Returns their IDs, least
+    /// recently used first.
+    fn suspend_idle_tabs(&mut self, now: i64, memory_pressure: bool) -> Vec<String>;
+    /// Counts a request blocked on the tab's page. Returns the page's count.
+    fn count_blocked_request(&mut self, tab_id: &str) -> Result<u64, TabError>;
+    /// Starts the tab's blocked-request count over, for a new page.
+    fn reset_blocked_requests(&mut self, tab_id: &str) -> Result<(), TabError>;
+    /// Records the page the tab shows in its back/forward history.
+    fn record_navigation(&mut self, tab_id: &str, url: &str) -> Result<(), TabError>;
+    /// Moves the tab back a page. `None` when there is none to go to.
+    fn go_back(&mut self, tab_id: &str) -> Result<Option<HistoryStep>, TabError>;
+    /// Moves the tab forward a page. `None` when there is none to go to.
+    fn go_forward(&mut self, tab_id: &str) -> Result<Option<HistoryStep>, TabError>;
+    fn can_go_back(&self, tab_id: &str) -> bool;
+    fn can_go_forward(&self, tab_id: &str) -> bool;
+    /// The tab has a new webview, with none of the tab's history but the
+    /// current page.
+    fn restart_webview_history(&mut self, tab_id: &str) -> Result<(), TabError>;
+    /// Marks the tab as loading a page, or done with it.
+    fn set_tab_loading(&mut self, tab_id: &str, loading: bool) -> Result<(), TabError>;
+    /// Records a page that failed to load: the tab shows its URL, with the
+    /// error page in its place, until the next page loads.
+    fn fail_tab_load(&mut self, tab_id: &str, error: LoadError) -> Result<(), TabError>;
+    /// Why the tab's page failed to load, if it did.
+    fn load_error(&self, tab_id: &str) -> Option<&LoadError>;
+    /// Web pages closed this session, most recent first; at most
+    /// `MAX_CLOSED_TABS`, each URL once.
+    fn recently_closed(&self) -> Vec<&Tab>;
 }
 
+/// Under memory pressure, background tabs idle this long are suspended
+/// whatever the timeout.
+pub const MEMORY_PRESSURE_IDLE_SECS: i64 = 60;
+
+/// How many closed tabs are remembered.
+pub const MAX_CLOSED_TABS: usize = 20;
+
 /// In-memory tab manager for the browser.
 pub struct TabManager {
     tabs: Vec<Tab>,
     tab_order: Vec<String>,
     active_tab_id: Option<String>,
     suspended_tabs: HashSet<String>,
+    suspend_timeout_minutes: u32,
+    /// When each tab was last the active one; tabs never shown count from creation
+    last_active: HashMap<String, i64>,
+    /// Back/forward history of each tab
+    histories: HashMap<String, TabHistory>,
+    /// Why the page of each tab showing an error page failed to load
+    load_errors: HashMap<String, LoadError>,
+    /// Closed tabs showing web pages, oldest first
+    closed: Vec<Tab>,
+    events: EventHub,
+    bus: EventBus,
 }
 
 impl TabManager {
@@ -44,9 +107,41 @@ impl TabManager {
             tab_order: Vec::new(),
             active_tab_id: None,
             suspended_tabs: HashSet::new(),
+            suspend_timeout_minutes: 30,
+            last_active: HashMap::new(),
+            histories: HashMap::new(),
+            load_errors: HashMap::new(),
+            closed: Vec::new(),
+            events: EventHub::new(),
+            bus: EventBus::new(),
         }
     }
 
+    /// Publishes tab changes to `events`.
+    pub fn with_events(mut self, events: EventHub) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Emits tab changes on `bus`.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    fn changed(&self, action: &str, tab_id: &str) {
+        self.events.publish(
+            EventTopic::Tabs,
+            serde_json::json!({
+                "action": action,
+                "tab_id": tab_id,
+                "tab": self.get_tab(tab_id),
+                "active_tab_id": self.active_tab_id,
+            }),
+        );
+        self.bus.emit(AppEvent::TabChanged { action: action.to_string(), tab_id: tab_id.to_string() });
+    }
+
     fn now() -> i64 {
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -54,6 +149,16 @@ impl TabManager {
             .as_secs() as i64
     }
 
+    /// Makes `tab_id` the active tab. The tab it replaces was in use until now.
+    fn set_active(&mut self, tab_id: String) {
+        let now = Self::now();
+        if let Some(previous) = self.active_tab_id.take().filter(|id| self.find_tab_index(id).is_some()) {
+            self.last_active.insert(previous, now);
+        }
+        self.last_active.insert(tab_id.clone(), now);
+        self.active_tab_id = Some(tab_id);
+    }
+
     fn find_tab_index(&self, tab_id: &str) -> Option<usize> {
         self.tabs.iter().position(|t| t.id == tab_id)
     }
@@ -62,6 +167,19 @@ impl TabManager {
         self.tab_order.iter().position(|id| id == tab_id)
     }
 
+    /// Remembers a closed tab for `recently_closed`; internal pages are not
+    /// worth reopening.
+    fn remember_closed(&mut self, tab: Tab) {
+        if !(tab.url.starts_with("http://") || tab.url.starts_with("https://")) {
+            return;
+        }
+        self.closed.retain(|t| t.url != tab.url);
+        self.closed.push(tab);
+        if self.closed.len() > MAX_CLOSED_TABS {
+            self.closed.remove(0);
+        }
+    }
+
     /// Count of pinned tabs in the current order (they are always at the left).
     fn pinned_count(&self) -> usize {
         self.tab_order
@@ -92,13 +210,17 @@ impl TabManagerTrait for TabManager {
             loading: false,
             crashed: false,
             scroll_position: ScrollPosition::default(),
+            zoom: 1.0,
+            blocked_requests: 0,
             created_at: Self::now(),
         };
+        self.histories.insert(id.clone(), TabHistory::new(&tab.url));
         self.tabs.push(tab);
         self.tab_order.push(id.clone());
         if active || self.active_tab_id.is_none() {
-            self.active_tab_id = Some(id.clone());
+            self.set_active(id.clone());
         }
+        self.changed("created", &id);
         id
     }
 
@@ -116,14 +238,19 @@ impl TabManagerTrait for TabManager {
         let need_switch = self.active_tab_id.as_deref() == Some(tab_id);
 
         // Remove from data structures
-        self.tabs.remove(tab_idx);
+        let tab = self.tabs.remove(tab_idx);
+        self.remember_closed(tab);
         self.tab_order.remove(order_idx);
         self.suspended_tabs.remove(tab_id);
+        self.last_active.remove(tab_id);
+        self.histories.remove(tab_id);
+        self.load_errors.remove(tab_id);
 
         // If that was the last tab, create a new empty one
         if self.tabs.is_empty() {
+            self.changed("closed", tab_id);
             let new_id = self.create_tab(None, true);
-            self.active_tab_id = Some(new_id);
+            self.set_active(new_id);
             return Ok(());
         }
 
@@ -134,9 +261,10 @@ impl TabManagerTrait for TabManager {
             } else {
                 self.tab_order.len() - 1
             };
-            self.active_tab_id = Some(self.tab_order[new_order_idx].clone());
+            self.set_active(self.tab_order[new_order_idx].clone());
         }
 
+        self.changed("closed", tab_id);
         Ok(())
     }
 
@@ -145,7 +273,8 @@ impl TabManagerTrait for TabManager {
         if self.find_tab_index(tab_id).is_none() {
             return Err(TabError::NotFound(tab_id.to_string()));
         }
-        self.active_tab_id = Some(tab_id.to_string());
+        self.set_active(tab_id.to_string());
+        self.changed("switched", tab_id);
         Ok(())
     }
 
@@ -161,6 +290,7 @@ impl TabManagerTrait for TabManager {
 
         let id = self.tab_order.remove(order_idx);
         self.tab_order.insert(new_index, id);
+        self.changed("moved", tab_id);
         Ok(())
     }
 
@@ -186,6 +316,7 @@ impl TabManagerTrait for TabManager {
             self.tab_order.insert(insert_pos, id);
         }
 
+        self.changed("pinned", tab_id);
         Ok(())
     }
 
@@ -209,6 +340,7 @@ impl TabManagerTrait for TabManager {
             self.tab_order.insert(insert_pos, id);
         }
 
+        self.changed("unpinned", tab_id);
         Ok(())
     }
 
@@ -218,6 +350,7 @@ impl TabManagerTrait for TabManager {
             .find_tab_index(tab_id)
             .ok_or_else(|| TabError::NotFound(tab_id.to_string()))?;
         self.tabs[tab_idx].muted = !self.tabs[tab_idx].muted;
+        self.changed("updated", tab_id);
         Ok(())
     }
 
@@ -240,14 +373,22 @@ impl TabManagerTrait for TabManager {
             loading: false,
             crashed: false,
             scroll_position: source.scroll_position.clone(),
+            zoom: source.zoom,
+            blocked_requests: 0,
             created_at: Self::now(),
         };
 
+        // The copy goes back where the original does, in a webview of its own
+        let mut history = self.histories.get(tab_id).cloned().unwrap_or_else(|| TabHistory::new(&new_tab.url));
+        history.restart_in_webview();
+        self.histories.insert(new_id.clone(), history);
+
         // Insert the duplicate right after the source in tab_order
         let order_idx = self.find_order_index(tab_id).unwrap();
         self.tabs.push(new_tab);
         self.tab_order.insert(order_idx + 1, new_id.clone());
 
+        self.changed("created", &new_id);
         Ok(new_id)
     }
 
@@ -257,10 +398,21 @@ impl TabManagerTrait for TabManager {
             return Err(TabError::NotFound(tab_id.to_string()));
         }
 
+        let others: Vec<String> = self.tab_order.iter().filter(|id| *id != tab_id).cloned().collect();
+        for id in &others {
+            if let Some(index) = self.find_tab_index(id) {
+                let tab = self.tabs.remove(index);
+                self.remember_closed(tab);
+            }
+        }
         self.tabs.retain(|t| t.id == tab_id);
         self.tab_order.retain(|id| id == tab_id);
         self.suspended_tabs.retain(|id| id == tab_id);
-        self.active_tab_id = Some(tab_id.to_string());
+        self.last_active.retain(|id, _| id == tab_id);
+        self.histories.retain(|id, _| id == tab_id);
+        self.load_errors.retain(|id, _| id == tab_id);
+        self.set_active(tab_id.to_string());
+        self.changed("closed_others", tab_id);
         Ok(())
     }
 
@@ -274,18 +426,23 @@ impl TabManagerTrait for TabManager {
         let to_remove: Vec<String> = self.tab_order[order_idx + 1..].to_vec();
 
         for id in &to_remove {
-            self.tabs.retain(|t| t.id != *id);
+            if let Some(index) = self.find_tab_index(id) {
+                let tab = self.tabs.remove(index);
+                self.remember_closed(tab);
+            }
             self.suspended_tabs.remove(id);
+            self.last_active.remove(id);
+            self.histories.remove(id);
+            self.load_errors.remove(id);
         }
         self.tab_order.truncate(order_idx + 1);
 
         // If active tab was removed, switch to the specified tab
-        if let Some(ref active) = self.active_tab_id {
-            if to_remove.contains(active) {
-                self.active_tab_id = Some(tab_id.to_string());
-            }
+        if self.active_tab_id.as_ref().is_some_and(|active| to_remove.contains(active)) {
+            self.set_active(tab_id.to_string());
         }
 
+        self.changed("closed_to_right", tab_id);
         Ok(())
     }
 
@@ -313,6 +470,7 @@ impl TabManagerTrait for TabManager {
             return Err(TabError::NotFound(tab_id.to_string()));
         }
         self.suspended_tabs.insert(tab_id.to_string());
+        self.changed("suspended", tab_id);
         Ok(())
     }
 
@@ -322,9 +480,14 @@ impl TabManagerTrait for TabManager {
             return Err(TabError::NotFound(tab_id.to_string()));
         }
         self.suspended_tabs.remove(tab_id);
+        self.changed("resumed", tab_id);
         Ok(())
     }
 
+    fn is_suspended(&self, tab_id: &str) -> bool {
+        self.suspended_tabs.contains(tab_id)
+    }
+
     fn tab_count(&self) -> usize {
         self.tabs.len()
     }
@@ -338,6 +501,10 @@ impl TabManagerTrait for TabManager {
             .ok_or(TabError::NotFound(tab_id.to_string()))?;
         tab.url = url.to_string();
         tab.title = url.to_string();
+        // Navigating starts a new renderer
+        tab.crashed = false;
+        self.load_errors.remove(tab_id);
+        self.changed("updated", tab_id);
         Ok(())
     }
 
@@ -345,6 +512,161 @@ impl TabManagerTrait for TabManager {
         let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
             .ok_or(TabError::NotFound(tab_id.to_string()))?;
         tab.title = title.to_string();
+        self.changed("updated", tab_id);
+        Ok(())
+    }
+
+    fn update_tab_favicon(&mut self, tab_id: &str, favicon: Option<&str>) -> Result<(), TabError> {
+        let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
+            .ok_or(TabError::NotFound(tab_id.to_string()))?;
+        if tab.favicon.as_deref() != favicon {
+            tab.favicon = favicon.map(String::from);
+            self.changed("updated", tab_id);
+        }
+        Ok(())
+    }
+
+    fn set_tab_zoom(&mut self, tab_id: &str, zoom: f64) -> Result<(), TabError> {
+        let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
+            .ok_or(TabError::NotFound(tab_id.to_string()))?;
+        let zoom = if zoom.is_finite() {
+            zoom.clamp(ZOOM_LEVELS[0], ZOOM_LEVELS[ZOOM_LEVELS.len() - 1])
+        } else {
+            1.0
+        };
+        if tab.zoom != zoom {
+            tab.zoom = zoom;
+            self.changed("updated", tab_id);
+        }
+        Ok(())
+    }
+
+    fn mark_tab_crashed(&mut self, tab_id: &str) -> Result<(), TabError> {
+        let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
+            .ok_or(TabError::NotFound(tab_id.to_string()))?;
+        tab.crashed = true;
+        tab.loading = false;
+        self.changed("crashed", tab_id);
+        Ok(())
+    }
+
+    fn reload_crashed_tab(&mut self, tab_id: &str) -> Result<(), TabError> {
+        let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
+            .ok_or(TabError::NotFound(tab_id.to_string()))?;
+        tab.crashed = false;
+        self.changed("updated", tab_id);
+        Ok(())
+    }
+
+    fn set_suspend_timeout_minutes(&mut self, minutes: u32) {
+        self.suspend_timeout_minutes = minutes;
+    }
+
+    fn suspend_timeout_minutes(&self) -> u32 {
+        self.suspend_timeout_minutes
+    }
+
+    fn suspend_idle_tabs(&mut self, now: i64, memory_pressure: bool) -> Vec<String> {
+        let timeout = i64::from(self.suspend_timeout_minutes) * 60;
+        if timeout == 0 {
+            return Vec::new();
+        }
+        let mut idle: Vec<(i64, String)> = self
+            .tabs
+            .iter()
+            .filter(|t| self.active_tab_id.as_deref() != Some(t.id.as_str()) && !self.suspended_tabs.contains(&t.id))
+            .filter_map(|t| {
+                let since = self.last_active.get(&t.id).copied().unwrap_or(t.created_at);
+                let due = if memory_pressure {
+                    now - since >= MEMORY_PRESSURE_IDLE_SECS
+                } else {
+                    !t.pinned && now - since >= timeout
+                };
+                due.then(|| (since, t.id.clone()))
+            })
+            .collect();
+        idle.sort();
+        for (_, id) in &idle {
+            self.suspended_tabs.insert(id.clone());
+            self.changed("suspended", id);
+        }
+        idle.into_iter().map(|(_, id)| id).collect()
+    }
+
+    fn count_blocked_request(&mut self, tab_id: &str) -> Result<u64, TabError> {
+        let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
+            .ok_or(TabError::NotFound(tab_id.to_string()))?;
+        tab.blocked_requests += 1;
+        let count = tab.blocked_requests;
+        self.changed("updated", tab_id);
+        Ok(count)
+    }
+
+    fn reset_blocked_requests(&mut self, tab_id: &str) -> Result<(), TabError> {
+        let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
+            .ok_or(TabError::NotFound(tab_id.to_string()))?;
+        if tab.blocked_requests > 0 {
+            tab.blocked_requests = 0;
+            self.changed("updated", tab_id);
+        }
+        Ok(())
+    }
+
+    fn record_navigation(&mut self, tab_id: &str, url: &str) -> Result<(), TabError> {
+        self.histories.get_mut(tab_id).ok_or(TabError::NotFound(tab_id.to_string()))?.record(url);
         Ok(())
     }
+
+    fn go_back(&mut self, tab_id: &str) -> Result<Option<HistoryStep>, TabError> {
+        Ok(self.histories.get_mut(tab_id).ok_or(TabError::NotFound(tab_id.to_string()))?.back())
+    }
+
+    fn go_forward(&mut self, tab_id: &str) -> Result<Option<HistoryStep>, TabError> {
+        Ok(self.histories.get_mut(tab_id).ok_or(TabError::NotFound(tab_id.to_string()))?.forward())
+    }
+
+    fn can_go_back(&self, tab_id: &str) -> bool {
+        self.histories.get(tab_id).is_some_and(|h| h.can_go_back())
+    }
+
+    fn can_go_forward(&self, tab_id: &str) -> bool {
+        self.histories.get(tab_id).is_some_and(|h| h.can_go_forward())
+    }
+
+    fn restart_webview_history(&mut self, tab_id: &str) -> Result<(), TabError> {
+        self.histories.get_mut(tab_id).ok_or(TabError::NotFound(tab_id.to_string()))?.restart_in_webview();
+        Ok(())
+    }
+
+    fn set_tab_loading(&mut self, tab_id: &str, loading: bool) -> Result<(), TabError> {
+        let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
+            .ok_or(TabError::NotFound(tab_id.to_string()))?;
+        if tab.loading != loading {
+            tab.loading = loading;
+            self.changed("updated", tab_id);
+        }
+        Ok(())
+    }
+
+    fn fail_tab_load(&mut self, tab_id: &str, error: LoadError) -> Result<(), TabError> {
+        let tab = self.tabs.iter_mut().find(|t| t.id == tab_id)
+            .ok_or(TabError::NotFound(tab_id.to_string()))?;
+        tab.url = error.url.clone();
+        tab.title = error.url.clone();
+        tab.loading = false;
+        if let Some(history) = self.histories.get_mut(tab_id) {
+            history.record(&error.url);
+        }
+        self.load_errors.insert(tab_id.to_string(), error);
+        self.changed("updated", tab_id);
+        Ok(())
+    }
+
+    fn load_error(&self, tab_id: &str) -> Option<&LoadError> {
+        self.load_errors.get(tab_id)
+    }
+
+    fn recently_closed(&self) -> Vec<&Tab> {
+        self.closed.iter().rev().collect()
+    }
 }
@@ -0,0 +1,185 @@
+// GitBrowser clipboard
+// Plain text on the system clipboard, through each OS's own tools:
+// Windows: PowerShell (Set-Clipboard / Get-Clipboard)
+// macOS:   pbcopy / pbpaste
+// Linux:   wl-copy / wl-paste on Wayland, otherwise xclip or xsel
+//
+// Secrets (copied passwords) are cleared again after a while, but only if
+// the clipboard still holds them: whatever the user copied since is kept.
+// On Windows they are also kept out of clipboard history and cloud sync.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use zeroize::Zeroizing;
+
+/// Puts `text` on the clipboard. Returns whether the OS took it.
+pub fn set_text(text: &str) -> bool {
+    backend::set_text(text, false)
+}
+
+/// The text on the clipboard, or `None` if it holds none or cannot be read.
+pub fn get_text() -> Option<String> {
+    backend::get_text()
+}
+
+/// Empties the clipboard.
+pub fn clear() -> bool {
+    backend::clear()
+}
+
+/// Empties the clipboard if it still holds `text`. Returns whether it did.
+/// What was read back is wiped from memory, since it is usually a secret.
+pub fn clear_if(text: &str) -> bool {
+    let current = get_text().map(Zeroizing::new);
+    current.is_some_and(|current| current.as_str() == text) && clear()
+}
+
+/// Puts `secret` on the clipboard and clears it again after `clear_after`,
+/// unless something else was copied in the meantime. A zero `clear_after`
+/// leaves it there. Returns whether the OS took it.
+pub fn copy_secret(secret: &str, clear_after: Duration) -> bool {
+    if !backend::set_text(secret, true) {
+        return false;
+    }
+    if !clear_after.is_zero() {
+        let secret = Zeroizing::new(secret.to_string());
+        std::thread::spawn(move || {
+            std::thread::sleep(clear_after);
+            clear_if(&secret);
+        });
+    }
+    true
+}
+
+/// Runs `cmd` with `input` on its stdin. Output is discarded: the X11 and
+/// Wayland tools leave a child behind holding the selection, which would
+/// otherwise keep the pipes open.
+fn pipe_in(mut cmd: Command, input: &str) -> bool {
+    let Ok(mut child) = cmd.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null()).spawn() else {
+        return false;
+    };
+    let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(input.as_bytes()).is_ok());
+    child.wait().is_ok_and(|status| status.success()) && written
+}
+
+/// Runs `cmd` and returns its stdout as is; clipboard text is not trimmed.
+fn read_out(mut cmd: Command) -> Option<String> {
+    let mut child = cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn().ok()?;
+    let mut text = String::new();
+    let read = child.stdout.take()?.read_to_string(&mut text);
+    (child.wait().ok()?.success() && read.is_ok()).then_some(text)
+}
+
+#[cfg(target_os = "windows")]
+mod backend {
+    use super::super::command;
+    use super::{pipe_in, read_out};
+
+    /// Reads the text from stdin as UTF-8. Secrets go in through a data
+    /// object marked to be left out of clipboard history and cloud sync.
+    const SET_SCRIPT: &str = "\
+[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+$t = [Console]::In.ReadToEnd(); \
+if ($env:GB_CLIPBOARD_SECRET) { \
+Add-Type -AssemblyName System.Windows.Forms; \
+$d = New-Object System.Windows.Forms.DataObject; \
+$d.SetText($t); \
+$d.SetData('ExcludeClipboardContentFromMonitorProcessing', $true); \
+$d.SetData('CanIncludeInClipboardHistory', (New-Object System.IO.MemoryStream(,[byte[]](0, 0, 0, 0)))); \
+$d.SetData('CanUploadToCloudClipboard', (New-Object System.IO.MemoryStream(,[byte[]](0, 0, 0, 0)))); \
+[System.Windows.Forms.Clipboard]::SetDataObject($d, $true) \
+} else { Set-Clipboard -Value $t }";
+
+    const GET_SCRIPT: &str = "\
+[Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+$t = Get-Clipboard -Raw; if ($t -ne $null) { [Console]::Out.Write($t) } else { exit 1 }";
+
+    const CLEAR_SCRIPT: &str =
+        "Add-Type -AssemblyName System.Windows.Forms; [System.Windows.Forms.Clipboard]::Clear()";
+
+    pub fn set_text(text: &str, secret: bool) -> bool {
+        if text.is_empty() {
+            return clear();
+        }
+        let mut cmd = command("powershell", &["-NoProfile", "-STA", "-Command", SET_SCRIPT]);
+        if secret {
+            cmd.env("GB_CLIPBOARD_SECRET", "1");
+        }
+        pipe_in(cmd, text)
+    }
+
+    pub fn get_text() -> Option<String> {
+        read_out(command("powershell", &["-NoProfile", "-STA", "-Command", GET_SCRIPT]))
+    }
+
+    pub fn clear() -> bool {
+        command("powershell", &["-NoProfile", "-STA", "-Command", CLEAR_SCRIPT])
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod backend {
+    use super::super::command;
+    use super::{pipe_in, read_out};
+
+    pub fn set_text(text: &str, _secret: bool) -> bool {
+        pipe_in(command("pbcopy", &[]), text)
+    }
+
+    pub fn get_text() -> Option<String> {
+        read_out(command("pbpaste", &[]))
+    }
+
+    pub fn clear() -> bool {
+        set_text("", false)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod backend {
+    use super::super::command;
+    use super::{pipe_in, read_out};
+
+    /// Clipboard tools of the running session, tried in order.
+    enum Tool {
+        WlClipboard,
+        Xclip,
+        Xsel,
+    }
+
+    fn tools() -> &'static [Tool] {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            &[Tool::WlClipboard, Tool::Xclip, Tool::Xsel]
+        } else {
+            &[Tool::Xclip, Tool::Xsel]
+        }
+    }
+
+    pub fn set_text(text: &str, _secret: bool) -> bool {
+        tools().iter().any(|tool| match tool {
+            Tool::WlClipboard => pipe_in(command("wl-copy", &[]), text),
+            Tool::Xclip => pipe_in(command("xclip", &["-selection", "clipboard"]), text),
+            Tool::Xsel => pipe_in(command("xsel", &["--clipboard", "--input"]), text),
+        })
+    }
+
+    pub fn get_text() -> Option<String> {
+        tools().iter().find_map(|tool| match tool {
+            Tool::WlClipboard => read_out(command("wl-paste", &["--no-newline"])),
+            Tool::Xclip => read_out(command("xclip", &["-selection", "clipboard", "-o"])),
+            Tool::Xsel => read_out(command("xsel", &["--clipboard", "--output"])),
+        })
+    }
+
+    pub fn clear() -> bool {
+        tools().iter().any(|tool| match tool {
+            Tool::WlClipboard => command("wl-copy", &["--clear"]).status().is_ok_and(|s| s.success()),
+            Tool::Xclip => pipe_in(command("xclip", &["-selection", "clipboard"]), ""),
+            Tool::Xsel => command("xsel", &["--clipboard", "--delete"]).status().is_ok_and(|s| s.success()),
+        })
+    }
+}
@@ -0,0 +1,110 @@
+// GitBrowser OS keystore
+// Small secrets (keys, not passwords typed by the user) kept by the OS:
+// Windows: Credential Manager, protected with DPAPI
+// macOS:   the login Keychain
+// Linux:   the Secret Service (GNOME Keyring / KWallet through libsecret)
+//
+// Every entry lives under the `gitbrowser` service and is stored base64
+// encoded, so one entry reads back the same on every backend. Builds
+// without the `keystore` feature fail every call with `Unavailable`.
+
+use crate::types::errors::KeystoreError;
+
+/// Keystore service every GitBrowser entry is filed under.
+pub const SERVICE: &str = "gitbrowser";
+
+/// Entry holding the random key that encrypts GitHub tokens and AI API
+/// keys while no master password is unlocked.
+pub const DATA_KEY: &str = "data-key";
+
+/// Length of the data key (AES-256).
+pub const DATA_KEY_LENGTH: usize = 32;
+
+/// Stores `secret` under `name`, replacing any previous value.
+pub fn store(name: &str, secret: &[u8]) -> Result<(), KeystoreError> {
+    backend::store(name, secret)
+}
+
+/// The secret stored under `name`, or `None` if there is none.
+pub fn retrieve(name: &str) -> Result<Option<Vec<u8>>, KeystoreError> {
+    backend::retrieve(name)
+}
+
+/// Removes the secret stored under `name`. Returns whether there was one.
+pub fn delete(name: &str) -> Result<bool, KeystoreError> {
+    backend::delete(name)
+}
+
+/// The secret stored under `name`; a random one of `length` bytes is
+/// created and stored first if there is none.
+pub fn get_or_create(name: &str, length: usize) -> Result<Vec<u8>, KeystoreError> {
+    use crate::services::crypto_service::{CryptoService, CryptoServiceTrait};
+
+    if let Some(secret) = retrieve(name)? {
+        return Ok(secret);
+    }
+    let secret = CryptoService::new().generate_random_bytes(length);
+    store(name, &secret)?;
+    Ok(secret)
+}
+
+/// The data key, created on first use.
+pub fn data_key() -> Result<Vec<u8>, KeystoreError> {
+    get_or_create(DATA_KEY, DATA_KEY_LENGTH)
+}
+
+#[cfg(feature = "keystore")]
+mod backend {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+    use super::SERVICE;
+    use crate::types::errors::KeystoreError;
+
+    fn entry(name: &str) -> Result<keyring::Entry, KeystoreError> {
+        keyring::Entry::new(SERVICE, name).map_err(access_failed)
+    }
+
+    fn access_failed(e: keyring::Error) -> KeystoreError {
+        KeystoreError::AccessFailed(e.to_string())
+    }
+
+    pub fn store(name: &str, secret: &[u8]) -> Result<(), KeystoreError> {
+        entry(name)?.set_password(&BASE64.encode(secret)).map_err(access_failed)
+    }
+
+    pub fn retrieve(name: &str) -> Result<Option<Vec<u8>>, KeystoreError> {
+        match entry(name)?.get_password() {
+            Ok(encoded) => BASE64
+                .decode(encoded.trim())
+                .map(Some)
+                .map_err(|_| KeystoreError::InvalidSecret(name.to_string())),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(access_failed(e)),
+        }
+    }
+
+    pub fn delete(name: &str) -> Result<bool, KeystoreError> {
+        match entry(name)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(access_failed(e)),
+        }
+    }
+}
+
+#[cfg(not(feature = "keystore"))]
+mod backend {
+    use crate::types::errors::KeystoreError;
+
+    pub fn store(_name: &str, _secret: &[u8]) -> Result<(), KeystoreError> {
+        Err(KeystoreError::Unavailable)
+    }
+
+    pub fn retrieve(_name: &str) -> Result<Option<Vec<u8>>, KeystoreError> {
+        Err(KeystoreError::Unavailable)
+    }
+
+    pub fn delete(_name: &str) -> Result<bool, KeystoreError> {
+        Err(KeystoreError::Unavailable)
+    }
+}
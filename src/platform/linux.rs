@@ -4,7 +4,8 @@
 // Cache:  ~/.cache/gitbrowser
 
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// Returns the configuration directory for GitBrowser on Linux.
 /// Uses `$XDG_CONFIG_HOME/gitbrowser` if set, otherwise `~/.config/gitbrowser`.
@@ -42,10 +43,194 @@ pub fn get_cache_dir() -> PathBuf {
     }
 }
 
+/// Returns the user's downloads directory on Linux.
+/// Uses `$XDG_DOWNLOAD_DIR` if set, otherwise `~/Downloads`.
+pub fn get_downloads_dir() -> PathBuf {
+    if let Ok(dir) = env::var("XDG_DOWNLOAD_DIR") {
+        PathBuf::from(dir)
+    } else {
+        let home = env::var("HOME").unwrap_or_else(|_| String::from("/tmp"));
+        PathBuf::from(home).join("Downloads")
+    }
+}
+
+/// Reports whether the desktop prefers a dark color scheme.
+/// An explicit `GTK_THEME` wins; otherwise the XDG desktop portal is asked,
+/// then GNOME's `color-scheme` and `gtk-theme` settings.
+pub fn system_prefers_dark() -> Option<bool> {
+    if let Ok(gtk_theme) = env::var("GTK_THEME") {
+        return Some(gtk_theme.to_lowercase().contains("dark"));
+    }
+    let portal = super::command_output(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.portal.Desktop",
+            "--object-path",
+            "/org/freedesktop/portal/desktop",
+            "--method",
+            "org.freedesktop.portal.Settings.Read",
+            "org.freedesktop.appearance",
+            "color-scheme",
+        ],
+    );
+    if let Some(dark) = portal.as_deref().and_then(parse_portal_color_scheme) {
+        return Some(dark);
+    }
+    let scheme = super::command_output("gsettings", &["get", "org.gnome.desktop.interface", "color-scheme"]);
+    match scheme.as_deref() {
+        Some(s) if s.contains("prefer-dark") => return Some(true),
+        Some(s) if s.contains("prefer-light") => return Some(false),
+        _ => {}
+    }
+    super::command_output("gsettings", &["get", "org.gnome.desktop.interface", "gtk-theme"])
+        .map(|theme| theme.to_lowercase().contains("dark"))
+}
+
+/// Returns GNOME's named accent color (GNOME 47+) as a hex value.
+pub fn system_accent_color() -> Option<String> {
+    let name = super::command_output("gsettings", &["get", "org.gnome.desktop.interface", "accent-color"])?;
+    gnome_accent_hex(name.trim_matches('\'')).map(str::to_string)
+}
+
+/// Maps a GNOME accent color name to the libadwaita palette value.
+pub fn gnome_accent_hex(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "blue" => "#3584e4",
+        "teal" => "#2190a4",
+        "green" => "#3a944a",
+        "yellow" => "#c88800",
+        "orange" => "#ed5b00",
+        "red" => "#e62d42",
+        "pink" => "#d56199",
+        "purple" => "#9141ac",
+        "slate" => "#6f8396",
+        _ => return None,
+    })
+}
+
+/// Parses a portal `color-scheme` reply such as `(<<uint32 1>>,)`.
+/// 1 means prefer dark, 2 prefer light, 0 no preference (`None`).
+pub fn parse_portal_color_scheme(reply: &str) -> Option<bool> {
+    let value = reply.split("uint32").nth(1)?;
+    let digits: String = value.trim().chars().take_while(|c| c.is_ascii_digit()).collect();
+    match digits.as_str() {
+        "1" => Some(true),
+        "2" => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads the power supplies in `/sys/class/power_supply`; a machine without
+/// any (a desktop) is on mains power. Low power is the `power-saver` profile
+/// of power-profiles-daemon.
+pub fn power_status() -> Option<super::PowerStatus> {
+    let supplies = fs::read_dir("/sys/class/power_supply").ok()?;
+    let on_battery = supplies
+        .flatten()
+        .filter_map(|supply| fs::read_to_string(supply.path().join("uevent")).ok())
+        .any(|uevent| is_discharging_battery(&uevent));
+    let low_power = super::command_output("powerprofilesctl", &["get"]).is_some_and(|p| p == "power-saver");
+    Some(super::PowerStatus { on_battery, low_power })
+}
+
+/// Whether a power supply's `uevent` describes a battery being drained.
+pub fn is_discharging_battery(uevent: &str) -> bool {
+    let field = |name: &str| uevent.lines().find_map(|line| line.strip_prefix(name)?.strip_prefix('='));
+    field("POWER_SUPPLY_TYPE") == Some("Battery") && field("POWER_SUPPLY_STATUS") == Some("Discharging")
+}
+
+/// Desktop entry that hands `gitbrowser://` links to the browser.
+const URL_HANDLER_ENTRY: &str = "gitbrowser-url-handler.desktop";
+
+/// Registers `exe` as the handler of `scheme://` links: a hidden desktop
+/// entry in `$XDG_DATA_HOME/applications`, made the default for
+/// `x-scheme-handler/<scheme>` with `xdg-mime`.
+pub fn register_url_scheme(scheme: &str, exe: &Path) -> bool {
+    let Some(applications) = get_data_dir().parent().map(|dir| dir.join("applications")) else {
+        return false;
+    };
+    let entry = url_handler_entry(scheme, exe);
+    if fs::create_dir_all(&applications).and_then(|_| fs::write(applications.join(URL_HANDLER_ENTRY), entry)).is_err() {
+        return false;
+    }
+    super::command("xdg-mime", &["default", URL_HANDLER_ENTRY, &format!("x-scheme-handler/{}", scheme)])
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// The desktop entry for `scheme://` links. The executable is quoted, and
+/// the link follows `--` so one that looks like an option is still a URL.
+pub fn url_handler_entry(scheme: &str, exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\nType=Application\nName=GitBrowser\nExec={} -- %u\nIcon=gitbrowser\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+        desktop_exec_arg(exe),
+        scheme,
+    )
+}
+
+/// Quotes `path` as one argument of a desktop entry's `Exec` key. The key's
+/// own escapes apply on top of the quoting, so a backslash is doubled twice.
+pub fn desktop_exec_arg(path: &Path) -> String {
+    let mut quoted = String::from("\"");
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '"' | '`' | '$' => {
+                quoted.push_str("\\\\");
+                quoted.push(c);
+            }
+            '\\' => quoted.push_str("\\\\\\\\"),
+            '%' => quoted.push_str("%%"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_discharging_battery() {
+        let battery = "POWER_SUPPLY_NAME=BAT0\nPOWER_SUPPLY_TYPE=Battery\nPOWER_SUPPLY_STATUS=Discharging\nPOWER_SUPPLY_CAPACITY=71\n";
+        assert!(is_discharging_battery(battery));
+        assert!(!is_discharging_battery(&battery.replace("Discharging", "Charging")));
+        assert!(!is_discharging_battery("POWER_SUPPLY_NAME=AC\nPOWER_SUPPLY_TYPE=Mains\nPOWER_SUPPLY_ONLINE=1\n"));
+    }
+
+    #[test]
+    fn test_desktop_exec_arg() {
+        assert_eq!(desktop_exec_arg(Path::new("/opt/git browser/gitbrowser")), "\"/opt/git browser/gitbrowser\"");
+        assert_eq!(desktop_exec_arg(Path::new("/opt/100%/gb")), "\"/opt/100%%/gb\"");
+        assert_eq!(desktop_exec_arg(Path::new("/opt/$x")), "\"/opt/\\\\$x\"");
+    }
+
+    #[test]
+    fn test_url_handler_entry() {
+        let entry = url_handler_entry("gitbrowser", Path::new("/opt/git browser/gitbrowser"));
+        assert!(entry.contains("\nExec=\"/opt/git browser/gitbrowser\" -- %u\n"));
+        assert!(entry.contains("\nMimeType=x-scheme-handler/gitbrowser;\n"));
+    }
+
+    #[test]
+    fn test_parse_portal_color_scheme() {
+        assert_eq!(parse_portal_color_scheme("(<<uint32 1>>,)"), Some(true));
+        assert_eq!(parse_portal_color_scheme("(<<uint32 2>>,)"), Some(false));
+        assert_eq!(parse_portal_color_scheme("(<<uint32 0>>,)"), None);
+        assert_eq!(parse_portal_color_scheme("Error: no such interface"), None);
+    }
+
+    #[test]
+    fn test_gnome_accent_hex() {
+        assert_eq!(gnome_accent_hex("blue"), Some("#3584e4"));
+        assert_eq!(gnome_accent_hex("slate"), Some("#6f8396"));
+        assert_eq!(gnome_accent_hex("magenta"), None);
+    }
+
     #[test]
     fn test_config_dir_default() {
         // Temporarily remove XDG_CONFIG_HOME to test default path
@@ -4,7 +4,7 @@
 // Cache:  ~/Library/Caches/GitBrowser
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Returns the home directory on macOS.
 fn home_dir() -> PathBuf {
@@ -35,10 +35,202 @@ pub fn get_cache_dir() -> PathBuf {
     home_dir().join("Library").join("Caches").join("GitBrowser")
 }
 
+/// Returns the user's downloads directory on macOS.
+/// `~/Downloads`
+pub fn get_downloads_dir() -> PathBuf {
+    home_dir().join("Downloads")
+}
+
+/// Reports whether macOS is in Dark Mode. `AppleInterfaceStyle` is only
+/// present (as `Dark`) in dark mode, so a failed read means light mode.
+pub fn system_prefers_dark() -> Option<bool> {
+    let style = super::command_output("defaults", &["read", "-g", "AppleInterfaceStyle"]);
+    Some(style.is_some_and(|s| s.eq_ignore_ascii_case("dark")))
+}
+
+/// Returns the macOS accent color. `AppleAccentColor` is absent for the
+/// default (multicolor/blue) setting.
+pub fn system_accent_color() -> Option<String> {
+    let value = super::command_output("defaults", &["read", "-g", "AppleAccentColor"]);
+    let index = value.and_then(|v| v.parse::<i32>().ok()).unwrap_or(4);
+    macos_accent_hex(index).map(str::to_string)
+}
+
+/// Maps an `AppleAccentColor` index to the system color it selects.
+pub fn macos_accent_hex(index: i32) -> Option<&'static str> {
+    Some(match index {
+        -1 => "#8c8c8c",
+        0 => "#ff3b30",
+        1 => "#ff9500",
+        2 => "#ffcc00",
+        3 => "#28cd41",
+        4 => "#007aff",
+        5 => "#af52de",
+        6 => "#ff2d55",
+        _ => return None,
+    })
+}
+
+/// Launch Services' registration tool.
+const LSREGISTER: &str =
+    "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister";
+
+/// Registers the app bundle around `exe` with Launch Services. The scheme
+/// comes from the `CFBundleURLTypes` of the bundle's `Info.plist`, so
+/// `_scheme` must be declared there; a binary outside a bundle cannot
+/// handle links.
+pub fn register_url_scheme(_scheme: &str, exe: &Path) -> bool {
+    let Some(bundle) = exe.ancestors().find(|p| p.extension().is_some_and(|e| e == "app")) else {
+        return false;
+    };
+    super::command(LSREGISTER, &["-f", &bundle.to_string_lossy()])
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// AppleScript for each kind of file dialog. The text comes in as script
+/// arguments rather than being spliced into the source; a cancelled dialog
+/// fails the script.
+const CHOOSE_FILE_SCRIPT: &[&str] = &[
+    "on run argv",
+    "if (count of argv) > 1 then",
+    "return POSIX path of (choose file with prompt (item 1 of argv) of type (rest of argv))",
+    "end if",
+    "return POSIX path of (choose file with prompt (item 1 of argv))",
+    "end run",
+];
+const CHOOSE_FOLDER_SCRIPT: &[&str] =
+    &["on run argv", "return POSIX path of (choose folder with prompt (item 1 of argv))", "end run"];
+const CHOOSE_FILE_NAME_SCRIPT: &[&str] = &[
+    "on run argv",
+    "return POSIX path of (choose file name with prompt (item 1 of argv) default name (item 2 of argv) default location (POSIX file (item 3 of argv)))",
+    "end run",
+];
+
+/// Shows a file dialog; see `platform::pick_file`.
+pub(super) fn file_dialog(title: &str, dialog: &super::FileDialog) -> Option<PathBuf> {
+    let (script, mut args) = match dialog {
+        super::FileDialog::Open(filters) => {
+            let extensions = filters.iter().flat_map(|f| f.extensions.iter().map(|e| e.to_string()));
+            (CHOOSE_FILE_SCRIPT, extensions.collect::<Vec<_>>())
+        }
+        super::FileDialog::Folder => (CHOOSE_FOLDER_SCRIPT, Vec::new()),
+        super::FileDialog::Save(name) => {
+            (CHOOSE_FILE_NAME_SCRIPT, vec![name.to_string(), get_downloads_dir().display().to_string()])
+        }
+    };
+    args.insert(0, title.to_string());
+    let mut cmd = super::command("osascript", &[]);
+    for line in script {
+        cmd.args(["-e", line]);
+    }
+    cmd.args(&args);
+    super::run_dialog(cmd).ok().flatten()
+}
+
+/// What the dock menu offers: the executable its items start, and the items
+/// in order, `None` standing for a separator.
+#[cfg(feature = "gui")]
+static DOCK_MENU: std::sync::Mutex<Option<(std::path::PathBuf, Vec<Option<super::LauncherItem>>)>> =
+    std::sync::Mutex::new(None);
+
+/// Makes the dock menu show `tasks`, then `recent`, each starting `exe`
+/// with its arguments. The menu is built when the user opens it, through
+/// `applicationDockMenu:` added to tao's application delegate.
+#[cfg(feature = "gui")]
+pub fn set_dock_menu(exe: &Path, tasks: &[super::LauncherItem], recent: &[super::LauncherItem]) -> bool {
+    let mut entries: Vec<Option<super::LauncherItem>> = tasks.iter().cloned().map(Some).collect();
+    if !recent.is_empty() {
+        entries.push(None);
+        entries.extend(recent.iter().cloned().map(Some));
+    }
+    match DOCK_MENU.lock() {
+        Ok(mut menu) => *menu = Some((exe.to_path_buf(), entries)),
+        Err(_) => return false,
+    }
+    dock_menu::install()
+}
+
+#[cfg(feature = "gui")]
+mod dock_menu {
+    use std::sync::OnceLock;
+
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject, Imp, Sel};
+    use objc2::{sel, MainThreadMarker};
+    use objc2_app_kit::{NSMenu, NSMenuItem};
+    use objc2_foundation::NSString;
+
+    use super::DOCK_MENU;
+
+    /// Adds the dock menu methods to tao's application delegate class, once.
+    pub fn install() -> bool {
+        static INSTALLED: OnceLock<bool> = OnceLock::new();
+        *INSTALLED.get_or_init(|| unsafe {
+            let Some(class) = AnyClass::get(c"TaoAppDelegateParent") else {
+                return false;
+            };
+            let class = class as *const AnyClass as *mut AnyClass;
+            let menu: extern "C" fn(&AnyObject, Sel, *mut AnyObject) -> *mut NSMenu = dock_menu;
+            let clicked: extern "C" fn(&AnyObject, Sel, &NSMenuItem) = item_clicked;
+            objc2::ffi::class_addMethod(class, sel!(applicationDockMenu:), std::mem::transmute::<_, Imp>(menu), c"@@:@".as_ptr())
+                .as_bool()
+                && objc2::ffi::class_addMethod(class, sel!(gitbrowserDockItem:), std::mem::transmute::<_, Imp>(clicked), c"v@:@".as_ptr())
+                    .as_bool()
+        })
+    }
+
+    /// `applicationDockMenu:`, answered with the items of `DOCK_MENU`.
+    extern "C" fn dock_menu(this: &AnyObject, _cmd: Sel, _sender: *mut AnyObject) -> *mut NSMenu {
+        let Some(mtm) = MainThreadMarker::new() else {
+            return std::ptr::null_mut();
+        };
+        let menu = NSMenu::new(mtm);
+        if let Ok(guard) = DOCK_MENU.lock() {
+            for (index, entry) in guard.iter().flat_map(|(_, entries)| entries).enumerate() {
+                let Some(entry) = entry else {
+                    menu.addItem(&NSMenuItem::separatorItem(mtm));
+                    continue;
+                };
+                let item = unsafe {
+                    NSMenuItem::initWithTitle_action_keyEquivalent(
+                        mtm.alloc(),
+                        &NSString::from_str(&entry.title),
+                        Some(sel!(gitbrowserDockItem:)),
+                        &NSString::from_str(""),
+                    )
+                };
+                item.setTag(index as isize);
+                unsafe { item.setTarget(Some(this)) };
+                menu.addItem(&item);
+            }
+        }
+        Retained::autorelease_return(menu)
+    }
+
+    /// A dock menu item was clicked: start GitBrowser with its arguments.
+    extern "C" fn item_clicked(_this: &AnyObject, _cmd: Sel, sender: &NSMenuItem) {
+        let Ok(guard) = DOCK_MENU.lock() else { return };
+        let Some((exe, entries)) = guard.as_ref() else { return };
+        if let Some(Some(entry)) = entries.get(sender.tag() as usize) {
+            if let Err(e) = std::process::Command::new(exe).args(&entry.args).spawn() {
+                eprintln!("[DOCK] {}", e);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_macos_accent_hex() {
+        assert_eq!(macos_accent_hex(4), Some("#007aff"));
+        assert_eq!(macos_accent_hex(-1), Some("#8c8c8c"));
+        assert_eq!(macos_accent_hex(9), None);
+    }
+
     #[test]
     fn test_config_dir() {
         let config_dir = get_config_dir();
@@ -1,10 +1,17 @@
 // GitBrowser platform abstraction
-// Provides platform-specific paths and utilities for Windows, macOS, and Linux.
+// Provides platform-specific paths and utilities for Windows, macOS, and Linux,
+// with a generic XDG fallback (`unix`) for the BSDs and other Unix systems.
 //
 // Uses `cfg(target_os)` for conditional compilation to select the correct
 // platform-specific implementation at compile time.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+pub mod clipboard;
+pub mod keystore;
 
 #[cfg(target_os = "linux")]
 mod linux;
@@ -15,9 +22,12 @@ mod macos;
 #[cfg(target_os = "windows")]
 mod windows;
 
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+mod unix;
+
 /// Returns the platform-specific configuration directory for GitBrowser.
 ///
-/// - **Linux**: `~/.config/gitbrowser` (or `$XDG_CONFIG_HOME/gitbrowser`)
+/// - **Linux** and other Unix: `~/.config/gitbrowser` (or `$XDG_CONFIG_HOME/gitbrowser`)
 /// - **macOS**: `~/Library/Application Support/GitBrowser`
 /// - **Windows**: `%APPDATA%/GitBrowser`
 pub fn get_config_dir() -> PathBuf {
@@ -33,11 +43,15 @@ pub fn get_config_dir() -> PathBuf {
     {
         windows::get_config_dir()
     }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        unix::get_config_dir()
+    }
 }
 
 /// Returns the platform-specific data directory for GitBrowser.
 ///
-/// - **Linux**: `~/.local/share/gitbrowser` (or `$XDG_DATA_HOME/gitbrowser`)
+/// - **Linux** and other Unix: `~/.local/share/gitbrowser` (or `$XDG_DATA_HOME/gitbrowser`)
 /// - **macOS**: `~/Library/Application Support/GitBrowser`
 /// - **Windows**: `%APPDATA%/GitBrowser`
 pub fn get_data_dir() -> PathBuf {
@@ -53,11 +67,15 @@ pub fn get_data_dir() -> PathBuf {
     {
         windows::get_data_dir()
     }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        unix::get_data_dir()
+    }
 }
 
 /// Returns the platform-specific cache directory for GitBrowser.
 ///
-/// - **Linux**: `~/.cache/gitbrowser` (or `$XDG_CACHE_HOME/gitbrowser`)
+/// - **Linux** and other Unix: `~/.cache/gitbrowser` (or `$XDG_CACHE_HOME/gitbrowser`)
 /// - **macOS**: `~/Library/Caches/GitBrowser`
 /// - **Windows**: `%LOCALAPPDATA%/GitBrowser/cache`
 pub fn get_cache_dir() -> PathBuf {
@@ -73,12 +91,819 @@ pub fn get_cache_dir() -> PathBuf {
     {
         windows::get_cache_dir()
     }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        unix::get_cache_dir()
+    }
+}
+
+/// Returns the user's downloads directory, where files saved from pages go.
+///
+/// - **Linux** and other Unix: `$XDG_DOWNLOAD_DIR`, or `~/Downloads`
+/// - **macOS**: `~/Downloads`
+/// - **Windows**: `%USERPROFILE%/Downloads`
+pub fn get_downloads_dir() -> PathBuf {
+    #[cfg(target_os = "linux")]
+    {
+        linux::get_downloads_dir()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        macos::get_downloads_dir()
+    }
+    #[cfg(target_os = "windows")]
+    {
+        windows::get_downloads_dir()
+    }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        unix::get_downloads_dir()
+    }
+}
+
+/// Reports whether the OS prefers a dark color scheme, or `None` if it
+/// cannot be determined.
+///
+/// - **Linux**: `GTK_THEME`, then the XDG desktop portal, then GNOME gsettings
+/// - **Other Unix**: `GTK_THEME`, then GNOME gsettings
+/// - **macOS**: `AppleInterfaceStyle` in the global defaults domain
+/// - **Windows**: `AppsUseLightTheme` under `HKCU\...\Themes\Personalize`
+pub fn system_prefers_dark() -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::system_prefers_dark()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        macos::system_prefers_dark()
+    }
+    #[cfg(target_os = "windows")]
+    {
+        windows::system_prefers_dark()
+    }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        unix::system_prefers_dark()
+    }
+}
+
+/// Watches the OS color scheme on a background thread, calling `on_change`
+/// with the new preference whenever it flips. The thread stops once
+/// `on_change` returns `false`.
+///
+/// The preference is polled every `interval`; each platform query is a single
+/// short-lived process, which keeps this free of native toolkit dependencies.
+pub fn watch_system_theme<F>(interval: Duration, on_change: F) -> JoinHandle<()>
+where
+    F: FnMut(bool) -> bool + Send + 'static,
+{
+    watch_value(interval, system_prefers_dark, on_change)
+}
+
+/// Returns the OS accent color as `#rrggbb`, or `None` if the platform does
+/// not expose one.
+///
+/// - **Linux**: GNOME's `accent-color` setting (GNOME 47+)
+/// - **Other Unix**: none
+/// - **macOS**: `AppleAccentColor` (blue when unset)
+/// - **Windows**: the DWM `AccentColor` value
+pub fn system_accent_color() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::system_accent_color()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        macos::system_accent_color()
+    }
+    #[cfg(target_os = "windows")]
+    {
+        windows::system_accent_color()
+    }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        None
+    }
+}
+
+/// Watches the OS accent color like `watch_system_theme`, calling
+/// `on_change` with the new `#rrggbb` value whenever it changes.
+pub fn watch_system_accent<F>(interval: Duration, on_change: F) -> JoinHandle<()>
+where
+    F: FnMut(String) -> bool + Send + 'static,
+{
+    watch_value(interval, system_accent_color, on_change)
+}
+
+/// Polls `probe` every `interval` and reports changed, known values.
+fn watch_value<T, F>(interval: Duration, probe: fn() -> Option<T>, mut on_change: F) -> JoinHandle<()>
+where
+    T: PartialEq + Clone + Send + 'static,
+    F: FnMut(T) -> bool + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut last = probe();
+        loop {
+            std::thread::sleep(interval);
+            let current = probe();
+            if current == last {
+                continue;
+            }
+            last = current.clone();
+            if let Some(value) = current {
+                if !on_change(value) {
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Returns the local time zone's current offset from UTC in minutes
+/// (e.g. 120 for UTC+2), or `None` if it cannot be determined.
+pub fn local_utc_offset_minutes() -> Option<i32> {
+    #[cfg(target_os = "windows")]
+    {
+        command_output(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "[int][System.TimeZoneInfo]::Local.GetUtcOffset([DateTime]::Now).TotalMinutes",
+            ],
+        )?
+        .parse()
+        .ok()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        parse_utc_offset(&command_output("date", &["+%z"])?)
+    }
+}
+
+/// Parses a `date +%z` style offset (`+0530`, `-0800`) into minutes.
+pub fn parse_utc_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let (sign, digits) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// Physical memory, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStatus {
+    pub available: u64,
+    pub total: u64,
+}
+
+impl MemoryStatus {
+    /// Whether less than `fraction` of physical memory is available.
+    pub fn is_low(&self, fraction: f64) -> bool {
+        self.total > 0 && (self.available as f64) < self.total as f64 * fraction
+    }
+}
+
+/// Reports how much physical memory is available, or `None` if it cannot
+/// be determined.
+///
+/// - **Linux**: `MemAvailable` and `MemTotal` from `/proc/meminfo`
+/// - **Other Unix**: free and inactive pages from `sysctl` (FreeBSD only)
+/// - **macOS**: free, inactive and speculative pages from `vm_stat`, of `hw.memsize`
+/// - **Windows**: `FreePhysicalMemory` and `TotalVisibleMemorySize` of `Win32_OperatingSystem`
+pub fn memory_status() -> Option<MemoryStatus> {
+    #[cfg(target_os = "linux")]
+    {
+        parse_meminfo(&std::fs::read_to_string("/proc/meminfo").ok()?)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let total = command_output("sysctl", &["-n", "hw.memsize"])?.parse().ok()?;
+        let available = parse_vm_stat(&command_output("vm_stat", &[])?)?;
+        Some(MemoryStatus { available, total })
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let output = command_output(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "$os = Get-CimInstance Win32_OperatingSystem; $os.FreePhysicalMemory; $os.TotalVisibleMemorySize",
+            ],
+        )?;
+        let mut kib = output.lines().map(|line| line.trim().parse::<u64>().ok());
+        let (available, total) = (kib.next()??, kib.next()??);
+        Some(MemoryStatus { available: available * 1024, total: total * 1024 })
+    }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        unix::memory_status()
+    }
+}
+
+/// Parses `/proc/meminfo`, whose sizes are in kB.
+pub fn parse_meminfo(text: &str) -> Option<MemoryStatus> {
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':')?.split_whitespace().next()?.parse::<u64>().ok())
+            .map(|kib| kib * 1024)
+    };
+    Some(MemoryStatus { available: field("MemAvailable")?, total: field("MemTotal")? })
+}
+
+/// Parses `vm_stat` output into the bytes available: free, inactive and
+/// speculative pages.
+pub fn parse_vm_stat(text: &str) -> Option<u64> {
+    let page_size: u64 = text.lines().next()?.split("page size of ").nth(1)?.split_whitespace().next()?.parse().ok()?;
+    let pages = |name: &str| {
+        text.lines().find_map(|line| line.strip_prefix(name)?.trim().trim_end_matches('.').parse::<u64>().ok())
+    };
+    let free = pages("Pages free:")?;
+    let reclaimable = pages("Pages inactive:").unwrap_or(0) + pages("Pages speculative:").unwrap_or(0);
+    Some((free + reclaimable) * page_size)
+}
+
+/// Where the machine draws its power from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerStatus {
+    /// Running on battery rather than mains power.
+    pub on_battery: bool,
+    /// The OS power or energy saver mode is on.
+    pub low_power: bool,
+}
+
+impl PowerStatus {
+    /// Whether background work should be held back to save power.
+    pub fn is_saving(&self) -> bool {
+        self.on_battery || self.low_power
+    }
+}
+
+/// Reports whether the machine runs on battery and whether the OS saves
+/// power, or `None` if it cannot be determined.
+///
+/// - **Linux**: discharging batteries in `/sys/class/power_supply`, and the
+///   `power-saver` profile of `powerprofilesctl`
+/// - **Other Unix**: `hw.acpi.acline` from `sysctl` (FreeBSD only)
+/// - **macOS**: the power source and `lowpowermode` reported by `pmset`
+/// - **Windows**: `PowerLineStatus` of `SystemInformation.PowerStatus`, and
+///   the `PowerManager` energy saver
+pub fn power_status() -> Option<PowerStatus> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::power_status()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let settings = command_output("pmset", &["-g"]).unwrap_or_default();
+        parse_pmset(&command_output("pmset", &["-g", "batt"])?, &settings)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let output = command_output(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "Add-Type -AssemblyName System.Windows.Forms; \
+                 [System.Windows.Forms.SystemInformation]::PowerStatus.PowerLineStatus; \
+                 [Windows.System.Power.PowerManager, Windows.System.Power, ContentType = WindowsRuntime] > $null; \
+                 [Windows.System.Power.PowerManager]::EnergySaverStatus",
+            ],
+        )?;
+        let mut lines = output.lines().map(str::trim);
+        let on_battery = match lines.next()? {
+            "Offline" => true,
+            "Online" => false,
+            _ => return None,
+        };
+        Some(PowerStatus { on_battery, low_power: lines.next() == Some("On") })
+    }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        unix::power_status()
+    }
+}
+
+/// Watches the power state like `watch_system_theme`, calling `on_change`
+/// with the new state whenever it changes.
+pub fn watch_power_status<F>(interval: Duration, on_change: F) -> JoinHandle<()>
+where
+    F: FnMut(PowerStatus) -> bool + Send + 'static,
+{
+    watch_value(interval, power_status, on_change)
+}
+
+/// Parses `pmset -g batt` (whose first line names the power source) and
+/// `pmset -g` (whose `lowpowermode` is 1 while Low Power Mode is on).
+pub fn parse_pmset(batt: &str, settings: &str) -> Option<PowerStatus> {
+    let source = batt.lines().next()?.strip_prefix("Now drawing from ")?;
+    let low_power = settings.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next() == Some("lowpowermode") && words.next() == Some("1")
+    });
+    Some(PowerStatus { on_battery: source.contains("Battery Power"), low_power })
+}
+
+/// Windows toast built from `GB_NOTIFY_TITLE`/`GB_NOTIFY_BODY`, so the text
+/// never has to be quoted into the script. `GB_NOTIFY_ACTIONS` holds one
+/// button label per line; when there are buttons the script waits for a
+/// click (ten minutes at most) and prints the index of the clicked one.
+#[cfg(target_os = "windows")]
+const WINDOWS_TOAST_SCRIPT: &str = "\
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+$t = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+$n = $t.GetElementsByTagName('text'); \
+$n.Item(0).AppendChild($t.CreateTextNode($env:GB_NOTIFY_TITLE)) > $null; \
+$n.Item(1).AppendChild($t.CreateTextNode($env:GB_NOTIFY_BODY)) > $null; \
+$labels = @(); if ($env:GB_NOTIFY_ACTIONS) { $labels = @($env:GB_NOTIFY_ACTIONS -split [char]10) }; \
+if ($labels.Count) { \
+$a = $t.CreateElement('actions'); \
+for ($i = 0; $i -lt $labels.Count; $i++) { $b = $t.CreateElement('action'); $b.SetAttribute('content', $labels[$i]); $b.SetAttribute('arguments', [string]$i); $a.AppendChild($b) > $null }; \
+$t.DocumentElement.AppendChild($a) > $null }; \
+$toast = [Windows.UI.Notifications.ToastNotification]::new($t); \
+if ($labels.Count) { Register-ObjectEvent -InputObject $toast -EventName Activated -SourceIdentifier gb > $null }; \
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('GitBrowser').Show($toast); \
+if ($labels.Count) { $e = Wait-Event -SourceIdentifier gb -Timeout 600; if ($e) { $e.SourceArgs[1].Arguments } }";
+
+/// A button on a desktop notification, run when the user clicks it.
+pub struct NotificationAction {
+    pub label: String,
+    run: Box<dyn FnOnce() + Send>,
+}
+
+impl NotificationAction {
+    pub fn new(label: impl Into<String>, run: impl FnOnce() + Send + 'static) -> Self {
+        // Labels go one per line to the platform tools
+        let label = label.into().replace(['\n', '\r'], " ");
+        Self { label, run: Box::new(run) }
+    }
+}
+
+/// Shows a desktop notification with `actions` as its buttons and reports
+/// whether the platform tool accepted it. Delivery is best effort: a missing
+/// tool just returns `false`. A clicked action runs on a background thread
+/// that waits for the notification to close.
+///
+/// - **Linux** and other Unix: `notify-send` (libnotify, which also reaches the desktop
+///   portal); one without `--action` support shows the text alone
+/// - **macOS**: `osascript` (`display notification`), which cannot show
+///   buttons, so the actions are left out
+/// - **Windows**: a toast through PowerShell and `Windows.UI.Notifications`
+pub fn notify(title: &str, body: &str, actions: Vec<NotificationAction>) -> bool {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let mut args = vec!["--app-name=GitBrowser".to_string(), "--icon=gitbrowser".to_string()];
+        if actions.is_empty() {
+            args.extend([title.to_string(), body.to_string()]);
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            return command("notify-send", &args).status().is_ok_and(|s| s.success());
+        }
+        args.push("--wait".to_string());
+        args.extend(actions.iter().enumerate().map(|(i, a)| format!("--action={}={}", i, a.label)));
+        args.extend([title.to_string(), body.to_string()]);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let (title, body) = (title.to_string(), body.to_string());
+        run_clicked_action(command("notify-send", &args), actions, move || {
+            notify(&title, &body, Vec::new());
+        })
+    }
+    #[cfg(target_os = "macos")]
+    {
+        drop(actions);
+        // Text is passed as script arguments rather than spliced into AppleScript
+        command(
+            "osascript",
+            &[
+                "-e", "on run argv",
+                "-e", "display notification (item 2 of argv) with title (item 1 of argv)",
+                "-e", "end run",
+                title, body,
+            ],
+        )
+        .status()
+        .is_ok_and(|s| s.success())
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let mut cmd = command("powershell", &["-NoProfile", "-Command", WINDOWS_TOAST_SCRIPT]);
+        cmd.env("GB_NOTIFY_TITLE", title).env("GB_NOTIFY_BODY", body);
+        if actions.is_empty() {
+            return cmd.status().is_ok_and(|s| s.success());
+        }
+        let labels: Vec<&str> = actions.iter().map(|a| a.label.as_str()).collect();
+        cmd.env("GB_NOTIFY_ACTIONS", labels.join("\n"));
+        run_clicked_action(cmd, actions, || {})
+    }
+}
+
+/// Spawns `cmd`, which prints the index of the clicked action, and runs
+/// that action once it exits; `on_failure` runs instead if it fails.
+#[cfg(not(target_os = "macos"))]
+fn run_clicked_action(
+    mut cmd: Command,
+    actions: Vec<NotificationAction>,
+    on_failure: impl FnOnce() + Send + 'static,
+) -> bool {
+    let Ok(child) = cmd.stdout(std::process::Stdio::piped()).spawn() else {
+        return false;
+    };
+    std::thread::spawn(move || match child.wait_with_output() {
+        Ok(output) if output.status.success() => {
+            let clicked = String::from_utf8_lossy(&output.stdout).trim().parse::<usize>().ok();
+            if let Some(action) = clicked.and_then(|i| actions.into_iter().nth(i)) {
+                (action.run)();
+            }
+        }
+        _ => on_failure(),
+    });
+    true
+}
+
+/// Opens the file manager on the folder holding `path`, with the file
+/// selected where the platform supports it. Best effort, like `notify`.
+///
+/// - **Linux** and other Unix: `xdg-open` on the parent folder
+/// - **macOS**: `open -R`
+/// - **Windows**: `explorer /select,`
+pub fn reveal_in_folder(path: &Path) -> bool {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let folder = path.parent().unwrap_or(path).to_string_lossy();
+        command("xdg-open", &[&folder]).spawn().is_ok()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        command("open", &["-R", &path.to_string_lossy()]).spawn().is_ok()
+    }
+    #[cfg(target_os = "windows")]
+    {
+        // explorer exits non-zero even when it opened the window, so only the spawn is checked
+        command("explorer", &[&format!("/select,{}", path.display())]).spawn().is_ok()
+    }
+}
+
+/// Schemes the webview loads itself; links with any other scheme belong to
+/// another application.
+const WEB_SCHEMES: &[&str] = &["http", "https", "gb", "about", "data", "blob", "file", "javascript", "ws", "wss"];
+
+/// Returns the lowercase scheme of `url` when it is handled outside the
+/// browser (`mailto`, `magnet`, `tel`, custom app schemes), or `None` for
+/// web and internal URLs.
+pub fn external_scheme(url: &str) -> Option<String> {
+    let (scheme, _) = url.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    let scheme = scheme.to_ascii_lowercase();
+    (valid && !WEB_SCHEMES.contains(&scheme.as_str())).then_some(scheme)
+}
+
+/// Hands `url` to the application the OS has registered for its scheme.
+/// Only URLs `external_scheme` accepts are opened. Best effort, like
+/// `notify`.
+///
+/// - **Linux** and other Unix: `xdg-open`
+/// - **macOS**: `open`
+/// - **Windows**: `url.dll`'s `FileProtocolHandler`
+pub fn open_external(url: &str) -> bool {
+    if external_scheme(url).is_none() {
+        return false;
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        command("xdg-open", &[url]).spawn().is_ok()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        command("open", &[url]).spawn().is_ok()
+    }
+    #[cfg(target_os = "windows")]
+    {
+        // Unlike `cmd /c start`, nothing in the URL is interpreted by a shell
+        command("rundll32", &["url.dll,FileProtocolHandler", url]).spawn().is_ok()
+    }
+}
+
+/// Scheme of links into GitBrowser's own pages, e.g.
+/// `gitbrowser://settings/privacy`.
+pub const URL_SCHEME: &str = "gitbrowser";
+
+/// Internal pages, opened as `about:<page>`; the only pages a
+/// `gitbrowser://` link can reach.
+pub const INTERNAL_PAGES: &[&str] = &["newtab", "settings", "reading-list", "downloads", "passwords", "extensions", "privacy"];
+
+/// The internal page a `gitbrowser://<page>[/<section>]` link opens, as
+/// `about:<page>[#<section>]`, e.g. `about:settings#privacy` for
+/// `gitbrowser://settings/privacy`. `None` for other URLs and unknown pages.
+pub fn deep_link_target(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    if !scheme.eq_ignore_ascii_case(URL_SCHEME) {
+        return None;
+    }
+    let rest = rest.split(['?', '#']).next().unwrap_or("").trim_end_matches('/');
+    let (page, section) = match rest.split_once('/') {
+        Some((page, section)) => (page, Some(section)),
+        None => (rest, None),
+    };
+    let page = page.to_ascii_lowercase();
+    if !INTERNAL_PAGES.contains(&page.as_str()) {
+        return None;
+    }
+    match section {
+        None => Some(format!("about:{}", page)),
+        Some(section) if !section.is_empty() && section.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => {
+            Some(format!("about:{}#{}", page, section.to_ascii_lowercase()))
+        }
+        Some(_) => None,
+    }
+}
+
+/// Makes the running executable the OS handler of `gitbrowser://` links,
+/// so other applications can link into internal pages. Safe to repeat on
+/// every start; best effort, like `notify`.
+///
+/// - **Linux**: a hidden desktop entry, the `x-scheme-handler/gitbrowser`
+///   default through `xdg-mime`
+/// - **macOS**: the app bundle registered with Launch Services; its
+///   `Info.plist` declares the scheme and links arrive as `Opened` events
+/// - **Windows**: `HKCU\Software\Classes\gitbrowser`
+/// - **Other Unix**: not registered yet
+pub fn register_url_scheme() -> bool {
+    let Ok(exe) = std::env::current_exe() else {
+        return false;
+    };
+    #[cfg(target_os = "linux")]
+    {
+        linux::register_url_scheme(URL_SCHEME, &exe)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        macos::register_url_scheme(URL_SCHEME, &exe)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        windows::register_url_scheme(URL_SCHEME, &exe)
+    }
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    {
+        // No desktop entry outside Linux yet
+        let _ = exe;
+        false
+    }
+}
+
+/// An entry of the taskbar jump list or dock menu: starts GitBrowser again
+/// with `args`, which hands them to the running browser (see
+/// `single_instance`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LauncherItem {
+    pub title: String,
+    pub args: Vec<String>,
+}
+
+impl LauncherItem {
+    pub fn new(title: impl Into<String>, args: Vec<String>) -> Self {
+        Self { title: title.into(), args }
+    }
+}
+
+/// Fills the menu the OS shows on GitBrowser's taskbar button or dock icon
+/// with `tasks` and, below them, `recent` (recently closed tabs), replacing
+/// what it showed before. Best effort, like `notify`; only GUI builds have
+/// a menu to fill.
+///
+/// - **Windows**: the jump list; `tasks` under Tasks, `recent` in a
+///   "Recently closed" category
+/// - **macOS**: the dock menu, built when the user opens it
+/// - **Linux** and other Unix: not supported
+pub fn set_launcher_menu(tasks: &[LauncherItem], recent: &[LauncherItem]) -> bool {
+    let Ok(exe) = std::env::current_exe() else {
+        return false;
+    };
+    #[cfg(all(target_os = "windows", feature = "gui"))]
+    {
+        windows::set_jump_list(&exe, tasks, recent)
+    }
+    #[cfg(all(target_os = "macos", feature = "gui"))]
+    {
+        macos::set_dock_menu(&exe, tasks, recent)
+    }
+    #[cfg(not(all(any(target_os = "windows", target_os = "macos"), feature = "gui")))]
+    {
+        let _ = (exe, tasks, recent);
+        false
+    }
+}
+
+/// A kind of file a file dialog offers, e.g. `JSON files` with `["json"]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileFilter<'a> {
+    pub name: &'a str,
+    /// Extensions without the dot
+    pub extensions: &'a [&'a str],
+}
+
+/// What a file dialog asks for.
+enum FileDialog<'a> {
+    /// An existing file, of one of the kinds given (any file when empty)
+    Open(&'a [FileFilter<'a>]),
+    Folder,
+    /// Where to write a file, suggested as this name in the downloads folder
+    Save(&'a str),
+}
+
+/// Asks the user for an existing file with the OS file dialog, offering the
+/// kinds in `filters` (any file when empty). `None` when they cancel or no
+/// dialog could be shown. Blocks until the dialog closes, so the GUI calls
+/// it off the event loop.
+///
+/// - **Linux** and other Unix: `zenity`, else `kdialog`
+/// - **macOS**: `osascript` (`choose file`)
+/// - **Windows**: the Windows Forms dialogs through PowerShell
+pub fn pick_file(title: &str, filters: &[FileFilter]) -> Option<PathBuf> {
+    file_dialog(title, FileDialog::Open(filters))
+}
+
+/// Asks the user for a folder, like `pick_file`.
+pub fn pick_folder(title: &str) -> Option<PathBuf> {
+    file_dialog(title, FileDialog::Folder)
+}
+
+/// Asks the user where to save a file, suggesting `file_name` in the
+/// downloads folder, like `pick_file`. Replacing an existing file is
+/// confirmed by the dialog.
+pub fn save_dialog(title: &str, file_name: &str) -> Option<PathBuf> {
+    file_dialog(title, FileDialog::Save(file_name))
+}
+
+fn file_dialog(title: &str, dialog: FileDialog) -> Option<PathBuf> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let args = zenity_args(title, &dialog);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        match run_dialog(command("zenity", &args)) {
+            Ok(path) => path,
+            // Not installed; KDE ships its own
+            Err(_) => {
+                let args = kdialog_args(title, &dialog);
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                run_dialog(command("kdialog", &args)).ok().flatten()
+            }
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        macos::file_dialog(title, &dialog)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        windows::file_dialog(title, &dialog)
+    }
+}
+
+/// Runs a dialog tool that prints the chosen path. `Err` if the tool could
+/// not be started; `Ok(None)` if the user cancelled.
+fn run_dialog(mut cmd: Command) -> std::io::Result<Option<PathBuf>> {
+    let output = cmd.output()?;
+    let path = String::from_utf8_lossy(&output.stdout).trim_end_matches(['\r', '\n']).to_string();
+    Ok((output.status.success() && !path.is_empty()).then(|| PathBuf::from(path)))
+}
+
+/// `zenity --file-selection` arguments for `dialog`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn zenity_args(title: &str, dialog: &FileDialog) -> Vec<String> {
+    let mut args = vec!["--file-selection".to_string(), format!("--title={}", title)];
+    match dialog {
+        FileDialog::Open(filters) => {
+            for filter in filters.iter() {
+                let patterns: Vec<String> = filter.extensions.iter().map(|e| format!("*.{}", e)).collect();
+                args.push(format!("--file-filter={} | {}", filter.name, patterns.join(" ")));
+            }
+            if !filters.is_empty() {
+                args.push("--file-filter=All files | *".to_string());
+            }
+        }
+        FileDialog::Folder => args.push("--directory".to_string()),
+        FileDialog::Save(name) => {
+            args.extend(["--save".to_string(), "--confirm-overwrite".to_string()]);
+            args.push(format!("--filename={}", get_downloads_dir().join(name).display()));
+        }
+    }
+    args
+}
+
+/// `kdialog` arguments for `dialog`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn kdialog_args(title: &str, dialog: &FileDialog) -> Vec<String> {
+    let mut args = vec!["--title".to_string(), title.to_string()];
+    let start = get_downloads_dir().display().to_string();
+    match dialog {
+        FileDialog::Open(filters) => {
+            args.extend(["--getopenfilename".to_string(), start]);
+            let kinds: Vec<String> = filters
+                .iter()
+                .map(|f| {
+                    let patterns: Vec<String> = f.extensions.iter().map(|e| format!("*.{}", e)).collect();
+                    format!("{}|{}", patterns.join(" "), f.name)
+                })
+                .collect();
+            if !kinds.is_empty() {
+                args.push(kinds.join("\n"));
+            }
+        }
+        FileDialog::Folder => args.extend(["--getexistingdirectory".to_string(), start]),
+        FileDialog::Save(name) => {
+            args.extend(["--getsavefilename".to_string(), get_downloads_dir().join(name).display().to_string()])
+        }
+    }
+    args
+}
+
+/// Builds a command that never opens a console window.
+fn command(program: &str, args: &[&str]) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: don't flash a console window from the GUI process
+        cmd.creation_flags(0x0800_0000);
+    }
+    cmd
+}
+
+/// Runs a command and returns its trimmed stdout if it exited successfully.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = command(program, args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_utc_offset() {
+        assert_eq!(parse_utc_offset("+0000"), Some(0));
+        assert_eq!(parse_utc_offset("+0530\n"), Some(330));
+        assert_eq!(parse_utc_offset("-0800"), Some(-480));
+        assert_eq!(parse_utc_offset("0800"), None);
+        assert_eq!(parse_utc_offset("+8"), None);
+    }
+
+    #[test]
+    fn test_notification_action_label_is_one_line() {
+        let action = NotificationAction::new("Show in\nfolder\r", || {});
+        assert_eq!(action.label, "Show in folder ");
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_zenity_args() {
+        let filters = [FileFilter { name: "Extensions", extensions: &["zip", "crx"] }];
+        assert_eq!(
+            zenity_args("Install", &FileDialog::Open(&filters)),
+            ["--file-selection", "--title=Install", "--file-filter=Extensions | *.zip *.crx", "--file-filter=All files | *"]
+        );
+        assert_eq!(zenity_args("Pick", &FileDialog::Folder), ["--file-selection", "--title=Pick", "--directory"]);
+        let save = zenity_args("Save", &FileDialog::Save("a.json"));
+        assert_eq!(save[2..4], ["--save", "--confirm-overwrite"]);
+        assert!(save[4].starts_with("--filename=") && save[4].ends_with("a.json"));
+    }
+
+    #[test]
+    fn test_deep_link_target() {
+        assert_eq!(deep_link_target("gitbrowser://settings").as_deref(), Some("about:settings"));
+        assert_eq!(deep_link_target("GitBrowser://Downloads/").as_deref(), Some("about:downloads"));
+        assert_eq!(deep_link_target("gitbrowser://settings/privacy?x=1").as_deref(), Some("about:settings#privacy"));
+        assert_eq!(deep_link_target("gitbrowser://settings/a/b"), None);
+        assert_eq!(deep_link_target("gitbrowser://crashed"), None);
+        assert_eq!(deep_link_target("https://settings/privacy"), None);
+    }
+
+    #[test]
+    fn test_external_scheme() {
+        assert_eq!(external_scheme("mailto:someone@example.com").as_deref(), Some("mailto"));
+        assert_eq!(external_scheme("Magnet:?xt=urn:btih:abc").as_deref(), Some("magnet"));
+        assert_eq!(external_scheme("zoommtg://zoom.us/join").as_deref(), Some("zoommtg"));
+        assert_eq!(external_scheme("https://example.com/"), None);
+        assert_eq!(external_scheme("gb://localhost/newtab"), None);
+        assert_eq!(external_scheme("about:blank"), None);
+        assert_eq!(external_scheme("not a url: really"), None);
+        assert!(!open_external("https://example.com/"));
+    }
+
     #[test]
     fn test_config_dir_returns_path() {
         let config_dir = get_config_dir();
@@ -133,6 +958,38 @@ mod tests {
         let _ = (config_dir, data_dir);
     }
 
+    #[test]
+    fn test_parse_meminfo() {
+        let text = "MemTotal:       16303412 kB\nMemFree:         1203932 kB\nMemAvailable:    8151706 kB\n";
+        let status = parse_meminfo(text).unwrap();
+        assert_eq!(status, MemoryStatus { available: 8151706 * 1024, total: 16303412 * 1024 });
+        assert!(!status.is_low(0.1));
+        assert!(status.is_low(0.6));
+        assert_eq!(parse_meminfo("MemTotal: 1 kB\n"), None);
+    }
+
+    #[test]
+    fn test_parse_vm_stat() {
+        let text = "Mach Virtual Memory Statistics: (page size of 16384 bytes)\n\
+                    Pages free:                               10000.\n\
+                    Pages active:                            500000.\n\
+                    Pages inactive:                           20000.\n\
+                    Pages speculative:                         1000.\n";
+        assert_eq!(parse_vm_stat(text), Some(31000 * 16384));
+        assert_eq!(parse_vm_stat("Pages free: 1."), None);
+    }
+
+    #[test]
+    fn test_parse_pmset() {
+        let batt = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t84%; discharging; 5:12 remaining present: true\n";
+        let settings = "System-wide power settings:\nCurrently in use:\n standby              1\n lowpowermode         1\n sleep                1\n";
+        assert_eq!(parse_pmset(batt, settings), Some(PowerStatus { on_battery: true, low_power: true }));
+
+        let status = parse_pmset("Now drawing from 'AC Power'\n", "").unwrap();
+        assert!(!status.is_saving());
+        assert_eq!(parse_pmset("", settings), None);
+    }
+
     #[test]
     fn test_cache_dir_differs_from_config() {
         let config_dir = get_config_dir();
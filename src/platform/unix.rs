@@ -0,0 +1,130 @@
+// GitBrowser platform paths for other Unix systems (FreeBSD, OpenBSD, NetBSD, ...)
+// Config: ~/.config/gitbrowser
+// Data:   ~/.local/share/gitbrowser
+// Cache:  ~/.cache/gitbrowser
+//
+// The same XDG base directories as Linux. Desktop integration sticks to the
+// freedesktop tools; whatever a system lacks reports `None`.
+
+use std::env;
+use std::path::PathBuf;
+
+/// Returns `$<var>/gitbrowser` if `var` is set, otherwise `~/<default>/gitbrowser`.
+fn xdg_dir(var: &str, default: &[&str]) -> PathBuf {
+    if let Ok(xdg) = env::var(var) {
+        return PathBuf::from(xdg).join("gitbrowser");
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| String::from("/tmp"));
+    default.iter().fold(PathBuf::from(home), |dir, part| dir.join(part)).join("gitbrowser")
+}
+
+/// Returns the configuration directory for GitBrowser.
+/// Uses `$XDG_CONFIG_HOME/gitbrowser` if set, otherwise `~/.config/gitbrowser`.
+pub fn get_config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+/// Returns the data directory for GitBrowser.
+/// Uses `$XDG_DATA_HOME/gitbrowser` if set, otherwise `~/.local/share/gitbrowser`.
+pub fn get_data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", &[".local", "share"])
+}
+
+/// Returns the cache directory for GitBrowser.
+/// Uses `$XDG_CACHE_HOME/gitbrowser` if set, otherwise `~/.cache/gitbrowser`.
+pub fn get_cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", &[".cache"])
+}
+
+/// Returns the user's downloads directory.
+/// Uses `$XDG_DOWNLOAD_DIR` if set, otherwise `~/Downloads`.
+pub fn get_downloads_dir() -> PathBuf {
+    if let Ok(dir) = env::var("XDG_DOWNLOAD_DIR") {
+        PathBuf::from(dir)
+    } else {
+        let home = env::var("HOME").unwrap_or_else(|_| String::from("/tmp"));
+        PathBuf::from(home).join("Downloads")
+    }
+}
+
+/// Reports whether the desktop prefers a dark color scheme: an explicit
+/// `GTK_THEME`, then GNOME's `color-scheme` setting.
+pub fn system_prefers_dark() -> Option<bool> {
+    if let Ok(gtk_theme) = env::var("GTK_THEME") {
+        return Some(gtk_theme.to_lowercase().contains("dark"));
+    }
+    match super::command_output("gsettings", &["get", "org.gnome.desktop.interface", "color-scheme"])?.as_str() {
+        s if s.contains("prefer-dark") => Some(true),
+        s if s.contains("prefer-light") => Some(false),
+        _ => None,
+    }
+}
+
+/// Reports physical memory from FreeBSD's `sysctl` counters; other systems
+/// name them differently and report `None`.
+pub fn memory_status() -> Option<super::MemoryStatus> {
+    let output = super::command_output(
+        "sysctl",
+        &["-n", "hw.physmem", "hw.pagesize", "vm.stats.vm.v_free_count", "vm.stats.vm.v_inactive_count"],
+    )?;
+    parse_sysctl_memory(&output)
+}
+
+/// Parses `sysctl -n hw.physmem hw.pagesize v_free_count v_inactive_count`
+/// output, one value per line; free and inactive pages count as available.
+pub fn parse_sysctl_memory(text: &str) -> Option<super::MemoryStatus> {
+    let mut values = text.lines().map(|line| line.trim().parse::<u64>().ok());
+    let (total, page_size) = (values.next()??, values.next()??);
+    let (free, inactive) = (values.next()??, values.next()??);
+    Some(super::MemoryStatus { available: (free + inactive) * page_size, total })
+}
+
+/// Reports the power source from FreeBSD's ACPI `hw.acpi.acline` (1 on
+/// mains power); there is no system-wide power saver to read.
+pub fn power_status() -> Option<super::PowerStatus> {
+    let on_battery = match super::command_output("sysctl", &["-n", "hw.acpi.acline"])?.as_str() {
+        "0" => true,
+        "1" => false,
+        _ => return None,
+    };
+    Some(super::PowerStatus { on_battery, low_power: false })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sysctl_memory() {
+        let status = parse_sysctl_memory("17045577728\n4096\n1000000\n500000\n").unwrap();
+        assert_eq!(status.total, 17045577728);
+        assert_eq!(status.available, 1500000 * 4096);
+        assert_eq!(parse_sysctl_memory("17045577728\n4096\n"), None);
+    }
+
+    #[test]
+    fn test_config_dir_with_xdg() {
+        let original = env::var("XDG_CONFIG_HOME").ok();
+        env::set_var("XDG_CONFIG_HOME", "/custom/config");
+
+        assert_eq!(get_config_dir(), PathBuf::from("/custom/config/gitbrowser"));
+
+        match original {
+            Some(val) => env::set_var("XDG_CONFIG_HOME", val),
+            None => env::remove_var("XDG_CONFIG_HOME"),
+        }
+    }
+
+    #[test]
+    fn test_data_dir_default() {
+        let original = env::var("XDG_DATA_HOME").ok();
+        env::remove_var("XDG_DATA_HOME");
+
+        let home = env::var("HOME").unwrap_or_else(|_| String::from("/tmp"));
+        assert_eq!(get_data_dir(), PathBuf::from(&home).join(".local").join("share").join("gitbrowser"));
+
+        if let Some(val) = original {
+            env::set_var("XDG_DATA_HOME", val);
+        }
+    }
+}
@@ -4,7 +4,7 @@
 // Cache:  %LOCALAPPDATA%/GitBrowser/cache
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Returns the configuration directory for GitBrowser on Windows.
 /// `%APPDATA%/GitBrowser`
@@ -32,10 +32,253 @@ pub fn get_cache_dir() -> PathBuf {
         .join("cache")
 }
 
+/// Returns the user's downloads directory on Windows.
+/// `%USERPROFILE%/Downloads`
+pub fn get_downloads_dir() -> PathBuf {
+    let profile = env::var("USERPROFILE").unwrap_or_else(|_| String::from("C:\\Users\\Default"));
+    PathBuf::from(profile).join("Downloads")
+}
+
+/// Reports whether Windows apps use the dark theme (`AppsUseLightTheme` = 0).
+pub fn system_prefers_dark() -> Option<bool> {
+    let output = super::command_output(
+        "reg",
+        &[
+            "query",
+            "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize",
+            "/v",
+            "AppsUseLightTheme",
+        ],
+    )?;
+    parse_reg_dword(&output, "AppsUseLightTheme").map(|light| light == 0)
+}
+
+/// Returns the Windows accent color from `HKCU\Software\Microsoft\Windows\DWM`.
+pub fn system_accent_color() -> Option<String> {
+    let output = super::command_output(
+        "reg",
+        &["query", "HKCU\\Software\\Microsoft\\Windows\\DWM", "/v", "AccentColor"],
+    )?;
+    parse_reg_dword(&output, "AccentColor").map(abgr_to_hex)
+}
+
+/// Converts a DWM `0xAABBGGRR` color to `#rrggbb`.
+pub fn abgr_to_hex(value: u32) -> String {
+    let r = value & 0xff;
+    let g = (value >> 8) & 0xff;
+    let b = (value >> 16) & 0xff;
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Extracts a `REG_DWORD` value from `reg query` output, e.g.
+/// `    AppsUseLightTheme    REG_DWORD    0x0`.
+pub fn parse_reg_dword(output: &str, name: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        if parts.next()? != name || parts.next()? != "REG_DWORD" {
+            return None;
+        }
+        let hex = parts.next()?.strip_prefix("0x")?;
+        u32::from_str_radix(hex, 16).ok()
+    })
+}
+
+/// Registers `exe` as the handler of `scheme://` links for the current
+/// user, under `HKCU\Software\Classes\<scheme>`.
+pub fn register_url_scheme(scheme: &str, exe: &Path) -> bool {
+    let key = format!("HKCU\\Software\\Classes\\{}", scheme);
+    let open = format!("{}\\shell\\open\\command", key);
+    let command = url_handler_command(exe);
+    [
+        vec!["add", key.as_str(), "/ve", "/d", "URL:GitBrowser link", "/f"],
+        vec!["add", key.as_str(), "/v", "URL Protocol", "/d", "", "/f"],
+        vec!["add", open.as_str(), "/ve", "/d", command.as_str(), "/f"],
+    ]
+    .iter()
+    .all(|args| super::command("reg", args).status().is_ok_and(|s| s.success()))
+}
+
+/// The command a link is opened with. The link follows `--`, so one that
+/// looks like an option is still taken as a URL.
+pub fn url_handler_command(exe: &Path) -> String {
+    format!("\"{}\" -- \"%1\"", exe.display())
+}
+
+/// The Windows Forms file dialogs, driven by `GB_DIALOG_*` variables so no
+/// text is quoted into the script. Prints the chosen path, or nothing.
+const FILE_DIALOG_SCRIPT: &str = "\
+[Console]::OutputEncoding = [Text.Encoding]::UTF8; \
+Add-Type -AssemblyName System.Windows.Forms; \
+switch ($env:GB_DIALOG_KIND) { \
+'folder' { $d = New-Object System.Windows.Forms.FolderBrowserDialog; $d.Description = $env:GB_DIALOG_TITLE; \
+if ($d.ShowDialog() -eq 'OK') { $d.SelectedPath } } \
+'save' { $d = New-Object System.Windows.Forms.SaveFileDialog; $d.Title = $env:GB_DIALOG_TITLE; \
+$d.InitialDirectory = $env:GB_DIALOG_DIR; $d.FileName = $env:GB_DIALOG_NAME; \
+if ($d.ShowDialog() -eq 'OK') { $d.FileName } } \
+default { $d = New-Object System.Windows.Forms.OpenFileDialog; $d.Title = $env:GB_DIALOG_TITLE; \
+if ($env:GB_DIALOG_FILTER) { $d.Filter = $env:GB_DIALOG_FILTER }; \
+if ($d.ShowDialog() -eq 'OK') { $d.FileName } } }";
+
+/// Shows a file dialog; see `platform::pick_file`.
+pub(super) fn file_dialog(title: &str, dialog: &super::FileDialog) -> Option<PathBuf> {
+    // The dialogs need a single-threaded apartment
+    let mut cmd = super::command("powershell", &["-NoProfile", "-STA", "-Command", FILE_DIALOG_SCRIPT]);
+    cmd.env("GB_DIALOG_TITLE", title);
+    match dialog {
+        super::FileDialog::Open(filters) => cmd.env("GB_DIALOG_KIND", "open").env("GB_DIALOG_FILTER", file_dialog_filter(filters)),
+        super::FileDialog::Folder => cmd.env("GB_DIALOG_KIND", "folder"),
+        super::FileDialog::Save(name) => cmd
+            .env("GB_DIALOG_KIND", "save")
+            .env("GB_DIALOG_DIR", get_downloads_dir())
+            .env("GB_DIALOG_NAME", name),
+    };
+    super::run_dialog(cmd).ok().flatten()
+}
+
+/// A Windows Forms `Filter` offering `filters`, then all files; empty for
+/// no filters.
+pub fn file_dialog_filter(filters: &[super::FileFilter]) -> String {
+    if filters.is_empty() {
+        return String::new();
+    }
+    let mut parts: Vec<String> = filters
+        .iter()
+        .map(|f| {
+            let patterns: Vec<String> = f.extensions.iter().map(|e| format!("*.{}", e)).collect();
+            format!("{} ({})|{}", f.name, patterns.join(", "), patterns.join(";"))
+        })
+        .collect();
+    parts.push("All files (*.*)|*.*".to_string());
+    parts.join("|")
+}
+
+/// Replaces the taskbar jump list of the running process: `tasks` as its
+/// Tasks, `recent` in a "Recently closed" category, each a link to `exe`.
+#[cfg(feature = "gui")]
+pub fn set_jump_list(exe: &Path, tasks: &[super::LauncherItem], recent: &[super::LauncherItem]) -> bool {
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+    use windows::Win32::UI::Shell::Common::IObjectArray;
+    use windows::Win32::UI::Shell::{DestinationList, ICustomDestinationList};
+    use windows_core::{Interface, HSTRING};
+
+    // The list is shown for the calling process, so this runs in the browser itself
+    let initialized = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }.is_ok();
+    let result = (|| unsafe {
+        let list: ICustomDestinationList = CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+        let mut slots = 0u32;
+        let _removed: IObjectArray = list.BeginList(&mut slots)?;
+        if !recent.is_empty() {
+            list.AppendCategory(&HSTRING::from("Recently closed"), &shell_links(exe, recent)?.cast::<IObjectArray>()?)?;
+        }
+        list.AddUserTasks(&shell_links(exe, tasks)?.cast::<IObjectArray>()?)?;
+        list.CommitList()
+    })();
+    if initialized {
+        unsafe { CoUninitialize() };
+    }
+    result.is_ok()
+}
+
+/// A collection of shell links running `exe` with each item's arguments.
+#[cfg(feature = "gui")]
+unsafe fn shell_links(
+    exe: &Path,
+    items: &[super::LauncherItem],
+) -> windows_core::Result<windows::Win32::UI::Shell::Common::IObjectCollection> {
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::Common::IObjectCollection;
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{EnumerableObjectCollection, IShellLinkW, ShellLink};
+    use windows_core::{Interface, HSTRING, PROPVARIANT};
+
+    let collection: IObjectCollection = CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+    let exe = HSTRING::from(exe.as_os_str());
+    for item in items {
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+        link.SetPath(&exe)?;
+        link.SetArguments(&HSTRING::from(command_line(&item.args)))?;
+        link.SetIconLocation(&exe, 0)?;
+        // Jump list entries show the link's title, not its description
+        let store: IPropertyStore = link.cast()?;
+        store.SetValue(&PKEY_Title, &PROPVARIANT::from(item.title.as_str()))?;
+        store.Commit()?;
+        collection.AddObject(&link)?;
+    }
+    Ok(collection)
+}
+
+/// Quotes `args` into a command line the C runtime splits back into them.
+pub fn command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let mut quoted = String::from("\"");
+            let mut backslashes = 0;
+            for c in arg.chars() {
+                match c {
+                    '\\' => backslashes += 1,
+                    '"' => {
+                        quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                        quoted.push(c);
+                        backslashes = 0;
+                    }
+                    _ => {
+                        quoted.push_str(&"\\".repeat(backslashes));
+                        quoted.push(c);
+                        backslashes = 0;
+                    }
+                }
+            }
+            // Backslashes before the closing quote are doubled so they stay literal
+            quoted.push_str(&"\\".repeat(backslashes * 2));
+            quoted.push('"');
+            quoted
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_url_handler_command() {
+        let exe = Path::new(r"C:\Program Files\GitBrowser\gitbrowser.exe");
+        assert_eq!(url_handler_command(exe), r#""C:\Program Files\GitBrowser\gitbrowser.exe" -- "%1""#);
+    }
+
+    #[test]
+    fn test_parse_reg_dword() {
+        let output = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize\r\n    AppsUseLightTheme    REG_DWORD    0x0\r\n";
+        assert_eq!(parse_reg_dword(output, "AppsUseLightTheme"), Some(0));
+        assert_eq!(parse_reg_dword("    AppsUseLightTheme    REG_DWORD    0x1", "AppsUseLightTheme"), Some(1));
+        assert_eq!(parse_reg_dword(output, "SystemUsesLightTheme"), None);
+    }
+
+    #[test]
+    fn test_command_line() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(command_line(&args(&["--private"])), "\"--private\"");
+        assert_eq!(command_line(&args(&["https://a.example/?q=1 2", "x"])), "\"https://a.example/?q=1 2\" \"x\"");
+        assert_eq!(command_line(&args(&["say \"hi\""])), "\"say \\\"hi\\\"\"");
+        assert_eq!(command_line(&args(&["C:\\dir\\"])), "\"C:\\dir\\\\\"");
+    }
+
+    #[test]
+    fn test_file_dialog_filter() {
+        use crate::platform::FileFilter;
+        let filters = [FileFilter { name: "Extensions", extensions: &["zip", "crx"] }];
+        assert_eq!(file_dialog_filter(&filters), "Extensions (*.zip, *.crx)|*.zip;*.crx|All files (*.*)|*.*");
+        assert_eq!(file_dialog_filter(&[]), "");
+    }
+
+    #[test]
+    fn test_abgr_to_hex() {
+        assert_eq!(abgr_to_hex(0xffd77800), "#0078d7");
+        assert_eq!(abgr_to_hex(0x00000000), "#000000");
+    }
+
     #[test]
     fn test_config_dir_with_appdata() {
         let config_dir = get_config_dir();
@@ -0,0 +1,139 @@
+//! Per-launch authentication for RPC clients on the local socket.
+//!
+//! Each backend generates a fresh secret at startup and writes it to
+//! `rpc.token` beside the socket, readable by the current user only. A socket
+//! client's first message must present it:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","id":1,"method":"auth","params":{"token":"..."}}
+//! ```
+//!
+//! Nothing else is dispatched before that succeeds, and a client that gets it
+//! wrong is disconnected. Stdin needs no token: only the process that started
+//! the backend can write to it.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::rpc_protocol::{error_response, parse_line, Incoming, UNAUTHORIZED};
+use crate::services::crypto_service::{CryptoService, CryptoServiceTrait};
+use crate::types::errors::GitBrowserError;
+
+/// Bytes of randomness in a token.
+const TOKEN_BYTES: usize = 32;
+
+/// Method a socket client authenticates with.
+pub const AUTH_METHOD: &str = "auth";
+
+/// The secret socket clients must present; new for every launch.
+pub struct AuthToken(String);
+
+impl AuthToken {
+    pub fn generate() -> Self {
+        let bytes = CryptoService::new().generate_random_bytes(TOKEN_BYTES);
+        Self(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether `presented` is this token, in time independent of where they differ.
+    pub fn verify(&self, presented: &str) -> bool {
+        let (a, b) = (self.0.as_bytes(), presented.as_bytes());
+        a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+    }
+
+    /// Writes the token to `<dir>/rpc.token`, readable by the current user
+    /// only (file mode on Unix, DACL on Windows); the file is removed when
+    /// the returned guard is dropped.
+    pub fn write_file(&self, dir: &Path) -> io::Result<TokenFile> {
+        let path = dir.join("rpc.token");
+        let _ = std::fs::remove_file(&path);
+        io::Write::write_all(&mut create_private(&path)?, self.0.as_bytes())?;
+        Ok(TokenFile { path })
+    }
+}
+
+/// Creates `path` for the current user alone: mode `0600` on Unix, an
+/// owner-only DACL on Windows. Either is set as the file is created, so
+/// there is no moment where someone else can open it.
+#[cfg(not(windows))]
+fn create_private(path: &Path) -> io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+#[cfg(windows)]
+fn create_private(path: &Path) -> io::Result<std::fs::File> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+    use windows_sys::Win32::Foundation::{GENERIC_WRITE, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{CreateFileW, CREATE_NEW, FILE_ATTRIBUTE_NORMAL};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    crate::rpc_socket::owner_only(|attributes| {
+        // SAFETY: `wide` is NUL-terminated and `attributes` is valid for the call.
+        let handle = unsafe {
+            CreateFileW(
+                wide.as_ptr(),
+                GENERIC_WRITE,
+                0,
+                attributes,
+                CREATE_NEW,
+                FILE_ATTRIBUTE_NORMAL,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the handle was just opened and nothing else owns it.
+        Ok(unsafe { std::fs::File::from_raw_handle(handle) })
+    })
+}
+
+/// The token file of a running backend; removed on drop.
+pub struct TokenFile {
+    path: PathBuf,
+}
+
+impl TokenFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TokenFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Checks a client's first line against `token`. `Ok` carries the answer to
+/// send (`None` for a notification); `Err` the answer to send before
+/// disconnecting the client.
+pub fn handshake(token: &AuthToken, line: &str) -> Result<Option<Value>, Option<Value>> {
+    let call = match parse_line(line) {
+        Ok(Incoming::Single(Ok(call))) => call,
+        Ok(Incoming::Single(Err(response))) | Err(response) => return Err(Some(response)),
+        Ok(Incoming::Batch(_)) => {
+            let message = GitBrowserError::Unauthorized.to_string();
+            return Err(Some(error_response(Value::Null, UNAUTHORIZED, &message)));
+        }
+    };
+    let presented = call.params.get("token").and_then(|v| v.as_str()).unwrap_or_default();
+    if call.method == AUTH_METHOD && token.verify(presented) {
+        Ok(call.response(&Ok(json!({"ok": true}))))
+    } else {
+        Err(call.response(&Err(GitBrowserError::Unauthorized)))
+    }
+}
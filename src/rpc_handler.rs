@@ -4,166 +4,1202 @@
 //! The `handle_method` function dispatches JSON-RPC method calls to the
 //! appropriate managers and services via the `App` struct.
 
+use std::collections::HashMap;
 use std::sync::Mutex;
 
 use crate::app::App;
+use crate::database::maintenance;
+use crate::managers::annotation_manager::{AnnotationManager, AnnotationManagerTrait};
 use crate::managers::bookmark_manager::{BookmarkManager, BookmarkManagerTrait};
+use crate::managers::download_manager::DownloadManagerTrait;
+use crate::managers::favicon_manager::{FaviconManager, FaviconManagerTrait};
 use crate::managers::history_manager::{HistoryManager, HistoryManagerTrait};
+use crate::managers::permission_manager::PermissionManagerTrait;
+use crate::managers::profile_manager::ProfileManagerTrait;
+use crate::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+use crate::managers::shortcut_manager::{ShortcutManager, ShortcutManagerTrait};
+use crate::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, SHIELDS_KEY};
 use crate::managers::tab_manager::TabManagerTrait;
+use crate::rpc_methods::{self, METHODS};
+use crate::rpc_stream::{Progress, Stream};
 use crate::services::password_manager::PasswordManagerTrait;
+use crate::services::privacy_engine::PrivacyEngineTrait;
 use crate::services::settings_engine::SettingsEngineTrait;
+use crate::services::sync::{GistRemote, SyncServiceTrait};
 use crate::services::localization_engine::LocalizationEngineTrait;
-use crate::services::github_integration::GitHubIntegrationTrait;
+use crate::services::github_integration::{GitHubEndpoints, GitHubIntegration, GitHubIntegrationTrait};
+use crate::services::github_notifications::{alert_texts, GitHubNotifications, GitHubNotificationsTrait};
+use crate::services::github_quick_open::{command_query, GitHubQuickOpen, GitHubQuickOpenTrait};
 use crate::services::extension_framework::ExtensionFrameworkTrait;
 use crate::services::ai_assistant::AIAssistantTrait;
+use crate::services::crash_recovery::CrashRecoveryTrait;
+use crate::services::event_hub::{Deliver, EventTopic};
+use crate::services::crash_reporter::{issue_draft_url, sanitize_report, upload, UploadTarget};
+use crate::services::reader_mode::{ReaderMode, ReaderModeTrait, MAX_STITCHED_PAGES};
+use crate::services::search_engine_registry::{SearchEngineRegistry, SearchEngineRegistryTrait};
+use crate::services::theme_engine::{is_valid_hex_color, ThemeEngineTrait, SYSTEM_ACCENT};
+use crate::services::theme_scheduler::ThemeSchedulerTrait;
+use crate::services::update_installer::UpdateInstallerTrait;
+use crate::services::update_manager::{channel_switch_warning, check_releases, UpdateManagerTrait};
+use crate::types::ai::AIAnswerPart;
+use crate::types::credential::CredentialEntry;
+use crate::types::download::{DownloadItem, DownloadStatus};
+use crate::types::errors::{
+    AIError, CrashError, CryptoError, DownloadError, GitBrowserError, GitHubError, SearchEngineError, ShortcutError, SyncError,
+    TabError, ThemeError, UpdateError,
+};
+use crate::types::extension::{ExtensionInfo, ExtensionPermission, MessageSender};
+use crate::types::github::{DeviceFlowProgress, OAuthDeviceCode};
+use crate::types::permission::{PermissionType, PermissionValue};
+use crate::types::privacy::{CrashLogEntry, CrashReport};
+use crate::types::reader::{ReaderContent, ReaderSettings};
+use crate::types::search::SearchEngine;
+use crate::types::session::SessionTab;
+use crate::types::settings::ThemeMode;
+use crate::types::tab::{Tab, ZoomStep};
+use crate::types::sync::{ConflictResolution, SyncCollection};
+use crate::types::update::UpdateChannel;
+use crate::types::theme::CustomTheme;
 
 use serde_json::{json, Value};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
+/// How long `ai.ask` waits for the next part of the answer.
+const AI_ANSWER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
 /// Encode bytes to base64 string.
 pub fn base64_encode(data: &[u8]) -> String {
     BASE64.encode(data)
 }
 
 /// Decode base64 string to bytes.
-pub fn base64_decode(input: &str) -> Result<Vec<u8>, String> {
-    BASE64.decode(input).map_err(|e| format!("base64 decode error: {}", e))
+pub fn base64_decode(input: &str) -> Result<Vec<u8>, GitBrowserError> {
+    BASE64.decode(input).map_err(|e| invalid(format!("invalid base64: {}", e)))
+}
+
+/// Current time in unix milliseconds.
+/// The error for a missing parameter `name`.
+fn missing(name: &str) -> GitBrowserError {
+    GitBrowserError::InvalidParams(format!("missing {}", name))
+}
+
+/// The error for a parameter the method cannot use; `message` says which.
+fn invalid(message: impl Into<String>) -> GitBrowserError {
+    GitBrowserError::InvalidParams(message.into())
+}
+
+/// The string parameter `name`.
+fn str_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, GitBrowserError> {
+    params.get(name).and_then(|v| v.as_str()).ok_or_else(|| missing(name))
+}
+
+fn bool_param(params: &Value, name: &str) -> Result<bool, GitBrowserError> {
+    params.get(name).and_then(|v| v.as_bool()).ok_or_else(|| missing(name))
+}
+
+fn u64_param(params: &Value, name: &str) -> Result<u64, GitBrowserError> {
+    params.get(name).and_then(|v| v.as_u64()).ok_or_else(|| missing(name))
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis() as i64
+}
+
+/// `subscribe {topics}` and `unsubscribe {subscription}`. Subscriptions belong
+/// to the connection they are made on (`client`) and push events through
+/// `deliver`; omitting `topics` subscribes to all of them. Returns `None` for
+/// every other method.
+pub fn handle_subscription(
+    app: &Mutex<App>,
+    client: u64,
+    method: &str,
+    params: &Value,
+    deliver: &Deliver,
+) -> Option<Result<Value, GitBrowserError>> {
+    match method {
+        "subscribe" => Some(subscribe(app, client, params, deliver)),
+        "unsubscribe" => Some(unsubscribe(app, client, params)),
+        _ => None,
+    }
+}
+
+/// Methods that send their result in parts through `stream` (see
+/// `rpc_stream`) before answering. Returns `None` for every other method.
+pub fn handle_streaming(
+    app: &Mutex<App>,
+    method: &str,
+    params: &Value,
+    stream: &mut Stream,
+) -> Option<Result<Value, GitBrowserError>> {
+    match method {
+        "history.export" => Some(export_history(app, params, stream)),
+        "settings.import" => Some(import_settings(app, params, stream)),
+        "password.import" => Some(import_passwords(app, params, stream)),
+        "ai.ask" => Some(ask_ai(app, params, stream)),
+        _ => None,
+    }
+}
+
+/// `history.export {date?, chunk_size?}`: the history, newest first, in parts
+/// of `chunk_size` entries. The app is not locked between parts.
+fn export_history(app: &Mutex<App>, params: &Value, stream: &mut Stream) -> Result<Value, GitBrowserError> {
+    let date = params.get("date").and_then(|v| v.as_str()).map(String::from);
+    let chunk_size = params.get("chunk_size").and_then(|v| v.as_i64()).unwrap_or(500).clamp(1, 5000);
+    let worker = app.lock()?.db_worker.clone();
+    let mut offset = 0;
+    loop {
+        let date = date.clone();
+        let (entries, total) = worker
+            .run(move |conn| HistoryManager::new(conn).list_history_paginated(date.as_deref(), chunk_size, offset))
+            .wait()??;
+        if entries.is_empty() {
+            return Ok(json!({"exported": offset, "chunks": stream.sent()}));
+        }
+        offset += entries.len() as i64;
+        let progress = Progress { done: offset as u64, total: Some(total.max(offset) as u64) };
+        stream.chunk(json!({"entries": entries}), progress);
+        if offset >= total {
+            return Ok(json!({"exported": offset, "chunks": stream.sent()}));
+        }
+    }
+}
+
+/// `settings.import {path, apply?}`: the changes the file makes, one per
+/// part; they are applied only with `apply`.
+fn import_settings(app: &Mutex<App>, params: &Value, stream: &mut Stream) -> Result<Value, GitBrowserError> {
+    let path = str_param(params, "path")?;
+    let apply = params.get("apply").and_then(|v| v.as_bool()).unwrap_or(false);
+    let changes = {
+        let mut a = app.lock()?;
+        if apply {
+            let changes = a.settings_engine.import_from(path)?;
+            a.process_settings_changes();
+            changes
+        } else {
+            a.settings_engine.preview_import(path)?
+        }
+    };
+    let total = changes.len() as u64;
+    for (i, change) in changes.into_iter().enumerate() {
+        stream.chunk(json!({"change": change}), Progress { done: i as u64 + 1, total: Some(total) });
+    }
+    Ok(json!({"applied": apply, "changes": total, "chunks": stream.sent()}))
+}
+
+/// `password.import {master_password, path}`: one part per credential as it
+/// is stored, without its password.
+fn import_passwords(app: &Mutex<App>, params: &Value, stream: &mut Stream) -> Result<Value, GitBrowserError> {
+    let master = str_param(params, "master_password")?;
+    let path = str_param(params, "path")?;
+    let mut a = app.lock()?;
+    let imported = a.password_manager.import_encrypted_each(master, path, |entry, done, total| {
+        let credential = json!({"id": entry.id, "url": entry.url, "username": entry.username});
+        stream.chunk(json!({"credential": credential}), Progress { done: done as u64, total: Some(total as u64) });
+    })?;
+    Ok(json!({"imported": imported, "chunks": stream.sent()}))
+}
+
+/// `ai.ask {selected_text, page_url?}`: hands the selection to the assistant
+/// panel and passes its answer on as the panel sends it with `ai.answer`,
+/// one part per piece of text. The app is not locked while waiting.
+fn ask_ai(app: &Mutex<App>, params: &Value, stream: &mut Stream) -> Result<Value, GitBrowserError> {
+    let text = str_param(params, "selected_text")?;
+    let context = crate::types::ai::AIContext {
+        selected_text: Some(text.to_string()),
+        page_content: None,
+        page_url: params.get("page_url").and_then(|v| v.as_str()).map(String::from),
+    };
+    let Some((ask, parts)) = app.lock()?.ask_ai_for_answer(&context) else {
+        return Ok(json!({"delivered": false}));
+    };
+    let mut answer = String::new();
+    loop {
+        let Ok(part) = parts.recv_timeout(AI_ANSWER_TIMEOUT) else {
+            app.lock()?.cancel_ai_ask(ask);
+            return Err(AIError::NoAnswer.into());
+        };
+        if !part.text.is_empty() {
+            answer.push_str(&part.text);
+            let progress = Progress { done: answer.chars().count() as u64, total: None };
+            stream.chunk(json!({"text": part.text}), progress);
+        }
+        if part.done {
+            return Ok(json!({"delivered": true, "answer": answer, "chunks": stream.sent()}));
+        }
+    }
+}
+
+fn subscribe(app: &Mutex<App>, client: u64, params: &Value, deliver: &Deliver) -> Result<Value, GitBrowserError> {
+    let topics = match params.get("topics").and_then(|v| v.as_array()) {
+        Some(names) => names
+            .iter()
+            .map(|name| {
+                let name = name.as_str().unwrap_or_default();
+                EventTopic::parse(name).ok_or_else(|| invalid(format!("invalid topic: {}", name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => EventTopic::ALL.to_vec(),
+    };
+    let a = app.lock()?;
+    let id = a.events.subscribe(client, &topics, deliver.clone());
+    let names: Vec<&str> = topics.iter().map(|t| t.as_str()).collect();
+    Ok(json!({"subscription": id, "topics": names}))
+}
+
+fn unsubscribe(app: &Mutex<App>, client: u64, params: &Value) -> Result<Value, GitBrowserError> {
+    let id = u64_param(params, "subscription")?;
+    let a = app.lock()?;
+    if !a.events.unsubscribe(client, id) {
+        return Err(invalid(format!("invalid subscription: {}", id)));
+    }
+    Ok(json!({"ok": true}))
+}
+
+/// Applies a registry mutation to the stored search settings and persists it.
+fn update_search_engines(
+    app: &Mutex<App>,
+    f: impl FnOnce(&mut SearchEngineRegistry) -> Result<(), SearchEngineError>,
+) -> Result<Value, GitBrowserError> {
+    let mut a = app.lock()?;
+    let mut registry = SearchEngineRegistry::new(a.settings_engine.get_settings().search.clone());
+    f(&mut registry)?;
+    let search = serde_json::to_value(registry.settings())?;
+    a.settings_engine.set_value("search", search)?;
+    a.process_settings_changes();
+    Ok(json!({"ok": true}))
+}
+
+/// A tab as the `tab.*` methods return it.
+fn tab_json(a: &App, tab: &Tab) -> Value {
+    let mut value = json!(tab);
+    value["suspended"] = json!(a.tab_manager.is_suspended(&tab.id));
+    value["can_go_back"] = json!(a.tab_manager.can_go_back(&tab.id));
+    value["can_go_forward"] = json!(a.tab_manager.can_go_forward(&tab.id));
+    value
+}
+
+/// The cached icons of the sites of `urls`, for listings.
+fn favicons_for(a: &App, urls: &[&str]) -> HashMap<String, String> {
+    FaviconManager::new(a.db.connection()).favicons_for_urls(urls).unwrap_or_default()
+}
+
+/// The icon of `url`'s site among `icons`, or null.
+fn favicon_json(icons: &HashMap<String, String>, url: &str) -> Value {
+    json!(SiteSettingsManager::site_for_url(url).and_then(|site| icons.get(&site)))
+}
+
+/// Sites listed on the privacy dashboard, most blocked first.
+const DASHBOARD_TOP_SITES: usize = 10;
+
+/// Everything the privacy dashboard shows, as `privacy.dashboard` reports it.
+pub fn privacy_dashboard_json(a: &App) -> Value {
+    let stats = a.privacy_engine.get_stats();
+    let mut by_site: Vec<(&String, &u64)> = stats.blocked_by_site.iter().collect();
+    by_site.sort_by(|x, y| y.1.cmp(x.1).then_with(|| x.0.cmp(y.0)));
+    let top_sites: Vec<Value> = by_site
+        .into_iter()
+        .take(DASHBOARD_TOP_SITES)
+        .map(|(site, blocked)| {
+            json!({"site": site, "blocked": blocked, "shields": !a.privacy_engine.is_site_disabled(site)})
+        })
+        .collect();
+    let disabled_sites: Vec<String> = SiteSettingsManager::new(a.db.connection())
+        .list_sites(SHIELDS_KEY)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| s.value == Value::Bool(false))
+        .map(|s| s.site)
+        .collect();
+    let privacy = &a.settings_engine.get_settings().privacy;
+    json!({
+        "stats": stats,
+        "total_blocked": stats.trackers_blocked + stats.ads_blocked
+            + stats.extension_rules_blocked + stats.custom_filters_blocked,
+        "top_sites": top_sites,
+        "disabled_sites": disabled_sites,
+        "dns_over_https": a.privacy_engine.dns_over_https_provider(),
+        "lists": a.privacy_engine.filter_lists(),
+        "toggles": {
+            "tracker_blocking": privacy.tracker_blocking,
+            "ad_blocking": privacy.ad_blocking,
+            "https_enforcement": privacy.https_enforcement,
+            "dns_over_https": privacy.dns_over_https,
+            "anti_fingerprinting": privacy.anti_fingerprinting,
+        },
+    })
+}
+
+/// An installed extension as `extension.list` reports it.
+pub fn extension_json(a: &App, e: &ExtensionInfo) -> Value {
+    json!({
+        "id": e.id, "name": e.name, "version": e.version, "enabled": e.enabled,
+        "permissions": e.permissions, "performance_impact_ms": e.performance_impact_ms,
+        "install_path": e.install_path,
+        "content_scripts": e.content_scripts,
+        "options_url": a.extension_framework.options_page_path(&e.id)
+            .map(|_| crate::services::extension_framework::options_url(&e.id)),
+    })
+}
+
+/// A saved login without its password, which only `password.decrypt` gives out.
+pub fn credential_json(c: &CredentialEntry) -> Value {
+    json!({
+        "id": c.id, "url": c.url, "username": c.username,
+        "created_at": c.created_at, "updated_at": c.updated_at
+    })
+}
+
+/// A download with its progress, sizes formatted for the current locale.
+pub fn download_json(a: &App, item: &DownloadItem) -> Value {
+    let mut value = json!(item);
+    let progress = match (&item.status, item.size) {
+        (DownloadStatus::Completed, _) => Some(1.0),
+        (_, Some(size)) if size > 0 => Some((item.downloaded as f64 / size as f64).min(1.0)),
+        _ => None,
+    };
+    value["progress"] = json!(progress);
+    value["downloaded_text"] = json!(a.localization_engine.format_bytes(item.downloaded));
+    value["size_text"] = json!(item.size.map(|size| a.localization_engine.format_bytes(size)));
+    value
+}
+
+/// `{download}` for download `id`, as the `download.*` methods answer.
+fn download_response(a: &App, id: &str) -> Result<Value, GitBrowserError> {
+    let item = a
+        .download_manager
+        .get_download(id)
+        .ok_or_else(|| DownloadError::NotFound(id.to_string()))?;
+    Ok(json!({"download": download_json(a, item)}))
+}
+
+/// The article in the `html` of `url`, for `reader.extract` and
+/// `reader.render`. With `max_pages` above 1 the following pages are fetched
+/// and stitched on; the app is not locked meanwhile.
+fn reader_content(params: &Value) -> Result<ReaderContent, GitBrowserError> {
+    let html = str_param(params, "html")?;
+    let url = str_param(params, "url")?;
+    let max_pages = params.get("max_pages").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
+    let content = ReaderMode::new().extract_stitched(html, url, max_pages)?;
+    Ok(content)
+}
+
+/// The theme as `theme.get` returns it: the configured mode and accent, what
+/// is showing now, and the CSS variables for it.
+fn theme_json(a: &App) -> Value {
+    let appearance = &a.settings_engine.get_settings().appearance;
+    let active = match a.theme_engine.get_theme() {
+        ThemeMode::System => a.theme_engine.detect_system_theme(),
+        mode => mode.clone(),
+    };
+    json!({
+        "mode": appearance.theme,
+        "active_mode": active,
+        "accent": appearance.accent_color,
+        "accent_color": a.theme_engine.get_accent_color(),
+        "custom_theme": a.theme_engine.get_custom_theme().map(|t| t.id.clone()),
+        "variables": a.theme_engine.get_css_variables(),
+    })
+}
+
+/// A binding as the `shortcuts.*` methods return it; `keys` is null for an
+/// unbound action.
+fn shortcut_json(action: &str, keys: Option<&str>, defaults: &HashMap<String, String>) -> Value {
+    let default = defaults.get(action);
+    json!({
+        "action": action,
+        "keys": keys,
+        "default": default,
+        "customized": keys != default.map(String::as_str),
+    })
+}
+
+/// Applies a change to the shortcut bindings and persists them under
+/// `shortcuts`, where sync picks them up.
+fn update_shortcuts(
+    app: &Mutex<App>,
+    f: impl FnOnce(&mut ShortcutManager) -> Result<(), ShortcutError>,
+) -> Result<Value, GitBrowserError> {
+    let mut a = app.lock()?;
+    f(&mut a.shortcut_manager)?;
+    let shortcuts = json!(a.shortcut_manager.list_shortcuts());
+    a.settings_engine.set_value("shortcuts", shortcuts)?;
+    a.process_settings_changes();
+    Ok(json!({"ok": true}))
+}
+
+/// The `permission` parameter, e.g. `"Camera"`.
+fn permission_type(params: &Value) -> Result<PermissionType, GitBrowserError> {
+    params
+        .get("permission")
+        .cloned()
+        .ok_or_else(|| missing("permission"))
+        .and_then(|v| serde_json::from_value(v).map_err(|_| invalid("invalid permission")))
+}
+
+/// The `value` parameter: `"Allow"`, `"Deny"` or `"Ask"`.
+fn permission_value(params: &Value) -> Result<PermissionValue, GitBrowserError> {
+    params
+        .get("value")
+        .cloned()
+        .ok_or_else(|| missing("value"))
+        .and_then(|v| serde_json::from_value(v).map_err(|_| invalid("invalid value")))
+}
+
+/// One sync run against the user's gist, for `sync.now`. The key derivation
+/// and the gist requests run without the app lock; it is taken to read the
+/// local data and again to apply the result. A rate limit fails the run
+/// rather than being waited out.
+fn sync_now(app: &Mutex<App>) -> Result<Value, GitBrowserError> {
+    let (client, gist_id, run) = {
+        let a = app.lock()?;
+        let client = a.github_integration.client().map_err(|e| match e {
+            GitHubError::NotAuthenticated => GitBrowserError::from(SyncError::NotAuthenticated),
+            e => e.into(),
+        })?.without_waiting();
+        (client, a.sync_service.gist_id()?, a.sync_service.begin_sync(&a.settings_engine)?)
+    };
+    let mut remote = GistRemote::new(client, gist_id);
+    let merged = run.exchange(&mut remote)?;
+    let mut guard = app.lock()?;
+    let a = &mut *guard;
+    let report = a.sync_service.finish_sync(merged, &mut a.settings_engine)?;
+    if let Some(gist_id) = remote.gist_id() {
+        a.sync_service.set_gist_id(gist_id)?;
+    }
+    a.process_settings_changes();
+    Ok(json!({
+        "pulled": report.pulled,
+        "merged": report.merged,
+        "conflicts": report.conflicts,
+        "pushed": report.pushed,
+        "gist_id": remote.gist_id(),
+    }))
+}
+
+/// Second half of `github.login_device`, run off the request loop: polls for
+/// the token without holding the app lock, stores it with the profile, and
+/// reports every step through `emit` as a `github.login` event.
+pub fn complete_device_login(app: &Mutex<App>, params: &Value, started: &Value, mut emit: impl FnMut(Value)) {
+    let mut report = |progress: DeviceFlowProgress| {
+        let mut event = serde_json::to_value(&progress).unwrap_or_else(|_| json!({}));
+        event["event"] = json!("github.login");
+        emit(event);
+    };
+    let result = serde_json::from_value::<OAuthDeviceCode>(started.clone())
+        .map_err(GitBrowserError::from)
+        .and_then(|code| {
+            let endpoints = app.lock()?.github_integration.endpoints().clone();
+            let client_id = endpoints.device_client_id(params.get("client_id").and_then(|v| v.as_str()))?;
+            let token = GitHubIntegration::wait_for_device_token(
+                &code,
+                || GitHubIntegration::poll_device_token(&endpoints, &client_id, &code.device_code),
+                std::thread::sleep,
+                &mut report,
+            )?;
+            let profile = GitHubIntegration::fetch_profile(&endpoints, &token)?;
+            let mut a = app.lock()?;
+            // The login belongs to the host it started on
+            if a.github_integration.endpoints() != &endpoints {
+                return Err(GitHubError::AuthFailed("GitHub host changed during login".to_string()).into());
+            }
+            a.github_integration.complete_login(&token, &profile)?;
+            Ok(profile)
+        });
+    match result {
+        Ok(profile) => report(DeviceFlowProgress::Authorized(profile)),
+        Err(e) => report(DeviceFlowProgress::Failed { error: e.to_string(), code: e.code().to_string() }),
+    }
+}
+
+/// One background poll of the GitHub notifications inbox. The request runs
+/// without the app lock; mentions and review requests go to `notify` once
+/// each, with the thread's page (none for the summary of an overflow), and
+/// a `github.notifications` event is emitted when anything arrived.
+pub fn poll_github_notifications(
+    app: &Mutex<App>,
+    now: i64,
+    mut notify: impl FnMut(&str, &str, Option<&str>),
+    mut emit: impl FnMut(Value),
+) {
+    let client = match app.lock() {
+        Ok(a) if a.github_notifications.poll_due(now) => match a.github_integration.client() {
+            Ok(client) => client,
+            Err(_) => return,
+        },
+        _ => return,
+    };
+    let response = match GitHubNotifications::fetch(&client) {
+        Ok(response) => response,
+        Err(e) => {
+            if let Ok(mut a) = app.lock() {
+                a.github_notifications.record_failure(&e, now);
+            }
+            return;
+        }
+    };
+    let (alerts, unread) = match app.lock() {
+        Ok(mut a) => match a.github_notifications.record_response(&response, now) {
+            Ok(alerts) => (alerts, a.github_notifications.unread_count().unwrap_or(0)),
+            Err(_) => return,
+        },
+        Err(_) => return,
+    };
+    let texts = alert_texts(&alerts);
+    let summarised = texts.len() < alerts.len();
+    for (i, (title, body)) in texts.iter().enumerate() {
+        let url = alerts.get(i).filter(|_| !(summarised && i + 1 == texts.len())).map(|n| n.url.as_str());
+        notify(title, body, url);
+    }
+    if response.status != 304 {
+        emit(json!({"event": "github.notifications", "unread": unread, "new": alerts}));
+    }
+}
+
+/// Refreshes the `gh` repository cache when it is stale, fetching without
+/// the app lock. Returns whether the cache was replaced.
+pub fn refresh_github_repos(app: &Mutex<App>, now: i64) -> bool {
+    let client = match app.lock() {
+        Ok(a) if a.github_quick_open.cache_is_stale(now) => match a.github_integration.client() {
+            Ok(client) => client,
+            Err(_) => return false,
+        },
+        _ => return false,
+    };
+    let Ok(repos) = GitHubQuickOpen::fetch_repos(&client) else { return false };
+    match app.lock() {
+        Ok(mut a) => a.github_quick_open.store_repos(&repos, now).is_ok(),
+        Err(_) => false,
+    }
 }
 
 /// Dispatch a JSON-RPC method call to the appropriate handler.
 ///
-/// Returns `Ok(Value)` on success or `Err(String)` with an error message.
-pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<Value, String> {
+/// Returns `Ok(Value)` on success or a `GitBrowserError`, whose `code()`
+/// tells frontends what went wrong without parsing the message.
+pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<Value, GitBrowserError> {
+    // Only registered methods are dispatched, so `rpc.discover` lists them all
+    if rpc_methods::find(method).is_none() {
+        return Err(GitBrowserError::MethodNotFound(method.to_string()));
+    }
     match method {
         // ─── Bookmarks ───
         "bookmark.add" => {
-            let url = params.get("url").and_then(|v| v.as_str()).ok_or("missing url")?;
-            let title = params.get("title").and_then(|v| v.as_str()).ok_or("missing title")?;
+            let url = str_param(params, "url")?;
+            let title = str_param(params, "title")?;
             if !url.starts_with("http://") && !url.starts_with("https://") && !url.starts_with("gb://") {
-                return Err("invalid url: must start with http://, https://, or gb://".to_string());
+                return Err(invalid("invalid url: must start with http://, https://, or gb://"));
             }
             let folder = params.get("folder_id").and_then(|v| v.as_str());
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             let conn = a.db.connection();
             let mut mgr = BookmarkManager::new(conn);
-            let bm_id = mgr.add_bookmark(url, title, folder).map_err(|e| e.to_string())?;
+            let bm_id = mgr.add_bookmark(url, title, folder)?;
             Ok(json!({"id": bm_id, "url": url, "title": title}))
         }
         "bookmark.list" => {
             let folder = params.get("folder_id").and_then(|v| v.as_str());
             let limit = params.get("limit").and_then(|v| v.as_i64()).unwrap_or(100);
             let offset = params.get("offset").and_then(|v| v.as_i64()).unwrap_or(0);
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             let conn = a.db.connection();
             let mgr = BookmarkManager::new(conn);
-            let (bms, total) = mgr.list_bookmarks_paginated(folder, limit, offset).map_err(|e| e.to_string())?;
-            let arr: Vec<Value> = bms.iter().map(|b| json!({"id":b.id,"url":b.url,"title":b.title,"folder_id":b.folder_id})).collect();
+            let (bms, total) = mgr.list_bookmarks_paginated(folder, limit, offset)?;
+            let icons = favicons_for(&a, &bms.iter().map(|b| b.url.as_str()).collect::<Vec<_>>());
+            let arr: Vec<Value> = bms.iter().map(|b| json!({
+                "id": b.id, "url": b.url, "title": b.title, "folder_id": b.folder_id,
+                "favicon": favicon_json(&icons, &b.url),
+            })).collect();
             Ok(json!({"items": arr, "total": total, "limit": limit, "offset": offset}))
         }
         "bookmark.search" => {
-            let query = params.get("query").and_then(|v| v.as_str()).ok_or("missing query")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let query = str_param(params, "query")?;
+            let a = app.lock()?;
             let conn = a.db.connection();
             let mgr = BookmarkManager::new(conn);
-            let bms = mgr.search_bookmarks(query).map_err(|e| e.to_string())?;
-            let arr: Vec<Value> = bms.iter().map(|b| json!({"id":b.id,"url":b.url,"title":b.title})).collect();
+            let bms = mgr.search_bookmarks(query)?;
+            let icons = favicons_for(&a, &bms.iter().map(|b| b.url.as_str()).collect::<Vec<_>>());
+            let arr: Vec<Value> = bms.iter()
+                .map(|b| json!({"id":b.id,"url":b.url,"title":b.title,"favicon":favicon_json(&icons, &b.url)}))
+                .collect();
             Ok(json!(arr))
         }
         "bookmark.delete" => {
-            let id = params.get("id").and_then(|v| v.as_str()).ok_or("missing id")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let id = str_param(params, "id")?;
+            let a = app.lock()?;
             let conn = a.db.connection();
             let mut mgr = BookmarkManager::new(conn);
-            mgr.remove_bookmark(id).map_err(|e| e.to_string())?;
+            mgr.remove_bookmark(id)?;
             Ok(json!({"ok": true}))
         }
 
         // ─── History ───
         "history.record" => {
-            let url = params.get("url").and_then(|v| v.as_str()).ok_or("missing url")?;
-            let title = params.get("title").and_then(|v| v.as_str()).ok_or("missing title")?;
+            let url = str_param(params, "url")?;
+            let title = str_param(params, "title")?;
             if !url.starts_with("http://") && !url.starts_with("https://") {
-                return Err("invalid url: must start with http:// or https://".to_string());
+                return Err(invalid("invalid url: must start with http:// or https://"));
             }
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             let conn = a.db.connection();
             let mut mgr = HistoryManager::new(conn);
-            mgr.record_visit(url, title).map_err(|e| e.to_string())?;
+            mgr.record_visit(url, title)?;
             Ok(json!({"ok": true}))
         }
         "history.search" => {
-            let query = params.get("query").and_then(|v| v.as_str()).ok_or("missing query")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
-            let conn = a.db.connection();
-            let mgr = HistoryManager::new(conn);
-            let entries = mgr.search_history(query).map_err(|e| e.to_string())?;
-            let arr: Vec<Value> = entries.iter().map(|h| json!({"id":h.id,"url":h.url,"title":h.title,"visit_count":h.visit_count,"visit_time":h.visit_time * 1000})).collect();
+            let query = str_param(params, "query")?.to_string();
+            // Searched on the database thread; the app is only locked to format the results
+            let worker = app.lock()?.db_worker.clone();
+            let entries = worker
+                .run(move |conn| HistoryManager::new(conn).search_history(&query))
+                .wait()??;
+            let a = app.lock()?;
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+            let icons = favicons_for(&a, &entries.iter().map(|h| h.url.as_str()).collect::<Vec<_>>());
+            let arr: Vec<Value> = entries.iter().map(|h| json!({"id":h.id,"url":h.url,"title":h.title,"visit_count":h.visit_count,"visit_time":h.visit_time * 1000,"visited":a.localization_engine.format_relative_time(h.visit_time, now),"favicon":favicon_json(&icons, &h.url)})).collect();
             Ok(json!(arr))
         }
         "history.recent" => {
             let limit = params.get("limit").and_then(|v| v.as_i64()).unwrap_or(100);
             let offset = params.get("offset").and_then(|v| v.as_i64()).unwrap_or(0);
-            let date = params.get("date").and_then(|v| v.as_str());
-            let a = app.lock().map_err(|e| e.to_string())?;
-            let conn = a.db.connection();
-            let mgr = HistoryManager::new(conn);
-            let (entries, total) = mgr.list_history_paginated(date, limit, offset).map_err(|e| e.to_string())?;
-            let arr: Vec<Value> = entries.iter().map(|h| json!({"id":h.id,"url":h.url,"title":h.title,"visit_count":h.visit_count,"visit_time":h.visit_time * 1000})).collect();
+            let date = params.get("date").and_then(|v| v.as_str()).map(String::from);
+            let worker = app.lock()?.db_worker.clone();
+            let (entries, total) = worker
+                .run(move |conn| HistoryManager::new(conn).list_history_paginated(date.as_deref(), limit, offset))
+                .wait()??;
+            let a = app.lock()?;
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+            let icons = favicons_for(&a, &entries.iter().map(|h| h.url.as_str()).collect::<Vec<_>>());
+            let arr: Vec<Value> = entries.iter().map(|h| json!({"id":h.id,"url":h.url,"title":h.title,"visit_count":h.visit_count,"visit_time":h.visit_time * 1000,"visited":a.localization_engine.format_relative_time(h.visit_time, now),"favicon":favicon_json(&icons, &h.url)})).collect();
             Ok(json!({"items": arr, "total": total, "limit": limit, "offset": offset}))
         }
         "history.delete" => {
-            let id = params.get("id").and_then(|v| v.as_str()).ok_or("missing id")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let id = str_param(params, "id")?;
+            let a = app.lock()?;
             let conn = a.db.connection();
             let mut mgr = HistoryManager::new(conn);
-            mgr.delete_entry(id).map_err(|e| e.to_string())?;
+            mgr.delete_entry(id)?;
             Ok(json!({"ok": true}))
         }
         "history.clear" => {
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             let conn = a.db.connection();
             let mut mgr = HistoryManager::new(conn);
-            mgr.clear_all().map_err(|e| e.to_string())?;
+            mgr.clear_all()?;
             Ok(json!({"ok": true}))
         }
 
+        // ─── Reader mode ───
+        "reader.is_article" => {
+            let html = str_param(params, "html")?;
+            let url = str_param(params, "url")?;
+            Ok(json!({"is_article": ReaderMode::new().is_article_page(html, url)}))
+        }
+        "reader.extract" => {
+            let content = reader_content(params)?;
+            Ok(json!({"content": content}))
+        }
+        "reader.render" => {
+            // `settings` overrides the stored reader settings for this document only
+            let settings: ReaderSettings = match params.get("settings") {
+                Some(s) => serde_json::from_value(s.clone()).map_err(|e| invalid(format!("invalid settings: {}", e)))?,
+                None => app.lock()?.reader_mode.get_settings().clone(),
+            };
+            let content = reader_content(params)?;
+            let html = ReaderMode::new().format_for_display(&content, &settings);
+            Ok(json!({"html": html, "title": content.title, "page_count": content.page_count}))
+        }
+
+        // ─── Reading list ───
+        "reading_list.add" => {
+            let url = str_param(params, "url")?;
+            let html = str_param(params, "html")?;
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(invalid("invalid url: must start with http:// or https://"));
+            }
+            // Optional { "<img src>": "data:image/...;base64,..." } map for offline images
+            let images: Option<HashMap<String, String>> = params.get("images")
+                .and_then(|v| v.as_object())
+                .map(|m| m.iter().filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string()))).collect());
+            // Paginated articles are stitched before saving; fetching happens
+            // outside the App lock so other RPC calls aren't blocked meanwhile.
+            let max_pages = params.get("max_pages").and_then(|v| v.as_u64()).unwrap_or(MAX_STITCHED_PAGES as u64) as usize;
+            let content = ReaderMode::new().extract_stitched(html, url, max_pages)?;
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mut mgr = ReadingListManager::new(conn);
+            let id = mgr.save_article(url, &content, images.as_ref())?;
+            Ok(json!({"id": id, "url": url, "title": content.title, "page_count": content.page_count}))
+        }
+        "reading_list.list" => {
+            let unread_only = params.get("unread_only").and_then(|v| v.as_bool()).unwrap_or(false);
+            let limit = params.get("limit").and_then(|v| v.as_i64()).unwrap_or(100);
+            let offset = params.get("offset").and_then(|v| v.as_i64()).unwrap_or(0);
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mgr = ReadingListManager::new(conn);
+            let (items, total) = mgr.list_items(unread_only, limit, offset)?;
+            let arr: Vec<Value> = items.iter().map(|i| json!({
+                "id": i.id, "url": i.url, "title": i.title, "site_name": i.site_name,
+                "estimated_read_time_minutes": i.estimated_read_time_minutes,
+                "saved_at": i.saved_at * 1000, "read_at": i.read_at.map(|t| t * 1000),
+            })).collect();
+            Ok(json!({"items": arr, "total": total, "limit": limit, "offset": offset}))
+        }
+        "reading_list.get" => {
+            let id = str_param(params, "id")?;
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mgr = ReadingListManager::new(conn);
+            let item = mgr.get_item(id)?;
+            Ok(serde_json::to_value(item)?)
+        }
+        "reading_list.mark_read" => {
+            let id = str_param(params, "id")?;
+            let read = params.get("read").and_then(|v| v.as_bool()).unwrap_or(true);
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mut mgr = ReadingListManager::new(conn);
+            mgr.mark_read(id, read)?;
+            Ok(json!({"ok": true}))
+        }
+        "reading_list.delete" => {
+            let id = str_param(params, "id")?;
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mut mgr = ReadingListManager::new(conn);
+            mgr.remove_item(id)?;
+            Ok(json!({"ok": true}))
+        }
+
+        // ─── Annotations ───
+        "annotations.add" => {
+            let url = str_param(params, "url")?;
+            let text = str_param(params, "text")?;
+            let prefix = params.get("prefix").and_then(|v| v.as_str()).unwrap_or("");
+            let suffix = params.get("suffix").and_then(|v| v.as_str()).unwrap_or("");
+            let note = params.get("note").and_then(|v| v.as_str());
+            let color = params.get("color").and_then(|v| v.as_str());
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mut mgr = AnnotationManager::new(conn);
+            let id = mgr.add_annotation(url, text, prefix, suffix, note, color)?;
+            Ok(json!({"id": id}))
+        }
+        "annotations.list" => {
+            let url = str_param(params, "url")?;
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mgr = AnnotationManager::new(conn);
+            let items = mgr.list_annotations(url)?;
+            Ok(serde_json::to_value(items)?)
+        }
+        "annotations.update" => {
+            let id = str_param(params, "id")?;
+            let note = params.get("note").and_then(|v| v.as_str());
+            let color = params.get("color").and_then(|v| v.as_str());
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mut mgr = AnnotationManager::new(conn);
+            mgr.update_annotation(id, note, color)?;
+            Ok(json!({"ok": true}))
+        }
+        "annotations.delete" => {
+            let id = str_param(params, "id")?;
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mut mgr = AnnotationManager::new(conn);
+            mgr.remove_annotation(id)?;
+            Ok(json!({"ok": true}))
+        }
+        "annotations.apply" => {
+            // Re-apply stored highlights to freshly extracted reader HTML
+            let url = str_param(params, "url")?;
+            let html = str_param(params, "html")?;
+            let a = app.lock()?;
+            let conn = a.db.connection();
+            let mgr = AnnotationManager::new(conn);
+            let items = mgr.list_annotations(url)?;
+            Ok(json!({"html": AnnotationManager::apply_annotations(html, &items), "count": items.len()}))
+        }
+
         // ─── Settings ───
+        "settings.schema" => {
+            let schema = crate::services::settings_engine::settings_schema();
+            Ok(json!({"keys": schema}))
+        }
+        "settings.status" => {
+            let a = app.lock()?;
+            Ok(json!({
+                "config_path": a.settings_engine.get_config_path(),
+                "profile": a.settings_engine.active_profile(),
+                "recovery_notice": a.settings_engine.recovery_notice(),
+            }))
+        }
+        "settings.export" => {
+            let path = str_param(params, "path")?;
+            let a = app.lock()?;
+            let stripped = a.settings_engine.export_to(path)?;
+            Ok(json!({"ok": true, "stripped": stripped}))
+        }
+        "settings.profiles.list" => {
+            let a = app.lock()?;
+            Ok(json!({
+                "profiles": a.settings_engine.list_profiles(),
+                "active": a.settings_engine.active_profile(),
+            }))
+        }
+        "settings.profiles.create" => {
+            let name = str_param(params, "name")?;
+            let clone_from = params.get("clone_from").and_then(|v| v.as_str());
+            let mut a = app.lock()?;
+            a.settings_engine.create_profile(name, clone_from)?;
+            Ok(json!({"ok": true}))
+        }
+        "settings.profiles.switch" => {
+            let name = str_param(params, "name")?;
+            let mut a = app.lock()?;
+            let changes = a.switch_settings_profile(name)?;
+            let changed: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+            Ok(json!({"ok": true, "active": a.settings_engine.active_profile(), "changed": changed}))
+        }
+        "settings.profiles.delete" => {
+            let name = str_param(params, "name")?;
+            let mut a = app.lock()?;
+            a.settings_engine.delete_profile(name)?;
+            Ok(json!({"ok": true}))
+        }
+
+        // ─── User profiles (separate data; opened with --profile) ───
+        "profiles.list" => {
+            let a = app.lock()?;
+            let profiles = a.profile_manager.list_profiles()?;
+            Ok(json!({"profiles": profiles, "active": a.profile().map(|p| p.name.as_str())}))
+        }
+        "profiles.create" => {
+            let name = str_param(params, "name")?;
+            let a = app.lock()?;
+            let profile = a.profile_manager.create_profile(name)?;
+            Ok(json!({"profile": profile}))
+        }
+        "profiles.delete" => {
+            let name = str_param(params, "name")?;
+            let confirm = params.get("confirm").and_then(|v| v.as_bool()).unwrap_or(false);
+            let a = app.lock()?;
+            if !confirm {
+                // All of the profile's data goes; the caller asks the user first
+                let profile = a.profile_manager.get_profile(name)?;
+                return Ok(json!({"pending": true, "profile": profile}));
+            }
+            a.profile_manager.delete_profile(name)?;
+            Ok(json!({"ok": true}))
+        }
         "settings.get" => {
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             let settings = a.settings_engine.get_settings();
-            let json_val = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+            let json_val = serde_json::to_value(settings)?;
             Ok(json_val)
         }
         "settings.set" => {
-            let key = params.get("key").and_then(|v| v.as_str()).ok_or("missing key")?;
-            let value = params.get("value").cloned().ok_or("missing value")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.settings_engine.set_value(key, value).map_err(|e| e.to_string())?;
-            if key == "general.language" || key == "language" {
-                if let Some(lang) = params.get("value").and_then(|v| v.as_str()) {
-                    let _ = a.localization_engine.set_locale(lang);
+            let key = str_param(params, "key")?;
+            let value = params.get("value").cloned().ok_or_else(|| missing("value"))?;
+            let mut a = app.lock()?;
+            a.settings_engine.set_value(key, value)?;
+            let changes = a.process_settings_changes();
+            let changed: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+            Ok(json!({"ok": true, "changed": changed}))
+        }
+
+        // ─── Search Engines ───
+        "search.list" => {
+            let a = app.lock()?;
+            let search = &a.settings_engine.get_settings().search;
+            let registry = SearchEngineRegistry::new(search.clone());
+            let default = registry.default_engine().map(|e| e.id.clone());
+            Ok(json!({"engines": registry.list_engines(), "default": default}))
+        }
+        "search.add" | "search.update" => {
+            let engine: SearchEngine = params
+                .get("engine")
+                .cloned()
+                .ok_or_else(|| missing("engine"))
+                .and_then(|v| serde_json::from_value(v).map_err(|_| invalid("invalid engine")))?;
+            let is_add = method == "search.add";
+            update_search_engines(app, |r| {
+                if is_add { r.add_engine(engine) } else { r.update_engine(engine) }
+            })
+        }
+        "search.remove" => {
+            let id = str_param(params, "id")?;
+            update_search_engines(app, |r| r.remove_engine(id))
+        }
+        "search.set_default" => {
+            let id = str_param(params, "id")?;
+            update_search_engines(app, |r| r.set_default(id))
+        }
+        "search.resolve" => {
+            let query = str_param(params, "query")?;
+            let a = app.lock()?;
+            if let Some(url) = a.resolve_gh_command(query) {
+                return Ok(json!({"url": url, "suggest_url": null}));
+            }
+            let registry = SearchEngineRegistry::new(a.settings_engine.get_settings().search.clone());
+            Ok(json!({
+                "url": registry.search_url(query),
+                "suggest_url": registry.suggestion_url(query),
+            }))
+        }
+
+        // ─── Shortcuts ───
+        "shortcuts.list" => {
+            let a = app.lock()?;
+            let defaults = a.shortcut_manager.get_default_shortcuts();
+            let mut shortcuts: Vec<Value> = a.shortcut_manager.list_shortcuts().iter()
+                .map(|(action, keys)| shortcut_json(action, Some(keys), &defaults))
+                .collect();
+            shortcuts.sort_by(|x, y| x["action"].as_str().cmp(&y["action"].as_str()));
+            Ok(json!({"shortcuts": shortcuts}))
+        }
+        "shortcuts.get" => {
+            let action = str_param(params, "action")?;
+            let a = app.lock()?;
+            let defaults = a.shortcut_manager.get_default_shortcuts();
+            let keys = a.shortcut_manager.get_shortcut(action);
+            if keys.is_none() && !defaults.contains_key(action) {
+                return Err(ShortcutError::NotFound(action.to_string()).into());
+            }
+            Ok(shortcut_json(action, keys, &defaults))
+        }
+        "shortcuts.set" => {
+            let action = str_param(params, "action")?;
+            let keys = str_param(params, "keys")?;
+            update_shortcuts(app, |m| m.register_shortcut(action, keys))
+        }
+        "shortcuts.reset" => {
+            // Resets one action when `action` is given, otherwise every binding
+            match params.get("action").and_then(|v| v.as_str()) {
+                Some(action) => update_shortcuts(app, |m| {
+                    let defaults = m.get_default_shortcuts();
+                    let keys = defaults.get(action).ok_or_else(|| ShortcutError::NotFound(action.to_string()))?;
+                    m.register_shortcut(action, keys)
+                }),
+                None => update_shortcuts(app, |m| m.reset_to_defaults()),
+            }
+        }
+        "shortcuts.has_conflict" => {
+            // `action` is the binding being edited; its own keys never conflict
+            let keys = str_param(params, "keys")?;
+            let action = params.get("action").and_then(|v| v.as_str());
+            let a = app.lock()?;
+            let conflict = a.shortcut_manager.has_conflict(keys, action);
+            Ok(json!({"conflict": conflict.is_some(), "action": conflict}))
+        }
+
+        // ─── Themes ───
+        "theme.get" => {
+            let a = app.lock()?;
+            Ok(theme_json(&a))
+        }
+        "theme.set" => {
+            let mode: ThemeMode = serde_json::from_value(params.get("mode").cloned().ok_or_else(|| missing("mode"))?)
+                .map_err(|_| invalid("invalid mode"))?;
+            let mut a = app.lock()?;
+            a.settings_engine.set_value("appearance.theme", json!(mode))?;
+            a.process_settings_changes();
+            Ok(theme_json(&a))
+        }
+        "theme.css_variables" => {
+            let a = app.lock()?;
+            Ok(json!({"variables": a.theme_engine.get_css_variables()}))
+        }
+        "theme.set_accent" => {
+            // A hex color, or "system" to follow the OS accent
+            let color = str_param(params, "color")?;
+            if !color.eq_ignore_ascii_case(SYSTEM_ACCENT) && !is_valid_hex_color(color) {
+                return Err(invalid(format!("invalid color: {}", color)));
+            }
+            let mut a = app.lock()?;
+            a.settings_engine.set_value("appearance.accent_color", json!(color))?;
+            a.process_settings_changes();
+            Ok(theme_json(&a))
+        }
+        "theme.install" => {
+            // Accepts either an inline theme object or a path to a theme file
+            let theme: CustomTheme = if let Some(theme) = params.get("theme") {
+                serde_json::from_value(theme.clone()).map_err(|e| invalid(format!("invalid theme: {}", e)))?
+            } else {
+                let path = params.get("path").and_then(|v| v.as_str()).ok_or_else(|| invalid("missing theme or path"))?;
+                let content = std::fs::read_to_string(path)?;
+                serde_json::from_str(&content).map_err(|e| invalid(format!("invalid theme: {}", e)))?
+            };
+            let mut a = app.lock()?;
+            let installed = a.theme_engine.install_theme(theme)?;
+            Ok(json!({"theme": installed}))
+        }
+        "theme.list" => {
+            let a = app.lock()?;
+            let active = a.theme_engine.get_custom_theme().map(|t| t.id.clone());
+            Ok(json!({"themes": a.theme_engine.list_themes(), "active": active}))
+        }
+        "theme.apply" => {
+            // `id: null` (or omitted) clears the custom theme
+            let id = params.get("id").and_then(|v| v.as_str());
+            let mut a = app.lock()?;
+            if let Some(id) = id {
+                if !a.theme_engine.list_themes().iter().any(|t| t.id == id) {
+                    return Err(ThemeError::NotFound(id.to_string()).into());
                 }
             }
-            let _ = a.settings_engine.save();
+            a.settings_engine
+                .set_value("appearance.custom_theme", json!(id))?;
+            a.process_settings_changes();
+            Ok(json!({"ok": true, "variables": a.theme_engine.get_css_variables()}))
+        }
+        "theme.remove" => {
+            let id = str_param(params, "id")?;
+            let mut a = app.lock()?;
+            a.theme_engine.remove_theme(id)?;
+            if a.settings_engine.get_settings().appearance.custom_theme.as_deref() == Some(id) {
+                a.settings_engine
+                    .set_value("appearance.custom_theme", Value::Null)?;
+                a.process_settings_changes();
+            }
+            Ok(json!({"ok": true}))
+        }
+
+        "theme.force_dark" => {
+            let url = str_param(params, "url")?;
+            let a = app.lock()?;
+            let enabled = a.force_dark_for(url);
+            Ok(json!({"enabled": enabled, "script": a.theme_engine.force_dark_script(enabled)}))
+        }
+        "theme.force_dark.toggle" => {
+            let url = str_param(params, "url")?;
+            let mut a = app.lock()?;
+            let enabled = a.toggle_force_dark(url)?;
+            Ok(json!({"enabled": enabled, "script": a.theme_engine.force_dark_script(enabled)}))
+        }
+
+        "theme.schedule" => {
+            let a = app.lock()?;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            Ok(json!({
+                "settings": a.theme_scheduler.get_settings(),
+                "mode": a.theme_scheduler.scheduled_mode(now),
+                "next_transition": a.theme_scheduler.next_transition(now),
+            }))
+        }
+
+        // ─── Site Settings ───
+        "site_settings.get" => {
+            let site = str_param(params, "site")?;
+            let a = app.lock()?;
+            let mgr = SiteSettingsManager::new(a.db.connection());
+            let settings = mgr.get_site_settings(site)?;
+            Ok(json!({"settings": settings}))
+        }
+        "site_settings.set" => {
+            let site = str_param(params, "site")?;
+            let key = str_param(params, "key")?;
+            let value = params.get("value").ok_or_else(|| missing("value"))?;
+            let mut a = app.lock()?;
+            SiteSettingsManager::new(a.db.connection()).set_setting(site, key, value)?;
+            if key == SHIELDS_KEY {
+                a.refresh_shield_exceptions();
+            }
             Ok(json!({"ok": true}))
         }
+        "site_settings.remove" => {
+            let site = str_param(params, "site")?;
+            let mut a = app.lock()?;
+            let mut mgr = SiteSettingsManager::new(a.db.connection());
+            let removed = match params.get("key").and_then(|v| v.as_str()) {
+                Some(key) => mgr.remove_setting(site, key)?,
+                None => {
+                    mgr.clear_site(site)?;
+                    true
+                }
+            };
+            a.refresh_shield_exceptions();
+            Ok(json!({"removed": removed}))
+        }
+        "site_settings.user_agent" => {
+            let url = str_param(params, "url")?;
+            let a = app.lock()?;
+            Ok(json!({"user_agent": a.user_agent_for(url)}))
+        }
+        "site_settings.user_agent.set" => {
+            // Without a user_agent the site goes back to the global one
+            let url = str_param(params, "url")?;
+            let user_agent = params.get("user_agent").and_then(|v| v.as_str());
+            let mut a = app.lock()?;
+            let site = a.set_site_user_agent(url, user_agent)?;
+            Ok(json!({"site": site, "user_agent": a.user_agent_for(url)}))
+        }
 
         // ─── Localization ───
         "i18n.t" => {
-            let key = params.get("key").and_then(|v| v.as_str()).ok_or("missing key")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let key = str_param(params, "key")?;
+            let a = app.lock()?;
             let text = a.localization_engine.t(key, None);
             Ok(json!({"text": text}))
         }
         "i18n.locale" => {
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
+            let locale = a.localization_engine.get_locale();
+            let direction = a.localization_engine.get_direction();
+            Ok(json!({"locale": locale, "direction": direction}))
+        }
+        "i18n.locales" => {
+            let a = app.lock()?;
             let locale = a.localization_engine.get_locale();
-            Ok(json!({"locale": locale}))
+            Ok(json!({
+                "locales": a.localization_engine.get_available_locales(),
+                "fallback": a.localization_engine.fallback_chain(locale),
+                "direction": a.localization_engine.get_direction(),
+            }))
+        }
+        "i18n.stats" => {
+            let a = app.lock()?;
+            let stats: Vec<Value> = a.localization_engine.locale_stats().into_iter().map(|s| {
+                let completion = s.completion();
+                let mut v = serde_json::to_value(s).unwrap_or_default();
+                v["completion"] = json!(completion);
+                v
+            }).collect();
+            Ok(json!({"stats": stats}))
+        }
+        "i18n.reload" => {
+            let mut a = app.lock()?;
+            a.localization_engine.reload()?;
+            let direction = a.localization_engine.get_direction();
+            a.theme_engine.set_direction(direction);
+            Ok(json!({
+                "locale": a.localization_engine.get_locale(),
+                "locales": a.localization_engine.get_available_locales(),
+            }))
+        }
+        "i18n.debug" => {
+            let enabled = bool_param(params, "enabled")?;
+            let mut a = app.lock()?;
+            a.localization_engine.set_debug_mode(enabled);
+            Ok(json!({"ok": true, "enabled": enabled}))
+        }
+        "i18n.missing" => {
+            let clear = params.get("clear").and_then(|v| v.as_bool()).unwrap_or(false);
+            let mut a = app.lock()?;
+            let missing = a.localization_engine.missing_keys();
+            if clear {
+                a.localization_engine.clear_missing_keys();
+            }
+            Ok(json!({"debug": a.localization_engine.is_debug_mode(), "missing": missing}))
         }
 
         // ─── Session ───
         "session.save" => {
-            let tabs_val = params.get("tabs").ok_or("missing tabs")?;
+            let tabs_val = params.get("tabs").ok_or_else(|| missing("tabs"))?;
             let session_path = if let Ok(dir) = std::env::var("GITBROWSER_DATA_DIR") {
                 std::path::PathBuf::from(dir).join("session.json")
             } else if let Ok(exe) = std::env::current_exe() {
@@ -171,8 +1207,25 @@ pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<V
             } else {
                 std::path::PathBuf::from("session.json")
             };
-            let data = serde_json::to_string(tabs_val).map_err(|e| e.to_string())?;
-            std::fs::write(&session_path, data).map_err(|e| e.to_string())?;
+            let data = serde_json::to_string(tabs_val)?;
+            std::fs::write(&session_path, data)?;
+            // Also kept as the session offered after a crash
+            let tabs: Vec<SessionTab> = tabs_val.as_array().map(|tabs| tabs.iter()
+                .filter_map(|t| {
+                    let url = t.get("url").and_then(|v| v.as_str())?;
+                    Some(SessionTab {
+                        id: t.get("id").and_then(|v| v.as_str()).map(String::from)
+                            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                        url: url.to_string(),
+                        title: t.get("title").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                        pinned: t.get("pinned").and_then(|v| v.as_bool()).unwrap_or(false),
+                        scroll_position: Default::default(),
+                    })
+                })
+                .collect()).unwrap_or_default();
+            let active = params.get("active_tab_id").and_then(|v| v.as_str()).map(String::from);
+            let a = app.lock()?;
+            a.store_session(tabs, active)?;
             Ok(json!({"ok": true}))
         }
         "session.restore" => {
@@ -192,72 +1245,230 @@ pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<V
             }
         }
 
-        // ─── Password Manager ───
-        "password.unlock" => {
-            let master = params.get("master_password").and_then(|v| v.as_str()).ok_or("missing master_password")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            let ok = a.password_manager.unlock(master).map_err(|e| e.to_string())?;
-            if ok {
-                if let Some(master_key) = a.password_manager.get_derived_key() {
-                    let _ = a.github_integration.rekey_with_master(&master_key);
-                    let _ = a.ai_assistant.rekey_with_master(&master_key);
+        // ─── Database backups ───
+        "db.backup" => {
+            let a = app.lock()?;
+            let backup = a.db_backups.backup_now(&a.db, now_millis())?;
+            Ok(json!({"backup": backup}))
+        }
+        "db.backups" => {
+            let a = app.lock()?;
+            let backups = a.db_backups.list()?;
+            Ok(json!(backups))
+        }
+        "db.restore" => {
+            let name = str_param(params, "name")?;
+            let confirm = params.get("confirm").and_then(|v| v.as_bool()).unwrap_or(false);
+            let a = app.lock()?;
+            if !confirm {
+                // The caller asks the user and repeats the call with confirm: true
+                let backup = a.db_backups.find(name)?;
+                return Ok(json!({"pending": true, "backup": backup}));
+            }
+            let safety = a.db_backups.restore(&a.db, name, now_millis())?;
+            Ok(json!({"ok": true, "safety_backup": safety, "restart_required": true}))
+        }
+        "db.stats" => {
+            // Profile size for the settings page: database, its log and the backups
+            let a = app.lock()?;
+            let stats = maintenance::stats(a.db.connection())?;
+            let last = maintenance::last_run(a.db.connection())?;
+            let backups_size: u64 = a.db_backups.list()?.iter().map(|b| b.size).sum();
+            Ok(json!({
+                "stats": stats,
+                "backups_size": backups_size,
+                "total": stats.size + stats.wal_size + backups_size,
+                "last_maintenance": last,
+            }))
+        }
+        "db.compact" => {
+            // VACUUM rewrites the whole file; done on the database thread
+            let worker = app.lock()?.db_worker.clone();
+            let (before, after) = worker
+                .run(|conn| Ok::<_, rusqlite::Error>((maintenance::stats(conn)?, maintenance::compact(conn)?)))
+                .wait()??;
+            Ok(json!({"ok": true, "stats": after, "reclaimed": (before.size + before.wal_size).saturating_sub(after.size + after.wal_size)}))
+        }
+
+        // ─── Crash logs ───
+        "crash.logs" => {
+            let a = app.lock()?;
+            let logs = a.crash_recovery.get_crash_logs()?;
+            Ok(json!(logs))
+        }
+        "crash.list" => {
+            let unrecovered_only = params.get("unrecovered_only").and_then(|v| v.as_bool()).unwrap_or(false);
+            let a = app.lock()?;
+            let crashes: Vec<CrashLogEntry> = a.crash_recovery.get_crash_logs()?
+                .into_iter()
+                .filter(|log| !unrecovered_only || !log.recovered)
+                .collect();
+            Ok(json!({
+                "crashes": crashes,
+                "unrecovered": a.crash_recovery.has_unrecovered_crash(),
+                "restore_pending": a.crash_restore_prompt().is_some(),
+            }))
+        }
+        "crash.mark_recovered" => {
+            // Without `ids` every crash is recovered, and a pending restore prompt is declined
+            let ids: Option<Vec<String>> = match params.get("ids") {
+                Some(v) => Some(serde_json::from_value(v.clone()).map_err(|_| invalid("invalid ids"))?),
+                None => None,
+            };
+            let mut a = app.lock()?;
+            let result = match ids {
+                Some(ids) => a.crash_recovery.mark_logs_recovered(&ids),
+                None if a.crash_restore_prompt().is_some() => a.resolve_crash_restore(false).map(drop),
+                None => a.crash_recovery.mark_crash_recovered(),
+            };
+            result?;
+            Ok(json!({"ok": true, "unrecovered": a.crash_recovery.has_unrecovered_crash()}))
+        }
+        "crash.clear" => {
+            let mut a = app.lock()?;
+            let removed = a.crash_recovery.clear_crash_logs()?;
+            Ok(json!({"removed": removed}))
+        }
+        "crash.renderer" => {
+            // A tab's renderer died (Electron's `render-process-gone`); the frontend shows the crashed page
+            let reason = str_param(params, "reason")?;
+            let url = params.get("url").and_then(|v| v.as_str()).map(String::from);
+            let entry = crate::services::crash_recovery::crash_entry("renderer_crash", reason, None, url);
+            let mut a = app.lock()?;
+            a.crash_recovery.log_crash(entry)?;
+            Ok(json!({"ok": true}))
+        }
+        "crash.restore_prompt" => {
+            // Pending after a crash until answered with crash.restore
+            let a = app.lock()?;
+            Ok(a.crash_restore_prompt().unwrap_or(Value::Null))
+        }
+        "crash.restore" => {
+            let restore = bool_param(params, "restore")?;
+            let mut a = app.lock()?;
+            let session = a.resolve_crash_restore(restore)?;
+            let tabs: Vec<Value> = session.iter().flat_map(|s| s.tabs.iter())
+                .map(|t| json!({"url": t.url, "title": t.title, "pinned": t.pinned}))
+                .collect();
+            let active_index = session.as_ref().and_then(|s| s.tabs.iter().position(|t| Some(&t.id) == s.active_tab_id.as_ref()));
+            Ok(json!({"restored": restore, "tabs": tabs, "active_index": active_index}))
+        }
+        "crash.upload" => {
+            // Only with consent; the network request runs outside the app lock
+            let ids: Option<Vec<String>> = params.get("ids").and_then(|v| serde_json::from_value(v.clone()).ok());
+            let (reports, target) = {
+                let a = app.lock()?;
+                let privacy = a.settings_engine.get_settings().privacy.clone();
+                if !privacy.telemetry_consent {
+                    return Err(CrashError::ConsentRequired.into());
+                }
+                let reports: Vec<CrashReport> = a.crash_recovery.pending_uploads()?
+                    .iter()
+                    .filter(|log| ids.as_ref().is_none_or(|ids| ids.contains(&log.id)))
+                    .map(|log| sanitize_report(log, privacy.crash_report_urls))
+                    .collect();
+                (reports, UploadTarget::from_settings(&privacy))
+            };
+            match target {
+                _ if reports.is_empty() => Ok(json!({"uploaded": 0, "reports": []})),
+                UploadTarget::IssueDraft => Ok(json!({
+                    "uploaded": 0,
+                    "issue_url": issue_draft_url(&reports),
+                    "reports": reports,
+                })),
+                UploadTarget::Endpoint(endpoint) => {
+                    upload(&endpoint, &reports)?;
+                    let sent: Vec<String> = reports.iter().map(|r| r.id.clone()).collect();
+                    let mut a = app.lock()?;
+                    a.crash_recovery.mark_uploaded(&sent)?;
+                    Ok(json!({"uploaded": sent.len(), "reports": reports}))
                 }
             }
+        }
+
+        // ─── Password Manager ───
+        "password.unlock" => {
+            let master = str_param(params, "master_password")?;
+            let mut a = app.lock()?;
+            let ok = a.unlock_vault(master)?;
+            Ok(json!({"ok": ok}))
+        }
+        "password.quick_unlock" => {
+            let mut a = app.lock()?;
+            let ok = a.quick_unlock_vault()?;
             Ok(json!({"ok": ok}))
         }
+        "password.set_quick_unlock" => {
+            let enabled = bool_param(params, "enabled")?;
+            let a = app.lock()?;
+            if enabled {
+                a.password_manager.enable_quick_unlock()?;
+            } else {
+                a.password_manager.disable_quick_unlock()?;
+            }
+            Ok(json!({"ok": true}))
+        }
         "password.lock" => {
-            let mut a = app.lock().map_err(|e| e.to_string())?;
+            let mut a = app.lock()?;
             a.password_manager.lock();
             Ok(json!({"ok": true}))
         }
         "password.is_unlocked" => {
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             Ok(json!({"unlocked": a.password_manager.is_unlocked()}))
         }
         "password.list" => {
             let url = params.get("url").and_then(|v| v.as_str()).unwrap_or("");
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             let creds = if url.is_empty() {
-                a.password_manager.list_all_credentials().map_err(|e| e.to_string())?
+                a.password_manager.list_all_credentials()?
             } else {
-                a.password_manager.get_credentials(url).map_err(|e| e.to_string())?
+                a.password_manager.get_credentials(url)?
             };
-            let arr: Vec<Value> = creds.iter().map(|c| {
-                json!({
-                    "id": c.id, "url": c.url, "username": c.username,
-                    "created_at": c.created_at, "updated_at": c.updated_at
-                })
-            }).collect();
+            let arr: Vec<Value> = creds.iter().map(credential_json).collect();
             Ok(json!(arr))
         }
         "password.decrypt" => {
-            let id = params.get("id").and_then(|v| v.as_str()).ok_or("missing id")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
-            let creds = a.password_manager.list_all_credentials().map_err(|e| e.to_string())?;
-            let entry = creds.iter().find(|c| c.id == id).ok_or("credential not found")?;
-            let pw = a.password_manager.decrypt_password(entry).map_err(|e| e.to_string())?;
+            let id = str_param(params, "id")?;
+            let a = app.lock()?;
+            let creds = a.password_manager.list_all_credentials()?;
+            let entry = creds.iter().find(|c| c.id == id).ok_or_else(|| invalid(format!("credential not found: {}", id)))?;
+            let pw = a.password_manager.decrypt_password(entry)?;
             Ok(json!({"password": pw}))
         }
+        "password.copy" => {
+            let id = str_param(params, "id")?;
+            let a = app.lock()?;
+            let copied = a.copy_password(id)?;
+            Ok(json!({"copied": copied}))
+        }
         "password.save" => {
-            let url = params.get("url").and_then(|v| v.as_str()).ok_or("missing url")?;
-            let username = params.get("username").and_then(|v| v.as_str()).ok_or("missing username")?;
-            let password = params.get("password").and_then(|v| v.as_str()).ok_or("missing password")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            let id = a.password_manager.save_credential(url, username, password).map_err(|e| e.to_string())?;
+            let url = str_param(params, "url")?;
+            let username = str_param(params, "username")?;
+            let password = str_param(params, "password")?;
+            let mut a = app.lock()?;
+            let id = a.password_manager.save_credential(url, username, password)?;
             Ok(json!({"id": id}))
         }
         "password.update" => {
-            let id = params.get("id").and_then(|v| v.as_str()).ok_or("missing id")?;
+            let id = str_param(params, "id")?;
             let username = params.get("username").and_then(|v| v.as_str());
             let password = params.get("password").and_then(|v| v.as_str());
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.password_manager.update_credential(id, username, password).map_err(|e| e.to_string())?;
+            let mut a = app.lock()?;
+            a.password_manager.update_credential(id, username, password)?;
             Ok(json!({"ok": true}))
         }
         "password.delete" => {
-            let id = params.get("id").and_then(|v| v.as_str()).ok_or("missing id")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.password_manager.delete_credential(id).map_err(|e| e.to_string())?;
+            let id = str_param(params, "id")?;
+            let mut a = app.lock()?;
+            a.password_manager.delete_credential(id)?;
+            Ok(json!({"ok": true}))
+        }
+        "password.export" => {
+            let master = str_param(params, "master_password")?;
+            let path = str_param(params, "path")?;
+            let a = app.lock()?;
+            a.password_manager.export_encrypted(master, path)?;
             Ok(json!({"ok": true}))
         }
         "password.generate" => {
@@ -268,12 +1479,12 @@ pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<V
             let symbols = params.get("symbols").and_then(|v| v.as_bool()).unwrap_or(true);
             // BUG: Validate that at least one charset is selected
             if !uppercase && !lowercase && !numbers && !symbols {
-                return Err("at least one character set must be enabled".to_string());
+                return Err(invalid("invalid character sets: at least one must be enabled"));
             }
             if length == 0 || length > 1024 {
-                return Err("password length must be between 1 and 1024".to_string());
+                return Err(invalid("invalid length: must be between 1 and 1024"));
             }
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             let opts = crate::types::credential::PasswordGenOptions {
                 length, uppercase, lowercase, numbers, symbols,
             };
@@ -284,45 +1495,97 @@ pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<V
         // ─── Ping ───
         "ping" => Ok(json!({"pong": true})),
 
+        // ─── Desktop notifications ───
+        "notify.show" => {
+            let title = str_param(params, "title")?.to_string();
+            let body = params.get("body").and_then(|v| v.as_str()).unwrap_or("").to_string();
+            // The platform tools take a moment to start; don't hold up the request loop
+            std::thread::spawn(move || crate::platform::notify(&title, &body, Vec::new()));
+            Ok(json!({"ok": true}))
+        }
+
+        // ─── AI ───
+        "ai.usage" => {
+            let a = app.lock()?;
+            let usage = a.ai_assistant.get_token_usage();
+            Ok(json!({
+                "total_tokens": usage.total_tokens,
+                "total_cost": usage.total_cost,
+                "tokens_display": a.localization_engine.format_number(usage.total_tokens as f64, 0),
+                "cost_display": a.localization_engine.format_cost(usage.total_cost),
+            }))
+        }
+        "ai.answer" => {
+            let ask = u64_param(params, "ask")?;
+            let part = AIAnswerPart {
+                text: params.get("text").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                done: params.get("done").and_then(|v| v.as_bool()).unwrap_or(false),
+            };
+            let mut a = app.lock()?;
+            if !a.answer_ai(ask, part) {
+                return Err(invalid(format!("invalid ask: {}", ask)));
+            }
+            Ok(json!({"ok": true}))
+        }
+
         // ─── Extensions ───
         "extension.list" => {
-            let a = app.lock().map_err(|e| e.to_string())?;
-            let exts = a.extension_framework.list_extensions();
-            let arr: Vec<Value> = exts.iter().map(|e| json!({
-                "id": e.id, "name": e.name, "version": e.version, "enabled": e.enabled,
-                "permissions": e.permissions, "performance_impact_ms": e.performance_impact_ms,
-                "install_path": e.install_path,
-                "content_scripts": e.content_scripts
-            })).collect();
+            let a = app.lock()?;
+            let arr: Vec<Value> =
+                a.extension_framework.list_extensions().into_iter().map(|e| extension_json(&a, e)).collect();
             Ok(json!(arr))
         }
         "extension.install" => {
-            let path = params.get("path").and_then(|v| v.as_str()).ok_or("missing path")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            let id = a.extension_framework.install(path).map_err(|e| e.to_string())?;
+            let path = str_param(params, "path")?;
+            let mut a = app.lock()?;
+            let pending = a.extension_framework.request_install(path)?;
+            if !pending.permissions.is_empty() {
+                // The caller shows the permission list and answers with extension.confirm_install
+                return Ok(json!({"pending": true, "install": pending}));
+            }
+            let id = a.extension_framework.confirm_install(&pending.install_id, &[])?;
+            a.refresh_extension_rules();
             Ok(json!({"id": id}))
         }
+        "extension.confirm_install" => {
+            let install_id = str_param(params, "install_id")?;
+            let permissions = params.get("permissions").cloned().ok_or_else(|| missing("permissions"))?;
+            let granted: Vec<ExtensionPermission> = serde_json::from_value(permissions)?;
+            let mut a = app.lock()?;
+            let id = a.extension_framework.confirm_install(install_id, &granted)?;
+            a.refresh_extension_rules();
+            Ok(json!({"id": id}))
+        }
+        "extension.cancel_install" => {
+            let install_id = str_param(params, "install_id")?;
+            let mut a = app.lock()?;
+            a.extension_framework.cancel_install(install_id)?;
+            Ok(json!({"ok": true}))
+        }
         "extension.uninstall" => {
-            let id = params.get("id").and_then(|v| v.as_str()).ok_or("missing id")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.extension_framework.uninstall(id).map_err(|e| e.to_string())?;
+            let id = str_param(params, "id")?;
+            let mut a = app.lock()?;
+            a.extension_framework.uninstall(id)?;
+            a.refresh_extension_rules();
             Ok(json!({"ok": true}))
         }
         "extension.enable" => {
-            let id = params.get("id").and_then(|v| v.as_str()).ok_or("missing id")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.extension_framework.enable(id).map_err(|e| e.to_string())?;
+            let id = str_param(params, "id")?;
+            let mut a = app.lock()?;
+            a.extension_framework.enable(id)?;
+            a.refresh_extension_rules();
             Ok(json!({"ok": true}))
         }
         "extension.disable" => {
-            let id = params.get("id").and_then(|v| v.as_str()).ok_or("missing id")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.extension_framework.disable(id).map_err(|e| e.to_string())?;
+            let id = str_param(params, "id")?;
+            let mut a = app.lock()?;
+            a.extension_framework.disable(id)?;
+            a.refresh_extension_rules();
             Ok(json!({"ok": true}))
         }
         "extension.content_scripts" => {
-            let url = params.get("url").and_then(|v| v.as_str()).ok_or("missing url")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let url = str_param(params, "url")?;
+            let a = app.lock()?;
             let scripts = a.extension_framework.get_content_scripts_for_url(url);
             let arr: Vec<Value> = scripts.iter().map(|s| json!({
                 "extension_id": s.extension_id,
@@ -333,32 +1596,248 @@ pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<V
             })).collect();
             Ok(json!(arr))
         }
+        "extension.send_message" => {
+            let id = str_param(params, "id")?;
+            let message = params.get("message").cloned().ok_or_else(|| missing("message"))?;
+            let sender = match params.get("url").and_then(|v| v.as_str()) {
+                Some(url) => MessageSender::ContentScript { url: url.to_string(), request_id: None },
+                None => MessageSender::Background,
+            };
+            let mut a = app.lock()?;
+            let message_id = a.extension_framework.send_message(id, sender, message)?;
+            Ok(json!({"message_id": message_id}))
+        }
+        "extension.messages" => {
+            let id = str_param(params, "id")?;
+            let mut a = app.lock()?;
+            let messages = a.extension_framework.take_messages(id);
+            Ok(json!({"messages": messages}))
+        }
+        "extension.reply" => {
+            let id = str_param(params, "id")?;
+            let message_id = u64_param(params, "message_id")?;
+            let response = params.get("response").cloned().unwrap_or(Value::Null);
+            let mut a = app.lock()?;
+            let original = a.extension_framework.reply(id, message_id, &response)?;
+            Ok(json!({"ok": true, "sender": original.sender}))
+        }
+        "extension.storage_get" => {
+            let id = str_param(params, "id")?;
+            let keys: Option<Vec<String>> = params.get("keys").and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|k| k.as_str().map(String::from)).collect());
+            let a = app.lock()?;
+            let items = a.extension_framework.storage_get(id, keys.as_deref())?;
+            Ok(json!({"items": items}))
+        }
+        "extension.storage_set" => {
+            let id = str_param(params, "id")?;
+            let items = params.get("items").and_then(|v| v.as_object()).ok_or_else(|| missing("items"))?;
+            let mut a = app.lock()?;
+            a.extension_framework.storage_set(id, items)?;
+            Ok(json!({"ok": true}))
+        }
+        "extension.storage_remove" => {
+            let id = str_param(params, "id")?;
+            let keys: Vec<String> = params.get("keys").and_then(|v| v.as_array()).ok_or_else(|| missing("keys"))?
+                .iter().filter_map(|k| k.as_str().map(String::from)).collect();
+            let mut a = app.lock()?;
+            a.extension_framework.storage_remove(id, &keys)?;
+            Ok(json!({"ok": true}))
+        }
+
+        // ─── Privacy ───
+        "privacy.check_request" => {
+            let url = str_param(params, "url")?;
+            let resource_type = params.get("resource_type").and_then(|v| v.as_str()).unwrap_or("other");
+            let a = app.lock()?;
+            Ok(json!({
+                "blocked": a.privacy_engine.should_block_request(url, resource_type),
+                "extension_rules": a.privacy_engine.extension_rule_count(),
+            }))
+        }
+        "privacy.should_block" => {
+            // `page_url` applies the page's site exceptions; `record` counts a block in the stats
+            let url = str_param(params, "url")?;
+            let resource_type = params.get("resource_type").and_then(|v| v.as_str()).unwrap_or("other");
+            let site = params.get("page_url").and_then(|v| v.as_str()).and_then(SiteSettingsManager::site_for_url);
+            let mut a = app.lock()?;
+            let blocked = a.privacy_engine.should_block_on_site(url, resource_type, site.as_deref());
+            if blocked && params.get("record").and_then(|v| v.as_bool()).unwrap_or(false) {
+                a.privacy_engine.record_blocked_on_site(url, site.as_deref());
+            }
+            Ok(json!({"blocked": blocked, "https_url": a.privacy_engine.upgrade_to_https(url)}))
+        }
+        "privacy.stats" => {
+            let a = app.lock()?;
+            Ok(json!({
+                "stats": a.privacy_engine.get_stats(),
+                "private_mode": a.privacy_engine.is_private_mode(),
+                "dns_over_https": a.privacy_engine.dns_over_https_provider(),
+            }))
+        }
+        "privacy.dashboard" => {
+            let a = app.lock()?;
+            Ok(privacy_dashboard_json(&a))
+        }
+        "privacy.set_private_mode" => {
+            // Leaving private mode drops what the private session recorded
+            let enabled = bool_param(params, "enabled")?;
+            let mut a = app.lock()?;
+            if enabled {
+                a.privacy_engine.enable_private_mode();
+            } else if a.privacy_engine.is_private_mode() {
+                a.privacy_engine.disable_private_mode();
+                a.privacy_engine.clear_private_data()?;
+            }
+            Ok(json!({"private_mode": a.privacy_engine.is_private_mode()}))
+        }
+        "privacy.configure_doh" => {
+            // Saved as `privacy.dns_over_https` / `privacy.dns_provider`
+            let enabled = params.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true);
+            let mut a = app.lock()?;
+            if let Some(provider) = params.get("provider").and_then(|v| v.as_str()) {
+                if !provider.starts_with("https://") {
+                    return Err(invalid("invalid provider: must start with https://"));
+                }
+                a.settings_engine.set_value("privacy.dns_provider", json!(provider))?;
+            }
+            a.settings_engine.set_value("privacy.dns_over_https", json!(enabled))?;
+            a.process_settings_changes();
+            Ok(json!({"dns_over_https": a.privacy_engine.dns_over_https_provider()}))
+        }
+        "privacy.filters.list" => {
+            let a = app.lock()?;
+            Ok(json!({
+                "lists": a.privacy_engine.filter_lists(),
+                "custom": a.settings_engine.get_settings().privacy.custom_filters,
+            }))
+        }
+        "privacy.filters.enable" => {
+            // The built-in lists follow `privacy.tracker_blocking` / `privacy.ad_blocking`
+            let list = str_param(params, "list")?;
+            let enabled = bool_param(params, "enabled")?;
+            let key = match list {
+                "trackers" => "privacy.tracker_blocking",
+                "ads" => "privacy.ad_blocking",
+                _ => return Err(invalid(format!("invalid list: {}", list))),
+            };
+            let mut a = app.lock()?;
+            a.settings_engine.set_value(key, json!(enabled))?;
+            a.process_settings_changes();
+            Ok(json!({"lists": a.privacy_engine.filter_lists()}))
+        }
+        "privacy.filters.add" | "privacy.filters.remove" => {
+            let filter = params.get("filter").and_then(|v| v.as_str()).map(str::trim).ok_or_else(|| missing("filter"))?;
+            if filter.is_empty() {
+                return Err(invalid("invalid filter: empty"));
+            }
+            let mut a = app.lock()?;
+            let mut filters = a.settings_engine.get_settings().privacy.custom_filters.clone();
+            let present = filters.iter().any(|f| f == filter);
+            match method {
+                "privacy.filters.add" if !present => filters.push(filter.to_string()),
+                "privacy.filters.remove" if present => filters.retain(|f| f != filter),
+                _ => return Ok(json!({"changed": false, "custom": filters})),
+            }
+            a.settings_engine.set_value("privacy.custom_filters", json!(filters))?;
+            a.process_settings_changes();
+            Ok(json!({"changed": true, "custom": filters}))
+        }
+        "privacy.site" => {
+            let url = str_param(params, "url")?;
+            let site = SiteSettingsManager::site_for_url(url).ok_or_else(|| invalid(format!("invalid url: {}", url)))?;
+            let a = app.lock()?;
+            Ok(json!({"site": site, "shields": !a.privacy_engine.is_site_disabled(&site)}))
+        }
+        "privacy.set_site" => {
+            // Turns all protections on or off for the site of `url`
+            let url = str_param(params, "url")?;
+            let shields = bool_param(params, "shields")?;
+            let mut a = app.lock()?;
+            let site = a.set_site_shields(url, shields)?;
+            Ok(json!({"site": site, "shields": shields}))
+        }
 
         // ─── GitHub (secure token storage) ───
         "github.store_token" => {
-            let token = params.get("token").and_then(|v| v.as_str()).ok_or("missing token")?;
-            let login = params.get("login").and_then(|v| v.as_str()).ok_or("missing login")?;
+            let token = str_param(params, "token")?;
+            let login = str_param(params, "login")?;
             let avatar_url = params.get("avatar_url").and_then(|v| v.as_str());
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
             // SEC-01: Warn if master password is not set — token will use fallback key
             let uses_master = a.password_manager.is_unlocked();
-            a.github_integration.store_token(token, login, avatar_url).map_err(|e| e.to_string())?;
+            a.github_integration.store_token(token, login, avatar_url)?;
             Ok(json!({"ok": true, "master_key_active": uses_master}))
         }
+        "github.login_device" => {
+            // Only the code is fetched here; `complete_device_login` polls for the token
+            let endpoints = app.lock()?.github_integration.endpoints().clone();
+            let client_id = endpoints
+                .device_client_id(params.get("client_id").and_then(|v| v.as_str()))?;
+            let code = GitHubIntegration::request_device_code(&endpoints, &client_id)?;
+            Ok(serde_json::to_value(&code)?)
+        }
+        "github.notifications" => {
+            let unread_only = params.get("unread_only").and_then(|v| v.as_bool()).unwrap_or(false);
+            let refresh = params.get("refresh").and_then(|v| v.as_bool()).unwrap_or(false);
+            if refresh {
+                // Fetched without the app lock, as the background poll does
+                let client = app.lock()?.github_integration.client()?;
+                let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+                let fetched = GitHubNotifications::fetch(&client);
+                let mut a = app.lock()?;
+                match fetched {
+                    Ok(response) => {
+                        a.github_notifications.record_response(&response, now)?;
+                    }
+                    Err(e) => {
+                        a.github_notifications.record_failure(&e, now);
+                        return Err(e.into());
+                    }
+                }
+            }
+            let a = app.lock()?;
+            let notifications = a.github_notifications.list_notifications(unread_only)?;
+            let unread = a.github_notifications.unread_count()?;
+            Ok(json!({"notifications": notifications, "unread": unread}))
+        }
+        "github.endpoints" => {
+            let a = app.lock()?;
+            let endpoints = a.github_integration.endpoints();
+            Ok(json!({
+                "web_url": endpoints.web_url,
+                "api_url": endpoints.api_url,
+                "enterprise": endpoints.credential_slot != GitHubEndpoints::github_com().credential_slot,
+                "authenticated": a.github_integration.is_authenticated(),
+            }))
+        }
+        "github.quick_open" => {
+            // Accepts the whole address bar input or just the text after `gh`
+            let input = str_param(params, "query")?;
+            let query = command_query(input).unwrap_or(input);
+            let limit = params.get("limit").and_then(|v| v.as_u64()).unwrap_or(8) as usize;
+            let a = app.lock()?;
+            let web_url = &a.github_integration.endpoints().web_url;
+            let suggestions = a.github_quick_open.suggest(web_url, query, limit)?;
+            Ok(json!({
+                "url": a.github_quick_open.resolve(web_url, query),
+                "suggestions": suggestions,
+            }))
+        }
         "github.get_token" => {
-            let a = app.lock().map_err(|e| e.to_string())?;
-            let token = a.github_integration.get_token().map_err(|e| e.to_string())?;
+            let a = app.lock()?;
+            let token = a.github_integration.get_token()?;
             Ok(json!({"token": token}))
         }
         "github.logout" => {
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.github_integration.logout().map_err(|e| e.to_string())?;
+            let mut a = app.lock()?;
+            a.github_integration.logout()?;
             Ok(json!({"ok": true}))
         }
         "github.encrypt_sync" => {
-            let data = params.get("data").and_then(|v| v.as_str()).ok_or("missing data")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
-            let encrypted = a.github_integration.encrypt_for_sync(data.as_bytes()).map_err(|e| e.to_string())?;
+            let data = str_param(params, "data")?;
+            let a = app.lock()?;
+            let encrypted = a.github_integration.encrypt_for_sync(data.as_bytes())?;
             Ok(json!({
                 "ciphertext": base64_encode(&encrypted.ciphertext),
                 "iv": base64_encode(&encrypted.iv),
@@ -366,55 +1845,130 @@ pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<V
             }))
         }
         "github.decrypt_sync" => {
-            let ciphertext = params.get("ciphertext").and_then(|v| v.as_str()).ok_or("missing ciphertext")?;
-            let iv = params.get("iv").and_then(|v| v.as_str()).ok_or("missing iv")?;
-            let auth_tag = params.get("auth_tag").and_then(|v| v.as_str()).ok_or("missing auth_tag")?;
+            let ciphertext = str_param(params, "ciphertext")?;
+            let iv = str_param(params, "iv")?;
+            let auth_tag = str_param(params, "auth_tag")?;
             let encrypted = crate::types::credential::EncryptedData {
-                ciphertext: base64_decode(ciphertext).map_err(|e| e.to_string())?,
-                iv: base64_decode(iv).map_err(|e| e.to_string())?,
-                auth_tag: base64_decode(auth_tag).map_err(|e| e.to_string())?,
+                ciphertext: base64_decode(ciphertext)?,
+                iv: base64_decode(iv)?,
+                auth_tag: base64_decode(auth_tag)?,
             };
-            let a = app.lock().map_err(|e| e.to_string())?;
-            let decrypted = a.github_integration.decrypt_from_sync(&encrypted).map_err(|e| e.to_string())?;
-            let text = String::from_utf8(decrypted).map_err(|e| e.to_string())?;
+            let a = app.lock()?;
+            let decrypted = a.github_integration.decrypt_from_sync(&encrypted)?;
+            let text = String::from_utf8(decrypted)?;
             Ok(json!({"data": text}))
         }
 
+        // ─── Sync ───
+        "sync.set_passphrase" => {
+            let passphrase = str_param(params, "passphrase")?;
+            app.lock()?.sync_service.set_passphrase(passphrase)?;
+            Ok(json!({"ok": true}))
+        }
+        "sync.now" => {
+            app.lock()?.events.publish(EventTopic::Sync, json!({"status": "started"}));
+            let result = sync_now(app);
+            let event = match &result {
+                Ok(report) => json!({"status": "finished", "report": report}),
+                Err(e) => json!({"status": "failed", "error": e.to_string(), "code": e.code()}),
+            };
+            app.lock()?.events.publish(EventTopic::Sync, event);
+            result
+        }
+        "sync.conflicts" => {
+            let a = app.lock()?;
+            let conflicts = a.sync_service.conflicts()?;
+            Ok(json!({"conflicts": conflicts}))
+        }
+        "sync.resolve_conflict" => {
+            let collection: SyncCollection = params
+                .get("collection")
+                .cloned()
+                .ok_or_else(|| missing("collection"))
+                .and_then(|v| serde_json::from_value(v).map_err(|_| invalid("invalid collection")))?;
+            let resolution: ConflictResolution = params
+                .get("resolution")
+                .cloned()
+                .ok_or_else(|| missing("resolution"))
+                .and_then(|v| serde_json::from_value(v).map_err(|_| invalid("invalid resolution")))?;
+            let mut guard = app.lock()?;
+            let a = &mut *guard;
+            a.sync_service
+                .resolve_conflict(collection, resolution, &mut a.settings_engine)?;
+            a.process_settings_changes();
+            Ok(json!({"ok": true}))
+        }
+
+        // ─── Updates ───
+        "update.status" => {
+            let a = app.lock()?;
+            Ok(json!({
+                "version": a.update_manager.get_current_version(),
+                "channel": a.update_manager.channel(),
+                "auto_check": a.update_manager.is_auto_check_enabled(),
+                "ready": a.update_manager.ready_update(),
+                "install": a.update_installer.state(),
+            }))
+        }
+        "update.check" => {
+            let (channel, version) = {
+                let a = app.lock()?;
+                (a.update_manager.channel(), a.update_manager.get_current_version().to_string())
+            };
+            let update = check_releases(channel, &version)?;
+            Ok(json!({"update": update}))
+        }
+        "update.set_channel" => {
+            let channel: UpdateChannel = params
+                .get("channel")
+                .cloned()
+                .ok_or_else(|| missing("channel"))
+                .and_then(|v| serde_json::from_value(v).map_err(|_| invalid("invalid channel")))?;
+            let mut a = app.lock()?;
+            let warning = channel_switch_warning(a.update_manager.get_current_version(), channel);
+            a.settings_engine.set_value("updates.channel", json!(channel))?;
+            a.process_settings_changes();
+            Ok(json!({"channel": channel, "warning": warning}))
+        }
+        "update.install" => {
+            let mut a = app.lock()?;
+            let ready = a.update_manager.ready_update().cloned().ok_or_else(|| UpdateError::InstallFailed("no verified update is ready to install".to_string()))?;
+            a.update_installer.stage(&ready)?;
+            Ok(json!({"staged": ready.info.version, "restart_required": true}))
+        }
+        "update.rollback" => {
+            let mut a = app.lock()?;
+            a.update_installer.rollback()?;
+            Ok(json!({"ok": true, "restart_required": true}))
+        }
+
         // ─── Secure secret storage ───
         "secret.store" => {
-            let key = params.get("key").and_then(|v| v.as_str()).ok_or("missing key")?;
-            let value = params.get("value").and_then(|v| v.as_str()).ok_or("missing value")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let key = str_param(params, "key")?;
+            let value = str_param(params, "value")?;
+            let a = app.lock()?;
             // SEC-01: Track whether master key is active for the response
             let master_key_active = a.password_manager.get_derived_key().is_some();
             let encrypted = if let Some(master_key) = a.password_manager.get_derived_key() {
                 let crypto = crate::services::crypto_service::CryptoService::new();
                 use crate::services::crypto_service::CryptoServiceTrait;
-                crypto.encrypt_aes256gcm(value.as_bytes(), &master_key).map_err(|e| e.to_string())?
+                crypto.encrypt_aes256gcm(value.as_bytes(), &master_key)?
             } else {
-                a.github_integration.encrypt_for_sync(value.as_bytes()).map_err(|e| e.to_string())?
+                a.github_integration.encrypt_for_sync(value.as_bytes())?
             };
             let conn = a.db.connection();
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS secure_store (key TEXT PRIMARY KEY, ciphertext BLOB, iv BLOB, auth_tag BLOB, updated_at INTEGER, uses_master INTEGER DEFAULT 0)",
-                [],
-            ).map_err(|e| e.to_string())?;
             let uses_master = if master_key_active { 1i32 } else { 0i32 };
             let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
             conn.execute(
                 "INSERT OR REPLACE INTO secure_store (key, ciphertext, iv, auth_tag, updated_at, uses_master) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
                 rusqlite::params![key, encrypted.ciphertext, encrypted.iv, encrypted.auth_tag, now, uses_master],
-            ).map_err(|e| e.to_string())?;
+            )?;
             Ok(json!({"ok": true, "master_key_active": master_key_active}))
         }
         "secret.get" => {
-            let key = params.get("key").and_then(|v| v.as_str()).ok_or("missing key")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let key = str_param(params, "key")?;
+            let a = app.lock()?;
             let conn = a.db.connection();
-            let _ = conn.execute(
-                "CREATE TABLE IF NOT EXISTS secure_store (key TEXT PRIMARY KEY, ciphertext BLOB, iv BLOB, auth_tag BLOB, updated_at INTEGER, uses_master INTEGER DEFAULT 0)",
-                [],
-            );
             let result = conn.query_row(
                 "SELECT ciphertext, iv, auth_tag, COALESCE(uses_master, 0) FROM secure_store WHERE key = ?1",
                 rusqlite::params![key],
@@ -430,42 +1984,228 @@ pub fn handle_method(app: &Mutex<App>, method: &str, params: &Value) -> Result<V
                         if let Some(master_key) = a.password_manager.get_derived_key() {
                             let crypto = crate::services::crypto_service::CryptoService::new();
                             use crate::services::crypto_service::CryptoServiceTrait;
-                            crypto.decrypt_aes256gcm(&encrypted, &master_key).map_err(|e| e.to_string())?
+                            crypto.decrypt_aes256gcm(&encrypted, &master_key)?
                         } else {
-                            return Err("master password required to decrypt this secret".to_string());
+                            return Err(CryptoError::InvalidKey("master password required to decrypt this secret".to_string()).into());
                         }
                     } else {
-                        a.github_integration.decrypt_from_sync(&encrypted).map_err(|e| e.to_string())?
+                        a.github_integration.decrypt_from_sync(&encrypted)?
                     };
-                    let text = String::from_utf8(decrypted).map_err(|e| e.to_string())?;
+                    let text = String::from_utf8(decrypted)?;
                     Ok(json!({"value": text}))
                 }
                 Err(rusqlite::Error::QueryReturnedNoRows) => Ok(json!({"value": null})),
-                Err(e) => Err(e.to_string()),
+                Err(e) => Err(e.into()),
             }
         }
         "secret.delete" => {
-            let key = params.get("key").and_then(|v| v.as_str()).ok_or("missing key")?;
-            let a = app.lock().map_err(|e| e.to_string())?;
+            let key = str_param(params, "key")?;
+            let a = app.lock()?;
             let conn = a.db.connection();
             let _ = conn.execute("DELETE FROM secure_store WHERE key = ?1", rusqlite::params![key]);
             Ok(json!({"ok": true}))
         }
 
+        // ─── Tabs ───
+        "tab.list" => {
+            let a = app.lock()?;
+            let tabs: Vec<Value> = a.tab_manager.get_all_tabs().into_iter().map(|t| tab_json(&a, t)).collect();
+            Ok(json!({"tabs": tabs, "active_tab_id": a.tab_manager.get_active_tab().map(|t| &t.id)}))
+        }
+        "tab.create" => {
+            let url = params.get("url").and_then(|v| v.as_str());
+            let active = params.get("active").and_then(|v| v.as_bool()).unwrap_or(true);
+            let mut a = app.lock()?;
+            let id = a.tab_manager.create_tab(url, active);
+            let tab = a.tab_manager.get_tab(&id).map(|t| tab_json(&a, t));
+            Ok(json!({"tab": tab}))
+        }
+        "tab.close" => {
+            let tab_id = str_param(params, "tab_id")?;
+            let mut a = app.lock()?;
+            a.tab_manager.close_tab(tab_id)?;
+            Ok(json!({"ok": true, "active_tab_id": a.tab_manager.get_active_tab().map(|t| &t.id)}))
+        }
+        "tab.switch" => {
+            let tab_id = str_param(params, "tab_id")?;
+            let mut a = app.lock()?;
+            a.tab_manager.switch_tab(tab_id)?;
+            Ok(json!({"ok": true}))
+        }
+        "tab.pin" => {
+            // `pinned: false` unpins
+            let tab_id = str_param(params, "tab_id")?;
+            let pinned = params.get("pinned").and_then(|v| v.as_bool()).unwrap_or(true);
+            let mut a = app.lock()?;
+            if pinned {
+                a.tab_manager.pin_tab(tab_id)?;
+            } else {
+                a.tab_manager.unpin_tab(tab_id)?;
+            }
+            Ok(json!({"ok": true, "order": a.tab_manager.get_tab_order()}))
+        }
+        "tab.mute" => {
+            // Toggles unless `muted` says which state to end up in
+            let tab_id = str_param(params, "tab_id")?;
+            let mut a = app.lock()?;
+            let current = a.tab_manager.get_tab(tab_id).map(|t| t.muted)
+                .ok_or_else(|| TabError::NotFound(tab_id.to_string()))?;
+            if params.get("muted").and_then(|v| v.as_bool()) != Some(current) {
+                a.tab_manager.mute_tab(tab_id)?;
+            }
+            Ok(json!({"muted": a.tab_manager.get_tab(tab_id).map(|t| t.muted)}))
+        }
+        "tab.zoom" => {
+            // `step` is "in", "out" or "reset"; without it the zoom is only read
+            let tab_id = str_param(params, "tab_id")?;
+            let mut a = app.lock()?;
+            let zoom = match params.get("step").and_then(|v| v.as_str()) {
+                Some(step) => {
+                    let step = ZoomStep::parse(step).ok_or_else(|| invalid(format!("invalid step: {}", step)))?;
+                    a.zoom_tab(tab_id, step)?
+                }
+                None => a.tab_manager.get_tab(tab_id).map(|t| t.zoom)
+                    .ok_or_else(|| TabError::NotFound(tab_id.to_string()))?,
+            };
+            Ok(json!({"zoom": zoom}))
+        }
+
         // ─── Tab suspension (FEAT-04) ───
         "tab.suspend" => {
-            let tab_id = params.get("tab_id").and_then(|v| v.as_str()).ok_or("missing tab_id")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.tab_manager.suspend_tab(tab_id).map_err(|e| e.to_string())?;
+            let tab_id = str_param(params, "tab_id")?;
+            let mut a = app.lock()?;
+            a.tab_manager.suspend_tab(tab_id)?;
             Ok(json!({"ok": true}))
         }
         "tab.resume" => {
-            let tab_id = params.get("tab_id").and_then(|v| v.as_str()).ok_or("missing tab_id")?;
-            let mut a = app.lock().map_err(|e| e.to_string())?;
-            a.tab_manager.resume_tab(tab_id).map_err(|e| e.to_string())?;
+            let tab_id = str_param(params, "tab_id")?;
+            let mut a = app.lock()?;
+            a.tab_manager.resume_tab(tab_id)?;
+            Ok(json!({"ok": true}))
+        }
+
+        // ─── Pages ───
+        "page.print" => {
+            // The print dialog opens in the browser; `pdf` saves to Downloads instead
+            let tab_id = str_param(params, "tab_id")?;
+            let pdf = params.get("pdf").and_then(|v| v.as_bool()).unwrap_or(false);
+            let mut a = app.lock()?;
+            let download_id = a.print_tab(tab_id, pdf)?;
+            Ok(json!({"ok": true, "download_id": download_id}))
+        }
+        "page.screenshot" => {
+            // The PNG lands in Downloads once the browser has taken it
+            let tab_id = str_param(params, "tab_id")?;
+            let full_page = params.get("full_page").and_then(|v| v.as_bool()).unwrap_or(true);
+            let mut a = app.lock()?;
+            let download_id = a.capture_tab(tab_id, full_page)?;
+            Ok(json!({"ok": true, "download_id": download_id}))
+        }
+
+        // ─── Downloads ───
+        "download.start" => {
+            let url = str_param(params, "url")?;
+            let filepath = str_param(params, "filepath")?;
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(invalid("invalid url: must start with http:// or https://"));
+            }
+            let mut a = app.lock()?;
+            let id = a.download_manager.start_download(url, filepath)?;
+            download_response(&a, &id)
+        }
+        "download.pause" | "download.resume" | "download.cancel" | "download.retry" => {
+            let id = str_param(params, "id")?;
+            let mut a = app.lock()?;
+            let downloads = &mut a.download_manager;
+            let result = match method {
+                "download.pause" => downloads.pause_download(id),
+                "download.resume" => downloads.resume_download(id),
+                "download.cancel" => downloads.cancel_download(id),
+                _ => downloads.retry_download(id),
+            };
+            result?;
+            download_response(&a, id)
+        }
+        "download.progress" => {
+            // Reported by the frontend doing the transfer
+            let id = str_param(params, "id")?;
+            let downloaded = u64_param(params, "downloaded")?;
+            let size = params.get("size").and_then(|v| v.as_u64());
+            let mut a = app.lock()?;
+            a.download_manager.update_progress(id, downloaded, size)?;
+            download_response(&a, id)
+        }
+        "download.list" => {
+            let a = app.lock()?;
+            let downloads: Vec<Value> =
+                a.download_manager.list_downloads().into_iter().map(|d| download_json(&a, d)).collect();
+            Ok(json!({"downloads": downloads}))
+        }
+        "download.get" => {
+            let id = str_param(params, "id")?;
+            let a = app.lock()?;
+            download_response(&a, id)
+        }
+
+        // ─── Site permissions ───
+        "permission.get" => {
+            let origin = str_param(params, "origin")?;
+            let perm_type = permission_type(params)?;
+            let a = app.lock()?;
+            Ok(json!({"value": a.permission_manager.get_permission(origin, &perm_type)}))
+        }
+        "permission.set" => {
+            let origin = str_param(params, "origin")?;
+            let (perm_type, value) = (permission_type(params)?, permission_value(params)?);
+            let mut a = app.lock()?;
+            a.permission_manager.set_permission(origin, perm_type, value)?;
+            Ok(json!({"ok": true}))
+        }
+        "permission.revoke" => {
+            let origin = str_param(params, "origin")?;
+            let perm_type = permission_type(params)?;
+            let mut a = app.lock()?;
+            a.permission_manager.revoke_permission(origin, &perm_type)?;
+            Ok(json!({"ok": true}))
+        }
+        "permission.list" => {
+            // All sites, or just `origin`
+            let a = app.lock()?;
+            let permissions = match params.get("origin").and_then(|v| v.as_str()) {
+                Some(origin) => a.permission_manager.get_site_permissions(origin),
+                None => a.permission_manager.list_all_permissions(),
+            }?;
+            Ok(json!({"permissions": permissions}))
+        }
+        "permission.reset_site" => {
+            let origin = str_param(params, "origin")?;
+            let mut a = app.lock()?;
+            a.permission_manager.reset_site_permissions(origin)?;
             Ok(json!({"ok": true}))
         }
+        "permission.request" => {
+            // Undecided permissions are announced as a `prompt` event for the user to answer
+            let origin = str_param(params, "origin")?;
+            let perm_type = permission_type(params)?;
+            let a = app.lock()?;
+            let value = a.permission_manager.request_permission(origin, &perm_type);
+            Ok(json!({"prompt": value == PermissionValue::Ask, "value": value}))
+        }
+        "permission.respond" => {
+            let origin = str_param(params, "origin")?;
+            let (perm_type, value) = (permission_type(params)?, permission_value(params)?);
+            if value == PermissionValue::Ask {
+                return Err(invalid("invalid value: answer with Allow or Deny"));
+            }
+            let remember = params.get("remember").and_then(|v| v.as_bool()).unwrap_or(true);
+            let mut a = app.lock()?;
+            a.permission_manager.answer_prompt(origin, perm_type, value, remember)?;
+            Ok(json!({"ok": true}))
+        }
+
+        // ─── Introspection ───
+        "rpc.discover" => Ok(json!({"version": env!("CARGO_PKG_VERSION"), "methods": METHODS})),
 
-        _ => Err(format!("unknown method: {}", method)),
+        _ => Err(GitBrowserError::MethodNotFound(method.to_string())),
     }
 }
@@ -0,0 +1,181 @@
+//! Guards that keep one RPC client from wedging the backend.
+//!
+//! Each connection gets its own request-rate budget, request lines are cut
+//! off at `MAX_REQUEST_BYTES` while they are read (so an endless line never
+//! sits in memory), and results over `MAX_RESPONSE_BYTES` are replaced by an
+//! error. Clients are told which limit they hit:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","id":null,"error":{"code":-32003,"message":"request too large","data":{"size":...,"limit":...}}}
+//! {"jsonrpc":"2.0","id":7,"error":{"code":-32001,"message":"rate limit exceeded","data":{"limit_per_second":200}}}
+//! ```
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::rpc_protocol::{error_response, TOO_LARGE};
+use crate::types::errors::GitBrowserError;
+
+/// Longest request line accepted, in bytes. Leaves room for pages and
+/// inlined images sent to `reading_list.add`.
+pub const MAX_REQUEST_BYTES: usize = 16 * 1024 * 1024;
+
+/// Largest result sent back, in bytes of JSON.
+pub const MAX_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// Calls each connection may make per second; every call in a batch counts.
+pub const MAX_REQUESTS_PER_SECOND: u32 = 200;
+
+/// Bytes read from a client at a time.
+const CHUNK_BYTES: usize = 8 * 1024;
+
+/// A request line over `MAX_REQUEST_BYTES`; only its length is kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineTooLong {
+    pub size: usize,
+}
+
+impl LineTooLong {
+    /// The error sent for the line. It was never parsed, so has no `id`.
+    pub fn response(&self, limit: usize) -> Value {
+        let mut response = error_response(Value::Null, TOO_LARGE, &GitBrowserError::TooLarge("request").to_string());
+        response["error"]["data"] = json!({"size": self.size, "limit": limit});
+        response
+    }
+}
+
+/// One line from a client.
+pub type Line = Result<String, LineTooLong>;
+
+/// Splits the bytes a client sends into lines, dropping the contents of
+/// lines over the limit as they arrive.
+pub struct LineSplitter {
+    max: usize,
+    buf: Vec<u8>,
+    /// Length so far of a line already over the limit.
+    skipped: Option<usize>,
+}
+
+impl LineSplitter {
+    pub fn new(max: usize) -> Self {
+        Self { max, buf: Vec::new(), skipped: None }
+    }
+
+    /// Takes the next bytes read and returns the lines they complete.
+    pub fn feed(&mut self, mut bytes: &[u8]) -> Vec<Line> {
+        let mut lines = Vec::new();
+        while let Some(end) = bytes.iter().position(|&b| b == b'\n') {
+            self.take(&bytes[..end]);
+            lines.push(self.finish_line());
+            bytes = &bytes[end + 1..];
+        }
+        self.take(bytes);
+        lines
+    }
+
+    /// The last line if the client disconnected without ending it.
+    pub fn finish(&mut self) -> Option<Line> {
+        (!self.buf.is_empty() || self.skipped.is_some()).then(|| self.finish_line())
+    }
+
+    fn take(&mut self, bytes: &[u8]) {
+        match &mut self.skipped {
+            Some(size) => *size += bytes.len(),
+            None if self.buf.len() + bytes.len() > self.max => {
+                self.skipped = Some(self.buf.len() + bytes.len());
+                self.buf = Vec::new();
+            }
+            None => self.buf.extend_from_slice(bytes),
+        }
+    }
+
+    fn finish_line(&mut self) -> Line {
+        if let Some(size) = self.skipped.take() {
+            return Err(LineTooLong { size });
+        }
+        let mut line = std::mem::take(&mut self.buf);
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    }
+}
+
+/// The lines read from `reader` until it closes, none held beyond `max` bytes.
+pub struct BoundedLines<R> {
+    reader: R,
+    splitter: LineSplitter,
+    ready: VecDeque<Line>,
+    closed: bool,
+}
+
+/// Reads `reader` as lines of at most `max` bytes.
+pub fn bounded_lines<R: Read>(reader: R, max: usize) -> BoundedLines<R> {
+    BoundedLines { reader, splitter: LineSplitter::new(max), ready: VecDeque::new(), closed: false }
+}
+
+impl<R: Read> Iterator for BoundedLines<R> {
+    type Item = Line;
+
+    fn next(&mut self) -> Option<Line> {
+        let mut chunk = [0u8; CHUNK_BYTES];
+        loop {
+            if let Some(line) = self.ready.pop_front() {
+                return Some(line);
+            }
+            if self.closed {
+                return None;
+            }
+            match self.reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.closed = true;
+                    return self.splitter.finish();
+                }
+                Ok(n) => self.ready.extend(self.splitter.feed(&chunk[..n])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => self.closed = true,
+            }
+        }
+    }
+}
+
+/// Size of `result` as JSON if it is over `MAX_RESPONSE_BYTES`.
+pub fn oversized_response(result: &Value) -> Option<usize> {
+    let size = serde_json::to_vec(result).map(|bytes| bytes.len()).unwrap_or(0);
+    (size > MAX_RESPONSE_BYTES).then_some(size)
+}
+
+/// Per-connection request budget over one-second windows.
+pub struct RateLimiter {
+    window_start: Instant,
+    request_count: u32,
+    max_per_second: u32,
+}
+
+impl RateLimiter {
+    pub fn new(max_per_second: u32) -> Self {
+        Self { window_start: Instant::now(), request_count: 0, max_per_second }
+    }
+
+    pub fn max_per_second(&self) -> u32 {
+        self.max_per_second
+    }
+
+    /// Returns true if the request is allowed, false if rate-limited.
+    pub fn check(&mut self) -> bool {
+        self.check_at(Instant::now())
+    }
+
+    /// `check` for a request arriving at `now`.
+    pub fn check_at(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.request_count = 0;
+        }
+        self.request_count = self.request_count.saturating_add(1);
+        self.request_count <= self.max_per_second
+    }
+}
@@ -0,0 +1,336 @@
+//! Registry of the RPC methods and their parameters.
+//!
+//! `handle_method` dispatches only the methods listed here, and
+//! `rpc.discover` returns the list, so clients and debugging tools can find
+//! out what the backend accepts without a copy of its source. A method added
+//! to the dispatcher must be added here too, or it answers `unknown method`.
+
+use serde::Serialize;
+
+/// JSON type a parameter takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ParamType {
+    #[serde(rename = "string")]
+    Str,
+    #[serde(rename = "integer")]
+    Int,
+    #[serde(rename = "boolean")]
+    Bool,
+    #[serde(rename = "array")]
+    Array,
+    #[serde(rename = "object")]
+    Object,
+    /// Any JSON value.
+    #[serde(rename = "any")]
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub ty: ParamType,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MethodSpec {
+    pub name: &'static str,
+    pub params: &'static [ParamSpec],
+    /// Sends its result in parts before the response; see `rpc_stream`.
+    pub streaming: bool,
+}
+
+const fn method(name: &'static str, params: &'static [ParamSpec]) -> MethodSpec {
+    MethodSpec { name, params, streaming: false }
+}
+
+const fn streaming(name: &'static str, params: &'static [ParamSpec]) -> MethodSpec {
+    MethodSpec { name, params, streaming: true }
+}
+
+const fn req(name: &'static str, ty: ParamType) -> ParamSpec {
+    ParamSpec { name, ty, required: true }
+}
+
+const fn opt(name: &'static str, ty: ParamType) -> ParamSpec {
+    ParamSpec { name, ty, required: false }
+}
+
+use ParamType::*;
+
+/// Every method the backend answers, in the order of `handle_method`.
+/// `subscribe` and `unsubscribe` are answered by `handle_subscription` and
+/// streaming methods by `handle_streaming`; the socket handshake (`auth`, see
+/// `rpc_auth`) is not a method.
+pub const METHODS: &[MethodSpec] = &[
+    // ─── Bookmarks ───
+    method("bookmark.add", &[req("url", Str), req("title", Str), opt("folder_id", Str)]),
+    method("bookmark.list", &[opt("folder_id", Str), opt("limit", Int), opt("offset", Int)]),
+    method("bookmark.search", &[req("query", Str)]),
+    method("bookmark.delete", &[req("id", Str)]),
+
+    // ─── History ───
+    method("history.record", &[req("url", Str), req("title", Str)]),
+    method("history.search", &[req("query", Str)]),
+    method("history.recent", &[opt("limit", Int), opt("offset", Int), opt("date", Str)]),
+    method("history.delete", &[req("id", Str)]),
+    method("history.clear", &[]),
+    streaming("history.export", &[opt("date", Str), opt("chunk_size", Int)]),
+
+    // ─── Reader mode ───
+    method("reader.is_article", &[req("html", Str), req("url", Str)]),
+    method("reader.extract", &[req("html", Str), req("url", Str), opt("max_pages", Int)]),
+    method("reader.render", &[req("html", Str), req("url", Str), opt("max_pages", Int), opt("settings", Object)]),
+
+    // ─── Reading list ───
+    method("reading_list.add", &[req("url", Str), req("html", Str), opt("images", Object), opt("max_pages", Int)]),
+    method("reading_list.list", &[opt("unread_only", Bool), opt("limit", Int), opt("offset", Int)]),
+    method("reading_list.get", &[req("id", Str)]),
+    method("reading_list.mark_read", &[req("id", Str), opt("read", Bool)]),
+    method("reading_list.delete", &[req("id", Str)]),
+
+    // ─── Annotations ───
+    method(
+        "annotations.add",
+        &[
+            req("url", Str),
+            req("text", Str),
+            opt("prefix", Str),
+            opt("suffix", Str),
+            opt("note", Str),
+            opt("color", Str),
+        ],
+    ),
+    method("annotations.list", &[req("url", Str)]),
+    method("annotations.update", &[req("id", Str), opt("note", Str), opt("color", Str)]),
+    method("annotations.delete", &[req("id", Str)]),
+    method("annotations.apply", &[req("url", Str), req("html", Str)]),
+
+    // ─── Settings ───
+    method("settings.schema", &[]),
+    method("settings.status", &[]),
+    method("settings.export", &[req("path", Str)]),
+    streaming("settings.import", &[req("path", Str), opt("apply", Bool)]),
+    method("settings.profiles.list", &[]),
+    method("settings.profiles.create", &[req("name", Str), opt("clone_from", Str)]),
+    method("settings.profiles.switch", &[req("name", Str)]),
+    method("settings.profiles.delete", &[req("name", Str)]),
+
+    // ─── Profiles ───
+    method("profiles.list", &[]),
+    method("profiles.create", &[req("name", Str)]),
+    method("profiles.delete", &[req("name", Str), opt("confirm", Bool)]),
+
+    // ─── Settings ───
+    method("settings.get", &[]),
+    method("settings.set", &[req("key", Str), req("value", Any)]),
+
+    // ─── Search engines ───
+    method("search.list", &[]),
+    method("search.add", &[req("engine", Object)]),
+    method("search.update", &[req("engine", Object)]),
+    method("search.remove", &[req("id", Str)]),
+    method("search.set_default", &[req("id", Str)]),
+    method("search.resolve", &[req("query", Str)]),
+
+    // ─── Shortcuts ───
+    method("shortcuts.list", &[]),
+    method("shortcuts.get", &[req("action", Str)]),
+    method("shortcuts.set", &[req("action", Str), req("keys", Str)]),
+    method("shortcuts.reset", &[opt("action", Str)]),
+    method("shortcuts.has_conflict", &[req("keys", Str), opt("action", Str)]),
+
+    // ─── Themes ───
+    method("theme.get", &[]),
+    method("theme.set", &[req("mode", Str)]),
+    method("theme.css_variables", &[]),
+    method("theme.set_accent", &[req("color", Str)]),
+    method("theme.install", &[opt("theme", Object), opt("path", Str)]),
+    method("theme.list", &[]),
+    method("theme.apply", &[opt("id", Str)]),
+    method("theme.remove", &[req("id", Str)]),
+    method("theme.force_dark", &[req("url", Str)]),
+    method("theme.force_dark.toggle", &[req("url", Str)]),
+    method("theme.schedule", &[]),
+
+    // ─── Site settings ───
+    method("site_settings.get", &[req("site", Str)]),
+    method("site_settings.set", &[req("site", Str), req("key", Str), req("value", Any)]),
+    method("site_settings.remove", &[req("site", Str), opt("key", Str)]),
+    method("site_settings.user_agent", &[req("url", Str)]),
+    method("site_settings.user_agent.set", &[req("url", Str), opt("user_agent", Str)]),
+
+    // ─── Localization ───
+    method("i18n.t", &[req("key", Str)]),
+    method("i18n.locale", &[]),
+    method("i18n.locales", &[]),
+    method("i18n.stats", &[]),
+    method("i18n.reload", &[]),
+    method("i18n.debug", &[req("enabled", Bool)]),
+    method("i18n.missing", &[opt("clear", Bool)]),
+
+    // ─── Session ───
+    method("session.save", &[req("tabs", Array), opt("active_tab_id", Str)]),
+    method("session.restore", &[]),
+
+    // ─── Database ───
+    method("db.backup", &[]),
+    method("db.backups", &[]),
+    method("db.restore", &[req("name", Str), opt("confirm", Bool)]),
+    method("db.stats", &[]),
+    method("db.compact", &[]),
+
+    // ─── Crash recovery ───
+    method("crash.logs", &[]),
+    method("crash.list", &[opt("unrecovered_only", Bool)]),
+    method("crash.mark_recovered", &[opt("ids", Array)]),
+    method("crash.clear", &[]),
+    method("crash.renderer", &[req("reason", Str), opt("url", Str)]),
+    method("crash.restore_prompt", &[]),
+    method("crash.restore", &[req("restore", Bool)]),
+    method("crash.upload", &[opt("ids", Array)]),
+
+    // ─── Passwords ───
+    method("password.unlock", &[req("master_password", Str)]),
+    method("password.quick_unlock", &[]),
+    method("password.set_quick_unlock", &[req("enabled", Bool)]),
+    method("password.lock", &[]),
+    method("password.is_unlocked", &[]),
+    method("password.list", &[opt("url", Str)]),
+    method("password.decrypt", &[req("id", Str)]),
+    method("password.copy", &[req("id", Str)]),
+    method("password.save", &[req("url", Str), req("username", Str), req("password", Str)]),
+    method("password.update", &[req("id", Str), opt("username", Str), opt("password", Str)]),
+    method("password.delete", &[req("id", Str)]),
+    method("password.export", &[req("master_password", Str), req("path", Str)]),
+    streaming("password.import", &[req("master_password", Str), req("path", Str)]),
+    method(
+        "password.generate",
+        &[
+            opt("length", Int),
+            opt("uppercase", Bool),
+            opt("lowercase", Bool),
+            opt("numbers", Bool),
+            opt("symbols", Bool),
+        ],
+    ),
+
+    // ─── Misc ───
+    method("ping", &[]),
+    method("notify.show", &[req("title", Str), opt("body", Str)]),
+
+    // ─── AI ───
+    method("ai.usage", &[]),
+    streaming("ai.ask", &[req("selected_text", Str), opt("page_url", Str)]),
+    method("ai.answer", &[req("ask", Int), opt("text", Str), opt("done", Bool)]),
+
+    // ─── Extensions ───
+    method("extension.list", &[]),
+    method("extension.install", &[req("path", Str)]),
+    method("extension.confirm_install", &[req("install_id", Str), req("permissions", Array)]),
+    method("extension.cancel_install", &[req("install_id", Str)]),
+    method("extension.uninstall", &[req("id", Str)]),
+    method("extension.enable", &[req("id", Str)]),
+    method("extension.disable", &[req("id", Str)]),
+    method("extension.content_scripts", &[req("url", Str)]),
+    method("extension.send_message", &[req("id", Str), req("message", Any), opt("url", Str)]),
+    method("extension.messages", &[req("id", Str)]),
+    method("extension.reply", &[req("id", Str), req("message_id", Int), opt("response", Any)]),
+    method("extension.storage_get", &[req("id", Str), opt("keys", Array)]),
+    method("extension.storage_set", &[req("id", Str), req("items", Object)]),
+    method("extension.storage_remove", &[req("id", Str), req("keys", Array)]),
+
+    // ─── Privacy ───
+    method("privacy.check_request", &[req("url", Str), opt("resource_type", Str)]),
+    method(
+        "privacy.should_block",
+        &[req("url", Str), opt("resource_type", Str), opt("page_url", Str), opt("record", Bool)],
+    ),
+    method("privacy.stats", &[]),
+    method("privacy.dashboard", &[]),
+    method("privacy.set_private_mode", &[req("enabled", Bool)]),
+    method("privacy.configure_doh", &[opt("enabled", Bool), opt("provider", Str)]),
+    method("privacy.filters.list", &[]),
+    method("privacy.filters.enable", &[req("list", Str), req("enabled", Bool)]),
+    method("privacy.filters.add", &[req("filter", Str)]),
+    method("privacy.filters.remove", &[req("filter", Str)]),
+    method("privacy.site", &[req("url", Str)]),
+    method("privacy.set_site", &[req("url", Str), req("shields", Bool)]),
+
+    // ─── GitHub ───
+    method("github.store_token", &[req("token", Str), req("login", Str), opt("avatar_url", Str)]),
+    method("github.login_device", &[opt("client_id", Str)]),
+    method("github.notifications", &[opt("unread_only", Bool), opt("refresh", Bool)]),
+    method("github.endpoints", &[]),
+    method("github.quick_open", &[req("query", Str), opt("limit", Int)]),
+    method("github.get_token", &[]),
+    method("github.logout", &[]),
+    method("github.encrypt_sync", &[req("data", Str)]),
+    method("github.decrypt_sync", &[req("ciphertext", Str), req("iv", Str), req("auth_tag", Str)]),
+
+    // ─── Sync ───
+    method("sync.set_passphrase", &[req("passphrase", Str)]),
+    method("sync.now", &[]),
+    method("sync.conflicts", &[]),
+    method("sync.resolve_conflict", &[req("collection", Str), req("resolution", Str)]),
+
+    // ─── Updates ───
+    method("update.status", &[]),
+    method("update.check", &[]),
+    method("update.set_channel", &[req("channel", Str)]),
+    method("update.install", &[]),
+    method("update.rollback", &[]),
+
+    // ─── Secure secret storage ───
+    method("secret.store", &[req("key", Str), req("value", Str)]),
+    method("secret.get", &[req("key", Str)]),
+    method("secret.delete", &[req("key", Str)]),
+
+    // ─── Tabs ───
+    method("tab.list", &[]),
+    method("tab.create", &[opt("url", Str), opt("active", Bool)]),
+    method("tab.close", &[req("tab_id", Str)]),
+    method("tab.switch", &[req("tab_id", Str)]),
+    method("tab.pin", &[req("tab_id", Str), opt("pinned", Bool)]),
+    method("tab.mute", &[req("tab_id", Str), opt("muted", Bool)]),
+    method("tab.zoom", &[req("tab_id", Str), opt("step", Str)]),
+    method("tab.suspend", &[req("tab_id", Str)]),
+    method("tab.resume", &[req("tab_id", Str)]),
+
+    // ─── Pages ───
+    method("page.print", &[req("tab_id", Str), opt("pdf", Bool)]),
+    method("page.screenshot", &[req("tab_id", Str), opt("full_page", Bool)]),
+
+    // ─── Downloads ───
+    method("download.start", &[req("url", Str), req("filepath", Str)]),
+    method("download.pause", &[req("id", Str)]),
+    method("download.resume", &[req("id", Str)]),
+    method("download.cancel", &[req("id", Str)]),
+    method("download.retry", &[req("id", Str)]),
+    method("download.progress", &[req("id", Str), req("downloaded", Int), opt("size", Int)]),
+    method("download.list", &[]),
+    method("download.get", &[req("id", Str)]),
+
+    // ─── Site permissions ───
+    method("permission.get", &[req("origin", Str), req("permission", Str)]),
+    method("permission.set", &[req("origin", Str), req("permission", Str), req("value", Str)]),
+    method("permission.revoke", &[req("origin", Str), req("permission", Str)]),
+    method("permission.list", &[opt("origin", Str)]),
+    method("permission.reset_site", &[req("origin", Str)]),
+    method("permission.request", &[req("origin", Str), req("permission", Str)]),
+    method(
+        "permission.respond",
+        &[req("origin", Str), req("permission", Str), req("value", Str), opt("remember", Bool)],
+    ),
+
+    // ─── Introspection and events ───
+    method("rpc.discover", &[]),
+    method("subscribe", &[opt("topics", Array)]),
+    method("unsubscribe", &[req("subscription", Int)]),
+];
+
+/// The registered method called `name`.
+pub fn find(name: &str) -> Option<&'static MethodSpec> {
+    METHODS.iter().find(|m| m.name == name)
+}
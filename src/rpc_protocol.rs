@@ -0,0 +1,193 @@
+//! Framing for the GitBrowser RPC protocol.
+//!
+//! Requests are JSON-RPC 2.0: `{"jsonrpc":"2.0","id":1,"method":"...","params":{...}}`,
+//! answered with `result` or an `error` object carrying a code. A request
+//! without `id` is a notification and gets no answer, and an array of
+//! requests on one line is a batch, answered with one array of responses.
+//!
+//! Requests without `"jsonrpc"` use the original format and get the original
+//! answers, `{"id":1,"result":...}` or `{"id":1,"error":"message"}`, so older
+//! frontends keep working unchanged.
+//!
+//! Every error also carries a stable name from `GitBrowserError::code`, such
+//! as `"bookmark.duplicate_url"`: in `error.data.code`, or in `error_code`
+//! next to the message in the original format. Frontends branch on it rather
+//! than on the English message.
+
+use serde_json::{json, Value};
+
+use crate::types::errors::{ErrorKind, GitBrowserError};
+
+/// Invalid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// Not a request object, or an empty batch.
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// A parameter is missing or has the wrong type.
+pub const INVALID_PARAMS: i64 = -32602;
+/// The method ran and failed; the message says why.
+pub const APPLICATION_ERROR: i64 = -32000;
+/// Too many requests; see the server's rate limit.
+pub const RATE_LIMITED: i64 = -32001;
+
+/// The client has not authenticated; see `rpc_auth`.
+pub const UNAUTHORIZED: i64 = -32002;
+/// A request or response is over the size limit; see `rpc_limits`.
+pub const TOO_LARGE: i64 = -32003;
+/// What the method was asked about does not exist.
+pub const NOT_FOUND: i64 = -32004;
+pub const ALREADY_EXISTS: i64 = -32005;
+/// The vault or database needs its password, or the key is wrong.
+pub const LOCKED: i64 = -32006;
+/// Not allowed: a missing permission, consent or GitHub login.
+pub const PERMISSION_DENIED: i64 = -32007;
+/// A request the method made over the network failed.
+pub const NETWORK_ERROR: i64 = -32008;
+/// A feature or service this build or system does not have.
+pub const UNAVAILABLE: i64 = -32009;
+
+/// Which format a request came in, and so which one its answer takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    /// The original format, without `"jsonrpc"`.
+    Legacy,
+    V2,
+}
+
+/// One method call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub protocol: Protocol,
+    /// `None` for a JSON-RPC 2.0 notification.
+    pub id: Option<Value>,
+    pub method: String,
+    pub params: Value,
+}
+
+/// A line read from the client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incoming {
+    Single(Result<Call, Value>),
+    Batch(Vec<Result<Call, Value>>),
+}
+
+impl Call {
+    /// A notification gets no answer.
+    pub fn is_notification(&self) -> bool {
+        self.protocol == Protocol::V2 && self.id.is_none()
+    }
+
+    /// The answer to this call carrying `result`, or `None` for a notification.
+    pub fn response(&self, result: &Result<Value, GitBrowserError>) -> Option<Value> {
+        if self.is_notification() {
+            return None;
+        }
+        let id = self.id.clone().unwrap_or(Value::Null);
+        Some(match (self.protocol, result) {
+            (Protocol::Legacy, Ok(value)) => json!({"id": id, "result": value}),
+            (Protocol::Legacy, Err(error)) => {
+                json!({"id": id, "error": error.to_string(), "error_code": error_codes(error).1})
+            }
+            (Protocol::V2, Ok(value)) => json!({"jsonrpc": "2.0", "id": id, "result": value}),
+            (Protocol::V2, Err(error)) => {
+                let (code, name) = error_codes(error);
+                let mut response = error_response(id, code, &error.to_string());
+                response["error"]["data"] = json!({"code": name});
+                response
+            }
+        })
+    }
+
+    /// The answer to this call failing with `error`, with `data` describing
+    /// it, e.g. the limit that was hit, next to the error's `code`. The
+    /// original format has no room for `data`.
+    pub fn error_with_data(&self, error: GitBrowserError, data: Value) -> Option<Value> {
+        let mut response = self.response(&Err(error))?;
+        if self.protocol == Protocol::V2 {
+            let code = response["error"]["data"]["code"].take();
+            response["error"]["data"] = data;
+            if let Some(data) = response["error"]["data"].as_object_mut() {
+                data.entry("code").or_insert(code);
+            }
+        }
+        Some(response)
+    }
+}
+
+/// Parses one line into a call or a batch of calls. A request that cannot be
+/// used comes back as the error response to send.
+pub fn parse_line(line: &str) -> Result<Incoming, Value> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|e| error_response(Value::Null, PARSE_ERROR, &format!("parse error: {}", e)))?;
+    match value {
+        Value::Array(items) if items.is_empty() => {
+            Err(error_response(Value::Null, INVALID_REQUEST, "invalid request: empty batch"))
+        }
+        Value::Array(items) => Ok(Incoming::Batch(items.into_iter().map(parse_call).collect())),
+        value => Ok(Incoming::Single(parse_call(value))),
+    }
+}
+
+fn parse_call(value: Value) -> Result<Call, Value> {
+    let Value::Object(mut request) = value else {
+        return Err(error_response(Value::Null, INVALID_REQUEST, "invalid request: not an object"));
+    };
+    let id = request.remove("id");
+    let protocol = match request.get("jsonrpc") {
+        None => Protocol::Legacy,
+        Some(v) if v == "2.0" => Protocol::V2,
+        Some(_) => {
+            let id = id.unwrap_or(Value::Null);
+            return Err(error_response(id, INVALID_REQUEST, "invalid request: jsonrpc must be \"2.0\""));
+        }
+    };
+    let method = match request.remove("method") {
+        Some(Value::String(method)) => method,
+        _ if protocol == Protocol::Legacy => String::new(),
+        _ => return Err(error_response(id.unwrap_or(Value::Null), INVALID_REQUEST, "invalid request: missing method")),
+    };
+    let params = match request.remove("params") {
+        None | Some(Value::Null) => json!({}),
+        Some(params @ (Value::Object(_) | Value::Array(_))) => params,
+        Some(_) if protocol == Protocol::Legacy => json!({}),
+        Some(_) => {
+            let id = id.unwrap_or(Value::Null);
+            return Err(error_response(id, INVALID_PARAMS, "invalid params: must be an object or array"));
+        }
+    };
+    // The original format answered requests without id with `"id": null`
+    let id = match protocol {
+        Protocol::Legacy => Some(id.unwrap_or(Value::Null)),
+        Protocol::V2 => id,
+    };
+    Ok(Call { protocol, id, method, params })
+}
+
+/// A JSON-RPC 2.0 error response.
+pub fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+/// The numeric code and the stable name `error` is answered with. Module
+/// errors take the code of their `ErrorKind`.
+pub fn error_codes(error: &GitBrowserError) -> (i64, &'static str) {
+    let code = match error {
+        GitBrowserError::MethodNotFound(_) => METHOD_NOT_FOUND,
+        GitBrowserError::InvalidParams(_) => INVALID_PARAMS,
+        GitBrowserError::RateLimited => RATE_LIMITED,
+        GitBrowserError::Unauthorized => UNAUTHORIZED,
+        GitBrowserError::TooLarge(_) => TOO_LARGE,
+        error => match error.kind() {
+            ErrorKind::NotFound => NOT_FOUND,
+            ErrorKind::AlreadyExists => ALREADY_EXISTS,
+            ErrorKind::InvalidInput => INVALID_PARAMS,
+            ErrorKind::Locked => LOCKED,
+            ErrorKind::PermissionDenied => PERMISSION_DENIED,
+            ErrorKind::RateLimited => RATE_LIMITED,
+            ErrorKind::Network => NETWORK_ERROR,
+            ErrorKind::Unavailable => UNAVAILABLE,
+            ErrorKind::Failed => APPLICATION_ERROR,
+        },
+    };
+    (code, error.code())
+}
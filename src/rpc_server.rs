@@ -1,43 +1,280 @@
-//! GitBrowser RPC Server — JSON-RPC over stdin/stdout for Electron integration.
+//! GitBrowser RPC Server — JSON-RPC over stdin/stdout for Electron integration,
+//! and over a local socket (`rpc_socket`) for other clients; the `ready`
+//! event names the socket and the file holding the token socket clients
+//! authenticate with first (`rpc_auth`).
 //!
-//! Protocol: one JSON object per line (newline-delimited JSON).
-//! Request:  {"id":1, "method":"bookmark.add", "params":{"url":"...","title":"..."}}
-//! Response: {"id":1, "result":{...}} or {"id":1, "error":"..."}
+//! Protocol: one JSON request, or a batch array of them, per line
+//! (newline-delimited JSON); see `gitbrowser::rpc_protocol`.
+//! Request:  {"jsonrpc":"2.0", "id":1, "method":"bookmark.add", "params":{"url":"...","title":"..."}}
+//! Response: {"jsonrpc":"2.0", "id":1, "result":{...}} or {"jsonrpc":"2.0", "id":1, "error":{"code":-32000, "message":"..."}}
+//! Requests without "jsonrpc" get the original {"id":1, "result":...} / {"id":1, "error":"..."}.
+//! Events: `subscribe {topics}` pushes {"jsonrpc":"2.0", "method":"event", "params":{...}}
+//! to the connection that subscribed, until `unsubscribe` or disconnect.
+//! Each connection is held to the rate and size limits in `gitbrowser::rpc_limits`.
+//! Streaming methods send their result in parts first; see `gitbrowser::rpc_stream`.
+//! Takes the arguments in `gitbrowser::cli`; with `--headless` alone, stdin
+//! is left alone and only socket clients are served, until Ctrl+C.
 
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::io::{self, BufRead, Write};
-use std::time::Instant;
+use std::time::Duration;
 
 use gitbrowser::app::App;
-use gitbrowser::rpc_handler::handle_method;
+use gitbrowser::cli::{CliArgs, USAGE};
+use gitbrowser::platform::{notify, NotificationAction};
+use gitbrowser::rpc_auth::{self, AuthToken};
+use gitbrowser::rpc_handler::{
+    complete_device_login, handle_method, handle_streaming, handle_subscription, poll_github_notifications,
+    refresh_github_repos,
+};
+use gitbrowser::rpc_limits::{
+    bounded_lines, oversized_response, Line, RateLimiter, MAX_REQUESTS_PER_SECOND, MAX_REQUEST_BYTES,
+    MAX_RESPONSE_BYTES,
+};
+use gitbrowser::rpc_protocol::{parse_line, Call, Incoming};
+use gitbrowser::rpc_methods;
+use gitbrowser::rpc_socket::{self, Sink};
+use gitbrowser::rpc_stream::Stream;
+use gitbrowser::services::update_manager::{check_releases, UpdateManagerTrait};
+use gitbrowser::types::errors::{EncryptionError, GitBrowserError, ProfileError};
 
 use serde_json::{json, Value};
 
-/// Simple rate limiter: max requests per second per method.
-struct RateLimiter {
-    window_start: Instant,
-    request_count: u32,
-    max_per_second: u32,
+/// Methods answered from a thread of their own so the requests behind them
+/// are not held up; responses can arrive out of order (matched by `id`).
+const SLOW_METHODS: &[&str] = &["history.search", "history.recent", "sync.now", "db.compact"];
+
+/// Whether `method` is answered off the request loop.
+fn is_slow(method: &str) -> bool {
+    SLOW_METHODS.contains(&method) || rpc_methods::find(method).is_some_and(|m| m.streaming)
+}
+
+/// Identifies each connection (stdin or socket client) for its subscriptions.
+static NEXT_CLIENT: AtomicU64 = AtomicU64::new(1);
+
+/// Opens the app with `open`, given the master password once known; a
+/// database encrypted with it is unlocked by the frontend with
+/// `database.unlock {password}` after a `database.locked` event. Exits if
+/// stdin closes first, or if the profile is open in another instance.
+fn open_app(open: impl Fn(Option<&str>) -> Result<App, Box<dyn std::error::Error>>) -> App {
+    let locked = |e: &(dyn std::error::Error + 'static)| {
+        matches!(e.downcast_ref::<EncryptionError>(), Some(EncryptionError::PasswordRequired | EncryptionError::WrongKey))
+    };
+    match open(None) {
+        Ok(app) => return app,
+        Err(e) if locked(e.as_ref()) => {}
+        Err(e) if matches!(e.downcast_ref::<ProfileError>(), Some(ProfileError::InUse(_))) => {
+            eprintln!("[PROFILE] {}", e);
+            std::process::exit(1);
+        }
+        Err(e) => panic!("Failed to initialize GitBrowser: {}", e),
+    }
+
+    println!("{}", json!({"event": "database.locked"}));
+    io::stdout().flush().unwrap();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let Ok(req) = serde_json::from_str::<Value>(&line) else { continue };
+        let id = req.get("id").cloned().unwrap_or(Value::Null);
+        let password = req.get("params").and_then(|p| p.get("password")).and_then(|v| v.as_str());
+        let (response, app) = match (req.get("method").and_then(|v| v.as_str()), password) {
+            (Some("database.unlock"), Some(password)) => match open(Some(password)) {
+                Ok(app) => (json!({"id": id, "result": {"ok": true}}), Some(app)),
+                Err(e) if locked(e.as_ref()) => (json!({"id": id, "error": e.to_string()}), None),
+                Err(e) => panic!("Failed to initialize GitBrowser: {}", e),
+            },
+            (Some("database.unlock"), None) => (json!({"id": id, "error": "missing password"}), None),
+            _ => (json!({"id": id, "error": EncryptionError::PasswordRequired.to_string()}), None),
+        };
+        println!("{}", response);
+        io::stdout().flush().unwrap();
+        if let Some(app) = app {
+            return app;
+        }
+    }
+    std::process::exit(0);
+}
+
+/// Writes one line to stdout; responses and events come from several threads.
+fn emit(value: &Value) {
+    let mut out = io::stdout().lock();
+    let _ = writeln!(out, "{}", value);
+    let _ = out.flush();
+}
+
+/// Runs `call` and returns its response (`None` for a notification), then
+/// starts any background work the method continues with, reporting to `sink`.
+fn run_call(app: &Arc<Mutex<App>>, client: u64, call: &Call, sink: &Sink) -> Option<Value> {
+    let mut stream = Stream::new(call, sink.clone());
+    let result = handle_subscription(app, client, &call.method, &call.params, sink)
+        .or_else(|| handle_streaming(app, &call.method, &call.params, &mut stream))
+        .unwrap_or_else(|| handle_method(app, &call.method, &call.params));
+    let response = match result.as_ref().ok().and_then(oversized_response) {
+        Some(size) => {
+            call.error_with_data(GitBrowserError::TooLarge("response"), json!({"size": size, "limit": MAX_RESPONSE_BYTES}))
+        }
+        None => call.response(&result),
+    };
+
+    // Device login answers with the code at once; the token is awaited in the background
+    if let ("github.login_device", Ok(started)) = (call.method.as_str(), result) {
+        let login_app = app.clone();
+        let params = call.params.clone();
+        let sink = sink.clone();
+        std::thread::spawn(move || {
+            complete_device_login(&login_app, &params, &started, |event| sink(&event));
+        });
+    }
+    response
+}
+
+/// Runs a batch in order; `Err` items are answers already decided (`None`
+/// for a notification). Returns the array of responses, if any.
+fn run_batch(
+    app: &Arc<Mutex<App>>,
+    client: u64,
+    items: Vec<Result<Call, Option<Value>>>,
+    sink: &Sink,
+) -> Option<Value> {
+    let responses: Vec<Value> = items
+        .into_iter()
+        .filter_map(|item| match item {
+            Ok(call) => run_call(app, client, &call, sink),
+            Err(response) => response,
+        })
+        .collect();
+    if responses.is_empty() {
+        None
+    } else {
+        Some(Value::Array(responses))
+    }
 }
 
-impl RateLimiter {
-    fn new(max_per_second: u32) -> Self {
-        Self { window_start: Instant::now(), request_count: 0, max_per_second }
+/// Answers the requests in `lines` through `sink` until the client goes
+/// away; stdin and every socket client are served alike. Its event
+/// subscriptions end with it. With a `token`, the client must authenticate
+/// with it first and is dropped if it does not.
+fn serve(app: &Arc<Mutex<App>>, mut lines: impl Iterator<Item = Line>, sink: Sink, token: Option<&AuthToken>) {
+    let client = NEXT_CLIENT.fetch_add(1, Ordering::Relaxed);
+    if let Some(token) = token {
+        let Some(first) = lines.find(|line| !matches!(line, Ok(l) if l.trim().is_empty())) else { return };
+        let (response, accepted) = match first {
+            Ok(first) => match rpc_auth::handshake(token, &first) {
+                Ok(response) => (response, true),
+                Err(response) => (response, false),
+            },
+            Err(too_long) => (Some(too_long.response(MAX_REQUEST_BYTES)), false),
+        };
+        if let Some(response) = response {
+            sink(&response);
+        }
+        if !accepted {
+            eprintln!("[RPC] rejected unauthenticated socket client");
+            return;
+        }
     }
+    // 2.10: Rate limiting — max 200 RPC requests per second to prevent DoS
+    let mut rate_limiter = RateLimiter::new(MAX_REQUESTS_PER_SECOND);
 
-    /// Returns true if the request is allowed, false if rate-limited.
-    fn check(&mut self) -> bool {
-        let elapsed = self.window_start.elapsed();
-        if elapsed.as_secs() >= 1 {
-            self.window_start = Instant::now();
-            self.request_count = 0;
+    for line in lines {
+        let line = match line {
+            Ok(line) => line,
+            Err(too_long) => {
+                sink(&too_long.response(MAX_REQUEST_BYTES));
+                continue;
+            }
+        };
+        if line.trim().is_empty() { continue; }
+
+        let incoming = match parse_line(&line) {
+            Ok(incoming) => incoming,
+            Err(response) => {
+                sink(&response);
+                continue;
+            }
+        };
+
+        let batch = matches!(incoming, Incoming::Batch(_));
+        // 2.10: Check rate limit before processing; every call in a batch counts
+        let mut admit = |item: Result<Call, Value>| match item {
+            Ok(call) if !rate_limiter.check() => {
+                let limit = json!({"limit_per_second": rate_limiter.max_per_second()});
+                Err(call.error_with_data(GitBrowserError::RateLimited, limit))
+            }
+            Ok(call) => Ok(call),
+            Err(response) => Err(Some(response)),
+        };
+        let items: Vec<Result<Call, Option<Value>>> = match incoming {
+            Incoming::Single(item) => vec![admit(item)],
+            Incoming::Batch(items) => items.into_iter().map(&mut admit).collect(),
+        };
+
+        // Batches holding a slow or streaming method run on a thread of their own too
+        let slow = items.iter().any(|item| matches!(item, Ok(call) if is_slow(&call.method)));
+        let sink = sink.clone();
+        let respond = move |app: &Arc<Mutex<App>>| {
+            let response = if batch {
+                run_batch(app, client, items, &sink)
+            } else {
+                match items.into_iter().next() {
+                    Some(Ok(call)) => run_call(app, client, &call, &sink),
+                    Some(Err(response)) => response,
+                    None => None,
+                }
+            };
+            if let Some(response) = response {
+                sink(&response);
+            }
+        };
+        if slow {
+            let slow_app = app.clone();
+            std::thread::spawn(move || respond(&slow_app));
+        } else {
+            respond(app);
         }
-        self.request_count += 1;
-        self.request_count <= self.max_per_second
+    }
+
+    if let Ok(a) = app.lock() {
+        a.events.remove_client(client);
     }
 }
 
 fn main() {
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("gitbrowser-rpc: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if args.version {
+        println!("gitbrowser-rpc {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
+
+    {
+        // Swap in a staged update, or roll back one that never came up, before opening the profile
+        use gitbrowser::services::update_installer::{relaunch, UpdateInstaller, UpdateInstallerTrait};
+        use gitbrowser::types::update::StartupAction;
+        let mut installer = UpdateInstaller::for_current_exe();
+        match installer.on_startup(env!("CARGO_PKG_VERSION")) {
+            Ok(StartupAction::Continue) => {}
+            Ok(action) => {
+                eprintln!("[UPDATE] {:?}, restarting", action);
+                match relaunch(installer.executable()) {
+                    Ok(()) => return,
+                    Err(e) => eprintln!("[UPDATE] {}", e),
+                }
+            }
+            Err(e) => eprintln!("[UPDATE] {}", e),
+        }
+    }
+
     // BUG-08: Use absolute path for DB — prefer GITBROWSER_DATA_DIR, fallback to exe directory
     let db_path = if let Ok(dir) = std::env::var("GITBROWSER_DATA_DIR") {
         std::path::PathBuf::from(dir).join("gitbrowser.db")
@@ -46,54 +283,249 @@ fn main() {
     } else {
         std::path::PathBuf::from("gitbrowser.db")
     };
-    let app = Mutex::new(App::new(db_path.to_str().unwrap_or("gitbrowser.db")).expect("Failed to initialize GitBrowser"));
+    let db_path = db_path.to_str().unwrap_or("gitbrowser.db").to_string();
+    // `--profile <name>` runs an isolated profile instead of the default database
+    let mut app = open_app(|password| args.open_app(&db_path, password));
+    if let Err(e) = args.apply_settings(&mut app) {
+        eprintln!("[SETTINGS] {}", e);
+        std::process::exit(2);
+    }
+    args.open_urls(&mut app);
+    let app = Arc::new(Mutex::new(app));
 
-    // Signal ready
-    let ready = json!({"event":"ready","version":env!("CARGO_PKG_VERSION")});
-    println!("{}", ready);
-    io::stdout().flush().unwrap();
+    // Write panics as crash reports and log the ones from the last run
+    {
+        use gitbrowser::services::crash_recovery::install_panic_hook;
+        let mut a = app.lock().unwrap();
+        a.check_crash_recovery();
+        let crash_app = app.clone();
+        install_panic_hook(a.crash_recovery.reports_dir().to_path_buf(), move || {
+            use gitbrowser::managers::tab_manager::TabManagerTrait;
+            let a = crash_app.try_lock().ok()?;
+            a.tab_manager.get_active_tab().map(|t| t.url.clone())
+        });
+    }
 
-    // 2.10: Rate limiting — max 200 RPC requests per second to prevent DoS
-    let mut rate_limiter = RateLimiter::new(200);
+    // Follow OS dark/light changes and notify the frontend with the new CSS variables
+    if let Some(dark) = gitbrowser::platform::system_prefers_dark() {
+        if let Ok(mut a) = app.lock() {
+            a.set_system_dark_mode(dark);
+        }
+    }
+    let theme_app = app.clone();
+    gitbrowser::platform::watch_system_theme(Duration::from_secs(3), move |dark| {
+        let vars = match theme_app.lock() {
+            Ok(mut a) => a.set_system_dark_mode(dark),
+            Err(_) => return false,
+        };
+        if let Some(vars) = vars {
+            let event = json!({"event": "theme.changed", "dark": dark, "variables": vars});
+            let mut out = io::stdout().lock();
+            let _ = writeln!(out, "{}", event);
+            let _ = out.flush();
+        }
+        true
+    });
 
-    let stdin = io::stdin();
-    for line in stdin.lock().lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => break,
+    let accent_app = app.clone();
+    gitbrowser::platform::watch_system_accent(Duration::from_secs(3), move |color| {
+        let vars = match accent_app.lock() {
+            Ok(mut a) => a.set_system_accent_color(&color),
+            Err(_) => return false,
         };
-        if line.trim().is_empty() { continue; }
+        if let Some(vars) = vars {
+            let event = json!({"event": "theme.changed", "accent": color, "variables": vars});
+            let mut out = io::stdout().lock();
+            let _ = writeln!(out, "{}", event);
+            let _ = out.flush();
+        }
+        true
+    });
 
-        let req: Value = match serde_json::from_str(&line) {
-            Ok(v) => v,
-            Err(e) => {
-                let err = json!({"id":null,"error":format!("parse error: {}",e)});
-                println!("{}", err);
-                io::stdout().flush().unwrap();
-                continue;
+    // Hold back background work on battery while performance.battery_saver is on
+    let power_app = app.clone();
+    gitbrowser::platform::watch_power_status(Duration::from_secs(30), move |power| {
+        let saving = match power_app.lock() {
+            Ok(mut a) => a.set_power_status(power).then(|| a.saving_power()),
+            Err(_) => return false,
+        };
+        if let Some(saving) = saving {
+            let event = json!({
+                "event": "power.changed",
+                "on_battery": power.on_battery,
+                "low_power": power.low_power,
+                "battery_saver": saving,
+            });
+            let mut out = io::stdout().lock();
+            let _ = writeln!(out, "{}", event);
+            let _ = out.flush();
+        }
+        true
+    });
+
+    // Drive the theme schedule; the UTC offset is refreshed each tick to follow DST
+    let schedule_app = app.clone();
+    std::thread::spawn(move || loop {
+        use gitbrowser::services::theme_scheduler::ThemeSchedulerTrait;
+        let offset = gitbrowser::platform::local_utc_offset_minutes();
+        let vars = match schedule_app.lock() {
+            Ok(mut a) => {
+                if let Some(offset) = offset {
+                    a.theme_scheduler.set_utc_offset_minutes(offset);
+                }
+                a.tick_theme_schedule()
             }
+            Err(_) => break,
         };
+        if let Some(vars) = vars {
+            let event = json!({"event": "theme.changed", "scheduled": true, "variables": vars});
+            let mut out = io::stdout().lock();
+            let _ = writeln!(out, "{}", event);
+            let _ = out.flush();
+        }
+        std::thread::sleep(Duration::from_secs(60));
+    });
 
-        let id = req.get("id").cloned().unwrap_or(Value::Null);
+    // Keep rotating database backups and run maintenance; checked hourly,
+    // both done daily, maintenance only off the battery saver
+    let backup_app = app.clone();
+    std::thread::spawn(move || loop {
+        let maintenance = backup_app.lock().ok().and_then(|a| {
+            a.run_scheduled_backup();
+            (!a.saving_power()).then(|| a.run_scheduled_maintenance())
+        });
+        if let Some(task) = maintenance {
+            let _ = task.wait();
+        }
+        std::thread::sleep(Duration::from_secs(60 * 60));
+    });
+
+    // Poll GitHub notifications (the service itself honours X-Poll-Interval)
+    // and keep the `gh` repository cache fresh; slower on the battery saver
+    let notifications_app = app.clone();
+    std::thread::spawn(move || loop {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        poll_github_notifications(
+            &notifications_app,
+            now,
+            |title, body, url| {
+                // "Open" asks the frontend to show the thread
+                let actions = url
+                    .map(|url| {
+                        let event = json!({"event": "github.open", "url": url});
+                        vec![NotificationAction::new("Open", move || {
+                            let mut out = io::stdout().lock();
+                            let _ = writeln!(out, "{}", event);
+                            let _ = out.flush();
+                        })]
+                    })
+                    .unwrap_or_default();
+                notify(title, body, actions);
+            },
+            |event| {
+                let mut out = io::stdout().lock();
+                let _ = writeln!(out, "{}", event);
+                let _ = out.flush();
+            },
+        );
+        refresh_github_repos(&notifications_app, now);
+        let interval = Duration::from_secs(30);
+        std::thread::sleep(notifications_app.lock().map_or(interval, |a| a.background_interval(interval)));
+    });
 
-        // 2.10: Check rate limit before processing
-        if !rate_limiter.check() {
-            let response = json!({"id": id, "error": "rate limit exceeded"});
-            println!("{}", response);
-            io::stdout().flush().unwrap();
-            continue;
+    // Look for updates every six hours while updates.auto_check is on; each
+    // new version is announced once
+    let update_app = app.clone();
+    std::thread::spawn(move || {
+        let mut announced: Option<String> = None;
+        loop {
+            let check = update_app.lock().ok().filter(|a| a.update_manager.is_auto_check_enabled()).map(|a| {
+                (a.update_manager.channel(), a.update_manager.get_current_version().to_string())
+            });
+            if let Some(Ok(Some(update))) = check.map(|(channel, version)| check_releases(channel, &version)) {
+                if announced.as_deref() != Some(update.version.as_str()) {
+                    notify(
+                        &format!("GitBrowser {} is available", update.version),
+                        "Open Settings to download and install it.",
+                        Vec::new(),
+                    );
+                    let event = json!({"event": "update.available", "update": update});
+                    let mut out = io::stdout().lock();
+                    let _ = writeln!(out, "{}", event);
+                    let _ = out.flush();
+                    announced = Some(update.version);
+                }
+            }
+            std::thread::sleep(Duration::from_secs(6 * 60 * 60));
         }
+    });
 
-        let method = req.get("method").and_then(|v| v.as_str()).unwrap_or("");
-        let params = req.get("params").cloned().unwrap_or(json!({}));
+    // Other local clients connect through a socket (a named pipe on Windows)
+    // next to the profile's data, each served like stdin
+    let socket_dir = match app.lock().ok().and_then(|a| a.profile().map(|p| p.paths.dir.clone())) {
+        Some(dir) => dir,
+        None => std::path::Path::new(&db_path).parent().unwrap_or(std::path::Path::new(".")).to_path_buf(),
+    };
+    let token = Arc::new(AuthToken::generate());
+    let token_file = match token.write_file(&socket_dir) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            eprintln!("[RPC] cannot write token file: {}", e);
+            None
+        }
+    };
+    let socket_app = app.clone();
+    let on_connect: rpc_socket::OnConnect =
+        Arc::new(move |lines, sink| serve(&socket_app, lines, sink, Some(&token)));
+    let socket = match rpc_socket::listen(&rpc_socket::endpoint_for(&socket_dir), on_connect) {
+        Ok(socket) => Some(socket),
+        Err(e) => {
+            eprintln!("[RPC] local socket unavailable: {}", e);
+            None
+        }
+    };
 
-        let result = handle_method(&app, method, &params);
+    // Signal ready
+    let ready = json!({
+        "event": "ready",
+        "version": env!("CARGO_PKG_VERSION"),
+        "socket": socket.as_ref().map(|s| s.endpoint()),
+        "token_file": token_file.as_ref().map(|f| f.path()),
+    });
+    println!("{}", ready);
+    {
+        // The server came up, so an update just installed is good to keep
+        use gitbrowser::services::update_installer::UpdateInstallerTrait;
+        if let Some(Err(e)) = app.lock().ok().map(|mut a| a.update_installer.confirm_started()) {
+            eprintln!("[UPDATE] {}", e);
+        }
+    }
+    // After a crash, let the frontend offer the last session
+    if let Some(prompt) = app.lock().ok().and_then(|a| a.crash_restore_prompt()) {
+        println!("{}", prompt);
+    }
+    io::stdout().flush().unwrap();
 
-        let response = match result {
-            Ok(val) => json!({"id": id, "result": val}),
-            Err(err) => json!({"id": id, "error": err}),
-        };
-        println!("{}", response);
-        io::stdout().flush().unwrap();
+    if args.headless && !args.rpc {
+        // No frontend on stdin: serve socket clients until interrupted
+        let interrupted = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .and_then(|runtime| runtime.block_on(tokio::signal::ctrl_c()));
+        if let Err(e) = interrupted {
+            eprintln!("[RPC] cannot wait for Ctrl+C: {}", e);
+        }
+    } else {
+        let stdout_sink: Sink = Arc::new(emit);
+        // Only our parent can write to stdin, so it is trusted without a token
+        serve(&app, bounded_lines(io::stdin().lock(), MAX_REQUEST_BYTES), stdout_sink, None);
     }
+
+    // stdin closed (the frontend quit) or interrupted
+    app.lock().unwrap().shutdown();
+    drop(socket);
+    drop(token_file);
 }
@@ -0,0 +1,280 @@
+//! Local socket transport for the RPC protocol.
+//!
+//! Besides stdin/stdout, `gitbrowser-rpc` listens on a Unix domain socket
+//! (Linux, macOS) or a named pipe (Windows) so other local clients can talk
+//! to the running backend at the same time. Each connection speaks the same
+//! line-delimited protocol (`rpc_protocol`) and is served on a thread of its
+//! own. Only the current user can connect: the socket is bound in a private
+//! directory and is mode `0600` before it appears at its path, and the
+//! pipe's DACL grants access to its owner alone. Lines are read
+//! within `rpc_limits::MAX_REQUEST_BYTES`.
+
+use std::io;
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::rpc_limits::Line;
+
+/// Where a response or event for one client is written.
+pub type Sink = Arc<dyn Fn(&Value) + Send + Sync>;
+
+/// Request lines from one client, until it disconnects.
+pub type Lines = Box<dyn Iterator<Item = Line> + Send>;
+
+/// Called on its own thread for every client that connects.
+pub type OnConnect = Arc<dyn Fn(Lines, Sink) + Send + Sync>;
+
+/// The endpoint for the backend whose data lives in `dir`, so each profile
+/// gets its own: `<dir>/rpc.sock`, or on Windows a pipe named after `dir`.
+pub fn endpoint_for(dir: &Path) -> String {
+    endpoint_named(dir, "rpc")
+}
+
+/// Endpoint `name` of the profile whose data lives in `dir`:
+/// `<dir>/<name>.sock`, or on Windows the pipe `gitbrowser-<name>-<dir>`.
+pub fn endpoint_named(dir: &Path, name: &str) -> String {
+    #[cfg(unix)]
+    {
+        dir.join(format!("{}.sock", name)).to_string_lossy().into_owned()
+    }
+    #[cfg(windows)]
+    {
+        let dir: String = dir
+            .to_string_lossy()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect();
+        format!(r"\\.\pipe\gitbrowser-{}-{}", name, dir)
+    }
+}
+
+/// Listens on `endpoint` until dropped; the socket file is removed then.
+pub struct SocketListener {
+    endpoint: String,
+}
+
+impl SocketListener {
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+impl Drop for SocketListener {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            let _ = std::fs::remove_file(&self.endpoint);
+        }
+    }
+}
+
+/// Starts accepting clients on `endpoint`, calling `on_connect` for each.
+///
+/// # Errors
+/// `AddrInUse` if another backend is listening there; otherwise the error
+/// from creating the socket or pipe.
+pub fn listen(endpoint: &str, on_connect: OnConnect) -> io::Result<SocketListener> {
+    #[cfg(unix)]
+    {
+        unix::listen(PathBuf::from(endpoint), on_connect)?;
+    }
+    #[cfg(windows)]
+    {
+        windows::listen(endpoint, on_connect)?;
+    }
+    Ok(SocketListener { endpoint: endpoint.to_string() })
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::io::{self, Write};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    use super::{Lines, OnConnect, Sink};
+    use crate::rpc_limits::{bounded_lines, MAX_REQUEST_BYTES};
+
+    pub fn listen(path: PathBuf, on_connect: OnConnect) -> io::Result<()> {
+        if path.exists() {
+            // Left behind by a backend that did not shut down, unless one still answers
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{} is in use", path.display())));
+            }
+            std::fs::remove_file(&path)?;
+        }
+        let listener = bind_private(&path)?;
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let on_connect = on_connect.clone();
+                std::thread::spawn(move || {
+                    let Ok(writer) = stream.try_clone() else { return };
+                    let writer = Mutex::new(writer);
+                    let sink: Sink = Arc::new(move |value| {
+                        if let Ok(mut out) = writer.lock() {
+                            let _ = writeln!(out, "{}", value);
+                            let _ = out.flush();
+                        }
+                    });
+                    let lines: Lines = Box::new(bounded_lines(stream, MAX_REQUEST_BYTES));
+                    on_connect(lines, sink);
+                });
+            }
+        });
+        Ok(())
+    }
+
+    /// Binds the socket inside a fresh `0700` directory next to `path`,
+    /// makes it `0600` and only then moves it to `path`, so no other user
+    /// can connect while the socket still has the default mode.
+    fn bind_private(path: &Path) -> io::Result<UnixListener> {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let private = path.with_file_name(format!(".{}.{}", name, std::process::id()));
+        if private.exists() {
+            std::fs::remove_dir_all(&private)?;
+        }
+        std::fs::DirBuilder::new().mode(0o700).create(&private)?;
+        let staged = private.join("s");
+        let bound = UnixListener::bind(&staged).and_then(|listener| {
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+            std::fs::rename(&staged, path)?;
+            Ok(listener)
+        });
+        let _ = std::fs::remove_dir_all(&private);
+        bound
+    }
+}
+
+/// Security attributes granting access to the current user alone, for
+/// objects created next to the pipe such as the token file.
+#[cfg(windows)]
+pub(crate) use windows::owner_only;
+
+#[cfg(windows)]
+mod windows {
+    use std::io;
+    use std::sync::{mpsc, Arc};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+    use tokio::sync::mpsc as async_mpsc;
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::SECURITY_ATTRIBUTES;
+
+    use super::{Line, Lines, OnConnect, Sink};
+    use crate::rpc_limits::{LineSplitter, MAX_REQUEST_BYTES};
+
+    /// Protected DACL with full access for the owner only.
+    const OWNER_ONLY_SDDL: &str = "D:P(A;;GA;;;OW)";
+
+    pub fn listen(name: &str, on_connect: OnConnect) -> io::Result<()> {
+        // Created here so a pipe already in use is reported to the caller
+        let first = create(name, true)?;
+        let name = name.to_string();
+        std::thread::Builder::new().name("gitbrowser-rpc-pipe".to_string()).spawn(move || {
+            let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(rt) => rt,
+                Err(e) => return eprintln!("[RPC] {}", e),
+            };
+            rt.block_on(async move {
+                let mut server = first;
+                loop {
+                    if server.connect().await.is_err() {
+                        continue;
+                    }
+                    // The next instance is ready before this client is served
+                    let next = match create(&name, false) {
+                        Ok(next) => next,
+                        Err(e) => return eprintln!("[RPC] {}", e),
+                    };
+                    serve(std::mem::replace(&mut server, next), on_connect.clone());
+                }
+            });
+        })?;
+        Ok(())
+    }
+
+    /// Bridges one pipe client to blocking `Lines` and a `Sink`.
+    fn serve(pipe: NamedPipeServer, on_connect: OnConnect) {
+        let (mut reader, mut writer) = tokio::io::split(pipe);
+        let (line_tx, line_rx) = mpsc::channel::<Line>();
+        let (out_tx, mut out_rx) = async_mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            let mut splitter = LineSplitter::new(MAX_REQUEST_BYTES);
+            let mut chunk = [0u8; 8 * 1024];
+            while let Ok(n @ 1..) = reader.read(&mut chunk).await {
+                for line in splitter.feed(&chunk[..n]) {
+                    if line_tx.send(line).is_err() {
+                        return;
+                    }
+                }
+            }
+            if let Some(line) = splitter.finish() {
+                let _ = line_tx.send(line);
+            }
+        });
+        tokio::spawn(async move {
+            while let Some(line) = out_rx.recv().await {
+                if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err() {
+                    break;
+                }
+            }
+        });
+        let sink: Sink = Arc::new(move |value| {
+            let _ = out_tx.send(format!("{}\n", value));
+        });
+        let lines: Lines = Box::new(line_rx.into_iter());
+        std::thread::spawn(move || on_connect(lines, sink));
+    }
+
+    fn create(name: &str, first: bool) -> io::Result<NamedPipeServer> {
+        owner_only(|attributes| {
+            // SAFETY: `attributes` points at a valid descriptor for the whole call.
+            unsafe {
+                ServerOptions::new()
+                    .first_pipe_instance(first)
+                    .reject_remote_clients(true)
+                    .create_with_security_attributes_raw(name, attributes as *mut _)
+            }
+        })
+    }
+
+    /// Runs `create` with security attributes whose protected DACL grants
+    /// access to the owner alone; the descriptor is freed afterwards.
+    pub fn owner_only<T>(create: impl FnOnce(*mut SECURITY_ATTRIBUTES) -> io::Result<T>) -> io::Result<T> {
+        let sddl: Vec<u16> = OWNER_ONLY_SDDL.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut descriptor = std::ptr::null_mut();
+        // SAFETY: `sddl` is NUL-terminated and `descriptor` receives a
+        // LocalAlloc'd descriptor, freed below once the object holds a copy.
+        let converted = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                std::ptr::null_mut(),
+            )
+        };
+        if converted == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: descriptor,
+            bInheritHandle: 0,
+        };
+        let created = create(&mut attributes);
+        // SAFETY: allocated by ConvertStringSecurityDescriptorToSecurityDescriptorW.
+        unsafe { LocalFree(descriptor) };
+        created
+    }
+}
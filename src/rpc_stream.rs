@@ -0,0 +1,69 @@
+//! Chunked results for long RPC operations.
+//!
+//! A method registered as streaming (`MethodSpec::streaming`) sends its
+//! result in parts while it works instead of one line at the end. Each part
+//! is a notification tied to the request by its `id`, numbered from 0 and
+//! carrying the progress so far:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","method":"stream","params":{"id":5,"seq":0,"progress":{"done":500,"total":1200},"data":{...}}}
+//! ```
+//!
+//! The usual response to the request follows the last part and ends the
+//! stream; an error response ends it early. Requests in the original format
+//! get `{"event":"stream","id":5,"seq":0,...}` instead, and a notification
+//! (no `id`) has nowhere for the parts to go, so they are dropped.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::rpc_protocol::{Call, Protocol};
+use crate::services::event_hub::Deliver;
+
+/// Method name of the notifications carrying a part.
+pub const STREAM_METHOD: &str = "stream";
+
+/// How far a streaming operation has got; `total` when it is known.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Progress {
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+/// Sends the parts of one call's result to its client.
+pub struct Stream {
+    id: Option<Value>,
+    protocol: Protocol,
+    deliver: Deliver,
+    seq: u64,
+}
+
+impl Stream {
+    pub fn new(call: &Call, deliver: Deliver) -> Self {
+        let id = if call.is_notification() { None } else { call.id.clone() };
+        Self { id, protocol: call.protocol, deliver, seq: 0 }
+    }
+
+    /// Sends the next part of the result.
+    pub fn chunk(&mut self, data: Value, progress: Progress) {
+        let Some(id) = &self.id else { return };
+        let part = json!({"id": id, "seq": self.seq, "progress": progress, "data": data});
+        let message = match self.protocol {
+            Protocol::V2 => json!({"jsonrpc": "2.0", "method": STREAM_METHOD, "params": part}),
+            Protocol::Legacy => {
+                let mut event = json!({"event": STREAM_METHOD});
+                if let (Some(event), Value::Object(part)) = (event.as_object_mut(), part) {
+                    event.extend(part);
+                }
+                event
+            }
+        };
+        (self.deliver)(&message);
+        self.seq += 1;
+    }
+
+    /// Parts sent so far.
+    pub fn sent(&self) -> u64 {
+        self.seq
+    }
+}
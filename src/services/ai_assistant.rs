@@ -8,6 +8,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use rusqlite::params;
 use crate::database::connection::Database;
+use crate::platform::keystore;
 use crate::services::crypto_service::{CryptoService, CryptoServiceTrait};
 use crate::types::ai::*;
 use crate::types::credential::EncryptedData;
@@ -42,15 +43,56 @@ pub struct AIAssistant {
 impl AIAssistant {
     pub fn new(db: Arc<Database>) -> Result<Self, CryptoError> {
         let crypto = CryptoService::new();
-        let fallback_key = crypto.derive_key(AI_KEY_PASSPHRASE, AI_KEY_SALT)?;
+        let legacy_key = crypto.derive_key(AI_KEY_PASSPHRASE, AI_KEY_SALT)?;
+        // The OS keystore's data key, as for GitHub tokens.
+        let fallback_key = keystore::data_key().unwrap_or_else(|e| {
+            eprintln!("[AI] {}; encrypting API keys with the built-in key", e);
+            legacy_key.clone()
+        });
         let encryption_key = fallback_key.clone();
-        Ok(Self {
+        let assistant = Self {
             db,
             crypto,
             encryption_key,
             fallback_key,
             active_provider: None,
-        })
+        };
+        if assistant.fallback_key != legacy_key {
+            // API keys saved before the data key existed.
+            assistant.rekey_api_keys(&legacy_key, &assistant.fallback_key)?;
+        }
+        Ok(assistant)
+    }
+
+    /// Re-encrypts every stored API key that `old_key` opens with `new_key`.
+    fn rekey_api_keys(&self, old_key: &[u8], new_key: &[u8]) -> Result<(), CryptoError> {
+        let providers = vec!["openrouter", "openai", "anthropic", "deepseek"];
+        let conn = self.db.connection();
+        for provider in providers {
+            let key_id = format!("ai_key_{}", provider);
+            let result = conn.query_row(
+                "SELECT encrypted_password, iv, auth_tag FROM credentials WHERE id = ?1",
+                rusqlite::params![key_id],
+                |row| {
+                    Ok(EncryptedData {
+                        ciphertext: row.get(0)?,
+                        iv: row.get(1)?,
+                        auth_tag: row.get(2)?,
+                    })
+                },
+            );
+            if let Ok(encrypted) = result {
+                if let Ok(decrypted) = self.crypto.decrypt_aes256gcm(&encrypted, old_key) {
+                    let re_encrypted = self.crypto.encrypt_aes256gcm(&decrypted, new_key)?;
+                    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+                    let _ = conn.execute(
+                        "UPDATE credentials SET encrypted_password = ?1, iv = ?2, auth_tag = ?3, updated_at = ?4 WHERE id = ?5",
+                        rusqlite::params![re_encrypted.ciphertext, re_encrypted.iv, re_encrypted.auth_tag, now, key_id],
+                    );
+                }
+            }
+        }
+        Ok(())
     }
 
     fn provider_name_to_str(name: &AIProviderName) -> &'static str {
@@ -231,32 +273,7 @@ impl AIAssistantTrait for AIAssistant {
 
     fn rekey_with_master(&mut self, master_key: &[u8]) -> Result<(), CryptoError> {
         // Re-encrypt all AI API keys from current key to master key
-        let providers = vec!["openrouter", "openai", "anthropic", "deepseek"];
-        let conn = self.db.connection();
-        for provider in providers {
-            let key_id = format!("ai_key_{}", provider);
-            let result = conn.query_row(
-                "SELECT encrypted_password, iv, auth_tag FROM credentials WHERE id = ?1",
-                rusqlite::params![key_id],
-                |row| {
-                    Ok(EncryptedData {
-                        ciphertext: row.get(0)?,
-                        iv: row.get(1)?,
-                        auth_tag: row.get(2)?,
-                    })
-                },
-            );
-            if let Ok(encrypted) = result {
-                if let Ok(decrypted) = self.crypto.decrypt_aes256gcm(&encrypted, &self.encryption_key) {
-                    let re_encrypted = self.crypto.encrypt_aes256gcm(&decrypted, master_key)?;
-                    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
-                    let _ = conn.execute(
-                        "UPDATE credentials SET encrypted_password = ?1, iv = ?2, auth_tag = ?3, updated_at = ?4 WHERE id = ?5",
-                        rusqlite::params![re_encrypted.ciphertext, re_encrypted.iv, re_encrypted.auth_tag, now, key_id],
-                    );
-                }
-            }
-        }
+        self.rekey_api_keys(&self.encryption_key.clone(), master_key)?;
         self.encryption_key = master_key.to_vec();
         Ok(())
     }
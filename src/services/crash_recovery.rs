@@ -1,11 +1,24 @@
 //! Crash Recovery for GitBrowser.
 //!
 //! Logs crash events and provides session recovery after crashes.
+//!
+//! Crashes are also captured automatically. A panic hook writes a report
+//! (message, backtrace, OS, version, active tab URL) to a file in the
+//! reports directory: the database may be locked by the panicking thread,
+//! so reports are imported into `crash_logs` on the next start instead. A
+//! `running` marker in the same directory catches what no hook sees (a
+//! native crash, a kill): if it is still there on the next start, the
+//! previous run never shut down and an `unclean_exit` entry is logged.
 
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::Location;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use rusqlite::params;
+use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::database::connection::Database;
@@ -14,31 +27,116 @@ use crate::types::errors::CrashError;
 use crate::types::privacy::CrashLogEntry;
 use crate::types::session::SessionData;
 
+/// Crash logs kept; older ones are dropped as new ones come in.
+pub const MAX_CRASH_LOGS: i64 = 50;
+
+/// Longest backtrace kept with a crash, in bytes.
+const MAX_BACKTRACE_BYTES: usize = 64 * 1024;
+
+/// Marker file present while GitBrowser runs.
+const RUNNING_MARKER: &str = "running";
+
 /// Trait defining crash recovery operations.
 pub trait CrashRecoveryTrait {
     fn log_crash(&mut self, entry: CrashLogEntry) -> Result<(), CrashError>;
     fn get_crash_logs(&self) -> Result<Vec<CrashLogEntry>, CrashError>;
     fn has_unrecovered_crash(&self) -> bool;
     fn mark_crash_recovered(&mut self) -> Result<(), CrashError>;
+    /// Marks only the crash logs `ids` as recovered.
+    fn mark_logs_recovered(&mut self, ids: &[String]) -> Result<(), CrashError>;
+    /// Deletes every crash log. Returns the number deleted.
+    fn clear_crash_logs(&mut self) -> Result<usize, CrashError>;
     fn get_last_session_for_recovery(&self) -> Result<Option<SessionData>, CrashError>;
+    /// Imports the crash reports written since the last start (plus an
+    /// `unclean_exit` entry if the last run never shut down) and marks this
+    /// run as running. Returns the number of crashes logged.
+    fn collect_reports(&mut self) -> Result<usize, CrashError>;
+    /// Records that this run shut down cleanly.
+    fn mark_clean_exit(&self);
+    /// Crash logs not uploaded yet, newest first.
+    fn pending_uploads(&self) -> Result<Vec<CrashLogEntry>, CrashError>;
+    fn mark_uploaded(&mut self, ids: &[String]) -> Result<(), CrashError>;
 }
 
 /// Crash recovery backed by SQLite.
 pub struct CrashRecovery {
     db: Arc<Database>,
     unrecovered: bool,
+    reports_dir: PathBuf,
 }
 
 impl CrashRecovery {
     pub fn new(db: Arc<Database>) -> Self {
-        let unrecovered = {
-            let conn = db.connection();
-            let count: i64 = conn
-                .query_row("SELECT COUNT(*) FROM crash_logs", [], |row| row.get(0))
-                .unwrap_or(0);
-            count > 0
+        let unrecovered = Self::any_unrecovered(&db);
+        Self {
+            db,
+            unrecovered,
+            reports_dir: crate::platform::get_data_dir().join("crashes"),
+        }
+    }
+
+    /// Uses `dir` for crash reports and the running marker.
+    pub fn with_reports_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.reports_dir = dir.into();
+        self
+    }
+
+    /// Directory the panic hook should write reports to.
+    pub fn reports_dir(&self) -> &Path {
+        &self.reports_dir
+    }
+
+    fn any_unrecovered(db: &Database) -> bool {
+        let count: i64 = db.connection()
+            .query_row("SELECT COUNT(*) FROM crash_logs WHERE recovered = 0", [], |row| row.get(0))
+            .unwrap_or(0);
+        count > 0
+    }
+
+    fn query_logs(&self, filter: &str) -> Result<Vec<CrashLogEntry>, CrashError> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, tab_url, error_type, error_message, timestamp, backtrace, os, app_version, \
+             recovered, uploaded_at FROM crash_logs {} ORDER BY timestamp DESC",
+            filter
+        )).map_err(|e| CrashError::DatabaseError(e.to_string()))?;
+
+        let logs = stmt.query_map([], |row| {
+            Ok(CrashLogEntry {
+                id: row.get(0)?,
+                tab_url: row.get(1)?,
+                error_type: row.get(2)?,
+                error_message: row.get(3)?,
+                timestamp: row.get(4)?,
+                backtrace: row.get(5)?,
+                os: row.get(6)?,
+                app_version: row.get(7)?,
+                recovered: row.get(8)?,
+                uploaded_at: row.get(9)?,
+            })
+        }).map_err(|e| CrashError::DatabaseError(e.to_string()))?;
+
+        let mut result = Vec::new();
+        for log in logs {
+            result.push(log.map_err(|e| CrashError::DatabaseError(e.to_string()))?);
+        }
+        Ok(result)
+    }
+
+    /// Report files in the reports directory, oldest name first.
+    fn report_files(&self) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(&self.reports_dir) else {
+            return Vec::new();
         };
-        Self { db, unrecovered }
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                let name = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                name.starts_with("crash-") && name.ends_with(".json")
+            })
+            .collect();
+        paths.sort();
+        paths
     }
 }
 
@@ -51,14 +149,20 @@ impl CrashRecoveryTrait for CrashRecovery {
         };
 
         let timestamp = if entry.timestamp == 0 {
-            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+            unix_now()
         } else {
             entry.timestamp
         };
 
-        self.db.connection().execute(
-            "INSERT INTO crash_logs (id, tab_url, error_type, error_message, timestamp) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![id, entry.tab_url, entry.error_type, entry.error_message, timestamp],
+        let conn = self.db.connection();
+        conn.execute(
+            "INSERT INTO crash_logs (id, tab_url, error_type, error_message, timestamp, backtrace, os, app_version) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![id, entry.tab_url, entry.error_type, entry.error_message, timestamp, entry.backtrace, entry.os, entry.app_version],
+        ).map_err(|e| CrashError::DatabaseError(e.to_string()))?;
+        conn.execute(
+            "DELETE FROM crash_logs WHERE id NOT IN (SELECT id FROM crash_logs ORDER BY timestamp DESC LIMIT ?1)",
+            params![MAX_CRASH_LOGS],
         ).map_err(|e| CrashError::DatabaseError(e.to_string()))?;
 
         self.unrecovered = true;
@@ -66,26 +170,7 @@ impl CrashRecoveryTrait for CrashRecovery {
     }
 
     fn get_crash_logs(&self) -> Result<Vec<CrashLogEntry>, CrashError> {
-        let conn = self.db.connection();
-        let mut stmt = conn.prepare(
-            "SELECT id, tab_url, error_type, error_message, timestamp FROM crash_logs ORDER BY timestamp DESC"
-        ).map_err(|e| CrashError::DatabaseError(e.to_string()))?;
-
-        let logs = stmt.query_map([], |row| {
-            Ok(CrashLogEntry {
-                id: row.get(0)?,
-                tab_url: row.get(1)?,
-                error_type: row.get(2)?,
-                error_message: row.get(3)?,
-                timestamp: row.get(4)?,
-            })
-        }).map_err(|e| CrashError::DatabaseError(e.to_string()))?;
-
-        let mut result = Vec::new();
-        for log in logs {
-            result.push(log.map_err(|e| CrashError::DatabaseError(e.to_string()))?);
-        }
-        Ok(result)
+        self.query_logs("")
     }
 
     fn has_unrecovered_crash(&self) -> bool {
@@ -93,16 +178,171 @@ impl CrashRecoveryTrait for CrashRecovery {
     }
 
     fn mark_crash_recovered(&mut self) -> Result<(), CrashError> {
-        self.db.connection().execute("DELETE FROM crash_logs", [])
+        // Logs are kept for issue reports; only the recovery prompt is done
+        self.db.connection().execute("UPDATE crash_logs SET recovered = 1 WHERE recovered = 0", [])
             .map_err(|e| CrashError::DatabaseError(e.to_string()))?;
         self.unrecovered = false;
         Ok(())
     }
 
+    fn mark_logs_recovered(&mut self, ids: &[String]) -> Result<(), CrashError> {
+        for id in ids {
+            let updated = self.db.connection().execute("UPDATE crash_logs SET recovered = 1 WHERE id = ?1", params![id])
+                .map_err(|e| CrashError::DatabaseError(e.to_string()))?;
+            if updated == 0 {
+                return Err(CrashError::NotFound(id.clone()));
+            }
+        }
+        self.unrecovered = Self::any_unrecovered(&self.db);
+        Ok(())
+    }
+
+    fn clear_crash_logs(&mut self) -> Result<usize, CrashError> {
+        let removed = self.db.connection().execute("DELETE FROM crash_logs", [])
+            .map_err(|e| CrashError::DatabaseError(e.to_string()))?;
+        self.unrecovered = false;
+        Ok(removed)
+    }
+
     fn get_last_session_for_recovery(&self) -> Result<Option<SessionData>, CrashError> {
         let session_mgr = SessionManager::new(self.db.clone())
             .map_err(|e| CrashError::RecoveryFailed(e.to_string()))?;
         session_mgr.restore_session()
             .map_err(|e| CrashError::RecoveryFailed(e.to_string()))
     }
+
+    fn collect_reports(&mut self) -> Result<usize, CrashError> {
+        let mut logged = 0;
+        for path in self.report_files() {
+            // Unreadable reports are dropped rather than retried on every start
+            let entry = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<CrashLogEntry>(&s).ok());
+            if let Some(entry) = entry {
+                self.log_crash(entry)?;
+                logged += 1;
+            }
+            let _ = std::fs::remove_file(&path);
+        }
+
+        let marker = self.reports_dir.join(RUNNING_MARKER);
+        if let Ok(previous) = std::fs::read_to_string(&marker) {
+            // A panic report already explains why the last run ended
+            if logged == 0 {
+                let run: Value = serde_json::from_str(&previous).unwrap_or_default();
+                let mut entry = crash_entry(
+                    "unclean_exit",
+                    "GitBrowser exited without shutting down (native crash or killed)",
+                    None,
+                    None,
+                );
+                if let Some(version) = run["version"].as_str() {
+                    entry.app_version = Some(version.to_string());
+                }
+                self.log_crash(entry)?;
+                logged += 1;
+            }
+        }
+
+        let run = json!({"version": env!("CARGO_PKG_VERSION"), "pid": std::process::id(), "started_at": unix_now()});
+        std::fs::create_dir_all(&self.reports_dir)
+            .and_then(|_| std::fs::write(&marker, run.to_string()))
+            .map_err(|e| CrashError::ReportFailed(format!("cannot write {}: {}", marker.display(), e)))?;
+        Ok(logged)
+    }
+
+    fn mark_clean_exit(&self) {
+        let _ = std::fs::remove_file(self.reports_dir.join(RUNNING_MARKER));
+    }
+
+    fn pending_uploads(&self) -> Result<Vec<CrashLogEntry>, CrashError> {
+        self.query_logs("WHERE uploaded_at IS NULL")
+    }
+
+    fn mark_uploaded(&mut self, ids: &[String]) -> Result<(), CrashError> {
+        let now = unix_now();
+        for id in ids {
+            self.db.connection().execute("UPDATE crash_logs SET uploaded_at = ?1 WHERE id = ?2", params![now, id])
+                .map_err(|e| CrashError::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Installs a panic hook that writes a crash report to `reports_dir`, then
+/// runs the previous hook. `active_tab_url` is called from the panicking
+/// thread and must not block (use `try_lock`).
+pub fn install_panic_hook<F>(reports_dir: PathBuf, active_tab_url: F)
+where
+    F: Fn() -> Option<String> + Send + Sync + 'static,
+{
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let backtrace = Backtrace::force_capture().to_string();
+        let message = panic_message(info.payload(), info.location());
+        let entry = crash_entry("panic", &message, Some(backtrace), active_tab_url());
+        if let Err(e) = write_report(&reports_dir, &entry) {
+            eprintln!("[CRASH] cannot write crash report: {}", e);
+        }
+        previous(info);
+    }));
+}
+
+/// Crash log entry for a crash in this process, with OS and version filled in.
+pub fn crash_entry(error_type: &str, message: &str, backtrace: Option<String>, tab_url: Option<String>) -> CrashLogEntry {
+    CrashLogEntry {
+        id: Uuid::new_v4().to_string(),
+        tab_url,
+        error_type: error_type.to_string(),
+        error_message: Some(message.to_string()),
+        timestamp: unix_now(),
+        backtrace: backtrace.map(|b| truncate(b, MAX_BACKTRACE_BYTES)),
+        os: Some(os_description()),
+        app_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        recovered: false,
+        uploaded_at: None,
+    }
+}
+
+/// Writes `entry` as `crash-<timestamp>-<id>.json` in `dir`.
+pub fn write_report(dir: &Path, entry: &CrashLogEntry) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("crash-{}-{}.json", entry.timestamp, entry.id));
+    let json = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+    std::fs::write(&path, json)?;
+    Ok(path)
+}
+
+/// The panic's message with the source location, e.g.
+/// `index out of bounds at src/app.rs:10:5`.
+pub fn panic_message(payload: &(dyn Any + Send), location: Option<&Location<'_>>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string());
+    match location {
+        Some(l) => format!("{} at {}:{}:{}", message, l.file(), l.line(), l.column()),
+        None => message,
+    }
+}
+
+/// OS family and architecture, e.g. `linux x86_64`.
+pub fn os_description() -> String {
+    format!("{} {}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+fn truncate(mut s: String, max: usize) -> String {
+    if s.len() > max {
+        let mut end = max;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+    }
+    s
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
 }
@@ -0,0 +1,214 @@
+//! Opt-in upload of crash logs.
+//!
+//! Nothing leaves the machine unless `privacy.telemetry_consent` is on.
+//! Logs are turned into `CrashReport`s first: user names in paths become
+//! `<user>`, e-mail addresses `<email>` and URLs `<url>`. With
+//! `privacy.crash_report_urls` URLs are kept, cut down to scheme, host and
+//! path. Reports go to `privacy.crash_report_endpoint` as one JSON POST, or,
+//! with no endpoint set, into a GitHub issue draft the user reviews and
+//! submits themselves.
+
+use reqwest::Url;
+use serde_json::json;
+
+use crate::services::github_integration::send_request;
+use crate::types::errors::CrashError;
+use crate::types::privacy::{CrashLogEntry, CrashReport};
+use crate::types::settings::PrivacySettings;
+
+/// `format` of an upload, bumped when `CrashReport` changes incompatibly.
+pub const REPORT_FORMAT: &str = "gitbrowser-crash-report/1";
+
+/// New-issue page issue drafts are opened on.
+pub const ISSUE_URL: &str = "https://github.com/gothtr/gitbrowser/issues/new";
+
+/// Longest issue body put in a draft URL; backtraces are cut to fit.
+const MAX_ISSUE_BODY: usize = 6000;
+
+/// Path segments after which the next segment is a user name.
+const USER_DIR_MARKERS: &[&str] = &["/home/", "/Users/", "\\Users\\", "\\users\\"];
+
+/// Where crash reports go.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UploadTarget {
+    /// POSTed to this HTTPS URL.
+    Endpoint(String),
+    /// Opened as a GitHub issue draft; nothing is sent.
+    IssueDraft,
+}
+
+impl UploadTarget {
+    pub fn from_settings(privacy: &PrivacySettings) -> Self {
+        match privacy.crash_report_endpoint.trim() {
+            "" => UploadTarget::IssueDraft,
+            endpoint => UploadTarget::Endpoint(endpoint.to_string()),
+        }
+    }
+}
+
+/// The uploadable form of a crash log.
+pub fn sanitize_report(entry: &CrashLogEntry, include_urls: bool) -> CrashReport {
+    CrashReport {
+        id: entry.id.clone(),
+        error_type: entry.error_type.clone(),
+        message: entry.error_message.as_deref().map(|m| redact(m, include_urls)),
+        backtrace: entry.backtrace.as_deref().map(|b| redact(b, include_urls)),
+        os: entry.os.clone(),
+        app_version: entry.app_version.clone(),
+        timestamp: entry.timestamp,
+        url: entry.tab_url.as_deref().filter(|_| include_urls).and_then(page_url),
+    }
+}
+
+/// Scrubs user names from paths, and e-mail addresses and URLs (with or
+/// without a scheme) from `text`. URLs are kept without query and fragment
+/// when `include_urls` is set.
+pub fn redact(text: &str, include_urls: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word = String::new();
+    for ch in text.chars() {
+        if ch.is_whitespace() || "\"'`()<>[]{},;".contains(ch) {
+            out.push_str(&redact_word(&word, include_urls));
+            word.clear();
+            out.push(ch);
+        } else {
+            word.push(ch);
+        }
+    }
+    out.push_str(&redact_word(&word, include_urls));
+    out
+}
+
+fn redact_word(word: &str, include_urls: bool) -> String {
+    if word.contains("://") {
+        let kept = if include_urls { page_url(word) } else { None };
+        return kept.unwrap_or_else(|| "<url>".to_string());
+    }
+    if is_schemeless_url(word) {
+        let kept = if include_urls {
+            page_url(&format!("http://{}", word)).and_then(|url| url.strip_prefix("http://").map(str::to_string))
+        } else {
+            None
+        };
+        return kept.unwrap_or_else(|| "<url>".to_string());
+    }
+    if let Some((name, domain)) = word.split_once('@') {
+        if !name.is_empty() && domain.contains('.') && !domain.starts_with('.') {
+            return "<email>".to_string();
+        }
+    }
+    let mut out = word.to_string();
+    for marker in USER_DIR_MARKERS {
+        let mut from = 0;
+        while let Some(pos) = out[from..].find(marker) {
+            let start = from + pos + marker.len();
+            let end = out[start..].find(['/', '\\']).map_or(out.len(), |i| start + i);
+            if end > start {
+                out.replace_range(start..end, "<user>");
+            }
+            from = start + if end > start { "<user>".len() } else { 0 };
+        }
+    }
+    out
+}
+
+/// `example.com/path?query` or `www.example.com`: a host name ending in a
+/// top-level domain, followed by a path, query or fragment unless it starts
+/// with `www.`. Source paths like `src/app.rs:1:1` do not start with a host.
+fn is_schemeless_url(word: &str) -> bool {
+    let host_end = word.find(['/', '?', '#']).unwrap_or(word.len());
+    let host = &word[..host_end];
+    let name = host
+        .rsplit_once(':')
+        .filter(|(_, port)| !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()))
+        .map_or(host, |(name, _)| name);
+    let Some((_, tld)) = name.rsplit_once('.') else {
+        return false;
+    };
+    let is_host = !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic());
+    is_host && (host_end < word.len() || name.starts_with("www."))
+}
+
+/// Scheme, host and path of an http(s) URL; credentials, query and
+/// fragment are dropped.
+pub fn page_url(url: &str) -> Option<String> {
+    let mut parsed = Url::parse(url).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+    parsed.set_query(None);
+    parsed.set_fragment(None);
+    let _ = parsed.set_username("");
+    let _ = parsed.set_password(None);
+    Some(parsed.to_string())
+}
+
+/// Sends `reports` to `endpoint` as `{"format": ..., "reports": [...]}`.
+pub fn upload(endpoint: &str, reports: &[CrashReport]) -> Result<(), CrashError> {
+    let url = Url::parse(endpoint).map_err(|e| CrashError::UploadFailed(format!("invalid endpoint: {}", e)))?;
+    if url.scheme() != "https" {
+        return Err(CrashError::UploadFailed("endpoint must use https".to_string()));
+    }
+    let body = json!({"format": REPORT_FORMAT, "reports": reports});
+    let response = send_request("POST", url.as_str(), None, Some(body), &[])
+        .map_err(|e| CrashError::UploadFailed(e.to_string()))?;
+    if !(200..300).contains(&response.status) {
+        return Err(CrashError::UploadFailed(format!("HTTP {}", response.status)));
+    }
+    Ok(())
+}
+
+/// New-issue URL prefilled with `reports`, for the user to review and
+/// submit. Backtraces are shortened to keep the URL within GitHub's limits.
+pub fn issue_draft_url(reports: &[CrashReport]) -> String {
+    let version = reports.iter().find_map(|r| r.app_version.as_deref()).unwrap_or(env!("CARGO_PKG_VERSION"));
+    let title = match reports {
+        [report] => format!("Crash: {} in GitBrowser {}", report.error_type, version),
+        _ => format!("{} crashes in GitBrowser {}", reports.len(), version),
+    };
+
+    let mut budget = MAX_ISSUE_BODY / reports.len().max(1);
+    let body = loop {
+        let shortened: Vec<CrashReport> = reports.iter().map(|r| shorten_backtrace(r, budget)).collect();
+        let body = issue_body(&shortened);
+        if body.len() <= MAX_ISSUE_BODY || budget == 0 {
+            break body;
+        }
+        budget /= 2;
+    };
+
+    let mut url = Url::parse(ISSUE_URL).expect("ISSUE_URL is a valid URL");
+    url.query_pairs_mut().append_pair("title", &title).append_pair("body", &body);
+    url.to_string()
+}
+
+fn issue_body(reports: &[CrashReport]) -> String {
+    let payload = json!({"format": REPORT_FORMAT, "reports": reports});
+    format!(
+        "<!-- Generated by GitBrowser. Review it before submitting; add what you were doing when it crashed. -->\n\n\
+         **What happened:**\n\n\n\
+         **Crash report:**\n\n```json\n{}\n```\n",
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    )
+}
+
+/// `report` with its backtrace cut to whole lines within `max` bytes.
+fn shorten_backtrace(report: &CrashReport, max: usize) -> CrashReport {
+    let mut report = report.clone();
+    if let Some(backtrace) = report.backtrace.as_mut().filter(|b| b.len() > max) {
+        let mut kept = String::new();
+        for line in backtrace.lines() {
+            if kept.len() + line.len() + 1 > max {
+                break;
+            }
+            kept.push_str(line);
+            kept.push('\n');
+        }
+        kept.push_str("[…]");
+        *backtrace = kept;
+    }
+    report
+}
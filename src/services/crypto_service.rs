@@ -69,7 +69,7 @@ impl NonceSequence for SingleNonce {
     fn advance(&mut self) -> Result<Nonce, ring::error::Unspecified> {
         self.nonce
             .take()
-            .map(|n| Nonce::assume_unique_for_key(n))
+            .map(Nonce::assume_unique_for_key)
             .ok_or(ring::error::Unspecified)
     }
 }
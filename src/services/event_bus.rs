@@ -0,0 +1,68 @@
+//! Typed events between the app's own components.
+//!
+//! Where [`EventHub`](crate::services::event_hub::EventHub) pushes JSON to RPC
+//! clients, the [`EventBus`] carries the same kinds of changes as Rust values
+//! inside the process, so one component can react to another (the window
+//! repainting on a theme change, say) without whoever caused the change
+//! having to know about it.
+//!
+//! Each subscriber gets its own channel; dropping the receiver unsubscribes.
+//! Events are sent in the order they were emitted and never block the
+//! emitter.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use crate::types::settings::SettingsChange;
+use crate::types::tab::PageAction;
+
+/// Something that changed in the app.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    /// A tab was opened, closed, switched to or changed. `action` is the
+    /// same as in the `tabs` RPC event.
+    TabChanged { action: String, tab_id: String },
+    /// One leaf setting changed.
+    SettingChanged(SettingsChange),
+    /// A download started, moved forward or changed state.
+    DownloadProgress { id: String, downloaded: u64, size: Option<u64> },
+    /// The theme's CSS variables changed.
+    ThemeChanged { variables: HashMap<String, String> },
+    /// The password vault was locked and its key wiped.
+    VaultLocked,
+    /// Something is to be done with the page tab `tab_id` shows; only its
+    /// webview can.
+    PageActionRequested { tab_id: String, action: PageAction },
+}
+
+/// Subscribers to app events. Clones share them.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<AppEvent>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a subscriber; every event emitted afterwards arrives on the
+    /// returned channel.
+    pub fn subscribe(&self) -> Receiver<AppEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).push(tx);
+        rx
+    }
+
+    /// Sends `event` to every subscriber, forgetting those that went away.
+    pub fn emit(&self, event: AppEvent) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+
+    /// Number of subscribers still listening as of the last event.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
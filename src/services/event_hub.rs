@@ -0,0 +1,147 @@
+//! Event subscriptions for RPC clients.
+//!
+//! Managers publish changes to an [`EventHub`] shared through the app; RPC
+//! clients choose the topics they want with `subscribe` and get each event
+//! pushed as a JSON-RPC notification instead of polling:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","method":"event","params":{"subscription":1,"topic":"tabs","data":{...}}}
+//! ```
+//!
+//! Subscriptions belong to the client connection that made them and end
+//! with it.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+
+/// Writes one message to a client.
+pub type Deliver = Arc<dyn Fn(&Value) + Send + Sync>;
+
+/// What an event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventTopic {
+    /// Download started, paused, resumed, progressed, finished or failed.
+    Downloads,
+    /// Tab opened, closed, switched to or changed.
+    Tabs,
+    /// A site asked for a permission, or a decision changed.
+    Permissions,
+    /// A sync run started, finished or failed.
+    Sync,
+    /// Settings changed.
+    Settings,
+    /// The theme's CSS variables changed.
+    Theme,
+    /// The user asked the AI assistant about something on a page.
+    Ai,
+}
+
+impl EventTopic {
+    pub const ALL: [EventTopic; 7] = [
+        EventTopic::Downloads,
+        EventTopic::Tabs,
+        EventTopic::Permissions,
+        EventTopic::Sync,
+        EventTopic::Settings,
+        EventTopic::Theme,
+        EventTopic::Ai,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventTopic::Downloads => "downloads",
+            EventTopic::Tabs => "tabs",
+            EventTopic::Permissions => "permissions",
+            EventTopic::Sync => "sync",
+            EventTopic::Settings => "settings",
+            EventTopic::Theme => "theme",
+            EventTopic::Ai => "ai",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == name)
+    }
+}
+
+struct Subscription {
+    client: u64,
+    topics: HashSet<EventTopic>,
+    deliver: Deliver,
+}
+
+#[derive(Default)]
+struct Subscribers {
+    next_id: u64,
+    by_id: HashMap<u64, Subscription>,
+}
+
+/// Subscriptions and the events published to them. Clones share them.
+#[derive(Clone, Default)]
+pub struct EventHub {
+    subscribers: Arc<Mutex<Subscribers>>,
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends `client` the events on `topics` through `deliver` until
+    /// unsubscribed; returns the subscription id.
+    pub fn subscribe(&self, client: u64, topics: &[EventTopic], deliver: Deliver) -> u64 {
+        let mut subs = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subs.next_id += 1;
+        let id = subs.next_id;
+        subs.by_id.insert(id, Subscription { client, topics: topics.iter().copied().collect(), deliver });
+        id
+    }
+
+    /// Ends subscription `id` if `client` made it. Returns whether it did.
+    pub fn unsubscribe(&self, client: u64, id: u64) -> bool {
+        let mut subs = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        match subs.by_id.get(&id) {
+            Some(sub) if sub.client == client => subs.by_id.remove(&id).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Ends every subscription of a client that went away.
+    pub fn remove_client(&self, client: u64) {
+        let mut subs = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subs.by_id.retain(|_, sub| sub.client != client);
+    }
+
+    /// Number of live subscriptions.
+    pub fn subscription_count(&self) -> usize {
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).by_id.len()
+    }
+
+    /// Whether any client is subscribed to `topic`.
+    pub fn has_subscribers(&self, topic: EventTopic) -> bool {
+        let subs = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subs.by_id.values().any(|sub| sub.topics.contains(&topic))
+    }
+
+    /// Pushes `data` to everyone subscribed to `topic`.
+    pub fn publish(&self, topic: EventTopic, data: Value) {
+        // Delivered outside the lock, so a subscriber may subscribe in turn
+        let targets: Vec<(u64, Deliver)> = {
+            let subs = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+            subs.by_id
+                .iter()
+                .filter(|(_, sub)| sub.topics.contains(&topic))
+                .map(|(id, sub)| (*id, sub.deliver.clone()))
+                .collect()
+        };
+        for (id, deliver) in targets {
+            deliver(&json!({
+                "jsonrpc": "2.0",
+                "method": "event",
+                "params": {"subscription": id, "topic": topic.as_str(), "data": data},
+            }));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Unpacking of packaged extensions (`.zip` and Chrome `.crx`).
+//!
+//! A `.crx` is a zip archive behind a `Cr24` header; the header is skipped,
+//! its signature is not verified. Entries are written one by one into a
+//! fresh directory: names that escape it are rejected, and entry count and
+//! total size are capped so a crafted archive cannot fill the disk.
+
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use crate::types::errors::ExtensionError;
+
+/// Most entries an archive may contain.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Most bytes an archive may unpack to.
+const MAX_UNPACKED_BYTES: u64 = 64 * 1024 * 1024;
+
+const CRX_MAGIC: &[u8] = b"Cr24";
+
+/// Returns true if the path names a file this module can unpack.
+pub fn is_archive(path: &str) -> bool {
+    let p = Path::new(path);
+    p.is_file()
+        && p.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("zip") || e.eq_ignore_ascii_case("crx"))
+}
+
+/// Returns the zip payload of an archive, skipping a CRX2/CRX3 header if present.
+pub fn zip_payload(bytes: &[u8]) -> Result<&[u8], ExtensionError> {
+    if !bytes.starts_with(CRX_MAGIC) {
+        return Ok(bytes);
+    }
+    let read_u32 = |at: usize| -> Result<usize, ExtensionError> {
+        bytes.get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(|| ExtensionError::InvalidArchive("truncated crx header".to_string()))
+    };
+    let header_end = match read_u32(4)? {
+        // CRX2: public key and signature lengths, then both blobs
+        2 => 16 + read_u32(8)? + read_u32(12)?,
+        // CRX3: one protobuf header of the given length
+        3 => 12 + read_u32(8)?,
+        v => return Err(ExtensionError::InvalidArchive(format!("unsupported crx version {}", v))),
+    };
+    bytes.get(header_end..)
+        .ok_or_else(|| ExtensionError::InvalidArchive("truncated crx header".to_string()))
+}
+
+/// Unpacks the archive into `dest` (which must not exist yet) and returns the
+/// extension root: `dest` itself, or its single top-level folder when the
+/// archive wraps everything in one. The root must contain `manifest.json`.
+pub fn unpack(archive_path: &str, dest: &Path) -> Result<PathBuf, ExtensionError> {
+    let bytes = std::fs::read(archive_path)
+        .map_err(|e| ExtensionError::LoadError(format!("Cannot read {}: {}", archive_path, e)))?;
+    let payload = zip_payload(&bytes)?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(payload))
+        .map_err(|e| ExtensionError::InvalidArchive(e.to_string()))?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(ExtensionError::InvalidArchive(format!(
+            "{} entries, limit is {}", archive.len(), MAX_ENTRIES
+        )));
+    }
+
+    std::fs::create_dir(dest)
+        .map_err(|e| ExtensionError::LoadError(format!("Cannot create {}: {}", dest.display(), e)))?;
+    let mut unpacked: u64 = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| ExtensionError::InvalidArchive(e.to_string()))?;
+        let name = entry.enclosed_name().ok_or_else(|| {
+            ExtensionError::InvalidArchive(format!("entry {} escapes the extension directory", entry.name()))
+        })?;
+        let target = dest.join(name);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+        }
+
+        // Declared sizes can lie, so the limit is enforced on the bytes actually read
+        let mut data = Vec::new();
+        (&mut entry).take(MAX_UNPACKED_BYTES - unpacked + 1).read_to_end(&mut data)
+            .map_err(|e| ExtensionError::InvalidArchive(e.to_string()))?;
+        unpacked += data.len() as u64;
+        if unpacked > MAX_UNPACKED_BYTES {
+            return Err(ExtensionError::InvalidArchive(format!(
+                "unpacks to more than {} bytes", MAX_UNPACKED_BYTES
+            )));
+        }
+        std::fs::write(&target, data).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+    }
+
+    extension_root(dest)
+}
+
+fn extension_root(dest: &Path) -> Result<PathBuf, ExtensionError> {
+    if dest.join("manifest.json").is_file() {
+        return Ok(dest.to_path_buf());
+    }
+    let entries: Vec<PathBuf> = std::fs::read_dir(dest)
+        .map_err(|e| ExtensionError::LoadError(e.to_string()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    match entries.as_slice() {
+        [only] if only.is_dir() && only.join("manifest.json").is_file() => Ok(only.clone()),
+        _ => Err(ExtensionError::InvalidArchive("manifest.json not found".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crx3(zip: &[u8], header_len: u32) -> Vec<u8> {
+        let mut bytes = b"Cr24".to_vec();
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&header_len.to_le_bytes());
+        bytes.extend(std::iter::repeat_n(0u8, header_len as usize));
+        bytes.extend_from_slice(zip);
+        bytes
+    }
+
+    #[test]
+    fn test_plain_zip_passes_through() {
+        assert_eq!(zip_payload(b"PK\x03\x04rest").unwrap(), b"PK\x03\x04rest");
+    }
+
+    #[test]
+    fn test_crx3_header_skipped() {
+        assert_eq!(zip_payload(&crx3(b"PK-data", 5)).unwrap(), b"PK-data");
+    }
+
+    #[test]
+    fn test_crx2_header_skipped() {
+        let mut bytes = b"Cr24".to_vec();
+        for v in [2u32, 3, 2] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes.extend_from_slice(b"keysgPK-data");
+        assert_eq!(zip_payload(&bytes).unwrap(), b"PK-data");
+    }
+
+    #[test]
+    fn test_bad_crx_headers_rejected() {
+        assert!(zip_payload(b"Cr24\x03\x00").is_err());
+        assert!(zip_payload(b"Cr24\x09\x00\x00\x00").is_err());
+        let mut truncated = crx3(b"", 0);
+        truncated[8] = 200;
+        assert!(zip_payload(&truncated).is_err());
+    }
+}
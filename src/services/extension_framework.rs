@@ -1,20 +1,60 @@
 //! Extension Framework for GitBrowser.
 //!
 //! Manages browser extension lifecycle: install, enable/disable, uninstall,
-//! content script matching, and performance impact tracking.
+//! content script matching, background pages, runtime messaging, storage,
+//! and performance impact tracking.
 
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use rusqlite::params;
+use serde_json::{Map, Value};
 
 use crate::database::connection::Database;
+use crate::services::extension_archive;
 use crate::types::errors::ExtensionError;
-use crate::types::extension::{ContentScript, ExtensionInfo, ExtensionManifest, ExtensionPermission};
+use crate::types::privacy::{NetworkRule, NetworkRuleAction};
+use crate::types::extension::{
+    ContentScript, DeclarativeRule, ExtensionInfo, ExtensionManifest, ExtensionMessage, ExtensionPermission,
+    MessageSender, PendingInstall,
+};
+
+/// `chrome.runtime` messaging shim evaluated before content scripts.
+const EXTENSION_API_JS: &str = include_str!("../../resources/ui/extension_api.js");
+
+/// Largest serialized message payload accepted from an extension.
+const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Messages an extension may have waiting (queued or awaiting a reply).
+const MAX_PENDING_MESSAGES: usize = 256;
+
+/// Bytes (keys plus JSON values) an extension may keep in storage.
+const MAX_STORAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Blocking rules compiled per extension; the rest are dropped.
+const MAX_RULES_PER_EXTENSION: usize = 30_000;
+
+/// Directory under the extensions directory that holds unconfirmed archive installs.
+const STAGING_DIR: &str = ".staging";
 
 /// Trait defining extension framework operations.
 pub trait ExtensionFrameworkTrait {
+    /// Registers an extension and grants every permission its manifest
+    /// requests. User-initiated installs go through `request_install` and
+    /// `confirm_install` so the permissions are acknowledged first.
     fn install(&mut self, extension_path: &str) -> Result<String, ExtensionError>;
+    /// Parses the manifest and holds the install until its permissions are
+    /// confirmed. Requires a valid manifest. A `.zip`/`.crx` path is first
+    /// unpacked into a staging directory of its own, and only moved into the
+    /// managed extensions directory by `confirm_install`.
+    fn request_install(&mut self, extension_path: &str) -> Result<PendingInstall, ExtensionError>;
+    /// Completes a pending install. `granted` must list exactly the requested
+    /// permissions, and the manifest must not have changed since the request.
+    fn confirm_install(&mut self, install_id: &str, granted: &[ExtensionPermission]) -> Result<String, ExtensionError>;
+    /// Drops a pending install without installing anything.
+    fn cancel_install(&mut self, install_id: &str) -> Result<(), ExtensionError>;
     fn uninstall(&mut self, extension_id: &str) -> Result<(), ExtensionError>;
     fn enable(&mut self, extension_id: &str) -> Result<(), ExtensionError>;
     fn disable(&mut self, extension_id: &str) -> Result<(), ExtensionError>;
@@ -27,6 +67,42 @@ pub trait ExtensionFrameworkTrait {
     fn has_permission(&self, extension_id: &str, permission: &ExtensionPermission) -> bool;
     /// Check if an extension has permission to inject content scripts (requires PageContent).
     fn check_content_script_permission(&self, extension_id: &str) -> bool;
+    /// Queues a `runtime.sendMessage` payload for the extension's background
+    /// context and returns its message ID. Content script senders need
+    /// PageContent and must run on a URL the extension's content scripts match.
+    fn send_message(&mut self, extension_id: &str, sender: MessageSender, payload: Value) -> Result<u64, ExtensionError>;
+    /// Drains the messages queued for an extension, oldest first.
+    fn take_messages(&mut self, extension_id: &str) -> Vec<ExtensionMessage>;
+    /// Answers a taken message. Returns the original message so the caller
+    /// can route the response to its sender.
+    fn reply(&mut self, extension_id: &str, message_id: u64, response: &Value) -> Result<ExtensionMessage, ExtensionError>;
+    /// Checks that `sender` may act for the extension: it must be enabled,
+    /// content scripts need PageContent and a URL the extension's scripts
+    /// match, and extension pages must be served from the extension itself.
+    fn check_sender(&self, extension_id: &str, sender: &MessageSender) -> Result<(), ExtensionError>;
+    /// Compares enabled extensions that declare a background script with the
+    /// pages already started and returns what the host must start or stop.
+    fn poll_background_changes(&mut self) -> Vec<BackgroundChange>;
+    /// Marks a started background page as loaded and ready for messages.
+    fn mark_background_ready(&mut self, extension_id: &str);
+    /// IDs of background pages that have loaded.
+    fn ready_backgrounds(&self) -> Vec<String>;
+    /// Reads values from the extension's storage; `None` returns every key.
+    fn storage_get(&self, extension_id: &str, keys: Option<&[String]>) -> Result<Map<String, Value>, ExtensionError>;
+    /// Writes values to the extension's storage, replacing existing keys.
+    fn storage_set(&mut self, extension_id: &str, items: &Map<String, Value>) -> Result<(), ExtensionError>;
+    /// Removes keys from the extension's storage.
+    fn storage_remove(&mut self, extension_id: &str, keys: &[String]) -> Result<(), ExtensionError>;
+    /// Compiles the enabled rulesets of enabled extensions holding the
+    /// DeclarativeNetRequest permission, for `PrivacyEngine::set_extension_rules`.
+    fn compile_blocking_rules(&self) -> Vec<NetworkRule>;
+    /// Path of the extension's options page from its manifest, if it declares one.
+    fn options_page_path(&self, extension_id: &str) -> Option<String>;
+    /// The options page of an enabled extension, with the extension API
+    /// injected so it can use `chrome.storage` and `chrome.runtime`.
+    fn options_page(&self, extension_id: &str) -> Result<String, ExtensionError>;
+    /// Reads a file of an enabled extension for its pages (scripts, styles, images).
+    fn extension_resource(&self, extension_id: &str, relative: &str) -> Result<Vec<u8>, ExtensionError>;
 }
 
 /// A content script matched to a URL, with resolved file contents.
@@ -39,10 +115,38 @@ pub struct MatchedContentScript {
     pub run_at: String,
 }
 
+/// A background script ready to run in its own hidden page.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BackgroundScript {
+    pub extension_id: String,
+    pub extension_name: String,
+    pub source: String,
+}
+
+/// A lifecycle step for the host running background pages.
+#[derive(Debug, Clone)]
+pub enum BackgroundChange {
+    /// The extension was enabled or installed: start its page.
+    Start(BackgroundScript),
+    /// The extension was disabled or removed: stop its page.
+    Stop(String),
+}
+
 /// Extension framework backed by SQLite with in-memory cache.
 pub struct ExtensionFramework {
     db: Arc<Database>,
     extensions: Vec<ExtensionInfo>,
+    /// Messages waiting for each extension's background context.
+    inbox: HashMap<String, VecDeque<ExtensionMessage>>,
+    /// Messages handed out by `take_messages` that have not been answered.
+    awaiting_reply: HashMap<u64, ExtensionMessage>,
+    next_message_id: u64,
+    /// Background pages the host was told to start, and whether each has loaded.
+    backgrounds: HashMap<String, bool>,
+    /// Installs waiting for permission confirmation, by install ID.
+    pending_installs: HashMap<String, PendingInstall>,
+    /// Where packaged extensions are unpacked; files under it belong to us.
+    extensions_dir: Option<PathBuf>,
 }
 
 impl ExtensionFramework {
@@ -50,15 +154,128 @@ impl ExtensionFramework {
         let mut fw = Self {
             db,
             extensions: Vec::new(),
+            inbox: HashMap::new(),
+            awaiting_reply: HashMap::new(),
+            next_message_id: 1,
+            backgrounds: HashMap::new(),
+            pending_installs: HashMap::new(),
+            extensions_dir: None,
         };
         fw.load_from_db();
         fw
     }
 
+    /// Sets the directory `.zip`/`.crx` extensions are unpacked into.
+    pub fn with_extensions_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.extensions_dir = Some(dir.into());
+        self
+    }
+
+    /// Unpacks an archive into a fresh `<extensions_dir>/.staging/<uuid>` and
+    /// returns the extension root inside it. `<extensions_dir>/<id>` is only
+    /// touched once the install is confirmed, so two pending installs of the
+    /// same extension never clobber each other.
+    fn unpack_archive(&self, archive_path: &str) -> Result<String, ExtensionError> {
+        let dir = self.extensions_dir.as_ref()
+            .ok_or_else(|| ExtensionError::LoadError("no extensions directory configured".to_string()))?;
+        let staging = dir.join(STAGING_DIR).join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(staging.parent().unwrap_or(dir))
+            .map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+
+        let result = extension_archive::unpack(archive_path, &staging).and_then(|root| {
+            let manifest = Self::parse_manifest(&root.to_string_lossy())?;
+            Self::check_managed_id(&manifest.id)?;
+            if self.extensions.iter().any(|e| e.id == manifest.id) {
+                return Err(ExtensionError::LoadError(format!("{} is already installed", manifest.id)));
+            }
+            Ok(root.to_string_lossy().into_owned())
+        });
+        if result.is_err() {
+            let _ = std::fs::remove_dir_all(&staging);
+        }
+        result
+    }
+
+    /// An unpacked extension lives at `<extensions_dir>/<id>`, so the ID has
+    /// to be a plain directory name.
+    fn check_managed_id(id: &str) -> Result<(), ExtensionError> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            || id.starts_with('.')
+        {
+            return Err(ExtensionError::InvalidManifest(format!("unusable extension id '{}'", id)));
+        }
+        Ok(())
+    }
+
+    /// The `.staging/<uuid>` directory a pending archive install was unpacked into.
+    fn staging_dir_of(&self, path: &str) -> Option<PathBuf> {
+        let staging_root = self.extensions_dir.as_ref()?.join(STAGING_DIR);
+        let unique = Path::new(path).strip_prefix(&staging_root).ok()?.components().next()?;
+        Some(staging_root.join(unique))
+    }
+
+    /// Moves a confirmed archive install out of staging to `<extensions_dir>/<id>`.
+    fn move_into_place(&self, staging: &Path, root: &str, id: &str) -> Result<String, ExtensionError> {
+        Self::check_managed_id(id)?;
+        let dir = staging.parent().and_then(Path::parent)
+            .ok_or_else(|| ExtensionError::LoadError("no extensions directory configured".to_string()))?;
+        let target = dir.join(id);
+        if target.exists() {
+            // Left over from an install that stopped before it was recorded
+            std::fs::remove_dir_all(&target).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+        }
+        std::fs::rename(root, &target).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+        let _ = std::fs::remove_dir_all(staging);
+        Ok(target.to_string_lossy().into_owned())
+    }
+
+    /// Checks a pending install against what the user acknowledged and
+    /// installs it, moving an unpacked archive into place first.
+    fn finish_install(&mut self, pending: &PendingInstall, granted: &[ExtensionPermission]) -> Result<String, ExtensionError> {
+        let acknowledged = pending.permissions.len() == granted.len()
+            && pending.permissions.iter().all(|p| granted.contains(p));
+        if !acknowledged {
+            return Err(ExtensionError::PermissionDenied(format!(
+                "{} requests {:?}, acknowledged {:?}", pending.name, pending.permissions, granted
+            )));
+        }
+        // The directory could have been swapped since the prompt was shown
+        let manifest = Self::parse_manifest(&pending.path)?;
+        if manifest.permissions != pending.permissions {
+            return Err(ExtensionError::InvalidManifest(format!(
+                "{} changed its permissions during install", pending.name
+            )));
+        }
+        if manifest.id != pending.extension_id {
+            return Err(ExtensionError::InvalidManifest(format!(
+                "{} changed its id during install", pending.name
+            )));
+        }
+        let Some(staging) = self.staging_dir_of(&pending.path) else {
+            return self.install(&pending.path);
+        };
+        if self.extensions.iter().any(|e| e.id == manifest.id) {
+            return Err(ExtensionError::LoadError(format!("{} is already installed", manifest.id)));
+        }
+        let path = self.move_into_place(&staging, &pending.path, &manifest.id)?;
+        self.install(&path).inspect_err(|_| self.remove_managed_files(&path))
+    }
+
+    /// Deletes an unpacked extension directory, but only one we created.
+    fn remove_managed_files(&self, path: &str) {
+        if let Some(staging) = self.staging_dir_of(path) {
+            let _ = std::fs::remove_dir_all(staging);
+        } else if let Some(dir) = &self.extensions_dir {
+            if Path::new(path).parent() == Some(dir.as_path()) {
+                let _ = std::fs::remove_dir_all(path);
+            }
+        }
+    }
+
     fn load_from_db(&mut self) {
         let conn = self.db.connection();
         let stmt = conn.prepare(
-            "SELECT id, name, version, enabled, permissions, COALESCE(install_path, ''), COALESCE(content_scripts, '[]') FROM extensions ORDER BY name"
+            "SELECT id, name, version, enabled, permissions, COALESCE(install_path, ''), COALESCE(content_scripts, '[]'), background FROM extensions ORDER BY name"
         );
 
         let mut stmt = match stmt {
@@ -81,6 +298,7 @@ impl ExtensionFramework {
                         performance_impact_ms: 0,
                         install_path: String::new(),
                         content_scripts: Vec::new(),
+                        background: None,
                     })
                 }).unwrap().filter_map(|r| r.ok()).collect();
                 return;
@@ -104,6 +322,7 @@ impl ExtensionFramework {
                 performance_impact_ms: 0,
                 install_path,
                 content_scripts,
+                background: row.get(7)?,
             })
         }).unwrap().filter_map(|r| r.ok()).collect();
     }
@@ -113,6 +332,22 @@ impl ExtensionFramework {
             .ok_or_else(|| ExtensionError::NotFound(id.to_string()))
     }
 
+    /// Drops queued and unanswered messages of an extension.
+    fn clear_messages(&mut self, extension_id: &str) {
+        self.inbox.remove(extension_id);
+        self.awaiting_reply.retain(|_, m| m.extension_id != extension_id);
+    }
+
+    fn require_storage(&self, extension_id: &str) -> Result<(), ExtensionError> {
+        let ext = &self.extensions[self.find_index(extension_id)?];
+        if !ext.permissions.contains(&ExtensionPermission::Storage) {
+            return Err(ExtensionError::PermissionDenied(format!(
+                "{} lacks the storage permission", extension_id
+            )));
+        }
+        Ok(())
+    }
+
     /// Parse a manifest.json from the given extension directory path.
     fn parse_manifest(extension_path: &str) -> Result<ExtensionManifest, ExtensionError> {
         let manifest_path = std::path::Path::new(extension_path).join("manifest.json");
@@ -123,9 +358,9 @@ impl ExtensionFramework {
         Ok(manifest)
     }
 
-    /// Read a file from the extension directory, returning its contents as a string.
+    /// Resolve a file inside the extension directory.
     /// SEC-09: Canonicalize path and verify it stays within the extension directory.
-    fn read_extension_file(base_path: &str, relative: &str) -> Result<String, ExtensionError> {
+    fn resolve_extension_file(base_path: &str, relative: &str) -> Result<PathBuf, ExtensionError> {
         let base = std::path::Path::new(base_path)
             .canonicalize()
             .map_err(|e| ExtensionError::LoadError(format!("Invalid base path: {}", e)))?;
@@ -139,9 +374,104 @@ impl ExtensionFramework {
                 relative
             )));
         }
+        Ok(full)
+    }
+
+    /// Read a file from the extension directory, returning its contents as a string.
+    fn read_extension_file(base_path: &str, relative: &str) -> Result<String, ExtensionError> {
+        let full = Self::resolve_extension_file(base_path, relative)?;
         std::fs::read_to_string(&full)
             .map_err(|e| ExtensionError::LoadError(format!("Cannot read {}: {}", relative, e)))
     }
+
+    fn enabled_extension(&self, extension_id: &str) -> Result<&ExtensionInfo, ExtensionError> {
+        let ext = &self.extensions[self.find_index(extension_id)?];
+        if !ext.enabled {
+            return Err(ExtensionError::PermissionDenied(format!("{} is disabled", extension_id)));
+        }
+        Ok(ext)
+    }
+}
+
+/// Builds the script that injects matched content scripts into a page. Each
+/// extension's JS runs with `chrome`/`browser` bound to its own messaging
+/// shim, so `runtime.sendMessage` is tagged with the right extension ID.
+/// Content scripts share the page's JS world, so the backend re-checks the
+/// sender URL against the extension's match patterns on every message.
+pub fn content_script_injection(scripts: &[MatchedContentScript]) -> String {
+    let mut js = String::from(EXTENSION_API_JS);
+    for script in scripts {
+        let id = serde_json::to_string(&script.extension_id).unwrap_or_default();
+        for css in &script.css {
+            let css = serde_json::to_string(css).unwrap_or_default();
+            js.push_str(&format!(
+                ";(function(){{var s=document.createElement('style');s.dataset.gbExtension={id};s.textContent={css};(document.head||document.documentElement).appendChild(s)}})()"
+            ));
+        }
+        for source in &script.js {
+            js.push_str(&format!(
+                ";(function(){{var api=window.__gb_ext_api({id});(function(chrome,browser){{\n{source}\n}})(api,api)}})()"
+            ));
+        }
+    }
+    js
+}
+
+/// URL of an extension's options page.
+pub fn options_url(extension_id: &str) -> String {
+    format!("gb://extension/{}/options", extension_id)
+}
+
+/// The extension ID of a page served from `gb://extension/<id>/...`.
+/// Accepts the `http(s)://gb.extension/` form WebView2 uses for custom schemes.
+pub fn extension_page_id(url: &str) -> Option<&str> {
+    let rest = ["gb://extension/", "http://gb.extension/", "https://gb.extension/"]
+        .iter()
+        .find_map(|prefix| url.strip_prefix(prefix))?;
+    let id = rest.split(['/', '?', '#']).next()?;
+    (!id.is_empty()).then_some(id)
+}
+
+/// Script binding the page's global `chrome`/`browser` to an extension's API.
+/// `chrome` is extended rather than replaced: WebView2 keeps its IPC bridge there.
+fn bind_extension_api(extension_id: &str) -> String {
+    let id = serde_json::to_string(extension_id).unwrap_or_default();
+    format!(
+        "(function(){{var api=window.__gb_ext_api({id});var c=window.chrome||{{}};\
+         c.runtime=api.runtime;c.storage=api.storage;window.chrome=c;window.browser=api}})();"
+    )
+}
+
+/// Puts the extension API shim in front of an extension page's own scripts,
+/// binding `chrome`/`browser` for that extension.
+fn inject_extension_api(html: &str, extension_id: &str) -> String {
+    let script = format!("<script>{}\n;{}</script>", EXTENSION_API_JS, bind_extension_api(extension_id));
+    let lower = html.to_ascii_lowercase();
+    match lower.find("<head").and_then(|i| lower[i..].find('>').map(|j| i + j + 1)) {
+        Some(at) => format!("{}{}{}", &html[..at], script, &html[at..]),
+        None => format!("{}{}", script, html),
+    }
+}
+
+/// Builds the hidden page that hosts an extension's background script.
+/// The script is evaluated globally, like a classic background page, and
+/// the page reports `bg_ready` once it has run so queued messages are not
+/// delivered early.
+pub fn background_page_html(script: &BackgroundScript) -> String {
+    // Embedded as a JSON string; escape `</` so the source cannot close the tag
+    let source = serde_json::to_string(&script.source).unwrap_or_default().replace("</", "<\\/");
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title></head><body><script>{api}\n\
+         ;{bind}try{{(0,eval)({source})}}catch(e){{console.error(e)}}\
+         if(window.ipc)window.ipc.postMessage(JSON.stringify({{cmd:'bg_ready'}}))</script></body></html>",
+        title = escape_html_text(&script.extension_name),
+        api = EXTENSION_API_JS,
+        bind = bind_extension_api(&script.extension_id),
+    )
+}
+
+fn escape_html_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
 }
 
 /// Check if a URL matches a content script pattern.
@@ -184,8 +514,7 @@ fn url_matches_pattern(url: &str, pattern: &str) -> bool {
 
     // Match host
     if host_pat != "*" {
-        if host_pat.starts_with("*.") {
-            let domain = &host_pat[2..];
+        if let Some(domain) = host_pat.strip_prefix("*.") {
             if url_host != domain && !url_host.ends_with(&format!(".{}", domain)) {
                 return false;
             }
@@ -223,7 +552,7 @@ fn simple_glob_match(pattern: &str, text: &str) -> bool {
 impl ExtensionFrameworkTrait for ExtensionFramework {
     fn install(&mut self, extension_path: &str) -> Result<String, ExtensionError> {
         // Try to parse manifest.json; fall back to placeholder if not found
-        let (id, name, version, permissions, content_scripts) =
+        let (id, name, version, permissions, content_scripts, background) =
             match Self::parse_manifest(extension_path) {
                 Ok(manifest) => (
                     if manifest.id.is_empty() { uuid::Uuid::new_v4().to_string() } else { manifest.id },
@@ -231,6 +560,7 @@ impl ExtensionFrameworkTrait for ExtensionFramework {
                     manifest.version,
                     manifest.permissions,
                     manifest.content_scripts,
+                    manifest.background,
                 ),
                 Err(_) => {
                     let id = uuid::Uuid::new_v4().to_string();
@@ -238,7 +568,7 @@ impl ExtensionFrameworkTrait for ExtensionFramework {
                         .or_else(|| extension_path.rsplit('\\').next())
                         .unwrap_or(extension_path)
                         .to_string();
-                    (id, name, "1.0.0".to_string(), Vec::new(), Vec::new())
+                    (id, name, "1.0.0".to_string(), Vec::new(), Vec::new(), None)
                 }
             };
 
@@ -249,8 +579,8 @@ impl ExtensionFrameworkTrait for ExtensionFramework {
             .map_err(|e| ExtensionError::InvalidManifest(e.to_string()))?;
 
         self.db.connection().execute(
-            "INSERT INTO extensions (id, name, version, enabled, install_path, permissions, content_scripts, installed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![id, name, version, 1, extension_path, perms_json, cs_json, now],
+            "INSERT INTO extensions (id, name, version, enabled, install_path, permissions, content_scripts, background, installed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![id, name, version, 1, extension_path, perms_json, cs_json, background, now],
         ).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
 
         let info = ExtensionInfo {
@@ -262,18 +592,62 @@ impl ExtensionFrameworkTrait for ExtensionFramework {
             performance_impact_ms: 0,
             install_path: extension_path.to_string(),
             content_scripts,
+            background,
         };
         self.extensions.push(info);
         Ok(id)
     }
 
+    fn request_install(&mut self, extension_path: &str) -> Result<PendingInstall, ExtensionError> {
+        let path = if extension_archive::is_archive(extension_path) {
+            self.unpack_archive(extension_path)?
+        } else {
+            extension_path.to_string()
+        };
+        let manifest = Self::parse_manifest(&path)?;
+        let pending = PendingInstall {
+            install_id: uuid::Uuid::new_v4().to_string(),
+            extension_id: manifest.id,
+            name: manifest.name,
+            version: manifest.version,
+            path,
+            permissions: manifest.permissions,
+        };
+        self.pending_installs.insert(pending.install_id.clone(), pending.clone());
+        Ok(pending)
+    }
+
+    fn confirm_install(&mut self, install_id: &str, granted: &[ExtensionPermission]) -> Result<String, ExtensionError> {
+        let pending = self.pending_installs.remove(install_id)
+            .ok_or_else(|| ExtensionError::NotFound(format!("pending install {}", install_id)))?;
+        let result = self.finish_install(&pending, granted);
+        if result.is_err() {
+            // A refused install leaves nothing unpacked behind
+            self.remove_managed_files(&pending.path);
+        }
+        result
+    }
+
+    fn cancel_install(&mut self, install_id: &str) -> Result<(), ExtensionError> {
+        let pending = self.pending_installs.remove(install_id)
+            .ok_or_else(|| ExtensionError::NotFound(format!("pending install {}", install_id)))?;
+        self.remove_managed_files(&pending.path);
+        Ok(())
+    }
+
     fn uninstall(&mut self, extension_id: &str) -> Result<(), ExtensionError> {
         let idx = self.find_index(extension_id)?;
         self.db.connection().execute(
             "DELETE FROM extensions WHERE id = ?1",
             params![extension_id],
         ).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
-        self.extensions.remove(idx);
+        self.db.connection().execute(
+            "DELETE FROM extension_storage WHERE extension_id = ?1",
+            params![extension_id],
+        ).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+        let removed = self.extensions.remove(idx);
+        self.clear_messages(extension_id);
+        self.remove_managed_files(&removed.install_path);
         Ok(())
     }
 
@@ -294,6 +668,7 @@ impl ExtensionFrameworkTrait for ExtensionFramework {
             params![extension_id],
         ).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
         self.extensions[idx].enabled = false;
+        self.clear_messages(extension_id);
         Ok(())
     }
 
@@ -356,4 +731,267 @@ impl ExtensionFrameworkTrait for ExtensionFramework {
     fn check_content_script_permission(&self, extension_id: &str) -> bool {
         self.has_permission(extension_id, &ExtensionPermission::PageContent)
     }
+
+    fn send_message(&mut self, extension_id: &str, sender: MessageSender, payload: Value) -> Result<u64, ExtensionError> {
+        self.check_sender(extension_id, &sender)?;
+
+        let size = serde_json::to_string(&payload).map(|s| s.len()).unwrap_or(usize::MAX);
+        if size > MAX_MESSAGE_BYTES {
+            return Err(ExtensionError::InvalidMessage(format!(
+                "payload is {} bytes, limit is {}", size, MAX_MESSAGE_BYTES
+            )));
+        }
+        let queued = self.inbox.get(extension_id).map_or(0, |q| q.len());
+        let awaiting = self.awaiting_reply.values().filter(|m| m.extension_id == extension_id).count();
+        if queued + awaiting >= MAX_PENDING_MESSAGES {
+            return Err(ExtensionError::InvalidMessage(format!("{} has too many pending messages", extension_id)));
+        }
+
+        let id = self.next_message_id;
+        self.next_message_id += 1;
+        self.inbox.entry(extension_id.to_string()).or_default().push_back(ExtensionMessage {
+            id,
+            extension_id: extension_id.to_string(),
+            sender,
+            payload,
+        });
+        Ok(id)
+    }
+
+    fn take_messages(&mut self, extension_id: &str) -> Vec<ExtensionMessage> {
+        let messages: Vec<ExtensionMessage> = self.inbox.remove(extension_id)
+            .map(Vec::from)
+            .unwrap_or_default();
+        for m in &messages {
+            self.awaiting_reply.insert(m.id, m.clone());
+        }
+        messages
+    }
+
+    fn reply(&mut self, extension_id: &str, message_id: u64, response: &Value) -> Result<ExtensionMessage, ExtensionError> {
+        let size = serde_json::to_string(response).map(|s| s.len()).unwrap_or(usize::MAX);
+        if size > MAX_MESSAGE_BYTES {
+            return Err(ExtensionError::InvalidMessage(format!(
+                "response is {} bytes, limit is {}", size, MAX_MESSAGE_BYTES
+            )));
+        }
+        // An extension can only answer messages addressed to it
+        match self.awaiting_reply.remove(&message_id) {
+            Some(m) if m.extension_id == extension_id => Ok(m),
+            Some(m) => {
+                self.awaiting_reply.insert(message_id, m);
+                Err(ExtensionError::InvalidMessage(format!("no pending message {}", message_id)))
+            }
+            None => Err(ExtensionError::InvalidMessage(format!("no pending message {}", message_id))),
+        }
+    }
+
+    fn check_sender(&self, extension_id: &str, sender: &MessageSender) -> Result<(), ExtensionError> {
+        let ext = &self.extensions[self.find_index(extension_id)?];
+        if !ext.enabled {
+            return Err(ExtensionError::PermissionDenied(format!("{} is disabled", extension_id)));
+        }
+        if let MessageSender::ExtensionPage { url, .. } = sender {
+            if extension_page_id(url) != Some(extension_id) {
+                return Err(ExtensionError::PermissionDenied(format!(
+                    "{} is not a page of {}", url, extension_id
+                )));
+            }
+        }
+        if let MessageSender::ContentScript { url, .. } = sender {
+            if !ext.permissions.contains(&ExtensionPermission::PageContent) {
+                return Err(ExtensionError::PermissionDenied(format!(
+                    "{} lacks the pagecontent permission", extension_id
+                )));
+            }
+            let runs_on_page = ext.content_scripts.iter()
+                .any(|cs| cs.matches.iter().any(|pat| url_matches_pattern(url, pat)));
+            if !runs_on_page {
+                return Err(ExtensionError::PermissionDenied(format!(
+                    "{} has no content script on {}", extension_id, url
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_background_changes(&mut self) -> Vec<BackgroundChange> {
+        let wanted: Vec<&ExtensionInfo> = self.extensions.iter()
+            .filter(|e| e.enabled && e.background.is_some())
+            .collect();
+
+        let mut changes = Vec::new();
+        let stopped: Vec<String> = self.backgrounds.keys()
+            .filter(|id| !wanted.iter().any(|e| &e.id == *id))
+            .cloned()
+            .collect();
+        for id in stopped {
+            self.backgrounds.remove(&id);
+            changes.push(BackgroundChange::Stop(id));
+        }
+
+        for ext in wanted {
+            if self.backgrounds.contains_key(&ext.id) { continue; }
+            // A script that fails to load is still recorded so it is not retried every poll
+            self.backgrounds.insert(ext.id.clone(), false);
+            let path = ext.background.as_deref().unwrap_or_default();
+            match Self::read_extension_file(&ext.install_path, path) {
+                Ok(source) => changes.push(BackgroundChange::Start(BackgroundScript {
+                    extension_id: ext.id.clone(),
+                    extension_name: ext.name.clone(),
+                    source,
+                })),
+                Err(e) => eprintln!("[EXT] background script of {} not started: {}", ext.id, e),
+            }
+        }
+        changes
+    }
+
+    fn mark_background_ready(&mut self, extension_id: &str) {
+        if let Some(ready) = self.backgrounds.get_mut(extension_id) {
+            *ready = true;
+        }
+    }
+
+    fn ready_backgrounds(&self) -> Vec<String> {
+        self.extensions.iter()
+            .filter(|e| self.backgrounds.get(&e.id).copied().unwrap_or(false))
+            .map(|e| e.id.clone())
+            .collect()
+    }
+
+    fn storage_get(&self, extension_id: &str, keys: Option<&[String]>) -> Result<Map<String, Value>, ExtensionError> {
+        self.require_storage(extension_id)?;
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(
+            "SELECT key, value FROM extension_storage WHERE extension_id = ?1 ORDER BY key"
+        ).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+        let rows = stmt.query_map(params![extension_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        }).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+
+        let mut items = Map::new();
+        for (key, value) in rows.filter_map(|r| r.ok()) {
+            if keys.is_some_and(|k| !k.contains(&key)) { continue; }
+            if let Ok(value) = serde_json::from_str(&value) {
+                items.insert(key, value);
+            }
+        }
+        Ok(items)
+    }
+
+    fn storage_set(&mut self, extension_id: &str, items: &Map<String, Value>) -> Result<(), ExtensionError> {
+        self.require_storage(extension_id)?;
+        let conn = self.db.connection();
+
+        // Usage after the write: untouched keys plus the new values
+        let mut stmt = conn.prepare(
+            "SELECT key, LENGTH(CAST(value AS BLOB)) FROM extension_storage WHERE extension_id = ?1"
+        ).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+        let kept: usize = stmt.query_map(params![extension_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        }).map_err(|e| ExtensionError::LoadError(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .filter(|(key, _)| !items.contains_key(key))
+            .map(|(key, len)| key.len() + len as usize)
+            .sum();
+        let encoded: Vec<(&String, String)> = items.iter().map(|(k, v)| (k, v.to_string())).collect();
+        let used = kept + encoded.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>();
+        if used > MAX_STORAGE_BYTES {
+            return Err(ExtensionError::QuotaExceeded(format!(
+                "{} needs {} bytes, limit is {}", extension_id, used, MAX_STORAGE_BYTES
+            )));
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        for (key, value) in encoded {
+            conn.execute(
+                "INSERT OR REPLACE INTO extension_storage (extension_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                params![extension_id, key, value, now],
+            ).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn storage_remove(&mut self, extension_id: &str, keys: &[String]) -> Result<(), ExtensionError> {
+        self.require_storage(extension_id)?;
+        for key in keys {
+            self.db.connection().execute(
+                "DELETE FROM extension_storage WHERE extension_id = ?1 AND key = ?2",
+                params![extension_id, key],
+            ).map_err(|e| ExtensionError::LoadError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn compile_blocking_rules(&self) -> Vec<NetworkRule> {
+        let mut compiled = Vec::new();
+        for ext in &self.extensions {
+            if !ext.enabled || !ext.permissions.contains(&ExtensionPermission::DeclarativeNetRequest) {
+                continue;
+            }
+            // Rulesets are not kept in the database; read them from the installed manifest
+            let Some(dnr) = Self::parse_manifest(&ext.install_path).ok().and_then(|m| m.declarative_net_request) else {
+                continue;
+            };
+            let mut rules = Vec::new();
+            for resource in dnr.rule_resources.iter().filter(|r| r.enabled) {
+                let parsed = Self::read_extension_file(&ext.install_path, &resource.path).and_then(|json| {
+                    serde_json::from_str::<Vec<DeclarativeRule>>(&json)
+                        .map_err(|e| ExtensionError::InvalidManifest(format!("{}: {}", resource.path, e)))
+                });
+                match parsed {
+                    Ok(list) => rules.extend(list),
+                    Err(e) => eprintln!("[EXT] ruleset {} of {} skipped: {}", resource.path, ext.id, e),
+                }
+            }
+            if rules.len() > MAX_RULES_PER_EXTENSION {
+                eprintln!("[EXT] {} declares {} rules, keeping {}", ext.id, rules.len(), MAX_RULES_PER_EXTENSION);
+                rules.truncate(MAX_RULES_PER_EXTENSION);
+            }
+            compiled.extend(rules.into_iter().filter_map(|rule| {
+                let action = match rule.action.action_type.as_str() {
+                    "block" => NetworkRuleAction::Block,
+                    "allow" => NetworkRuleAction::Allow,
+                    _ => return None,
+                };
+                Some(NetworkRule {
+                    extension_id: ext.id.clone(),
+                    rule_id: rule.id,
+                    priority: rule.priority,
+                    action,
+                    url_filter: rule.condition.url_filter,
+                    resource_types: rule.condition.resource_types,
+                    excluded_resource_types: rule.condition.excluded_resource_types,
+                    request_domains: rule.condition.request_domains,
+                    excluded_request_domains: rule.condition.excluded_request_domains,
+                })
+            }));
+        }
+        compiled
+    }
+
+    fn options_page_path(&self, extension_id: &str) -> Option<String> {
+        let ext = self.get_extension(extension_id)?;
+        let manifest = Self::parse_manifest(&ext.install_path).ok()?;
+        manifest.options_page.or(manifest.options_ui.map(|ui| ui.page))
+    }
+
+    fn options_page(&self, extension_id: &str) -> Result<String, ExtensionError> {
+        let ext = self.enabled_extension(extension_id)?;
+        let path = self.options_page_path(extension_id)
+            .ok_or_else(|| ExtensionError::NotFound(format!("{} has no options page", extension_id)))?;
+        let html = Self::read_extension_file(&ext.install_path, &path)?;
+        Ok(inject_extension_api(&html, extension_id))
+    }
+
+    fn extension_resource(&self, extension_id: &str, relative: &str) -> Result<Vec<u8>, ExtensionError> {
+        let ext = self.enabled_extension(extension_id)?;
+        // manifest.json is ours to interpret, not a page resource
+        if relative.trim_start_matches('/') == "manifest.json" {
+            return Err(ExtensionError::PermissionDenied("manifest.json is not served".to_string()));
+        }
+        let full = Self::resolve_extension_file(&ext.install_path, relative.trim_start_matches('/'))?;
+        std::fs::read(&full).map_err(|e| ExtensionError::LoadError(format!("Cannot read {}: {}", relative, e)))
+    }
 }
@@ -0,0 +1,247 @@
+//! Authenticated GitHub REST client.
+//!
+//! Shared by sync, notifications and repo features. GET responses that carry
+//! an `ETag` or `Last-Modified` are cached per URL and revalidated with
+//! conditional headers, so unchanged resources come back as a 304 that does
+//! not count against the quota. The last rate-limit headers are tracked so an
+//! exhausted quota fails fast, and `Retry-After` or secondary-limit answers
+//! are waited out a bounded number of times. A client used under the app
+//! lock is made with `without_waiting` and fails with `RateLimited` instead.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::services::github_integration::{send_request, ApiResponse};
+use crate::types::errors::GitHubError;
+use crate::types::github::RateLimit;
+
+/// Base URL of the public GitHub REST API.
+pub const API_BASE_URL: &str = "https://api.github.com";
+
+/// How often one call waits out a rate limit before giving up.
+const MAX_RETRIES: u32 = 2;
+
+/// Longest single wait, in seconds; longer limits fail with `RateLimited`.
+const MAX_RETRY_WAIT: u64 = 60;
+
+/// Wait for secondary limits that come without `Retry-After`, as GitHub
+/// recommends, in seconds.
+const SECONDARY_LIMIT_WAIT: u64 = 60;
+
+/// Cached GET response, kept for revalidation.
+struct CachedResponse {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Value,
+}
+
+/// State shared by all clients of one login: the response cache and the
+/// last rate-limit reading.
+#[derive(Default)]
+pub(crate) struct ClientState {
+    rate_limit: Option<RateLimit>,
+    cache: HashMap<String, CachedResponse>,
+}
+
+/// Cheap to clone; clones share the cache and rate-limit state, so a client
+/// can be taken out of the app lock for a slow request.
+#[derive(Clone)]
+pub struct GitHubClient {
+    token: String,
+    api_base: String,
+    /// How often one call waits out a rate limit before giving up
+    max_retries: u32,
+    state: Arc<Mutex<ClientState>>,
+}
+
+impl GitHubClient {
+    /// A client with its own, empty state.
+    pub fn new(token: &str) -> Self {
+        Self::with_state(token, Arc::default())
+    }
+
+    pub(crate) fn with_state(token: &str, state: Arc<Mutex<ClientState>>) -> Self {
+        Self {
+            token: token.to_string(),
+            api_base: API_BASE_URL.to_string(),
+            max_retries: MAX_RETRIES,
+            state,
+        }
+    }
+
+    /// Points the client at another API root, e.g. a GitHub Enterprise
+    /// Server's `/api/v3`.
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Fails with `RateLimited` rather than sleeping through a rate limit,
+    /// for callers that hold a lock everything else waits on.
+    pub fn without_waiting(mut self) -> Self {
+        self.max_retries = 0;
+        self
+    }
+
+    /// Absolute URL for `path`; absolute URLs are passed through.
+    pub fn url(&self, path: &str) -> String {
+        if path.starts_with("https://") || path.starts_with("http://") {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.api_base, path.trim_start_matches('/'))
+        }
+    }
+
+    /// Rate limit reported by the last response, if any carried one.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.state.lock().ok().and_then(|s| s.rate_limit)
+    }
+
+    pub fn get(&self, path: &str) -> Result<ApiResponse, GitHubError> {
+        self.request("GET", path, None)
+    }
+
+    pub fn post(&self, path: &str, body: Value) -> Result<ApiResponse, GitHubError> {
+        self.request("POST", path, Some(body))
+    }
+
+    pub fn patch(&self, path: &str, body: Value) -> Result<ApiResponse, GitHubError> {
+        self.request("PATCH", path, Some(body))
+    }
+
+    /// Sends `method` to `path` over the network.
+    pub fn request(&self, method: &str, path: &str, body: Option<Value>) -> Result<ApiResponse, GitHubError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        self.request_with(method, path, body, now, send_request, std::thread::sleep)
+    }
+
+    /// `request` with the transport and sleep injected, so caching and
+    /// rate-limit handling can be driven without a network or clock.
+    /// `transport` has the signature of `send_request`. A 304 answer keeps
+    /// its status but carries the cached body.
+    pub fn request_with(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+        mut now: i64,
+        mut transport: impl FnMut(&str, &str, Option<&str>, Option<Value>, &[(&str, &str)]) -> Result<ApiResponse, GitHubError>,
+        mut sleep: impl FnMut(Duration),
+    ) -> Result<ApiResponse, GitHubError> {
+        let url = self.url(path);
+        let is_get = method.eq_ignore_ascii_case("GET");
+        let mut attempt = 0;
+        loop {
+            if let Some(wait) = self.exhausted_for(now) {
+                return Err(GitHubError::RateLimited(wait));
+            }
+            let conditional = if is_get { self.conditional_headers(&url) } else { Vec::new() };
+            let headers: Vec<(&str, &str)> = conditional.iter().map(|(k, v)| (*k, v.as_str())).collect();
+            let response = transport(method, &url, Some(self.token.as_str()), body.clone(), &headers)?;
+            self.observe_rate_limit(&response);
+
+            if let Some(wait) = retry_wait(&response, now) {
+                if attempt >= self.max_retries || wait > MAX_RETRY_WAIT {
+                    return Err(GitHubError::RateLimited(wait));
+                }
+                sleep(Duration::from_secs(wait));
+                now += wait as i64;
+                attempt += 1;
+                continue;
+            }
+            return Ok(if is_get { self.revalidate(&url, response) } else { response });
+        }
+    }
+
+    /// Seconds until the quota resets, when the last response used it up.
+    fn exhausted_for(&self, now: i64) -> Option<u64> {
+        let limit = self.rate_limit()?;
+        (limit.remaining == 0 && limit.reset_at > now).then(|| (limit.reset_at - now) as u64)
+    }
+
+    fn conditional_headers(&self, url: &str) -> Vec<(&'static str, String)> {
+        let Ok(state) = self.state.lock() else { return Vec::new() };
+        let Some(cached) = state.cache.get(url) else { return Vec::new() };
+        let mut headers = Vec::new();
+        if let Some(etag) = &cached.etag {
+            headers.push(("If-None-Match", etag.clone()));
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            headers.push(("If-Modified-Since", last_modified.clone()));
+        }
+        headers
+    }
+
+    fn observe_rate_limit(&self, response: &ApiResponse) {
+        let read = |name: &str| response.header(name).and_then(|v| v.trim().parse::<i64>().ok());
+        let (Some(limit), Some(remaining), Some(reset_at)) =
+            (read("x-ratelimit-limit"), read("x-ratelimit-remaining"), read("x-ratelimit-reset"))
+        else {
+            return;
+        };
+        if let Ok(mut state) = self.state.lock() {
+            state.rate_limit = Some(RateLimit {
+                limit: limit.max(0) as u64,
+                remaining: remaining.max(0) as u64,
+                reset_at,
+            });
+        }
+    }
+
+    /// Fills a 304 from the cache and caches validated 2xx answers.
+    fn revalidate(&self, url: &str, mut response: ApiResponse) -> ApiResponse {
+        let Ok(mut state) = self.state.lock() else { return response };
+        match response.status {
+            304 => {
+                if let Some(cached) = state.cache.get(url) {
+                    response.body = cached.body.clone();
+                }
+            }
+            200..=299 => {
+                let etag = response.header("etag").map(String::from);
+                let last_modified = response.header("last-modified").map(String::from);
+                if etag.is_some() || last_modified.is_some() {
+                    let body = response.body.clone();
+                    state.cache.insert(url.to_string(), CachedResponse { etag, last_modified, body });
+                } else {
+                    state.cache.remove(url);
+                }
+            }
+            _ => {}
+        }
+        response
+    }
+}
+
+/// Seconds to wait before retrying, when `response` is a rate-limit answer.
+fn retry_wait(response: &ApiResponse, now: i64) -> Option<u64> {
+    if response.status != 403 && response.status != 429 {
+        return None;
+    }
+    if let Some(secs) = response.header("retry-after").and_then(|v| v.trim().parse::<u64>().ok()) {
+        return Some(secs);
+    }
+    if response.header("x-ratelimit-remaining").map(str::trim) == Some("0") {
+        let reset = response.header("x-ratelimit-reset").and_then(|v| v.trim().parse::<i64>().ok())?;
+        return Some((reset - now).max(1) as u64);
+    }
+    let message = response.body.get("message").and_then(|v| v.as_str()).unwrap_or_default();
+    if response.status == 429 || message.to_ascii_lowercase().contains("secondary rate limit") {
+        return Some(SECONDARY_LIMIT_WAIT);
+    }
+    // A plain 403 is a permission problem, not a limit
+    None
+}
+
+/// URL of the next page of a paginated response, from its `Link` header.
+pub fn next_page_url(response: &ApiResponse) -> Option<String> {
+    response.header("link")?.split(',').find_map(|part| {
+        let (target, params) = part.split_once(';')?;
+        params.split(';').any(|p| p.trim() == "rel=\"next\"").then(|| {
+            target.trim().trim_start_matches('<').trim_end_matches('>').to_string()
+        })
+    })
+}
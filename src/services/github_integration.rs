@@ -3,19 +3,43 @@
 //! Handles GitHub OAuth Device Flow, profile/notification/repo access,
 //! and encrypted bookmark/settings sync via Gists.
 
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use rusqlite::params;
+use serde_json::{json, Value};
 
 use crate::database::connection::Database;
+use crate::platform::keystore;
 use crate::services::crypto_service::{CryptoService, CryptoServiceTrait};
+use crate::services::github_client::{ClientState, GitHubClient, API_BASE_URL};
 use crate::types::credential::EncryptedData;
 use crate::types::errors::{CryptoError, GitHubError};
+use crate::types::github::{DeviceFlowProgress, DeviceTokenPoll, GitHubProfile, GitHubSettings, OAuthDeviceCode};
 
 const GITHUB_KEY_PASSPHRASE: &str = "gitbrowser-github-key-v1";
 const GITHUB_KEY_SALT: &[u8] = b"gitbrowser-ghky";
 
+/// Client ID of the GitBrowser OAuth app, used when the caller passes none.
+pub const DEFAULT_CLIENT_ID: &str = "Ov23licJwKwUSbHm9XF2";
+
+/// Scopes requested at login: repos, profile, notifications and sync gists.
+const DEVICE_FLOW_SCOPES: &str = "repo read:user notifications gist";
+
+const GITHUB_WEB_URL: &str = "https://github.com";
+const DEVICE_CODE_PATH: &str = "/login/device/code";
+const ACCESS_TOKEN_PATH: &str = "/login/oauth/access_token";
+
+/// `github_auth` row holding the github.com token.
+const GITHUB_COM_SLOT: &str = "default";
+const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Seconds added to the poll interval when GitHub answers `slow_down` without a new one.
+const SLOW_DOWN_STEP: u32 = 5;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(15);
+
 /// Trait defining GitHub integration operations.
 pub trait GitHubIntegrationTrait {
     fn store_token(&self, token: &str, login: &str, avatar_url: Option<&str>) -> Result<(), GitHubError>;
@@ -26,78 +50,365 @@ pub trait GitHubIntegrationTrait {
     fn decrypt_from_sync(&self, encrypted: &EncryptedData) -> Result<Vec<u8>, GitHubError>;
     /// Re-encrypt all stored data with a new master key (called when master password is set/changed).
     fn rekey_with_master(&mut self, master_key: &[u8]) -> Result<(), GitHubError>;
+    /// Stores the token obtained by a device flow login and marks the session authenticated.
+    fn complete_login(&mut self, token: &str, profile: &GitHubProfile) -> Result<(), GitHubError>;
+    /// REST client for the stored token. Clients share one response cache and
+    /// rate-limit state until logout.
+    fn client(&self) -> Result<GitHubClient, GitHubError>;
+}
+
+/// Where logins and API calls go: github.com or a GitHub Enterprise Server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubEndpoints {
+    /// Web root, also serving the OAuth endpoints.
+    pub web_url: String,
+    /// REST API root.
+    pub api_url: String,
+    /// OAuth app for device flow logins, when one is known for the host.
+    pub client_id: Option<String>,
+    /// `github_auth` row holding this host's token, so each host keeps its own login.
+    pub credential_slot: String,
+}
+
+impl GitHubEndpoints {
+    pub fn github_com() -> Self {
+        Self {
+            web_url: GITHUB_WEB_URL.to_string(),
+            api_url: API_BASE_URL.to_string(),
+            client_id: Some(DEFAULT_CLIENT_ID.to_string()),
+            credential_slot: GITHUB_COM_SLOT.to_string(),
+        }
+    }
+
+    /// The endpoints selected by `github.*` settings. The enterprise token
+    /// slot is named after the server's host (and port).
+    pub fn from_settings(settings: &GitHubSettings) -> Result<Self, GitHubError> {
+        if !settings.use_enterprise {
+            return Ok(Self::github_com());
+        }
+        let web_url = settings.enterprise_web_url.as_deref().map(str::trim).filter(|u| !u.is_empty())
+            .ok_or_else(|| GitHubError::InvalidConfig("enterprise web URL is not set".to_string()))?;
+        let web = parse_https_url(web_url)?;
+        let host = web.host_str()
+            .ok_or_else(|| GitHubError::InvalidConfig(format!("no host in {}", web_url)))?;
+        let credential_slot = match web.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        let web_url = web_url.trim_end_matches('/').to_string();
+        let api_url = match settings.enterprise_api_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+            Some(api_url) => {
+                parse_https_url(api_url)?;
+                api_url.trim_end_matches('/').to_string()
+            }
+            None => format!("{}/api/v3", web_url),
+        };
+        Ok(Self {
+            web_url,
+            api_url,
+            client_id: settings.enterprise_client_id.clone().filter(|id| !id.trim().is_empty()),
+            credential_slot,
+        })
+    }
+
+    /// OAuth client ID for a device flow login: `requested` if given,
+    /// otherwise the one known for the host.
+    pub fn device_client_id(&self, requested: Option<&str>) -> Result<String, GitHubError> {
+        requested.map(str::to_string).or_else(|| self.client_id.clone()).ok_or_else(|| {
+            GitHubError::InvalidConfig(format!("no OAuth client ID configured for {}", self.web_url))
+        })
+    }
+}
+
+fn parse_https_url(url: &str) -> Result<reqwest::Url, GitHubError> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| GitHubError::InvalidConfig(format!("{}: {}", url, e)))?;
+    if parsed.scheme() != "https" {
+        return Err(GitHubError::InvalidConfig(format!("{} is not an https:// URL", url)));
+    }
+    Ok(parsed)
 }
 
 /// GitHub integration backed by SQLite + CryptoService.
 pub struct GitHubIntegration {
     db: Arc<Database>,
+    endpoints: GitHubEndpoints,
     crypto: CryptoService,
     encryption_key: Vec<u8>,
     #[allow(dead_code)]
     fallback_key: Vec<u8>,
     authenticated: bool,
+    client_state: Arc<Mutex<ClientState>>,
 }
 
 impl GitHubIntegration {
     pub fn new(db: Arc<Database>) -> Result<Self, CryptoError> {
         let crypto = CryptoService::new();
-        let fallback_key = crypto.derive_key(GITHUB_KEY_PASSPHRASE, GITHUB_KEY_SALT)?;
+        let legacy_key = crypto.derive_key(GITHUB_KEY_PASSPHRASE, GITHUB_KEY_SALT)?;
 
-        // Check if a master-derived key is stored; otherwise use fallback
+        // Until the master password is unlocked, tokens are encrypted with
+        // the data key from the OS keystore; the built-in key is only used
+        // where there is no keystore.
+        let fallback_key = keystore::data_key().unwrap_or_else(|e| {
+            eprintln!("[GITHUB] {}; encrypting tokens with the built-in key", e);
+            legacy_key.clone()
+        });
         let encryption_key = fallback_key.clone();
 
-        // Check if already authenticated
-        let authenticated = {
-            let conn = db.connection();
-            let count: i64 = conn
-                .query_row("SELECT COUNT(*) FROM github_auth", [], |row| row.get(0))
-                .unwrap_or(0);
-            count > 0
-        };
+        let endpoints = GitHubEndpoints::github_com();
+        let authenticated = Self::has_token(&db, &endpoints.credential_slot);
 
-        Ok(Self {
+        let integration = Self {
             db,
+            endpoints,
             crypto,
             encryption_key,
             fallback_key,
             authenticated,
-        })
+            client_state: Arc::default(),
+        };
+        if integration.fallback_key != legacy_key {
+            // Tokens saved before the data key existed.
+            let _ = integration.rekey_token(&legacy_key, &integration.fallback_key);
+        }
+        Ok(integration)
+    }
+
+    fn has_token(db: &Database, slot: &str) -> bool {
+        db.connection()
+            .query_row("SELECT COUNT(*) FROM github_auth WHERE id = ?1", params![slot], |row| row.get::<_, i64>(0))
+            .unwrap_or(0) > 0
+    }
+
+    pub fn endpoints(&self) -> &GitHubEndpoints {
+        &self.endpoints
+    }
+
+    /// Switches to another host. Its token slot decides whether the session
+    /// is authenticated; the other host's token is kept for switching back.
+    pub fn set_endpoints(&mut self, endpoints: GitHubEndpoints) {
+        if endpoints == self.endpoints {
+            return;
+        }
+        self.authenticated = Self::has_token(&self.db, &endpoints.credential_slot);
+        self.endpoints = endpoints;
+        self.reset_client_state();
+    }
+
+    /// Drops cached responses and rate-limit readings; they belong to the
+    /// previous token.
+    fn reset_client_state(&self) {
+        if let Ok(mut state) = self.client_state.lock() {
+            *state = ClientState::default();
+        }
     }
 
     /// Update the encryption key to use the master password derived key.
-    /// Re-encrypts the stored token with the new key.
+    /// Re-encrypts the stored token of every host with the new key; tokens
+    /// `old_key` does not open are already under another key and kept.
     fn rekey_token(&self, old_key: &[u8], new_key: &[u8]) -> Result<(), GitHubError> {
         let conn = self.db.connection();
-        let result = conn.query_row(
-            "SELECT encrypted_token, iv, auth_tag FROM github_auth WHERE id = 'default'",
-            [],
-            |row| {
-                Ok(EncryptedData {
-                    ciphertext: row.get(0)?,
-                    iv: row.get(1)?,
-                    auth_tag: row.get(2)?,
-                })
-            },
-        );
+        let stored: Vec<(String, EncryptedData)> = {
+            let mut stmt = conn.prepare("SELECT id, encrypted_token, iv, auth_tag FROM github_auth")
+                .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get(0)?, EncryptedData {
+                    ciphertext: row.get(1)?,
+                    iv: row.get(2)?,
+                    auth_tag: row.get(3)?,
+                }))
+            }).map_err(|e| GitHubError::ApiError(e.to_string()))?;
+            rows.collect::<Result<_, _>>().map_err(|e| GitHubError::ApiError(e.to_string()))?
+        };
 
-        match result {
-            Ok(encrypted) => {
-                let decrypted = self.crypto.decrypt_aes256gcm(&encrypted, old_key)
-                    .map_err(|e| GitHubError::AuthFailed(e.to_string()))?;
-                let re_encrypted = self.crypto.encrypt_aes256gcm(&decrypted, new_key)
-                    .map_err(|e| GitHubError::AuthFailed(e.to_string()))?;
-                conn.execute(
-                    "UPDATE github_auth SET encrypted_token = ?1, iv = ?2, auth_tag = ?3 WHERE id = 'default'",
-                    params![re_encrypted.ciphertext, re_encrypted.iv, re_encrypted.auth_tag],
-                ).map_err(|e| GitHubError::ApiError(e.to_string()))?;
-                Ok(())
+        for (slot, encrypted) in stored {
+            let Ok(decrypted) = self.crypto.decrypt_aes256gcm(&encrypted, old_key) else {
+                continue;
+            };
+            let re_encrypted = self.crypto.encrypt_aes256gcm(&decrypted, new_key)
+                .map_err(|e| GitHubError::AuthFailed(e.to_string()))?;
+            conn.execute(
+                "UPDATE github_auth SET encrypted_token = ?1, iv = ?2, auth_tag = ?3 WHERE id = ?4",
+                params![re_encrypted.ciphertext, re_encrypted.iv, re_encrypted.auth_tag, slot],
+            ).map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Starts a device flow login; the user enters `user_code` at `verification_uri`.
+    pub fn request_device_code(endpoints: &GitHubEndpoints, client_id: &str) -> Result<OAuthDeviceCode, GitHubError> {
+        let body = json!({"client_id": client_id, "scope": DEVICE_FLOW_SCOPES});
+        let url = format!("{}{}", endpoints.web_url, DEVICE_CODE_PATH);
+        let (_, response) = send_blocking("POST", &url, None, Some(body))?;
+        parse_device_code(&response)
+    }
+
+    /// Asks once whether the user has approved the device code.
+    pub fn poll_device_token(
+        endpoints: &GitHubEndpoints,
+        client_id: &str,
+        device_code: &str,
+    ) -> Result<DeviceTokenPoll, GitHubError> {
+        let body = json!({
+            "client_id": client_id,
+            "device_code": device_code,
+            "grant_type": DEVICE_GRANT_TYPE,
+        });
+        let url = format!("{}{}", endpoints.web_url, ACCESS_TOKEN_PATH);
+        let (_, response) = send_blocking("POST", &url, None, Some(body))?;
+        parse_token_poll(&response)
+    }
+
+    /// Fetches the profile of the account that owns `token`.
+    pub fn fetch_profile(endpoints: &GitHubEndpoints, token: &str) -> Result<GitHubProfile, GitHubError> {
+        let response = GitHubClient::new(token).with_api_base(&endpoints.api_url).get("/user")?;
+        match response.status {
+            200..=299 => parse_profile(&response.body),
+            401 => Err(GitHubError::AuthFailed(api_message(&response.body))),
+            status => Err(GitHubError::ApiError(format!("HTTP {}: {}", status, api_message(&response.body)))),
+        }
+    }
+
+    /// Polls until the device code is approved, honouring the interval and
+    /// `slow_down` answers, and returns the access token. `sleep` and `poll`
+    /// are injected so the schedule can be driven without a network or clock.
+    pub fn wait_for_device_token(
+        code: &OAuthDeviceCode,
+        mut poll: impl FnMut() -> Result<DeviceTokenPoll, GitHubError>,
+        mut sleep: impl FnMut(Duration),
+        mut progress: impl FnMut(DeviceFlowProgress),
+    ) -> Result<String, GitHubError> {
+        let mut interval = code.interval.max(1);
+        let mut waited: u64 = 0;
+        while waited < code.expires_in as u64 {
+            sleep(Duration::from_secs(interval as u64));
+            waited += interval as u64;
+            match poll() {
+                Ok(DeviceTokenPoll::Granted(token)) => return Ok(token),
+                Ok(DeviceTokenPoll::Pending) => progress(DeviceFlowProgress::Pending),
+                Ok(DeviceTokenPoll::SlowDown(next)) => {
+                    interval = next.unwrap_or(interval + SLOW_DOWN_STEP);
+                    progress(DeviceFlowProgress::SlowDown { interval });
+                }
+                // A dropped request is retried on the next tick; the deadline still applies
+                Err(GitHubError::NetworkError(_)) => {}
+                Err(e) => return Err(e),
             }
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(()),
-            Err(e) => Err(GitHubError::ApiError(e.to_string())),
         }
+        Err(GitHubError::DeviceCodeExpired)
+    }
+}
+
+/// Parses the device code endpoint response.
+pub fn parse_device_code(response: &Value) -> Result<OAuthDeviceCode, GitHubError> {
+    if response.get("error").is_some() {
+        return Err(GitHubError::AuthFailed(api_message(response)));
+    }
+    serde_json::from_value(response.clone()).map_err(|e| GitHubError::ApiError(e.to_string()))
+}
+
+/// Parses one access token endpoint response of the device flow.
+pub fn parse_token_poll(response: &Value) -> Result<DeviceTokenPoll, GitHubError> {
+    if let Some(token) = response.get("access_token").and_then(|v| v.as_str()) {
+        return Ok(DeviceTokenPoll::Granted(token.to_string()));
+    }
+    match response.get("error").and_then(|v| v.as_str()) {
+        Some("authorization_pending") => Ok(DeviceTokenPoll::Pending),
+        Some("slow_down") => Ok(DeviceTokenPoll::SlowDown(
+            response.get("interval").and_then(|v| v.as_u64()).map(|v| v as u32),
+        )),
+        Some("expired_token") => Err(GitHubError::DeviceCodeExpired),
+        Some(_) => Err(GitHubError::AuthFailed(api_message(response))),
+        None => Err(GitHubError::ApiError("no access token in response".to_string())),
     }
 }
 
+/// Parses a `GET /user` response.
+pub fn parse_profile(response: &Value) -> Result<GitHubProfile, GitHubError> {
+    serde_json::from_value(response.clone()).map_err(|e| GitHubError::ApiError(e.to_string()))
+}
+
+/// The human-readable part of a GitHub error body.
+pub(crate) fn api_message(response: &Value) -> String {
+    ["error_description", "message", "error"]
+        .iter()
+        .find_map(|key| response.get(*key).and_then(|v| v.as_str()))
+        .unwrap_or("unknown error")
+        .to_string()
+}
+
+/// A GitHub API response with the headers callers care about.
+#[derive(Debug, Clone, Default)]
+pub struct ApiResponse {
+    pub status: u16,
+    /// Response headers, names lowercased.
+    pub headers: HashMap<String, String>,
+    /// JSON body, `null` when the response has none.
+    pub body: Value,
+}
+
+impl ApiResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+}
+
+/// Sends one request, with `body` as JSON when set, and returns the status
+/// and JSON body (`null` when the response has none).
+pub(crate) fn send_blocking(
+    method: &str,
+    url: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> Result<(u16, Value), GitHubError> {
+    send_request(method, url, token, body, &[]).map(|r| (r.status, r.body))
+}
+
+/// Like `send_blocking`, with extra request headers and the full response.
+pub(crate) fn send_request(
+    method: &str,
+    url: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+    extra_headers: &[(&str, &str)],
+) -> Result<ApiResponse, GitHubError> {
+    let method = reqwest::Method::from_bytes(method.as_bytes())
+        .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| GitHubError::NetworkError(e.to_string()))?;
+    rt.block_on(async {
+        let client = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .user_agent(concat!("GitBrowser/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| GitHubError::NetworkError(e.to_string()))?;
+        let mut request = client.request(method, url).header("Accept", "application/json");
+        if let Some(body) = body {
+            request = request.header("Content-Type", "application/json").body(body.to_string());
+        }
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        for (name, value) in extra_headers {
+            request = request.header(*name, *value);
+        }
+        let resp = request.send().await.map_err(|e| GitHubError::NetworkError(e.to_string()))?;
+        let status = resp.status().as_u16();
+        let headers = resp.headers().iter()
+            .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let bytes = resp.bytes().await.map_err(|e| GitHubError::NetworkError(e.to_string()))?;
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes)
+                .map_err(|e| GitHubError::ApiError(format!("HTTP {}: {}", status, e)))?
+        };
+        Ok(ApiResponse { status, headers, body })
+    })
+}
+
 impl GitHubIntegrationTrait for GitHubIntegration {
     fn store_token(&self, token: &str, login: &str, avatar_url: Option<&str>) -> Result<(), GitHubError> {
         let encrypted = self.crypto.encrypt_aes256gcm(token.as_bytes(), &self.encryption_key)
@@ -106,9 +417,10 @@ impl GitHubIntegrationTrait for GitHubIntegration {
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
 
         self.db.connection().execute(
-            "INSERT OR REPLACE INTO github_auth (id, encrypted_token, iv, auth_tag, login, avatar_url, updated_at) VALUES ('default', ?1, ?2, ?3, ?4, ?5, ?6)",
-            params![encrypted.ciphertext, encrypted.iv, encrypted.auth_tag, login, avatar_url, now],
+            "INSERT OR REPLACE INTO github_auth (id, encrypted_token, iv, auth_tag, login, avatar_url, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![self.endpoints.credential_slot, encrypted.ciphertext, encrypted.iv, encrypted.auth_tag, login, avatar_url, now],
         ).map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        self.reset_client_state();
 
         Ok(())
     }
@@ -116,8 +428,8 @@ impl GitHubIntegrationTrait for GitHubIntegration {
     fn get_token(&self) -> Result<Option<String>, GitHubError> {
         let conn = self.db.connection();
         let result = conn.query_row(
-            "SELECT encrypted_token, iv, auth_tag FROM github_auth WHERE id = 'default'",
-            [],
+            "SELECT encrypted_token, iv, auth_tag FROM github_auth WHERE id = ?1",
+            params![self.endpoints.credential_slot],
             |row| {
                 Ok(EncryptedData {
                     ciphertext: row.get(0)?,
@@ -141,10 +453,15 @@ impl GitHubIntegrationTrait for GitHubIntegration {
     }
 
     fn logout(&mut self) -> Result<(), GitHubError> {
-        self.db.connection().execute("DELETE FROM github_auth", [])
+        self.db.connection().execute("DELETE FROM github_auth WHERE id = ?1", params![self.endpoints.credential_slot])
             .map_err(|e| GitHubError::ApiError(e.to_string()))?;
         self.db.connection().execute("DELETE FROM github_sync", [])
             .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        self.db.connection().execute("DELETE FROM github_notifications", [])
+            .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        self.db.connection().execute("DELETE FROM github_repos", [])
+            .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        self.reset_client_state();
         self.authenticated = false;
         Ok(())
     }
@@ -169,4 +486,15 @@ impl GitHubIntegrationTrait for GitHubIntegration {
         self.encryption_key = master_key.to_vec();
         Ok(())
     }
+
+    fn complete_login(&mut self, token: &str, profile: &GitHubProfile) -> Result<(), GitHubError> {
+        self.store_token(token, &profile.login, Some(&profile.avatar_url))?;
+        self.authenticated = true;
+        Ok(())
+    }
+
+    fn client(&self) -> Result<GitHubClient, GitHubError> {
+        let token = self.get_token()?.ok_or(GitHubError::NotAuthenticated)?;
+        Ok(GitHubClient::with_state(&token, self.client_state.clone()).with_api_base(&self.endpoints.api_url))
+    }
 }
@@ -0,0 +1,246 @@
+//! GitHub notifications for GitBrowser.
+//!
+//! Polls `GET /notifications` through `GitHubClient`, which revalidates the
+//! last answer with `If-Modified-Since`. Polls are spaced by the
+//! `X-Poll-Interval` GitHub asks for, so an idle inbox costs one 304 per
+//! interval. Fetched threads are kept in SQLite; mentions and review requests
+//! are handed out once each for a desktop notification.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use rusqlite::params;
+use serde_json::Value;
+
+use crate::database::connection::Database;
+use crate::services::github_client::{next_page_url, GitHubClient};
+use crate::services::github_integration::{api_message, ApiResponse};
+use crate::types::errors::GitHubError;
+use crate::types::github::GitHubNotification;
+
+const NOTIFICATIONS_PATH: &str = "/notifications?per_page=50";
+
+/// Poll interval used until GitHub sends `X-Poll-Interval`, in seconds.
+pub const DEFAULT_POLL_INTERVAL_SECS: u64 = 60;
+
+/// Reasons that raise a desktop notification.
+const ALERT_REASONS: &[&str] = &["mention", "team_mention", "review_requested"];
+
+/// Most desktop notifications shown per poll; the rest are summed up in one.
+pub const MAX_ALERTS_PER_POLL: usize = 3;
+
+/// Trait defining GitHub notification operations.
+pub trait GitHubNotificationsTrait {
+    /// Whether GitHub's poll interval has passed since the last request.
+    fn poll_due(&self, now: i64) -> bool;
+    /// Stores a `/notifications` response. Returns the unread mentions and
+    /// review requests that have not raised a desktop notification yet.
+    fn record_response(&mut self, response: &ApiResponse, now: i64) -> Result<Vec<GitHubNotification>, GitHubError>;
+    /// Notes a failed request, so the next poll waits a full interval (or
+    /// out the rate limit) rather than retrying at once.
+    fn record_failure(&mut self, error: &GitHubError, now: i64);
+    /// Stored notifications, most recently updated first.
+    fn list_notifications(&self, unread_only: bool) -> Result<Vec<GitHubNotification>, GitHubError>;
+    fn unread_count(&self) -> Result<i64, GitHubError>;
+}
+
+/// Notification inbox backed by SQLite; poll timing is kept in memory.
+pub struct GitHubNotifications {
+    db: Arc<Database>,
+    poll_interval: u64,
+    next_poll_at: i64,
+}
+
+impl GitHubNotifications {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            db,
+            poll_interval: DEFAULT_POLL_INTERVAL_SECS,
+            next_poll_at: 0,
+        }
+    }
+
+    /// Requests the unread notification list.
+    pub fn fetch(client: &GitHubClient) -> Result<ApiResponse, GitHubError> {
+        client.get(NOTIFICATIONS_PATH)
+    }
+
+    /// Fetches and records in one step.
+    pub fn poll(&mut self, client: &GitHubClient, now: i64) -> Result<Vec<GitHubNotification>, GitHubError> {
+        let response = Self::fetch(client).inspect_err(|e| self.record_failure(e, now))?;
+        self.record_response(&response, now)
+    }
+
+    fn row_to_notification(row: &rusqlite::Row) -> rusqlite::Result<GitHubNotification> {
+        Ok(GitHubNotification {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            repo_full_name: row.get(2)?,
+            notification_type: row.get(3)?,
+            reason: row.get(4)?,
+            unread: row.get::<_, i32>(5)? != 0,
+            updated_at: row.get(6)?,
+            url: row.get(7)?,
+        })
+    }
+
+    fn store(&self, threads: &[GitHubNotification], complete: bool) -> Result<(), GitHubError> {
+        let db_err = |e: rusqlite::Error| GitHubError::ApiError(e.to_string());
+        let conn = self.db.connection();
+        if complete {
+            // The response lists every unread thread, so the rest were read elsewhere
+            let fetched: HashSet<&str> = threads.iter().map(|t| t.id.as_str()).collect();
+            for id in self.unread_ids()?.iter().filter(|id| !fetched.contains(id.as_str())) {
+                conn.execute("UPDATE github_notifications SET unread = 0 WHERE id = ?1", params![id])
+                    .map_err(db_err)?;
+            }
+        }
+        for t in threads {
+            conn.execute(
+                "INSERT INTO github_notifications (id, title, repo_full_name, notification_type, reason, unread, updated_at, url) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) \
+                 ON CONFLICT(id) DO UPDATE SET title = excluded.title, reason = excluded.reason, \
+                 unread = excluded.unread, updated_at = excluded.updated_at, url = excluded.url",
+                params![t.id, t.title, t.repo_full_name, t.notification_type, t.reason, t.unread as i32, t.updated_at, t.url],
+            ).map_err(db_err)?;
+        }
+        Ok(())
+    }
+
+    fn unread_ids(&self) -> Result<Vec<String>, GitHubError> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare("SELECT id FROM github_notifications WHERE unread = 1")
+            .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        let rows = stmt.query_map([], |row| row.get(0))
+            .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        rows.collect::<Result<Vec<String>, _>>().map_err(|e| GitHubError::ApiError(e.to_string()))
+    }
+
+    /// Unread alert-worthy threads not alerted yet; marks them alerted.
+    fn take_alerts(&self) -> Result<Vec<GitHubNotification>, GitHubError> {
+        let conn = self.db.connection();
+        let placeholders = vec!["?"; ALERT_REASONS.len()].join(", ");
+        let sql = format!(
+            "SELECT {} FROM github_notifications WHERE unread = 1 AND alerted = 0 AND reason IN ({}) ORDER BY updated_at",
+            COLUMNS, placeholders
+        );
+        let alerts = {
+            let mut stmt = conn.prepare(&sql).map_err(|e| GitHubError::ApiError(e.to_string()))?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(ALERT_REASONS.iter()), Self::row_to_notification)
+                .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(|e| GitHubError::ApiError(e.to_string()))?
+        };
+        for n in &alerts {
+            conn.execute("UPDATE github_notifications SET alerted = 1 WHERE id = ?1", params![n.id])
+                .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        }
+        Ok(alerts)
+    }
+}
+
+const COLUMNS: &str = "id, title, repo_full_name, notification_type, reason, unread, updated_at, url";
+
+impl GitHubNotificationsTrait for GitHubNotifications {
+    fn poll_due(&self, now: i64) -> bool {
+        now >= self.next_poll_at
+    }
+
+    fn record_response(&mut self, response: &ApiResponse, now: i64) -> Result<Vec<GitHubNotification>, GitHubError> {
+        if let Some(secs) = response.header("x-poll-interval").and_then(|v| v.trim().parse::<u64>().ok()) {
+            self.poll_interval = secs.max(1);
+        }
+        self.next_poll_at = now + self.poll_interval as i64;
+
+        match response.status {
+            304 => Ok(Vec::new()),
+            200..=299 => {
+                let threads: Vec<GitHubNotification> = response.body.as_array()
+                    .ok_or_else(|| GitHubError::ApiError("notifications response is not a list".to_string()))?
+                    .iter()
+                    .filter_map(parse_notification)
+                    .collect();
+                // Only a single-page answer tells which threads are no longer unread
+                let complete = next_page_url(response).is_none();
+                self.store(&threads, complete)?;
+                self.take_alerts()
+            }
+            401 => Err(GitHubError::AuthFailed(api_message(&response.body))),
+            status => Err(GitHubError::ApiError(format!("HTTP {}: {}", status, api_message(&response.body)))),
+        }
+    }
+
+    fn record_failure(&mut self, error: &GitHubError, now: i64) {
+        let wait = match error {
+            GitHubError::RateLimited(secs) => (*secs).max(self.poll_interval),
+            _ => self.poll_interval,
+        };
+        self.next_poll_at = now + wait as i64;
+    }
+
+    fn list_notifications(&self, unread_only: bool) -> Result<Vec<GitHubNotification>, GitHubError> {
+        let filter = if unread_only { "WHERE unread = 1" } else { "" };
+        let sql = format!("SELECT {} FROM github_notifications {} ORDER BY updated_at DESC", COLUMNS, filter);
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(&sql).map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        let rows = stmt.query_map([], Self::row_to_notification)
+            .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| GitHubError::ApiError(e.to_string()))
+    }
+
+    fn unread_count(&self) -> Result<i64, GitHubError> {
+        self.db.connection()
+            .query_row("SELECT COUNT(*) FROM github_notifications WHERE unread = 1", [], |row| row.get(0))
+            .map_err(|e| GitHubError::ApiError(e.to_string()))
+    }
+}
+
+/// Parses one thread of the `/notifications` response.
+pub fn parse_notification(thread: &Value) -> Option<GitHubNotification> {
+    let repo = &thread["repository"];
+    let subject = &thread["subject"];
+    Some(GitHubNotification {
+        id: thread["id"].as_str()?.to_string(),
+        title: subject["title"].as_str().unwrap_or_default().to_string(),
+        repo_full_name: repo["full_name"].as_str().unwrap_or_default().to_string(),
+        notification_type: subject["type"].as_str().unwrap_or_default().to_string(),
+        reason: thread["reason"].as_str().unwrap_or_default().to_string(),
+        unread: thread["unread"].as_bool().unwrap_or(true),
+        updated_at: thread["updated_at"].as_str().unwrap_or_default().to_string(),
+        url: html_url(subject["url"].as_str(), repo["html_url"].as_str().unwrap_or_default()),
+    })
+}
+
+/// Maps a subject's API URL (`.../repos/o/r/pulls/1`) to the page for it
+/// under the repository's web URL. Unknown subjects open the repository.
+pub fn html_url(subject_url: Option<&str>, repo_html_url: &str) -> String {
+    let Some(rest) = subject_url.and_then(|u| u.split_once("/repos/")).map(|(_, rest)| rest) else {
+        return repo_html_url.to_string();
+    };
+    let parts: Vec<&str> = rest.split('/').collect();
+    match parts.as_slice() {
+        [_, _, "pulls", number, ..] => format!("{}/pull/{}", repo_html_url, number),
+        [_, _, "issues", number, ..] => format!("{}/issues/{}", repo_html_url, number),
+        [_, _, "commits", sha, ..] => format!("{}/commit/{}", repo_html_url, sha),
+        [_, _, "releases", ..] => format!("{}/releases", repo_html_url),
+        [_, _, "discussions", number, ..] => format!("{}/discussions/{}", repo_html_url, number),
+        _ => repo_html_url.to_string(),
+    }
+}
+
+/// Title and body of the desktop notifications for one poll: one per alert
+/// up to `MAX_ALERTS_PER_POLL`, then a single summary of the rest.
+pub fn alert_texts(alerts: &[GitHubNotification]) -> Vec<(String, String)> {
+    let describe = |n: &GitHubNotification| {
+        let what = if n.reason == "review_requested" { "Review requested" } else { "Mentioned" };
+        (format!("{} in {}", what, n.repo_full_name), n.title.clone())
+    };
+    if alerts.len() <= MAX_ALERTS_PER_POLL {
+        return alerts.iter().map(describe).collect();
+    }
+    let mut texts: Vec<(String, String)> = alerts[..MAX_ALERTS_PER_POLL - 1].iter().map(describe).collect();
+    texts.push((
+        "GitHub".to_string(),
+        format!("{} more mentions and review requests", alerts.len() - (MAX_ALERTS_PER_POLL - 1)),
+    ));
+    texts
+}
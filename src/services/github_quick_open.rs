@@ -0,0 +1,284 @@
+//! `gh` omnibox command for GitBrowser.
+//!
+//! `gh owner/repo [section | number | #number] [terms]` resolves to a page on
+//! the active GitHub host without a request; `gh owner` opens a profile.
+//! Suggestions come from a local cache of the user's own and starred
+//! repositories, refreshed through `GitHubClient` when it goes stale. A bare
+//! repository name (`gh rust issues`) is looked up in the same cache.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use rusqlite::params;
+
+use crate::database::connection::Database;
+use crate::services::github_client::{next_page_url, GitHubClient};
+use crate::services::github_integration::api_message;
+use crate::services::search_engine_registry::encode_query;
+use crate::types::errors::GitHubError;
+use crate::types::github::{GitHubRepo, QuickOpenSuggestion};
+
+/// Omnibox trigger word.
+pub const GH_COMMAND: &str = "gh";
+
+/// Age after which the repository cache is refreshed, in seconds.
+pub const REPO_CACHE_TTL_SECS: i64 = 6 * 60 * 60;
+
+const OWNED_REPOS_PATH: &str = "/user/repos?per_page=100&sort=pushed";
+const STARRED_REPOS_PATH: &str = "/user/starred?per_page=100";
+
+/// Pages fetched per list; 500 repositories are plenty for suggestions.
+const MAX_PAGES: usize = 5;
+
+/// Section words and the repository page they open. Issue and pull request
+/// lists also take search terms.
+const SECTIONS: &[(&[&str], &str)] = &[
+    (&["issues", "issue", "i"], "issues"),
+    (&["pulls", "pull", "prs", "pr", "p"], "pulls"),
+    (&["actions", "ci"], "actions"),
+    (&["releases", "release"], "releases"),
+    (&["commits"], "commits"),
+    (&["branches"], "branches"),
+    (&["tags"], "tags"),
+    (&["wiki"], "wiki"),
+    (&["discussions"], "discussions"),
+    (&["projects"], "projects"),
+    (&["security"], "security"),
+    (&["settings"], "settings"),
+];
+
+/// A repository from the user's lists, before it is cached.
+#[derive(Debug, Clone)]
+pub struct ListedRepo {
+    pub repo: GitHubRepo,
+    pub owned: bool,
+    pub starred: bool,
+}
+
+/// Trait defining `gh` command operations.
+pub trait GitHubQuickOpenTrait {
+    /// Page for the text after `gh` on the host at `web_url`, or `None` if
+    /// it is not a valid shorthand.
+    fn resolve(&self, web_url: &str, query: &str) -> Option<String>;
+    /// Cached repositories matching the first word of `query`, own ones first.
+    fn suggest(&self, web_url: &str, query: &str, limit: usize) -> Result<Vec<QuickOpenSuggestion>, GitHubError>;
+    /// Whether the cache is empty or older than `REPO_CACHE_TTL_SECS`.
+    fn cache_is_stale(&self, now: i64) -> bool;
+    /// Replaces the cache with `repos`.
+    fn store_repos(&mut self, repos: &[ListedRepo], now: i64) -> Result<(), GitHubError>;
+    fn clear_cache(&mut self) -> Result<(), GitHubError>;
+}
+
+/// `gh` command over the `github_repos` cache table.
+pub struct GitHubQuickOpen {
+    db: Arc<Database>,
+}
+
+impl GitHubQuickOpen {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// Fetches the user's own and starred repositories.
+    pub fn fetch_repos(client: &GitHubClient) -> Result<Vec<ListedRepo>, GitHubError> {
+        let mut listed: BTreeMap<String, ListedRepo> = BTreeMap::new();
+        for (path, starred) in [(OWNED_REPOS_PATH, false), (STARRED_REPOS_PATH, true)] {
+            for repo in fetch_pages(client, path)? {
+                let entry = listed.entry(repo.full_name.clone()).or_insert(ListedRepo {
+                    repo,
+                    owned: false,
+                    starred: false,
+                });
+                if starred {
+                    entry.starred = true;
+                } else {
+                    entry.owned = true;
+                }
+            }
+        }
+        Ok(listed.into_values().collect())
+    }
+
+    /// Full name of the cached repository called `name`, when exactly one
+    /// own repository (or else one starred repository) has that name.
+    fn find_by_name(&self, name: &str) -> Option<String> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(
+            "SELECT full_name, owned FROM github_repos WHERE full_name LIKE '%/' || ?1 ESCAPE '\\'",
+        ).ok()?;
+        let rows = stmt.query_map(params![escape_like(name)], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)? != 0)))
+            .ok()?;
+        let matches: Vec<(String, bool)> = rows.filter_map(Result::ok).collect();
+        let owned: Vec<&String> = matches.iter().filter(|(_, owned)| *owned).map(|(n, _)| n).collect();
+        match (owned.as_slice(), matches.as_slice()) {
+            ([only], _) => Some((*only).clone()),
+            ([], [(only, _)]) => Some(only.clone()),
+            _ => None,
+        }
+    }
+}
+
+fn fetch_pages(client: &GitHubClient, path: &str) -> Result<Vec<GitHubRepo>, GitHubError> {
+    let mut repos = Vec::new();
+    let mut next = Some(path.to_string());
+    for _ in 0..MAX_PAGES {
+        let Some(url) = next.take() else { break };
+        let response = client.get(&url)?;
+        match response.status {
+            200..=299 | 304 => {}
+            401 => return Err(GitHubError::AuthFailed(api_message(&response.body))),
+            status => {
+                return Err(GitHubError::ApiError(format!("HTTP {}: {}", status, api_message(&response.body))))
+            }
+        }
+        let page: Vec<GitHubRepo> = serde_json::from_value(response.body.clone())
+            .map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        repos.extend(page);
+        next = next_page_url(&response);
+    }
+    Ok(repos)
+}
+
+impl GitHubQuickOpenTrait for GitHubQuickOpen {
+    fn resolve(&self, web_url: &str, query: &str) -> Option<String> {
+        resolve_with(web_url, query, |name| self.find_by_name(name))
+    }
+
+    fn suggest(&self, web_url: &str, query: &str, limit: usize) -> Result<Vec<QuickOpenSuggestion>, GitHubError> {
+        let query = query.trim();
+        let (first, rest) = query.split_once(char::is_whitespace).unwrap_or((query, ""));
+        let first = first.split('#').next().unwrap_or_default();
+        if first.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Prefix matches on the full or the short name rank above substring matches
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(
+            "SELECT full_name, description, owned, starred FROM github_repos \
+             WHERE full_name LIKE '%' || ?1 || '%' ESCAPE '\\' \
+             ORDER BY (full_name LIKE ?1 || '%' ESCAPE '\\' \
+                       OR full_name LIKE '%/' || ?1 || '%' ESCAPE '\\') DESC, \
+                      owned DESC, stargazers_count DESC, full_name \
+             LIMIT ?2",
+        ).map_err(|e| GitHubError::ApiError(e.to_string()))?;
+        let rows = stmt.query_map(params![escape_like(first), limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i32>(2)? != 0,
+                row.get::<_, i32>(3)? != 0,
+            ))
+        }).map_err(|e| GitHubError::ApiError(e.to_string()))?;
+
+        let mut suggestions = Vec::new();
+        for row in rows {
+            let (full_name, description, owned, starred) = row.map_err(|e| GitHubError::ApiError(e.to_string()))?;
+            let url = resolve_with(web_url, &format!("{} {}", full_name, rest), |_| None)
+                .unwrap_or_else(|| format!("{}/{}", web_url, full_name));
+            suggestions.push(QuickOpenSuggestion { full_name, description, url, owned, starred });
+        }
+        Ok(suggestions)
+    }
+
+    fn cache_is_stale(&self, now: i64) -> bool {
+        let cached_at: Option<i64> = self.db.connection()
+            .query_row("SELECT MAX(cached_at) FROM github_repos", [], |row| row.get(0))
+            .unwrap_or(None);
+        !matches!(cached_at, Some(at) if now - at < REPO_CACHE_TTL_SECS)
+    }
+
+    fn store_repos(&mut self, repos: &[ListedRepo], now: i64) -> Result<(), GitHubError> {
+        let db_err = |e: rusqlite::Error| GitHubError::ApiError(e.to_string());
+        let conn = self.db.connection();
+        conn.execute("DELETE FROM github_repos", []).map_err(db_err)?;
+        for listed in repos {
+            let r = &listed.repo;
+            conn.execute(
+                "INSERT OR REPLACE INTO github_repos (full_name, description, html_url, stargazers_count, language, updated_at, owned, starred, cached_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![r.full_name, r.description, r.html_url, r.stargazers_count, r.language, r.updated_at,
+                        listed.owned as i32, listed.starred as i32, now],
+            ).map_err(db_err)?;
+        }
+        Ok(())
+    }
+
+    fn clear_cache(&mut self) -> Result<(), GitHubError> {
+        self.db.connection().execute("DELETE FROM github_repos", [])
+            .map(|_| ())
+            .map_err(|e| GitHubError::ApiError(e.to_string()))
+    }
+}
+
+/// The text after `gh` when `input` is a `gh` command (`gh` alone gives "").
+pub fn command_query(input: &str) -> Option<&str> {
+    let input = input.trim();
+    let (trigger, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    trigger.eq_ignore_ascii_case(GH_COMMAND).then(|| rest.trim())
+}
+
+/// `resolve` with the bare-name lookup injected.
+pub fn resolve_with(web_url: &str, query: &str, lookup: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let web_url = web_url.trim_end_matches('/');
+    let mut words = query.split_whitespace();
+    let Some(target) = words.next() else { return Some(web_url.to_string()) };
+    let rest: Vec<&str> = words.collect();
+
+    let (target, number) = match target.split_once('#') {
+        Some((repo, number)) => (repo, Some(number)),
+        None => (target, None),
+    };
+    let target = target.trim_end_matches('/');
+    let repo = match target.split_once('/') {
+        Some((owner, name)) => {
+            if !is_name(owner) || !is_name(name) {
+                return None;
+            }
+            target.to_string()
+        }
+        None if !is_name(target) => return None,
+        None => match lookup(target) {
+            Some(full_name) => full_name,
+            None if number.is_none() => return Some(user_url(web_url, target, &rest)),
+            None => return None,
+        },
+    };
+    let base = format!("{}/{}", web_url, repo);
+
+    if let Some(number) = number {
+        return number.parse::<u64>().ok().map(|n| format!("{}/issues/{}", base, n));
+    }
+    let Some((&first, terms)) = rest.split_first() else { return Some(base) };
+    if let Ok(n) = first.parse::<u64>() {
+        return Some(format!("{}/issues/{}", base, n));
+    }
+    let section = SECTIONS.iter()
+        .find(|(words, _)| words.iter().any(|w| w.eq_ignore_ascii_case(first)))
+        .map(|(_, section)| *section);
+    Some(match (section, terms.is_empty()) {
+        (Some(section), true) => format!("{}/{}", base, section),
+        (Some("issues"), false) => format!("{}/issues?q={}", base, encode_query(&format!("is:issue {}", terms.join(" ")))),
+        (Some("pulls"), false) => format!("{}/pulls?q={}", base, encode_query(&format!("is:pr {}", terms.join(" ")))),
+        (Some(section), false) => format!("{}/{}", base, section),
+        // Anything else searches the repository's code
+        (None, _) => format!("{}/search?q={}", base, encode_query(&rest.join(" "))),
+    })
+}
+
+/// Profile page of a user or organization; `repos` and `stars` open those tabs.
+fn user_url(web_url: &str, login: &str, rest: &[&str]) -> String {
+    match rest.first().map(|w| w.to_ascii_lowercase()).as_deref() {
+        Some("repos" | "repositories") => format!("{}/{}?tab=repositories", web_url, login),
+        Some("stars") => format!("{}/{}?tab=stars", web_url, login),
+        _ => format!("{}/{}", web_url, login),
+    }
+}
+
+/// Valid owner or repository name.
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
@@ -0,0 +1,116 @@
+//! Hang watchdog for the page renderer.
+//!
+//! The UI pings the page every `PING_INTERVAL` (a trivial `evaluate_script`
+//! whose callback answers with the ping's number). A ping unanswered for
+//! the timeout means the renderer hangs; that is reported once, and the
+//! user decides to wait (asked again after another timeout) or to kill the
+//! page. Times are passed in so the watchdog runs on any clock.
+
+use std::time::{Duration, Instant};
+
+/// How long a ping may go unanswered before the page counts as hung.
+pub const DEFAULT_HANG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Least time between two pings.
+pub const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What the watchdog noticed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchdogEvent {
+    /// The page has not answered for this long.
+    Hung { unresponsive_for: Duration },
+    /// A page reported hung answered again.
+    Recovered { after: Duration },
+}
+
+/// Ping bookkeeping for one webview.
+pub struct HangWatchdog {
+    timeout: Duration,
+    next_seq: u64,
+    /// Ping awaiting an answer: number and send time.
+    outstanding: Option<(u64, Instant)>,
+    last_ping_at: Option<Instant>,
+    /// When the outstanding ping counts as (still) hung.
+    deadline: Option<Instant>,
+    reported: bool,
+}
+
+impl HangWatchdog {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            next_seq: 0,
+            outstanding: None,
+            last_ping_at: None,
+            deadline: None,
+            reported: false,
+        }
+    }
+
+    /// Number of the ping to send now, if none is outstanding and
+    /// `PING_INTERVAL` has passed since the last one.
+    pub fn next_ping(&mut self, now: Instant) -> Option<u64> {
+        if self.outstanding.is_some() {
+            return None;
+        }
+        if self.last_ping_at.is_some_and(|at| now.duration_since(at) < PING_INTERVAL) {
+            return None;
+        }
+        self.next_seq += 1;
+        self.outstanding = Some((self.next_seq, now));
+        self.last_ping_at = Some(now);
+        self.deadline = Some(now + self.timeout);
+        Some(self.next_seq)
+    }
+
+    /// The page answered ping `seq`. Answers to pings already dropped by
+    /// `reset` are ignored.
+    pub fn pong(&mut self, seq: u64, now: Instant) -> Option<WatchdogEvent> {
+        let (pending, sent_at) = self.outstanding?;
+        if pending != seq {
+            return None;
+        }
+        self.outstanding = None;
+        self.deadline = None;
+        let was_reported = std::mem::take(&mut self.reported);
+        was_reported.then(|| WatchdogEvent::Recovered { after: now.duration_since(sent_at) })
+    }
+
+    /// Reports a hang once the outstanding ping passed its deadline; once
+    /// per hang until `wait` asks for another round.
+    pub fn check(&mut self, now: Instant) -> Option<WatchdogEvent> {
+        let (_, sent_at) = self.outstanding?;
+        if self.reported || self.deadline.is_some_and(|d| now < d) {
+            return None;
+        }
+        self.reported = true;
+        Some(WatchdogEvent::Hung { unresponsive_for: now.duration_since(sent_at) })
+    }
+
+    /// The user chose to keep waiting: report again if the page is still
+    /// hung after another timeout.
+    pub fn wait(&mut self, now: Instant) {
+        if self.outstanding.is_some() {
+            self.reported = false;
+            self.deadline = Some(now + self.timeout);
+        }
+    }
+
+    /// A new page replaced the one being watched; its ping will never be
+    /// answered.
+    pub fn reset(&mut self) {
+        self.outstanding = None;
+        self.deadline = None;
+        self.reported = false;
+    }
+
+    pub fn is_hung(&self) -> bool {
+        self.reported
+    }
+}
+
+impl Default for HangWatchdog {
+    fn default() -> Self {
+        Self::new(DEFAULT_HANG_TIMEOUT)
+    }
+}
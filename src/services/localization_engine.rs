@@ -1,36 +1,157 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde_json::Value;
 
+use crate::services::message_format::{cardinal_category, format_decimal, format_message};
 use crate::types::errors::LocaleError;
+use crate::types::locale::{LocaleStats, MissingKey, TextDirection};
 
-/// Supported locales.
-const SUPPORTED_LOCALES: &[&str] = &["en", "ru"];
-
-/// Default locale when system locale is not supported.
+/// Default locale and the last link of every fallback chain.
 const DEFAULT_LOCALE: &str = "en";
 
+/// Locales compiled into the binary so the UI is translated even when no
+/// `locales/` directory is present next to the executable.
+const BUNDLED_LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../../locales/en.json")),
+    ("ru", include_str!("../../locales/ru.json")),
+];
+
+/// Languages written right to left unless a script subtag says otherwise.
+const RTL_LANGUAGES: &[&str] = &["ar", "he", "fa", "ur", "ps", "sd", "yi", "dv", "ckb", "ug"];
+
+/// Script subtags written right to left (e.g. `az-Arab`).
+const RTL_SCRIPTS: &[&str] = &["Arab", "Hebr", "Thaa", "Syrc", "Nkoo", "Adlm", "Rohg"];
+
+/// Binary byte units and their translation keys, smallest first.
+const BYTE_UNITS: &[&str] = &["units.b", "units.kb", "units.mb", "units.gb", "units.tb"];
+
+/// Relative time units: (seconds per unit, key stem), smallest first. Each
+/// unit is used until the next one applies.
+const TIME_UNITS: &[(i64, &str)] = &[
+    (60, "minutes"),
+    (3_600, "hours"),
+    (86_400, "days"),
+    (604_800, "weeks"),
+    (2_592_000, "months"),
+    (31_536_000, "years"),
+];
+
+/// Suffixes marking plural forms of a key (`tabs_one`, `tabs_few`, ...).
+const PLURAL_SUFFIXES: &[&str] = &["_zero", "_one", "_two", "_few", "_many", "_other"];
+
 /// Trait defining the localization engine interface.
 pub trait LocalizationEngineTrait {
     fn initialize(&mut self) -> Result<(), LocaleError>;
+    /// Re-reads all locale files, keeping the current locale if it still exists.
+    fn reload(&mut self) -> Result<(), LocaleError>;
     fn set_locale(&mut self, lang: &str) -> Result<(), LocaleError>;
     fn get_locale(&self) -> &str;
     fn t(&self, key: &str, params: Option<&HashMap<String, String>>) -> String;
     fn plural(&self, key: &str, count: u64, params: Option<&HashMap<String, String>>) -> String;
     fn detect_system_locale(&self) -> String;
     fn get_available_locales(&self) -> Vec<String>;
+    fn fallback_chain(&self, locale: &str) -> Vec<String>;
+    /// Writing direction of the current locale.
+    fn get_direction(&self) -> TextDirection;
+    fn locale_stats(&self) -> Vec<LocaleStats>;
+    /// Enables or disables collection of missing keys hit at runtime.
+    fn set_debug_mode(&mut self, enabled: bool);
+    fn is_debug_mode(&self) -> bool;
+    /// Missing keys collected in debug mode, most frequent first.
+    fn missing_keys(&self) -> Vec<MissingKey>;
+    fn clear_missing_keys(&mut self);
+    /// Formats a number with the active locale's separators (`12,345.6`).
+    fn format_number(&self, value: f64, decimals: usize) -> String;
+    /// Formats a byte count with binary units (`1.5 MB`).
+    fn format_bytes(&self, bytes: u64) -> String;
+    /// Formats a Unix timestamp relative to `now` ("2 hours ago", "in 3 days").
+    fn format_relative_time(&self, timestamp: i64, now: i64) -> String;
+    /// Formats a USD amount; amounts below a dollar keep four decimals.
+    fn format_cost(&self, usd: f64) -> String;
 }
 
-/// Localization engine managing translations for Russian and English.
+/// Localization engine managing translations loaded from `locales/*.json`.
 pub struct LocalizationEngine {
-    /// Current active locale (e.g., "en" or "ru").
+    /// Current active locale (e.g., "en", "ru" or "pt-BR").
     current_locale: String,
     /// Loaded locale data: maps locale name to its parsed JSON value.
     locales: HashMap<String, Value>,
-    /// Path to the directory containing locale JSON files.
-    locales_dir: PathBuf,
+    /// Directory containing locale JSON files; `None` when the bundled
+    /// locales are used instead.
+    locales_dir: Option<PathBuf>,
+    /// Optional directory with community translations, merged key by key
+    /// over the locales loaded from `locales_dir` or the bundle.
+    override_dir: Option<PathBuf>,
+    /// Whether lookups that miss the active locale are recorded.
+    debug: bool,
+    /// Missing keys hit in debug mode, keyed by (locale, key). Lookups take
+    /// `&self`, hence the interior mutability.
+    missing: RefCell<BTreeMap<(String, String), MissingKey>>,
+}
+
+/// Normalizes a locale tag to BCP 47 casing: `pt_br` → `pt-BR`,
+/// `zh-hant-tw` → `zh-Hant-TW`. POSIX suffixes such as `.UTF-8` or
+/// `@euro` are dropped. Returns `None` for tags that are not locales
+/// (e.g. `C`, `POSIX` or an empty string).
+pub fn normalize_locale_tag(tag: &str) -> Option<String> {
+    let tag = tag.split(['.', '@']).next().unwrap_or("");
+    let mut parts = tag.split(['-', '_']);
+
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut normalized = language.to_ascii_lowercase();
+
+    for part in parts {
+        let subtag = match part.len() {
+            4 if part.chars().all(|c| c.is_ascii_alphabetic()) => {
+                let mut script = part.to_ascii_lowercase();
+                script[..1].make_ascii_uppercase();
+                script
+            }
+            2 if part.chars().all(|c| c.is_ascii_alphabetic()) => part.to_ascii_uppercase(),
+            3 if part.chars().all(|c| c.is_ascii_digit()) => part.to_string(),
+            5..=8 if part.chars().all(|c| c.is_ascii_alphanumeric()) => part.to_ascii_lowercase(),
+            _ => return None,
+        };
+        normalized.push('-');
+        normalized.push_str(&subtag);
+    }
+
+    Some(normalized)
+}
+
+/// Returns the writing direction of a locale tag. A script subtag decides
+/// when present (`pa-Arab` is RTL, `ku-Latn` is not); otherwise the language.
+pub fn text_direction(locale: &str) -> TextDirection {
+    let tag = normalize_locale_tag(locale).unwrap_or_default();
+    let mut subtags = tag.split('-');
+    let language = subtags.next().unwrap_or("");
+    let rtl = match subtags.find(|s| s.len() == 4) {
+        Some(script) => RTL_SCRIPTS.contains(&script),
+        None => RTL_LANGUAGES.contains(&language),
+    };
+    if rtl {
+        TextDirection::Rtl
+    } else {
+        TextDirection::Ltr
+    }
+}
+
+/// Returns the tag and its successively shorter prefixes:
+/// `zh-Hant-TW` → `[zh-Hant-TW, zh-Hant, zh]`.
+fn truncations(tag: &str) -> Vec<String> {
+    let mut result = vec![tag.to_string()];
+    let mut current = tag;
+    while let Some(idx) = current.rfind('-') {
+        current = &current[..idx];
+        result.push(current.to_string());
+    }
+    result
 }
 
 impl LocalizationEngine {
@@ -39,10 +160,34 @@ impl LocalizationEngine {
         Self {
             current_locale: DEFAULT_LOCALE.to_string(),
             locales: HashMap::new(),
-            locales_dir: locales_dir.into(),
+            locales_dir: Some(locales_dir.into()),
+            override_dir: None,
+            debug: false,
+            missing: RefCell::new(BTreeMap::new()),
         }
     }
 
+    /// Creates a LocalizationEngine backed by the locales embedded in the
+    /// binary. Initialization never depends on the working directory.
+    pub fn bundled() -> Self {
+        Self {
+            current_locale: DEFAULT_LOCALE.to_string(),
+            locales: HashMap::new(),
+            locales_dir: None,
+            override_dir: None,
+            debug: false,
+            missing: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Sets a directory of user-supplied `<locale>.json` files. They may add
+    /// new locales or override individual keys of loaded ones; a missing
+    /// directory or malformed file is skipped.
+    pub fn with_override_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.override_dir = Some(dir.into());
+        self
+    }
+
     /// Creates a new LocalizationEngine using the default `locales/` directory.
     pub fn with_default_path() -> Self {
         Self::new("locales")
@@ -62,107 +207,237 @@ impl LocalizationEngine {
         Some(current)
     }
 
-    /// Replaces `{param_name}` placeholders in a string with values from the params map.
-    fn interpolate(template: &str, params: &HashMap<String, String>) -> String {
-        let mut result = template.to_string();
-        for (key, value) in params {
-            let placeholder = format!("{{{}}}", key);
-            result = result.replace(&placeholder, value);
+    /// Lists the `<locale>.json` files of a directory as (normalized tag, path),
+    /// sorted by path. Files whose name is not a locale tag are ignored.
+    fn locale_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, LocaleError> {
+        let entries = fs::read_dir(dir).map_err(|e| {
+            LocaleError::FileNotFound(format!("{}: {}", dir.to_string_lossy(), e))
+        })?;
+
+        let mut files: Vec<(String, PathBuf)> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("json"))
+            .filter_map(|path| {
+                let locale = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(normalize_locale_tag)?;
+                Some((locale, path))
+            })
+            .collect();
+        files.sort_by(|a, b| a.1.cmp(&b.1));
+        Ok(files)
+    }
+
+    /// Reads and parses a single locale file.
+    fn read_locale_file(file_path: &Path) -> Result<Value, LocaleError> {
+        let content = fs::read_to_string(file_path).map_err(|e| {
+            LocaleError::FileNotFound(format!(
+                "{}: {}",
+                file_path.to_string_lossy(),
+                e
+            ))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            LocaleError::FileNotFound(format!(
+                "Failed to parse {}: {}",
+                file_path.to_string_lossy(),
+                e
+            ))
+        })
+    }
+
+    /// Merges locale data into `locales`: nested objects are merged key by
+    /// key, any other value replaces the existing one.
+    fn merge_locale(locales: &mut HashMap<String, Value>, locale: String, data: Value) {
+        fn merge(base: &mut Value, overlay: Value) {
+            match (base, overlay) {
+                (Value::Object(base), Value::Object(overlay)) => {
+                    for (k, v) in overlay {
+                        match base.get_mut(&k) {
+                            Some(existing) => merge(existing, v),
+                            None => {
+                                base.insert(k, v);
+                            }
+                        }
+                    }
+                }
+                (base, overlay) => *base = overlay,
+            }
+        }
+
+        match locales.get_mut(&locale) {
+            Some(existing) => merge(existing, data),
+            None => {
+                locales.insert(locale, data);
+            }
+        }
+    }
+
+    /// Records a lookup of `key` that the active locale could not satisfy.
+    /// The first hit of each key is logged so it shows up during development.
+    fn record_missing(&self, key: &str, resolved_from: Option<&str>) {
+        if !self.debug {
+            return;
         }
-        result
+        let mut missing = self.missing.borrow_mut();
+        let entry = missing
+            .entry((self.current_locale.clone(), key.to_string()))
+            .or_insert_with(|| {
+                eprintln!(
+                    "[I18N] missing key '{}' in {} ({})",
+                    key,
+                    self.current_locale,
+                    resolved_from.map_or("untranslated".to_string(), |l| format!("fell back to {}", l))
+                );
+                MissingKey {
+                    key: key.to_string(),
+                    locale: self.current_locale.clone(),
+                    hits: 0,
+                    resolved_from: resolved_from.map(str::to_string),
+                }
+            });
+        entry.hits += 1;
+    }
+
+    /// Looks up a string translation in a single loaded locale.
+    fn lookup_in(&self, locale: &str, key: &str) -> Option<&str> {
+        self.locales
+            .get(locale)
+            .and_then(|data| Self::lookup_key(data, key))
+            .and_then(|v| v.as_str())
+    }
+
+    /// Formats a MessageFormat pattern with the plural rules of `locale`.
+    /// Patterns that fail to parse are returned unchanged so a broken
+    /// translation still shows up instead of disappearing.
+    fn format(locale: &str, template: &str, params: &HashMap<String, String>) -> String {
+        format_message(template, Self::language(locale), params)
+            .unwrap_or_else(|_| template.to_string())
+    }
+
+    /// Returns the language subtag of a locale (`pt-BR` → `pt`).
+    fn language(locale: &str) -> &str {
+        locale.split('-').next().unwrap_or(locale)
     }
 
     /// Determines the Russian plural form for a given count.
     /// Returns one of: "one", "few", "many", "other".
+    #[cfg(test)]
     fn russian_plural_form(count: u64) -> &'static str {
-        let mod10 = count % 10;
-        let mod100 = count % 100;
-
-        if mod10 == 1 && mod100 != 11 {
-            "one"
-        } else if (2..=4).contains(&mod10) && !(12..=14).contains(&mod100) {
-            "few"
-        } else if mod10 == 0 || (5..=9).contains(&mod10) || (11..=14).contains(&mod100) {
-            "many"
-        } else {
-            "other"
-        }
+        cardinal_category("ru", count)
     }
 
     /// Determines the English plural form for a given count.
     /// Returns one of: "one", "other".
+    #[cfg(test)]
     fn english_plural_form(count: u64) -> &'static str {
-        if count == 1 {
-            "one"
-        } else {
-            "other"
-        }
+        cardinal_category("en", count)
+    }
+
+    /// Returns the plural form suffix for a locale, chosen by its language subtag.
+    fn plural_form_for(locale: &str, count: u64) -> &'static str {
+        cardinal_category(Self::language(locale), count)
     }
 
-    /// Returns the plural form suffix for the current locale.
-    fn get_plural_form(&self, count: u64) -> &'static str {
-        match self.current_locale.as_str() {
-            "ru" => Self::russian_plural_form(count),
-            _ => Self::english_plural_form(count),
+    /// Collects the dot-notation keys of all string leaves. Plural forms are
+    /// collapsed to their base key so locales with different plural
+    /// categories compare equal.
+    fn collect_keys(value: &Value, prefix: &str, keys: &mut BTreeSet<String>) {
+        let Value::Object(map) = value else { return };
+        for (k, v) in map {
+            let full_key = if prefix.is_empty() {
+                k.clone()
+            } else {
+                format!("{}.{}", prefix, k)
+            };
+            match v {
+                Value::Object(_) => Self::collect_keys(v, &full_key, keys),
+                Value::String(_) => {
+                    let base = PLURAL_SUFFIXES
+                        .iter()
+                        .find_map(|suffix| full_key.strip_suffix(suffix))
+                        .map(str::to_string)
+                        .unwrap_or(full_key);
+                    keys.insert(base);
+                }
+                _ => {}
+            }
         }
     }
 }
 
 impl LocalizationEngineTrait for LocalizationEngine {
-    /// Loads all locale JSON files from the locales directory.
+    /// Loads every `<locale>.json` file from the locales directory (or the
+    /// bundled locales), then merges the override directory on top. The
+    /// loaded locales are only replaced once everything parsed.
     fn initialize(&mut self) -> Result<(), LocaleError> {
-        let dir = &self.locales_dir;
-
-        if !dir.exists() {
-            return Err(LocaleError::FileNotFound(
-                dir.to_string_lossy().to_string(),
-            ));
+        let mut locales = HashMap::new();
+
+        match self.locales_dir.clone() {
+            Some(dir) => {
+                if !dir.exists() {
+                    return Err(LocaleError::FileNotFound(
+                        dir.to_string_lossy().to_string(),
+                    ));
+                }
+                for (locale, file_path) in Self::locale_files(&dir)? {
+                    let data = Self::read_locale_file(&file_path)?;
+                    Self::merge_locale(&mut locales, locale, data);
+                }
+            }
+            None => {
+                for (locale, content) in BUNDLED_LOCALES {
+                    let data: Value = serde_json::from_str(content).map_err(|e| {
+                        LocaleError::FileNotFound(format!(
+                            "Failed to parse bundled {}: {}",
+                            locale, e
+                        ))
+                    })?;
+                    Self::merge_locale(&mut locales, locale.to_string(), data);
+                }
+            }
         }
 
-        for locale in SUPPORTED_LOCALES {
-            let file_path = dir.join(format!("{}.json", locale));
-            if file_path.exists() {
-                let content = fs::read_to_string(&file_path).map_err(|e| {
-                    LocaleError::FileNotFound(format!(
-                        "{}: {}",
-                        file_path.to_string_lossy(),
-                        e
-                    ))
-                })?;
-                let data: Value = serde_json::from_str(&content).map_err(|e| {
-                    LocaleError::FileNotFound(format!(
-                        "Failed to parse {}: {}",
-                        file_path.to_string_lossy(),
-                        e
-                    ))
-                })?;
-                self.locales.insert(locale.to_string(), data);
+        // Community translations are optional: unreadable files are skipped
+        if let Some(dir) = self.override_dir.clone() {
+            for (locale, file_path) in Self::locale_files(&dir).unwrap_or_default() {
+                if let Ok(data) = Self::read_locale_file(&file_path) {
+                    Self::merge_locale(&mut locales, locale, data);
+                }
             }
         }
 
         // At least one locale must be loaded
-        if self.locales.is_empty() {
+        if locales.is_empty() {
             return Err(LocaleError::FileNotFound(
                 "No locale files found".to_string(),
             ));
         }
 
+        self.locales = locales;
         Ok(())
     }
 
-    /// Switches the active locale. Returns an error if the locale is not supported
-    /// or not loaded.
-    fn set_locale(&mut self, lang: &str) -> Result<(), LocaleError> {
-        if !SUPPORTED_LOCALES.contains(&lang) {
-            return Err(LocaleError::UnsupportedLocale(lang.to_string()));
+    fn reload(&mut self) -> Result<(), LocaleError> {
+        self.initialize()?;
+        if !self.locales.contains_key(&self.current_locale) {
+            self.current_locale = DEFAULT_LOCALE.to_string();
         }
-        if !self.locales.contains_key(lang) {
-            return Err(LocaleError::FileNotFound(format!(
-                "Locale '{}' not loaded",
-                lang
-            )));
+        Ok(())
+    }
+
+    /// Switches the active locale. Accepts any loaded locale in any casing
+    /// (`pt_br` selects `pt-BR`). Returns an error if the tag is malformed
+    /// or no file for it was loaded.
+    fn set_locale(&mut self, lang: &str) -> Result<(), LocaleError> {
+        let locale = normalize_locale_tag(lang)
+            .ok_or_else(|| LocaleError::UnsupportedLocale(lang.to_string()))?;
+        if !self.locales.contains_key(&locale) {
+            return Err(LocaleError::UnsupportedLocale(locale));
         }
-        self.current_locale = lang.to_string();
+        self.current_locale = locale;
         Ok(())
     }
 
@@ -171,38 +446,37 @@ impl LocalizationEngineTrait for LocalizationEngine {
         &self.current_locale
     }
 
-    /// Looks up a translation key using dot notation and optionally interpolates parameters.
-    /// Returns the key itself if the translation is not found.
+    /// Looks up a translation key using dot notation and formats it as an ICU
+    /// MessageFormat pattern (`{name}`, `{n, plural, ...}`, `{g, select, ...}`).
+    /// Missing keys are resolved along the fallback chain of the current locale;
+    /// the key itself is returned if no locale in the chain translates it.
     fn t(&self, key: &str, params: Option<&HashMap<String, String>>) -> String {
-        let data = match self.locales.get(&self.current_locale) {
-            Some(d) => d,
-            None => return key.to_string(),
-        };
-
-        let value = match Self::lookup_key(data, key) {
-            Some(v) => v,
-            None => return key.to_string(),
-        };
-
-        let text = match value.as_str() {
-            Some(s) => s.to_string(),
-            None => return key.to_string(),
+        let chain = self.fallback_chain(&self.current_locale);
+        let (locale, text) = match chain
+            .iter()
+            .find_map(|locale| self.lookup_in(locale, key).map(|text| (locale, text)))
+        {
+            Some(found) => found,
+            None => {
+                self.record_missing(key, None);
+                return key.to_string();
+            }
         };
-
-        match params {
-            Some(p) => Self::interpolate(&text, p),
-            None => text,
+        if *locale != self.current_locale {
+            self.record_missing(key, Some(locale.as_str()));
         }
+
+        let empty = HashMap::new();
+        Self::format(locale, text, params.unwrap_or(&empty))
     }
 
-    /// Looks up a pluralized translation key. The base key is appended with the
-    /// appropriate plural suffix (e.g., "_one", "_few", "_many", "_other") based
-    /// on the count and current locale's plural rules.
+    /// Looks up a pluralized translation. A base key holding a single
+    /// MessageFormat pattern (`{count, plural, one {# tab} other {# tabs}}`)
+    /// is preferred; otherwise the key is appended with the plural suffix
+    /// (e.g., "_one", "_few", "_many", "_other") chosen by the plural rules of
+    /// each locale in the fallback chain.
     /// A `{count}` parameter is automatically added to the params.
     fn plural(&self, key: &str, count: u64, params: Option<&HashMap<String, String>>) -> String {
-        let form = self.get_plural_form(count);
-        let plural_key = format!("{}_{}", key, form);
-
         // Build params with count included
         let mut merged_params = match params {
             Some(p) => p.clone(),
@@ -212,43 +486,47 @@ impl LocalizationEngineTrait for LocalizationEngine {
             .entry("count".to_string())
             .or_insert_with(|| count.to_string());
 
-        // Try the specific plural form first
-        let result = self.t(&plural_key, Some(&merged_params));
-
-        // If the specific form wasn't found, try "_other" as fallback
-        if result == plural_key {
-            let other_key = format!("{}_other", key);
-            let other_result = self.t(&other_key, Some(&merged_params));
-            if other_result == other_key {
-                // If even "_other" is not found, return the base key
-                return key.to_string();
+        // Try the message itself, then the specific plural form, then "_other", per locale
+        for locale in self.fallback_chain(&self.current_locale) {
+            let form = Self::plural_form_for(&locale, count);
+            let text = self
+                .lookup_in(&locale, key)
+                .or_else(|| self.lookup_in(&locale, &format!("{}_{}", key, form)))
+                .or_else(|| self.lookup_in(&locale, &format!("{}_other", key)));
+            if let Some(text) = text {
+                if locale != self.current_locale {
+                    self.record_missing(key, Some(locale.as_str()));
+                }
+                return Self::format(&locale, text, &merged_params);
             }
-            return other_result;
         }
 
-        result
+        // If even "_other" is not found, return the base key
+        self.record_missing(key, None);
+        key.to_string()
     }
 
     /// Detects the system locale by reading the `LANG` environment variable.
-    /// Returns the language code (e.g., "ru" or "en"). Falls back to "en"
-    /// if the system locale is not supported.
+    /// Returns the most specific locale that has a translation file
+    /// (`pt_BR.UTF-8` → `pt-BR`, else `pt`). Falls back to "en" if none does.
     fn detect_system_locale(&self) -> String {
         let lang = std::env::var("LANG").unwrap_or_default();
 
-        // LANG is typically like "ru_RU.UTF-8" or "en_US.UTF-8"
-        let lang_code = lang
-            .split('_')
-            .next()
-            .unwrap_or("")
-            .split('.')
-            .next()
-            .unwrap_or("");
-
-        if SUPPORTED_LOCALES.contains(&lang_code) {
-            lang_code.to_string()
-        } else {
-            DEFAULT_LOCALE.to_string()
-        }
+        let tag = match normalize_locale_tag(&lang) {
+            Some(t) => t,
+            None => return DEFAULT_LOCALE.to_string(),
+        };
+
+        truncations(&tag)
+            .into_iter()
+            .find(|candidate| {
+                self.locales.contains_key(candidate)
+                    || self
+                        .locales_dir
+                        .as_ref()
+                        .is_some_and(|dir| dir.join(format!("{}.json", candidate)).exists())
+            })
+            .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
     }
 
     /// Returns a list of all available (loaded) locales.
@@ -257,6 +535,110 @@ impl LocalizationEngineTrait for LocalizationEngine {
         locales.sort();
         locales
     }
+
+    /// Returns the loaded locales consulted for a lookup in `locale`, most
+    /// specific first: `pt-BR` → `[pt-BR, pt, en]`.
+    fn fallback_chain(&self, locale: &str) -> Vec<String> {
+        let mut chain: Vec<String> = normalize_locale_tag(locale)
+            .map(|tag| truncations(&tag))
+            .unwrap_or_default();
+        chain.push(DEFAULT_LOCALE.to_string());
+
+        let mut seen = BTreeSet::new();
+        chain
+            .into_iter()
+            .filter(|l| self.locales.contains_key(l) && seen.insert(l.clone()))
+            .collect()
+    }
+
+    fn get_direction(&self) -> TextDirection {
+        text_direction(&self.current_locale)
+    }
+
+    /// Reports, for every loaded locale, which keys of the default locale it
+    /// does not translate itself. Sorted by locale.
+    fn locale_stats(&self) -> Vec<LocaleStats> {
+        let mut reference = BTreeSet::new();
+        if let Some(data) = self.locales.get(DEFAULT_LOCALE) {
+            Self::collect_keys(data, "", &mut reference);
+        }
+
+        self.get_available_locales()
+            .into_iter()
+            .map(|locale| {
+                let mut keys = BTreeSet::new();
+                Self::collect_keys(&self.locales[&locale], "", &mut keys);
+                let missing: Vec<String> = reference.difference(&keys).cloned().collect();
+                LocaleStats {
+                    translated: reference.len() - missing.len(),
+                    total_keys: reference.len(),
+                    missing,
+                    locale,
+                }
+            })
+            .collect()
+    }
+
+    fn set_debug_mode(&mut self, enabled: bool) {
+        self.debug = enabled;
+    }
+
+    fn is_debug_mode(&self) -> bool {
+        self.debug
+    }
+
+    fn missing_keys(&self) -> Vec<MissingKey> {
+        let mut keys: Vec<MissingKey> = self.missing.borrow().values().cloned().collect();
+        keys.sort_by(|a, b| b.hits.cmp(&a.hits).then_with(|| a.key.cmp(&b.key)));
+        keys
+    }
+
+    fn clear_missing_keys(&mut self) {
+        self.missing.get_mut().clear();
+    }
+
+    fn format_number(&self, value: f64, decimals: usize) -> String {
+        format_decimal(value, decimals, Self::language(&self.current_locale))
+    }
+
+    fn format_bytes(&self, bytes: u64) -> String {
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        let decimals = if unit > 0 && value < 10.0 { 1 } else { 0 };
+        format!("{} {}", self.format_number(value, decimals), self.t(BYTE_UNITS[unit], None))
+    }
+
+    fn format_relative_time(&self, timestamp: i64, now: i64) -> String {
+        let delta = now - timestamp;
+        let elapsed = delta.abs();
+        if elapsed < TIME_UNITS[0].0 {
+            return self.t("time.just_now", None);
+        }
+
+        let (seconds, stem) = TIME_UNITS
+            .iter()
+            .rev()
+            .find(|(seconds, _)| elapsed >= *seconds)
+            .copied()
+            .unwrap_or(TIME_UNITS[0]);
+        let key = if delta >= 0 {
+            format!("time.{}_ago", stem)
+        } else {
+            format!("time.in_{}", stem)
+        };
+        self.plural(&key, (elapsed / seconds) as u64, None)
+    }
+
+    fn format_cost(&self, usd: f64) -> String {
+        let decimals = if usd.abs() >= 1.0 { 2 } else { 4 };
+        let mut params = HashMap::new();
+        params.insert("amount".to_string(), self.format_number(usd, decimals));
+        self.t("units.usd", Some(&params))
+    }
 }
 
 #[cfg(test)]
@@ -473,6 +855,262 @@ mod tests {
         unsafe { std::env::set_var("LANG", "en_US.UTF-8") };
     }
 
+    fn create_regional_locales(dir: &std::path::Path) {
+        create_test_locales(dir);
+        let pt = serde_json::json!({
+            "tabs": { "new_tab": "Nova aba" },
+            "common": {
+                "tabs_one": "{count} aba",
+                "tabs_other": "{count} abas"
+            }
+        });
+        let pt_br = serde_json::json!({
+            "tabs": { "close_tab": "Fechar aba" }
+        });
+        fs::write(dir.join("pt.json"), pt.to_string()).unwrap();
+        fs::write(dir.join("pt_BR.json"), pt_br.to_string()).unwrap();
+        fs::write(dir.join("manifest.json"), "{}").unwrap();
+    }
+
+    #[test]
+    fn test_initialize_loads_any_locale_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        create_regional_locales(tmp.path());
+
+        let mut engine = LocalizationEngine::new(tmp.path());
+        engine.initialize().unwrap();
+
+        assert_eq!(engine.get_available_locales(), vec!["en", "pt", "pt-BR", "ru"]);
+        engine.set_locale("pt_br").unwrap();
+        assert_eq!(engine.get_locale(), "pt-BR");
+    }
+
+    #[test]
+    fn test_bundled_locales_need_no_directory() {
+        let mut engine = LocalizationEngine::bundled();
+        engine.initialize().unwrap();
+
+        assert_eq!(engine.get_available_locales(), vec!["en", "ru"]);
+        assert_ne!(engine.t("tabs.new_tab", None), "tabs.new_tab");
+        engine.set_locale("ru").unwrap();
+        assert_ne!(engine.t("tabs.new_tab", None), "tabs.new_tab");
+    }
+
+    #[test]
+    fn test_override_dir_merges_over_bundled() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ru = serde_json::json!({ "tabs": { "new_tab": "Новая вкладочка" } });
+        let de = serde_json::json!({ "tabs": { "new_tab": "Neuer Tab" } });
+        fs::write(tmp.path().join("ru.json"), ru.to_string()).unwrap();
+        fs::write(tmp.path().join("de.json"), de.to_string()).unwrap();
+        fs::write(tmp.path().join("fr.json"), "{ not json").unwrap();
+
+        let mut engine = LocalizationEngine::bundled().with_override_dir(tmp.path());
+        engine.initialize().unwrap();
+        assert_eq!(engine.get_available_locales(), vec!["de", "en", "ru"]);
+
+        engine.set_locale("ru").unwrap();
+        assert_eq!(engine.t("tabs.new_tab", None), "Новая вкладочка");
+        // Keys absent from the override keep their bundled translation
+        assert_ne!(engine.t("tabs.close_tab", None), "tabs.close_tab");
+
+        engine.set_locale("de").unwrap();
+        assert_eq!(engine.t("tabs.new_tab", None), "Neuer Tab");
+    }
+
+    #[test]
+    fn test_missing_override_dir_is_ignored() {
+        let mut engine = LocalizationEngine::bundled().with_override_dir("/nonexistent/path");
+        assert!(engine.initialize().is_ok());
+    }
+
+    #[test]
+    fn test_fallback_chain() {
+        let tmp = tempfile::tempdir().unwrap();
+        create_regional_locales(tmp.path());
+
+        let mut engine = LocalizationEngine::new(tmp.path());
+        engine.initialize().unwrap();
+
+        assert_eq!(engine.fallback_chain("pt-BR"), vec!["pt-BR", "pt", "en"]);
+        assert_eq!(engine.fallback_chain("ru"), vec!["ru", "en"]);
+        assert_eq!(engine.fallback_chain("de-AT"), vec!["en"]);
+
+        engine.set_locale("pt-BR").unwrap();
+        assert_eq!(engine.t("tabs.close_tab", None), "Fechar aba");
+        assert_eq!(engine.t("tabs.new_tab", None), "Nova aba");
+        assert_eq!(engine.t("greeting", None), "Hello, {name}!");
+        assert_eq!(engine.plural("common.tabs", 3, None), "3 abas");
+    }
+
+    #[test]
+    fn test_locale_stats_reports_missing_keys() {
+        let tmp = tempfile::tempdir().unwrap();
+        create_regional_locales(tmp.path());
+
+        let mut engine = LocalizationEngine::new(tmp.path());
+        engine.initialize().unwrap();
+
+        let stats = engine.locale_stats();
+        let by_locale = |l: &str| stats.iter().find(|s| s.locale == l).unwrap();
+
+        // en: tabs.new_tab, tabs.close_tab, common.tabs, greeting
+        assert_eq!(by_locale("en").total_keys, 4);
+        assert!(by_locale("en").missing.is_empty());
+        // ru uses more plural forms but still covers every key
+        assert_eq!(by_locale("ru").translated, 4);
+        assert_eq!(by_locale("pt").missing, vec!["greeting", "tabs.close_tab"]);
+        assert_eq!(by_locale("pt-BR").translated, 1);
+        assert_eq!(by_locale("pt-BR").completion(), 25.0);
+    }
+
+    #[test]
+    fn test_message_format_translations() {
+        let tmp = tempfile::tempdir().unwrap();
+        let en = serde_json::json!({
+            "groups": {
+                "tabs": "{count, plural, one {# tab} other {# tabs}} in {group}",
+                "broken": "{count, plural, one {# tab}}"
+            }
+        });
+        let ru = serde_json::json!({
+            "groups": {
+                "tabs": "{count, plural, one {# вкладка} few {# вкладки} other {# вкладок}} в {group}"
+            }
+        });
+        fs::write(tmp.path().join("en.json"), en.to_string()).unwrap();
+        fs::write(tmp.path().join("ru.json"), ru.to_string()).unwrap();
+
+        let mut engine = LocalizationEngine::new(tmp.path());
+        engine.initialize().unwrap();
+
+        let mut params = HashMap::new();
+        params.insert("group".to_string(), "Work".to_string());
+        assert_eq!(engine.plural("groups.tabs", 1, Some(&params)), "1 tab in Work");
+        assert_eq!(engine.plural("groups.tabs", 3, Some(&params)), "3 tabs in Work");
+
+        engine.set_locale("ru").unwrap();
+        assert_eq!(engine.plural("groups.tabs", 3, Some(&params)), "3 вкладки в Work");
+        assert_eq!(engine.plural("groups.tabs", 25, Some(&params)), "25 вкладок в Work");
+
+        // Invalid patterns are shown verbatim rather than dropped
+        assert_eq!(engine.t("groups.broken", None), "{count, plural, one {# tab}}");
+    }
+
+    #[test]
+    fn test_reload_picks_up_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        create_test_locales(tmp.path());
+
+        let mut engine = LocalizationEngine::new(tmp.path());
+        engine.initialize().unwrap();
+        engine.set_locale("ru").unwrap();
+
+        fs::write(tmp.path().join("ru.json"), r#"{"tabs":{"new_tab":"Вкладка"}}"#).unwrap();
+        engine.reload().unwrap();
+        assert_eq!(engine.get_locale(), "ru");
+        assert_eq!(engine.t("tabs.new_tab", None), "Вкладка");
+
+        fs::remove_file(tmp.path().join("ru.json")).unwrap();
+        engine.reload().unwrap();
+        assert_eq!(engine.get_locale(), "en");
+    }
+
+    #[test]
+    fn test_debug_mode_collects_missing_keys() {
+        let tmp = tempfile::tempdir().unwrap();
+        create_test_locales(tmp.path());
+        fs::write(tmp.path().join("de.json"), r#"{"tabs":{"new_tab":"Neuer Tab"}}"#).unwrap();
+
+        let mut engine = LocalizationEngine::new(tmp.path());
+        engine.initialize().unwrap();
+        engine.set_locale("de").unwrap();
+
+        // Nothing is recorded outside debug mode
+        engine.t("tabs.close_tab", None);
+        assert!(engine.missing_keys().is_empty());
+
+        engine.set_debug_mode(true);
+        engine.t("tabs.new_tab", None);
+        engine.t("tabs.close_tab", None);
+        engine.t("tabs.close_tab", None);
+        engine.t("nonexistent.key", None);
+        engine.plural("common.tabs", 2, None);
+
+        let missing = engine.missing_keys();
+        assert_eq!(missing.len(), 3);
+        assert_eq!(missing[0].key, "tabs.close_tab");
+        assert_eq!(missing[0].hits, 2);
+        assert_eq!(missing[0].resolved_from.as_deref(), Some("en"));
+        let untranslated = missing.iter().find(|m| m.key == "nonexistent.key").unwrap();
+        assert_eq!(untranslated.resolved_from, None);
+        assert_eq!(untranslated.locale, "de");
+
+        engine.clear_missing_keys();
+        assert!(engine.missing_keys().is_empty());
+    }
+
+    #[test]
+    fn test_locale_aware_formatting() {
+        let mut engine = LocalizationEngine::bundled();
+        engine.initialize().unwrap();
+        let now = 1_700_000_000;
+
+        assert_eq!(engine.format_number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(engine.format_bytes(512), "512 B");
+        assert_eq!(engine.format_bytes(1536), "1.5 KB");
+        assert_eq!(engine.format_bytes(50 * 1024 * 1024), "50 MB");
+        assert_eq!(engine.format_relative_time(now - 10, now), "just now");
+        assert_eq!(engine.format_relative_time(now - 60, now), "1 minute ago");
+        assert_eq!(engine.format_relative_time(now - 2 * 3600 - 5, now), "2 hours ago");
+        assert_eq!(engine.format_relative_time(now + 3 * 86_400, now), "in 3 days");
+        assert_eq!(engine.format_relative_time(now - 400 * 86_400, now), "1 year ago");
+        assert_eq!(engine.format_cost(0.01234), "$0.0123");
+        assert_eq!(engine.format_cost(1234.5), "$1,234.50");
+
+        engine.set_locale("ru").unwrap();
+        assert_eq!(engine.format_number(1234567.891, 2), "1\u{a0}234\u{a0}567,89");
+        assert_eq!(engine.format_bytes(1536), "1,5 КБ");
+        assert_eq!(engine.format_relative_time(now - 5 * 3600, now), "5 часов назад");
+        assert_eq!(engine.format_relative_time(now - 22 * 60, now), "22 минуты назад");
+        assert_eq!(engine.format_cost(0.5), "0,5000 $");
+    }
+
+    #[test]
+    fn test_text_direction() {
+        assert_eq!(text_direction("en"), TextDirection::Ltr);
+        assert_eq!(text_direction("ar"), TextDirection::Rtl);
+        assert_eq!(text_direction("he_IL"), TextDirection::Rtl);
+        assert_eq!(text_direction("fa-IR"), TextDirection::Rtl);
+        assert_eq!(text_direction("pa-Arab"), TextDirection::Rtl);
+        assert_eq!(text_direction("ku-Latn"), TextDirection::Ltr);
+        assert_eq!(text_direction("ru"), TextDirection::Ltr);
+    }
+
+    #[test]
+    fn test_get_direction_follows_locale() {
+        let tmp = tempfile::tempdir().unwrap();
+        create_test_locales(tmp.path());
+        fs::write(tmp.path().join("ar.json"), r#"{"tabs":{"new_tab":"علامة تبويب جديدة"}}"#).unwrap();
+
+        let mut engine = LocalizationEngine::new(tmp.path());
+        engine.initialize().unwrap();
+        assert_eq!(engine.get_direction(), TextDirection::Ltr);
+
+        engine.set_locale("ar").unwrap();
+        assert_eq!(engine.get_direction(), TextDirection::Rtl);
+    }
+
+    #[test]
+    fn test_normalize_locale_tag() {
+        assert_eq!(normalize_locale_tag("pt_br").as_deref(), Some("pt-BR"));
+        assert_eq!(normalize_locale_tag("ru_RU.UTF-8").as_deref(), Some("ru-RU"));
+        assert_eq!(normalize_locale_tag("zh-hant-tw").as_deref(), Some("zh-Hant-TW"));
+        assert_eq!(normalize_locale_tag("es-419").as_deref(), Some("es-419"));
+        assert_eq!(normalize_locale_tag("C"), None);
+        assert_eq!(normalize_locale_tag("manifest"), None);
+    }
+
     #[test]
     fn test_russian_plural_rules_comprehensive() {
         // one: n % 10 == 1 && n % 100 != 11
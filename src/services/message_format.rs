@@ -0,0 +1,556 @@
+//! A subset of ICU MessageFormat used by the localization engine.
+//!
+//! Supported syntax:
+//! - simple arguments: `{name}` (also `{name, number}` and other typed
+//!   arguments, which are rendered as-is)
+//! - `{count, plural, offset:1 =0 {none} one {# tab} other {# tabs}}`
+//! - `{place, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}`
+//! - `{gender, select, female {her} male {his} other {their}}`
+//! - nesting of all of the above, `#` inside plural cases
+//! - apostrophe quoting: `'{'` is a literal brace, `''` a literal apostrophe
+//!
+//! Arguments without a value are left in the output as `{name}`, matching the
+//! behavior of the previous `{param}` interpolation. Numbers substituted for
+//! `#` use the digit grouping and decimal mark of the language.
+
+use std::collections::HashMap;
+
+use crate::types::errors::LocaleError;
+
+/// Languages that use the East Slavic one/few/many plural rules.
+const SLAVIC_PLURAL_LANGUAGES: &[&str] = &["ru", "uk", "be"];
+
+/// Languages without grammatical plural: every count is "other".
+const INVARIANT_PLURAL_LANGUAGES: &[&str] = &["ja", "zh", "ko", "vi", "th", "id", "ms"];
+
+/// Languages where both 0 and 1 take the "one" form.
+const ZERO_ONE_PLURAL_LANGUAGES: &[&str] = &["fr", "hy", "kab"];
+
+/// Languages that group digits with a (non-breaking) space and use a decimal comma.
+const SPACE_GROUPING_LANGUAGES: &[&str] = &[
+    "ru", "uk", "be", "fr", "pl", "cs", "sk", "fi", "sv", "nb", "no", "bg", "hu", "lt", "lv", "et", "kk",
+];
+
+/// Languages that group digits with a period and use a decimal comma.
+const PERIOD_GROUPING_LANGUAGES: &[&str] = &[
+    "de", "es", "it", "pt", "nl", "id", "tr", "da", "el", "ro", "hr", "sl", "sr", "vi",
+];
+
+/// Returns the digit group separator and decimal mark of a language subtag.
+pub fn number_symbols(language: &str) -> (&'static str, &'static str) {
+    if SPACE_GROUPING_LANGUAGES.contains(&language) {
+        ("\u{a0}", ",")
+    } else if PERIOD_GROUPING_LANGUAGES.contains(&language) {
+        (".", ",")
+    } else {
+        (",", ".")
+    }
+}
+
+/// Formats a number with `decimals` fraction digits, grouping thousands with
+/// the separators of `language`: `1234567.891` → `1,234,567.89` (en),
+/// `1 234 567,89` (ru).
+pub fn format_decimal(value: f64, decimals: usize, language: &str) -> String {
+    let (group, decimal) = number_symbols(language);
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = match formatted.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut out = String::new();
+    if value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        out.push('-');
+    }
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            out.push_str(group);
+        }
+        out.push(c);
+    }
+    if let Some(frac) = frac_part {
+        out.push_str(decimal);
+        out.push_str(frac);
+    }
+    out
+}
+
+/// Returns the CLDR cardinal plural category of `n` for a language subtag.
+pub fn cardinal_category(language: &str, n: u64) -> &'static str {
+    let mod10 = n % 10;
+    let mod100 = n % 100;
+
+    if SLAVIC_PLURAL_LANGUAGES.contains(&language) {
+        if mod10 == 1 && mod100 != 11 {
+            "one"
+        } else if (2..=4).contains(&mod10) && !(12..=14).contains(&mod100) {
+            "few"
+        } else if mod10 == 0 || (5..=9).contains(&mod10) || (11..=14).contains(&mod100) {
+            "many"
+        } else {
+            "other"
+        }
+    } else if INVARIANT_PLURAL_LANGUAGES.contains(&language) {
+        "other"
+    } else if ZERO_ONE_PLURAL_LANGUAGES.contains(&language) {
+        if n <= 1 {
+            "one"
+        } else {
+            "other"
+        }
+    } else if n == 1 {
+        "one"
+    } else {
+        "other"
+    }
+}
+
+/// Returns the CLDR ordinal plural category of `n` for a language subtag.
+/// Only English distinguishes ordinal forms; other languages use "other".
+pub fn ordinal_category(language: &str, n: u64) -> &'static str {
+    if language != "en" {
+        return "other";
+    }
+    match (n % 10, n % 100) {
+        (1, m) if m != 11 => "one",
+        (2, m) if m != 12 => "two",
+        (3, m) if m != 13 => "few",
+        _ => "other",
+    }
+}
+
+/// A parsed message fragment.
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    /// `{name}`: replaced by the argument value.
+    Arg(String),
+    /// `#` inside a plural case: the (offset-adjusted) number.
+    Hash,
+    Plural {
+        arg: String,
+        ordinal: bool,
+        offset: f64,
+        cases: Vec<(String, Vec<Part>)>,
+    },
+    Select {
+        arg: String,
+        cases: Vec<(String, Vec<Part>)>,
+    },
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, msg: &str) -> LocaleError {
+        LocaleError::InvalidMessage(format!("{} at offset {}", msg, self.pos))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), LocaleError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    /// Reads a run of characters up to whitespace or a syntax character.
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | '{' | '}'))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Parses message text until the end of input or, when nested, the
+    /// closing brace of the enclosing case (which is left unconsumed).
+    fn message(&mut self, nested: bool, in_plural: bool) -> Result<Vec<Part>, LocaleError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '\'' => {
+                    let next = self.chars.get(self.pos + 1).copied();
+                    match next {
+                        Some('\'') => {
+                            text.push('\'');
+                            self.pos += 2;
+                        }
+                        Some('{') | Some('}') => self.quoted(&mut text),
+                        Some('#') if in_plural => self.quoted(&mut text),
+                        _ => {
+                            text.push('\'');
+                            self.pos += 1;
+                        }
+                    }
+                }
+                '{' => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    self.pos += 1;
+                    parts.push(self.argument(in_plural)?);
+                }
+                '}' if nested => break,
+                '}' => return Err(self.error("unmatched '}'")),
+                '#' if in_plural => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Hash);
+                    self.pos += 1;
+                }
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(parts)
+    }
+
+    /// Consumes a quoted literal starting at an apostrophe. An unterminated
+    /// quote extends to the end of the message, as in ICU.
+    fn quoted(&mut self, text: &mut String) {
+        self.pos += 1;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == '\'' {
+                if self.peek() == Some('\'') {
+                    text.push('\'');
+                    self.pos += 1;
+                } else {
+                    return;
+                }
+            } else {
+                text.push(c);
+            }
+        }
+    }
+
+    /// Parses an argument after its opening brace, including the closing brace.
+    fn argument(&mut self, in_plural: bool) -> Result<Part, LocaleError> {
+        self.skip_whitespace();
+        let arg = self.word();
+        if arg.is_empty() {
+            return Err(self.error("missing argument name"));
+        }
+        self.skip_whitespace();
+
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Part::Arg(arg));
+        }
+        self.expect(',')?;
+        self.skip_whitespace();
+        let kind = self.word();
+        self.skip_whitespace();
+
+        let part = match kind.as_str() {
+            "plural" | "selectordinal" => {
+                self.expect(',')?;
+                self.skip_whitespace();
+                let mut offset = 0.0;
+                if self.chars[self.pos..].starts_with(&['o', 'f', 'f', 's', 'e', 't', ':']) {
+                    self.pos += 7;
+                    self.skip_whitespace();
+                    offset = self
+                        .word()
+                        .parse()
+                        .map_err(|_| self.error("invalid plural offset"))?;
+                }
+                Part::Plural {
+                    arg,
+                    ordinal: kind == "selectordinal",
+                    offset,
+                    cases: self.cases(true)?,
+                }
+            }
+            "select" => {
+                self.expect(',')?;
+                Part::Select {
+                    arg,
+                    cases: self.cases(in_plural)?,
+                }
+            }
+            "" => return Err(self.error("missing argument type")),
+            _ => {
+                // Typed arguments such as `{n, number}` or `{d, date, short}`
+                // are rendered as their raw value.
+                while self.peek().is_some_and(|c| c != '}') {
+                    self.pos += 1;
+                }
+                Part::Arg(arg)
+            }
+        };
+
+        self.skip_whitespace();
+        self.expect('}')?;
+        Ok(part)
+    }
+
+    /// Parses `selector {message}` pairs until the argument's closing brace.
+    fn cases(&mut self, in_plural: bool) -> Result<Vec<(String, Vec<Part>)>, LocaleError> {
+        let mut cases = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') || self.peek().is_none() {
+                break;
+            }
+            let selector = self.word();
+            if selector.is_empty() {
+                return Err(self.error("missing case selector"));
+            }
+            self.skip_whitespace();
+            self.expect('{')?;
+            let message = self.message(true, in_plural)?;
+            self.expect('}')?;
+            cases.push((selector, message));
+        }
+        if !cases.iter().any(|(s, _)| s == "other") {
+            return Err(self.error("missing 'other' case"));
+        }
+        Ok(cases)
+    }
+}
+
+/// Parses a message pattern without formatting it, reporting syntax errors.
+pub fn validate_message(pattern: &str) -> Result<(), LocaleError> {
+    parse(pattern).map(|_| ())
+}
+
+fn parse(pattern: &str) -> Result<Vec<Part>, LocaleError> {
+    let mut parser = Parser {
+        chars: pattern.chars().collect(),
+        pos: 0,
+    };
+    parser.message(false, false)
+}
+
+/// Formats a message pattern with the plural rules of `language`.
+pub fn format_message(
+    pattern: &str,
+    language: &str,
+    params: &HashMap<String, String>,
+) -> Result<String, LocaleError> {
+    let parts = parse(pattern)?;
+    let mut out = String::new();
+    render(&parts, language, params, None, &mut out)?;
+    Ok(out)
+}
+
+/// Formats the number for `#` and exact `=N` selectors. Integers are printed
+/// without a fraction; other values keep their significant digits.
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+/// Formats the number substituted for `#` with the language's separators.
+fn format_hash(n: f64, language: &str) -> String {
+    let decimals = if n.fract() == 0.0 {
+        0
+    } else {
+        n.to_string().split_once('.').map_or(0, |(_, f)| f.len())
+    };
+    format_decimal(n, decimals, language)
+}
+
+fn find_case<'a>(cases: &'a [(String, Vec<Part>)], selector: &str) -> Option<&'a [Part]> {
+    cases
+        .iter()
+        .find(|(s, _)| s == selector)
+        .map(|(_, parts)| parts.as_slice())
+}
+
+fn render(
+    parts: &[Part],
+    language: &str,
+    params: &HashMap<String, String>,
+    number: Option<f64>,
+    out: &mut String,
+) -> Result<(), LocaleError> {
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Arg(name) => match params.get(name) {
+                Some(value) => out.push_str(value),
+                None => {
+                    out.push('{');
+                    out.push_str(name);
+                    out.push('}');
+                }
+            },
+            Part::Hash => match number {
+                Some(n) => out.push_str(&format_hash(n, language)),
+                None => out.push('#'),
+            },
+            Part::Plural {
+                arg,
+                ordinal,
+                offset,
+                cases,
+            } => {
+                let value: f64 = params
+                    .get(arg)
+                    .and_then(|v| v.trim().parse().ok())
+                    .ok_or_else(|| {
+                        LocaleError::InvalidMessage(format!("'{}' is not a number", arg))
+                    })?;
+                let adjusted = value - offset;
+                let category = if adjusted >= 0.0 && adjusted.fract() == 0.0 {
+                    if *ordinal {
+                        ordinal_category(language, adjusted as u64)
+                    } else {
+                        cardinal_category(language, adjusted as u64)
+                    }
+                } else {
+                    "other"
+                };
+                let chosen = find_case(cases, &format!("={}", format_number(value)))
+                    .or_else(|| find_case(cases, category))
+                    .or_else(|| find_case(cases, "other"))
+                    .unwrap_or_default();
+                render(chosen, language, params, Some(adjusted), out)?;
+            }
+            Part::Select { arg, cases } => {
+                let selector = params.get(arg).map(String::as_str).unwrap_or("other");
+                let chosen = find_case(cases, selector)
+                    .or_else(|| find_case(cases, "other"))
+                    .unwrap_or_default();
+                render(chosen, language, params, number, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_simple_arguments() {
+        let p = params(&[("name", "World")]);
+        assert_eq!(format_message("Hello, {name}!", "en", &p).unwrap(), "Hello, World!");
+        assert_eq!(format_message("Hi {missing}", "en", &p).unwrap(), "Hi {missing}");
+        assert_eq!(format_message("{name, number}", "en", &p).unwrap(), "World");
+    }
+
+    #[test]
+    fn test_plural_with_nested_argument() {
+        let pattern = "{count, plural, one {# tab} other {# tabs}} in {group}";
+        let p = params(&[("count", "1"), ("group", "Work")]);
+        assert_eq!(format_message(pattern, "en", &p).unwrap(), "1 tab in Work");
+        let p = params(&[("count", "5"), ("group", "Work")]);
+        assert_eq!(format_message(pattern, "en", &p).unwrap(), "5 tabs in Work");
+    }
+
+    #[test]
+    fn test_plural_russian_and_exact_match() {
+        let pattern = "{n, plural, =0 {нет вкладок} one {# вкладка} few {# вкладки} many {# вкладок} other {# вкладки}}";
+        let cases = [("0", "нет вкладок"), ("1", "1 вкладка"), ("3", "3 вкладки"), ("11", "11 вкладок"), ("1.5", "1,5 вкладки"), ("1000", "1\u{a0}000 вкладок")];
+        for (n, expected) in cases {
+            assert_eq!(format_message(pattern, "ru", &params(&[("n", n)])).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_plural_offset() {
+        let pattern = "{n, plural, offset:1 =0 {nobody} =1 {{host}} one {{host} and # other} other {{host} and # others}}";
+        let p = |n| params(&[("n", n), ("host", "Ann")]);
+        assert_eq!(format_message(pattern, "en", &p("0")).unwrap(), "nobody");
+        assert_eq!(format_message(pattern, "en", &p("1")).unwrap(), "Ann");
+        assert_eq!(format_message(pattern, "en", &p("2")).unwrap(), "Ann and 1 other");
+        assert_eq!(format_message(pattern, "en", &p("4")).unwrap(), "Ann and 3 others");
+    }
+
+    #[test]
+    fn test_selectordinal() {
+        let pattern = "{n, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}";
+        let cases = [("1", "1st"), ("2", "2nd"), ("3", "3rd"), ("4", "4th"), ("11", "11th"), ("22", "22nd"), ("113", "113th")];
+        for (n, expected) in cases {
+            assert_eq!(format_message(pattern, "en", &params(&[("n", n)])).unwrap(), expected);
+        }
+        assert_eq!(format_message(pattern, "ru", &params(&[("n", "2")])).unwrap(), "2th");
+    }
+
+    #[test]
+    fn test_select_nested_in_plural() {
+        let pattern = "{g, select, female {{n, plural, one {She has # tab} other {She has # tabs}}} other {{n, plural, one {They have # tab} other {They have # tabs}}}}";
+        let p = params(&[("g", "female"), ("n", "2")]);
+        assert_eq!(format_message(pattern, "en", &p).unwrap(), "She has 2 tabs");
+        let p = params(&[("g", "unknown"), ("n", "1")]);
+        assert_eq!(format_message(pattern, "en", &p).unwrap(), "They have 1 tab");
+    }
+
+    #[test]
+    fn test_apostrophe_quoting() {
+        let p = params(&[("n", "2")]);
+        assert_eq!(format_message("Don't '{'quote'}' it's", "en", &p).unwrap(), "Don't {quote} it's");
+        assert_eq!(format_message("a''b", "en", &p).unwrap(), "a'b");
+        assert_eq!(format_message("{n, plural, other {'#' is #}}", "en", &p).unwrap(), "# is 2");
+    }
+
+    #[test]
+    fn test_invalid_messages() {
+        assert!(validate_message("{count, plural, one {# tab}}").is_err());
+        assert!(validate_message("{count, plural, other {# tabs}").is_err());
+        assert!(validate_message("stray }").is_err());
+        assert!(validate_message("{}").is_err());
+        assert!(format_message("{n, plural, other {#}}", "en", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_format_decimal() {
+        assert_eq!(format_decimal(1234567.891, 2, "en"), "1,234,567.89");
+        assert_eq!(format_decimal(1234567.891, 2, "ru"), "1\u{a0}234\u{a0}567,89");
+        assert_eq!(format_decimal(1234.5, 1, "de"), "1.234,5");
+        assert_eq!(format_decimal(999.0, 0, "en"), "999");
+        assert_eq!(format_decimal(-1234.0, 0, "en"), "-1,234");
+        assert_eq!(format_decimal(-0.001, 2, "en"), "0.00");
+        assert_eq!(format_decimal(0.0123, 4, "fr"), "0,0123");
+    }
+
+    #[test]
+    fn test_cardinal_categories() {
+        assert_eq!(cardinal_category("en", 1), "one");
+        assert_eq!(cardinal_category("en", 0), "other");
+        assert_eq!(cardinal_category("fr", 0), "one");
+        assert_eq!(cardinal_category("ja", 1), "other");
+        assert_eq!(cardinal_category("uk", 22), "few");
+    }
+}
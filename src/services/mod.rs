@@ -3,13 +3,28 @@
 
 pub mod ai_assistant;
 pub mod crash_recovery;
+pub mod crash_reporter;
 pub mod crypto_service;
+pub mod event_bus;
+pub mod event_hub;
+pub mod extension_archive;
 pub mod extension_framework;
+pub mod github_client;
 pub mod github_integration;
+pub mod github_notifications;
+pub mod github_quick_open;
+pub mod hang_watchdog;
 pub mod localization_engine;
+pub mod message_format;
 pub mod password_manager;
+pub mod pdf_viewer;
 pub mod privacy_engine;
 pub mod reader_mode;
+pub mod search_engine_registry;
 pub mod settings_engine;
+pub mod settings_migrations;
+pub mod sync;
 pub mod theme_engine;
+pub mod theme_scheduler;
+pub mod update_installer;
 pub mod update_manager;
@@ -4,13 +4,16 @@
 //! password generation, and import/export functionality.
 
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use rusqlite::params;
 use uuid::Uuid;
+use zeroize::Zeroizing;
 
 use crate::database::connection::Database;
+use crate::platform::{clipboard, keystore};
 use crate::services::crypto_service::{CryptoService, CryptoServiceTrait};
+use crate::services::event_bus::{AppEvent, EventBus};
 use crate::types::credential::{CredentialEntry, EncryptedData, PasswordGenOptions};
 use crate::types::errors::CryptoError;
 
@@ -32,12 +35,15 @@ pub trait PasswordManagerTrait {
 
 const MASTER_KEY_SALT_KEY: &str = "gitbrowser_master_salt";
 const MASTER_KEY_VERIFY_PLAINTEXT: &[u8] = b"gitbrowser-master-key-verify-v1";
+/// OS keystore entry holding the derived master key for quick unlock.
+const VAULT_KEY_ENTRY: &str = "vault-key";
 
 /// Password manager backed by SQLite + CryptoService.
 pub struct PasswordManager {
     db: Arc<Database>,
     crypto: CryptoService,
     derived_key: Option<Vec<u8>>,
+    bus: EventBus,
 }
 
 impl PasswordManager {
@@ -46,15 +52,128 @@ impl PasswordManager {
             db,
             crypto: CryptoService::new(),
             derived_key: None,
+            bus: EventBus::new(),
         }
     }
 
+    /// Emits `VaultLocked` on `bus` when the vault is locked.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
     /// Returns a clone of the derived master key if the manager is unlocked.
     /// Used by other services (GitHub, AI) to encrypt secrets with the master password.
     pub fn get_derived_key(&self) -> Option<Vec<u8>> {
         self.derived_key.clone()
     }
 
+    /// Keeps the derived master key in the OS keystore so the vault can be
+    /// unlocked without typing the master password. Whether reading it back
+    /// asks for the login password, Touch ID or Windows Hello is up to the OS.
+    pub fn enable_quick_unlock(&self) -> Result<(), CryptoError> {
+        let key = self.require_unlocked()?;
+        keystore::store(VAULT_KEY_ENTRY, key).map_err(|e| CryptoError::InvalidKey(e.to_string()))
+    }
+
+    /// Removes the master key from the OS keystore.
+    pub fn disable_quick_unlock(&self) -> Result<(), CryptoError> {
+        keystore::delete(VAULT_KEY_ENTRY)
+            .map(|_| ())
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+    }
+
+    /// Unlocks with the master key from the OS keystore. Returns `false` if
+    /// quick unlock is off or the stored key no longer opens the vault (the
+    /// stale key is then removed).
+    pub fn unlock_from_keystore(&mut self) -> Result<bool, CryptoError> {
+        let key = keystore::retrieve(VAULT_KEY_ENTRY).map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+        let (Some(key), Some(verify_token)) = (key, self.get_verification_token()) else {
+            return Ok(false);
+        };
+        match self.crypto.decrypt_aes256gcm(&verify_token, &key) {
+            Ok(plaintext) if plaintext == MASTER_KEY_VERIFY_PLAINTEXT => {
+                self.derived_key = Some(key);
+                Ok(true)
+            }
+            _ => {
+                let _ = keystore::delete(VAULT_KEY_ENTRY);
+                Ok(false)
+            }
+        }
+    }
+
+    /// The origin of `url` (`scheme://host[:port]`, lowercase, without the
+    /// scheme's default port): what HTTP authentication logins are saved under.
+    pub fn origin_of(url: &str) -> Option<String> {
+        let (scheme, rest) = url.trim().split_once("://")?;
+        let scheme = scheme.to_ascii_lowercase();
+        let authority = rest.split(['/', '?', '#']).next()?;
+        let host = authority.rsplit('@').next()?.to_ascii_lowercase();
+        if host.is_empty() || !matches!(scheme.as_str(), "http" | "https") {
+            return None;
+        }
+        let default_port = if scheme == "https" { ":443" } else { ":80" };
+        let host = host.strip_suffix(default_port).unwrap_or(&host);
+        Some(format!("{}://{}", scheme, host))
+    }
+
+    /// Logins saved under any URL of `origin`, oldest first.
+    pub fn credentials_for_origin(&self, origin: &str) -> Result<Vec<CredentialEntry>, CryptoError> {
+        let mut entries: Vec<CredentialEntry> = self
+            .list_all_credentials()?
+            .into_iter()
+            .filter(|c| Self::origin_of(&c.url).as_deref() == Some(origin))
+            .collect();
+        entries.sort_by_key(|c| c.created_at);
+        Ok(entries)
+    }
+
+    /// The first login saved for `origin`, decrypted. `None` when there is
+    /// none, or the vault is locked.
+    pub fn login_for_origin(&self, origin: &str) -> Option<(String, String)> {
+        let entry = self.credentials_for_origin(origin).ok()?.into_iter().next()?;
+        let password = self.decrypt_password(&entry).ok()?;
+        Some((entry.username, password))
+    }
+
+    /// Whether exactly this login is saved for `origin`.
+    pub fn is_login_saved(&self, origin: &str, username: &str, password: &str) -> bool {
+        self.credentials_for_origin(origin).unwrap_or_default().iter().any(|c| {
+            c.username == username && self.decrypt_password(c).is_ok_and(|saved| saved == password)
+        })
+    }
+
+    /// Saves a login for `url`, or changes the password of the one saved
+    /// with the same username for its origin. Returns its ID.
+    pub fn save_or_update_credential(&mut self, url: &str, username: &str, password: &str) -> Result<String, CryptoError> {
+        let saved = match Self::origin_of(url) {
+            Some(origin) => self.credentials_for_origin(&origin)?,
+            None => self.get_credentials(url)?,
+        };
+        let existing = saved.into_iter().find(|c| c.username == username);
+        match existing {
+            Some(entry) => {
+                self.update_credential(&entry.id, None, Some(password))?;
+                Ok(entry.id)
+            }
+            None => self.save_credential(url, username, password),
+        }
+    }
+
+    /// Puts the password of login `id` on the clipboard, to be cleared
+    /// after `clear_after` if it is still there (see
+    /// [`clipboard::copy_secret`]). Returns whether the clipboard took it.
+    pub fn copy_password(&self, id: &str, clear_after: Duration) -> Result<bool, CryptoError> {
+        let entry = self
+            .list_all_credentials()?
+            .into_iter()
+            .find(|c| c.id == id)
+            .ok_or_else(|| CryptoError::CredentialNotFound(id.to_string()))?;
+        let password = Zeroizing::new(self.decrypt_password(&entry)?);
+        Ok(clipboard::copy_secret(&password, clear_after))
+    }
+
     /// Ensures the master salt and verification token exist in the database.
     /// Returns the salt bytes.
     fn get_or_create_master_salt(&self) -> Result<Vec<u8>, CryptoError> {
@@ -118,6 +237,38 @@ impl PasswordManager {
     fn now_ts() -> i64 {
         SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
     }
+
+    /// Like `import_encrypted`, calling `imported` after each credential is
+    /// stored with the count so far and the number in the file.
+    pub fn import_encrypted_each(
+        &mut self,
+        master_password: &str,
+        file_path: &str,
+        mut imported: impl FnMut(&CredentialEntry, u32, u32),
+    ) -> Result<u32, CryptoError> {
+        let _key = self.require_unlocked()?;
+        let file_bytes = std::fs::read(file_path).map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        let export_data: serde_json::Value = serde_json::from_slice(&file_bytes).map_err(|e| CryptoError::Decryption(e.to_string()))?;
+
+        let salt: Vec<u8> = serde_json::from_value(export_data["salt"].clone()).map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        let encrypted: EncryptedData = serde_json::from_value(export_data["data"].clone()).map_err(|e| CryptoError::Decryption(e.to_string()))?;
+
+        let export_key = self.crypto.derive_key(master_password, &salt)?;
+        let json = self.crypto.decrypt_aes256gcm(&encrypted, &export_key)?;
+        let entries: Vec<CredentialEntry> = serde_json::from_slice(&json).map_err(|e| CryptoError::Decryption(e.to_string()))?;
+
+        let conn = self.db.connection();
+        let mut count = 0u32;
+        for entry in &entries {
+            conn.execute(
+                "INSERT OR REPLACE INTO credentials (id, url, username, encrypted_password, iv, auth_tag, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![entry.id, entry.url, entry.username, entry.encrypted_password, entry.iv, entry.auth_tag, entry.created_at, entry.updated_at],
+            ).map_err(|e| CryptoError::Encryption(e.to_string()))?;
+            count += 1;
+            imported(entry, count, entries.len() as u32);
+        }
+        Ok(count)
+    }
 }
 
 impl PasswordManagerTrait for PasswordManager {
@@ -152,6 +303,7 @@ impl PasswordManagerTrait for PasswordManager {
             self.crypto.zeroize_memory(key);
         }
         self.derived_key = None;
+        self.bus.emit(AppEvent::VaultLocked);
     }
 
     fn is_unlocked(&self) -> bool {
@@ -335,26 +487,6 @@ impl PasswordManagerTrait for PasswordManager {
     }
 
     fn import_encrypted(&mut self, master_password: &str, file_path: &str) -> Result<u32, CryptoError> {
-        let _key = self.require_unlocked()?;
-        let file_bytes = std::fs::read(file_path).map_err(|e| CryptoError::Decryption(e.to_string()))?;
-        let export_data: serde_json::Value = serde_json::from_slice(&file_bytes).map_err(|e| CryptoError::Decryption(e.to_string()))?;
-
-        let salt: Vec<u8> = serde_json::from_value(export_data["salt"].clone()).map_err(|e| CryptoError::Decryption(e.to_string()))?;
-        let encrypted: EncryptedData = serde_json::from_value(export_data["data"].clone()).map_err(|e| CryptoError::Decryption(e.to_string()))?;
-
-        let export_key = self.crypto.derive_key(master_password, &salt)?;
-        let json = self.crypto.decrypt_aes256gcm(&encrypted, &export_key)?;
-        let entries: Vec<CredentialEntry> = serde_json::from_slice(&json).map_err(|e| CryptoError::Decryption(e.to_string()))?;
-
-        let conn = self.db.connection();
-        let mut count = 0u32;
-        for entry in &entries {
-            conn.execute(
-                "INSERT OR REPLACE INTO credentials (id, url, username, encrypted_password, iv, auth_tag, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                params![entry.id, entry.url, entry.username, entry.encrypted_password, entry.iv, entry.auth_tag, entry.created_at, entry.updated_at],
-            ).map_err(|e| CryptoError::Encryption(e.to_string()))?;
-            count += 1;
-        }
-        Ok(count)
+        self.import_encrypted_each(master_password, file_path, |_, _, _| {})
     }
 }
@@ -0,0 +1,156 @@
+//! Built-in PDF viewing for GitBrowser.
+//!
+//! PDFs open in an internal page, `gb://localhost/pdf?src=<url>`, rendered
+//! by pdf.js instead of being downloaded. Its legacy build (`pdf.min.js`
+//! and `pdf.worker.min.js`) is compiled in by the `bundled-pdfjs` feature
+//! from `resources/pdfjs`, or ships in a `pdfjs` directory next to the
+//! executable, and is served over gb://.
+//! The PDF itself is fetched by the browser, so the viewer page never needs
+//! cross-origin access to the site.
+
+use std::path::{Path, PathBuf};
+
+use crate::services::search_engine_registry::encode_query;
+
+/// Largest PDF the viewer opens, in bytes.
+pub const MAX_PDF_BYTES: usize = 64 * 1024 * 1024;
+
+/// Files of pdf.js the viewer loads.
+pub const PDFJS_FILES: [&str; 2] = ["pdf.min.js", "pdf.worker.min.js"];
+
+/// `PDFJS_FILES` compiled into the executable.
+#[cfg(feature = "bundled-pdfjs")]
+const BUNDLED_FILES: [(&str, &[u8]); 2] = [
+    ("pdf.min.js", include_bytes!("../../resources/pdfjs/pdf.min.js")),
+    ("pdf.worker.min.js", include_bytes!("../../resources/pdfjs/pdf.worker.min.js")),
+];
+
+/// Internal page showing the PDF at `url`.
+pub fn viewer_url(url: &str) -> String {
+    format!("gb://localhost/pdf?src={}", encode_query(url))
+}
+
+/// The PDF a viewer page shows, from its query string (without the `?`).
+pub fn source_from_query(query: &str) -> Option<String> {
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("src="))
+        .map(decode_query)
+        .filter(|src| src.starts_with("http://") || src.starts_with("https://"))
+}
+
+/// Whether a load of `url` is a PDF: by its content type when known, else
+/// by the extension of its path.
+pub fn is_pdf(url: &str, content_type: Option<&str>) -> bool {
+    if let Some(content_type) = content_type {
+        let mime = content_type.split(';').next().unwrap_or("").trim();
+        return mime.eq_ignore_ascii_case("application/pdf");
+    }
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    (url.starts_with("http://") || url.starts_with("https://"))
+        && path.rsplit('/').next().is_some_and(|name| name.to_ascii_lowercase().ends_with(".pdf"))
+}
+
+/// Whether `bytes` start like a PDF file.
+pub fn has_pdf_signature(bytes: &[u8]) -> bool {
+    // The header may follow a little junk, as readers allow
+    bytes.windows(5).take(1024).any(|w| w == b"%PDF-")
+}
+
+/// Decodes a form-encoded query string component.
+fn decode_query(s: &str) -> String {
+    let hex = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i..i + 3) {
+            Some([b'%', hi, lo]) => hex(*hi).zip(hex(*lo)).map(|(hi, lo)| (hi << 4) | lo),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some(b), _) => {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, b) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Where pdf.js is installed.
+pub struct PdfJs {
+    dir: PathBuf,
+    /// Serves the copy compiled in with `bundled-pdfjs` rather than `dir`.
+    bundled: bool,
+}
+
+impl PdfJs {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), bundled: false }
+    }
+
+    /// The copy compiled in with the `bundled-pdfjs` feature, if any.
+    pub fn bundled() -> Option<Self> {
+        cfg!(feature = "bundled-pdfjs").then(|| Self { dir: PathBuf::new(), bundled: true })
+    }
+
+    /// The compiled-in copy, else the `pdfjs` directory shipped with the
+    /// app: next to the executable, or in `../share/gitbrowser` for Unix
+    /// installs. `None` when neither has pdf.js.
+    pub fn locate() -> Option<Self> {
+        if let Some(bundled) = Self::bundled() {
+            return Some(bundled);
+        }
+        let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf))?;
+        [
+            exe_dir.join("pdfjs"),
+            exe_dir.join("..").join("share").join("gitbrowser").join("pdfjs"),
+        ]
+        .into_iter()
+        .map(Self::new)
+        .find(|p| p.is_installed())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether all of `PDFJS_FILES` are there.
+    pub fn is_installed(&self) -> bool {
+        self.bundled || PDFJS_FILES.iter().all(|name| self.dir.join(name).is_file())
+    }
+
+    /// A file of pdf.js and its content type. Only plain file names inside
+    /// the directory are served.
+    pub fn asset(&self, name: &str) -> Option<(Vec<u8>, &'static str)> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return None;
+        }
+        let content_type = match name.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("js") | Some("mjs") => "text/javascript",
+            Some("css") => "text/css",
+            Some("json") => "application/json",
+            Some("bcmap") | Some("pfb") | Some("ttf") | Some("wasm") => "application/octet-stream",
+            _ => return None,
+        };
+        if self.bundled {
+            return bundled_file(name).map(|bytes| (bytes.to_vec(), content_type));
+        }
+        std::fs::read(self.dir.join(name)).ok().map(|bytes| (bytes, content_type))
+    }
+}
+
+#[cfg(feature = "bundled-pdfjs")]
+fn bundled_file(name: &str) -> Option<&'static [u8]> {
+    BUNDLED_FILES.iter().find(|(file, _)| *file == name).map(|(_, bytes)| *bytes)
+}
+
+#[cfg(not(feature = "bundled-pdfjs"))]
+fn bundled_file(_name: &str) -> Option<&'static [u8]> {
+    None
+}
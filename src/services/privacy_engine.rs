@@ -1,17 +1,26 @@
 //! Privacy Engine for GitBrowser.
 //!
-//! Handles tracker/ad blocking, HTTPS enforcement, DNS-over-HTTPS,
-//! private browsing mode, and anti-fingerprinting.
+//! Handles tracker/ad blocking (built-in lists, the user's own filters and
+//! rules contributed by extensions), per-site exceptions, HTTPS enforcement,
+//! DNS-over-HTTPS, private browsing mode, and anti-fingerprinting.
+
+use std::collections::HashSet;
 
 use crate::types::errors::PrivacyError;
-use crate::types::privacy::PrivacyStats;
+use crate::types::privacy::{FilterList, NetworkRule, NetworkRuleAction, PrivacyStats};
+use crate::types::settings::PrivacySettings;
 
 /// Trait defining privacy engine operations.
 pub trait PrivacyEngineTrait {
     fn initialize(&mut self) -> Result<(), PrivacyError>;
     fn should_block_request(&self, url: &str, resource_type: &str) -> bool;
+    /// `should_block_request` for a request made by a page on `site`;
+    /// nothing is blocked on sites whose protections are turned off.
+    fn should_block_on_site(&self, url: &str, resource_type: &str, site: Option<&str>) -> bool;
     fn upgrade_to_https(&self, url: &str) -> Option<String>;
     fn configure_dns_over_https(&mut self, provider: &str) -> Result<(), PrivacyError>;
+    /// The DNS-over-HTTPS resolver in use, if any.
+    fn dns_over_https_provider(&self) -> Option<&str>;
     fn enable_private_mode(&mut self);
     fn disable_private_mode(&mut self);
     fn is_private_mode(&self) -> bool;
@@ -19,10 +28,27 @@ pub trait PrivacyEngineTrait {
     fn clear_private_data(&mut self) -> Result<(), PrivacyError>;
     /// Record a blocked request in stats. Call after should_block_request returns true.
     fn record_blocked(&mut self, url: &str);
+    /// `record_blocked`, also counting the block for `site`, the page's host.
+    fn record_blocked_on_site(&mut self, url: &str, site: Option<&str>);
     /// Record an HTTPS upgrade in stats.
     fn record_https_upgrade(&mut self);
+    /// Apply the user's `privacy.*` settings (blocking, HTTPS-only, DoH).
+    fn apply_settings(&mut self, settings: &PrivacySettings);
+    /// Replaces the blocking rules contributed by extensions.
+    fn set_extension_rules(&mut self, rules: Vec<NetworkRule>);
+    /// Number of extension rules currently loaded.
+    fn extension_rule_count(&self) -> usize;
+    /// Replaces the sites (hosts) whose protections are turned off.
+    fn set_disabled_sites(&mut self, sites: Vec<String>);
+    fn is_site_disabled(&self, site: &str) -> bool;
+    /// The built-in lists and the user's filters, with their state.
+    fn filter_lists(&self) -> Vec<FilterList>;
 }
 
+/// When `TRACKER_DOMAINS` and `AD_PATH_PATTERNS` were last revised (UNIX
+/// seconds, 2026-10-01). Bump it with the lists.
+pub const BUILTIN_LISTS_UPDATED_AT: i64 = 1_790_812_800;
+
 /// Known tracker domains for basic blocking without the adblock crate.
 const TRACKER_DOMAINS: &[&str] = &[
     // Google
@@ -67,7 +93,12 @@ pub struct PrivacyEngine {
     stats: PrivacyStats,
     doh_provider: Option<String>,
     tracker_blocking_enabled: bool,
+    ad_blocking_enabled: bool,
     https_enforcement_enabled: bool,
+    /// Extension rules with their URL filters parsed and domains lowercased
+    extension_rules: Vec<(NetworkRule, Option<UrlFilter>)>,
+    custom_filters: Vec<UrlFilter>,
+    disabled_sites: HashSet<String>,
 }
 
 impl PrivacyEngine {
@@ -77,7 +108,11 @@ impl PrivacyEngine {
             stats: PrivacyStats::default(),
             doh_provider: None,
             tracker_blocking_enabled: true,
+            ad_blocking_enabled: true,
             https_enforcement_enabled: true,
+            extension_rules: Vec::new(),
+            custom_filters: Vec::new(),
+            disabled_sites: HashSet::new(),
         }
     }
 
@@ -90,6 +125,202 @@ impl PrivacyEngine {
         let url_lower = url.to_lowercase();
         AD_PATH_PATTERNS.iter().any(|pat| url_lower.contains(pat))
     }
+
+    fn is_custom_filtered(&self, url: &str) -> bool {
+        if self.custom_filters.is_empty() {
+            return false;
+        }
+        let target = FilterTarget::new(url);
+        self.custom_filters.iter().any(|filter| filter.matches_target(&target))
+    }
+
+    /// The action of the highest-priority extension rule matching the request.
+    fn extension_rule_action(&self, url: &str, resource_type: &str) -> Option<NetworkRuleAction> {
+        if self.extension_rules.is_empty() {
+            return None;
+        }
+        let target = FilterTarget::new(url);
+        self.extension_rules.iter()
+            .filter(|(r, filter)| rule_matches(r, filter.as_ref(), &target, resource_type))
+            .max_by_key(|(r, _)| (r.priority, r.action == NetworkRuleAction::Allow))
+            .map(|(r, _)| r.action)
+    }
+}
+
+/// Whether `rule` applies to the request; its domains are already lowercase.
+fn rule_matches(rule: &NetworkRule, filter: Option<&UrlFilter>, target: &FilterTarget, resource_type: &str) -> bool {
+    let type_ok = if rule.resource_types.is_empty() {
+        resource_type != "main_frame"
+    } else {
+        rule.resource_types.iter().any(|t| t == resource_type)
+    };
+    if !type_ok || rule.excluded_resource_types.iter().any(|t| t == resource_type) {
+        return false;
+    }
+    let host = target.host.as_str();
+    let on_domain = |d: &String| {
+        host == d || host.strip_suffix(d.as_str()).is_some_and(|sub| sub.ends_with('.'))
+    };
+    if !rule.request_domains.is_empty() && !rule.request_domains.iter().any(on_domain) {
+        return false;
+    }
+    if rule.excluded_request_domains.iter().any(on_domain) {
+        return false;
+    }
+    filter.is_none_or(|f| f.matches_target(target))
+}
+
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let authority = &rest[..end];
+    let host = authority.rsplit_once('@').map(|(_, h)| h).unwrap_or(authority);
+    host.split(':').next().unwrap_or(host)
+}
+
+/// Matches a declarativeNetRequest `urlFilter` (case-insensitive): `||`
+/// anchors to the host or a subdomain, a leading/trailing `|` anchors to the
+/// start/end of the URL, `*` is any run of characters and `^` is a separator
+/// (anything but a letter, digit, `_ - . %`) or the end of the URL.
+pub fn url_filter_matches(filter: &str, url: &str) -> bool {
+    UrlFilter::parse(filter).matches(url)
+}
+
+/// A `urlFilter` parsed once, to be matched against many URLs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlFilter {
+    start: FilterStart,
+    end_anchor: bool,
+    pattern: Vec<FilterToken>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FilterStart {
+    /// `||`: at the host or one of its subdomains
+    Host,
+    /// `|`: at the start of the URL
+    Url,
+    /// Anywhere in the URL
+    Anywhere,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FilterToken {
+    Char(char),
+    Separator,
+    Wildcard,
+}
+
+/// A URL lowercased once for matching against every filter.
+struct FilterTarget {
+    text: Vec<char>,
+    host: String,
+    /// Where `||` filters may start: the host and each of its subdomains
+    host_starts: Vec<usize>,
+}
+
+impl FilterTarget {
+    fn new(url: &str) -> Self {
+        let lower = url.to_ascii_lowercase();
+        let text: Vec<char> = lower.chars().collect();
+        let host_start = lower.find("://").map(|i| lower[..i + 3].chars().count()).unwrap_or(0);
+        let host = url_host(&lower).to_string();
+        let mut host_starts = vec![host_start];
+        host_starts.extend(
+            (host_start..host_start + host.chars().count()).filter(|&i| text[i] == '.').map(|i| i + 1),
+        );
+        Self { text, host, host_starts }
+    }
+}
+
+impl UrlFilter {
+    pub fn parse(filter: &str) -> Self {
+        let filter = filter.to_ascii_lowercase();
+        let (start, rest) = if let Some(rest) = filter.strip_prefix("||") {
+            (FilterStart::Host, rest)
+        } else if let Some(rest) = filter.strip_prefix('|') {
+            (FilterStart::Url, rest)
+        } else {
+            (FilterStart::Anywhere, filter.as_str())
+        };
+        let (end_anchor, pattern) = match rest.strip_suffix('|') {
+            Some(p) => (true, p),
+            None => (false, rest),
+        };
+        let mut tokens: Vec<FilterToken> = Vec::with_capacity(pattern.len() + 1);
+        if start == FilterStart::Anywhere {
+            tokens.push(FilterToken::Wildcard);
+        }
+        for c in pattern.chars() {
+            let token = match c {
+                '*' => FilterToken::Wildcard,
+                '^' => FilterToken::Separator,
+                c => FilterToken::Char(c),
+            };
+            // Runs of `*` match the same as one
+            if !(token == FilterToken::Wildcard && tokens.last() == Some(&FilterToken::Wildcard)) {
+                tokens.push(token);
+            }
+        }
+        Self { start, end_anchor, pattern: tokens }
+    }
+
+    pub fn matches(&self, url: &str) -> bool {
+        self.matches_target(&FilterTarget::new(url))
+    }
+
+    fn matches_target(&self, target: &FilterTarget) -> bool {
+        match self.start {
+            FilterStart::Host => target.host_starts.iter().any(|&s| self.matches_at(&target.text[s..])),
+            FilterStart::Url | FilterStart::Anywhere => self.matches_at(&target.text),
+        }
+    }
+
+    /// Matches the pattern from the start of `text`, greedily, going back
+    /// only to the last `*` seen on a mismatch. Every other token takes one
+    /// character (`^` none at the end), so that one retry point is enough
+    /// and the time stays within pattern length times text length.
+    fn matches_at(&self, text: &[char]) -> bool {
+        let (mut p, mut t) = (0, 0);
+        // Pattern position after the last `*`, and where in the text it resumed
+        let mut retry: Option<(usize, usize)> = None;
+        loop {
+            match self.pattern.get(p) {
+                None if !self.end_anchor || t == text.len() => return true,
+                Some(FilterToken::Wildcard) => {
+                    p += 1;
+                    retry = Some((p, t));
+                    continue;
+                }
+                Some(&token) => {
+                    if let Some(next) = token_match(token, text, t) {
+                        p += 1;
+                        t = next;
+                        continue;
+                    }
+                }
+                None => {}
+            }
+            match retry {
+                Some((after_star, resumed)) if resumed < text.len() => {
+                    retry = Some((after_star, resumed + 1));
+                    p = after_star;
+                    t = resumed + 1;
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// Where the text continues if `token` matches at `t`.
+fn token_match(token: FilterToken, text: &[char], t: usize) -> Option<usize> {
+    match (token, text.get(t)) {
+        (FilterToken::Separator, None) => Some(t),
+        (FilterToken::Separator, Some(c)) if !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '%')) => Some(t + 1),
+        (FilterToken::Char(expected), Some(&c)) if c == expected => Some(t + 1),
+        _ => None,
+    }
 }
 
 impl Default for PrivacyEngine {
@@ -104,15 +335,28 @@ impl PrivacyEngineTrait for PrivacyEngine {
         Ok(())
     }
 
-    fn should_block_request(&self, url: &str, _resource_type: &str) -> bool {
-        if !self.tracker_blocking_enabled {
-            return false;
+    fn should_block_request(&self, url: &str, resource_type: &str) -> bool {
+        // Extension rules are explicit user choices and apply regardless of settings
+        match self.extension_rule_action(url, resource_type) {
+            Some(NetworkRuleAction::Block) => return true,
+            Some(NetworkRuleAction::Allow) => return false,
+            None => {}
+        }
+        if self.is_custom_filtered(url) {
+            return true;
         }
-        let is_tracker = self.is_tracker_url(url);
-        let is_ad = self.is_ad_url(url);
+        let is_tracker = self.tracker_blocking_enabled && self.is_tracker_url(url);
+        let is_ad = self.ad_blocking_enabled && self.is_ad_url(url);
         is_tracker || is_ad
     }
 
+    fn should_block_on_site(&self, url: &str, resource_type: &str, site: Option<&str>) -> bool {
+        if site.is_some_and(|site| self.is_site_disabled(site)) {
+            return false;
+        }
+        self.should_block_request(url, resource_type)
+    }
+
     fn upgrade_to_https(&self, url: &str) -> Option<String> {
         if !self.https_enforcement_enabled {
             return None;
@@ -132,6 +376,10 @@ impl PrivacyEngineTrait for PrivacyEngine {
         Ok(())
     }
 
+    fn dns_over_https_provider(&self) -> Option<&str> {
+        self.doh_provider.as_deref()
+    }
+
     fn enable_private_mode(&mut self) {
         self.private_mode = true;
     }
@@ -156,15 +404,82 @@ impl PrivacyEngineTrait for PrivacyEngine {
     }
 
     fn record_blocked(&mut self, url: &str) {
-        if self.is_tracker_url(url) {
+        let by_tracker_list = self.is_tracker_url(url);
+        let by_ad_list = self.is_ad_url(url);
+        if by_tracker_list {
             self.stats.trackers_blocked += 1;
         }
-        if self.is_ad_url(url) {
+        if by_ad_list {
             self.stats.ads_blocked += 1;
         }
+        if !by_tracker_list && !by_ad_list {
+            if self.is_custom_filtered(url) {
+                self.stats.custom_filters_blocked += 1;
+            } else {
+                self.stats.extension_rules_blocked += 1;
+            }
+        }
+    }
+
+    fn record_blocked_on_site(&mut self, url: &str, site: Option<&str>) {
+        self.record_blocked(url);
+        if let Some(site) = site {
+            *self.stats.blocked_by_site.entry(site.to_lowercase()).or_default() += 1;
+        }
     }
 
     fn record_https_upgrade(&mut self) {
         self.stats.https_upgrades += 1;
     }
+
+    fn apply_settings(&mut self, settings: &PrivacySettings) {
+        self.tracker_blocking_enabled = settings.tracker_blocking;
+        self.ad_blocking_enabled = settings.ad_blocking;
+        self.https_enforcement_enabled = settings.https_enforcement;
+        self.doh_provider = if settings.dns_over_https && !settings.dns_provider.is_empty() {
+            Some(settings.dns_provider.clone())
+        } else {
+            None
+        };
+        self.custom_filters = settings.custom_filters.iter().map(|f| UrlFilter::parse(f)).collect();
+    }
+
+    fn set_extension_rules(&mut self, rules: Vec<NetworkRule>) {
+        self.extension_rules = rules
+            .into_iter()
+            .map(|mut rule| {
+                for domain in rule.request_domains.iter_mut().chain(rule.excluded_request_domains.iter_mut()) {
+                    domain.make_ascii_lowercase();
+                }
+                let filter = rule.url_filter.as_deref().map(UrlFilter::parse);
+                (rule, filter)
+            })
+            .collect();
+    }
+
+    fn extension_rule_count(&self) -> usize {
+        self.extension_rules.len()
+    }
+
+    fn set_disabled_sites(&mut self, sites: Vec<String>) {
+        self.disabled_sites = sites.into_iter().map(|s| s.to_lowercase()).collect();
+    }
+
+    fn is_site_disabled(&self, site: &str) -> bool {
+        self.disabled_sites.contains(&site.to_lowercase())
+    }
+
+    fn filter_lists(&self) -> Vec<FilterList> {
+        let list = |id: &str, entries: usize, enabled: bool, updated_at: Option<i64>| FilterList {
+            id: id.to_string(),
+            entries,
+            enabled,
+            updated_at,
+        };
+        vec![
+            list("trackers", TRACKER_DOMAINS.len(), self.tracker_blocking_enabled, Some(BUILTIN_LISTS_UPDATED_AT)),
+            list("ads", AD_PATH_PATTERNS.len(), self.ad_blocking_enabled, Some(BUILTIN_LISTS_UPDATED_AT)),
+            list("custom", self.custom_filters.len(), true, None),
+        ]
+    }
 }
@@ -1,15 +1,28 @@
 //! Reader Mode for GitBrowser.
 //!
 //! Extracts article content from web pages and formats it for distraction-free reading.
+//! Paginated articles can be stitched into a single document by following
+//! `rel="next"` / "Next page" links (see `ReaderMode::extract_multipage`).
+
+use std::collections::HashSet;
+use std::time::Duration;
 
 use crate::types::errors::ReaderError;
-use crate::types::reader::{FontFamily, ReaderContent, ReaderSettings};
+use crate::types::reader::{FontFamily, ReaderContent, ReaderPreset, ReaderSettings};
+
+/// Hard cap on the number of pages stitched into one article.
+pub const MAX_STITCHED_PAGES: usize = 10;
+
+/// Maximum size of a fetched continuation page, in bytes.
+const MAX_PAGE_BYTES: usize = 5 * 1024 * 1024;
 
 /// Trait defining reader mode operations.
 pub trait ReaderModeTrait {
     fn is_article_page(&self, html: &str, url: &str) -> bool;
     fn extract_content(&self, html: &str, url: &str) -> Result<ReaderContent, ReaderError>;
     fn format_for_display(&self, content: &ReaderContent, settings: &ReaderSettings) -> String;
+    /// Finds the absolute URL of the article's next page, if it is paginated.
+    fn find_next_page_url(&self, html: &str, url: &str) -> Option<String>;
     fn update_settings(&mut self, settings: ReaderSettings);
     fn get_settings(&self) -> &ReaderSettings;
 }
@@ -22,13 +35,31 @@ pub struct ReaderMode {
 impl ReaderMode {
     pub fn new() -> Self {
         Self {
-            settings: ReaderSettings {
-                font_size: 18,
-                font_family: FontFamily::SansSerif,
-                background_color: "#ffffff".to_string(),
-                line_height: 1.6,
-                max_width: 680,
-            },
+            settings: ReaderSettings::default(),
+        }
+    }
+
+    /// Creates a reader mode using persisted settings (the `reader.*` keys).
+    pub fn with_settings(settings: ReaderSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Returns (background, text, link, muted) colors for a preset.
+    /// Only hex `background_color` values are honored so settings can't inject CSS.
+    fn preset_palette(settings: &ReaderSettings) -> (String, &'static str, &'static str, &'static str) {
+        match settings.preset {
+            ReaderPreset::Default => {
+                let hex = settings.background_color.strip_prefix('#').unwrap_or("");
+                let bg = if (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    settings.background_color.clone()
+                } else {
+                    "#ffffff".to_string()
+                };
+                (bg, "#24292f", "#0969da", "#656d76")
+            }
+            ReaderPreset::Sepia => ("#f4ecd8".to_string(), "#5b4636", "#8a4b08", "#8b7355"),
+            ReaderPreset::Dark => ("#0d1117".to_string(), "#e6edf3", "#58a6ff", "#7d8590"),
+            ReaderPreset::HighContrast => ("#000000".to_string(), "#ffffff", "#ffff00", "#ffffff"),
         }
     }
 
@@ -109,10 +140,10 @@ impl ReaderMode {
                             if let Some(eq_pos) = lower[pos..].find('=') {
                                 let after_eq = pos + eq_pos + 1;
                                 let rest = &result[after_eq..].trim_start();
-                                let attr_end = if rest.starts_with('"') {
-                                    rest[1..].find('"').map(|i| after_eq + (result.len() - after_eq - rest.len()) + 1 + i + 1)
-                                } else if rest.starts_with('\'') {
-                                    rest[1..].find('\'').map(|i| after_eq + (result.len() - after_eq - rest.len()) + 1 + i + 1)
+                                let attr_end = if let Some(quoted) = rest.strip_prefix('"') {
+                                    quoted.find('"').map(|i| after_eq + (result.len() - after_eq - rest.len()) + 1 + i + 1)
+                                } else if let Some(quoted) = rest.strip_prefix('\'') {
+                                    quoted.find('\'').map(|i| after_eq + (result.len() - after_eq - rest.len()) + 1 + i + 1)
                                 } else {
                                     rest.find(|c: char| c.is_whitespace() || c == '>')
                                         .map(|i| after_eq + (result.len() - after_eq - rest.len()) + i)
@@ -153,6 +184,197 @@ impl ReaderMode {
         result
     }
 
+    /// Extracts content from `html` and follows next-page links, fetching each
+    /// continuation with `fetch` and appending it to the article. Stops after
+    /// `max_pages` pages (capped at `MAX_STITCHED_PAGES`), on a page that is
+    /// not an article, on a link to another host, or on a repeated URL.
+    pub fn extract_multipage<F>(&self, html: &str, url: &str, max_pages: usize, mut fetch: F) -> Result<ReaderContent, ReaderError>
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let mut content = self.extract_content(html, url)?;
+        let max_pages = max_pages.clamp(1, MAX_STITCHED_PAGES);
+        let mut visited: HashSet<String> = HashSet::new();
+        visited.insert(Self::strip_fragment(url).to_string());
+
+        let mut pages = vec![content.content.clone()];
+        let mut next = self.find_next_page_url(html, url);
+
+        while pages.len() < max_pages {
+            let next_url = match next.take() {
+                Some(u) => u,
+                None => break,
+            };
+            if Self::host_of(&next_url) != Self::host_of(url) || !visited.insert(next_url.clone()) {
+                break;
+            }
+            let page_html = match fetch(&next_url) {
+                Some(h) => h,
+                None => break,
+            };
+            let page = match self.extract_content(&page_html, &next_url) {
+                Ok(p) => p,
+                Err(_) => break,
+            };
+            content.text_content.push_str("\n\n");
+            content.text_content.push_str(&page.text_content);
+            pages.push(page.content);
+            next = self.find_next_page_url(&page_html, &next_url);
+        }
+
+        let total = pages.len();
+        if total > 1 {
+            let mut stitched = String::new();
+            for (i, page) in pages.iter().enumerate() {
+                if i > 0 {
+                    stitched.push_str(&format!(
+                        "<hr class=\"gb-page-break\"><div class=\"gb-page-marker\">Page {} of {}</div>",
+                        i + 1,
+                        total
+                    ));
+                }
+                stitched.push_str(page);
+            }
+            content.content = stitched;
+            content.estimated_read_time_minutes = Self::estimate_read_time(&content.text_content);
+        }
+        content.page_count = total as u32;
+        Ok(content)
+    }
+
+    /// `extract_multipage` with continuation pages fetched over the network
+    /// by `fetch_page_html`; what reader mode and the reading list use.
+    pub fn extract_stitched(&self, html: &str, url: &str, max_pages: usize) -> Result<ReaderContent, ReaderError> {
+        self.extract_multipage(html, url, max_pages, |next| Self::fetch_page_html(next).ok())
+    }
+
+    /// Blocking HTTP(S) fetch of a continuation page for `extract_multipage`.
+    /// The request gets its own thread and runtime, so callers may already
+    /// be running inside Tokio.
+    pub fn fetch_page_html(url: &str) -> Result<String, ReaderError> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(ReaderError::ExtractionFailed(format!("unsupported URL: {}", url)));
+        }
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| Self::fetch_on_own_runtime(url))
+                .join()
+                .unwrap_or_else(|_| Err(ReaderError::ExtractionFailed("page fetch panicked".to_string())))
+        })
+    }
+
+    fn fetch_on_own_runtime(url: &str) -> Result<String, ReaderError> {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| ReaderError::ExtractionFailed(e.to_string()))?;
+        rt.block_on(async {
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(15))
+                .build()
+                .map_err(|e| ReaderError::ExtractionFailed(e.to_string()))?;
+            let resp = client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| ReaderError::ExtractionFailed(e.to_string()))?;
+            if !resp.status().is_success() {
+                return Err(ReaderError::ExtractionFailed(format!("HTTP {}", resp.status())));
+            }
+            if resp.content_length().is_some_and(|len| len as usize > MAX_PAGE_BYTES) {
+                return Err(ReaderError::ExtractionFailed("page too large".to_string()));
+            }
+            let bytes = resp
+                .bytes()
+                .await
+                .map_err(|e| ReaderError::ExtractionFailed(e.to_string()))?;
+            if bytes.len() > MAX_PAGE_BYTES {
+                return Err(ReaderError::ExtractionFailed("page too large".to_string()));
+            }
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        })
+    }
+
+    fn strip_fragment(url: &str) -> &str {
+        url.split('#').next().unwrap_or(url)
+    }
+
+    /// Returns `scheme://host[:port]` of an absolute URL.
+    fn origin_of(url: &str) -> Option<&str> {
+        let scheme_end = url.find("://")? + 3;
+        let host_end = url[scheme_end..]
+            .find(['/', '?', '#'])
+            .map(|i| scheme_end + i)
+            .unwrap_or(url.len());
+        Some(&url[..host_end])
+    }
+
+    fn host_of(url: &str) -> Option<String> {
+        let origin = Self::origin_of(url)?;
+        let host = &origin[origin.find("://")? + 3..];
+        Some(host.to_lowercase())
+    }
+
+    /// Resolves `href` against the page URL `base` (absolute, scheme-relative,
+    /// root-relative, query-only and path-relative forms).
+    fn resolve_url(base: &str, href: &str) -> Option<String> {
+        let href = Self::strip_fragment(href.trim());
+        if href.is_empty() {
+            return None;
+        }
+        let lower = href.to_lowercase();
+        if lower.starts_with("http://") || lower.starts_with("https://") {
+            return Some(href.to_string());
+        }
+        if let Some(colon) = lower.find(':') {
+            // Other schemes (javascript:, mailto:, ...) are never followed
+            if lower.find(['/', '?']).is_none_or(|i| colon < i) {
+                return None;
+            }
+        }
+        let base = Self::strip_fragment(base);
+        let origin = Self::origin_of(base)?;
+        if let Some(rest) = href.strip_prefix("//") {
+            let scheme = &origin[..origin.find("://")?];
+            return Some(format!("{}://{}", scheme, rest));
+        }
+        if href.starts_with('/') {
+            return Some(format!("{}{}", origin, href));
+        }
+        let path_and_query = &base[origin.len()..];
+        let path = path_and_query.split('?').next().unwrap_or("");
+        if href.starts_with('?') {
+            let path = if path.is_empty() { "/" } else { path };
+            return Some(format!("{}{}{}", origin, path, href));
+        }
+        let dir = match path.rfind('/') {
+            Some(i) => &path[..=i],
+            None => "/",
+        };
+        Some(format!("{}{}{}", origin, dir, href))
+    }
+
+    /// Returns the value of attribute `name` in a raw start tag.
+    fn attr_value(tag: &str, name: &str) -> Option<String> {
+        let lower = tag.to_ascii_lowercase();
+        let mut from = 0;
+        while let Some(pos) = lower[from..].find(name) {
+            let start = from + pos;
+            from = start + name.len();
+            let preceded_ok = start > 0 && lower.as_bytes()[start - 1].is_ascii_whitespace();
+            let rest = lower[from..].trim_start();
+            if !preceded_ok || !rest.starts_with('=') {
+                continue;
+            }
+            let value = tag[tag.len() - rest.len() + 1..].trim_start();
+            return match value.chars().next() {
+                Some(q @ ('"' | '\'')) => value[1..].find(q).map(|end| value[1..1 + end].to_string()),
+                _ => Some(value.split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or("").to_string()),
+            };
+        }
+        None
+    }
+
     /// Extracts content between a given tag pair.
     fn extract_between_tags(html: &str, tag: &str) -> Option<String> {
         let open = format!("<{}", tag);
@@ -223,6 +445,7 @@ impl ReaderModeTrait for ReaderMode {
             publish_date: None,
             site_name: None,
             estimated_read_time_minutes: estimated_read_time,
+            page_count: 1,
         })
     }
 
@@ -232,6 +455,12 @@ impl ReaderModeTrait for ReaderMode {
             FontFamily::SansSerif => "-apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif",
             FontFamily::Monospace => "'SF Mono', 'Fira Code', monospace",
         };
+        let (background, text_color, link_color, muted_color) = Self::preset_palette(settings);
+        let text_align = if settings.justify { "justify" } else { "left" };
+        // Clamp persisted values so a hand-edited settings file can't break layout
+        let font_size = settings.font_size.clamp(10, 40);
+        let line_height = if settings.line_height.is_finite() { settings.line_height.clamp(1.0, 3.0) } else { 1.6 };
+        let column_width = settings.column_width.clamp(40, 120);
 
         // SEC-10: HTML-escape the title to prevent XSS
         let safe_title = Self::escape_html(&content.title);
@@ -241,20 +470,68 @@ impl ReaderModeTrait for ReaderMode {
         format!(
             r#"<!DOCTYPE html>
 <html><head><meta charset="utf-8"><style>
-body {{ font-family: {}; font-size: {}px; line-height: {}; background: {}; max-width: {}px; margin: 0 auto; padding: 2em; color: #24292f; }}
+body {{ font-family: {}; font-size: {}px; line-height: {}; background: {}; max-width: min({}px, {}ch); margin: 0 auto; padding: 2em; color: {}; }}
 h1 {{ font-size: 1.8em; margin-bottom: 0.5em; }}
-.meta {{ color: #656d76; margin-bottom: 2em; }}
+a {{ color: {}; }}
+.meta {{ color: {}; margin-bottom: 2em; }}
+.content p {{ text-align: {}; hyphens: auto; }}
+.gb-page-break {{ border: none; border-top: 1px dashed {}; margin: 2em 0 0.5em; }}
+.gb-page-marker {{ color: {}; font-size: 0.8em; margin-bottom: 1em; }}
 </style></head><body>
 <h1>{}</h1>
-<div class="meta">{} min read</div>
+<div class="meta">{} min read{}</div>
 <div class="content">{}</div>
 </body></html>"#,
-            font_family, settings.font_size, settings.line_height,
-            settings.background_color, settings.max_width,
-            safe_title, content.estimated_read_time_minutes, safe_content
+            font_family, font_size, line_height,
+            background, settings.max_width, column_width, text_color,
+            link_color, muted_color, text_align, muted_color, muted_color,
+            safe_title, content.estimated_read_time_minutes,
+            if content.page_count > 1 { format!(" · {} pages", content.page_count) } else { String::new() },
+            safe_content
         )
     }
 
+    fn find_next_page_url(&self, html: &str, url: &str) -> Option<String> {
+        // ASCII lowercasing keeps byte offsets valid for slicing `html`
+        let lower = html.to_ascii_lowercase();
+        let mut fallback: Option<String> = None;
+
+        // Scan <link> and <a> start tags; rel="next" wins over "Next page" link text
+        let mut from = 0;
+        while let Some(pos) = lower[from..].find('<') {
+            let start = from + pos;
+            let end = match lower[start..].find('>') {
+                Some(e) => start + e + 1,
+                None => break,
+            };
+            from = end;
+            let tag_lower = &lower[start..end];
+            let is_link = tag_lower.starts_with("<link ");
+            let is_anchor = tag_lower.starts_with("<a ");
+            if !is_link && !is_anchor {
+                continue;
+            }
+            let tag = &html[start..end];
+            let href = match Self::attr_value(tag, "href") {
+                Some(h) => h,
+                None => continue,
+            };
+            let rel = Self::attr_value(tag, "rel").unwrap_or_default().to_lowercase();
+            if rel.split_whitespace().any(|r| r == "next") {
+                return Self::resolve_url(url, &href);
+            }
+            if is_anchor && fallback.is_none() {
+                let text_end = lower[end..].find("</a>").map(|i| end + i).unwrap_or(end);
+                let text = Self::strip_tags(&html[end..text_end]).to_lowercase();
+                let text = text.trim().trim_end_matches(|c: char| matches!(c, '»' | '›' | '→' | '>') || c.is_whitespace());
+                if matches!(text, "next" | "next page" | "следующая" | "следующая страница" | "далее") {
+                    fallback = Self::resolve_url(url, &href);
+                }
+            }
+        }
+        fallback
+    }
+
     fn update_settings(&mut self, settings: ReaderSettings) {
         self.settings = settings;
     }
@@ -0,0 +1,230 @@
+//! Search Engine Registry for GitBrowser.
+//!
+//! Implements `SearchEngineRegistryTrait` over the user's `SearchSettings`:
+//! add/edit/remove engines with `%s` URL templates, keyword triggers, a
+//! default selection, and suggestion URLs. The registry works on a copy of
+//! the settings; callers persist `settings()` back through `SettingsEngine`.
+
+use crate::platform;
+use crate::types::errors::SearchEngineError;
+use crate::types::search::{SearchEngine, SearchSettings};
+
+/// Placeholder replaced by the encoded query in URL templates.
+pub const QUERY_PLACEHOLDER: &str = "%s";
+
+/// Trait defining search engine registry operations.
+pub trait SearchEngineRegistryTrait {
+    fn list_engines(&self) -> &[SearchEngine];
+    fn get_engine(&self, id: &str) -> Option<&SearchEngine>;
+    /// The default engine, falling back to the first one if the stored ID is stale.
+    fn default_engine(&self) -> Option<&SearchEngine>;
+    fn add_engine(&mut self, engine: SearchEngine) -> Result<(), SearchEngineError>;
+    /// Replaces the engine with the same ID.
+    fn update_engine(&mut self, engine: SearchEngine) -> Result<(), SearchEngineError>;
+    fn remove_engine(&mut self, id: &str) -> Result<(), SearchEngineError>;
+    fn set_default(&mut self, id: &str) -> Result<(), SearchEngineError>;
+    /// Search URL for address bar input. `<keyword> <query>` uses the engine
+    /// with that keyword; anything else goes to the default engine.
+    fn search_url(&self, input: &str) -> Option<String>;
+    /// Suggestion URL for a query on the default engine, if it has one.
+    fn suggestion_url(&self, query: &str) -> Option<String>;
+}
+
+/// Search engine registry over a copy of `SearchSettings`.
+pub struct SearchEngineRegistry {
+    settings: SearchSettings,
+}
+
+impl SearchEngineRegistry {
+    pub fn new(settings: SearchSettings) -> Self {
+        Self { settings }
+    }
+
+    /// The current registry state, for persisting under `search`.
+    pub fn settings(&self) -> &SearchSettings {
+        &self.settings
+    }
+
+    /// Substitutes the form-encoded query into a `%s` template.
+    pub fn expand_template(template: &str, query: &str) -> String {
+        template.replace(QUERY_PLACEHOLDER, &encode_query(query))
+    }
+
+    fn validate_template(template: &str) -> Result<(), SearchEngineError> {
+        let lower = template.to_ascii_lowercase();
+        if !(lower.starts_with("https://") || lower.starts_with("http://")) {
+            return Err(SearchEngineError::InvalidTemplate(format!(
+                "'{}' must be an http(s) URL",
+                template
+            )));
+        }
+        if !template.contains(QUERY_PLACEHOLDER) {
+            return Err(SearchEngineError::InvalidTemplate(format!(
+                "'{}' has no {} placeholder",
+                template, QUERY_PLACEHOLDER
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks an engine's fields and that its keyword is not used by another engine.
+    fn validate(&self, engine: &SearchEngine) -> Result<(), SearchEngineError> {
+        let id_ok = !engine.id.is_empty()
+            && engine
+                .id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !id_ok {
+            return Err(SearchEngineError::InvalidId(engine.id.clone()));
+        }
+        if engine.name.trim().is_empty() {
+            return Err(SearchEngineError::InvalidName(engine.id.clone()));
+        }
+        Self::validate_template(&engine.url_template)?;
+        if let Some(suggest) = &engine.suggest_url {
+            Self::validate_template(suggest)?;
+        }
+        if let Some(keyword) = &engine.keyword {
+            if keyword.is_empty() || keyword.chars().any(char::is_whitespace) {
+                return Err(SearchEngineError::InvalidKeyword(keyword.clone()));
+            }
+            let taken = self.settings.engines.iter().any(|e| {
+                e.id != engine.id
+                    && e.keyword.as_deref().is_some_and(|k| k.eq_ignore_ascii_case(keyword))
+            });
+            if taken {
+                return Err(SearchEngineError::InvalidKeyword(format!(
+                    "{} is already in use",
+                    keyword
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn position(&self, id: &str) -> Result<usize, SearchEngineError> {
+        self.settings
+            .engines
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| SearchEngineError::NotFound(id.to_string()))
+    }
+}
+
+/// The page to open for address bar (or command-line) input: a URL or
+/// internal page as given, the page of a `gitbrowser://` link, a bare host over
+/// https, anything else searched for with the default engine.
+pub fn url_for_input(input: &str, search: &SearchSettings) -> String {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return "about:newtab".to_string();
+    }
+    let internal = trimmed.strip_prefix("about:").is_some_and(|page| platform::INTERNAL_PAGES.contains(&page));
+    if internal || trimmed == "about:blank" {
+        return trimmed.to_string();
+    }
+    if let Some(page) = platform::deep_link_target(trimmed) {
+        return page;
+    }
+    if trimmed.split_once("://").is_some_and(|(scheme, _)| scheme.eq_ignore_ascii_case(platform::URL_SCHEME)) {
+        // Unknown links into the browser are not searched for
+        return "about:newtab".to_string();
+    }
+    if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+        return trimmed.to_string();
+    }
+    if trimmed.contains('.') && !trimmed.contains(' ') {
+        return format!("https://{}", trimmed);
+    }
+    SearchEngineRegistry::new(search.clone())
+        .search_url(trimmed)
+        .unwrap_or_else(|| "about:newtab".to_string())
+}
+
+/// Form-encodes a query string component (spaces become `+`).
+pub fn encode_query(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() * 3);
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char);
+            }
+            b' ' => out.push('+'),
+            _ => {
+                out.push('%');
+                out.push(char::from(b"0123456789ABCDEF"[(b >> 4) as usize]));
+                out.push(char::from(b"0123456789ABCDEF"[(b & 0xf) as usize]));
+            }
+        }
+    }
+    out
+}
+
+impl SearchEngineRegistryTrait for SearchEngineRegistry {
+    fn list_engines(&self) -> &[SearchEngine] {
+        &self.settings.engines
+    }
+
+    fn get_engine(&self, id: &str) -> Option<&SearchEngine> {
+        self.settings.engines.iter().find(|e| e.id == id)
+    }
+
+    fn default_engine(&self) -> Option<&SearchEngine> {
+        self.get_engine(&self.settings.default_engine)
+            .or_else(|| self.settings.engines.first())
+    }
+
+    fn add_engine(&mut self, engine: SearchEngine) -> Result<(), SearchEngineError> {
+        if self.get_engine(&engine.id).is_some() {
+            return Err(SearchEngineError::AlreadyExists(engine.id));
+        }
+        self.validate(&engine)?;
+        self.settings.engines.push(engine);
+        Ok(())
+    }
+
+    fn update_engine(&mut self, engine: SearchEngine) -> Result<(), SearchEngineError> {
+        let idx = self.position(&engine.id)?;
+        self.validate(&engine)?;
+        self.settings.engines[idx] = engine;
+        Ok(())
+    }
+
+    fn remove_engine(&mut self, id: &str) -> Result<(), SearchEngineError> {
+        let idx = self.position(id)?;
+        if self.settings.engines.len() == 1 {
+            return Err(SearchEngineError::LastEngine);
+        }
+        self.settings.engines.remove(idx);
+        if self.settings.default_engine == id {
+            self.settings.default_engine = self.settings.engines[0].id.clone();
+        }
+        Ok(())
+    }
+
+    fn set_default(&mut self, id: &str) -> Result<(), SearchEngineError> {
+        self.position(id)?;
+        self.settings.default_engine = id.to_string();
+        Ok(())
+    }
+
+    fn search_url(&self, input: &str) -> Option<String> {
+        let input = input.trim();
+        if let Some((trigger, rest)) = input.split_once(char::is_whitespace) {
+            let rest = rest.trim();
+            let keyed = self.settings.engines.iter().find(|e| {
+                e.keyword.as_deref().is_some_and(|k| k.eq_ignore_ascii_case(trigger))
+            });
+            if let (Some(engine), false) = (keyed, rest.is_empty()) {
+                return Some(Self::expand_template(&engine.url_template, rest));
+            }
+        }
+        self.default_engine()
+            .map(|e| Self::expand_template(&e.url_template, input))
+    }
+
+    fn suggestion_url(&self, query: &str) -> Option<String> {
+        let template = self.default_engine()?.suggest_url.as_deref()?;
+        Some(Self::expand_template(template, query.trim()))
+    }
+}
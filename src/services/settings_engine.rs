@@ -1,13 +1,30 @@
 // GitBrowser Settings Engine
 // Manages user settings: loading, saving, updating individual values, and resetting to defaults.
 // Settings are stored as a JSON file at the platform-specific config path.
+// Writes go through a temp file and rename, and the previous good file is
+// kept as `settings.json.bak` for recovery if the main file gets corrupted.
+// Named profiles live under `profiles/` next to it; `profiles.json` records
+// the profile list and which one is active. The "Default" profile is the
+// original settings file.
 
 use std::fs;
 use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::platform;
+use crate::services::settings_migrations::{self, SETTINGS_SCHEMA_VERSION, VERSION_KEY};
 use crate::types::errors::SettingsError;
-use crate::types::settings::BrowserSettings;
+use crate::types::ai::AIProviderName;
+use crate::types::reader::{FontFamily, ReaderPreset};
+use crate::types::settings::{
+    BrowserSettings, DatabaseEncryption, SettingSchema, SettingsChange, StartupBehavior, ThemeMode,
+};
+use crate::types::sync::ConflictStrategy;
+use crate::types::theme::ScheduleMode;
+use crate::types::update::UpdateChannel;
 
 /// Trait defining the settings engine interface.
 pub trait SettingsEngineTrait {
@@ -17,12 +34,128 @@ pub trait SettingsEngineTrait {
     fn set_value(&mut self, key: &str, value: serde_json::Value) -> Result<(), SettingsError>;
     fn reset(&mut self) -> Result<(), SettingsError>;
     fn get_config_path(&self) -> &str;
+    /// Set when the last `load` found the settings file unreadable and
+    /// restored it from the backup; describes what happened.
+    fn recovery_notice(&self) -> Option<&str>;
+    /// Registers a subscriber. Every leaf setting that changes afterwards is
+    /// sent on the returned channel; dropping the receiver unsubscribes.
+    fn subscribe(&mut self) -> Receiver<SettingsChange>;
+    /// Profile names, with "Default" first.
+    fn list_profiles(&self) -> Vec<String>;
+    fn active_profile(&self) -> &str;
+    /// Creates a profile from a copy of `clone_from`, or from defaults.
+    fn create_profile(&mut self, name: &str, clone_from: Option<&str>) -> Result<(), SettingsError>;
+    /// Makes `name` the active profile and loads its settings.
+    fn switch_profile(&mut self, name: &str) -> Result<BrowserSettings, SettingsError>;
+    /// Deletes an inactive, non-default profile and its settings file.
+    fn delete_profile(&mut self, name: &str) -> Result<(), SettingsError>;
+    /// Writes the current settings to `path` with sensitive values removed.
+    /// Returns the keys that were stripped.
+    fn export_to(&self, path: &str) -> Result<Vec<String>, SettingsError>;
+    /// Validates the settings file at `path` and lists what importing it would change.
+    fn preview_import(&self, path: &str) -> Result<Vec<SettingsChange>, SettingsError>;
+    /// Imports the settings file at `path`, keeping the current values of
+    /// sensitive keys. Returns the applied changes.
+    fn import_from(&mut self, path: &str) -> Result<Vec<SettingsChange>, SettingsError>;
+    /// The settings document carried by sync: sensitive values and shortcuts
+    /// (synced on their own) are left out.
+    fn sync_snapshot(&self) -> Result<Value, SettingsError>;
+    /// Applies a document from `sync_snapshot` on another device, keeping the
+    /// local shortcuts and sensitive values. Returns the applied changes.
+    fn apply_synced(&mut self, snapshot: Value) -> Result<Vec<SettingsChange>, SettingsError>;
+}
+
+/// Key-name fragments that mark a setting as sensitive; such values are
+/// never written by `export_to` nor taken from an imported file.
+const SENSITIVE_KEY_MARKERS: &[&str] = &[
+    "api_key", "apikey", "access_key", "private_key", "token", "secret", "password", "credential",
+];
+
+/// Largest settings file accepted by `import_from`, in bytes.
+const MAX_IMPORT_BYTES: u64 = 1024 * 1024;
+
+fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEY_MARKERS.iter().any(|m| key.contains(m))
+}
+
+/// Removes sensitive keys from `value` in place, recording their dot paths.
+fn strip_sensitive(prefix: &str, value: &mut Value, stripped: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            let path_of = |k: &str| if prefix.is_empty() { k.to_string() } else { format!("{}.{}", prefix, k) };
+            map.retain(|k, _| {
+                let keep = !is_sensitive_key(k);
+                if !keep {
+                    stripped.push(path_of(k));
+                }
+                keep
+            });
+            for (k, v) in map.iter_mut() {
+                strip_sensitive(&path_of(k), v, stripped);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter_mut().enumerate() {
+                strip_sensitive(&format!("{}[{}]", prefix, i), v, stripped);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Copies sensitive values from `current` into `imported` so an import never
+/// clears or replaces them. Arrays are matched by position.
+fn restore_sensitive(current: &Value, imported: &mut Value) {
+    match (current, imported) {
+        (Value::Object(cur), Value::Object(imp)) => {
+            for (k, v) in cur {
+                if is_sensitive_key(k) {
+                    imp.insert(k.clone(), v.clone());
+                } else if let Some(target) = imp.get_mut(k) {
+                    restore_sensitive(v, target);
+                }
+            }
+        }
+        (Value::Array(cur), Value::Array(imp)) => {
+            for (c, i) in cur.iter().zip(imp.iter_mut()) {
+                restore_sensitive(c, i);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Name of the built-in profile backed by the original settings file.
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// On-disk record of settings profiles (`profiles.json`).
+#[derive(Debug, Serialize, Deserialize)]
+struct ProfileIndex {
+    active: String,
+    profiles: Vec<String>,
+}
+
+impl Default for ProfileIndex {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            profiles: vec![DEFAULT_PROFILE.to_string()],
+        }
+    }
 }
 
 /// Settings engine implementation that persists settings as JSON on disk.
 pub struct SettingsEngine {
     config_path: String,
+    /// Settings file of the "Default" profile; other profiles live beside it.
+    base_path: String,
+    active_profile: String,
     settings: BrowserSettings,
+    subscribers: Vec<Sender<SettingsChange>>,
+    recovery_notice: Option<String>,
+    /// False for a private profile: changes stay in memory.
+    persist: bool,
 }
 
 impl SettingsEngine {
@@ -43,57 +176,482 @@ impl SettingsEngine {
         };
 
         Self {
+            base_path: config_path.clone(),
             config_path,
+            active_profile: DEFAULT_PROFILE.to_string(),
             settings: BrowserSettings::default(),
+            subscribers: Vec::new(),
+            recovery_notice: None,
+            persist: true,
         }
     }
-}
 
-impl SettingsEngineTrait for SettingsEngine {
-    /// Loads settings from the JSON config file.
-    ///
-    /// If the file does not exist, returns default settings.
-    /// If the file exists but is malformed, returns a serialization error.
-    fn load(&mut self) -> Result<BrowserSettings, SettingsError> {
-        let path = Path::new(&self.config_path);
+    /// Keeps changes in memory instead of saving them, for private profiles.
+    /// The settings file is still read.
+    pub fn in_memory(mut self) -> Self {
+        self.persist = false;
+        self
+    }
 
-        if !path.exists() {
-            self.settings = BrowserSettings::default();
-            return Ok(self.settings.clone());
+    /// Path of the last-known-good copy of a settings file.
+    pub fn backup_path(path: &str) -> String {
+        format!("{}.bak", path)
+    }
+
+    /// Writes `contents` to `path` atomically: the data goes to a sibling temp
+    /// file which is synced and renamed over the target, so a crash never
+    /// leaves a half-written file. With `keep_backup`, the current file is
+    /// first copied to `<path>.bak` if it still parses.
+    fn write_atomic(path: &Path, contents: &str, keep_backup: bool) -> Result<(), SettingsError> {
+        use std::io::Write;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                SettingsError::IoError(format!("Failed to create config directory: {}", e))
+            })?;
+        }
+        let tmp_path = path.with_file_name(format!(
+            "{}.tmp",
+            path.file_name().and_then(|n| n.to_str()).unwrap_or("settings.json")
+        ));
+        {
+            let mut file = fs::File::create(&tmp_path).map_err(|e| {
+                SettingsError::IoError(format!("Failed to create temp file: {}", e))
+            })?;
+            file.write_all(contents.as_bytes())
+                .and_then(|_| file.sync_all())
+                .map_err(|e| SettingsError::IoError(format!("Failed to write temp file: {}", e)))?;
         }
 
-        let content = fs::read_to_string(path)
-            .map_err(|e| SettingsError::IoError(format!("Failed to read config file: {}", e)))?;
+        if keep_backup {
+            let current_is_good = fs::read_to_string(path)
+                .ok()
+                .is_some_and(|s| serde_json::from_str::<Value>(&s).is_ok());
+            if current_is_good {
+                let backup = Self::backup_path(&path.to_string_lossy());
+                fs::copy(path, backup).map_err(|e| {
+                    SettingsError::IoError(format!("Failed to back up config file: {}", e))
+                })?;
+            }
+        }
 
-        let settings: BrowserSettings = serde_json::from_str(&content).map_err(|e| {
-            SettingsError::SerializationError(format!("Failed to parse config file: {}", e))
+        fs::rename(&tmp_path, path).map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            SettingsError::IoError(format!("Failed to replace config file: {}", e))
+        })
+    }
+
+    fn base_dir(&self) -> &Path {
+        Path::new(&self.base_path).parent().unwrap_or(Path::new("."))
+    }
+
+    fn index_path(&self) -> std::path::PathBuf {
+        self.base_dir().join("profiles.json")
+    }
+
+    /// File name used for a profile; also the key for uniqueness checks.
+    fn profile_slug(name: &str) -> String {
+        name.trim().to_ascii_lowercase().replace(' ', "_")
+    }
+
+    fn profile_path(&self, name: &str) -> String {
+        if Self::profile_slug(name) == Self::profile_slug(DEFAULT_PROFILE) {
+            return self.base_path.clone();
+        }
+        self.base_dir()
+            .join("profiles")
+            .join(format!("{}.json", Self::profile_slug(name)))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn validate_profile_name(name: &str) -> Result<(), SettingsError> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > 64 {
+            return Err(SettingsError::InvalidValue(
+                "Profile name must be 1-64 characters".to_string(),
+            ));
+        }
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_') {
+            return Err(SettingsError::InvalidValue(format!(
+                "Profile name '{}' may only contain letters, digits, spaces, '-' and '_'",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reads `profiles.json`, falling back to a Default-only index.
+    fn read_index(&self) -> ProfileIndex {
+        let mut index: ProfileIndex = fs::read_to_string(self.index_path())
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        if !index.profiles.iter().any(|p| p == DEFAULT_PROFILE) {
+            index.profiles.insert(0, DEFAULT_PROFILE.to_string());
+        }
+        if !index.profiles.contains(&index.active) {
+            index.active = DEFAULT_PROFILE.to_string();
+        }
+        index
+    }
+
+    fn write_index(&self, index: &ProfileIndex) -> Result<(), SettingsError> {
+        let json = serde_json::to_string_pretty(index).map_err(|e| {
+            SettingsError::SerializationError(format!("Failed to serialize profiles: {}", e))
         })?;
+        Self::write_atomic(&self.index_path(), &json, false)
+    }
 
-        self.settings = settings;
-        Ok(self.settings.clone())
+    /// Looks up a profile by name (case-insensitive), returning its stored spelling.
+    fn find_profile(index: &ProfileIndex, name: &str) -> Option<String> {
+        let slug = Self::profile_slug(name);
+        index.profiles.iter().find(|p| Self::profile_slug(p) == slug).cloned()
     }
 
-    /// Saves the current settings to the JSON config file.
+    /// Reads a profile's settings file; a missing file means defaults.
     ///
-    /// Creates parent directories if they don't exist.
-    fn save(&self) -> Result<(), SettingsError> {
-        let path = Path::new(&self.config_path);
+    /// Older files are upgraded through `settings_migrations` and written
+    /// back so the migration only runs once.
+    fn read_settings_file(path: &str) -> Result<BrowserSettings, SettingsError> {
+        let path = Path::new(path);
+        if !path.exists() {
+            return Ok(BrowserSettings::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| SettingsError::IoError(format!("Failed to read config file: {}", e)))?;
+        let mut doc: Value = serde_json::from_str(&content).map_err(|e| {
+            SettingsError::SerializationError(format!("Failed to parse config file: {}", e))
+        })?;
 
-        // Ensure parent directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|e| {
-                SettingsError::IoError(format!("Failed to create config directory: {}", e))
+        let from_version = settings_migrations::migrate(&mut doc);
+        let migrated = from_version < SETTINGS_SCHEMA_VERSION;
+        if migrated {
+            let json = serde_json::to_string_pretty(&doc).map_err(|e| {
+                SettingsError::SerializationError(format!("Failed to serialize settings: {}", e))
             })?;
+            // The pre-migration file becomes the backup
+            Self::write_atomic(path, &json, true)?;
+        }
+
+        if let Some(map) = doc.as_object_mut() {
+            map.remove(VERSION_KEY);
         }
+        serde_json::from_value(doc).map_err(|e| {
+            SettingsError::SerializationError(format!(
+                "Failed to parse config file (schema version {}): {}",
+                from_version, e
+            ))
+        })
+    }
 
-        let json = serde_json::to_string_pretty(&self.settings).map_err(|e| {
+    /// Reads and validates a settings file for import. Sensitive keys in the
+    /// file are ignored in favor of the current values.
+    fn read_import(&self, path: &str) -> Result<BrowserSettings, SettingsError> {
+        let meta = fs::metadata(path)
+            .map_err(|e| SettingsError::IoError(format!("Failed to read import file: {}", e)))?;
+        if meta.len() > MAX_IMPORT_BYTES {
+            return Err(SettingsError::InvalidValue(format!(
+                "Import file is larger than {} bytes",
+                MAX_IMPORT_BYTES
+            )));
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| SettingsError::IoError(format!("Failed to read import file: {}", e)))?;
+        let doc: Value = serde_json::from_str(&content).map_err(|e| {
+            SettingsError::SerializationError(format!("Failed to parse import file: {}", e))
+        })?;
+        if !doc.is_object() {
+            return Err(SettingsError::InvalidValue(
+                "Import file must contain a settings object".to_string(),
+            ));
+        }
+        self.adopt_document(doc, "Import file")
+    }
+
+    /// Turns a settings document from elsewhere into settings: its sensitive
+    /// keys are replaced by the current values and older layouts are migrated.
+    /// `source` names the document in errors.
+    fn adopt_document(&self, mut doc: Value, source: &str) -> Result<BrowserSettings, SettingsError> {
+        strip_sensitive("", &mut doc, &mut Vec::new());
+        settings_migrations::migrate(&mut doc);
+        let current = serde_json::to_value(&self.settings).map_err(|e| {
             SettingsError::SerializationError(format!("Failed to serialize settings: {}", e))
         })?;
+        restore_sensitive(&current, &mut doc);
+        if let Some(map) = doc.as_object_mut() {
+            map.remove(VERSION_KEY);
+        }
+        serde_json::from_value(doc).map_err(|e| {
+            SettingsError::InvalidValue(format!("{} is not valid settings: {}", source, e))
+        })
+    }
 
-        fs::write(path, json)
-            .map_err(|e| SettingsError::IoError(format!("Failed to write config file: {}", e)))?;
+    /// Serializes settings as a pretty-printed, versioned settings document.
+    fn serialize_settings(settings: &BrowserSettings) -> Result<String, SettingsError> {
+        settings_migrations::to_document(settings)
+            .and_then(|doc| serde_json::to_string_pretty(&doc))
+            .map_err(|e| {
+                SettingsError::SerializationError(format!("Failed to serialize settings: {}", e))
+            })
+    }
 
-        Ok(())
+    /// Replaces the in-memory settings and notifies subscribers of every
+    /// leaf value that differs. Disconnected subscribers are dropped.
+    fn replace_settings(&mut self, settings: BrowserSettings) {
+        let old = std::mem::replace(&mut self.settings, settings);
+        if self.subscribers.is_empty() {
+            return;
+        }
+        for change in diff_settings(&old, &self.settings) {
+            self.subscribers.retain(|tx| tx.send(change.clone()).is_ok());
+        }
+    }
+}
+
+/// Lists every leaf setting that differs between `old` and `new`, keyed by
+/// dot-notation path (e.g. `privacy.tracker_blocking`, `shortcuts.new_tab`).
+pub fn diff_settings(old: &BrowserSettings, new: &BrowserSettings) -> Vec<SettingsChange> {
+    let old_value = serde_json::to_value(old).unwrap_or(Value::Null);
+    let new_value = serde_json::to_value(new).unwrap_or(Value::Null);
+    let mut changes = Vec::new();
+    diff_values("", &old_value, &new_value, &mut changes);
+    changes
+}
+
+fn diff_values(prefix: &str, old: &Value, new: &Value, out: &mut Vec<SettingsChange>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for k in keys {
+                let path = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+                diff_values(
+                    &path,
+                    a.get(k).unwrap_or(&Value::Null),
+                    b.get(k).unwrap_or(&Value::Null),
+                    out,
+                );
+            }
+        }
+        _ => out.push(SettingsChange {
+            key: prefix.to_string(),
+            old_value: old.clone(),
+            new_value: new.clone(),
+        }),
+    }
+}
+
+/// Human-readable descriptions for every non-shortcut key.
+const SETTING_DESCRIPTIONS: &[(&str, &str)] = &[
+    ("general.language", "Interface language as a locale tag (e.g. en, ru, pt-BR)"),
+    ("general.startup_behavior", "What to open when the browser starts"),
+    ("general.homepage", "Page opened by the home button and on Homepage startup"),
+    ("general.user_agent", "User-Agent string pages see, unless overridden for the site; empty for the default"),
+    ("general.allowed_protocols", "Link schemes such as mailto or magnet opened in their app without asking"),
+    ("privacy.tracker_blocking", "Block requests to known tracking domains"),
+    ("privacy.ad_blocking", "Block requests matching known ad URL patterns"),
+    ("privacy.https_enforcement", "Upgrade http:// navigations to https://"),
+    ("privacy.dns_over_https", "Resolve hostnames over DNS-over-HTTPS"),
+    ("privacy.dns_provider", "DNS-over-HTTPS resolver endpoint"),
+    ("privacy.anti_fingerprinting", "Reduce browser fingerprinting surface"),
+    ("privacy.clear_data_on_exit", "Clear browsing data when the browser closes"),
+    ("privacy.telemetry_consent", "Allow sending anonymous crash reports"),
+    ("privacy.crash_report_urls", "Include page URLs (without query strings) in crash reports"),
+    ("privacy.crash_report_endpoint", "HTTPS endpoint crash reports are sent to; empty drafts a GitHub issue"),
+    ("privacy.database_encryption", "Encrypt the database at rest: off, keystore or master_password (applied on restart)"),
+    ("privacy.custom_filters", "Extra URL filters to block, e.g. ||ads.example^ or |https://*.example/track"),
+    ("privacy.clipboard_clear_seconds", "Seconds before a copied password is cleared from the clipboard (0 never)"),
+    ("appearance.theme", "Color theme; System follows the OS setting"),
+    ("appearance.accent_color", "Accent color as a hex value, or \"system\" to follow the OS"),
+    ("appearance.font_size", "Interface font size in pixels"),
+    ("appearance.show_telegram", "Show the Telegram button in the toolbar"),
+    ("appearance.show_github", "Show the GitHub button in the toolbar"),
+    ("appearance.custom_theme", "ID of an installed custom theme (null for none)"),
+    ("appearance.force_dark", "Render bright sites dark unless disabled for the site"),
+    ("theme_schedule.mode", "Automatic light/dark switching: off, fixed times, or sunrise/sunset"),
+    ("theme_schedule.light_start", "Local time (HH:MM) light mode starts"),
+    ("theme_schedule.dark_start", "Local time (HH:MM) dark mode starts"),
+    ("theme_schedule.latitude", "Latitude for sunrise/sunset switching (degrees north)"),
+    ("theme_schedule.longitude", "Longitude for sunrise/sunset switching (degrees east)"),
+    ("ai.active_provider", "AI provider used by the assistant"),
+    ("ai.active_model", "Model name requested from the AI provider"),
+    ("performance.tab_suspend_timeout_minutes", "Minutes before idle background tabs are suspended (0 disables)"),
+    ("performance.lazy_load_images", "Defer loading offscreen images"),
+    ("performance.battery_saver", "Slow background work while on battery or in the OS power saver"),
+    ("reader.font_size", "Reader mode font size in pixels"),
+    ("reader.font_family", "Reader mode font family"),
+    ("reader.background_color", "Page background for the Default reader preset"),
+    ("reader.line_height", "Reader mode line height multiplier"),
+    ("reader.max_width", "Maximum reader column width in pixels"),
+    ("reader.preset", "Reader mode color preset"),
+    ("reader.column_width", "Preferred reader column width in characters"),
+    ("reader.justify", "Justify reader paragraph text"),
+    ("search.engines", "Configured search engines with %s URL templates and keywords"),
+    ("search.default_engine", "ID of the search engine used for address bar queries"),
+    ("github.use_enterprise", "Use the GitHub Enterprise Server below instead of github.com"),
+    ("github.enterprise_web_url", "Web address of the GitHub Enterprise Server (e.g. https://ghe.example.com)"),
+    ("github.enterprise_api_url", "REST API root of the enterprise server (null for <web url>/api/v3)"),
+    ("github.enterprise_client_id", "OAuth app client ID registered on the enterprise server"),
+    ("github.sync_conflict_strategy", "How sync settles data changed on two devices: merge, last_writer_wins, local_wins or remote_wins"),
+    ("updates.channel", "Release channel updates come from: stable, beta or nightly"),
+    ("updates.auto_check", "Check for updates in the background"),
+];
+
+/// Serializes each variant so allowed values always match what `set_value` accepts.
+fn variants<T: serde::Serialize>(all: &[T]) -> Vec<Value> {
+    all.iter().filter_map(|v| serde_json::to_value(v).ok()).collect()
+}
+
+/// Allowed values for enum-typed keys.
+fn enum_values(key: &str) -> Option<Vec<Value>> {
+    match key {
+        "general.startup_behavior" => Some(variants(&[
+            StartupBehavior::Restore,
+            StartupBehavior::NewTab,
+            StartupBehavior::Homepage,
+        ])),
+        "appearance.theme" => Some(variants(&[ThemeMode::Dark, ThemeMode::Light, ThemeMode::System])),
+        "ai.active_provider" => Some(variants(&[
+            AIProviderName::OpenRouter,
+            AIProviderName::OpenAI,
+            AIProviderName::Anthropic,
+            AIProviderName::DeepSeek,
+        ])),
+        "theme_schedule.mode" => Some(variants(&[
+            ScheduleMode::Off,
+            ScheduleMode::FixedTimes,
+            ScheduleMode::SunriseSunset,
+        ])),
+        "reader.font_family" => Some(variants(&[FontFamily::Serif, FontFamily::SansSerif, FontFamily::Monospace])),
+        "reader.preset" => Some(variants(&[
+            ReaderPreset::Default,
+            ReaderPreset::Sepia,
+            ReaderPreset::Dark,
+            ReaderPreset::HighContrast,
+        ])),
+        "github.sync_conflict_strategy" => Some(variants(&[
+            ConflictStrategy::Merge,
+            ConflictStrategy::LastWriterWins,
+            ConflictStrategy::LocalWins,
+            ConflictStrategy::RemoteWins,
+        ])),
+        "updates.channel" => Some(variants(&[UpdateChannel::Stable, UpdateChannel::Beta, UpdateChannel::Nightly])),
+        "privacy.database_encryption" => Some(variants(&[
+            DatabaseEncryption::Off,
+            DatabaseEncryption::Keystore,
+            DatabaseEncryption::MasterPassword,
+        ])),
+        _ => None,
+    }
+}
+
+/// Describes every settable key, derived from the serialized default
+/// `BrowserSettings` so new fields show up without extra bookkeeping.
+/// Entries are sorted by key.
+pub fn settings_schema() -> Vec<SettingSchema> {
+    let defaults = serde_json::to_value(BrowserSettings::default()).unwrap_or(Value::Null);
+    let mut leaves = Vec::new();
+    collect_leaves("", &defaults, &mut leaves);
+
+    let mut schema: Vec<SettingSchema> = leaves
+        .into_iter()
+        .map(|(key, default)| {
+            let allowed_values = enum_values(&key);
+            let value_type = if allowed_values.is_some() {
+                "enum"
+            } else {
+                match &default {
+                    Value::Bool(_) => "boolean",
+                    Value::Number(n) if n.is_f64() => "number",
+                    Value::Number(_) => "integer",
+                    Value::Array(_) => "array",
+                    _ => "string",
+                }
+            };
+            let description = match key.strip_prefix("shortcuts.") {
+                Some(action) => format!("Keyboard shortcut for {}", action.replace('_', " ")),
+                None => SETTING_DESCRIPTIONS
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, d)| d.to_string())
+                    .unwrap_or_default(),
+            };
+            SettingSchema {
+                nullable: default.is_null(),
+                value_type: value_type.to_string(),
+                key,
+                default,
+                allowed_values,
+                description,
+            }
+        })
+        .collect();
+    schema.sort_by(|a, b| a.key.cmp(&b.key));
+    schema
+}
+
+fn collect_leaves(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                let path = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+                collect_leaves(&path, v, out);
+            }
+        }
+        _ => out.push((prefix.to_string(), value.clone())),
+    }
+}
+
+impl SettingsEngineTrait for SettingsEngine {
+    /// Loads settings from the JSON config file.
+    ///
+    /// If the file does not exist, returns default settings.
+    /// If the file exists but is malformed, returns a serialization error.
+    fn load(&mut self) -> Result<BrowserSettings, SettingsError> {
+        let index = self.read_index();
+        self.config_path = self.profile_path(&index.active);
+        self.active_profile = index.active;
+        self.recovery_notice = None;
+
+        let settings = match Self::read_settings_file(&self.config_path) {
+            Ok(settings) => settings,
+            Err(SettingsError::SerializationError(err)) => {
+                let backup = Self::backup_path(&self.config_path);
+                let recovered = Path::new(&backup)
+                    .exists()
+                    .then(|| Self::read_settings_file(&backup).ok())
+                    .flatten()
+                    .ok_or_else(|| SettingsError::SerializationError(err.clone()))?;
+                self.recovery_notice = Some(format!(
+                    "{} could not be read ({}); restored the last good copy from {}",
+                    self.config_path, err, backup
+                ));
+                self.replace_settings(recovered);
+                self.save()?;
+                return Ok(self.settings.clone());
+            }
+            Err(e) => return Err(e),
+        };
+        self.replace_settings(settings);
+        Ok(self.settings.clone())
+    }
+
+    /// Saves the current settings to the JSON config file.
+    ///
+    /// Creates parent directories if they don't exist. The write is atomic
+    /// and the previous file is kept as `settings.json.bak`. An `in_memory`
+    /// engine writes nothing.
+    fn save(&self) -> Result<(), SettingsError> {
+        if !self.persist {
+            return Ok(());
+        }
+        let json = Self::serialize_settings(&self.settings)?;
+        Self::write_atomic(Path::new(&self.config_path), &json, true)
     }
 
     /// Returns a reference to the current in-memory settings.
@@ -175,7 +733,7 @@ impl SettingsEngineTrait for SettingsEngine {
                 ))
             })?;
 
-        self.settings = new_settings;
+        self.replace_settings(new_settings);
 
         // Persist to disk
         self.save()?;
@@ -185,7 +743,7 @@ impl SettingsEngineTrait for SettingsEngine {
 
     /// Resets all settings to factory defaults and saves to disk.
     fn reset(&mut self) -> Result<(), SettingsError> {
-        self.settings = BrowserSettings::default();
+        self.replace_settings(BrowserSettings::default());
         self.save()?;
         Ok(())
     }
@@ -194,6 +752,149 @@ impl SettingsEngineTrait for SettingsEngine {
     fn get_config_path(&self) -> &str {
         &self.config_path
     }
+
+    fn recovery_notice(&self) -> Option<&str> {
+        self.recovery_notice.as_deref()
+    }
+
+    fn subscribe(&mut self) -> Receiver<SettingsChange> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    fn list_profiles(&self) -> Vec<String> {
+        self.read_index().profiles
+    }
+
+    fn active_profile(&self) -> &str {
+        &self.active_profile
+    }
+
+    fn create_profile(&mut self, name: &str, clone_from: Option<&str>) -> Result<(), SettingsError> {
+        Self::validate_profile_name(name)?;
+        let name = name.trim();
+        let mut index = self.read_index();
+        if Self::find_profile(&index, name).is_some() {
+            return Err(SettingsError::ProfileExists(name.to_string()));
+        }
+
+        let settings = match clone_from {
+            Some(source) => {
+                let source = Self::find_profile(&index, source)
+                    .ok_or_else(|| SettingsError::ProfileNotFound(source.to_string()))?;
+                if source == self.active_profile {
+                    self.settings.clone()
+                } else {
+                    Self::read_settings_file(&self.profile_path(&source))?
+                }
+            }
+            None => BrowserSettings::default(),
+        };
+
+        let json = Self::serialize_settings(&settings)?;
+        Self::write_atomic(Path::new(&self.profile_path(name)), &json, false)?;
+
+        index.profiles.push(name.to_string());
+        self.write_index(&index)
+    }
+
+    fn switch_profile(&mut self, name: &str) -> Result<BrowserSettings, SettingsError> {
+        let mut index = self.read_index();
+        let name = Self::find_profile(&index, name)
+            .ok_or_else(|| SettingsError::ProfileNotFound(name.to_string()))?;
+        let path = self.profile_path(&name);
+        let settings = Self::read_settings_file(&path)?;
+
+        index.active = name.clone();
+        self.write_index(&index)?;
+        self.config_path = path;
+        self.active_profile = name;
+        self.replace_settings(settings);
+        Ok(self.settings.clone())
+    }
+
+    fn delete_profile(&mut self, name: &str) -> Result<(), SettingsError> {
+        let mut index = self.read_index();
+        let name = Self::find_profile(&index, name)
+            .ok_or_else(|| SettingsError::ProfileNotFound(name.to_string()))?;
+        if name == DEFAULT_PROFILE {
+            return Err(SettingsError::InvalidValue(
+                "The Default profile cannot be deleted".to_string(),
+            ));
+        }
+        if name == self.active_profile {
+            return Err(SettingsError::InvalidValue(format!(
+                "Profile '{}' is active; switch to another profile first",
+                name
+            )));
+        }
+
+        let path = self.profile_path(&name);
+        if Path::new(&path).exists() {
+            fs::remove_file(&path).map_err(|e| {
+                SettingsError::IoError(format!("Failed to delete profile file: {}", e))
+            })?;
+        }
+        let _ = fs::remove_file(Self::backup_path(&path));
+        index.profiles.retain(|p| *p != name);
+        self.write_index(&index)
+    }
+
+    fn export_to(&self, path: &str) -> Result<Vec<String>, SettingsError> {
+        let mut doc = settings_migrations::to_document(&self.settings).map_err(|e| {
+            SettingsError::SerializationError(format!("Failed to serialize settings: {}", e))
+        })?;
+        let mut stripped = Vec::new();
+        strip_sensitive("", &mut doc, &mut stripped);
+
+        let json = serde_json::to_string_pretty(&doc).map_err(|e| {
+            SettingsError::SerializationError(format!("Failed to serialize settings: {}", e))
+        })?;
+        Self::write_atomic(Path::new(path), &json, false)?;
+        Ok(stripped)
+    }
+
+    fn preview_import(&self, path: &str) -> Result<Vec<SettingsChange>, SettingsError> {
+        let imported = self.read_import(path)?;
+        Ok(diff_settings(&self.settings, &imported))
+    }
+
+    fn import_from(&mut self, path: &str) -> Result<Vec<SettingsChange>, SettingsError> {
+        let imported = self.read_import(path)?;
+        let changes = diff_settings(&self.settings, &imported);
+        self.replace_settings(imported);
+        self.save()?;
+        Ok(changes)
+    }
+
+    fn sync_snapshot(&self) -> Result<Value, SettingsError> {
+        let mut doc = settings_migrations::to_document(&self.settings).map_err(|e| {
+            SettingsError::SerializationError(format!("Failed to serialize settings: {}", e))
+        })?;
+        strip_sensitive("", &mut doc, &mut Vec::new());
+        if let Some(map) = doc.as_object_mut() {
+            map.remove("shortcuts");
+        }
+        Ok(doc)
+    }
+
+    fn apply_synced(&mut self, mut snapshot: Value) -> Result<Vec<SettingsChange>, SettingsError> {
+        let map = snapshot.as_object_mut().ok_or_else(|| {
+            SettingsError::InvalidValue("Synced settings must be an object".to_string())
+        })?;
+        let shortcuts = serde_json::to_value(&self.settings.shortcuts).map_err(|e| {
+            SettingsError::SerializationError(format!("Failed to serialize settings: {}", e))
+        })?;
+        map.insert("shortcuts".to_string(), shortcuts);
+        let synced = self.adopt_document(snapshot, "Synced settings")?;
+        let changes = diff_settings(&self.settings, &synced);
+        if !changes.is_empty() {
+            self.replace_settings(synced);
+            self.save()?;
+        }
+        Ok(changes)
+    }
 }
 
 #[cfg(test)]
@@ -377,6 +1078,14 @@ mod tests {
             )
             .unwrap();
         assert!(!engine.get_settings().performance.lazy_load_images);
+
+        engine
+            .set_value(
+                "performance.battery_saver",
+                serde_json::Value::Bool(false),
+            )
+            .unwrap();
+        assert!(!engine.get_settings().performance.battery_saver);
     }
 
     #[test]
@@ -401,7 +1110,7 @@ mod tests {
         assert_eq!(defaults.general.language, "en");
         assert_eq!(defaults.general.startup_behavior, crate::types::settings::StartupBehavior::Restore);
         assert_eq!(defaults.general.homepage, "about:newtab");
-        assert_eq!(defaults.general.default_search_engine, "google");
+        assert_eq!(defaults.search.default_engine, "google");
 
         // Privacy
         assert!(defaults.privacy.tracker_blocking);
@@ -441,5 +1150,6 @@ mod tests {
         // Performance
         assert_eq!(defaults.performance.tab_suspend_timeout_minutes, 30);
         assert!(defaults.performance.lazy_load_images);
+        assert!(defaults.performance.battery_saver);
     }
 }
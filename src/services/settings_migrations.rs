@@ -0,0 +1,141 @@
+//! Settings file migrations for GitBrowser.
+//!
+//! The settings file carries a top-level `schema_version`. On load, the raw
+//! JSON document is upgraded step by step to `SETTINGS_SCHEMA_VERSION` before
+//! being deserialized, so renamed keys, new defaults and removed options are
+//! handled explicitly instead of failing to parse or silently losing values.
+//! Files without a version are treated as version 0.
+
+use serde_json::{Map, Value};
+
+use crate::types::search::SearchSettings;
+use crate::types::settings::BrowserSettings;
+
+/// Current settings file version. Bump this when adding a migration.
+pub const SETTINGS_SCHEMA_VERSION: u32 = 3;
+
+/// Name of the version field in the settings file.
+pub const VERSION_KEY: &str = "schema_version";
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// Ordered migration steps; each upgrades a document to the listed version.
+///
+/// Steps only move or rewrite values. Filling in new defaults and dropping
+/// removed options happens once afterwards in `reconcile_with_defaults`, so a
+/// step never sees keys pruned before it had a chance to migrate them.
+const MIGRATIONS: &[(u32, Migration)] = &[(1, migration_v1), (3, migration_v3)];
+
+/// Reads the `schema_version` of a raw settings document (0 if absent).
+pub fn document_version(doc: &Value) -> u32 {
+    doc.get(VERSION_KEY).and_then(|v| v.as_u64()).unwrap_or(0) as u32
+}
+
+/// Upgrades `doc` in place to `SETTINGS_SCHEMA_VERSION` and stamps the new
+/// version. Returns the version the document started at. Documents from a
+/// newer build are left untouched.
+pub fn migrate(doc: &mut Value) -> u32 {
+    let from = document_version(doc);
+    let Some(map) = doc.as_object_mut() else {
+        return from;
+    };
+    for (version, step) in MIGRATIONS {
+        if from < *version {
+            step(&mut *map);
+        }
+    }
+    if from < SETTINGS_SCHEMA_VERSION {
+        reconcile_with_defaults(map);
+        map.insert(VERSION_KEY.to_string(), Value::from(SETTINGS_SCHEMA_VERSION));
+    }
+    from
+}
+
+/// Serializes settings as a versioned settings document.
+pub fn to_document(settings: &BrowserSettings) -> Result<Value, serde_json::Error> {
+    let mut doc = serde_json::to_value(settings)?;
+    if let Some(map) = doc.as_object_mut() {
+        map.insert(VERSION_KEY.to_string(), Value::from(SETTINGS_SCHEMA_VERSION));
+    }
+    Ok(doc)
+}
+
+/// V1: Moves the pre-sections flat `language` key into `general.language`
+/// and normalizes lowercase enum spellings written by early builds.
+fn migration_v1(doc: &mut Map<String, Value>) {
+    if let Some(lang) = doc.remove("language") {
+        let general = doc
+            .entry("general")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(general) = general.as_object_mut() {
+            general.entry("language").or_insert(lang);
+        }
+    }
+
+    let enums: [(&str, &str, &[&str]); 2] = [
+        ("appearance", "theme", &["Dark", "Light", "System"]),
+        ("general", "startup_behavior", &["Restore", "NewTab", "Homepage"]),
+    ];
+    for (section, key, variants) in enums {
+        let Some(value) = doc.get_mut(section).and_then(|s| s.get_mut(key)) else {
+            continue;
+        };
+        let normalized = value.as_str().and_then(|s| {
+            let flat = s.replace(['_', '-'], "");
+            variants.iter().find(|v| v.eq_ignore_ascii_case(&flat))
+        });
+        if let Some(variant) = normalized {
+            *value = Value::from(*variant);
+        }
+    }
+}
+
+/// V3: Replaces `general.default_search_engine` with the search engine
+/// registry; the old engine name becomes `search.default_engine`.
+fn migration_v3(doc: &mut Map<String, Value>) {
+    let old = doc
+        .get_mut("general")
+        .and_then(|g| g.as_object_mut())
+        .and_then(|g| g.remove("default_search_engine"));
+    let Some(Value::String(engine)) = old else {
+        return;
+    };
+    if doc.contains_key("search") {
+        return;
+    }
+    let mut search = serde_json::to_value(SearchSettings::default()).unwrap_or(Value::Null);
+    let known = search["engines"]
+        .as_array()
+        .is_some_and(|engines| engines.iter().any(|e| e["id"] == engine.as_str()));
+    if known {
+        search["default_engine"] = Value::from(engine);
+    }
+    doc.insert("search".to_string(), search);
+}
+
+/// Fills every missing section and key with its current default and drops
+/// options that no longer exist (V2). Shortcut bindings are user-defined
+/// keys, so only missing defaults are added there.
+fn reconcile_with_defaults(doc: &mut Map<String, Value>) {
+    let Ok(Value::Object(defaults)) = serde_json::to_value(BrowserSettings::default()) else {
+        return;
+    };
+    merge_defaults(doc, &defaults, true);
+}
+
+fn merge_defaults(doc: &mut Map<String, Value>, defaults: &Map<String, Value>, prune: bool) {
+    if prune {
+        doc.retain(|k, _| k == VERSION_KEY || defaults.contains_key(k));
+    }
+    for (key, default) in defaults {
+        match (doc.get_mut(key), default) {
+            (Some(Value::Object(section)), Value::Object(section_defaults)) => {
+                merge_defaults(section, section_defaults, key != "shortcuts");
+            }
+            (Some(_), _) => {}
+            (None, _) => {
+                doc.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
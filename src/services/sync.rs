@@ -0,0 +1,904 @@
+//! Sync of user data through a private GitHub gist.
+//!
+//! Bookmarks, settings, shortcuts and the reading list are synced as separate
+//! collections, each with its own version vector (one change counter per
+//! device). A run snapshots the local data, counts a change for this device
+//! on every collection whose content differs from the last run, and compares
+//! with the payload in the gist: newer remote data replaces the local copy,
+//! newer local data is pushed, and collections changed on both sides are
+//! settled by the `github.sync_conflict_strategy` setting. The default merges
+//! lists by union and keeps settings and shortcuts out of sync as a
+//! `SyncConflict` until the user picks a side.
+//! The payload is encrypted before it leaves the device, with a key derived
+//! from the sync passphrase by PBKDF2. The passphrase is the same on every
+//! device syncing through the gist and is kept in memory for the session; the
+//! salt and round count travel in the gist next to the ciphertext.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use crate::database::connection::Database;
+use crate::managers::bookmark_manager::{BookmarkManager, BookmarkManagerTrait};
+use crate::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+use crate::services::crypto_service::{CryptoService, CryptoServiceTrait};
+use crate::services::github_client::GitHubClient;
+use crate::services::github_integration::api_message;
+use crate::services::settings_engine::SettingsEngineTrait;
+use crate::types::bookmark::BookmarkFolder;
+use crate::types::credential::EncryptedData;
+use crate::types::errors::{GitHubError, SyncError};
+use crate::types::reading_list::ReadingListItem;
+use crate::types::sync::{
+    CollectionState, ConflictResolution, ConflictStrategy, SyncCollection, SyncConflict, SyncPayload, SyncReport,
+    VersionOrder, VersionVector,
+};
+
+/// Description that identifies the sync gist among the user's gists.
+pub const SYNC_GIST_DESCRIPTION: &str = "GitBrowser Sync";
+
+/// Name of the encrypted payload file in the sync gist.
+pub const SYNC_FILE_NAME: &str = "gitbrowser-sync.enc.json";
+
+/// Layout version of `SyncPayload` and of the encrypted envelope around it.
+/// Format 1 envelopes were sealed with a key of the device that wrote them.
+const PAYLOAD_FORMAT: u32 = 2;
+
+/// Key derivation of the payload key from the sync passphrase.
+const KDF_ALGORITHM: &str = "pbkdf2-sha256";
+
+/// PBKDF2 rounds for payloads this device seals first.
+const KDF_ITERATIONS: u32 = 600_000;
+
+/// Rounds accepted from a gist: enough to resist guessing, and few enough
+/// that a tampered gist cannot keep this device busy.
+const KDF_ITERATIONS_RANGE: std::ops::RangeInclusive<u32> = 100_000..=10_000_000;
+
+const KDF_SALT_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+
+const GISTS_PATH: &str = "/gists";
+
+/// Trait defining sync operations.
+pub trait SyncServiceTrait {
+    /// Stable ID of this installation in version vectors.
+    fn device_id(&self) -> Result<String, SyncError>;
+    /// ID of the sync gist found or created by an earlier run.
+    fn gist_id(&self) -> Result<Option<String>, SyncError>;
+    fn set_gist_id(&self, gist_id: &str) -> Result<(), SyncError>;
+    /// Current local data of every collection. A collection whose content
+    /// changed since the last run gets this device's counter bumped; one that
+    /// was never synced has an empty vector.
+    fn local_payload(&self, settings: &dyn SettingsEngineTrait) -> Result<SyncPayload, SyncError>;
+    /// Sets the passphrase the payload key is derived from, for this session.
+    /// Every device syncing through the same gist needs the same one.
+    fn set_passphrase(&self, passphrase: &str) -> Result<(), SyncError>;
+    fn has_passphrase(&self) -> bool;
+    /// Pulls, merges, pushes and applies in one go: `begin_sync`,
+    /// `SyncRun::exchange` and `finish_sync`. Needs the passphrase.
+    fn sync_now(
+        &self,
+        settings: &mut dyn SettingsEngineTrait,
+        remote: &mut dyn SyncRemote,
+    ) -> Result<SyncReport, SyncError>;
+    /// Conflicts left by earlier runs, oldest first.
+    fn conflicts(&self) -> Result<Vec<SyncConflict>, SyncError>;
+    /// Settles a conflict. Keeping the remote side applies it locally;
+    /// keeping the local side marks it newer so the next run pushes it.
+    fn resolve_conflict(
+        &self,
+        collection: SyncCollection,
+        resolution: ConflictResolution,
+        settings: &mut dyn SettingsEngineTrait,
+    ) -> Result<(), SyncError>;
+}
+
+/// Storage for the encrypted payload. `GistRemote` is the real one.
+pub trait SyncRemote {
+    /// The stored payload, or `None` if nothing was pushed yet.
+    fn fetch(&mut self) -> Result<Option<String>, SyncError>;
+    fn store(&mut self, content: &str) -> Result<(), SyncError>;
+}
+
+/// Result of comparing local and remote payloads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeOutcome {
+    /// The payload both sides should hold afterwards.
+    pub payload: SyncPayload,
+    /// Collections whose local data must be replaced by the payload's.
+    pub apply: Vec<SyncCollection>,
+    /// Whether the remote copy is out of date.
+    pub push: bool,
+    pub report: SyncReport,
+}
+
+/// What is written to the gist file: the encrypted `SyncPayload` JSON.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    format: u32,
+    /// How the key was derived from the passphrase; missing in format 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+    ciphertext: String,
+    iv: String,
+    auth_tag: String,
+}
+
+/// Salt and cost of the passphrase key derivation, stored in the envelope.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    iterations: u32,
+    salt: String,
+}
+
+/// Sync state backed by SQLite (`sync_state`, `sync_device`, `github_sync`).
+pub struct SyncService {
+    db: Arc<Database>,
+    /// The sync passphrase; never written to disk.
+    passphrase: Mutex<Option<Zeroizing<String>>>,
+    /// The last payload key derived, with the parameters it was derived with.
+    key: Mutex<Option<(KdfParams, Zeroizing<Vec<u8>>)>>,
+}
+
+/// A sync run whose local side was read by `SyncService::begin_sync`. It
+/// carries everything the exchange with the remote needs (key derivation,
+/// network, merge), so that part can run without access to the service.
+pub struct SyncRun {
+    crypto: CryptoService,
+    local: SyncPayload,
+    strategy: ConflictStrategy,
+    passphrase: Zeroizing<String>,
+    key: Option<(KdfParams, Zeroizing<Vec<u8>>)>,
+}
+
+/// A sync run after `SyncRun::exchange`: the remote holds the merged
+/// payload, and `SyncService::finish_sync` brings the local side along.
+pub struct MergedSync {
+    local: SyncPayload,
+    remote: Option<SyncPayload>,
+    outcome: MergeOutcome,
+    passphrase: Zeroizing<String>,
+    key: (KdfParams, Zeroizing<Vec<u8>>),
+}
+
+impl SyncRun {
+    /// Derivation parameters with a fresh salt, for a gist holding no
+    /// passphrase-sealed payload yet.
+    fn new_kdf_params(&self) -> KdfParams {
+        KdfParams {
+            algorithm: KDF_ALGORITHM.to_string(),
+            iterations: KDF_ITERATIONS,
+            salt: BASE64.encode(self.crypto.generate_random_bytes(KDF_SALT_LENGTH)),
+        }
+    }
+
+    /// The payload key for `params`, derived from the passphrase unless the
+    /// last run already did.
+    fn payload_key(&self, params: &KdfParams) -> Result<Zeroizing<Vec<u8>>, SyncError> {
+        if let Some((_, key)) = self.key.as_ref().filter(|(derived_for, _)| derived_for == params) {
+            return Ok(key.clone());
+        }
+        if params.algorithm != KDF_ALGORITHM {
+            return Err(SyncError::InvalidPayload(format!("unknown key derivation {}", params.algorithm)));
+        }
+        let iterations = NonZeroU32::new(params.iterations)
+            .filter(|_| KDF_ITERATIONS_RANGE.contains(&params.iterations))
+            .ok_or_else(|| SyncError::InvalidPayload(format!("{} key derivation rounds", params.iterations)))?;
+        let salt = BASE64.decode(&params.salt).map_err(|e| SyncError::InvalidPayload(e.to_string()))?;
+        let mut key = Zeroizing::new(vec![0u8; KEY_LENGTH]);
+        ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, self.passphrase.as_bytes(), &mut key);
+        Ok(key)
+    }
+
+    fn seal_envelope(&self, payload: &SyncPayload, kdf: KdfParams, key: &[u8]) -> Result<String, SyncError> {
+        let json = Zeroizing::new(serde_json::to_vec(payload).map_err(|e| SyncError::InvalidPayload(e.to_string()))?);
+        let encrypted = self.crypto.encrypt_aes256gcm(&json, key).map_err(|e| SyncError::InvalidPayload(e.to_string()))?;
+        let envelope = Envelope {
+            format: PAYLOAD_FORMAT,
+            kdf: Some(kdf),
+            ciphertext: BASE64.encode(&encrypted.ciphertext),
+            iv: BASE64.encode(&encrypted.iv),
+            auth_tag: BASE64.encode(&encrypted.auth_tag),
+        };
+        serde_json::to_string(&envelope).map_err(|e| SyncError::InvalidPayload(e.to_string()))
+    }
+
+    fn open_envelope(&self, envelope: &Envelope, key: &[u8]) -> Result<SyncPayload, SyncError> {
+        let decode = |s: &str| BASE64.decode(s).map_err(|e| SyncError::InvalidPayload(e.to_string()));
+        let encrypted = EncryptedData {
+            ciphertext: decode(&envelope.ciphertext)?,
+            iv: decode(&envelope.iv)?,
+            auth_tag: decode(&envelope.auth_tag)?,
+        };
+        // The tag only checks out under the key the payload was sealed with
+        let json = Zeroizing::new(self.crypto.decrypt_aes256gcm(&encrypted, key).map_err(|_| SyncError::WrongPassphrase)?);
+        serde_json::from_slice(&json).map_err(|e| SyncError::InvalidPayload(e.to_string()))
+    }
+
+    /// Pulls the remote payload, merges it with the local one and pushes the
+    /// result if the remote is out of date.
+    pub fn exchange(self, remote: &mut dyn SyncRemote) -> Result<MergedSync, SyncError> {
+        let envelope = match remote.fetch()? {
+            Some(content) => Some(parse_envelope(&content)?),
+            None => None,
+        };
+        // A format 1 payload only opens on the device that wrote it, so it
+        // is treated as absent and replaced by a passphrase-sealed one
+        let sealed = envelope.filter(|e| e.kdf.is_some());
+        let kdf = sealed.as_ref().and_then(|e| e.kdf.clone()).unwrap_or_else(|| self.new_kdf_params());
+        let key = self.payload_key(&kdf)?;
+        let remote_payload = match &sealed {
+            Some(envelope) => Some(self.open_envelope(envelope, &key)?),
+            None => None,
+        };
+        let outcome = merge_payloads(&self.local, remote_payload.as_ref(), &self.local.device_id, self.strategy);
+        if outcome.push {
+            remote.store(&self.seal_envelope(&outcome.payload, kdf.clone(), &key)?)?;
+        }
+        Ok(MergedSync {
+            local: self.local,
+            remote: remote_payload,
+            outcome,
+            passphrase: self.passphrase,
+            key: (kdf, key),
+        })
+    }
+}
+
+impl SyncService {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db, passphrase: Mutex::default(), key: Mutex::default() }
+    }
+
+    /// Reads the local side of a sync run. Needs the passphrase.
+    pub fn begin_sync(&self, settings: &dyn SettingsEngineTrait) -> Result<SyncRun, SyncError> {
+        let passphrase = self.passphrase.lock().unwrap_or_else(|e| e.into_inner()).clone()
+            .ok_or(SyncError::PassphraseRequired)?;
+        Ok(SyncRun {
+            crypto: CryptoService::new(),
+            local: self.local_payload(settings)?,
+            strategy: settings.get_settings().github.sync_conflict_strategy,
+            passphrase,
+            key: self.key.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        })
+    }
+
+    /// Applies what a run pulled and records the synced state. A collection
+    /// changed locally since `begin_sync` is left alone; the next run counts
+    /// that change.
+    pub fn finish_sync(&self, run: MergedSync, settings: &mut dyn SettingsEngineTrait) -> Result<SyncReport, SyncError> {
+        {
+            // Kept for the next run unless the passphrase changed meanwhile
+            let passphrase = self.passphrase.lock().unwrap_or_else(|e| e.into_inner());
+            if passphrase.as_ref() == Some(&run.passphrase) {
+                *self.key.lock().unwrap_or_else(|e| e.into_inner()) = Some(run.key);
+            }
+        }
+        let mut outcome = run.outcome;
+        let mut unchanged = HashSet::new();
+        for (collection, state) in &run.local.collections {
+            if digest_of(&self.snapshot(*collection, settings)?) == digest_of(&state.data) {
+                unchanged.insert(*collection);
+            }
+        }
+        outcome.report.pulled.retain(|c| unchanged.contains(c));
+        outcome.report.merged.retain(|c| unchanged.contains(c));
+        outcome.report.conflicts.retain(|c| unchanged.contains(c));
+
+        for collection in outcome.apply.iter().filter(|c| unchanged.contains(c)) {
+            self.apply(*collection, &outcome.payload.collections[collection].data, settings)?;
+        }
+        // Digests are taken from the data as it now reads locally, so the
+        // next run only counts changes made after this one
+        for (collection, state) in outcome.payload.collections.iter().filter(|(c, _)| unchanged.contains(c)) {
+            let theirs = run.remote.as_ref().and_then(|r| r.collections.get(collection));
+            if let Some(theirs) = theirs.filter(|_| outcome.report.conflicts.contains(collection)) {
+                // Local state is left as is so the local change still counts
+                self.save_conflict(&SyncConflict {
+                    collection: *collection,
+                    local: run.local.collections[collection].data.clone(),
+                    remote: theirs.data.clone(),
+                    remote_versions: theirs.versions.clone(),
+                    detected_at: Self::now(),
+                })?;
+                continue;
+            }
+            self.delete_conflict(*collection)?;
+            let data = self.snapshot(*collection, settings)?;
+            self.save_state(*collection, state, &digest_of(&data))?;
+        }
+        Ok(outcome.report)
+    }
+
+    fn now() -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+    }
+
+    /// Stored versions, digest and last change time of `collection`.
+    fn load_state(&self, collection: SyncCollection) -> Result<Option<(VersionVector, String, i64)>, SyncError> {
+        let result = self.db.connection().query_row(
+            "SELECT versions, digest, modified_at FROM sync_state WHERE collection = ?1",
+            params![collection.as_str()],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)),
+        );
+        match result {
+            Ok((versions, digest, modified_at)) => {
+                let versions = serde_json::from_str(&versions)
+                    .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+                Ok(Some((versions, digest, modified_at)))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(SyncError::DatabaseError(e.to_string())),
+        }
+    }
+
+    fn save_state(&self, collection: SyncCollection, state: &CollectionState, digest: &str) -> Result<(), SyncError> {
+        let versions = serde_json::to_string(&state.versions).map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        self.db.connection().execute(
+            "INSERT OR REPLACE INTO sync_state (collection, versions, digest, updated_at, modified_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![collection.as_str(), versions, digest, Self::now(), state.modified_at],
+        ).map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn save_conflict(&self, conflict: &SyncConflict) -> Result<(), SyncError> {
+        let versions = serde_json::to_string(&conflict.remote_versions)
+            .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        self.db.connection().execute(
+            "INSERT OR REPLACE INTO sync_conflicts (collection, local_data, remote_data, remote_versions, detected_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![conflict.collection.as_str(), conflict.local.to_string(), conflict.remote.to_string(), versions, conflict.detected_at],
+        ).map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn delete_conflict(&self, collection: SyncCollection) -> Result<(), SyncError> {
+        self.db.connection().execute(
+            "DELETE FROM sync_conflicts WHERE collection = ?1",
+            params![collection.as_str()],
+        ).map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The local data of one collection, in the form stored in the payload.
+    pub fn snapshot(&self, collection: SyncCollection, settings: &dyn SettingsEngineTrait) -> Result<Value, SyncError> {
+        let conn = self.db.connection();
+        match collection {
+            SyncCollection::Bookmarks => bookmarks_snapshot(conn),
+            SyncCollection::Settings => settings.sync_snapshot().map_err(|e| SyncError::DatabaseError(e.to_string())),
+            SyncCollection::Shortcuts => serde_json::to_value(&settings.get_settings().shortcuts)
+                .map_err(|e| SyncError::DatabaseError(e.to_string())),
+            SyncCollection::ReadingList => reading_list_snapshot(conn),
+        }
+    }
+
+    /// Replaces the local data of one collection with `data`.
+    fn apply(&self, collection: SyncCollection, data: &Value, settings: &mut dyn SettingsEngineTrait) -> Result<(), SyncError> {
+        let conn = self.db.connection();
+        match collection {
+            SyncCollection::Bookmarks => apply_bookmarks(conn, data),
+            SyncCollection::Settings => settings.apply_synced(data.clone()).map(|_| ())
+                .map_err(|e| SyncError::InvalidPayload(e.to_string())),
+            SyncCollection::Shortcuts => settings.set_value("shortcuts", data.clone())
+                .map_err(|e| SyncError::InvalidPayload(e.to_string())),
+            SyncCollection::ReadingList => apply_reading_list(conn, data),
+        }
+    }
+}
+
+impl SyncServiceTrait for SyncService {
+    fn set_passphrase(&self, passphrase: &str) -> Result<(), SyncError> {
+        if passphrase.is_empty() {
+            return Err(SyncError::PassphraseRequired);
+        }
+        *self.passphrase.lock().unwrap_or_else(|e| e.into_inner()) = Some(Zeroizing::new(passphrase.to_string()));
+        *self.key.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+
+    fn has_passphrase(&self) -> bool {
+        self.passphrase.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    fn device_id(&self) -> Result<String, SyncError> {
+        let conn = self.db.connection();
+        match conn.query_row("SELECT id FROM sync_device LIMIT 1", [], |row| row.get::<_, String>(0)) {
+            Ok(id) => Ok(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                let id = Uuid::new_v4().to_string();
+                conn.execute(
+                    "INSERT INTO sync_device (id, created_at) VALUES (?1, ?2)",
+                    params![id, Self::now()],
+                ).map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+                Ok(id)
+            }
+            Err(e) => Err(SyncError::DatabaseError(e.to_string())),
+        }
+    }
+
+    fn gist_id(&self) -> Result<Option<String>, SyncError> {
+        match self.db.connection().query_row(
+            "SELECT gist_id FROM github_sync WHERE id = 'default'",
+            [],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(SyncError::DatabaseError(e.to_string())),
+        }
+    }
+
+    fn set_gist_id(&self, gist_id: &str) -> Result<(), SyncError> {
+        self.db.connection().execute(
+            "INSERT OR REPLACE INTO github_sync (id, sync_type, gist_id, last_synced_at) VALUES ('default', 'full', ?1, ?2)",
+            params![gist_id, Self::now()],
+        ).map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn local_payload(&self, settings: &dyn SettingsEngineTrait) -> Result<SyncPayload, SyncError> {
+        let device_id = self.device_id()?;
+        let mut collections = BTreeMap::new();
+        for collection in SyncCollection::ALL {
+            let data = self.snapshot(collection, settings)?;
+            let (versions, modified_at) = match self.load_state(collection)? {
+                Some((mut versions, digest, mut modified_at)) => {
+                    if digest != digest_of(&data) {
+                        versions.bump(&device_id);
+                        modified_at = Self::now();
+                    }
+                    (versions, modified_at)
+                }
+                None => (VersionVector::default(), 0),
+            };
+            collections.insert(collection, CollectionState { versions, data, modified_at });
+        }
+        Ok(SyncPayload { format: PAYLOAD_FORMAT, device_id, collections })
+    }
+
+    fn sync_now(
+        &self,
+        settings: &mut dyn SettingsEngineTrait,
+        remote: &mut dyn SyncRemote,
+    ) -> Result<SyncReport, SyncError> {
+        let merged = self.begin_sync(settings)?.exchange(remote)?;
+        self.finish_sync(merged, settings)
+    }
+
+    fn conflicts(&self) -> Result<Vec<SyncConflict>, SyncError> {
+        let db_err = |e: rusqlite::Error| SyncError::DatabaseError(e.to_string());
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(
+            "SELECT collection, local_data, remote_data, remote_versions, detected_at FROM sync_conflicts ORDER BY detected_at",
+        ).map_err(db_err)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        }).map_err(db_err)?;
+        let mut conflicts = Vec::new();
+        for row in rows {
+            let (collection, local, remote, versions, detected_at) = row.map_err(db_err)?;
+            let parse_err = |e: serde_json::Error| SyncError::DatabaseError(e.to_string());
+            // Rows of collections this version does not know are skipped
+            let Ok(collection) = serde_json::from_value(Value::String(collection)) else { continue };
+            conflicts.push(SyncConflict {
+                collection,
+                local: serde_json::from_str(&local).map_err(parse_err)?,
+                remote: serde_json::from_str(&remote).map_err(parse_err)?,
+                remote_versions: serde_json::from_str(&versions).map_err(parse_err)?,
+                detected_at,
+            });
+        }
+        Ok(conflicts)
+    }
+
+    fn resolve_conflict(
+        &self,
+        collection: SyncCollection,
+        resolution: ConflictResolution,
+        settings: &mut dyn SettingsEngineTrait,
+    ) -> Result<(), SyncError> {
+        let conflict = self.conflicts()?.into_iter()
+            .find(|c| c.collection == collection)
+            .ok_or_else(|| SyncError::ConflictNotFound(collection.as_str().to_string()))?;
+        let stored = self.load_state(collection)?.map(|(versions, _, _)| versions).unwrap_or_default();
+        let mut versions = stored.merged(&conflict.remote_versions);
+        match resolution {
+            ConflictResolution::KeepRemote => self.apply(collection, &conflict.remote, settings)?,
+            // Newer than both sides, so the next run pushes the local data
+            ConflictResolution::KeepLocal => versions.bump(&self.device_id()?),
+        }
+        let data = self.snapshot(collection, settings)?;
+        let state = CollectionState { versions, data, modified_at: Self::now() };
+        self.save_state(collection, &state, &digest_of(&state.data))?;
+        self.delete_conflict(collection)
+    }
+}
+
+/// Decides, collection by collection, which side wins. Pure, so the rules
+/// can be tested without a database or network. Collections that
+/// `strategy` leaves to the user keep the remote state in the payload, are
+/// not applied and are listed in `report.conflicts`.
+pub fn merge_payloads(
+    local: &SyncPayload,
+    remote: Option<&SyncPayload>,
+    device_id: &str,
+    strategy: ConflictStrategy,
+) -> MergeOutcome {
+    let mut payload = SyncPayload {
+        format: PAYLOAD_FORMAT,
+        device_id: device_id.to_string(),
+        collections: BTreeMap::new(),
+    };
+    let mut apply = Vec::new();
+    let mut push = false;
+    let mut report = SyncReport::default();
+
+    for (&collection, mine) in &local.collections {
+        let theirs = remote.and_then(|r| r.collections.get(&collection));
+        let mut merged = false;
+        let state = match theirs {
+            None => {
+                let mut state = mine.clone();
+                if state.versions.is_empty() {
+                    state.versions.bump(device_id);
+                }
+                state
+            }
+            Some(theirs) => match mine.versions.compare(&theirs.versions) {
+                VersionOrder::Equal | VersionOrder::Ahead => mine.clone(),
+                // A list that never synced here has no deletions to honour,
+                // so it is merged instead of overwritten
+                VersionOrder::Behind if !(mine.versions.is_empty() && collection.is_list()) => theirs.clone(),
+                order => {
+                    let data = if order == VersionOrder::Behind {
+                        Some(merge_data(collection, &mine.data, &theirs.data))
+                    } else {
+                        settle_conflict(collection, mine, theirs, strategy)
+                    };
+                    let Some(data) = data else {
+                        report.conflicts.push(collection);
+                        payload.collections.insert(collection, theirs.clone());
+                        continue;
+                    };
+                    merged = true;
+                    let mut versions = mine.versions.merged(&theirs.versions);
+                    if data != theirs.data {
+                        versions.bump(device_id);
+                    }
+                    CollectionState { versions, data, modified_at: mine.modified_at.max(theirs.modified_at) }
+                }
+            },
+        };
+
+        let stale = theirs != Some(&state);
+        let changed = state.data != mine.data;
+        push |= stale;
+        if changed {
+            apply.push(collection);
+        }
+        if merged && (changed || stale) {
+            report.merged.push(collection);
+        } else if changed {
+            report.pulled.push(collection);
+        }
+        payload.collections.insert(collection, state);
+    }
+
+    report.pushed = push;
+    MergeOutcome { payload, apply, push, report }
+}
+
+/// Data a collection changed on both sides settles on under `strategy`, or
+/// `None` when it is left for the user.
+pub fn settle_conflict(
+    collection: SyncCollection,
+    mine: &CollectionState,
+    theirs: &CollectionState,
+    strategy: ConflictStrategy,
+) -> Option<Value> {
+    match strategy {
+        ConflictStrategy::Merge if collection.is_list() => Some(merge_data(collection, &mine.data, &theirs.data)),
+        // Settings and shortcuts are one document; picking keys from both
+        // sides could combine values that only make sense together
+        ConflictStrategy::Merge => None,
+        ConflictStrategy::LastWriterWins if theirs.modified_at > mine.modified_at => Some(theirs.data.clone()),
+        ConflictStrategy::LastWriterWins | ConflictStrategy::LocalWins => Some(mine.data.clone()),
+        ConflictStrategy::RemoteWins => Some(theirs.data.clone()),
+    }
+}
+
+/// Combines two versions of a list collection: every entry of either side
+/// is kept once (local order first). Bookmarks are the same entry when
+/// folder and URL match, reading list items when the URL does. Other
+/// collections keep the local value.
+pub fn merge_data(collection: SyncCollection, local: &Value, remote: &Value) -> Value {
+    let (Some(mine), Some(theirs)) = (local.as_array(), remote.as_array()) else {
+        return local.clone();
+    };
+    let key = |entry: &Value| match collection {
+        SyncCollection::Bookmarks => json!([entry["folder"], entry["url"]]),
+        _ => entry["url"].clone(),
+    };
+    let mut merged: Vec<Value> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for entry in mine.iter().chain(theirs) {
+        match index.get(&key(entry).to_string()) {
+            // An article read on either device stays read
+            Some(&i) if collection == SyncCollection::ReadingList => {
+                if merged[i]["read_at"].is_null() && !entry["read_at"].is_null() {
+                    merged[i]["read_at"] = entry["read_at"].clone();
+                }
+            }
+            Some(_) => {}
+            None => {
+                index.insert(key(entry).to_string(), merged.len());
+                merged.push(entry.clone());
+            }
+        }
+    }
+    Value::Array(merged)
+}
+
+/// Hex SHA-256 of the canonical JSON of a collection.
+fn digest_of(data: &Value) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, data.to_string().as_bytes());
+    digest.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_envelope(content: &str) -> Result<Envelope, SyncError> {
+    let envelope: Envelope = serde_json::from_str(content).map_err(|e| SyncError::InvalidPayload(e.to_string()))?;
+    if envelope.format > PAYLOAD_FORMAT {
+        return Err(SyncError::InvalidPayload(format!("format {} is newer than this version supports", envelope.format)));
+    }
+    Ok(envelope)
+}
+
+// ─── Bookmarks ───
+
+/// Folder ID → names from the root folder down.
+fn folder_paths(folders: &[BookmarkFolder]) -> HashMap<String, Vec<String>> {
+    let by_id: HashMap<&str, &BookmarkFolder> = folders.iter().map(|f| (f.id.as_str(), f)).collect();
+    folders.iter().map(|folder| {
+        let mut path = vec![folder.name.clone()];
+        let mut parent = folder.parent_id.as_deref();
+        // Bounded walk: a parent cycle must not hang sync
+        while let Some(p) = parent.and_then(|id| by_id.get(id)).filter(|_| path.len() <= folders.len()) {
+            path.insert(0, p.name.clone());
+            parent = p.parent_id.as_deref();
+        }
+        (folder.id.clone(), path)
+    }).collect()
+}
+
+fn bookmarks_snapshot(conn: &Connection) -> Result<Value, SyncError> {
+    let db_err = |e: crate::types::errors::BookmarkError| SyncError::DatabaseError(e.to_string());
+    let mgr = BookmarkManager::new(conn);
+    let paths = folder_paths(&mgr.list_folders().map_err(db_err)?);
+    let mut entries: Vec<(Vec<String>, Value)> = mgr.list_all_bookmarks().map_err(db_err)?
+        .into_iter()
+        .map(|b| {
+            let folder = b.folder_id.as_ref().and_then(|id| paths.get(id)).cloned().unwrap_or_default();
+            let entry = json!({"url": b.url, "title": b.title, "folder": folder});
+            (folder, entry)
+        })
+        .collect();
+    // Folder IDs differ between devices, so order by path; positions keep their order within a folder
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(Value::Array(entries.into_iter().map(|(_, e)| e).collect()))
+}
+
+fn apply_bookmarks(conn: &Connection, data: &Value) -> Result<(), SyncError> {
+    let db_err = |e: crate::types::errors::BookmarkError| SyncError::DatabaseError(e.to_string());
+    let entries = data.as_array().ok_or_else(|| SyncError::InvalidPayload("bookmarks must be a list".to_string()))?;
+    let mut mgr = BookmarkManager::new(conn);
+    let mut folder_ids: HashMap<Vec<String>, String> = folder_paths(&mgr.list_folders().map_err(db_err)?)
+        .into_iter()
+        .map(|(id, path)| (path, id))
+        .collect();
+
+    let wanted: HashSet<(Vec<String>, &str)> = entries.iter()
+        .filter_map(|e| Some((folder_of(e), e["url"].as_str()?)))
+        .collect();
+    let mut existing = HashMap::new();
+    let paths: HashMap<String, Vec<String>> = folder_ids.iter().map(|(p, id)| (id.clone(), p.clone())).collect();
+    for bookmark in mgr.list_all_bookmarks().map_err(db_err)? {
+        let folder = bookmark.folder_id.as_ref().and_then(|id| paths.get(id)).cloned().unwrap_or_default();
+        if wanted.contains(&(folder.clone(), bookmark.url.as_str())) {
+            existing.insert((folder, bookmark.url.clone()), bookmark);
+        } else {
+            mgr.remove_bookmark(&bookmark.id).map_err(db_err)?;
+        }
+    }
+
+    for entry in entries {
+        let (Some(url), Some(title)) = (entry["url"].as_str(), entry["title"].as_str()) else { continue };
+        let folder = folder_of(entry);
+        match existing.get(&(folder.clone(), url.to_string())) {
+            Some(bookmark) if bookmark.title != title => {
+                mgr.update_bookmark(&bookmark.id, None, Some(title)).map_err(db_err)?;
+            }
+            Some(_) => {}
+            None => {
+                let folder_id = ensure_folder(&mut mgr, &mut folder_ids, &folder)?;
+                mgr.add_bookmark(url, title, folder_id.as_deref()).map_err(db_err)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn folder_of(entry: &Value) -> Vec<String> {
+    entry["folder"].as_array()
+        .map(|names| names.iter().filter_map(|n| n.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+/// ID of the folder at `path`, creating missing folders along the way.
+fn ensure_folder(
+    mgr: &mut BookmarkManager,
+    folder_ids: &mut HashMap<Vec<String>, String>,
+    path: &[String],
+) -> Result<Option<String>, SyncError> {
+    let mut parent: Option<String> = None;
+    for depth in 1..=path.len() {
+        let prefix = path[..depth].to_vec();
+        let id = match folder_ids.get(&prefix) {
+            Some(id) => id.clone(),
+            None => {
+                let id = mgr.create_folder(&path[depth - 1], parent.as_deref())
+                    .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+                folder_ids.insert(prefix, id.clone());
+                id
+            }
+        };
+        parent = Some(id);
+    }
+    Ok(parent)
+}
+
+// ─── Reading list ───
+
+fn reading_list_snapshot(conn: &Connection) -> Result<Value, SyncError> {
+    let (mut items, _) = ReadingListManager::new(conn).list_items(false, i64::MAX, 0)
+        .map_err(|e| SyncError::DatabaseError(e.to_string()))?;
+    items.sort_by(|a, b| a.url.cmp(&b.url));
+    let entries = items.into_iter().map(|item| {
+        let mut entry = serde_json::to_value(item).unwrap_or(Value::Null);
+        // Item IDs are local to each device
+        if let Some(map) = entry.as_object_mut() {
+            map.remove("id");
+        }
+        entry
+    }).collect();
+    Ok(Value::Array(entries))
+}
+
+fn apply_reading_list(conn: &Connection, data: &Value) -> Result<(), SyncError> {
+    let db_err = |e: crate::types::errors::ReadingListError| SyncError::DatabaseError(e.to_string());
+    let entries = data.as_array().ok_or_else(|| SyncError::InvalidPayload("reading list must be a list".to_string()))?;
+    let mut items = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let mut entry = entry.clone();
+        entry["id"] = json!("");
+        let item: ReadingListItem = serde_json::from_value(entry)
+            .map_err(|e| SyncError::InvalidPayload(e.to_string()))?;
+        items.push(item);
+    }
+
+    let mut mgr = ReadingListManager::new(conn);
+    let wanted: HashSet<&str> = items.iter().map(|i| i.url.as_str()).collect();
+    let (existing, _) = mgr.list_items(false, i64::MAX, 0).map_err(db_err)?;
+    for item in existing.iter().filter(|i| !wanted.contains(i.url.as_str())) {
+        mgr.remove_item(&item.id).map_err(db_err)?;
+    }
+    for item in &items {
+        mgr.import_item(item).map_err(db_err)?;
+    }
+    Ok(())
+}
+
+// ─── Gist remote ───
+
+/// The payload file in a private gist of the logged-in user. The gist is
+/// looked up by description on first use and created on first push.
+pub struct GistRemote {
+    client: GitHubClient,
+    gist_id: Option<String>,
+}
+
+impl GistRemote {
+    pub fn new(client: GitHubClient, gist_id: Option<String>) -> Self {
+        Self { client, gist_id }
+    }
+
+    /// The gist in use, once found or created.
+    pub fn gist_id(&self) -> Option<&str> {
+        self.gist_id.as_deref()
+    }
+
+    fn request(&self, method: &str, url: &str, body: Option<Value>) -> Result<(u16, Value), SyncError> {
+        self.client.request(method, url, body)
+            .map(|response| (response.status, response.body))
+            .map_err(|e| match e {
+                GitHubError::RateLimited(secs) => SyncError::RateLimited(secs),
+                e => SyncError::RemoteError(e.to_string()),
+            })
+    }
+
+    fn find_gist(&self) -> Result<Option<String>, SyncError> {
+        let (status, gists) = self.request("GET", &format!("{}?per_page=100", GISTS_PATH), None)?;
+        check_status(status, &gists)?;
+        Ok(gists.as_array().and_then(|list| {
+            list.iter()
+                .find(|g| g["description"].as_str() == Some(SYNC_GIST_DESCRIPTION))
+                .and_then(|g| g["id"].as_str().map(str::to_string))
+        }))
+    }
+}
+
+impl SyncRemote for GistRemote {
+    fn fetch(&mut self) -> Result<Option<String>, SyncError> {
+        if self.gist_id.is_none() {
+            self.gist_id = self.find_gist()?;
+        }
+        let Some(id) = self.gist_id.clone() else { return Ok(None) };
+        let (status, gist) = self.request("GET", &format!("{}/{}", GISTS_PATH, id), None)?;
+        if status == 404 {
+            // Deleted on GitHub: the next push creates a new one
+            self.gist_id = None;
+            return Ok(None);
+        }
+        check_status(status, &gist)?;
+        let file = &gist["files"][SYNC_FILE_NAME];
+        if file["truncated"].as_bool() == Some(true) {
+            // Large files are cut off in the gist response; the raw URL serves them whole
+            let raw_url = file["raw_url"].as_str()
+                .ok_or_else(|| SyncError::RemoteError("truncated file without raw_url".to_string()))?;
+            let (status, raw) = self.request("GET", raw_url, None)?;
+            check_status(status, &raw)?;
+            return Ok(Some(raw.to_string()));
+        }
+        Ok(file["content"].as_str().map(str::to_string))
+    }
+
+    fn store(&mut self, content: &str) -> Result<(), SyncError> {
+        let body = json!({
+            "description": SYNC_GIST_DESCRIPTION,
+            "public": false,
+            "files": {SYNC_FILE_NAME: {"content": content}},
+        });
+        let (status, gist) = match &self.gist_id {
+            Some(id) => self.request("PATCH", &format!("{}/{}", GISTS_PATH, id), Some(body))?,
+            None => self.request("POST", GISTS_PATH, Some(body))?,
+        };
+        check_status(status, &gist)?;
+        if let Some(id) = gist["id"].as_str() {
+            self.gist_id = Some(id.to_string());
+        }
+        Ok(())
+    }
+}
+
+fn check_status(status: u16, body: &Value) -> Result<(), SyncError> {
+    match status {
+        200..=299 => Ok(()),
+        401 => Err(SyncError::NotAuthenticated),
+        _ => Err(SyncError::RemoteError(format!("HTTP {}: {}", status, api_message(body)))),
+    }
+}
@@ -1,9 +1,16 @@
 //! Theme Engine — manages dark/light/system themes, accent colors, and CSS variables.
+//!
+//! User themes (JSON files of CSS variable overrides) are validated on install
+//! and kept in a gallery directory under the data dir, one `<id>.json` each.
 
 use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
 
 use crate::types::errors::ThemeError;
+use crate::types::locale::TextDirection;
 use crate::types::settings::ThemeMode;
+use crate::types::theme::CustomTheme;
 
 /// Trait defining the theme engine interface.
 pub trait ThemeEngineTrait {
@@ -12,9 +19,54 @@ pub trait ThemeEngineTrait {
     fn set_accent_color(&mut self, color: &str) -> Result<(), ThemeError>;
     fn get_accent_color(&self) -> &str;
     fn detect_system_theme(&self) -> ThemeMode;
+    /// Script that applies (`enabled`) or removes the forced dark mode filter
+    /// on the current page. Pages that are already dark are left alone.
+    fn force_dark_script(&self, enabled: bool) -> String;
+    /// Records the OS color scheme reported by the platform watcher.
+    /// Returns true if the effective palette changed as a result.
+    fn set_system_preference(&mut self, dark: bool) -> bool;
+    /// Sets the UI writing direction emitted as `--gb-direction`,
+    /// `--gb-start` and `--gb-end`.
+    fn set_direction(&mut self, direction: TextDirection);
     fn get_css_variables(&self) -> HashMap<String, String>;
+    /// Validates a theme and saves it to the gallery, replacing any theme with the same ID.
+    fn install_theme(&mut self, theme: CustomTheme) -> Result<CustomTheme, ThemeError>;
+    /// Installed themes, sorted by name. Invalid files are skipped.
+    fn list_themes(&self) -> Vec<CustomTheme>;
+    fn remove_theme(&mut self, id: &str) -> Result<(), ThemeError>;
+    /// Applies an installed theme's overrides, or clears them with `None`.
+    fn apply_custom_theme(&mut self, id: Option<&str>) -> Result<(), ThemeError>;
+    fn get_custom_theme(&self) -> Option<&CustomTheme>;
 }
 
+/// `appearance.accent_color` value that follows the OS accent color.
+pub const SYSTEM_ACCENT: &str = "system";
+
+/// Accent used when none is configured or the OS accent is unavailable.
+pub const DEFAULT_ACCENT: &str = "#2ea44f";
+
+/// Variables a theme may override with a color value.
+const COLOR_VARIABLES: &[&str] = &[
+    "--bg-primary", "--bg-secondary", "--bg-tertiary", "--text-primary", "--text-secondary",
+    "--border-color", "--link-color", "--hover-bg", "--input-bg", "--scrollbar-color", "--accent-color",
+];
+
+/// Variables a theme may override with a duration (e.g. `150ms`).
+const DURATION_VARIABLES: &[&str] = &["--transition-fast", "--transition-normal", "--transition-slow"];
+
+/// Forced dark mode stylesheet: inverts the page, then inverts media back so
+/// photos and video keep their colors. Browser UI is restored the same way.
+const FORCE_DARK_CSS: &str = "html[data-gb-force-dark]{filter:invert(1) hue-rotate(180deg);background:#fff}\
+html[data-gb-force-dark] :is(img,video,picture,canvas,iframe,embed,object,svg image,[style*=\"background-image\"]),\
+html[data-gb-force-dark] :is(#gb-toolbar,#gb-status,.gb-toast){filter:invert(1) hue-rotate(180deg)}\
+html[data-gb-force-dark] :is(picture img,#gb-toolbar *,#gb-status *){filter:none}";
+
+/// Background luminance (0–1) below which a page counts as already dark.
+const FORCE_DARK_LUMINANCE_THRESHOLD: f64 = 0.4;
+
+/// Largest number of overrides accepted in one theme.
+const MAX_THEME_VARIABLES: usize = 64;
+
 /// GitHub-style dark theme colors.
 struct DarkPalette;
 impl DarkPalette {
@@ -45,8 +97,90 @@ impl LightPalette {
     const SCROLLBAR: &'static str = "#afb8c1";
 }
 
+/// Validates a CSS color: hex (`#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`),
+/// `rgb()/rgba()/hsl()/hsla()` with numeric arguments, or `transparent`.
+fn is_valid_css_color(value: &str) -> bool {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    if value.eq_ignore_ascii_case("transparent") {
+        return true;
+    }
+    let lower = value.to_ascii_lowercase();
+    ["rgb(", "rgba(", "hsl(", "hsla("].iter().any(|f| {
+        lower
+            .strip_prefix(f)
+            .and_then(|rest| rest.strip_suffix(')'))
+            .is_some_and(|args| {
+                !args.trim().is_empty()
+                    && args.chars().all(|c| c.is_ascii_digit() || " .,%/-deg".contains(c))
+            })
+    })
+}
+
+/// Validates a theme's name, ID and variable overrides. Fills in `id` from
+/// `name` when it is missing.
+pub fn validate_theme(theme: &mut CustomTheme) -> Result<(), ThemeError> {
+    if theme.name.trim().is_empty() || theme.name.len() > 100 {
+        return Err(ThemeError::InvalidTheme("name must be 1-100 characters".to_string()));
+    }
+    if theme.id.is_empty() {
+        theme.id = theme
+            .name
+            .trim()
+            .to_ascii_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+    }
+    let id_ok = theme.id.len() <= 64
+        && theme.id.chars().any(|c| c.is_ascii_alphanumeric())
+        && theme.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !id_ok {
+        return Err(ThemeError::InvalidTheme(format!("invalid theme id '{}'", theme.id)));
+    }
+    if theme.variables.is_empty() || theme.variables.len() > MAX_THEME_VARIABLES {
+        return Err(ThemeError::InvalidTheme(format!(
+            "a theme must override 1-{} variables",
+            MAX_THEME_VARIABLES
+        )));
+    }
+
+    for (name, value) in &theme.variables {
+        let ok = if COLOR_VARIABLES.contains(&name.as_str()) {
+            is_valid_css_color(value)
+        } else if DURATION_VARIABLES.contains(&name.as_str()) {
+            value
+                .strip_suffix("ms")
+                .is_some_and(|n| !n.is_empty() && n.len() <= 5 && n.chars().all(|c| c.is_ascii_digit()))
+        } else if name == "--font-family" {
+            !value.trim().is_empty()
+                && value.len() <= 200
+                && value.chars().all(|c| c.is_alphanumeric() || " ,-_'\"".contains(c))
+        } else {
+            return Err(ThemeError::InvalidTheme(format!("unknown variable '{}'", name)));
+        };
+        if !ok {
+            return Err(ThemeError::InvalidColor(format!("{}: {}", name, value)));
+        }
+    }
+    Ok(())
+}
+
+/// Builds a script that applies CSS variables to the current page's root
+/// element and fires a `gb-theme-changed` event with the new values.
+pub fn css_variables_script(vars: &HashMap<String, String>) -> String {
+    let json = serde_json::to_string(vars).unwrap_or_else(|_| "{}".to_string());
+    format!(
+        "(function(v){{var s=document.documentElement.style;for(var k in v)s.setProperty(k,v[k]);\
+         window.dispatchEvent(new CustomEvent('gb-theme-changed',{{detail:v}}))}})({})",
+        json
+    )
+}
+
 /// Validates a hex color string (e.g. "#2ea44f" or "#fff").
-fn is_valid_hex_color(color: &str) -> bool {
+pub(crate) fn is_valid_hex_color(color: &str) -> bool {
     if !color.starts_with('#') {
         return false;
     }
@@ -59,6 +193,13 @@ fn is_valid_hex_color(color: &str) -> bool {
 pub struct ThemeEngine {
     current_theme: ThemeMode,
     accent_color: String,
+    /// Gallery directory for installed themes; `None` disables installation.
+    themes_dir: Option<PathBuf>,
+    custom_theme: Option<CustomTheme>,
+    /// Last OS preference pushed via `set_system_preference` (true = dark).
+    system_dark: Option<bool>,
+    /// Writing direction of the active locale.
+    direction: TextDirection,
 }
 
 impl ThemeEngine {
@@ -66,8 +207,38 @@ impl ThemeEngine {
     pub fn new(mode: ThemeMode) -> Self {
         Self {
             current_theme: mode,
-            accent_color: "#2ea44f".to_string(),
+            accent_color: DEFAULT_ACCENT.to_string(),
+            themes_dir: None,
+            custom_theme: None,
+            system_dark: None,
+            direction: TextDirection::Ltr,
+        }
+    }
+
+    /// Sets the gallery directory used by `install_theme` and friends.
+    pub fn with_themes_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.themes_dir = Some(dir.into());
+        self
+    }
+
+    fn theme_path(&self, id: &str) -> Result<PathBuf, ThemeError> {
+        let dir = self
+            .themes_dir
+            .as_ref()
+            .ok_or_else(|| ThemeError::IoError("no theme directory configured".to_string()))?;
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(ThemeError::NotFound(id.to_string()));
         }
+        Ok(dir.join(format!("{}.json", id)))
+    }
+
+    fn read_theme(&self, id: &str) -> Result<CustomTheme, ThemeError> {
+        let path = self.theme_path(id)?;
+        let content = fs::read_to_string(&path).map_err(|_| ThemeError::NotFound(id.to_string()))?;
+        let mut theme: CustomTheme = serde_json::from_str(&content)
+            .map_err(|e| ThemeError::InvalidTheme(e.to_string()))?;
+        validate_theme(&mut theme)?;
+        Ok(theme)
     }
 
     /// Returns the effective theme, resolving `System` to a concrete mode.
@@ -79,6 +250,7 @@ impl ThemeEngine {
     }
 
     /// Builds the CSS variable map for a given palette.
+    #[allow(clippy::too_many_arguments)]
     fn build_variables(
         bg_primary: &str,
         bg_secondary: &str,
@@ -134,9 +306,13 @@ impl ThemeEngineTrait for ThemeEngine {
     }
 
     fn detect_system_theme(&self) -> ThemeMode {
-        // In a full GTK4 build this would query gtk::Settings for
-        // "gtk-application-prefer-dark-theme". Without the GTK runtime
-        // we fall back to checking the GTK_THEME environment variable.
+        // The app pushes the platform preference in via set_system_preference.
+        // Until then, fall back to checking the GTK_THEME environment variable.
+        match self.system_dark {
+            Some(true) => return ThemeMode::Dark,
+            Some(false) => return ThemeMode::Light,
+            None => {}
+        }
         if let Ok(gtk_theme) = std::env::var("GTK_THEME") {
             let lower = gtk_theme.to_lowercase();
             if lower.contains("dark") {
@@ -148,9 +324,42 @@ impl ThemeEngineTrait for ThemeEngine {
         ThemeMode::Dark
     }
 
+    fn force_dark_script(&self, enabled: bool) -> String {
+        let css = serde_json::to_string(FORCE_DARK_CSS).unwrap_or_default();
+        format!(
+            "(function(on,css){{var id='gb-force-dark',root=document.documentElement,old=document.getElementById(id);\
+             if(!on){{if(old)old.remove();root.removeAttribute('data-gb-force-dark');return;}}\
+             if(old)return;\
+             function lum(el){{if(!el)return null;var m=getComputedStyle(el).backgroundColor.match(/[\\d.]+/g);\
+             if(!m||m.length<3||(m.length>3&&+m[3]===0))return null;\
+             return (0.2126*m[0]+0.7152*m[1]+0.0722*m[2])/255;}}\
+             var l=lum(document.body);if(l===null)l=lum(root);\
+             if(l!==null&&l<{threshold})return;\
+             var s=document.createElement('style');s.id=id;s.textContent=css;\
+             (document.head||root).appendChild(s);root.setAttribute('data-gb-force-dark','');}})({enabled},{css})",
+            threshold = FORCE_DARK_LUMINANCE_THRESHOLD,
+            enabled = enabled,
+            css = css,
+        )
+    }
+
+    fn set_system_preference(&mut self, dark: bool) -> bool {
+        let before = self.effective_theme();
+        self.system_dark = Some(dark);
+        self.effective_theme() != before
+    }
+
+    fn set_direction(&mut self, direction: TextDirection) {
+        self.direction = direction;
+    }
+
     fn get_css_variables(&self) -> HashMap<String, String> {
         let accent = &self.accent_color;
-        match self.effective_theme() {
+        let mode = match self.custom_theme.as_ref().and_then(|t| t.base.clone()) {
+            Some(ThemeMode::System) | None => self.effective_theme(),
+            Some(base) => base,
+        };
+        let mut vars = match mode {
             ThemeMode::Dark => Self::build_variables(
                 DarkPalette::BG_PRIMARY,
                 DarkPalette::BG_SECONDARY,
@@ -179,7 +388,78 @@ impl ThemeEngineTrait for ThemeEngine {
             ),
             // System is already resolved by effective_theme()
             ThemeMode::System => unreachable!(),
+        };
+        if let Some(theme) = &self.custom_theme {
+            vars.extend(theme.variables.clone());
+        }
+        let (start, end) = match self.direction {
+            TextDirection::Ltr => ("left", "right"),
+            TextDirection::Rtl => ("right", "left"),
+        };
+        vars.insert("--gb-direction".into(), self.direction.as_str().into());
+        vars.insert("--gb-start".into(), start.into());
+        vars.insert("--gb-end".into(), end.into());
+        vars
+    }
+
+    fn install_theme(&mut self, mut theme: CustomTheme) -> Result<CustomTheme, ThemeError> {
+        validate_theme(&mut theme)?;
+        let path = self.theme_path(&theme.id)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| ThemeError::IoError(e.to_string()))?;
+        }
+        let json = serde_json::to_string_pretty(&theme)
+            .map_err(|e| ThemeError::InvalidTheme(e.to_string()))?;
+        fs::write(&path, json).map_err(|e| ThemeError::IoError(e.to_string()))?;
+
+        // Reinstalling the active theme refreshes it
+        if self.custom_theme.as_ref().is_some_and(|t| t.id == theme.id) {
+            self.custom_theme = Some(theme.clone());
+        }
+        Ok(theme)
+    }
+
+    fn list_themes(&self) -> Vec<CustomTheme> {
+        let Some(dir) = &self.themes_dir else {
+            return Vec::new();
+        };
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut themes: Vec<CustomTheme> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let path = e.path();
+                if path.extension().and_then(|x| x.to_str()) != Some("json") {
+                    return None;
+                }
+                let id = path.file_stem()?.to_str()?.to_string();
+                self.read_theme(&id).ok()
+            })
+            .collect();
+        themes.sort_by_key(|t| t.name.to_lowercase());
+        themes
+    }
+
+    fn remove_theme(&mut self, id: &str) -> Result<(), ThemeError> {
+        let path = self.theme_path(id)?;
+        fs::remove_file(&path).map_err(|_| ThemeError::NotFound(id.to_string()))?;
+        if self.custom_theme.as_ref().is_some_and(|t| t.id == id) {
+            self.custom_theme = None;
         }
+        Ok(())
+    }
+
+    fn apply_custom_theme(&mut self, id: Option<&str>) -> Result<(), ThemeError> {
+        self.custom_theme = match id {
+            Some(id) => Some(self.read_theme(id)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    fn get_custom_theme(&self) -> Option<&CustomTheme> {
+        self.custom_theme.as_ref()
     }
 }
 
@@ -246,6 +526,20 @@ mod tests {
         assert_eq!(vars.get("--transition-slow").unwrap(), "300ms");
     }
 
+    #[test]
+    fn test_css_variables_follow_direction() {
+        let mut engine = ThemeEngine::new(ThemeMode::Dark);
+        let vars = engine.get_css_variables();
+        assert_eq!(vars.get("--gb-direction").unwrap(), "ltr");
+        assert_eq!(vars.get("--gb-start").unwrap(), "left");
+
+        engine.set_direction(TextDirection::Rtl);
+        let vars = engine.get_css_variables();
+        assert_eq!(vars.get("--gb-direction").unwrap(), "rtl");
+        assert_eq!(vars.get("--gb-start").unwrap(), "right");
+        assert_eq!(vars.get("--gb-end").unwrap(), "left");
+    }
+
     #[test]
     fn test_accent_color_reflected_in_css_variables() {
         let mut engine = ThemeEngine::new(ThemeMode::Dark);
@@ -263,4 +557,104 @@ mod tests {
         // Should resolve to dark palette
         assert_eq!(vars.get("--bg-primary").unwrap(), "#0d1117");
     }
+
+    #[test]
+    fn test_system_preference_flips_palette() {
+        let mut engine = ThemeEngine::new(ThemeMode::System);
+        engine.set_system_preference(true);
+        assert!(engine.set_system_preference(false));
+        assert_eq!(engine.get_css_variables().get("--bg-primary").unwrap(), "#ffffff");
+        assert!(!engine.set_system_preference(false));
+
+        // Explicit modes ignore the OS preference
+        engine.set_theme(ThemeMode::Dark);
+        assert!(!engine.set_system_preference(true));
+        assert!(!engine.set_system_preference(false));
+        assert_eq!(engine.get_css_variables().get("--bg-primary").unwrap(), "#0d1117");
+    }
+
+    #[test]
+    fn test_css_variables_script() {
+        let vars = HashMap::from([("--bg-primary".to_string(), "#fff".to_string())]);
+        let js = css_variables_script(&vars);
+        assert!(js.contains("setProperty"));
+        assert!(js.contains("{\"--bg-primary\":\"#fff\"}"));
+        assert!(js.contains("gb-theme-changed"));
+    }
+
+    #[test]
+    fn test_force_dark_script() {
+        let engine = ThemeEngine::new(ThemeMode::Light);
+        let on = engine.force_dark_script(true);
+        assert!(on.contains("invert(1)"));
+        assert!(on.contains("data-gb-force-dark"));
+        assert!(on.ends_with(")"));
+        assert!(on.contains("})(true,"));
+        assert!(engine.force_dark_script(false).contains("})(false,"));
+    }
+
+    fn nord() -> CustomTheme {
+        CustomTheme {
+            id: String::new(),
+            name: "Nord Night".to_string(),
+            author: None,
+            base: Some(ThemeMode::Light),
+            variables: HashMap::from([
+                ("--bg-primary".to_string(), "#2e3440".to_string()),
+                ("--link-color".to_string(), "rgba(136, 192, 208, 0.9)".to_string()),
+                ("--transition-fast".to_string(), "80ms".to_string()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_validate_theme_colors() {
+        let mut theme = nord();
+        assert!(validate_theme(&mut theme).is_ok());
+        assert_eq!(theme.id, "nord-night");
+
+        for (name, value) in [
+            ("--bg-primary", "red"),
+            ("--bg-primary", "#12345"),
+            ("--bg-primary", "url(x)"),
+            ("--transition-fast", "fast"),
+            ("--font-family", "x; } body {"),
+        ] {
+            let mut bad = nord();
+            bad.variables.insert(name.to_string(), value.to_string());
+            assert!(matches!(validate_theme(&mut bad), Err(ThemeError::InvalidColor(_))), "{}", value);
+        }
+
+        let mut unknown = nord();
+        unknown.variables.insert("--evil".to_string(), "#fff".to_string());
+        assert!(matches!(validate_theme(&mut unknown), Err(ThemeError::InvalidTheme(_))));
+    }
+
+    #[test]
+    fn test_install_list_apply_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = ThemeEngine::new(ThemeMode::Dark).with_themes_dir(dir.path().join("themes"));
+
+        let installed = engine.install_theme(nord()).unwrap();
+        assert!(dir.path().join("themes").join("nord-night.json").exists());
+        assert_eq!(engine.list_themes(), vec![installed.clone()]);
+
+        engine.apply_custom_theme(Some("nord-night")).unwrap();
+        let vars = engine.get_css_variables();
+        assert_eq!(vars.get("--bg-primary").unwrap(), "#2e3440");
+        // Unoverridden variables come from the theme's Light base
+        assert_eq!(vars.get("--text-primary").unwrap(), "#24292f");
+
+        assert!(matches!(engine.apply_custom_theme(Some("missing")), Err(ThemeError::NotFound(_))));
+        engine.remove_theme("nord-night").unwrap();
+        assert!(engine.get_custom_theme().is_none());
+        assert!(engine.list_themes().is_empty());
+    }
+
+    #[test]
+    fn test_install_without_themes_dir() {
+        let mut engine = ThemeEngine::new(ThemeMode::Dark);
+        assert!(matches!(engine.install_theme(nord()), Err(ThemeError::IoError(_))));
+        assert!(engine.list_themes().is_empty());
+    }
 }
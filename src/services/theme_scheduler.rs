@@ -0,0 +1,192 @@
+//! Theme Scheduler for GitBrowser.
+//!
+//! Implements `ThemeSchedulerTrait` — decides whether light or dark mode is
+//! due at a given moment, either from fixed local times or from sunrise and
+//! sunset computed for a static latitude/longitude. The app core ticks the
+//! scheduler periodically and forwards changes to `ThemeEngine`.
+
+use std::f64::consts::PI;
+
+use crate::types::settings::ThemeMode;
+use crate::types::theme::{ScheduleMode, ThemeScheduleSettings};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Julian date of the UNIX epoch.
+const UNIX_EPOCH_JULIAN: f64 = 2_440_587.5;
+
+/// Julian date of J2000.0 (2000-01-01 12:00 TT).
+const J2000: f64 = 2_451_545.0;
+
+/// Trait defining theme scheduling operations.
+pub trait ThemeSchedulerTrait {
+    fn update_settings(&mut self, settings: ThemeScheduleSettings);
+    fn get_settings(&self) -> &ThemeScheduleSettings;
+    /// Local time offset from UTC used for `FixedTimes` schedules.
+    fn set_utc_offset_minutes(&mut self, offset: i32);
+    /// Mode due at `now` (UNIX seconds), or `None` when scheduling is off or
+    /// the configured times are invalid.
+    fn scheduled_mode(&self, now: i64) -> Option<ThemeMode>;
+    /// UNIX time of the next light/dark switch after `now`, if any.
+    fn next_transition(&self, now: i64) -> Option<i64>;
+    /// Returns the scheduled mode when it differs from the one returned by
+    /// the previous tick, so callers only touch the theme on transitions.
+    fn tick(&mut self, now: i64) -> Option<ThemeMode>;
+}
+
+/// Theme scheduler over a copy of `ThemeScheduleSettings`.
+pub struct ThemeScheduler {
+    settings: ThemeScheduleSettings,
+    utc_offset_minutes: i32,
+    last_mode: Option<ThemeMode>,
+}
+
+impl ThemeScheduler {
+    pub fn new(settings: ThemeScheduleSettings) -> Self {
+        Self {
+            settings,
+            utc_offset_minutes: 0,
+            last_mode: None,
+        }
+    }
+
+    /// Parses `HH:MM` into minutes after midnight.
+    pub fn parse_time(value: &str) -> Option<i64> {
+        let (h, m) = value.trim().split_once(':')?;
+        let (h, m): (i64, i64) = (h.parse().ok()?, m.parse().ok()?);
+        if !(0..24).contains(&h) || !(0..60).contains(&m) {
+            return None;
+        }
+        Some(h * 60 + m)
+    }
+
+    /// Both fixed times in minutes, if valid and distinct.
+    fn fixed_times(&self) -> Option<(i64, i64)> {
+        let light = Self::parse_time(&self.settings.light_start)?;
+        let dark = Self::parse_time(&self.settings.dark_start)?;
+        (light != dark).then_some((light, dark))
+    }
+
+    /// Sunrise and sunset/polar state for each day around `now`.
+    fn sun_events(&self, now: i64) -> Vec<SunEvent> {
+        let day = now.div_euclid(SECONDS_PER_DAY);
+        (day - 1..=day + 2)
+            .map(|d| sun_times(d, self.settings.latitude, self.settings.longitude))
+            .collect()
+    }
+}
+
+/// Sun state for one day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SunEvent {
+    /// Sunrise and sunset as UNIX seconds.
+    RiseSet(i64, i64),
+    /// The sun stays above the horizon all day.
+    PolarDay,
+    /// The sun stays below the horizon all day.
+    PolarNight,
+}
+
+/// Computes sunrise and sunset for the UTC day `day` (days since the UNIX
+/// epoch) at the given coordinates, using the standard sunrise equation.
+pub fn sun_times(day: i64, latitude: f64, longitude: f64) -> SunEvent {
+    let rad = PI / 180.0;
+    // Mean solar noon in days since J2000, shifted by longitude
+    let noon = day as f64 + 0.5 + UNIX_EPOCH_JULIAN - J2000;
+    let j_star = noon.round() - longitude / 360.0;
+    let m = (357.5291 + 0.985_600_28 * j_star).rem_euclid(360.0);
+    let c = 1.9148 * (m * rad).sin() + 0.02 * (2.0 * m * rad).sin() + 0.0003 * (3.0 * m * rad).sin();
+    let lambda = (m + c + 180.0 + 102.9372).rem_euclid(360.0);
+    let transit = J2000 + j_star + 0.0053 * (m * rad).sin() - 0.0069 * (2.0 * lambda * rad).sin();
+    let sin_decl = (lambda * rad).sin() * (23.4397 * rad).sin();
+    let cos_decl = sin_decl.asin().cos();
+    let phi = latitude.clamp(-90.0, 90.0) * rad;
+    // -0.833° accounts for refraction and the solar disc radius
+    let cos_hour = ((-0.833 * rad).sin() - phi.sin() * sin_decl) / (phi.cos() * cos_decl);
+    if cos_hour.is_nan() || cos_hour > 1.0 {
+        return SunEvent::PolarNight;
+    }
+    if cos_hour < -1.0 {
+        return SunEvent::PolarDay;
+    }
+    let half = cos_hour.acos() / rad / 360.0;
+    let to_unix = |jd: f64| ((jd - UNIX_EPOCH_JULIAN) * SECONDS_PER_DAY as f64).round() as i64;
+    SunEvent::RiseSet(to_unix(transit - half), to_unix(transit + half))
+}
+
+impl ThemeSchedulerTrait for ThemeScheduler {
+    fn update_settings(&mut self, settings: ThemeScheduleSettings) {
+        self.settings = settings;
+        self.last_mode = None;
+    }
+
+    fn get_settings(&self) -> &ThemeScheduleSettings {
+        &self.settings
+    }
+
+    fn set_utc_offset_minutes(&mut self, offset: i32) {
+        self.utc_offset_minutes = offset;
+    }
+
+    fn scheduled_mode(&self, now: i64) -> Option<ThemeMode> {
+        let light = match self.settings.mode {
+            ScheduleMode::Off => return None,
+            ScheduleMode::FixedTimes => {
+                let (light, dark) = self.fixed_times()?;
+                let local = now + self.utc_offset_minutes as i64 * 60;
+                let minute = local.rem_euclid(SECONDS_PER_DAY) / 60;
+                if light < dark {
+                    (light..dark).contains(&minute)
+                } else {
+                    minute >= light || minute < dark
+                }
+            }
+            ScheduleMode::SunriseSunset => {
+                let day = now.div_euclid(SECONDS_PER_DAY);
+                let (lat, lon) = (self.settings.latitude, self.settings.longitude);
+                sun_times(day, lat, lon) == SunEvent::PolarDay
+                    || self.sun_events(now).iter().any(|e| {
+                        matches!(e, SunEvent::RiseSet(rise, set) if (*rise..*set).contains(&now))
+                    })
+            }
+        };
+        Some(if light { ThemeMode::Light } else { ThemeMode::Dark })
+    }
+
+    fn next_transition(&self, now: i64) -> Option<i64> {
+        match self.settings.mode {
+            ScheduleMode::Off => None,
+            ScheduleMode::FixedTimes => {
+                let (light, dark) = self.fixed_times()?;
+                let offset = self.utc_offset_minutes as i64 * 60;
+                let local = now + offset;
+                let midnight = local - local.rem_euclid(SECONDS_PER_DAY);
+                [light, dark]
+                    .iter()
+                    .flat_map(|m| [midnight + m * 60, midnight + SECONDS_PER_DAY + m * 60])
+                    .filter(|t| *t > local)
+                    .min()
+                    .map(|t| t - offset)
+            }
+            ScheduleMode::SunriseSunset => self
+                .sun_events(now)
+                .into_iter()
+                .filter_map(|e| match e {
+                    SunEvent::RiseSet(rise, set) => Some([rise, set]),
+                    _ => None,
+                })
+                .flatten()
+                .filter(|t| *t > now)
+                .min(),
+        }
+    }
+
+    fn tick(&mut self, now: i64) -> Option<ThemeMode> {
+        let mode = self.scheduled_mode(now);
+        if mode == self.last_mode {
+            return None;
+        }
+        self.last_mode = mode.clone();
+        mode
+    }
+}
@@ -0,0 +1,240 @@
+//! Staged installation of verified updates.
+//!
+//! `stage` unpacks a verified download to `<exe>.new` next to the running
+//! executable. The next start swaps it in, keeps the running build as
+//! `<exe>.old` and relaunches. The new build counts its starts until it
+//! confirms it came up (`confirm_started`, once the UI or the RPC server is
+//! ready); one that keeps failing to get there is rolled back on the
+//! following start. The previous build stays for a manual rollback until
+//! the next update.
+//! Progress is kept in `<exe>.update.json` so it survives restarts.
+//!
+//! Only renames are used on the executable: a running image can be renamed
+//! on every platform, but not replaced or deleted on Windows.
+
+use std::ffi::OsString;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::types::errors::UpdateError;
+use crate::types::update::{InstallState, ReadyUpdate, StartupAction};
+
+/// Starts a new build gets to confirm before it is rolled back.
+pub const MAX_UNCONFIRMED_LAUNCHES: u32 = 2;
+
+/// Largest executable taken from an update package.
+const MAX_EXECUTABLE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Package formats that would need an unpacker this module does not have.
+const UNSUPPORTED_PACKAGES: &[&str] = &["gz", "tgz", "xz", "bz2", "tar", "dmg", "msi", "pkg", "deb", "rpm"];
+
+/// Trait defining staged update installation.
+pub trait UpdateInstallerTrait {
+    /// Puts the executable from a verified update next to the running one;
+    /// it replaces the running build on the next start.
+    fn stage(&mut self, update: &ReadyUpdate) -> Result<(), UpdateError>;
+    /// Run first thing on startup with the running build's version. Swaps
+    /// in a staged update, or rolls back a new build that did not start.
+    fn on_startup(&mut self, running_version: &str) -> Result<StartupAction, UpdateError>;
+    /// Marks the running build as started successfully.
+    fn confirm_started(&mut self) -> Result<(), UpdateError>;
+    /// Puts the previous build back; takes effect on the next start.
+    fn rollback(&mut self) -> Result<(), UpdateError>;
+    fn state(&self) -> &InstallState;
+}
+
+/// Installer for one executable; state is kept next to it.
+pub struct UpdateInstaller {
+    exe: PathBuf,
+    state: InstallState,
+}
+
+impl UpdateInstaller {
+    pub fn new(exe: impl Into<PathBuf>) -> Self {
+        let exe = exe.into();
+        let mut installer = Self { exe, state: InstallState::default() };
+        // A missing or unreadable state file means nothing is in progress
+        installer.state = std::fs::read_to_string(installer.sibling(".update.json"))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        installer
+    }
+
+    /// Installer for the executable of this process.
+    pub fn for_current_exe() -> Self {
+        Self::new(std::env::current_exe().unwrap_or_else(|_| PathBuf::from("gitbrowser")))
+    }
+
+    /// Path of the executable updates are installed over.
+    pub fn executable(&self) -> &Path {
+        &self.exe
+    }
+
+    /// `<exe><suffix>` in the executable's directory.
+    fn sibling(&self, suffix: &str) -> PathBuf {
+        let mut name = self.exe.file_name().map(OsString::from).unwrap_or_default();
+        name.push(suffix);
+        self.exe.with_file_name(name)
+    }
+
+    fn save(&self) -> Result<(), UpdateError> {
+        let json = serde_json::to_string_pretty(&self.state).map_err(|e| UpdateError::InstallFailed(e.to_string()))?;
+        std::fs::write(self.sibling(".update.json"), json).map_err(|e| UpdateError::InstallFailed(e.to_string()))
+    }
+
+    /// The executable inside an update package: the package itself, or the
+    /// entry of a `.zip` named like the running executable.
+    fn executable_from(&self, package: &Path) -> Result<Vec<u8>, UpdateError> {
+        let failed = UpdateError::InstallFailed;
+        let extension = package.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+        if UNSUPPORTED_PACKAGES.contains(&extension.as_str()) {
+            return Err(failed(format!("unsupported package format: .{}", extension)));
+        }
+        let bytes = std::fs::read(package).map_err(|e| failed(format!("cannot read {}: {}", package.display(), e)))?;
+        if extension != "zip" {
+            return Ok(bytes);
+        }
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| failed(e.to_string()))?;
+        let wanted = self.exe.file_name().ok_or_else(|| failed("executable has no file name".to_string()))?;
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i).map_err(|e| failed(e.to_string()))?;
+            let matches = entry.enclosed_name().is_some_and(|p| p.file_name() == Some(wanted));
+            if !matches || entry.is_dir() {
+                continue;
+            }
+            if entry.size() > MAX_EXECUTABLE_BYTES {
+                return Err(failed(format!("{} is too large", entry.name())));
+            }
+            let mut binary = Vec::with_capacity(entry.size() as usize);
+            entry.take(MAX_EXECUTABLE_BYTES).read_to_end(&mut binary).map_err(|e| failed(e.to_string()))?;
+            return Ok(binary);
+        }
+        Err(failed(format!("package has no {}", wanted.to_string_lossy())))
+    }
+
+    /// Replaces the executable with the staged build, keeping the running
+    /// one as `<exe>.old`.
+    fn swap_in(&mut self, running_version: &str, staged_version: String) -> Result<(), UpdateError> {
+        let failed = |e: std::io::Error| UpdateError::InstallFailed(e.to_string());
+        let (old, new) = (self.sibling(".old"), self.sibling(".new"));
+        if old.exists() {
+            std::fs::remove_file(&old).map_err(failed)?;
+        }
+        std::fs::rename(&self.exe, &old).map_err(failed)?;
+        if let Err(e) = std::fs::rename(&new, &self.exe) {
+            let _ = std::fs::rename(&old, &self.exe);
+            return Err(failed(e));
+        }
+        self.state = InstallState {
+            staged: None,
+            pending: Some(staged_version),
+            launches: 0,
+            previous: Some(running_version.to_string()),
+        };
+        self.save()
+    }
+
+    /// Moves `<exe>.old` back into place. The replaced build is parked as
+    /// `<exe>.rollback` (it may be running) and deleted on the next start.
+    fn restore_previous(&mut self) -> Result<(), UpdateError> {
+        let failed = |e: std::io::Error| UpdateError::InstallFailed(e.to_string());
+        let old = self.sibling(".old");
+        if !old.is_file() {
+            return Err(UpdateError::InstallFailed("no previous version to roll back to".to_string()));
+        }
+        let parked = self.sibling(".rollback");
+        let _ = std::fs::remove_file(&parked);
+        std::fs::rename(&self.exe, &parked).map_err(failed)?;
+        if let Err(e) = std::fs::rename(&old, &self.exe) {
+            let _ = std::fs::rename(&parked, &self.exe);
+            return Err(failed(e));
+        }
+        // A staged build would undo the rollback on the next start
+        let _ = std::fs::remove_file(self.sibling(".new"));
+        self.state = InstallState::default();
+        self.save()
+    }
+}
+
+impl UpdateInstallerTrait for UpdateInstaller {
+    fn stage(&mut self, update: &ReadyUpdate) -> Result<(), UpdateError> {
+        let binary = self.executable_from(&update.path)?;
+        if binary.is_empty() {
+            return Err(UpdateError::InstallFailed("update package is empty".to_string()));
+        }
+        let (partial, new) = (self.sibling(".new.part"), self.sibling(".new"));
+        let written = std::fs::write(&partial, &binary).and_then(|_| {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&partial, std::fs::Permissions::from_mode(0o755))?;
+            }
+            std::fs::rename(&partial, &new)
+        });
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&partial);
+            return Err(UpdateError::InstallFailed(format!("cannot write next to {}: {}", self.exe.display(), e)));
+        }
+        self.state.staged = Some(update.info.version.clone());
+        self.save()
+    }
+
+    fn on_startup(&mut self, running_version: &str) -> Result<StartupAction, UpdateError> {
+        let _ = std::fs::remove_file(self.sibling(".rollback"));
+
+        if let Some(pending) = self.state.pending.clone() {
+            if pending == running_version {
+                if self.state.launches >= MAX_UNCONFIRMED_LAUNCHES {
+                    self.restore_previous()?;
+                    return Ok(StartupAction::RolledBack { from: pending });
+                }
+                self.state.launches += 1;
+                self.save()?;
+                return Ok(StartupAction::Continue);
+            }
+            // Another build runs (e.g. reinstalled by hand): nothing left to confirm
+            self.state.pending = None;
+            self.state.launches = 0;
+            self.save()?;
+        }
+
+        if let Some(staged) = self.state.staged.clone() {
+            if self.sibling(".new").is_file() {
+                self.swap_in(running_version, staged)?;
+                return Ok(StartupAction::Relaunch);
+            }
+            self.state.staged = None;
+            self.save()?;
+        }
+        Ok(StartupAction::Continue)
+    }
+
+    fn confirm_started(&mut self) -> Result<(), UpdateError> {
+        if self.state.pending.is_none() {
+            return Ok(());
+        }
+        self.state.pending = None;
+        self.state.launches = 0;
+        self.save()
+    }
+
+    fn rollback(&mut self) -> Result<(), UpdateError> {
+        self.restore_previous()
+    }
+
+    fn state(&self) -> &InstallState {
+        &self.state
+    }
+}
+
+/// Starts the executable at `exe` with this process's arguments. The caller
+/// exits afterwards.
+pub fn relaunch(exe: &Path) -> Result<(), UpdateError> {
+    Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| UpdateError::InstallFailed(format!("cannot start {}: {}", exe.display(), e)))
+}
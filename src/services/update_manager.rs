@@ -1,11 +1,40 @@
 //! Update Manager for GitBrowser.
 //!
 //! Checks for updates via GitHub Releases API, downloads and verifies updates.
+//! A download is only marked installable once its SHA-256 checksum matches
+//! the release and its minisign signature verifies against the public key
+//! built into the binary; anything else is deleted. The key is set at build
+//! time from `GITBROWSER_UPDATE_PUBLIC_KEY` by the release workflow, which
+//! signs the release assets with its secret half. Builds without it cannot
+//! install updates.
+//!
+//! Releases are sorted into channels by tag: `-nightly` builds, other
+//! prereleases (beta), and plain versions (stable). Updates never go to an
+//! older version, so leaving a less stable channel keeps the running build
+//! until the new channel passes it.
+
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use std::time::Duration;
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use ring::digest;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde_json::Value;
 
+use crate::services::github_integration::send_request;
 use crate::types::errors::UpdateError;
-use crate::types::update::UpdateInfo;
+use crate::types::update::{ReadyUpdate, UpdateChannel, UpdateInfo};
+
+/// Minisign public key release artifacts are signed with, if this build
+/// was given one.
+pub const UPDATE_PUBLIC_KEY: Option<&str> = option_env!("GITBROWSER_UPDATE_PUBLIC_KEY");
+
+/// Recent releases of GitBrowser, newest first.
+const RELEASES_URL: &str = "https://api.github.com/repos/gothtr/gitbrowser/releases?per_page=30";
+
+/// Upper bound for one update download.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
 
 /// Trait defining update management operations.
 pub trait UpdateManagerTrait {
@@ -14,12 +43,23 @@ pub trait UpdateManagerTrait {
     fn get_current_version(&self) -> &str;
     fn set_auto_check_enabled(&mut self, enabled: bool);
     fn is_auto_check_enabled(&self) -> bool;
+    fn channel(&self) -> UpdateChannel;
+    fn set_channel(&mut self, channel: UpdateChannel);
+    /// Downloads `info` into the download directory and verifies it.
+    /// Returns the path of the verified file.
+    fn download_update(&mut self, info: &UpdateInfo) -> Result<PathBuf, UpdateError>;
+    /// The last download that passed verification, if any.
+    fn ready_update(&self) -> Option<&ReadyUpdate>;
 }
 
 /// Update manager implementation.
 pub struct UpdateManager {
     current_version: String,
     auto_check_enabled: bool,
+    channel: UpdateChannel,
+    download_dir: PathBuf,
+    public_key: Option<String>,
+    ready: Option<ReadyUpdate>,
 }
 
 impl UpdateManager {
@@ -27,20 +67,70 @@ impl UpdateManager {
         Self {
             current_version: env!("CARGO_PKG_VERSION").to_string(),
             auto_check_enabled: true,
+            channel: UpdateChannel::Stable,
+            download_dir: crate::platform::get_cache_dir().join("updates"),
+            public_key: UPDATE_PUBLIC_KEY.map(str::trim).filter(|key| !key.is_empty()).map(str::to_string),
+            ready: None,
         }
     }
 
+    /// Sets the directory updates are downloaded into.
+    pub fn with_download_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.download_dir = dir.into();
+        self
+    }
+
+    /// Replaces the built-in signing key, for tests and self-built releases.
+    pub fn with_public_key(mut self, public_key: &str) -> Self {
+        self.public_key = Some(public_key.to_string());
+        self
+    }
+
     /// Compares two semver strings. Returns true if `latest` is newer than `current`.
     pub fn is_newer_version(current: &str, latest: &str) -> bool {
-        let parse = |v: &str| -> Vec<u32> {
-            v.trim_start_matches('v')
-                .split('.')
-                .filter_map(|s| s.parse().ok())
-                .collect()
-        };
-        let c = parse(current);
-        let l = parse(latest);
-        l > c
+        compare_versions(latest, current) == Ordering::Greater
+    }
+
+    /// `download_update` with the HTTP GET injected, so verification can be
+    /// tested without a network. `fetch` returns the body of a URL.
+    pub fn download_update_with(
+        &mut self,
+        info: &UpdateInfo,
+        mut fetch: impl FnMut(&str) -> Result<Vec<u8>, UpdateError>,
+    ) -> Result<PathBuf, UpdateError> {
+        self.ready = None;
+        let public_key = self.public_key.clone()
+            .ok_or_else(|| UpdateError::SignatureInvalid("this build has no update signing key".to_string()))?;
+        let file_name = file_name_of(&info.download_url)?;
+        let signature = fetch(&info.signature_url)?;
+        let signature = String::from_utf8(signature)
+            .map_err(|_| UpdateError::SignatureInvalid("signature file is not text".to_string()))?;
+        let data = fetch(&info.download_url)?;
+
+        std::fs::create_dir_all(&self.download_dir)
+            .map_err(|e| UpdateError::InstallFailed(e.to_string()))?;
+        let path = self.download_dir.join(&file_name);
+        let partial = self.download_dir.join(format!("{}.part", file_name));
+        std::fs::write(&partial, &data).map_err(|e| UpdateError::InstallFailed(e.to_string()))?;
+
+        // Checked against the file as written, so a short write cannot pass
+        let verified = std::fs::read(&partial)
+            .map_err(|e| UpdateError::InstallFailed(e.to_string()))
+            .and_then(|written| {
+                let actual = sha256_hex(&written);
+                if actual != info.sha256.trim().to_lowercase() {
+                    return Err(UpdateError::ChecksumMismatch(format!("expected {}, got {}", info.sha256, actual)));
+                }
+                verify_signature(&written, &signature, &public_key)
+            });
+        if let Err(e) = verified {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+        std::fs::rename(&partial, &path).map_err(|e| UpdateError::InstallFailed(e.to_string()))?;
+
+        self.ready = Some(ReadyUpdate { info: info.clone(), path: path.clone() });
+        Ok(path)
     }
 }
 
@@ -52,17 +142,13 @@ impl Default for UpdateManager {
 
 impl UpdateManagerTrait for UpdateManager {
     fn check_for_updates(&self) -> Result<Option<UpdateInfo>, UpdateError> {
-        // In a full implementation, this would call the GitHub Releases API.
-        // For now, return None (no update available).
-        Ok(None)
+        check_releases(self.channel, &self.current_version)
     }
 
     fn verify_checksum(&self, file_path: &str, expected_sha256: &str) -> Result<bool, UpdateError> {
         let data = std::fs::read(file_path)
             .map_err(|e| UpdateError::NetworkError(e.to_string()))?;
-        let actual = digest::digest(&digest::SHA256, &data);
-        let actual_hex = hex_encode(actual.as_ref());
-        Ok(actual_hex == expected_sha256.to_lowercase())
+        Ok(sha256_hex(&data) == expected_sha256.to_lowercase())
     }
 
     fn get_current_version(&self) -> &str {
@@ -76,6 +162,234 @@ impl UpdateManagerTrait for UpdateManager {
     fn is_auto_check_enabled(&self) -> bool {
         self.auto_check_enabled
     }
+
+    fn channel(&self) -> UpdateChannel {
+        self.channel
+    }
+
+    fn set_channel(&mut self, channel: UpdateChannel) {
+        self.channel = channel;
+    }
+
+    fn download_update(&mut self, info: &UpdateInfo) -> Result<PathBuf, UpdateError> {
+        self.download_update_with(info, fetch_bytes)
+    }
+
+    fn ready_update(&self) -> Option<&ReadyUpdate> {
+        // A file removed since (e.g. by a cache cleaner) is no longer installable
+        self.ready.as_ref().filter(|r| r.path.is_file())
+    }
+}
+
+/// Fetches the release list and picks the update for `channel`. Needs no
+/// `UpdateManager`, so callers can run it without holding the app lock.
+pub fn check_releases(channel: UpdateChannel, current_version: &str) -> Result<Option<UpdateInfo>, UpdateError> {
+    let response = send_request("GET", RELEASES_URL, None, None, &[])
+        .map_err(|e| UpdateError::NetworkError(e.to_string()))?;
+    if !(200..300).contains(&response.status) {
+        return Err(UpdateError::NetworkError(format!("HTTP {} listing releases", response.status)));
+    }
+    Ok(select_update(&response.body, channel, current_version, &release_target()))
+}
+
+/// `<os>-<arch>` as used in release asset names, e.g. `linux-x86_64`.
+pub fn release_target() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// Channel of a release: `-nightly` tags are nightly, other prerelease tags
+/// or releases flagged as prerelease are beta.
+pub fn release_channel(tag: &str, prerelease: bool) -> UpdateChannel {
+    match version_parts(tag).1.first() {
+        Some(id) if id.eq_ignore_ascii_case("nightly") => UpdateChannel::Nightly,
+        Some(_) => UpdateChannel::Beta,
+        None if prerelease => UpdateChannel::Beta,
+        None => UpdateChannel::Stable,
+    }
+}
+
+/// Newest release in a GitHub `/releases` list that `channel` is offered,
+/// is newer than `current_version` and has a signed asset for `target`.
+pub fn select_update(releases: &Value, channel: UpdateChannel, current_version: &str, target: &str) -> Option<UpdateInfo> {
+    releases.as_array()?
+        .iter()
+        .filter(|r| !r["draft"].as_bool().unwrap_or(false))
+        .filter_map(|r| release_info(r, target))
+        .filter(|info| info.channel <= channel && UpdateManager::is_newer_version(current_version, &info.version))
+        .max_by(|a, b| compare_versions(&a.version, &b.version))
+}
+
+/// Warning for switching to `channel` while running `current_version`:
+/// set when the running build is from a less stable channel, since it
+/// stays installed until `channel` has a newer release.
+pub fn channel_switch_warning(current_version: &str, channel: UpdateChannel) -> Option<String> {
+    let running = release_channel(current_version, false);
+    (running > channel).then(|| format!(
+        "GitBrowser {} is a {} build. It stays installed until the {} channel has a newer release; \
+         installing an older {} build by hand may leave data it cannot read.",
+        current_version, running.as_str(), channel.as_str(), channel.as_str(),
+    ))
+}
+
+/// Update info for `release` if it carries an asset for `target` with a
+/// SHA-256 digest and a `.minisig` signature next to it.
+fn release_info(release: &Value, target: &str) -> Option<UpdateInfo> {
+    let tag = release["tag_name"].as_str()?;
+    let assets = release["assets"].as_array()?;
+    let name_of = |asset: &Value| asset["name"].as_str().unwrap_or_default().to_string();
+    let asset = assets.iter().find(|a| {
+        let name = name_of(a);
+        name.contains(target) && !name.ends_with(".minisig")
+    })?;
+    let signature_name = format!("{}.minisig", name_of(asset));
+    let signature = assets.iter().find(|a| name_of(a) == signature_name)?;
+    // GitHub publishes asset digests as `sha256:<hex>`
+    let sha256 = asset["digest"].as_str()?.strip_prefix("sha256:")?;
+    Some(UpdateInfo {
+        version: tag.trim_start_matches('v').to_string(),
+        changelog: release["body"].as_str().unwrap_or_default().to_string(),
+        download_url: asset["browser_download_url"].as_str()?.to_string(),
+        signature_url: signature["browser_download_url"].as_str()?.to_string(),
+        sha256: sha256.to_string(),
+        published_at: release["published_at"].as_str().unwrap_or_default().to_string(),
+        file_size: asset["size"].as_u64().unwrap_or(0),
+        channel: release_channel(tag, release["prerelease"].as_bool().unwrap_or(false)),
+    })
+}
+
+/// Numeric core and prerelease identifiers of `v1.2.3-beta.1+build`.
+fn version_parts(version: &str) -> (Vec<u64>, Vec<&str>) {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split('+').next().unwrap_or_default();
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, pre.split('.').collect()),
+        None => (version, Vec::new()),
+    };
+    (core.split('.').filter_map(|s| s.parse().ok()).collect(), pre)
+}
+
+/// Orders versions by semver precedence, e.g. `1.0.0-beta.2 < 1.0.0-rc.1 < 1.0.0`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let ((core_a, pre_a), (core_b, pre_b)) = (version_parts(a), version_parts(b));
+    let len = core_a.len().max(core_b.len());
+    let part = |core: &[u64], i: usize| core.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| part(&core_a, i).cmp(&part(&core_b, i)))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| match (pre_a.is_empty(), pre_b.is_empty()) {
+            (true, true) => Ordering::Equal,
+            // A release ranks above its prereleases
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => compare_identifiers(&pre_a, &pre_b),
+        })
+}
+
+fn compare_identifiers(a: &[&str], b: &[&str]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if order.is_ne() {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Checks a minisign signature of `data`: the Ed25519 signature over the
+/// file's BLAKE2b-512 hash (minisign's default) or over the file itself
+/// (`minisign -S -l`), and the global signature over the trusted comment.
+/// `public_key` is the key line of a `.pub` file, or the whole file.
+pub fn verify_signature(data: &[u8], signature: &str, public_key: &str) -> Result<(), UpdateError> {
+    let invalid = |msg: &str| UpdateError::SignatureInvalid(msg.to_string());
+    let key_line = public_key.lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty() && !l.starts_with("untrusted comment:"))
+        .ok_or_else(|| invalid("empty public key"))?;
+    let key = BASE64.decode(key_line).map_err(|_| invalid("public key is not base64"))?;
+    if key.len() != 42 || &key[..2] != b"Ed" {
+        return Err(invalid("not an Ed25519 minisign public key"));
+    }
+    let (key_id, key) = (&key[2..10], &key[10..]);
+
+    let mut lines = signature.lines().map(str::trim_end);
+    let (Some(untrusted), Some(sig_line), Some(trusted), Some(global_line)) =
+        (lines.next(), lines.next(), lines.next(), lines.next())
+    else {
+        return Err(invalid("truncated signature file"));
+    };
+    if !untrusted.starts_with("untrusted comment:") {
+        return Err(invalid("malformed signature file"));
+    }
+    let trusted = trusted.strip_prefix("trusted comment: ").ok_or_else(|| invalid("malformed signature file"))?;
+    let sig = BASE64.decode(sig_line.trim()).map_err(|_| invalid("signature is not base64"))?;
+    if sig.len() != 74 {
+        return Err(invalid("signature has the wrong length"));
+    }
+    let prehashed = match &sig[..2] {
+        b"Ed" => false,
+        b"ED" => true,
+        _ => return Err(invalid("unknown signature algorithm")),
+    };
+    if &sig[2..10] != key_id {
+        return Err(invalid("signed with a different key"));
+    }
+    let sig = &sig[10..];
+
+    let verifier = UnparsedPublicKey::new(&ED25519, key);
+    let hash;
+    let signed = if prehashed {
+        hash = blake2b_simd::Params::new().hash_length(64).hash(data);
+        hash.as_bytes()
+    } else {
+        data
+    };
+    verifier.verify(signed, sig).map_err(|_| invalid("file signature does not match"))?;
+    let global = BASE64.decode(global_line.trim()).map_err(|_| invalid("global signature is not base64"))?;
+    let signed_comment = [sig, trusted.as_bytes()].concat();
+    verifier.verify(&signed_comment, &global).map_err(|_| invalid("trusted comment signature does not match"))
+}
+
+/// Last path segment of `url`, checked to be a plain file name.
+fn file_name_of(url: &str) -> Result<String, UpdateError> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| UpdateError::ParseError(e.to_string()))?;
+    let name = parsed.path_segments().and_then(|mut s| s.next_back()).unwrap_or_default();
+    let safe = !name.is_empty() && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+'));
+    if safe {
+        Ok(name.to_string())
+    } else {
+        Err(UpdateError::ParseError(format!("unusable download file name in {}", url)))
+    }
+}
+
+fn fetch_bytes(url: &str) -> Result<Vec<u8>, UpdateError> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| UpdateError::NetworkError(e.to_string()))?;
+    rt.block_on(async {
+        let client = reqwest::Client::builder()
+            .timeout(DOWNLOAD_TIMEOUT)
+            .user_agent(concat!("GitBrowser/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| UpdateError::NetworkError(e.to_string()))?;
+        let resp = client.get(url).send().await.map_err(|e| UpdateError::NetworkError(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(UpdateError::NetworkError(format!("HTTP {} for {}", resp.status().as_u16(), url)));
+        }
+        let bytes = resp.bytes().await.map_err(|e| UpdateError::NetworkError(e.to_string()))?;
+        Ok(bytes.to_vec())
+    })
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex_encode(digest::digest(&digest::SHA256, data).as_ref())
 }
 
 fn hex_encode(bytes: &[u8]) -> String {
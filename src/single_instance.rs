@@ -0,0 +1,71 @@
+//! Hands links to the GitBrowser window already open on a profile.
+//!
+//! The browser listens on `instance.sock` in the profile directory (a named
+//! pipe on Windows; see `rpc_socket`). Another `gitbrowser` started for the
+//! same profile, e.g. by the OS for a `gitbrowser://` link, sends its URL
+//! arguments there with [`hand_off`] and exits; the running browser opens
+//! them and comes to the front. Each hand-off is one line,
+//! `{"urls": [...]}`, answered with `{"ok": true}` once taken.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(unix)]
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::rpc_socket::{self, OnConnect, SocketListener};
+
+/// Name of the endpoint in the profile directory.
+const ENDPOINT_NAME: &str = "instance";
+
+/// How long a hand-off waits for the running browser to answer.
+#[cfg(unix)]
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Takes hand-offs for the profile whose data lives in `dir` until the
+/// listener is dropped. `on_urls` gets the URLs of each; none means the
+/// window should just come to the front.
+///
+/// # Errors
+/// `AddrInUse` if another browser is listening for the profile.
+pub fn listen(dir: &Path, on_urls: impl Fn(Vec<String>) + Send + Sync + 'static) -> io::Result<SocketListener> {
+    let on_urls = Arc::new(on_urls);
+    let on_connect: OnConnect = Arc::new(move |lines, sink| {
+        for line in lines.flatten() {
+            let urls = serde_json::from_str::<Value>(&line)
+                .ok()
+                .and_then(|v| serde_json::from_value::<Vec<String>>(v.get("urls")?.clone()).ok());
+            let taken = urls.is_some();
+            if let Some(urls) = urls {
+                on_urls(urls);
+            }
+            sink(&json!({"ok": taken}));
+        }
+    });
+    rpc_socket::listen(&rpc_socket::endpoint_named(dir, ENDPOINT_NAME), on_connect)
+}
+
+/// Gives `urls` to the browser open on the profile whose data lives in
+/// `dir`. Fails if none is listening or it does not take them.
+pub fn hand_off(dir: &Path, urls: &[String]) -> io::Result<()> {
+    let endpoint = rpc_socket::endpoint_named(dir, ENDPOINT_NAME);
+    #[cfg(unix)]
+    let mut stream = {
+        let stream = std::os::unix::net::UnixStream::connect(&endpoint)?;
+        stream.set_read_timeout(Some(ANSWER_TIMEOUT))?;
+        stream
+    };
+    #[cfg(windows)]
+    let mut stream = std::fs::OpenOptions::new().read(true).write(true).open(&endpoint)?;
+
+    writeln!(stream, "{}", json!({"urls": urls}))?;
+    stream.flush()?;
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    match serde_json::from_str::<Value>(&answer) {
+        Ok(v) if v["ok"] == true => Ok(()),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "the running browser did not take the links")),
+    }
+}
@@ -26,6 +26,14 @@ pub struct AIContext {
     pub page_url: Option<String>,
 }
 
+/// Part of an answer the assistant panel sends back for an `ai.ask`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AIAnswerPart {
+    pub text: String,
+    /// Set on the last part.
+    pub done: bool,
+}
+
 /// A single message in the AI chat history.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIChatMessage {
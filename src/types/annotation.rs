@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// A highlighted passage (optionally with a note) in a reader-mode article.
+///
+/// Annotations are keyed by article URL and anchored by the highlighted text
+/// plus a little surrounding context so they can be re-applied when the
+/// article is reopened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: String,
+    /// Article URL (fragment stripped).
+    pub url: String,
+    /// The exact highlighted text.
+    pub anchor_text: String,
+    /// Text immediately before the highlight, used to disambiguate repeats.
+    pub prefix: String,
+    /// Text immediately after the highlight.
+    pub suffix: String,
+    pub note: Option<String>,
+    /// Highlight color as `#rgb` or `#rrggbb`.
+    pub color: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
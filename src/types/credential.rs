@@ -13,6 +13,17 @@ pub struct CredentialEntry {
     pub updated_at: i64,
 }
 
+/// An HTTP authentication challenge (Basic, Digest) a site made.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuthChallenge {
+    /// Origin asking for credentials (`https://host[:port]`).
+    pub origin: String,
+    /// The protection space the server named; empty if it named none.
+    pub realm: String,
+    /// The credentials just given for it were refused.
+    pub is_retry: bool,
+}
+
 /// Options for generating a random password.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PasswordGenOptions {
@@ -23,4 +23,19 @@ pub struct DownloadItem {
     pub mime_type: Option<String>,
     pub started_at: i64,
     pub completed_at: Option<i64>,
+    /// Page the download was started from.
+    #[serde(default)]
+    pub referrer: Option<String>,
+}
+
+/// A download as the webview starts it, before it has a file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DownloadRequest {
+    pub url: String,
+    /// File name the server or link suggested; may be empty.
+    pub suggested_name: String,
+    pub referrer: Option<String>,
+    /// Total size, if the response said.
+    pub size: Option<u64>,
+    pub mime_type: Option<String>,
 }
@@ -40,6 +40,8 @@ pub enum CryptoError {
     RandomGeneration(String),
     /// The provided key is invalid.
     InvalidKey(String),
+    /// No saved login has this ID.
+    CredentialNotFound(String),
 }
 
 impl fmt::Display for CryptoError {
@@ -52,6 +54,7 @@ impl fmt::Display for CryptoError {
                 write!(f, "Random generation failed: {}", msg)
             }
             CryptoError::InvalidKey(msg) => write!(f, "Invalid key: {}", msg),
+            CryptoError::CredentialNotFound(id) => write!(f, "Credential not found: {}", id),
         }
     }
 }
@@ -110,6 +113,112 @@ impl fmt::Display for HistoryError {
 
 impl std::error::Error for HistoryError {}
 
+// === ReadingListError ===
+
+/// Errors related to the offline reading list.
+#[derive(Debug)]
+pub enum ReadingListError {
+    /// Reading list item with the given ID was not found.
+    NotFound(String),
+    /// Database operation failed.
+    DatabaseError(String),
+}
+
+impl fmt::Display for ReadingListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadingListError::NotFound(id) => write!(f, "Reading list item not found: {}", id),
+            ReadingListError::DatabaseError(msg) => {
+                write!(f, "Reading list database error: {}", msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReadingListError {}
+
+// === AnnotationError ===
+
+/// Errors related to reader annotations and highlights.
+#[derive(Debug)]
+pub enum AnnotationError {
+    /// Annotation with the given ID was not found.
+    NotFound(String),
+    /// The highlighted text anchor is empty or too long.
+    InvalidAnchor(String),
+    /// The highlight color is not a `#rgb`/`#rrggbb` hex value.
+    InvalidColor(String),
+    /// Database operation failed.
+    DatabaseError(String),
+}
+
+impl fmt::Display for AnnotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnnotationError::NotFound(id) => write!(f, "Annotation not found: {}", id),
+            AnnotationError::InvalidAnchor(msg) => write!(f, "Invalid annotation anchor: {}", msg),
+            AnnotationError::InvalidColor(color) => write!(f, "Invalid annotation color: {}", color),
+            AnnotationError::DatabaseError(msg) => {
+                write!(f, "Annotation database error: {}", msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnnotationError {}
+
+// === SiteSettingsError ===
+
+/// Errors related to per-site setting overrides.
+#[derive(Debug)]
+pub enum SiteSettingsError {
+    /// The site is empty or not a bare host.
+    InvalidSite(String),
+    /// The setting key contains unsupported characters.
+    InvalidKey(String),
+    /// The value is not one the setting key takes.
+    InvalidValue(String),
+    /// Database operation failed.
+    DatabaseError(String),
+}
+
+impl fmt::Display for SiteSettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SiteSettingsError::InvalidSite(site) => write!(f, "Invalid site: {}", site),
+            SiteSettingsError::InvalidKey(key) => write!(f, "Invalid site setting key: {}", key),
+            SiteSettingsError::InvalidValue(msg) => write!(f, "Invalid site setting value: {}", msg),
+            SiteSettingsError::DatabaseError(msg) => {
+                write!(f, "Site settings database error: {}", msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SiteSettingsError {}
+
+// === FaviconError ===
+
+/// Errors related to the favicon cache.
+#[derive(Debug)]
+pub enum FaviconError {
+    /// The icon is not an image `data:` URL, or is too large to keep.
+    InvalidIcon(String),
+    /// Database operation failed.
+    DatabaseError(String),
+}
+
+impl fmt::Display for FaviconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaviconError::InvalidIcon(msg) => write!(f, "Invalid favicon: {}", msg),
+            FaviconError::DatabaseError(msg) => write!(f, "Favicon database error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FaviconError {}
+
 // === DownloadError ===
 
 /// Errors related to download management operations.
@@ -242,6 +351,10 @@ pub enum SettingsError {
     InvalidKey(String),
     /// The provided settings value is invalid.
     InvalidValue(String),
+    /// No settings profile with the given name exists.
+    ProfileNotFound(String),
+    /// A settings profile with the given name already exists.
+    ProfileExists(String),
 }
 
 impl fmt::Display for SettingsError {
@@ -255,6 +368,12 @@ impl fmt::Display for SettingsError {
             SettingsError::InvalidValue(msg) => {
                 write!(f, "Invalid settings value: {}", msg)
             }
+            SettingsError::ProfileNotFound(name) => {
+                write!(f, "Settings profile not found: {}", name)
+            }
+            SettingsError::ProfileExists(name) => {
+                write!(f, "Settings profile already exists: {}", name)
+            }
         }
     }
 }
@@ -276,6 +395,8 @@ pub enum AIError {
     RateLimited(String),
     /// The AI provider returned an error.
     ProviderError(String),
+    /// The assistant panel stopped answering an `ai.ask`.
+    NoAnswer,
 }
 
 impl fmt::Display for AIError {
@@ -286,6 +407,7 @@ impl fmt::Display for AIError {
             AIError::NetworkError(msg) => write!(f, "AI network error: {}", msg),
             AIError::RateLimited(msg) => write!(f, "AI rate limited: {}", msg),
             AIError::ProviderError(msg) => write!(f, "AI provider error: {}", msg),
+            AIError::NoAnswer => write!(f, "The assistant did not answer in time"),
         }
     }
 }
@@ -330,6 +452,12 @@ pub enum ExtensionError {
     PermissionDenied(String),
     /// Failed to load the extension.
     LoadError(String),
+    /// A runtime message was rejected (too large, unknown, or queue full).
+    InvalidMessage(String),
+    /// A storage write would exceed the extension's quota.
+    QuotaExceeded(String),
+    /// A packaged extension (.zip/.crx) is malformed or unsafe to unpack.
+    InvalidArchive(String),
 }
 
 impl fmt::Display for ExtensionError {
@@ -343,6 +471,9 @@ impl fmt::Display for ExtensionError {
                 write!(f, "Extension permission denied: {}", msg)
             }
             ExtensionError::LoadError(msg) => write!(f, "Extension load error: {}", msg),
+            ExtensionError::InvalidMessage(msg) => write!(f, "Extension message rejected: {}", msg),
+            ExtensionError::QuotaExceeded(msg) => write!(f, "Extension storage quota exceeded: {}", msg),
+            ExtensionError::InvalidArchive(msg) => write!(f, "Invalid extension archive: {}", msg),
         }
     }
 }
@@ -382,6 +513,12 @@ pub enum ThemeError {
     InvalidColor(String),
     /// Failed to parse or apply CSS.
     CssError(String),
+    /// A custom theme file is malformed or overrides unknown variables.
+    InvalidTheme(String),
+    /// No installed theme with the given ID exists.
+    NotFound(String),
+    /// Reading or writing the theme gallery failed.
+    IoError(String),
 }
 
 impl fmt::Display for ThemeError {
@@ -389,6 +526,9 @@ impl fmt::Display for ThemeError {
         match self {
             ThemeError::InvalidColor(color) => write!(f, "Invalid color: {}", color),
             ThemeError::CssError(msg) => write!(f, "CSS error: {}", msg),
+            ThemeError::InvalidTheme(msg) => write!(f, "Invalid theme: {}", msg),
+            ThemeError::NotFound(id) => write!(f, "Theme not found: {}", id),
+            ThemeError::IoError(msg) => write!(f, "Theme I/O error: {}", msg),
         }
     }
 }
@@ -406,6 +546,8 @@ pub enum LocaleError {
     MissingKey(String),
     /// The locale file was not found.
     FileNotFound(String),
+    /// A translation is not a valid MessageFormat pattern.
+    InvalidMessage(String),
 }
 
 impl fmt::Display for LocaleError {
@@ -416,6 +558,7 @@ impl fmt::Display for LocaleError {
             }
             LocaleError::MissingKey(key) => write!(f, "Missing locale key: {}", key),
             LocaleError::FileNotFound(path) => write!(f, "Locale file not found: {}", path),
+            LocaleError::InvalidMessage(msg) => write!(f, "Invalid locale message: {}", msg),
         }
     }
 }
@@ -431,6 +574,14 @@ pub enum CrashError {
     DatabaseError(String),
     /// Failed to recover from a crash.
     RecoveryFailed(String),
+    /// Crash report files could not be read or written.
+    ReportFailed(String),
+    /// Uploading crash reports needs the user's consent first.
+    ConsentRequired,
+    /// Crash reports could not be uploaded.
+    UploadFailed(String),
+    /// No crash log has the given ID.
+    NotFound(String),
 }
 
 impl fmt::Display for CrashError {
@@ -440,6 +591,12 @@ impl fmt::Display for CrashError {
                 write!(f, "Crash recovery database error: {}", msg)
             }
             CrashError::RecoveryFailed(msg) => write!(f, "Crash recovery failed: {}", msg),
+            CrashError::ReportFailed(msg) => write!(f, "Crash report error: {}", msg),
+            CrashError::ConsentRequired => {
+                write!(f, "Crash reports are only sent with consent (privacy.telemetry_consent)")
+            }
+            CrashError::UploadFailed(msg) => write!(f, "Crash report upload failed: {}", msg),
+            CrashError::NotFound(id) => write!(f, "Crash log not found: {}", id),
         }
     }
 }
@@ -455,6 +612,9 @@ pub enum UpdateError {
     NetworkError(String),
     /// The downloaded file's checksum does not match the expected value.
     ChecksumMismatch(String),
+    /// The downloaded file's signature is missing, malformed or made with
+    /// another key.
+    SignatureInvalid(String),
     /// Failed to install the update.
     InstallFailed(String),
     /// Failed to parse update information.
@@ -468,6 +628,9 @@ impl fmt::Display for UpdateError {
             UpdateError::ChecksumMismatch(msg) => {
                 write!(f, "Update checksum mismatch: {}", msg)
             }
+            UpdateError::SignatureInvalid(msg) => {
+                write!(f, "Update signature verification failed: {}", msg)
+            }
             UpdateError::InstallFailed(msg) => {
                 write!(f, "Update installation failed: {}", msg)
             }
@@ -478,6 +641,53 @@ impl fmt::Display for UpdateError {
 
 impl std::error::Error for UpdateError {}
 
+// === SearchEngineError ===
+
+/// Errors related to search engine registry operations.
+#[derive(Debug)]
+pub enum SearchEngineError {
+    /// No search engine with the given ID exists.
+    NotFound(String),
+    /// A search engine with the given ID already exists.
+    AlreadyExists(String),
+    /// The engine ID is empty or contains invalid characters.
+    InvalidId(String),
+    /// The engine with the given ID has an empty display name.
+    InvalidName(String),
+    /// A search or suggestion URL template is invalid.
+    InvalidTemplate(String),
+    /// The keyword trigger is malformed or already used by another engine.
+    InvalidKeyword(String),
+    /// The last remaining search engine cannot be removed.
+    LastEngine,
+}
+
+impl fmt::Display for SearchEngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchEngineError::NotFound(id) => write!(f, "Search engine not found: {}", id),
+            SearchEngineError::AlreadyExists(id) => {
+                write!(f, "Search engine already exists: {}", id)
+            }
+            SearchEngineError::InvalidId(id) => write!(f, "Invalid search engine ID: {}", id),
+            SearchEngineError::InvalidName(id) => {
+                write!(f, "Search engine {} must have a name", id)
+            }
+            SearchEngineError::InvalidTemplate(msg) => {
+                write!(f, "Invalid search URL template: {}", msg)
+            }
+            SearchEngineError::InvalidKeyword(msg) => {
+                write!(f, "Invalid search keyword: {}", msg)
+            }
+            SearchEngineError::LastEngine => {
+                write!(f, "Cannot remove the last search engine")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SearchEngineError {}
+
 // === GitHubError ===
 
 /// Errors related to GitHub integration operations.
@@ -493,6 +703,12 @@ pub enum GitHubError {
     ApiError(String),
     /// The user is not authenticated with GitHub.
     NotAuthenticated,
+    /// The device code expired before the user entered it.
+    DeviceCodeExpired,
+    /// GitHub's rate limit is exhausted; carries the seconds until it resets.
+    RateLimited(u64),
+    /// The GitHub Enterprise settings are incomplete or malformed.
+    InvalidConfig(String),
 }
 
 impl fmt::Display for GitHubError {
@@ -505,8 +721,582 @@ impl fmt::Display for GitHubError {
             GitHubError::NetworkError(msg) => write!(f, "GitHub network error: {}", msg),
             GitHubError::ApiError(msg) => write!(f, "GitHub API error: {}", msg),
             GitHubError::NotAuthenticated => write!(f, "Not authenticated with GitHub"),
+            GitHubError::DeviceCodeExpired => write!(f, "GitHub device code expired"),
+            GitHubError::RateLimited(secs) => {
+                write!(f, "GitHub rate limit exceeded, retry in {}s", secs)
+            }
+            GitHubError::InvalidConfig(msg) => write!(f, "Invalid GitHub configuration: {}", msg),
         }
     }
 }
 
 impl std::error::Error for GitHubError {}
+
+// === SyncError ===
+
+/// Errors related to syncing user data through GitHub.
+#[derive(Debug)]
+pub enum SyncError {
+    /// Sync needs a stored GitHub token.
+    NotAuthenticated,
+    /// Reading or writing local data failed.
+    DatabaseError(String),
+    /// The remote payload could not be decrypted or parsed.
+    InvalidPayload(String),
+    /// Reading or writing the sync gist failed.
+    RemoteError(String),
+    /// No unresolved conflict exists for the collection.
+    ConflictNotFound(String),
+    /// No sync passphrase was set this session.
+    PassphraseRequired,
+    /// The sync passphrase does not open the remote payload.
+    WrongPassphrase,
+    /// GitHub rate-limited the gist request; retry after this many seconds.
+    RateLimited(u64),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::NotAuthenticated => write!(f, "Sync requires a GitHub login"),
+            SyncError::DatabaseError(msg) => write!(f, "Sync database error: {}", msg),
+            SyncError::InvalidPayload(msg) => write!(f, "Invalid sync payload: {}", msg),
+            SyncError::RemoteError(msg) => write!(f, "Sync remote error: {}", msg),
+            SyncError::ConflictNotFound(collection) => write!(f, "No sync conflict for {}", collection),
+            SyncError::PassphraseRequired => write!(f, "Sync requires the sync passphrase"),
+            SyncError::WrongPassphrase => write!(f, "The sync passphrase does not match the synced data"),
+            SyncError::RateLimited(secs) => write!(f, "Sync is rate limited by GitHub, retry in {}s", secs),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+// === EncryptionError ===
+
+/// Errors related to at-rest database encryption.
+#[derive(Debug)]
+pub enum EncryptionError {
+    /// This build has no SQLCipher support (the `sqlcipher` feature).
+    Unsupported,
+    /// The database is keyed from the master password, which was not given.
+    PasswordRequired,
+    /// The key does not open the database.
+    WrongKey,
+    /// The OS keystore could not be read or written.
+    KeystoreError(String),
+    /// Encrypting, decrypting or re-keying the database file failed.
+    MigrationFailed(String),
+    /// Database operation failed.
+    DatabaseError(String),
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncryptionError::Unsupported => {
+                write!(f, "Database encryption is not supported by this build")
+            }
+            EncryptionError::PasswordRequired => {
+                write!(f, "The database is locked with the master password")
+            }
+            EncryptionError::WrongKey => write!(f, "Wrong database key"),
+            EncryptionError::KeystoreError(msg) => write!(f, "Keystore error: {}", msg),
+            EncryptionError::MigrationFailed(msg) => {
+                write!(f, "Database encryption migration failed: {}", msg)
+            }
+            EncryptionError::DatabaseError(msg) => write!(f, "Encrypted database error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+// === KeystoreError ===
+
+/// Errors related to secrets kept in the OS keystore.
+#[derive(Debug)]
+pub enum KeystoreError {
+    /// This build has no OS keystore support (the `keystore` feature).
+    Unavailable,
+    /// The keystore refused the request or could not be reached.
+    AccessFailed(String),
+    /// The stored entry is not a secret written by GitBrowser.
+    InvalidSecret(String),
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeystoreError::Unavailable => write!(f, "The OS keystore is not supported by this build"),
+            KeystoreError::AccessFailed(msg) => write!(f, "OS keystore access failed: {}", msg),
+            KeystoreError::InvalidSecret(name) => write!(f, "Keystore entry {} is not a valid secret", name),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+// === DbWorkerError ===
+
+/// Errors related to the database worker thread.
+#[derive(Debug)]
+pub enum DbWorkerError {
+    /// The worker stopped, or the job panicked, before answering.
+    Stopped,
+    /// The job had not answered when the caller stopped waiting.
+    TimedOut,
+}
+
+impl fmt::Display for DbWorkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbWorkerError::Stopped => write!(f, "Database worker stopped before finishing the job"),
+            DbWorkerError::TimedOut => write!(f, "Database job did not finish in time"),
+        }
+    }
+}
+
+impl std::error::Error for DbWorkerError {}
+
+// === BackupError ===
+
+/// Errors related to database backups.
+#[derive(Debug)]
+pub enum BackupError {
+    /// No backup with this name exists.
+    NotFound(String),
+    /// Reading or writing the backup directory failed.
+    IoError(String),
+    /// Copying the database failed.
+    DatabaseError(String),
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupError::NotFound(name) => write!(f, "Backup not found: {}", name),
+            BackupError::IoError(msg) => write!(f, "Backup I/O error: {}", msg),
+            BackupError::DatabaseError(msg) => write!(f, "Backup database error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
+/// Errors related to user profiles.
+#[derive(Debug)]
+pub enum ProfileError {
+    /// The name is empty, too long or has characters other than letters,
+    /// digits, `-` and `_`.
+    InvalidName(String),
+    /// A profile with this name already exists.
+    AlreadyExists(String),
+    /// No profile with this name exists.
+    NotFound(String),
+    /// Another GitBrowser has the profile open.
+    InUse(String),
+    /// Reading or writing the profile directory failed.
+    IoError(String),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::InvalidName(name) => write!(f, "Invalid profile name: {}", name),
+            ProfileError::AlreadyExists(name) => write!(f, "Profile already exists: {}", name),
+            ProfileError::NotFound(name) => write!(f, "Profile not found: {}", name),
+            ProfileError::InUse(name) => write!(f, "Profile is open in another GitBrowser window: {}", name),
+            ProfileError::IoError(msg) => write!(f, "Profile I/O error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+// === CliError ===
+
+/// Errors in the command-line arguments.
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    /// An option GitBrowser does not know.
+    UnknownOption(String),
+    /// An option given without the value it takes.
+    MissingValue(String),
+    /// A `--settings` value not of the form `key=value`.
+    InvalidSetting(String),
+    /// A `--profile` name that is not a valid profile name.
+    InvalidProfile(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(option) => write!(f, "Unknown option: {}", option),
+            CliError::MissingValue(option) => write!(f, "Missing value for {}", option),
+            CliError::InvalidSetting(value) => write!(f, "Invalid setting '{}', expected key=value", value),
+            CliError::InvalidProfile(name) => write!(f, "Invalid profile name '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+// === GitBrowserError ===
+
+/// Any GitBrowser error: one of the module errors above, or a failure of an
+/// RPC request itself. `code()` names it for frontends, which branch on the
+/// code rather than on the English message.
+#[derive(Debug, thiserror::Error)]
+pub enum GitBrowserError {
+    #[error(transparent)]
+    Tab(#[from] TabError),
+    #[error(transparent)]
+    Crypto(#[from] CryptoError),
+    #[error(transparent)]
+    Bookmark(#[from] BookmarkError),
+    #[error(transparent)]
+    History(#[from] HistoryError),
+    #[error(transparent)]
+    ReadingList(#[from] ReadingListError),
+    #[error(transparent)]
+    Annotation(#[from] AnnotationError),
+    #[error(transparent)]
+    SiteSettings(#[from] SiteSettingsError),
+    #[error(transparent)]
+    Favicon(#[from] FaviconError),
+    #[error(transparent)]
+    Download(#[from] DownloadError),
+    #[error(transparent)]
+    Permission(#[from] PermissionError),
+    #[error(transparent)]
+    Shortcut(#[from] ShortcutError),
+    #[error(transparent)]
+    Session(#[from] SessionError),
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+    #[error(transparent)]
+    AI(#[from] AIError),
+    #[error(transparent)]
+    Privacy(#[from] PrivacyError),
+    #[error(transparent)]
+    Extension(#[from] ExtensionError),
+    #[error(transparent)]
+    Reader(#[from] ReaderError),
+    #[error(transparent)]
+    Theme(#[from] ThemeError),
+    #[error(transparent)]
+    Locale(#[from] LocaleError),
+    #[error(transparent)]
+    Crash(#[from] CrashError),
+    #[error(transparent)]
+    Update(#[from] UpdateError),
+    #[error(transparent)]
+    SearchEngine(#[from] SearchEngineError),
+    #[error(transparent)]
+    GitHub(#[from] GitHubError),
+    #[error(transparent)]
+    Sync(#[from] SyncError),
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+    #[error(transparent)]
+    Keystore(#[from] KeystoreError),
+    #[error(transparent)]
+    DbWorker(#[from] DbWorkerError),
+    #[error(transparent)]
+    Backup(#[from] BackupError),
+    #[error(transparent)]
+    Profile(#[from] ProfileError),
+    #[error(transparent)]
+    Cli(#[from] CliError),
+    /// A value could not be turned into or out of JSON.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A file a method reads or writes directly.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A query a method runs on the database directly.
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
+    /// Text that should be UTF-8 is not, e.g. a decrypted secret.
+    #[error(transparent)]
+    Utf8(#[from] std::string::FromUtf8Error),
+    /// Shared state is unusable: a thread panicked while holding its lock.
+    #[error("poisoned lock: another task failed inside")]
+    Poisoned,
+    /// An RPC method GitBrowser does not have.
+    #[error("unknown method: {0}")]
+    MethodNotFound(String),
+    /// A parameter is missing or has the wrong type; the message says which.
+    #[error("{0}")]
+    InvalidParams(String),
+    /// The client is over the RPC server's rate limit.
+    #[error("rate limit exceeded")]
+    RateLimited,
+    /// The RPC client has not authenticated; see `rpc_auth`.
+    #[error("unauthorized")]
+    Unauthorized,
+    /// A request or response (which one) is over the size limit.
+    #[error("{0} too large")]
+    TooLarge(&'static str),
+}
+
+/// What went wrong, independent of the module it went wrong in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    NotFound,
+    AlreadyExists,
+    /// Input the caller should fix: a bad parameter, name, URL or setting.
+    InvalidInput,
+    /// The vault or database needs its password, or the key is wrong.
+    Locked,
+    /// Not allowed: missing permission, consent or sign-in.
+    PermissionDenied,
+    RateLimited,
+    Network,
+    /// A feature or service this build or system does not have.
+    Unavailable,
+    /// Anything else; the operation ran and failed.
+    Failed,
+}
+
+impl ErrorKind {
+    /// The kind as sent to frontends, e.g. `"not_found"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::AlreadyExists => "already_exists",
+            ErrorKind::InvalidInput => "invalid_input",
+            ErrorKind::Locked => "locked",
+            ErrorKind::PermissionDenied => "permission_denied",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Network => "network",
+            ErrorKind::Unavailable => "unavailable",
+            ErrorKind::Failed => "failed",
+        }
+    }
+}
+
+impl GitBrowserError {
+    /// A stable code naming the error, `<module>.<error>` for module errors
+    /// (e.g. `"bookmark.duplicate_url"`). Codes never change once released.
+    pub fn code(&self) -> &'static str {
+        self.describe().0
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.describe().1
+    }
+
+    fn describe(&self) -> (&'static str, ErrorKind) {
+        use ErrorKind::*;
+        match self {
+            GitBrowserError::Tab(e) => match e {
+                TabError::NotFound(_) => ("tab.not_found", NotFound),
+                TabError::AlreadyExists(_) => ("tab.already_exists", AlreadyExists),
+                TabError::InvalidIndex(_) => ("tab.invalid_index", InvalidInput),
+            },
+            GitBrowserError::Crypto(e) => match e {
+                CryptoError::KeyDerivation(_) => ("crypto.key_derivation", Failed),
+                CryptoError::Encryption(_) => ("crypto.encryption", Failed),
+                CryptoError::Decryption(_) => ("crypto.decryption", Failed),
+                CryptoError::RandomGeneration(_) => ("crypto.random_generation", Failed),
+                CryptoError::InvalidKey(_) => ("crypto.invalid_key", Locked),
+                CryptoError::CredentialNotFound(_) => ("crypto.credential_not_found", NotFound),
+            },
+            GitBrowserError::Bookmark(e) => match e {
+                BookmarkError::NotFound(_) => ("bookmark.not_found", NotFound),
+                BookmarkError::DuplicateUrl(_) => ("bookmark.duplicate_url", AlreadyExists),
+                BookmarkError::FolderNotFound(_) => ("bookmark.folder_not_found", NotFound),
+                BookmarkError::DatabaseError(_) => ("bookmark.database", Failed),
+            },
+            GitBrowserError::History(e) => match e {
+                HistoryError::NotFound(_) => ("history.not_found", NotFound),
+                HistoryError::DatabaseError(_) => ("history.database", Failed),
+            },
+            GitBrowserError::ReadingList(e) => match e {
+                ReadingListError::NotFound(_) => ("reading_list.not_found", NotFound),
+                ReadingListError::DatabaseError(_) => ("reading_list.database", Failed),
+            },
+            GitBrowserError::Annotation(e) => match e {
+                AnnotationError::NotFound(_) => ("annotation.not_found", NotFound),
+                AnnotationError::InvalidAnchor(_) => ("annotation.invalid_anchor", InvalidInput),
+                AnnotationError::InvalidColor(_) => ("annotation.invalid_color", InvalidInput),
+                AnnotationError::DatabaseError(_) => ("annotation.database", Failed),
+            },
+            GitBrowserError::SiteSettings(e) => match e {
+                SiteSettingsError::InvalidSite(_) => ("site_settings.invalid_site", InvalidInput),
+                SiteSettingsError::InvalidKey(_) => ("site_settings.invalid_key", InvalidInput),
+                SiteSettingsError::InvalidValue(_) => ("site_settings.invalid_value", InvalidInput),
+                SiteSettingsError::DatabaseError(_) => ("site_settings.database", Failed),
+            },
+            GitBrowserError::Favicon(e) => match e {
+                FaviconError::InvalidIcon(_) => ("favicon.invalid_icon", InvalidInput),
+                FaviconError::DatabaseError(_) => ("favicon.database", Failed),
+            },
+            GitBrowserError::Download(e) => match e {
+                DownloadError::NotFound(_) => ("download.not_found", NotFound),
+                DownloadError::NetworkError(_) => ("download.network", Network),
+                DownloadError::FileSystemError(_) => ("download.file_system", Failed),
+                DownloadError::AlreadyCompleted(_) => ("download.already_completed", AlreadyExists),
+            },
+            GitBrowserError::Permission(e) => match e {
+                PermissionError::NotFound(_) => ("permission.not_found", NotFound),
+                PermissionError::DatabaseError(_) => ("permission.database", Failed),
+                PermissionError::InvalidOrigin(_) => ("permission.invalid_origin", InvalidInput),
+            },
+            GitBrowserError::Shortcut(e) => match e {
+                ShortcutError::NotFound(_) => ("shortcut.not_found", NotFound),
+                ShortcutError::Conflict(_) => ("shortcut.conflict", AlreadyExists),
+                ShortcutError::InvalidKeys(_) => ("shortcut.invalid_keys", InvalidInput),
+            },
+            GitBrowserError::Session(e) => match e {
+                SessionError::SerializationError(_) => ("session.serialization", Failed),
+                SessionError::DatabaseError(_) => ("session.database", Failed),
+                SessionError::CryptoError(_) => ("session.crypto", Failed),
+            },
+            GitBrowserError::Settings(e) => match e {
+                SettingsError::IoError(_) => ("settings.io", Failed),
+                SettingsError::SerializationError(_) => ("settings.serialization", Failed),
+                SettingsError::InvalidKey(_) => ("settings.invalid_key", InvalidInput),
+                SettingsError::InvalidValue(_) => ("settings.invalid_value", InvalidInput),
+                SettingsError::ProfileNotFound(_) => ("settings.profile_not_found", NotFound),
+                SettingsError::ProfileExists(_) => ("settings.profile_exists", AlreadyExists),
+            },
+            GitBrowserError::AI(e) => match e {
+                AIError::NoProvider => ("ai.no_provider", Unavailable),
+                AIError::InvalidApiKey(_) => ("ai.invalid_api_key", PermissionDenied),
+                AIError::NetworkError(_) => ("ai.network", Network),
+                AIError::RateLimited(_) => ("ai.rate_limited", RateLimited),
+                AIError::ProviderError(_) => ("ai.provider", Failed),
+                AIError::NoAnswer => ("ai.no_answer", Failed),
+            },
+            GitBrowserError::Privacy(e) => match e {
+                PrivacyError::FilterListError(_) => ("privacy.filter_list", Failed),
+                PrivacyError::DnsError(_) => ("privacy.dns", Network),
+                PrivacyError::ClearDataError(_) => ("privacy.clear_data", Failed),
+            },
+            GitBrowserError::Extension(e) => match e {
+                ExtensionError::NotFound(_) => ("extension.not_found", NotFound),
+                ExtensionError::InvalidManifest(_) => ("extension.invalid_manifest", InvalidInput),
+                ExtensionError::PermissionDenied(_) => ("extension.permission_denied", PermissionDenied),
+                ExtensionError::LoadError(_) => ("extension.load", Failed),
+                ExtensionError::InvalidMessage(_) => ("extension.invalid_message", InvalidInput),
+                ExtensionError::QuotaExceeded(_) => ("extension.quota_exceeded", RateLimited),
+                ExtensionError::InvalidArchive(_) => ("extension.invalid_archive", InvalidInput),
+            },
+            GitBrowserError::Reader(e) => match e {
+                ReaderError::ExtractionFailed(_) => ("reader.extraction_failed", Failed),
+                ReaderError::NotAnArticle => ("reader.not_an_article", Unavailable),
+            },
+            GitBrowserError::Theme(e) => match e {
+                ThemeError::InvalidColor(_) => ("theme.invalid_color", InvalidInput),
+                ThemeError::CssError(_) => ("theme.css", InvalidInput),
+                ThemeError::InvalidTheme(_) => ("theme.invalid_theme", InvalidInput),
+                ThemeError::NotFound(_) => ("theme.not_found", NotFound),
+                ThemeError::IoError(_) => ("theme.io", Failed),
+            },
+            GitBrowserError::Locale(e) => match e {
+                LocaleError::UnsupportedLocale(_) => ("locale.unsupported", Unavailable),
+                LocaleError::MissingKey(_) => ("locale.missing_key", NotFound),
+                LocaleError::FileNotFound(_) => ("locale.file_not_found", NotFound),
+                LocaleError::InvalidMessage(_) => ("locale.invalid_message", InvalidInput),
+            },
+            GitBrowserError::Crash(e) => match e {
+                CrashError::DatabaseError(_) => ("crash.database", Failed),
+                CrashError::RecoveryFailed(_) => ("crash.recovery_failed", Failed),
+                CrashError::ReportFailed(_) => ("crash.report_failed", Failed),
+                CrashError::ConsentRequired => ("crash.consent_required", PermissionDenied),
+                CrashError::UploadFailed(_) => ("crash.upload_failed", Network),
+                CrashError::NotFound(_) => ("crash.not_found", NotFound),
+            },
+            GitBrowserError::Update(e) => match e {
+                UpdateError::NetworkError(_) => ("update.network", Network),
+                UpdateError::ChecksumMismatch(_) => ("update.checksum_mismatch", Failed),
+                UpdateError::SignatureInvalid(_) => ("update.signature_invalid", Failed),
+                UpdateError::InstallFailed(_) => ("update.install_failed", Failed),
+                UpdateError::ParseError(_) => ("update.parse", Failed),
+            },
+            GitBrowserError::SearchEngine(e) => match e {
+                SearchEngineError::NotFound(_) => ("search_engine.not_found", NotFound),
+                SearchEngineError::AlreadyExists(_) => ("search_engine.already_exists", AlreadyExists),
+                SearchEngineError::InvalidId(_) => ("search_engine.invalid_id", InvalidInput),
+                SearchEngineError::InvalidName(_) => ("search_engine.invalid_name", InvalidInput),
+                SearchEngineError::InvalidTemplate(_) => ("search_engine.invalid_template", InvalidInput),
+                SearchEngineError::InvalidKeyword(_) => ("search_engine.invalid_keyword", InvalidInput),
+                SearchEngineError::LastEngine => ("search_engine.last_engine", InvalidInput),
+            },
+            GitBrowserError::GitHub(e) => match e {
+                GitHubError::AuthFailed(_) => ("github.auth_failed", PermissionDenied),
+                GitHubError::TokenExpired => ("github.token_expired", PermissionDenied),
+                GitHubError::NetworkError(_) => ("github.network", Network),
+                GitHubError::ApiError(_) => ("github.api", Failed),
+                GitHubError::NotAuthenticated => ("github.not_authenticated", PermissionDenied),
+                GitHubError::DeviceCodeExpired => ("github.device_code_expired", PermissionDenied),
+                GitHubError::RateLimited(_) => ("github.rate_limited", RateLimited),
+                GitHubError::InvalidConfig(_) => ("github.invalid_config", InvalidInput),
+            },
+            GitBrowserError::Sync(e) => match e {
+                SyncError::NotAuthenticated => ("sync.not_authenticated", PermissionDenied),
+                SyncError::DatabaseError(_) => ("sync.database", Failed),
+                SyncError::InvalidPayload(_) => ("sync.invalid_payload", InvalidInput),
+                SyncError::RemoteError(_) => ("sync.remote", Network),
+                SyncError::ConflictNotFound(_) => ("sync.conflict_not_found", NotFound),
+                SyncError::PassphraseRequired => ("sync.passphrase_required", Locked),
+                SyncError::WrongPassphrase => ("sync.wrong_passphrase", Locked),
+                SyncError::RateLimited(_) => ("sync.rate_limited", RateLimited),
+            },
+            GitBrowserError::Encryption(e) => match e {
+                EncryptionError::Unsupported => ("encryption.unsupported", Unavailable),
+                EncryptionError::PasswordRequired => ("encryption.password_required", Locked),
+                EncryptionError::WrongKey => ("encryption.wrong_key", Locked),
+                EncryptionError::KeystoreError(_) => ("encryption.keystore", Failed),
+                EncryptionError::MigrationFailed(_) => ("encryption.migration_failed", Failed),
+                EncryptionError::DatabaseError(_) => ("encryption.database", Failed),
+            },
+            GitBrowserError::Keystore(e) => match e {
+                KeystoreError::Unavailable => ("keystore.unavailable", Unavailable),
+                KeystoreError::AccessFailed(_) => ("keystore.access_failed", Failed),
+                KeystoreError::InvalidSecret(_) => ("keystore.invalid_secret", InvalidInput),
+            },
+            GitBrowserError::DbWorker(e) => match e {
+                DbWorkerError::Stopped => ("db_worker.stopped", Unavailable),
+                DbWorkerError::TimedOut => ("db_worker.timed_out", Failed),
+            },
+            GitBrowserError::Backup(e) => match e {
+                BackupError::NotFound(_) => ("backup.not_found", NotFound),
+                BackupError::IoError(_) => ("backup.io", Failed),
+                BackupError::DatabaseError(_) => ("backup.database", Failed),
+            },
+            GitBrowserError::Profile(e) => match e {
+                ProfileError::InvalidName(_) => ("profile.invalid_name", InvalidInput),
+                ProfileError::AlreadyExists(_) => ("profile.already_exists", AlreadyExists),
+                ProfileError::NotFound(_) => ("profile.not_found", NotFound),
+                ProfileError::InUse(_) => ("profile.in_use", Unavailable),
+                ProfileError::IoError(_) => ("profile.io", Failed),
+            },
+            GitBrowserError::Cli(e) => match e {
+                CliError::UnknownOption(_) => ("cli.unknown_option", InvalidInput),
+                CliError::MissingValue(_) => ("cli.missing_value", InvalidInput),
+                CliError::InvalidSetting(_) => ("cli.invalid_setting", InvalidInput),
+                CliError::InvalidProfile(_) => ("cli.invalid_profile", InvalidInput),
+            },
+            GitBrowserError::Json(_) => ("json", Failed),
+            GitBrowserError::Io(_) => ("io", Failed),
+            GitBrowserError::Database(_) => ("database", Failed),
+            GitBrowserError::Utf8(_) => ("utf8", Failed),
+            GitBrowserError::Poisoned => ("poisoned", Failed),
+            GitBrowserError::MethodNotFound(_) => ("method_not_found", NotFound),
+            GitBrowserError::InvalidParams(_) => ("invalid_params", InvalidInput),
+            GitBrowserError::RateLimited => ("rate_limited", RateLimited),
+            GitBrowserError::Unauthorized => ("unauthorized", PermissionDenied),
+            GitBrowserError::TooLarge(_) => ("too_large", InvalidInput),
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for GitBrowserError {
+    fn from(_: std::sync::PoisonError<T>) -> Self {
+        GitBrowserError::Poisoned
+    }
+}
@@ -24,6 +24,21 @@ pub struct ExtensionManifest {
     /// Minimum GitBrowser version required.
     #[serde(default)]
     pub min_browser_version: String,
+    /// Request-blocking rulesets (declarativeNetRequest format).
+    #[serde(default)]
+    pub declarative_net_request: Option<NetRequestManifest>,
+    /// Options page (relative to extension root), served at `gb://extension/<id>/options`.
+    #[serde(default)]
+    pub options_page: Option<String>,
+    /// Chrome's newer form of `options_page`; used when `options_page` is absent.
+    #[serde(default)]
+    pub options_ui: Option<OptionsUi>,
+}
+
+/// The `options_ui` manifest section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionsUi {
+    pub page: String,
 }
 
 /// Permissions an extension can request.
@@ -44,6 +59,67 @@ pub enum ExtensionPermission {
     Bookmarks,
     /// Ability to show notifications.
     Notifications,
+    /// Block or allow network requests through declarative rules.
+    #[serde(alias = "declarativeNetRequest")]
+    DeclarativeNetRequest,
+}
+
+/// The `declarative_net_request` manifest section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetRequestManifest {
+    #[serde(default)]
+    pub rule_resources: Vec<RuleResource>,
+}
+
+/// A JSON file of rules shipped with the extension.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleResource {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Path to the rules file (relative to extension root).
+    pub path: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One rule in a ruleset file, in Chrome's declarativeNetRequest format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeclarativeRule {
+    pub id: u32,
+    #[serde(default = "default_priority")]
+    pub priority: u32,
+    pub action: DeclarativeRuleAction,
+    #[serde(default)]
+    pub condition: DeclarativeRuleCondition,
+}
+
+fn default_priority() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeclarativeRuleAction {
+    /// `block` and `allow` are supported; other action types are skipped.
+    #[serde(rename = "type")]
+    pub action_type: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeclarativeRuleCondition {
+    pub url_filter: Option<String>,
+    #[serde(default)]
+    pub resource_types: Vec<String>,
+    #[serde(default)]
+    pub excluded_resource_types: Vec<String>,
+    #[serde(default)]
+    pub request_domains: Vec<String>,
+    #[serde(default)]
+    pub excluded_request_domains: Vec<String>,
 }
 
 /// A content script injected into matching pages.
@@ -89,4 +165,52 @@ pub struct ExtensionInfo {
     /// Parsed content scripts from the manifest.
     #[serde(default)]
     pub content_scripts: Vec<ContentScript>,
+    /// Background script path from the manifest (relative to extension root).
+    #[serde(default)]
+    pub background: Option<String>,
+}
+
+/// Origin of a `runtime.sendMessage` call.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MessageSender {
+    /// A content script running on `url`. `request_id` is the page-local ID
+    /// the shim uses to resolve the promise when the reply arrives.
+    ContentScript {
+        url: String,
+        #[serde(default)]
+        request_id: Option<u64>,
+    },
+    /// The extension's background context (or an RPC client acting for it).
+    Background,
+    /// One of the extension's own pages, such as its options page, served
+    /// from `gb://extension/<id>/`.
+    ExtensionPage {
+        url: String,
+        #[serde(default)]
+        request_id: Option<u64>,
+    },
+}
+
+/// A message queued for an extension's background context.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionMessage {
+    /// Backend-assigned ID used to route the reply.
+    pub id: u64,
+    pub extension_id: String,
+    pub sender: MessageSender,
+    pub payload: serde_json::Value,
+}
+
+/// An install waiting for the user to acknowledge the requested permissions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingInstall {
+    /// Handle passed back to `confirm_install`/`cancel_install`.
+    pub install_id: String,
+    pub extension_id: String,
+    pub name: String,
+    pub version: String,
+    pub path: String,
+    /// Permissions the manifest requests; the user must acknowledge all of them.
+    pub permissions: Vec<ExtensionPermission>,
 }
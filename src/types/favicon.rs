@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// A site's icon, cached as a `data:` URL so it shows without a request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Favicon {
+    /// Lowercase host the icon belongs to (e.g. `example.com`).
+    pub site: String,
+    /// Where the icon was fetched from.
+    pub icon_url: String,
+    pub data_url: String,
+    pub fetched_at: i64,
+}
+
+/// An icon `<link>` of a page, as the page reports it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct IconLink {
+    /// `rel` attribute, e.g. `icon`, `shortcut icon` or `apple-touch-icon`.
+    pub rel: String,
+    /// Absolute URL of the icon.
+    pub href: String,
+    /// `sizes` attribute, e.g. `16x16 32x32` or `any`.
+    #[serde(default)]
+    pub sizes: String,
+    /// `type` attribute, e.g. `image/png`.
+    #[serde(default, rename = "type")]
+    pub mime: String,
+}
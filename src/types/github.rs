@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::sync::ConflictStrategy;
+
 /// OAuth Device Flow code response from GitHub.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthDeviceCode {
@@ -19,6 +21,58 @@ pub struct GitHubProfile {
     pub html_url: String,
 }
 
+/// One answer from the device flow token endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceTokenPoll {
+    /// The user has not entered the code yet.
+    Pending,
+    /// Polling too fast; carries the new interval in seconds when GitHub sends one.
+    SlowDown(Option<u32>),
+    /// The user approved the login.
+    Granted(String),
+}
+
+/// Progress of a device flow login, reported to the frontend as
+/// `github.login` events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DeviceFlowProgress {
+    /// Still waiting for the user to enter the code.
+    Pending,
+    /// GitHub asked to poll less often.
+    SlowDown { interval: u32 },
+    /// The token is stored and the profile fetched.
+    Authorized(GitHubProfile),
+    /// The flow ended without a token; `code` is the error's
+    /// `GitBrowserError::code`.
+    Failed { error: String, code: String },
+}
+
+/// GitHub account settings, persisted under `github.*`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GitHubSettings {
+    /// Send GitHub logins and API calls to the enterprise server instead of github.com.
+    pub use_enterprise: bool,
+    /// Web root of a GitHub Enterprise Server, e.g. `https://ghe.example.com`.
+    pub enterprise_web_url: Option<String>,
+    /// REST API root of the server; `<web url>/api/v3` when unset.
+    pub enterprise_api_url: Option<String>,
+    /// Client ID of the OAuth app registered on the server for device flow logins.
+    pub enterprise_client_id: Option<String>,
+    /// How sync settles collections changed on two devices.
+    #[serde(default)]
+    pub sync_conflict_strategy: ConflictStrategy,
+}
+
+/// GitHub API quota as of the last response.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Unix time (seconds) at which `remaining` is refilled.
+    pub reset_at: i64,
+}
+
 /// A GitHub notification.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubNotification {
@@ -26,6 +80,8 @@ pub struct GitHubNotification {
     pub title: String,
     pub repo_full_name: String,
     pub notification_type: String,
+    /// Why the user got it (`mention`, `review_requested`, `subscribed`, ...).
+    pub reason: String,
     pub unread: bool,
     pub updated_at: String,
     pub url: String,
@@ -42,6 +98,17 @@ pub struct GitHubRepo {
     pub updated_at: String,
 }
 
+/// A `gh` omnibox suggestion from the cached repository list.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuickOpenSuggestion {
+    pub full_name: String,
+    pub description: Option<String>,
+    /// Page the suggestion opens, including any section typed after the repo.
+    pub url: String,
+    pub owned: bool,
+    pub starred: bool,
+}
+
 /// A GitHub pull request summary.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubPullRequest {
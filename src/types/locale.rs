@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+/// Writing direction of a locale.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TextDirection {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+impl TextDirection {
+    /// The value used for the HTML `dir` attribute and `--gb-direction`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextDirection::Ltr => "ltr",
+            TextDirection::Rtl => "rtl",
+        }
+    }
+}
+
+/// Translation coverage of one locale measured against the default locale.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LocaleStats {
+    /// Normalized locale tag (e.g. `pt-BR`).
+    pub locale: String,
+    /// Number of keys in the default locale (plural forms count once).
+    pub total_keys: usize,
+    /// Keys the locale file translates itself, without fallback.
+    pub translated: usize,
+    /// Dot-notation keys that fall back to another locale, sorted.
+    pub missing: Vec<String>,
+}
+
+impl LocaleStats {
+    /// Share of translated keys in percent (100 when there is nothing to translate).
+    pub fn completion(&self) -> f64 {
+        if self.total_keys == 0 {
+            100.0
+        } else {
+            self.translated as f64 * 100.0 / self.total_keys as f64
+        }
+    }
+}
+
+/// A translation key looked up at runtime that the active locale lacks,
+/// collected while the localization engine runs in debug mode.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MissingKey {
+    pub key: String,
+    /// Active locale at the time of the lookup.
+    pub locale: String,
+    /// Number of lookups that missed.
+    pub hits: u64,
+    /// Locale that supplied the text instead, `None` when the raw key was shown.
+    pub resolved_from: Option<String>,
+}
@@ -2,17 +2,27 @@
 // Each submodule defines types used across the application.
 
 pub mod ai;
+pub mod annotation;
 pub mod bookmark;
 pub mod credential;
 pub mod download;
 pub mod errors;
 pub mod extension;
+pub mod favicon;
 pub mod github;
 pub mod history;
+pub mod locale;
 pub mod permission;
 pub mod privacy;
+pub mod profile;
 pub mod reader;
+pub mod reading_list;
+pub mod search;
 pub mod session;
+pub mod shutdown;
 pub mod settings;
+pub mod site_settings;
+pub mod sync;
 pub mod tab;
+pub mod theme;
 pub mod update;
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 /// Aggregated privacy protection statistics.
@@ -7,6 +9,65 @@ pub struct PrivacyStats {
     pub ads_blocked: u64,
     pub https_upgrades: u64,
     pub fingerprint_attempts_blocked: u64,
+    /// Requests blocked by rules contributed by extensions.
+    #[serde(default)]
+    pub extension_rules_blocked: u64,
+    /// Requests blocked by the user's own filters.
+    #[serde(default)]
+    pub custom_filters_blocked: u64,
+    /// Blocked requests by the site (host) of the page that made them.
+    #[serde(default)]
+    pub blocked_by_site: HashMap<String, u64>,
+}
+
+/// A list of blocking filters and whether it is in use.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FilterList {
+    /// `trackers`, `ads` or `custom`.
+    pub id: String,
+    pub entries: usize,
+    pub enabled: bool,
+    /// When the list's entries were last revised (UNIX seconds); `None` for
+    /// the user's own filters.
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+}
+
+/// What a network rule does with a matching request.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkRuleAction {
+    Block,
+    /// Exempts the request from lower-priority block rules and built-in lists.
+    Allow,
+}
+
+/// What becomes of a request a page makes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "url", rename_all = "lowercase")]
+pub enum RequestDecision {
+    Allow,
+    Block,
+    /// Load the `https://` URL instead.
+    Upgrade(String),
+}
+
+/// A compiled request-blocking rule contributed by an extension.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkRule {
+    pub extension_id: String,
+    pub rule_id: u32,
+    /// Higher wins; at equal priority `Allow` beats `Block`.
+    pub priority: u32,
+    pub action: NetworkRuleAction,
+    /// Filter in declarativeNetRequest syntax (`||`, `|`, `*`, `^`); `None` matches any URL.
+    pub url_filter: Option<String>,
+    /// Resource types the rule applies to; empty means all but `main_frame`.
+    pub resource_types: Vec<String>,
+    pub excluded_resource_types: Vec<String>,
+    /// Hosts (and their subdomains) the rule is limited to; empty means any.
+    pub request_domains: Vec<String>,
+    pub excluded_request_domains: Vec<String>,
 }
 
 /// A crash log entry recording details of a tab or process crash.
@@ -17,6 +78,34 @@ pub struct CrashLogEntry {
     pub error_type: String,
     pub error_message: Option<String>,
     pub timestamp: i64,
+    #[serde(default)]
+    pub backtrace: Option<String>,
+    /// OS and architecture, e.g. `linux x86_64`.
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub app_version: Option<String>,
+    /// Whether the user has been through recovery for this crash.
+    #[serde(default)]
+    pub recovered: bool,
+    /// When the report was uploaded (unix seconds), if it was.
+    #[serde(default)]
+    pub uploaded_at: Option<i64>,
+}
+
+/// A crash log as uploaded: paths and messages are scrubbed of user names,
+/// e-mail addresses and URLs, and the page URL is only included on opt-in.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CrashReport {
+    pub id: String,
+    pub error_type: String,
+    pub message: Option<String>,
+    pub backtrace: Option<String>,
+    pub os: Option<String>,
+    pub app_version: Option<String>,
+    pub timestamp: i64,
+    /// Scheme, host and path of the page, without query or fragment.
+    pub url: Option<String>,
 }
 
 /// Plural rules for localization (supports Russian and English).
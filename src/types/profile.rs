@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+/// Where a user profile keeps its data, all inside its own directory.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProfilePaths {
+    pub dir: PathBuf,
+    /// The profile database; sessions are stored in it too.
+    pub database: PathBuf,
+    pub settings: PathBuf,
+    /// Web content cache and storage.
+    pub cache: PathBuf,
+    pub extensions: PathBuf,
+    /// Installed custom themes.
+    pub themes: PathBuf,
+    /// Crash reports and the running marker.
+    pub crashes: PathBuf,
+    /// Rotating database backups.
+    pub backups: PathBuf,
+}
+
+/// What an `App` is opened on: the profile's name, where its data lives, and
+/// whether it is private.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProfileContext {
+    pub name: String,
+    pub paths: ProfilePaths,
+    /// A private profile keeps its database in memory and never writes
+    /// settings, backups or crash reports; it reads the rest from `paths`.
+    pub private: bool,
+}
+
+impl ProfileContext {
+    pub fn new(name: &str, paths: ProfilePaths) -> Self {
+        Self { name: name.to_string(), paths, private: false }
+    }
+
+    /// A private profile over this one's settings, extensions and themes.
+    pub fn private_of(&self) -> Self {
+        let mut paths = self.paths.clone();
+        paths.database = PathBuf::from(":memory:");
+        Self { name: format!("{} (private)", self.name), paths, private: true }
+    }
+}
+
+/// A user profile as listed by the profile manager.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UserProfile {
+    pub name: String,
+    pub paths: ProfilePaths,
+    /// Whether a running GitBrowser has the profile open.
+    pub in_use: bool,
+}
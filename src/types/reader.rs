@@ -12,16 +12,65 @@ pub struct ReaderContent {
     pub publish_date: Option<String>,
     pub site_name: Option<String>,
     pub estimated_read_time_minutes: u32,
+    /// Number of source pages stitched into this article (1 = single page).
+    #[serde(default = "default_page_count")]
+    pub page_count: u32,
+}
+
+fn default_page_count() -> u32 {
+    1
 }
 
 /// User-configurable reader mode display settings.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Persisted under the `reader.*` keys of `BrowserSettings`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ReaderSettings {
     pub font_size: u32,
     pub font_family: FontFamily,
+    /// Page background for the `Default` preset; other presets use their own palette.
     pub background_color: String,
     pub line_height: f32,
+    /// Hard cap on the text column width, in pixels.
     pub max_width: u32,
+    #[serde(default)]
+    pub preset: ReaderPreset,
+    /// Preferred column width (measure) in characters per line.
+    #[serde(default = "default_column_width")]
+    pub column_width: u32,
+    /// Justify paragraph text instead of left-aligning it.
+    #[serde(default)]
+    pub justify: bool,
+}
+
+fn default_column_width() -> u32 {
+    70
+}
+
+impl Default for ReaderSettings {
+    fn default() -> Self {
+        Self {
+            font_size: 18,
+            font_family: FontFamily::SansSerif,
+            background_color: "#ffffff".to_string(),
+            line_height: 1.6,
+            max_width: 680,
+            preset: ReaderPreset::Default,
+            column_width: default_column_width(),
+            justify: false,
+        }
+    }
+}
+
+/// Color presets for reader mode.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum ReaderPreset {
+    /// Light text page using `background_color`.
+    #[default]
+    Default,
+    Sepia,
+    Dark,
+    HighContrast,
 }
 
 /// Font family options for reader mode.
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// An article saved to the offline reading list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingListItem {
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    /// Cleaned article HTML as extracted by reader mode.
+    pub content_html: String,
+    /// Plain text content.
+    pub text_content: String,
+    pub author: Option<String>,
+    pub site_name: Option<String>,
+    pub estimated_read_time_minutes: u32,
+    /// Whether `<img>` sources were replaced with inline data URIs.
+    pub images_inlined: bool,
+    pub saved_at: i64,
+    /// When the article was marked as read (None = unread).
+    pub read_at: Option<i64>,
+    /// Number of source pages stitched into the article.
+    #[serde(default = "default_page_count")]
+    pub page_count: u32,
+}
+
+fn default_page_count() -> u32 {
+    1
+}
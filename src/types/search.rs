@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// A configured search engine.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchEngine {
+    /// Stable identifier (e.g. `google`), referenced by `SearchSettings::default_engine`.
+    pub id: String,
+    pub name: String,
+    /// Search URL with `%s` where the encoded query goes.
+    pub url_template: String,
+    /// Address bar trigger: typing `<keyword> <query>` searches this engine.
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// Suggestion endpoint with `%s` for the query (OpenSearch JSON format).
+    #[serde(default)]
+    pub suggest_url: Option<String>,
+}
+
+/// Search engine registry, persisted under the `search.*` settings keys.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchSettings {
+    pub engines: Vec<SearchEngine>,
+    /// ID of the engine used for plain address bar queries.
+    pub default_engine: String,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        let engine = |id: &str, name: &str, url: &str, keyword: &str, suggest: &str| SearchEngine {
+            id: id.to_string(),
+            name: name.to_string(),
+            url_template: url.to_string(),
+            keyword: Some(keyword.to_string()),
+            suggest_url: Some(suggest.to_string()),
+        };
+        Self {
+            engines: vec![
+                engine(
+                    "google",
+                    "Google",
+                    "https://www.google.com/search?q=%s",
+                    "g",
+                    "https://suggestqueries.google.com/complete/search?client=firefox&q=%s",
+                ),
+                engine(
+                    "duckduckgo",
+                    "DuckDuckGo",
+                    "https://duckduckgo.com/?q=%s",
+                    "ddg",
+                    "https://duckduckgo.com/ac/?type=list&q=%s",
+                ),
+                engine(
+                    "bing",
+                    "Bing",
+                    "https://www.bing.com/search?q=%s",
+                    "b",
+                    "https://api.bing.com/osjson.aspx?query=%s",
+                ),
+            ],
+            default_engine: "google".to_string(),
+        }
+    }
+}
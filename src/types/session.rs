@@ -29,3 +29,10 @@ pub struct WindowBounds {
     pub width: i32,
     pub height: i32,
 }
+
+impl Default for WindowBounds {
+    /// The size a new window opens with.
+    fn default() -> Self {
+        Self { x: 0, y: 0, width: 1280, height: 800 }
+    }
+}
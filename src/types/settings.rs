@@ -2,6 +2,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use super::ai::AIProviderName;
+use super::github::GitHubSettings;
+use super::reader::ReaderSettings;
+use super::search::SearchSettings;
+use super::theme::ThemeScheduleSettings;
+use super::update::UpdateSettings;
 
 /// Top-level browser settings container.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -12,6 +17,16 @@ pub struct BrowserSettings {
     pub shortcuts: HashMap<String, String>,
     pub ai: AISettings,
     pub performance: PerformanceSettings,
+    #[serde(default)]
+    pub reader: ReaderSettings,
+    #[serde(default)]
+    pub search: SearchSettings,
+    #[serde(default)]
+    pub theme_schedule: ThemeScheduleSettings,
+    #[serde(default)]
+    pub github: GitHubSettings,
+    #[serde(default)]
+    pub updates: UpdateSettings,
 }
 
 impl Default for BrowserSettings {
@@ -23,6 +38,11 @@ impl Default for BrowserSettings {
             shortcuts: Self::default_shortcuts(),
             ai: AISettings::default(),
             performance: PerformanceSettings::default(),
+            reader: ReaderSettings::default(),
+            search: SearchSettings::default(),
+            theme_schedule: ThemeScheduleSettings::default(),
+            github: GitHubSettings::default(),
+            updates: UpdateSettings::default(),
         }
     }
 }
@@ -48,13 +68,48 @@ impl BrowserSettings {
     }
 }
 
+/// A single setting that changed, identified by its dot-notation key.
+///
+/// Emitted by `SettingsEngine` to subscribers after `set_value`, `reset`
+/// and `load`. Keys that were removed carry `Value::Null` as `new_value`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SettingsChange {
+    pub key: String,
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+}
+
+/// Describes one settable key, as returned by `settings.schema`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SettingSchema {
+    /// Dot-notation key accepted by `settings.set`.
+    pub key: String,
+    /// One of `boolean`, `integer`, `number`, `string`, `enum` or `array`.
+    #[serde(rename = "type")]
+    pub value_type: String,
+    pub default: serde_json::Value,
+    /// Valid values for `enum` keys.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_values: Option<Vec<serde_json::Value>>,
+    /// Whether `null` is accepted.
+    pub nullable: bool,
+    pub description: String,
+}
+
 /// General browser settings.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GeneralSettings {
     pub language: String,
     pub startup_behavior: StartupBehavior,
     pub homepage: String,
-    pub default_search_engine: String,
+    /// User-Agent sent by every page; empty keeps the webview's own.
+    /// Per-site overrides win.
+    #[serde(default)]
+    pub user_agent: String,
+    /// Link schemes (`mailto`, `magnet`, ...) opened in their application
+    /// without asking first.
+    #[serde(default)]
+    pub allowed_protocols: Vec<String>,
 }
 
 impl Default for GeneralSettings {
@@ -63,7 +118,8 @@ impl Default for GeneralSettings {
             language: "en".to_string(),
             startup_behavior: StartupBehavior::Restore,
             homepage: "about:newtab".to_string(),
-            default_search_engine: "google".to_string(),
+            user_agent: String::new(),
+            allowed_protocols: Vec::new(),
         }
     }
 }
@@ -88,6 +144,38 @@ pub struct PrivacySettings {
     pub clear_data_on_exit: bool,
     #[serde(default)]
     pub telemetry_consent: bool,
+    /// Include page URLs (without query or fragment) in uploaded crash reports.
+    #[serde(default)]
+    pub crash_report_urls: bool,
+    /// Where crash reports are POSTed; empty drafts a GitHub issue instead.
+    #[serde(default)]
+    pub crash_report_endpoint: String,
+    /// At-rest encryption of `gitbrowser.db`; applied on the next start.
+    #[serde(default)]
+    pub database_encryption: DatabaseEncryption,
+    /// Extra blocking filters in declarativeNetRequest syntax (`||host^`, `|`, `*`).
+    #[serde(default)]
+    pub custom_filters: Vec<String>,
+    /// Seconds before a copied password is cleared from the clipboard (0 never).
+    #[serde(default = "default_clipboard_clear_seconds")]
+    pub clipboard_clear_seconds: u32,
+}
+
+fn default_clipboard_clear_seconds() -> u32 {
+    30
+}
+
+/// How `gitbrowser.db` is encrypted at rest (SQLCipher).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseEncryption {
+    /// Plain SQLite.
+    #[default]
+    Off,
+    /// Keyed with a random key kept in the OS keystore.
+    Keystore,
+    /// Keyed from the master password, asked for on every start.
+    MasterPassword,
 }
 
 impl Default for PrivacySettings {
@@ -101,6 +189,11 @@ impl Default for PrivacySettings {
             anti_fingerprinting: true,
             clear_data_on_exit: false,
             telemetry_consent: false,
+            crash_report_urls: false,
+            crash_report_endpoint: String::new(),
+            database_encryption: DatabaseEncryption::Off,
+            custom_filters: Vec::new(),
+            clipboard_clear_seconds: default_clipboard_clear_seconds(),
         }
     }
 }
@@ -115,6 +208,12 @@ pub struct AppearanceSettings {
     pub show_telegram: bool,
     #[serde(default = "default_true")]
     pub show_github: bool,
+    /// ID of the installed custom theme layered over `theme`, if any.
+    #[serde(default)]
+    pub custom_theme: Option<String>,
+    /// Force a dark rendering of bright sites; per-site overrides win.
+    #[serde(default)]
+    pub force_dark: bool,
 }
 
 fn default_true() -> bool {
@@ -129,6 +228,8 @@ impl Default for AppearanceSettings {
             font_size: 14,
             show_telegram: true,
             show_github: true,
+            custom_theme: None,
+            force_dark: false,
         }
     }
 }
@@ -142,26 +243,20 @@ pub enum ThemeMode {
 }
 
 /// AI assistant settings.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct AISettings {
     pub active_provider: Option<AIProviderName>,
     pub active_model: Option<String>,
 }
 
-impl Default for AISettings {
-    fn default() -> Self {
-        Self {
-            active_provider: None,
-            active_model: None,
-        }
-    }
-}
-
 /// Performance tuning settings.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PerformanceSettings {
     pub tab_suspend_timeout_minutes: u32,
     pub lazy_load_images: bool,
+    /// Hold back background work while on battery or in the OS power saver.
+    #[serde(default = "default_true")]
+    pub battery_saver: bool,
 }
 
 impl Default for PerformanceSettings {
@@ -169,6 +264,7 @@ impl Default for PerformanceSettings {
         Self {
             tab_suspend_timeout_minutes: 30,
             lazy_load_images: true,
+            battery_saver: true,
         }
     }
 }
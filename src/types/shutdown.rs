@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// One step of `App::shutdown`. Steps run in the order of `ALL`, each even
+/// if an earlier one failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownStep {
+    /// Writes the open tabs out, as the autosave would have.
+    SaveSession,
+    /// Stops the periodic session save and the app's background loops.
+    StopSchedulers,
+    /// Clears browsing data for `privacy.clear_data_on_exit` and private apps.
+    ClearOnExit,
+    /// Records that the app closed cleanly, so no crash is reported.
+    MarkCleanExit,
+    /// Moves the write-ahead log into the database file.
+    CheckpointWal,
+    /// Locks the password vault, wiping its key from memory.
+    ZeroizeKeys,
+}
+
+impl ShutdownStep {
+    pub const ALL: [ShutdownStep; 6] = [
+        ShutdownStep::SaveSession,
+        ShutdownStep::StopSchedulers,
+        ShutdownStep::ClearOnExit,
+        ShutdownStep::MarkCleanExit,
+        ShutdownStep::CheckpointWal,
+        ShutdownStep::ZeroizeKeys,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShutdownStep::SaveSession => "save_session",
+            ShutdownStep::StopSchedulers => "stop_schedulers",
+            ShutdownStep::ClearOnExit => "clear_on_exit",
+            ShutdownStep::MarkCleanExit => "mark_clean_exit",
+            ShutdownStep::CheckpointWal => "checkpoint_wal",
+            ShutdownStep::ZeroizeKeys => "zeroize_keys",
+        }
+    }
+
+    /// How long the step may take. Database work is given up on after this;
+    /// other steps are only logged when they overrun it.
+    pub fn timeout(&self) -> Duration {
+        match self {
+            ShutdownStep::SaveSession | ShutdownStep::ClearOnExit => Duration::from_secs(2),
+            ShutdownStep::CheckpointWal => Duration::from_secs(3),
+            ShutdownStep::StopSchedulers | ShutdownStep::MarkCleanExit | ShutdownStep::ZeroizeKeys => {
+                Duration::from_millis(500)
+            }
+        }
+    }
+}
+
+/// How a shutdown step ended.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "error")]
+pub enum StepStatus {
+    Done,
+    /// Nothing to do for this app, e.g. saving the session of a private one.
+    Skipped,
+    Failed(String),
+    /// Given up on after `ShutdownStep::timeout`.
+    TimedOut,
+}
+
+/// The outcome of one shutdown step.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepReport {
+    pub step: ShutdownStep,
+    pub status: StepStatus,
+    pub elapsed_ms: u64,
+}
+
+/// What `App::shutdown` did, step by step.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShutdownReport {
+    pub steps: Vec<StepReport>,
+}
+
+impl ShutdownReport {
+    /// Whether no step failed or timed out.
+    pub fn is_clean(&self) -> bool {
+        self.steps.iter().all(|s| matches!(s.status, StepStatus::Done | StepStatus::Skipped))
+    }
+
+    pub fn status(&self, step: ShutdownStep) -> Option<&StepStatus> {
+        self.steps.iter().find(|s| s.step == step).map(|s| &s.status)
+    }
+}
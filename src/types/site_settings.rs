@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A per-site override of a browser behavior (e.g. forced dark mode).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SiteSetting {
+    /// Lowercase host the override applies to (e.g. `example.com`).
+    pub site: String,
+    pub key: String,
+    pub value: Value,
+    pub updated_at: i64,
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A group of user data synced as one unit.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncCollection {
+    Bookmarks,
+    Settings,
+    Shortcuts,
+    ReadingList,
+}
+
+impl SyncCollection {
+    /// Every collection, in the order they are synced.
+    pub const ALL: [SyncCollection; 4] = [
+        SyncCollection::Bookmarks,
+        SyncCollection::Settings,
+        SyncCollection::Shortcuts,
+        SyncCollection::ReadingList,
+    ];
+
+    /// The key used in the sync payload and the `sync_state` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncCollection::Bookmarks => "bookmarks",
+            SyncCollection::Settings => "settings",
+            SyncCollection::Shortcuts => "shortcuts",
+            SyncCollection::ReadingList => "reading_list",
+        }
+    }
+
+    /// True for collections of independent entries that can be merged by union.
+    pub fn is_list(&self) -> bool {
+        matches!(self, SyncCollection::Bookmarks | SyncCollection::ReadingList)
+    }
+}
+
+/// How two version vectors relate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOrder {
+    Equal,
+    /// The left side has seen everything the right side has, and more.
+    Ahead,
+    /// The right side has seen everything the left side has, and more.
+    Behind,
+    /// Each side has changes the other has not seen.
+    Concurrent,
+}
+
+/// Per-device change counters for one collection.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct VersionVector(pub BTreeMap<String, u64>);
+
+impl VersionVector {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Counter of `device` (0 if it never changed the collection).
+    pub fn get(&self, device: &str) -> u64 {
+        self.0.get(device).copied().unwrap_or(0)
+    }
+
+    /// Records one more change made on `device`.
+    pub fn bump(&mut self, device: &str) {
+        *self.0.entry(device.to_string()).or_insert(0) += 1;
+    }
+
+    /// Pointwise maximum of both vectors.
+    pub fn merged(&self, other: &VersionVector) -> VersionVector {
+        let mut out = self.clone();
+        for (device, &count) in &other.0 {
+            let entry = out.0.entry(device.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+        out
+    }
+
+    pub fn compare(&self, other: &VersionVector) -> VersionOrder {
+        let devices = self.0.keys().chain(other.0.keys());
+        let (mut ahead, mut behind) = (false, false);
+        for device in devices {
+            let (mine, theirs) = (self.get(device), other.get(device));
+            ahead |= mine > theirs;
+            behind |= mine < theirs;
+        }
+        match (ahead, behind) {
+            (false, false) => VersionOrder::Equal,
+            (true, false) => VersionOrder::Ahead,
+            (false, true) => VersionOrder::Behind,
+            (true, true) => VersionOrder::Concurrent,
+        }
+    }
+}
+
+/// One collection's data and the versions it reflects.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CollectionState {
+    pub versions: VersionVector,
+    pub data: Value,
+    /// Unix time of the last change to `data`, for last-writer-wins.
+    #[serde(default)]
+    pub modified_at: i64,
+}
+
+/// How a collection changed on both sides since the last sync is settled.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Lists are merged by union; settings and shortcuts become a
+    /// `SyncConflict` for the user to settle.
+    #[default]
+    Merge,
+    /// The side changed most recently wins.
+    LastWriterWins,
+    LocalWins,
+    RemoteWins,
+}
+
+/// Which side the user picked for a `SyncConflict`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    KeepLocal,
+    KeepRemote,
+}
+
+/// A collection changed on both sides that could not be merged
+/// automatically. Until it is resolved, the collection is left out of sync
+/// runs: neither side overwrites the other.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncConflict {
+    pub collection: SyncCollection,
+    pub local: Value,
+    pub remote: Value,
+    /// Versions of the remote side, folded in when the conflict is resolved.
+    pub remote_versions: VersionVector,
+    pub detected_at: i64,
+}
+
+/// Everything stored in the sync gist (before encryption).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SyncPayload {
+    /// Payload layout version.
+    pub format: u32,
+    /// Device that wrote the payload.
+    pub device_id: String,
+    pub collections: BTreeMap<SyncCollection, CollectionState>,
+}
+
+/// Outcome of one `sync.now` run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SyncReport {
+    /// Collections replaced by newer remote data.
+    pub pulled: Vec<SyncCollection>,
+    /// Collections changed on both sides and merged.
+    pub merged: Vec<SyncCollection>,
+    /// Collections changed on both sides and left for the user to resolve.
+    pub conflicts: Vec<SyncCollection>,
+    /// Whether the gist was written.
+    pub pushed: bool,
+}
@@ -12,12 +12,239 @@ pub struct Tab {
     pub loading: bool,
     pub crashed: bool,
     pub scroll_position: ScrollPosition,
+    /// Page zoom factor, 1.0 being 100%.
+    #[serde(default = "default_zoom")]
+    pub zoom: f64,
+    /// Requests blocked on the page shown, since it started loading.
+    #[serde(default)]
+    pub blocked_requests: u64,
     pub created_at: i64,
 }
 
+fn default_zoom() -> f64 {
+    1.0
+}
+
+/// Zoom factors the zoom in/out commands step through.
+pub const ZOOM_LEVELS: [f64; 15] = [0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 3.0, 5.0];
+
+/// A zoom command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoomStep {
+    In,
+    Out,
+    Reset,
+}
+
+impl ZoomStep {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "in" => Some(ZoomStep::In),
+            "out" => Some(ZoomStep::Out),
+            "reset" => Some(ZoomStep::Reset),
+            _ => None,
+        }
+    }
+
+    /// The zoom factor after this step from `current`: the next of
+    /// `ZOOM_LEVELS` up or down, staying put at either end.
+    pub fn apply(self, current: f64) -> f64 {
+        let last = ZOOM_LEVELS[ZOOM_LEVELS.len() - 1];
+        match self {
+            ZoomStep::In => ZOOM_LEVELS.iter().copied().find(|&z| z > current + 1e-6).unwrap_or(last),
+            ZoomStep::Out => {
+                ZOOM_LEVELS.iter().rev().copied().find(|&z| z < current - 1e-6).unwrap_or(ZOOM_LEVELS[0])
+            }
+            ZoomStep::Reset => 1.0,
+        }
+    }
+}
+
 /// Scroll position within a web page.
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct ScrollPosition {
     pub x: f64,
     pub y: f64,
 }
+
+/// Most pages a tab's back/forward history keeps.
+pub const MAX_TAB_HISTORY: usize = 50;
+
+/// Where going back or forward in a tab's history leads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryStep {
+    /// The webview has the page in its own history and goes there itself.
+    Traverse(String),
+    /// The webview never showed the page (it was made since), so it loads it.
+    Load(String),
+}
+
+/// A tab's back/forward history: the pages it showed, and which it shows.
+/// The tab's webview keeps its own history only for the stretch of pages
+/// it showed itself; a webview made again (after suspending, say) starts
+/// with the current page alone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabHistory {
+    entries: Vec<String>,
+    index: usize,
+    /// First and last of `entries` in the webview's own history
+    in_webview: (usize, usize),
+}
+
+impl TabHistory {
+    pub fn new(url: &str) -> Self {
+        Self { entries: vec![url.to_string()], index: 0, in_webview: (0, 0) }
+    }
+
+    pub fn current(&self) -> &str {
+        &self.entries[self.index]
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.index > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.index + 1 < self.entries.len()
+    }
+
+    /// Records that the tab shows `url`. The page just before or after the
+    /// current one in the webview's history is taken as the webview going
+    /// back or forward; any other page is a new one, dropping the pages
+    /// ahead.
+    pub fn record(&mut self, url: &str) {
+        let (first, last) = self.in_webview;
+        if self.current() == url {
+            return;
+        }
+        if self.index > first && self.entries[self.index - 1] == url {
+            self.index -= 1;
+            return;
+        }
+        if self.index < last && self.entries[self.index + 1] == url {
+            self.index += 1;
+            return;
+        }
+        self.entries.truncate(self.index + 1);
+        self.entries.push(url.to_string());
+        let dropped = self.entries.len().saturating_sub(MAX_TAB_HISTORY);
+        self.entries.drain(..dropped);
+        self.index = self.entries.len() - 1;
+        self.in_webview = (first.saturating_sub(dropped).min(self.index), self.index);
+    }
+
+    /// Moves back a page, if there is one.
+    pub fn back(&mut self) -> Option<HistoryStep> {
+        let target = self.index.checked_sub(1)?;
+        Some(self.step_to(target))
+    }
+
+    /// Moves forward a page, if there is one.
+    pub fn forward(&mut self) -> Option<HistoryStep> {
+        let target = self.index + 1;
+        if target >= self.entries.len() {
+            return None;
+        }
+        Some(self.step_to(target))
+    }
+
+    fn step_to(&mut self, target: usize) -> HistoryStep {
+        let (first, last) = self.in_webview;
+        self.index = target;
+        let url = self.entries[target].clone();
+        if (first..=last).contains(&target) {
+            HistoryStep::Traverse(url)
+        } else {
+            // Loading it starts the webview's stretch over
+            self.in_webview = (target, target);
+            HistoryStep::Load(url)
+        }
+    }
+
+    /// The tab has a new webview, whose history holds only the current page.
+    pub fn restart_in_webview(&mut self) {
+        self.in_webview = (self.index, self.index);
+    }
+}
+
+/// Why a page failed to load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadErrorKind {
+    /// The site's name did not resolve.
+    Dns,
+    /// The secure connection failed, e.g. on an untrusted certificate.
+    Tls,
+    Timeout,
+    /// The server refused the connection or could not be reached.
+    Connection,
+    Other,
+}
+
+impl LoadErrorKind {
+    /// A best guess from an engine's error message, for engines that
+    /// report no more than that.
+    pub fn from_message(message: &str) -> Self {
+        let m = message.to_ascii_lowercase();
+        let any = |words: &[&str]| words.iter().any(|w| m.contains(w));
+        if any(&["resolve", "resolving", "name_not_resolved", "unknown host", "dns", "name or service not known"]) {
+            LoadErrorKind::Dns
+        } else if any(&["tls", "ssl", "certificate", "handshake"]) {
+            LoadErrorKind::Tls
+        } else if any(&["timed out", "timeout"]) {
+            LoadErrorKind::Timeout
+        } else if any(&["refused", "unreachable", "reset", "could not connect", "connection"]) {
+            LoadErrorKind::Connection
+        } else {
+            LoadErrorKind::Other
+        }
+    }
+
+    /// Heading of the error page.
+    pub fn title(self) -> &'static str {
+        match self {
+            LoadErrorKind::Dns => "This site can't be found",
+            LoadErrorKind::Tls => "This connection is not secure",
+            LoadErrorKind::Timeout => "This site took too long to respond",
+            LoadErrorKind::Connection => "This site can't be reached",
+            LoadErrorKind::Other => "This page failed to load",
+        }
+    }
+
+    /// What the user can try, under the heading.
+    pub fn hint(self) -> &'static str {
+        match self {
+            LoadErrorKind::Dns => "Check the address for typos, or your network connection.",
+            LoadErrorKind::Tls => "The site's certificate could not be verified, so the page was not loaded.",
+            LoadErrorKind::Timeout => "The server may be busy or down. Try again in a moment.",
+            LoadErrorKind::Connection => "The server refused the connection or is not reachable from this network.",
+            LoadErrorKind::Other => "Something went wrong while loading the page.",
+        }
+    }
+}
+
+/// A page that failed to load, shown as an error page in its place.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadError {
+    pub url: String,
+    pub kind: LoadErrorKind,
+    /// The engine's own message.
+    pub detail: String,
+}
+
+/// Something to do with the page a tab shows, asked for from outside its
+/// webview (a shortcut, the menu, or RPC).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PageAction {
+    /// Open the print dialog.
+    Print,
+    /// Print straight to a PDF file at `path`, recorded as download
+    /// `download_id`.
+    PrintToPdf { download_id: String, path: String },
+    /// Save a PNG of the page to `path`, recorded as download `download_id`,
+    /// and copy it to the clipboard: the whole page with `full_page`, else
+    /// what is in view.
+    Screenshot { download_id: String, path: String, full_page: bool },
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::settings::ThemeMode;
+
+/// A user-installed theme: CSS variable overrides on top of a base palette.
+///
+/// Theme files are JSON, e.g.
+/// `{"id": "nord", "name": "Nord", "base": "Dark", "variables": {"--bg-primary": "#2e3440"}}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomTheme {
+    /// Identifier used for the gallery file name; derived from `name` if omitted.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Palette the overrides apply to. `None` follows the current theme mode.
+    #[serde(default)]
+    pub base: Option<ThemeMode>,
+    /// CSS custom properties (`--name` → value) overriding the base palette.
+    pub variables: HashMap<String, String>,
+}
+
+/// How the theme scheduler picks light or dark mode.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ScheduleMode {
+    /// No automatic switching; `appearance.theme` applies.
+    Off,
+    /// Light from `light_start` to `dark_start` (local time).
+    FixedTimes,
+    /// Light between sunrise and sunset at `latitude`/`longitude`.
+    SunriseSunset,
+}
+
+/// Time-based automatic theme switching, persisted under `theme_schedule.*`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThemeScheduleSettings {
+    pub mode: ScheduleMode,
+    /// Local time (`HH:MM`) light mode starts in `FixedTimes` mode.
+    pub light_start: String,
+    /// Local time (`HH:MM`) dark mode starts in `FixedTimes` mode.
+    pub dark_start: String,
+    /// Degrees north (negative for south), used by `SunriseSunset`.
+    pub latitude: f64,
+    /// Degrees east (negative for west), used by `SunriseSunset`.
+    pub longitude: f64,
+}
+
+impl Default for ThemeScheduleSettings {
+    fn default() -> Self {
+        Self {
+            mode: ScheduleMode::Off,
+            light_start: "07:00".to_string(),
+            dark_start: "19:00".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
 /// Information about an available browser update.
@@ -6,9 +8,54 @@ pub struct UpdateInfo {
     pub version: String,
     pub changelog: String,
     pub download_url: String,
+    /// URL of the minisign signature of the download.
+    pub signature_url: String,
     pub sha256: String,
     pub published_at: String,
     pub file_size: u64,
+    /// Channel the release was published on.
+    #[serde(default)]
+    pub channel: UpdateChannel,
+}
+
+/// Release channel, from most to least stable. A channel is offered its own
+/// releases and those of every more stable channel.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// GitHub prereleases (`-beta`, `-rc`, `-alpha` tags).
+    Beta,
+    /// Builds tagged `-nightly`.
+    Nightly,
+}
+
+impl UpdateChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+            UpdateChannel::Nightly => "nightly",
+        }
+    }
+}
+
+/// Update settings, persisted under `updates.*`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpdateSettings {
+    pub channel: UpdateChannel,
+    /// Check for updates in the background.
+    pub auto_check: bool,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            auto_check: true,
+        }
+    }
 }
 
 /// Progress of an update download.
@@ -17,3 +64,34 @@ pub struct DownloadProgress {
     pub downloaded: u64,
     pub total: u64,
 }
+
+/// A downloaded update whose checksum and signature verified.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadyUpdate {
+    pub info: UpdateInfo,
+    pub path: PathBuf,
+}
+
+/// Progress of a staged update install, kept next to the executable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct InstallState {
+    /// Version waiting in `<exe>.new` for the next start.
+    pub staged: Option<String>,
+    /// Version swapped in that has not confirmed a successful start yet.
+    pub pending: Option<String>,
+    /// Starts of the pending version so far.
+    pub launches: u32,
+    /// Version kept in `<exe>.old` for rollback.
+    pub previous: Option<String>,
+}
+
+/// What startup has to do after `UpdateInstaller::on_startup`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartupAction {
+    Continue,
+    /// A staged update was swapped in; start it and exit.
+    Relaunch,
+    /// The new build never confirmed a start and the previous one is back;
+    /// start it and exit.
+    RolledBack { from: String },
+}
@@ -1,20 +1,34 @@
 //! WebView-based browser application using `wry` + `tao`.
 //!
 //! Architecture:
-//! - `with_initialization_script(TOOLBAR_JS)` injects the toolbar on EVERY page
+//! - `with_initialization_script(TOOLBAR_JS)` injects the toolbar, find bar and context menu on EVERY page
 //!   (both internal custom-protocol pages and external http/https sites).
 //!   On Windows WebView2 this uses AddScriptToExecuteOnDocumentCreatedAsync.
-//! - Internal pages (newtab, settings) are served via `gb://` custom protocol.
-//! - External sites are loaded via `load_url()`.
+//! - Internal pages (newtab, settings, reading list) are served via `gb://` custom protocol.
+//! - External sites are loaded via `load_url()`. Their top-level navigations
+//!   go through the privacy engine in the navigation handler, and requests
+//!   made from page scripts through `request_filter.js`.
+//! - Each tab has a webview of its own, shown while it is the active tab, so
+//!   switching tabs keeps pages as they were. Suspended tabs give theirs up.
 //! - IPC from JS → Rust via `window.ipc.postMessage()`.
+//! - Extension background scripts run in hidden webviews, one per extension,
+//!   each with its own IPC handler bound to that extension's ID.
 
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tao::event::{Event, WindowEvent};
-use tao::event_loop::{ControlFlow, EventLoop, EventLoopBuilder};
+use tao::event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopProxy};
 use tao::window::WindowBuilder;
-use wry::WebViewBuilder;
+use wry::{WebContext, WebViewBuilder};
 
 use crate::app::App;
+use crate::cli::CliArgs;
+use crate::services::event_bus::AppEvent;
+use crate::services::extension_framework::BackgroundScript;
+use crate::services::search_engine_registry::url_for_input;
+use crate::types::credential::AuthChallenge;
+use crate::types::reader::ReaderContent;
+use crate::types::tab::{LoadError, PageAction, ZoomStep};
 
 #[derive(Debug)]
 enum UserEvent {
@@ -22,15 +36,222 @@ enum UserEvent {
     EvalScript(String),
     /// Navigate to URL and update tab state (from new_window_req_handler)
     NavigateUrl(String),
+    /// Create the hidden page running an extension's background script
+    StartBackground(BackgroundScript),
+    /// Destroy an extension's background page
+    StopBackground(String),
+    /// Evaluate a script in an extension's background page (extension ID, script)
+    EvalBackground(String, String),
+    /// The main webview's renderer process died (reason)
+    RendererCrashed(String),
+    /// Time to ping the page and check for a hang
+    WatchdogTick,
+    /// The page answered watchdog ping N
+    WatchdogPong(u64),
+    /// The page started loading a new document
+    PageLoadStarted,
+    /// How far the page's load has got, from 0.0 to 1.0
+    LoadProgress(f64),
+    /// The page finished loading
+    PageLoadFinished,
+    /// The page failed to load; an error page is shown instead
+    LoadFailed(crate::types::tab::LoadError),
+    /// Answer to the hang prompt: true to kill the page, false to wait
+    HangChoice(bool),
+    /// Set the page zoom factor, then evaluate a script
+    Zoom(f64, String),
+    /// Save the image at this URL into the downloads folder, or where the
+    /// user picks (whether to ask)
+    SaveImage(String, bool),
+    /// Ask for a path with the OS file dialog for an internal page, which
+    /// gets it through `__gb_fileChosen` (what for; see `choose_file`)
+    ChooseFile(String),
+    /// An event from the webview of a tab (tab ID): scripts and loads go
+    /// back to that webview rather than the active tab's
+    ForTab(String, Box<UserEvent>),
+    /// Evaluate a script in every tab's webview
+    EvalAll(String),
+    /// Show the active tab's webview, after tabs were switched, opened or closed
+    ShowActiveTab,
+    /// Time to suspend idle background tabs (whether memory runs low,
+    /// sampled off the UI thread)
+    SuspendTick(bool),
+    /// Fetch the icon of a site (site, icon URLs to try, best first)
+    FetchFavicon(String, Vec<String>),
+    /// Fetch a PDF for the viewer page (URL)
+    FetchPdf(String),
+    /// Save an article to the reading list, fetching its following pages
+    /// (URL, page HTML, inlined images)
+    SaveArticle(String, String, std::collections::HashMap<String, String>),
+    /// Make the window fullscreen, or not (`None`)
+    Fullscreen(Option<FullscreenMode>),
+    /// Switch the webview's User-Agent (`None` for its own), then load the URL
+    LoadWithUserAgent(Option<String>, String),
+    /// A site asked for HTTP credentials, none saved would do (challenge ID)
+    AuthRequested(u64, AuthChallenge),
+    /// The credential prompt was answered (challenge ID, login, or `None` to cancel)
+    AuthAnswered(u64, Option<AuthLogin>),
+    /// A link to another application (`mailto:`, `magnet:`, ...) was followed
+    ExternalLink(String),
+    /// Answer to the external link prompt: `None` to stay, or to open it
+    /// (and whether to always open its scheme)
+    ExternalLinkChoice(Option<bool>),
+    /// A page with a login form has saved logins (origin, ID and username
+    /// of each), to offer in the autofill prompt
+    AutofillOffer(String, Vec<(String, String)>),
+    /// Answer to the autofill prompt: the ID of the login to fill, or `None`
+    AutofillChoice(Option<String>),
+    /// A login was submitted in a page and can be saved (origin, username,
+    /// whether it replaces a saved password), to ask in the save prompt
+    SaveLoginOffer(String, String, bool),
+    /// Answer to the save prompt: true to save the login
+    SaveLoginChoice(bool),
+    /// Print the page, or save it as a PDF
+    Page(PageAction),
+    /// A file the page was saved to is written, or failed (download ID, result)
+    PageSaved(String, Result<(), String>),
+    /// Take the screenshot, now the toolbar is out of the way (download ID, full page)
+    Capture(String, bool),
+    /// A screenshot of the page was taken, or failed (download ID, PNG)
+    PageCaptured(String, Result<Vec<u8>, String>),
+    /// Open these URLs in new tabs and bring the window to the front: from
+    /// another `gitbrowser` on this profile, or a `gitbrowser://` link
+    OpenUrls(Vec<String>),
 }
 
 struct BrowserState {
     app: App,
     /// When true, a navigation is in progress — ignore IPC from stale pages
     navigating: bool,
+    /// Last find-in-page search per tab ID
+    find: HashMap<String, FindState>,
+    /// PDFs fetched for viewer pages, by URL, oldest first
+    pdfs: Vec<(String, Vec<u8>)>,
+    /// Whether the window is fullscreen, and for what
+    fullscreen: Option<FullscreenMode>,
+    /// The User-Agent each tab's webview sends, by tab ID (`None` for its own)
+    user_agents: HashMap<String, Option<String>>,
+    /// Articles shown in reader view, by tab ID, with the URL of the page
+    /// each was made from
+    readers: HashMap<String, (String, ReaderContent)>,
+    /// Logins submitted in a tab's page and not saved yet, by tab ID:
+    /// offered for saving until the tab leaves their origin
+    pending_logins: HashMap<String, PendingLogin>,
+    /// Whether the browser window has the keyboard focus; downloads that
+    /// finish while it does not are announced with a notification
+    window_focused: bool,
 }
 
-const TOOLBAR_JS: &str = include_str!("../../resources/ui/toolbar.js");
+/// A login a page's form submitted.
+#[derive(Debug, Clone)]
+struct PendingLogin {
+    origin: String,
+    username: String,
+    password: String,
+}
+
+/// A login typed into the credential prompt.
+#[derive(Debug, serde::Deserialize)]
+struct AuthLogin {
+    username: String,
+    password: String,
+    /// Save it in the password manager for the challenge's origin
+    #[serde(default)]
+    save: bool,
+}
+
+/// What made the window fullscreen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum FullscreenMode {
+    /// The user, with F11
+    Window,
+    /// A page element, through the Fullscreen API
+    Element,
+}
+
+/// A tab's find-in-page search, offered again when its find bar reopens.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+struct FindState {
+    query: String,
+    case_sensitive: bool,
+    /// Matches on the page, and which one is highlighted (1-based, 0 for none)
+    matches: u64,
+    active: u64,
+}
+
+/// The toolbar, the find bar and the context menu, run in every page,
+/// after the request filter, which has to be in place before page scripts.
+const TOOLBAR_JS: &str = concat!(
+    include_str!("../../resources/ui/request_filter.js"),
+    "\n",
+    include_str!("../../resources/ui/toolbar.js"),
+    "\n",
+    include_str!("../../resources/ui/find.js"),
+    "\n",
+    include_str!("../../resources/ui/context_menu.js")
+);
+
+/// The page showing a tab's article in reader view.
+const READER_PAGE_URL: &str = "gb://localhost/reader";
+
+/// Images saved from the context menu larger than this are refused.
+const MAX_SAVED_IMAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Favicons larger than this are skipped; their `data:` URLs must fit the cache.
+const MAX_FAVICON_BYTES: usize = 96 * 1024;
+
+/// Icon URLs of a page tried before giving up.
+const MAX_FAVICON_TRIES: usize = 3;
+
+/// How often the OS color scheme and accent color are re-checked.
+const SYSTEM_THEME_POLL: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// How often the power source and the OS power saver are re-checked.
+const POWER_POLL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How often the theme schedule is checked for a due transition.
+const THEME_SCHEDULE_TICK: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How often background pages are started/stopped and sent queued messages.
+const EXTENSION_BACKGROUND_TICK: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// How often the hang watchdog runs.
+const WATCHDOG_TICK: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How often idle background tabs are checked for suspension.
+const TAB_SUSPEND_TICK: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Below this share of physical memory available, idle tabs are suspended early.
+const MEMORY_PRESSURE_FRACTION: f64 = 0.1;
+
+/// How often the open tabs are saved for restoring after a crash.
+const SESSION_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How often the database backup and maintenance schedules are checked.
+const BACKUP_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Height of the hang prompt strip.
+const HANG_PROMPT_HEIGHT: f64 = 56.0;
+
+/// Height of the HTTP credential prompt strip.
+const AUTH_PROMPT_HEIGHT: f64 = 56.0;
+
+/// Height of the external link prompt strip.
+const EXTERNAL_PROMPT_HEIGHT: f64 = 56.0;
+
+/// Height of the autofill prompt strip.
+const AUTOFILL_PROMPT_HEIGHT: f64 = 56.0;
+
+/// Height of the save login prompt strip.
+const SAVE_LOGIN_PROMPT_HEIGHT: f64 = 56.0;
+
+/// Recently closed tabs offered in the taskbar jump list or dock menu.
+const LAUNCHER_RECENT_TABS: usize = 10;
+
+/// Least time between two progress updates of a download.
+#[cfg(target_os = "linux")]
+const DOWNLOAD_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
 
 /// Build HTML for internal pages (newtab, settings).
 /// Toolbar JS is INLINED because `with_initialization_script` does NOT run
@@ -73,6 +294,7 @@ fn newtab_html() -> String {
 <div class="quick-link" data-url="https://reddit.com"><div class="quick-link-icon">R</div>Reddit</div>
 <div class="quick-link" data-url="https://stackoverflow.com"><div class="quick-link-icon">S</div>Stack Overflow</div>
 <div class="quick-link" data-url="https://wikipedia.org"><div class="quick-link-icon">W</div>Wikipedia</div>
+<div class="quick-link" data-url="about:reading-list"><div class="quick-link-icon">&#x2398;</div>Reading List</div>
 </div>
 </div>"#;
 
@@ -104,10 +326,15 @@ function applySettingsData(d){
   if(!d)return;
   var sv=function(id,v){var e=document.getElementById(id);if(e)e.value=v};
   var st=function(id,v){var e=document.getElementById(id);if(e){if(v)e.classList.add('on');else e.classList.remove('on')}};
-  if(d.general){sv('s-language',d.general.language);sv('s-startup',d.general.startup_behavior);sv('s-search',d.general.default_search_engine)}
-  if(d.privacy){st('s-trackers',d.privacy.tracker_blocking);st('s-ads',d.privacy.ad_blocking);st('s-https',d.privacy.https_enforcement);st('s-doh',d.privacy.dns_over_https);st('s-fingerprint',d.privacy.anti_fingerprinting);st('s-clearonexit',d.privacy.clear_data_on_exit)}
+  if(d.general){sv('s-language',d.general.language);sv('s-startup',d.general.startup_behavior);sv('s-useragent',d.general.user_agent||'')}
+  if(d.search){var ss=document.getElementById('s-search');if(ss){ss.innerHTML='';d.search.engines.forEach(function(e){var o=document.createElement('option');o.value=e.id;o.textContent=e.name+(e.keyword?' ('+e.keyword+')':'');ss.appendChild(o)});ss.value=d.search.default_engine}}
+  if(d.privacy){st('s-trackers',d.privacy.tracker_blocking);st('s-ads',d.privacy.ad_blocking);st('s-https',d.privacy.https_enforcement);st('s-doh',d.privacy.dns_over_https);st('s-fingerprint',d.privacy.anti_fingerprinting);st('s-clearonexit',d.privacy.clear_data_on_exit);sv('s-clipclear',d.privacy.clipboard_clear_seconds)}
   if(d.appearance){sv('s-theme',d.appearance.theme);sv('s-accent',d.appearance.accent_color);sv('s-fontsize',d.appearance.font_size)}
-  if(d.performance){sv('s-suspend',d.performance.tab_suspend_timeout_minutes);st('s-lazyimg',d.performance.lazy_load_images)}
+  if(d.performance){sv('s-suspend',d.performance.tab_suspend_timeout_minutes);st('s-lazyimg',d.performance.lazy_load_images);st('s-battery',d.performance.battery_saver)}
+  if(d.reader){sv('s-reader-preset',d.reader.preset);sv('s-reader-fontsize',d.reader.font_size);sv('s-reader-column',d.reader.column_width);st('s-reader-justify',d.reader.justify)}
+}
+function onSettingsChanged(c){
+  if(c.some(function(x){return x.key==='general.language'||x.key.indexOf('appearance.')===0}))location.reload();
 }
 document.querySelectorAll('.toggle').forEach(function(t){
   t.addEventListener('click',function(){
@@ -115,36 +342,830 @@ document.querySelectorAll('.toggle').forEach(function(t){
     var k=this.dataset.key;if(k)setSetting(k,this.classList.contains('on'));
   });
 });
+window.__gb_fileChosen=function(purpose,path){if(window.__gb_ipc)window.__gb_ipc(purpose,{path:path})};
 if(window.__gb_ipc)window.__gb_ipc('get_settings',{});
 "#;
 
     internal_page(&body, extra_css, js)
 }
 
+/// Offline reading list: saved articles, newest first.
+fn reading_list_html(app: &App) -> String {
+    use crate::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+    let mgr = ReadingListManager::new(app.db.connection());
+    let items = mgr.list_items(false, 500, 0).map(|(items, _)| items).unwrap_or_default();
+
+    let mut rows = String::new();
+    for item in &items {
+        let meta = format!(
+            "{}{} min read{}",
+            item.site_name.as_deref().map(|s| format!("{} · ", escape_html(s))).unwrap_or_default(),
+            item.estimated_read_time_minutes,
+            if item.read_at.is_some() { " · read" } else { "" },
+        );
+        rows.push_str(&format!(
+            "<div class=\"rl-item{}\" data-id=\"{}\"><a class=\"rl-title\" href=\"gb://localhost/reading-list/{}\">{}</a>\
+             <div class=\"rl-meta\">{} · {}</div>\
+             <div class=\"rl-actions\"><button class=\"rl-read\">{}</button><button class=\"rl-del\">Remove</button></div></div>",
+            if item.read_at.is_some() { " read" } else { "" },
+            escape_html(&item.id),
+            escape_html(&item.id),
+            escape_html(&item.title),
+            escape_html(&item.url),
+            meta,
+            if item.read_at.is_some() { "Mark unread" } else { "Mark read" },
+        ));
+    }
+    if items.is_empty() {
+        rows.push_str("<div class=\"rl-empty\">No saved articles yet. Use the &#x2398; toolbar button to save a page for offline reading.</div>");
+    }
+
+    let extra_css = ".rl-page{padding:32px 48px;max-width:800px;overflow-y:auto;height:100%;user-select:text}\
+.rl-page h1{font-size:24px;margin-bottom:16px}\
+.rl-item{padding:12px 0;border-bottom:1px solid var(--border-muted)}\
+.rl-item.read .rl-title{color:var(--fg-muted)}\
+.rl-title{color:var(--accent-fg);font-size:15px;text-decoration:none}\
+.rl-meta{color:var(--fg-muted);font-size:12px;margin:4px 0;word-break:break-all}\
+.rl-actions button{background:none;border:1px solid var(--border-default);color:var(--fg-default);border-radius:var(--radius-sm);padding:2px 8px;margin-right:6px;cursor:pointer;font-size:12px}\
+.rl-empty{color:var(--fg-muted)}";
+    let body = format!("<div class=\"rl-page\"><h1>Reading List</h1>{}</div>", rows);
+
+    let js = r#"
+document.querySelectorAll('.rl-item').forEach(function(el){
+  var id=el.dataset.id;
+  el.querySelector('.rl-read').addEventListener('click',function(){
+    if(window.__gb_ipc)window.__gb_ipc('reading_list_mark_read',{id:id,read:!el.classList.contains('read')});
+  });
+  el.querySelector('.rl-del').addEventListener('click',function(){
+    if(window.__gb_ipc)window.__gb_ipc('reading_list_delete',{id:id});
+  });
+});
+"#;
+
+    internal_page(&body, extra_css, js)
+}
+
+/// Downloads, newest first. Rows are drawn by the page script and kept
+/// current by `__gb_downloadUpdate` as the download manager reports changes.
+fn downloads_html(app: &App) -> String {
+    use crate::managers::download_manager::DownloadManagerTrait;
+    let items: Vec<serde_json::Value> = app
+        .download_manager
+        .list_downloads()
+        .into_iter()
+        .map(|d| crate::rpc_handler::download_json(app, d))
+        .collect();
+    // Inside a <script>, so a `</script>` in a URL must not end it
+    let data = serde_json::Value::from(items).to_string().replace("</", "<\\/");
+
+    let extra_css = ".dl-page{padding:32px 48px;max-width:800px;overflow-y:auto;height:100%}\
+.dl-page h1{font-size:24px;margin-bottom:16px}\
+.dl-item{padding:12px 0;border-bottom:1px solid var(--border-muted)}\
+.dl-name{font-size:15px;user-select:text}\
+.dl-item.failed .dl-name{color:var(--fg-muted);text-decoration:line-through}\
+.dl-url{color:var(--fg-subtle);font-size:12px;word-break:break-all;user-select:text}\
+.dl-bar{height:4px;background:var(--bg-subtle);border-radius:2px;margin:8px 0;overflow:hidden}\
+.dl-fill{height:100%;background:var(--accent-emphasis);transition:width var(--transition-normal)}\
+.dl-item.completed .dl-bar{display:none}\
+.dl-meta{color:var(--fg-muted);font-size:12px;margin:4px 0}\
+.dl-actions button{background:none;border:1px solid var(--border-default);color:var(--fg-default);border-radius:var(--radius-sm);padding:2px 8px;margin-right:6px;cursor:pointer;font-size:12px}\
+.dl-empty{color:var(--fg-muted)}";
+    let body = "<div class=\"dl-page\"><h1>Downloads</h1><div id=\"dl-list\"></div></div>";
+
+    let js = r#"
+var list=document.getElementById('dl-list');
+var rows={};
+function act(id,action){if(window.__gb_ipc)window.__gb_ipc('download_action',{id:id,action:action})}
+function statusOf(d){return typeof d.status==='string'?d.status:'Failed'}
+function metaOf(d){
+  var s=statusOf(d);
+  if(s==='Completed')return d.size_text||d.downloaded_text;
+  if(s==='Failed')return d.status.Failed;
+  var sizes=d.size_text?d.downloaded_text+' of '+d.size_text:d.downloaded_text;
+  return s==='Paused'?'Paused · '+sizes:s==='Pending'?'Downloading…':sizes;
+}
+function buttonsOf(s){
+  if(s==='InProgress'||s==='Pending')return [['pause','Pause'],['cancel','Cancel']];
+  if(s==='Paused')return [['resume','Resume'],['cancel','Cancel']];
+  if(s==='Completed')return [['open_folder','Show in folder']];
+  return [['retry','Retry']];
+}
+function render(d){
+  var el=rows[d.id];
+  if(!el){
+    el=document.createElement('div');
+    el.innerHTML='<div class="dl-name"></div><div class="dl-url"></div><div class="dl-bar"><div class="dl-fill"></div></div><div class="dl-meta"></div><div class="dl-actions"></div>';
+    rows[d.id]=el;
+    list.insertBefore(el,list.firstChild);
+  }
+  var s=statusOf(d);
+  el.className='dl-item '+s.toLowerCase();
+  el.querySelector('.dl-name').textContent=d.filename;
+  el.querySelector('.dl-url').textContent=d.url;
+  el.querySelector('.dl-fill').style.width=(d.progress==null?0:d.progress*100)+'%';
+  el.querySelector('.dl-meta').textContent=metaOf(d);
+  var actions=el.querySelector('.dl-actions');
+  actions.innerHTML='';
+  buttonsOf(s).forEach(function(b){
+    var btn=document.createElement('button');
+    btn.textContent=b[1];
+    btn.onclick=function(){act(d.id,b[0])};
+    actions.appendChild(btn);
+  });
+  var empty=document.querySelector('.dl-empty');
+  if(empty)empty.remove();
+}
+// Pushed from Rust whenever a download changes
+window.__gb_downloadUpdate=render;
+var initial=__DOWNLOADS__;
+initial.slice().reverse().forEach(render);
+if(!initial.length)list.innerHTML='<div class="dl-empty">No downloads yet.</div>';
+"#
+    .replace("__DOWNLOADS__", &data);
+
+    internal_page(body, extra_css, &js)
+}
+
+/// The password vault. The page starts empty and asks for the vault's state
+/// over IPC; a password only reaches it when its Reveal button is pressed.
+fn passwords_html() -> String {
+    let extra_css = ".pw-page{padding:32px 48px;max-width:860px;overflow-y:auto;height:100%}\
+.pw-page h1{font-size:24px;margin-bottom:16px;display:flex;align-items:center;gap:12px}\
+.pw-page h2{font-size:15px;margin:24px 0 8px}\
+.pw-page input[type=text],.pw-page input[type=password],.pw-page input[type=number]{height:28px;border:1px solid var(--border-default);border-radius:var(--radius-sm);background:var(--bg-default);color:var(--fg-default);padding:0 8px;font:inherit;user-select:text}\
+.pw-page button{background:none;border:1px solid var(--border-default);color:var(--fg-default);border-radius:var(--radius-sm);padding:4px 10px;cursor:pointer;font-size:12px}\
+.pw-page button.pw-primary{background:var(--success-emphasis);border-color:var(--success-emphasis);color:#fff}\
+.pw-row{display:flex;gap:8px;align-items:center;flex-wrap:wrap;margin:6px 0}\
+.pw-item{display:grid;grid-template-columns:1fr 1fr 140px auto;gap:8px;align-items:center;padding:8px 0;border-bottom:1px solid var(--border-muted)}\
+.pw-url,.pw-user{overflow:hidden;text-overflow:ellipsis;white-space:nowrap;user-select:text}\
+.pw-url{color:var(--accent-fg)}\
+.pw-secret{font-family:monospace;color:var(--fg-muted);user-select:text;overflow:hidden;text-overflow:ellipsis}\
+.pw-msg{font-size:13px;margin:8px 0;min-height:18px}\
+.pw-msg.error{color:var(--danger-fg)}\
+.pw-msg.notice{color:var(--success-fg)}\
+.pw-empty{color:var(--fg-muted);padding:8px 0}\
+.pw-hidden{display:none!important}\
+#pw-generated{min-width:240px}";
+    let body = r#"<div class="pw-page">
+<h1>Passwords <button id="pw-lock" class="pw-hidden">Lock</button></h1>
+<div id="pw-msg" class="pw-msg"></div>
+<div id="pw-locked" class="pw-hidden">
+<p>Enter your master password to unlock the vault.</p>
+<div class="pw-row"><input id="pw-master" type="password" placeholder="Master password" autocomplete="off"/><button id="pw-unlock" class="pw-primary">Unlock</button></div>
+</div>
+<div id="pw-vault" class="pw-hidden">
+<div class="pw-row"><input id="pw-filter" type="text" placeholder="Search saved logins" spellcheck="false"/></div>
+<div id="pw-list"></div>
+<h2>Add a login</h2>
+<div class="pw-row"><input id="pw-new-url" type="text" placeholder="https://example.com" spellcheck="false"/><input id="pw-new-user" type="text" placeholder="Username" spellcheck="false"/><input id="pw-new-pass" type="password" placeholder="Password" autocomplete="new-password"/><button id="pw-save" class="pw-primary">Save</button></div>
+<h2>Generate a password</h2>
+<div class="pw-row"><input id="pw-gen-length" type="number" min="4" max="128" value="20"/>
+<label><input id="pw-gen-upper" type="checkbox" checked/> A-Z</label><label><input id="pw-gen-lower" type="checkbox" checked/> a-z</label>
+<label><input id="pw-gen-numbers" type="checkbox" checked/> 0-9</label><label><input id="pw-gen-symbols" type="checkbox" checked/> Symbols</label>
+<button id="pw-generate">Generate</button></div>
+<div class="pw-row"><input id="pw-generated" type="text" readonly/><button id="pw-use">Use for new login</button></div>
+<h2>Import and export</h2>
+<p class="pw-empty">Exports are encrypted with the password you give here, which is needed again to import them. Leave the path empty to choose the file.</p>
+<div class="pw-row"><input id="pw-file" type="text" placeholder="File path" spellcheck="false"/><input id="pw-file-pass" type="password" placeholder="File password" autocomplete="off"/><button id="pw-export">Export</button><button id="pw-import">Import</button></div>
+</div>
+</div>"#;
+
+    let js = r#"
+function $(id){return document.getElementById(id)}
+function send(cmd,data){if(window.__gb_ipc)window.__gb_ipc(cmd,data||{})}
+var credentials=[];
+var hideTimers={};
+function message(kind,text){var m=$('pw-msg');m.className='pw-msg '+kind;m.textContent=text||''}
+function renderList(){
+  var list=$('pw-list'),q=$('pw-filter').value.toLowerCase();
+  list.innerHTML='';
+  var shown=credentials.filter(function(c){return !q||c.url.toLowerCase().indexOf(q)>=0||c.username.toLowerCase().indexOf(q)>=0});
+  if(!shown.length){list.innerHTML='<div class="pw-empty">'+(credentials.length?'No matching logins.':'No saved logins yet.')+'</div>';return}
+  shown.forEach(function(c){
+    var row=document.createElement('div');
+    row.className='pw-item';
+    row.dataset.id=c.id;
+    row.innerHTML='<div class="pw-url"></div><div class="pw-user"></div><div class="pw-secret">••••••••</div><div><button class="pw-reveal">Reveal</button> <button class="pw-copy">Copy</button> <button class="pw-del">Delete</button></div>';
+    row.querySelector('.pw-url').textContent=c.url;
+    row.querySelector('.pw-user').textContent=c.username;
+    row.querySelector('.pw-reveal').onclick=function(){
+      if(this.textContent==='Hide'){conceal(c.id);return}
+      send('passwords_reveal',{id:c.id});
+    };
+    row.querySelector('.pw-copy').onclick=function(){send('passwords_copy',{id:c.id})};
+    row.querySelector('.pw-del').onclick=function(){
+      if(confirm('Delete the saved login for '+c.username+'?'))send('passwords_delete',{id:c.id});
+    };
+    list.appendChild(row);
+  });
+}
+function rowOf(id){return document.querySelector('.pw-item[data-id="'+id+'"]')}
+function conceal(id){
+  clearTimeout(hideTimers[id]);
+  var row=rowOf(id);
+  if(!row)return;
+  row.querySelector('.pw-secret').textContent='••••••••';
+  row.querySelector('.pw-reveal').textContent='Reveal';
+}
+// Replies from Rust
+window.__gb_passwords=function(r){
+  if(r.type==='state'){
+    $('pw-locked').classList.toggle('pw-hidden',r.unlocked);
+    $('pw-vault').classList.toggle('pw-hidden',!r.unlocked);
+    $('pw-lock').classList.toggle('pw-hidden',!r.unlocked);
+    credentials=r.credentials||[];
+    renderList();
+    message(r.notice?'notice':'',r.notice);
+    if(!r.unlocked)setTimeout(function(){$('pw-master').focus()},50);
+  }else if(r.type==='revealed'){
+    var row=rowOf(r.id);
+    if(!row)return;
+    row.querySelector('.pw-secret').textContent=r.password;
+    row.querySelector('.pw-reveal').textContent='Hide';
+    clearTimeout(hideTimers[r.id]);
+    hideTimers[r.id]=setTimeout(function(){conceal(r.id)},30000);
+  }else if(r.type==='generated'){
+    $('pw-generated').value=r.password;
+  }else if(r.type==='error'){
+    message('error',r.message);
+  }
+};
+function unlock(){var m=$('pw-master');send('passwords_unlock',{master_password:m.value});m.value=''}
+$('pw-unlock').onclick=unlock;
+$('pw-master').addEventListener('keydown',function(e){if(e.key==='Enter')unlock()});
+$('pw-lock').onclick=function(){send('passwords_lock')};
+$('pw-filter').addEventListener('input',renderList);
+$('pw-save').onclick=function(){
+  send('passwords_save',{url:$('pw-new-url').value.trim(),username:$('pw-new-user').value,password:$('pw-new-pass').value});
+  $('pw-new-pass').value='';
+};
+$('pw-generate').onclick=function(){
+  send('passwords_generate',{length:parseInt($('pw-gen-length').value,10)||20,uppercase:$('pw-gen-upper').checked,
+    lowercase:$('pw-gen-lower').checked,numbers:$('pw-gen-numbers').checked,symbols:$('pw-gen-symbols').checked});
+};
+$('pw-use').onclick=function(){$('pw-new-pass').value=$('pw-generated').value;$('pw-generated').value=''};
+function transfer(cmd){
+  if(!$('pw-file').value.trim()){send('choose_file',{purpose:cmd});return}
+  send(cmd,{path:$('pw-file').value.trim(),master_password:$('pw-file-pass').value});
+  $('pw-file-pass').value='';
+}
+window.__gb_fileChosen=function(purpose,path){$('pw-file').value=path;transfer(purpose)};
+$('pw-export').onclick=function(){transfer('passwords_export')};
+$('pw-import').onclick=function(){transfer('passwords_import')};
+send('passwords_state');
+"#;
+
+    internal_page(body, extra_css, js)
+}
+
+/// Installed extensions, with the permission prompt for new installs. Like
+/// the vault page it is filled in over IPC.
+fn extensions_html() -> String {
+    let extra_css = ".ext-page{padding:32px 48px;max-width:860px;overflow-y:auto;height:100%}\
+.ext-page h1{font-size:24px;margin-bottom:16px}\
+.ext-page h2{font-size:15px;margin:24px 0 8px}\
+.ext-page input[type=text]{height:28px;min-width:360px;border:1px solid var(--border-default);border-radius:var(--radius-sm);background:var(--bg-default);color:var(--fg-default);padding:0 8px;font:inherit;user-select:text}\
+.ext-page button{background:none;border:1px solid var(--border-default);color:var(--fg-default);border-radius:var(--radius-sm);padding:4px 10px;cursor:pointer;font-size:12px}\
+.ext-page button.ext-primary{background:var(--success-emphasis);border-color:var(--success-emphasis);color:#fff}\
+.ext-item{display:flex;gap:16px;align-items:flex-start;padding:12px 0;border-bottom:1px solid var(--border-muted)}\
+.ext-item.disabled .ext-name{color:var(--fg-muted)}\
+.ext-main{flex:1;min-width:0}\
+.ext-name{font-size:15px}\
+.ext-version,.ext-impact,.ext-path{color:var(--fg-muted);font-size:12px;margin-top:2px}\
+.ext-path{color:var(--fg-subtle);word-break:break-all;user-select:text}\
+.ext-perms{list-style:none;margin:6px 0 0;padding:0;font-size:12px;color:var(--fg-muted)}\
+.ext-perms li::before{content:'• '}\
+.ext-actions{display:flex;gap:6px;align-items:center}\
+.ext-row{display:flex;gap:8px;align-items:center;flex-wrap:wrap;margin:6px 0}\
+.ext-msg{font-size:13px;margin:8px 0;min-height:18px}\
+.ext-msg.error{color:var(--danger-fg)}\
+.ext-msg.notice{color:var(--success-fg)}\
+.ext-empty{color:var(--fg-muted);padding:8px 0}\
+.ext-prompt{border:1px solid var(--border-default);border-radius:var(--radius-md);padding:16px;margin:12px 0;background:var(--bg-default)}\
+.ext-hidden{display:none!important}";
+    let body = r#"<div class="ext-page">
+<h1>Extensions</h1>
+<div id="ext-msg" class="ext-msg"></div>
+<div id="ext-list"></div>
+<h2>Install an extension</h2>
+<div class="ext-row"><input id="ext-path" type="text" placeholder="Folder with a manifest.json, or a .zip/.crx file" spellcheck="false"/><button id="ext-install" class="ext-primary">Install</button><button id="ext-choose-file">Choose File…</button><button id="ext-choose-folder">Choose Folder…</button></div>
+<div id="ext-prompt" class="ext-prompt ext-hidden">
+<div id="ext-prompt-title"></div>
+<ul id="ext-prompt-perms" class="ext-perms"></ul>
+<div class="ext-row"><button id="ext-confirm" class="ext-primary">Add extension</button><button id="ext-cancel">Cancel</button></div>
+</div>
+</div>"#;
+
+    let js = r#"
+function $(id){return document.getElementById(id)}
+function send(cmd,data){if(window.__gb_ipc)window.__gb_ipc(cmd,data||{})}
+var PERMISSIONS={
+  pagecontent:'Read and change the pages you visit',
+  storage:'Store data on this device',
+  toolbar:'Add a toolbar button',
+  tabs:'See and manage your tabs',
+  network:'Make network requests',
+  bookmarks:'Read and change your bookmarks',
+  notifications:'Show notifications',
+  declarativenetrequest:'Block or allow network requests'
+};
+var pending=null;
+function message(kind,text){var m=$('ext-msg');m.className='ext-msg '+kind;m.textContent=text||''}
+function permList(el,perms){
+  el.innerHTML='';
+  perms.forEach(function(p){var li=document.createElement('li');li.textContent=PERMISSIONS[p]||p;el.appendChild(li)});
+  if(!perms.length){var li=document.createElement('li');li.textContent='No special permissions';el.appendChild(li)}
+}
+function render(exts){
+  var list=$('ext-list');
+  list.innerHTML='';
+  if(!exts.length){list.innerHTML='<div class="ext-empty">No extensions installed.</div>';return}
+  exts.forEach(function(e){
+    var row=document.createElement('div');
+    row.className='ext-item'+(e.enabled?'':' disabled');
+    row.innerHTML='<div class="ext-main"><div class="ext-name"></div><div class="ext-version"></div><div class="ext-impact"></div><ul class="ext-perms"></ul><div class="ext-path"></div></div><div class="ext-actions"><div class="toggle"></div><button class="ext-options ext-hidden">Options</button><button class="ext-remove">Remove</button></div>';
+    row.querySelector('.ext-name').textContent=e.name;
+    row.querySelector('.ext-version').textContent='Version '+e.version;
+    row.querySelector('.ext-impact').textContent=e.performance_impact_ms
+      ?'Adds about '+e.performance_impact_ms+' ms to page loads':'No measurable effect on page loads';
+    row.querySelector('.ext-path').textContent=e.install_path;
+    permList(row.querySelector('.ext-perms'),e.permissions);
+    var t=row.querySelector('.toggle');
+    if(e.enabled)t.classList.add('on');
+    t.title=e.enabled?'Disable':'Enable';
+    t.onclick=function(){send('extensions_toggle',{id:e.id,enabled:!e.enabled})};
+    if(e.options_url){
+      var o=row.querySelector('.ext-options');
+      o.classList.remove('ext-hidden');
+      o.onclick=function(){location.href=e.options_url};
+    }
+    row.querySelector('.ext-remove').onclick=function(){
+      if(confirm('Remove '+e.name+'?'))send('extensions_uninstall',{id:e.id});
+    };
+    list.appendChild(row);
+  });
+}
+// Replies from Rust
+window.__gb_extensions=function(r){
+  if(r.type==='list'){
+    render(r.extensions);
+    message(r.notice?'notice':'',r.notice);
+  }else if(r.type==='pending'){
+    pending=r.install;
+    $('ext-prompt-title').textContent='Add '+pending.name+' '+pending.version+'? It will be able to:';
+    permList($('ext-prompt-perms'),pending.permissions);
+    $('ext-prompt').classList.remove('ext-hidden');
+    message('','');
+  }else if(r.type==='error'){
+    message('error',r.message);
+  }
+};
+function closePrompt(){pending=null;$('ext-prompt').classList.add('ext-hidden')}
+$('ext-install').onclick=function(){var p=$('ext-path').value.trim();if(p)send('extensions_install',{path:p})};
+$('ext-choose-file').onclick=function(){send('choose_file',{purpose:'extensions_file'})};
+$('ext-choose-folder').onclick=function(){send('choose_file',{purpose:'extensions_folder'})};
+window.__gb_fileChosen=function(purpose,path){$('ext-path').value=path;send('extensions_install',{path:path})};
+$('ext-confirm').onclick=function(){
+  if(!pending)return;
+  send('extensions_confirm',{install_id:pending.install_id,permissions:pending.permissions});
+  closePrompt();
+  $('ext-path').value='';
+};
+$('ext-cancel').onclick=function(){if(pending)send('extensions_cancel',{install_id:pending.install_id});closePrompt()};
+send('extensions_list');
+"#;
+
+    internal_page(body, extra_css, js)
+}
+
+/// Privacy dashboard: what was blocked, globally and per site, and the
+/// switches behind it. Filled in over IPC like the vault page.
+fn privacy_html() -> String {
+    let extra_css = ".pv-page{padding:32px 48px;max-width:860px;overflow-y:auto;height:100%}\
+.pv-page h1{font-size:24px;margin-bottom:16px}\
+.pv-page h2{font-size:15px;margin:24px 0 8px}\
+.pv-cards{display:grid;grid-template-columns:repeat(4,1fr);gap:12px}\
+.pv-card{border:1px solid var(--border-default);border-radius:var(--radius-md);padding:12px;background:var(--bg-default)}\
+.pv-num{font-size:22px}\
+.pv-label{color:var(--fg-muted);font-size:12px;margin-top:2px}\
+.pv-row{display:flex;align-items:center;justify-content:space-between;gap:12px;padding:8px 0;border-bottom:1px solid var(--border-muted)}\
+.pv-row .pv-sub{color:var(--fg-muted);font-size:12px}\
+.pv-site{user-select:text;overflow:hidden;text-overflow:ellipsis}\
+.pv-empty{color:var(--fg-muted);padding:8px 0}\
+.pv-stale{color:var(--danger-fg)}";
+    let body = r#"<div class="pv-page">
+<h1>Privacy</h1>
+<div class="pv-cards">
+<div class="pv-card"><div class="pv-num" id="pv-total">0</div><div class="pv-label">Requests blocked</div></div>
+<div class="pv-card"><div class="pv-num" id="pv-trackers">0</div><div class="pv-label">Trackers blocked</div></div>
+<div class="pv-card"><div class="pv-num" id="pv-ads">0</div><div class="pv-label">Ads blocked</div></div>
+<div class="pv-card"><div class="pv-num" id="pv-https">0</div><div class="pv-label">Upgraded to HTTPS</div></div>
+</div>
+<h2>Protections</h2>
+<div id="pv-toggles"></div>
+<h2>Most blocked sites</h2>
+<div id="pv-sites"></div>
+<h2>Sites with protections off</h2>
+<div id="pv-disabled"></div>
+<h2>Filter lists</h2>
+<div id="pv-lists"></div>
+</div>"#;
+
+    let js = r#"
+function $(id){return document.getElementById(id)}
+function send(cmd,data){if(window.__gb_ipc)window.__gb_ipc(cmd,data||{})}
+var TOGGLES=[
+  ['tracker_blocking','Block trackers',''],
+  ['ad_blocking','Block ads',''],
+  ['https_enforcement','Upgrade connections to HTTPS',''],
+  ['dns_over_https','Secure DNS (DNS over HTTPS)','dns'],
+  ['anti_fingerprinting','Resist fingerprinting','']
+];
+var LISTS={trackers:'Tracker list',ads:'Ad list',custom:'Your filters'};
+function row(label,sub,control){
+  var r=document.createElement('div');
+  r.className='pv-row';
+  r.innerHTML='<div><div class="pv-site"></div><div class="pv-sub"></div></div>';
+  r.querySelector('.pv-site').textContent=label;
+  r.querySelector('.pv-sub').textContent=sub||'';
+  if(control)r.appendChild(control);
+  return r;
+}
+function toggle(on,title,onclick){
+  var t=document.createElement('div');
+  t.className='toggle'+(on?' on':'');
+  t.title=title;
+  t.onclick=onclick;
+  return t;
+}
+function fill(id,rows,empty){
+  var el=$(id);
+  el.innerHTML='';
+  if(!rows.length){el.innerHTML='<div class="pv-empty"></div>';el.firstChild.textContent=empty;return}
+  rows.forEach(function(r){el.appendChild(r)});
+}
+function age(ts){
+  var days=Math.floor((Date.now()/1000-ts)/86400);
+  return days<1?'today':days===1?'yesterday':days+' days ago';
+}
+// Pushed from Rust with the dashboard's data
+window.__gb_privacy=function(d){
+  $('pv-total').textContent=d.total_blocked;
+  $('pv-trackers').textContent=d.stats.trackers_blocked;
+  $('pv-ads').textContent=d.stats.ads_blocked;
+  $('pv-https').textContent=d.stats.https_upgrades;
+  fill('pv-toggles',TOGGLES.map(function(t){
+    var on=d.toggles[t[0]];
+    var sub=t[2]==='dns'?(d.dns_over_https?'Using '+d.dns_over_https:'Using the system resolver'):'';
+    return row(t[1],sub,toggle(on,on?'Turn off':'Turn on',function(){send('privacy_set',{key:t[0],value:!on})}));
+  }),'');
+  fill('pv-sites',d.top_sites.map(function(s){
+    var sub=s.blocked+' request'+(s.blocked===1?'':'s')+' blocked'+(s.shields?'':' · protections off');
+    return row(s.site,sub,toggle(s.shields,'Protections on this site',function(){
+      send('privacy_site',{site:s.site,shields:!s.shields});
+    }));
+  }),'Nothing blocked yet.');
+  fill('pv-disabled',d.disabled_sites.map(function(site){
+    return row(site,'',toggle(false,'Turn protections back on',function(){
+      send('privacy_site',{site:site,shields:true});
+    }));
+  }),'Protections are on everywhere.');
+  fill('pv-lists',d.lists.map(function(l){
+    var sub=l.entries+' entr'+(l.entries===1?'y':'ies')+(l.enabled?'':' · off');
+    var r=row(LISTS[l.id]||l.id,sub);
+    if(l.updated_at){
+      var u=document.createElement('div');
+      u.className='pv-sub'+((Date.now()/1000-l.updated_at)>90*86400?' pv-stale':'');
+      u.textContent='Updated '+age(l.updated_at);
+      r.appendChild(u);
+    }
+    return r;
+  }),'');
+};
+send('privacy_state');
+"#;
+
+    internal_page(body, extra_css, js)
+}
+
+/// Shown in place of a tab whose renderer crashed, until it is reloaded.
+fn crashed_html(app: &App) -> String {
+    use crate::managers::tab_manager::TabManagerTrait;
+    let url = app.tab_manager.get_active_tab().map(|t| t.url.clone()).unwrap_or_default();
+    let extra_css = ".crashed-page{display:flex;flex-direction:column;align-items:center;justify-content:center;flex:1;height:100%;gap:12px;text-align:center}\
+.crashed-page h1{font-size:22px}\
+.crashed-page p{color:var(--fg-muted);font-size:14px;max-width:480px}\
+.crashed-url{color:var(--fg-subtle);font-size:12px;word-break:break-all;max-width:600px;user-select:text}\
+.crashed-reload{background:var(--success-emphasis);border:none;color:#fff;border-radius:var(--radius-sm);padding:6px 16px;cursor:pointer;font-size:14px}";
+    let body = format!(
+        "<div class=\"crashed-page\"><h1>This tab crashed</h1>\
+         <p>The page stopped working. Other tabs are not affected.</p>\
+         <div class=\"crashed-url\">{}</div>\
+         <button class=\"crashed-reload\">Reload</button></div>",
+        escape_html(&url)
+    );
+    let js = r#"
+document.querySelector('.crashed-reload').addEventListener('click',function(){
+  if(window.__gb_ipc)window.__gb_ipc('reload_crashed',{});
+});
+"#;
+    internal_page(&body, extra_css, js)
+}
+
+/// Shown in place of a page that failed to load, with why and a retry.
+fn load_error_html(app: &App, tab_id: &str) -> String {
+    use crate::managers::tab_manager::TabManagerTrait;
+    use crate::types::tab::LoadErrorKind;
+    let url = app.tab_manager.get_tab(tab_id).map(|t| t.url.clone()).unwrap_or_default();
+    let (kind, detail) = app.tab_manager.load_error(tab_id)
+        .map_or((LoadErrorKind::Other, String::new()), |e| (e.kind, e.detail.clone()));
+    let extra_css = ".load-error{display:flex;flex-direction:column;align-items:center;justify-content:center;flex:1;height:100%;gap:12px;text-align:center}\
+.load-error h1{font-size:22px}\
+.load-error p{color:var(--fg-muted);font-size:14px;max-width:480px}\
+.load-error-url{color:var(--fg-subtle);font-size:12px;word-break:break-all;max-width:600px;user-select:text}\
+.load-error-detail{color:var(--fg-subtle);font-size:11px;font-family:monospace}\
+.load-error-retry{background:var(--success-emphasis);border:none;color:#fff;border-radius:var(--radius-sm);padding:6px 16px;cursor:pointer;font-size:14px}";
+    let body = format!(
+        "<div class=\"load-error\"><h1>{}</h1>\
+         <p>{}</p>\
+         <div class=\"load-error-url\">{}</div>\
+         <div class=\"load-error-detail\">{}</div>\
+         <button class=\"load-error-retry\">Try again</button></div>",
+        kind.title(),
+        kind.hint(),
+        escape_html(&url),
+        escape_html(&detail)
+    );
+    let js = r#"
+document.querySelector('.load-error-retry').addEventListener('click',function(){
+  if(window.__gb_ipc)window.__gb_ipc('retry_load',{});
+});
+"#;
+    internal_page(&body, extra_css, js)
+}
+
+/// Offered at startup after a crash: the last session's tabs, to restore
+/// or to drop.
+fn restore_html(app: &App) -> String {
+    let tabs = app.crash_restore_prompt()
+        .and_then(|prompt| prompt.get("tabs").and_then(|t| t.as_array()).cloned())
+        .unwrap_or_default();
+    let rows: String = tabs.iter().map(|t| {
+        let url = t.get("url").and_then(|v| v.as_str()).unwrap_or("");
+        let title = t.get("title").and_then(|v| v.as_str()).filter(|s| !s.is_empty()).unwrap_or(url);
+        format!(
+            "<li><div class=\"restore-title\">{}{}</div><div class=\"restore-url\">{}</div></li>",
+            if t.get("pinned").and_then(|v| v.as_bool()).unwrap_or(false) { "📌 " } else { "" },
+            escape_html(title),
+            escape_html(url)
+        )
+    }).collect();
+    let extra_css = ".restore-page{max-width:640px;margin:48px auto;padding:0 24px}\
+.restore-page h1{font-size:22px;margin-bottom:8px}\
+.restore-page p{color:var(--fg-muted);font-size:14px}\
+.restore-tabs{list-style:none;padding:0;margin:16px 0;border:1px solid var(--border-default);border-radius:var(--radius-sm)}\
+.restore-tabs li{padding:8px 12px;border-bottom:1px solid var(--border-default)}\
+.restore-tabs li:last-child{border-bottom:none}\
+.restore-title{font-size:14px}\
+.restore-url{color:var(--fg-subtle);font-size:12px;word-break:break-all}\
+.restore-actions button{border-radius:var(--radius-sm);padding:6px 16px;cursor:pointer;font-size:14px;margin-right:8px}\
+.restore-yes{background:var(--success-emphasis);border:none;color:#fff}\
+.restore-no{background:none;border:1px solid var(--border-default);color:var(--fg-default)}";
+    let body = format!(
+        "<div class=\"restore-page\"><h1>GitBrowser closed unexpectedly</h1>\
+         <p>Your last session had {} tab{}.</p>\
+         <ul class=\"restore-tabs\">{}</ul>\
+         <div class=\"restore-actions\"><button class=\"restore-yes\">Restore previous session</button>\
+         <button class=\"restore-no\">Start fresh</button></div></div>",
+        tabs.len(),
+        if tabs.len() == 1 { "" } else { "s" },
+        rows
+    );
+    let js = r#"
+document.querySelector('.restore-yes').addEventListener('click',function(){
+  if(window.__gb_ipc)window.__gb_ipc('crash_restore',{restore:true});
+});
+document.querySelector('.restore-no').addEventListener('click',function(){
+  if(window.__gb_ipc)window.__gb_ipc('crash_restore',{restore:false});
+});
+"#;
+    internal_page(&body, extra_css, js)
+}
+
+/// A saved article rendered offline with the current reader settings and
+/// the user's highlights re-applied.
+fn reading_list_article_html(app: &App, id: &str) -> String {
+    use crate::managers::annotation_manager::{AnnotationManager, AnnotationManagerTrait};
+    use crate::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+    use crate::services::reader_mode::ReaderModeTrait;
+    let mgr = ReadingListManager::new(app.db.connection());
+    let item = match mgr.get_item(id) {
+        Ok(item) => item,
+        Err(_) => return reading_list_html(app),
+    };
+    let annotations = AnnotationManager::new(app.db.connection())
+        .list_annotations(&item.url)
+        .unwrap_or_default();
+    let article_url = serde_json::to_string(&item.url).unwrap_or_default().replace("</", "<\\/");
+    let content = crate::types::reader::ReaderContent {
+        title: item.title,
+        content: AnnotationManager::apply_annotations(&item.content_html, &annotations),
+        text_content: item.text_content,
+        author: item.author,
+        publish_date: None,
+        site_name: item.site_name,
+        estimated_read_time_minutes: item.estimated_read_time_minutes,
+        page_count: item.page_count,
+    };
+    let html = app.reader_mode.format_for_display(&content, app.reader_mode.get_settings());
+    let annotate_js = format!(r#"
+var GB_ARTICLE_URL={};
+document.addEventListener('mouseup',function(){{
+  var sel=window.getSelection();var text=sel?sel.toString():'';
+  if(!text.trim()||!sel.rangeCount)return;
+  var r=sel.getRangeAt(0);
+  var pre=r.startContainer.nodeType===3?r.startContainer.textContent.slice(0,r.startOffset).slice(-32):'';
+  var suf=r.endContainer.nodeType===3?r.endContainer.textContent.slice(r.endOffset).slice(0,32):'';
+  var note=prompt('Highlight note (optional):','');
+  if(note===null)return;
+  if(window.__gb_ipc)window.__gb_ipc('annotation_add',{{url:GB_ARTICLE_URL,text:text,prefix:pre,suffix:suf,note:note||null}});
+}});
+document.querySelectorAll('mark.gb-annotation').forEach(function(m){{
+  m.style.cursor='pointer';
+  m.addEventListener('click',function(e){{
+    e.stopPropagation();
+    if(confirm('Remove this highlight?')&&window.__gb_ipc)window.__gb_ipc('annotation_delete',{{id:m.dataset.annotationId}});
+  }});
+}});
+"#, article_url);
+    // Inline the toolbar (initialization scripts don't run on gb:// pages on Windows)
+    html.replacen("</body>", &format!("<script>{}</script><script>{}</script></body>", TOOLBAR_JS, annotate_js), 1)
+}
+
+/// Tab `tab_id`'s article in reader view, with the current reader settings.
+/// Its links and images resolve against the page it was made from.
+fn reader_html(state: &BrowserState, tab_id: &str) -> String {
+    use crate::services::reader_mode::ReaderModeTrait;
+    let Some((source, content)) = state.readers.get(tab_id) else {
+        return newtab_html();
+    };
+    let reader = &state.app.reader_mode;
+    let html = reader.format_for_display(content, reader.get_settings());
+    let html = html.replacen("<head>", &format!("<head><base href=\"{}\">", escape_html(source)), 1);
+    // Inline the toolbar (initialization scripts don't run on gb:// pages on Windows)
+    html.replacen("</body>", &format!("<script>{}</script></body>", TOOLBAR_JS), 1)
+}
+
 // ─── IPC handler ───
 
-fn handle_ipc(state: &mut BrowserState, message: &str) -> Option<UserEvent> {
+/// `page_url` is the URL of the page that posted the message, as reported by
+/// the webview (not by the page's own JS).
+/// Handles IPC from the page in tab `tab_id`'s webview, usually the active tab.
+fn handle_ipc(state: &mut BrowserState, message: &str, page_url: &str, tab_id: &str) -> Option<UserEvent> {
     let msg: serde_json::Value = serde_json::from_str(message).ok()?;
     let cmd = msg.get("cmd")?.as_str()?;
 
     match cmd {
         "ui_ready" => {
-            // Toolbar just loaded on a page — send current tabs state
-            Some(UserEvent::EvalScript(build_tabs_update(state)))
+            {
+                // The UI came up, so an update just installed is good to keep
+                use crate::services::update_installer::UpdateInstallerTrait;
+                if let Err(e) = state.app.update_installer.confirm_started() {
+                    eprintln!("[UPDATE] {}", e);
+                }
+            }
+            // Toolbar just loaded on a page — send current tabs state and key bindings
+            let mut js = build_tabs_update(state);
+            {
+                use crate::managers::shortcut_manager::ShortcutManagerTrait;
+                let shortcuts = serde_json::to_string(state.app.shortcut_manager.list_shortcuts()).unwrap_or_default();
+                js.push_str(&format!(";if(window.__gb_setShortcuts)__gb_setShortcuts({})", shortcuts));
+            }
+            {
+                use crate::services::localization_engine::LocalizationEngineTrait;
+                let dir = state.app.localization_engine.get_direction();
+                js.push_str(&format!(";if(window.__gb_setDirection)__gb_setDirection('{}')", dir.as_str()));
+            }
+            // A login submitted on the previous page is kept while the tab stays on its origin
+            let _ = kept_login(state, tab_id, page_url);
+            if let Some(url) = msg.get("url").and_then(|v| v.as_str()) {
+                if state.app.force_dark_for(url) {
+                    js.push(';');
+                    js.push_str(&force_dark_update(state, true));
+                }
+                use crate::services::extension_framework::{content_script_injection, ExtensionFrameworkTrait};
+                let scripts = state.app.extension_framework.get_content_scripts_for_url(url);
+                if !scripts.is_empty() {
+                    js.push(';');
+                    js.push_str(&content_script_injection(&scripts));
+                }
+                // A new page in the tab takes on its site's zoom
+                if let Ok(zoom) = state.app.apply_site_zoom(tab_id, url) {
+                    return Some(UserEvent::Zoom(zoom, js));
+                }
+            }
+            Some(UserEvent::EvalScript(js))
+        }
+
+        "ext_message" => {
+            // runtime.sendMessage from a content script or extension page;
+            // answered once the extension's background context replies
+            use crate::services::extension_framework::ExtensionFrameworkTrait;
+            let ext = msg.get("ext").and_then(|v| v.as_str())?;
+            let request_id = msg.get("id").and_then(|v| v.as_u64());
+            let payload = msg.get("message").cloned().unwrap_or(serde_json::Value::Null);
+            let sender = page_sender(page_url, request_id);
+            match state.app.extension_framework.send_message(ext, sender, payload) {
+                Ok(_) => None,
+                Err(e) => request_id.map(|id| {
+                    UserEvent::EvalScript(ext_reply_script(id, &serde_json::Value::Null, Some(&e.to_string())))
+                }),
+            }
+        }
+
+        "ext_storage" => {
+            // chrome.storage from a content script or extension page, scoped like runtime.sendMessage
+            use crate::services::extension_framework::ExtensionFrameworkTrait;
+            let ext = msg.get("ext").and_then(|v| v.as_str())?;
+            let request_id = msg.get("id").and_then(|v| v.as_u64())?;
+            let sender = page_sender(page_url, Some(request_id));
+            let result = state.app.extension_framework.check_sender(ext, &sender)
+                .and_then(|_| ext_storage_op(&mut state.app, ext, &msg));
+            Some(UserEvent::EvalScript(ext_result_script(request_id, result)))
+        }
+
+        "toggle_force_dark" => {
+            let url = msg.get("url").and_then(|v| v.as_str())?;
+            let js = match state.app.toggle_force_dark(url) {
+                Ok(enabled) => {
+                    let toast = if enabled { "Dark mode on for this site" } else { "Dark mode off for this site" };
+                    format!("{};if(window.__gb_showToast)__gb_showToast('{}')", force_dark_update(state, enabled), toast)
+                }
+                Err(_) => "if(window.__gb_showToast)__gb_showToast('Dark mode is not available on this page')".to_string(),
+            };
+            Some(UserEvent::EvalScript(js))
+        }
+
+        "zoom" => {
+            let step = msg.get("step").and_then(|v| v.as_str()).and_then(ZoomStep::parse)?;
+            let zoom = state.app.zoom_tab(tab_id, step).ok()?;
+            let toast = format!("if(window.__gb_showToast)__gb_showToast('Zoom {}%')", (zoom * 100.0).round());
+            Some(UserEvent::Zoom(zoom, toast))
+        }
+
+        "print" => {
+            // The webview is asked through the event bus, as for RPC
+            let pdf = msg.get("pdf").and_then(|v| v.as_bool()).unwrap_or(false);
+            let toast = match state.app.print_tab(tab_id, pdf) {
+                Ok(Some(_)) => "Saving page as PDF to Downloads".to_string(),
+                Ok(None) => return None,
+                Err(e) => format!("Cannot print: {}", e),
+            };
+            let toast = serde_json::to_string(&toast).unwrap_or_default();
+            Some(UserEvent::EvalScript(format!("if(window.__gb_showToast)__gb_showToast({})", toast)))
+        }
+
+        "pdf_load" => {
+            let src = msg.get("src").and_then(|v| v.as_str())
+                .filter(|s| s.starts_with("http://") || s.starts_with("https://"))?;
+            if state.pdfs.iter().any(|(url, _)| url == src) {
+                return Some(UserEvent::EvalScript("if(window.__gb_pdfReady)__gb_pdfReady()".to_string()));
+            }
+            Some(UserEvent::FetchPdf(src.to_string()))
+        }
+
+        "pdf_download" => {
+            let src = msg.get("src").and_then(|v| v.as_str())?;
+            let toast = match save_pdf(state, src) {
+                Ok(name) => format!("Saved {} to Downloads", name),
+                Err(e) => format!("Cannot save the PDF: {}", e),
+            };
+            let toast = serde_json::to_string(&toast).unwrap_or_default();
+            Some(UserEvent::EvalScript(format!("if(window.__gb_showToast)__gb_showToast({})", toast)))
+        }
+
+        "screenshot" => {
+            let full_page = msg.get("full_page").and_then(|v| v.as_bool()).unwrap_or(true);
+            if let Err(e) = state.app.capture_tab(tab_id, full_page) {
+                let toast = serde_json::to_string(&format!("Cannot capture the page: {}", e)).unwrap_or_default();
+                return Some(UserEvent::EvalScript(format!("if(window.__gb_showToast)__gb_showToast({})", toast)));
+            }
+            None
         }
 
         "new_tab" => {
             use crate::managers::tab_manager::TabManagerTrait;
             state.app.tab_manager.create_tab(Some("about:newtab"), true);
-            Some(UserEvent::LoadUrl("gb://localhost/newtab".to_string()))
+            Some(UserEvent::ShowActiveTab)
         }
 
         "close_tab" => {
             use crate::managers::tab_manager::TabManagerTrait;
             if let Some(id) = msg.get("id").and_then(|v| v.as_str()) {
                 let _ = state.app.tab_manager.close_tab(id);
+                state.find.remove(id);
             }
-            navigate_to_active(state)
+            Some(UserEvent::ShowActiveTab)
         }
 
         "close_active_tab" => {
@@ -152,8 +1173,9 @@ fn handle_ipc(state: &mut BrowserState, message: &str) -> Option<UserEvent> {
             if let Some(tab) = state.app.tab_manager.get_active_tab() {
                 let id = tab.id.clone();
                 let _ = state.app.tab_manager.close_tab(&id);
+                state.find.remove(&id);
             }
-            navigate_to_active(state)
+            Some(UserEvent::ShowActiveTab)
         }
 
         "switch_tab" => {
@@ -161,12 +1183,52 @@ fn handle_ipc(state: &mut BrowserState, message: &str) -> Option<UserEvent> {
             if let Some(id) = msg.get("id").and_then(|v| v.as_str()) {
                 let _ = state.app.tab_manager.switch_tab(id);
             }
-            navigate_to_active(state)
+            Some(UserEvent::ShowActiveTab)
+        }
+
+        "find_open" => {
+            let last = state.find.get(tab_id).cloned().unwrap_or_default();
+            let json = serde_json::to_string(&last).unwrap_or_default();
+            Some(UserEvent::EvalScript(format!("if(window.__gb_openFind)__gb_openFind({})", json)))
+        }
+
+        "find" => {
+            let query = msg.get("query").and_then(|v| v.as_str()).unwrap_or("").to_string();
+            let case_sensitive = msg.get("case_sensitive").and_then(|v| v.as_bool()).unwrap_or(false);
+            let forward = msg.get("forward").and_then(|v| v.as_bool()).unwrap_or(true);
+            let js = format!(
+                "if(window.__gb_findInPage)__gb_findInPage({},{},{})",
+                serde_json::Value::String(query.clone()),
+                case_sensitive,
+                forward
+            );
+            let find = state.find.entry(tab_id.to_string()).or_default();
+            find.query = query;
+            find.case_sensitive = case_sensitive;
+            Some(UserEvent::EvalScript(js))
+        }
+
+        "find_result" => {
+            // Counts reported by the page for the search it just ran
+            let find = state.find.entry(tab_id.to_string()).or_default();
+            find.matches = msg.get("matches").and_then(|v| v.as_u64()).unwrap_or(0);
+            find.active = msg.get("active").and_then(|v| v.as_u64()).unwrap_or(0);
+            None
+        }
+
+        "find_close" => {
+            if let Some(find) = state.find.get_mut(tab_id) {
+                find.matches = 0;
+                find.active = 0;
+            }
+            None
         }
 
         "navigate" => {
             let input = msg.get("url").and_then(|v| v.as_str()).unwrap_or("");
-            let url = normalize_url(input);
+            use crate::services::settings_engine::SettingsEngineTrait;
+            let url = state.app.resolve_gh_command(input)
+                .unwrap_or_else(|| url_for_input(input, &state.app.settings_engine.get_settings().search));
 
             use crate::managers::tab_manager::TabManagerTrait;
             if let Some(tab) = state.app.tab_manager.get_active_tab() {
@@ -174,6 +1236,10 @@ fn handle_ipc(state: &mut BrowserState, message: &str) -> Option<UserEvent> {
                 let title = extract_title(&url);
                 let _ = state.app.tab_manager.update_tab_url(&tid, &url);
                 let _ = state.app.tab_manager.update_tab_title(&tid, &title);
+                // Web pages report themselves once shown (redirected, maybe); internal ones do not
+                if url.starts_with("about:") {
+                    let _ = state.app.tab_manager.record_navigation(&tid, &url);
+                }
             }
 
             if !url.starts_with("about:") {
@@ -186,218 +1252,3180 @@ fn handle_ipc(state: &mut BrowserState, message: &str) -> Option<UserEvent> {
             url_to_event(&url)
         }
 
+        "go_back" | "go_forward" => {
+            use crate::managers::tab_manager::TabManagerTrait;
+            use crate::types::tab::HistoryStep;
+            let back = cmd == "go_back";
+            // Back from reader view is back to its page, which the tab never left
+            if back && internal_page_path(page_url) == Some("/reader") {
+                return Some(UserEvent::EvalScript("history.back()".to_string()));
+            }
+            let tabs = &mut state.app.tab_manager;
+            let step = if back { tabs.go_back(tab_id) } else { tabs.go_forward(tab_id) };
+            let (url, event) = match step.ok()?? {
+                HistoryStep::Traverse(url) => {
+                    let js = if back { "history.back()" } else { "history.forward()" };
+                    (url, Some(UserEvent::EvalScript(js.to_string())))
+                }
+                HistoryStep::Load(url) => {
+                    let event = url_to_event(&url);
+                    (url, event)
+                }
+            };
+            let _ = tabs.update_tab_url(tab_id, &url);
+            let _ = tabs.update_tab_title(tab_id, &extract_title(&url));
+            event
+        }
+
+        "toggle_fullscreen" => {
+            state.fullscreen = match state.fullscreen {
+                Some(_) => None,
+                None => Some(FullscreenMode::Window),
+            };
+            Some(UserEvent::Fullscreen(state.fullscreen))
+        }
+
+        "exit_fullscreen" => {
+            state.fullscreen.take()?;
+            Some(UserEvent::Fullscreen(None))
+        }
+
+        "element_fullscreen" => {
+            // An element leaving fullscreen leaves the window as F11 had it
+            let on = msg.get("on").and_then(|v| v.as_bool()).unwrap_or(false);
+            let next = match (on, state.fullscreen) {
+                (true, None) => Some(FullscreenMode::Element),
+                (false, Some(FullscreenMode::Element)) => None,
+                _ => return None,
+            };
+            state.fullscreen = next;
+            Some(UserEvent::Fullscreen(next))
+        }
+
         "open_settings" => {
             use crate::managers::tab_manager::TabManagerTrait;
             state.app.tab_manager.create_tab(Some("about:settings"), true);
-            Some(UserEvent::LoadUrl("gb://localhost/settings".to_string()))
+            Some(UserEvent::ShowActiveTab)
         }
 
-        "add_bookmark" => {
-            if let (Some(url), Some(title)) = (
-                msg.get("url").and_then(|v| v.as_str()),
-                msg.get("title").and_then(|v| v.as_str()),
-            ) {
-                let conn = state.app.db.connection();
-                let mut mgr = crate::managers::bookmark_manager::BookmarkManager::new(conn);
-                use crate::managers::bookmark_manager::BookmarkManagerTrait;
-                let _ = mgr.add_bookmark(url, title, None);
+        "open_downloads" => {
+            use crate::managers::tab_manager::TabManagerTrait;
+            let existing = state.app.tab_manager.get_all_tabs().into_iter()
+                .find(|t| t.url == "about:downloads")
+                .map(|t| t.id.clone());
+            match existing {
+                Some(id) => {
+                    let _ = state.app.tab_manager.switch_tab(&id);
+                }
+                None => {
+                    state.app.tab_manager.create_tab(Some("about:downloads"), true);
+                }
             }
-            Some(UserEvent::EvalScript("if(window.__gb_showToast)__gb_showToast('Bookmark added')".into()))
+            Some(UserEvent::ShowActiveTab)
         }
 
-        "get_settings" => {
-            use crate::services::settings_engine::SettingsEngineTrait;
-            let json = serde_json::to_string(state.app.settings_engine.get_settings()).unwrap_or_default();
-            Some(UserEvent::EvalScript(format!("if(typeof applySettingsData==='function')applySettingsData({})", json)))
+        "open_privacy" => {
+            use crate::managers::tab_manager::TabManagerTrait;
+            let existing = state.app.tab_manager.get_all_tabs().into_iter()
+                .find(|t| t.url == "about:privacy")
+                .map(|t| t.id.clone());
+            match existing {
+                Some(id) => {
+                    let _ = state.app.tab_manager.switch_tab(&id);
+                }
+                None => {
+                    state.app.tab_manager.create_tab(Some("about:privacy"), true);
+                }
+            }
+            Some(UserEvent::ShowActiveTab)
         }
 
-        "set_setting" => {
-            if let (Some(key), Some(value)) = (msg.get("key").and_then(|v| v.as_str()), msg.get("value")) {
-                use crate::services::settings_engine::SettingsEngineTrait;
-                let _ = state.app.settings_engine.set_value(key, value.clone());
+        "favicon" => {
+            // A web page's icon links, sent once it has loaded
+            use crate::managers::favicon_manager::{FaviconManager, FaviconManagerTrait};
+            use crate::managers::site_settings_manager::SiteSettingsManager;
+            use crate::managers::tab_manager::TabManagerTrait;
+            use crate::types::favicon::IconLink;
+            let site = SiteSettingsManager::site_for_url(page_url)?;
+            let links: Vec<IconLink> = msg.get("icons")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default();
+            let cached = FaviconManager::new(state.app.db.connection()).get_favicon(&site).ok().flatten();
+            let icon = cached.as_ref().map(|icon| icon.data_url.as_str());
+            let _ = state.app.tab_manager.update_tab_favicon(tab_id, icon);
+            // Stale icons are kept rather than refetched while saving power
+            match cached {
+                Some(icon) if !FaviconManager::is_stale(icon.fetched_at) || state.app.saving_power() => {
+                    Some(UserEvent::EvalScript(build_tabs_update(state)))
+                }
+                _ => Some(UserEvent::FetchFavicon(site, FaviconManager::icon_candidates(&links, page_url))),
             }
-            None
         }
 
-        "reset_settings" => {
-            use crate::services::settings_engine::SettingsEngineTrait;
-            let _ = state.app.settings_engine.reset();
-            let json = serde_json::to_string(state.app.settings_engine.get_settings()).unwrap_or_default();
-            Some(UserEvent::EvalScript(format!("if(typeof applySettingsData==='function')applySettingsData({})", json)))
+        "check_request" => {
+            // A request a page script is about to make, held until answered
+            use crate::types::privacy::RequestDecision;
+            let id = msg.get("id").and_then(|v| v.as_u64())?;
+            let url = msg.get("url").and_then(|v| v.as_str())?;
+            let resource_type = msg.get("type").and_then(|v| v.as_str()).unwrap_or("other");
+            let decision = state.app.filter_request(Some(tab_id), url, resource_type, Some(page_url));
+            let mut js = format!(
+                "if(window.__gb_requestVerdict)__gb_requestVerdict({},{})",
+                id,
+                serde_json::json!(decision)
+            );
+            if decision == RequestDecision::Block {
+                js.push(';');
+                js.push_str(&blocked_count_update(state, tab_id));
+            }
+            Some(UserEvent::EvalScript(js))
         }
 
-        "url_changed" => {
-            // JS detected a URL change (SPA navigation, redirect, etc.)
-            if let Some(url) = msg.get("url").and_then(|v| v.as_str()) {
-                let title = msg.get("title").and_then(|v| v.as_str()).unwrap_or("");
-                use crate::managers::tab_manager::TabManagerTrait;
-                if let Some(tab) = state.app.tab_manager.get_active_tab() {
-                    let tid = tab.id.clone();
-                    let _ = state.app.tab_manager.update_tab_url(&tid, url);
-                    if !title.is_empty() {
-                        let _ = state.app.tab_manager.update_tab_title(&tid, title);
+        "download_action" => {
+            use crate::managers::download_manager::DownloadManagerTrait;
+            let id = msg.get("id").and_then(|v| v.as_str())?;
+            let mgr = &mut state.app.download_manager;
+            // The page redraws from the change event, so only failures answer here
+            let result = match msg.get("action").and_then(|v| v.as_str())? {
+                "pause" => mgr.pause_download(id),
+                "resume" => mgr.resume_download(id),
+                "cancel" => mgr.cancel_download(id),
+                "retry" => mgr.retry_download(id),
+                "open_folder" => {
+                    let path = mgr.get_download(id).map(|d| d.filepath.clone())?;
+                    if !crate::platform::reveal_in_folder(std::path::Path::new(&path)) {
+                        eprintln!("[DOWNLOAD] cannot show {}", path);
                     }
+                    Ok(())
                 }
+                _ => return None,
+            };
+            let e = result.err()?;
+            let toast = serde_json::to_string(&e.to_string()).unwrap_or_default();
+            Some(UserEvent::EvalScript(format!("if(window.__gb_showToast)__gb_showToast({})", toast)))
+        }
+
+        "open_link_in_new_tab" => {
+            use crate::managers::tab_manager::TabManagerTrait;
+            let url = msg.get("url").and_then(|v| v.as_str())?;
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return None;
             }
-            Some(UserEvent::EvalScript(build_tabs_update(state)))
+            // Opened behind the current tab, which keeps showing
+            let id = state.app.tab_manager.create_tab(Some(url), false);
+            let _ = state.app.tab_manager.update_tab_title(&id, &extract_title(url));
+            let toast = "if(window.__gb_showToast)__gb_showToast('Opened in a new tab')";
+            Some(UserEvent::EvalScript(format!("{};{}", build_tabs_update(state), toast)))
         }
 
-        _ => None,
-    }
-}
+        "search_selection" => {
+            use crate::managers::tab_manager::TabManagerTrait;
+            use crate::services::search_engine_registry::{SearchEngineRegistry, SearchEngineRegistryTrait};
+            use crate::services::settings_engine::SettingsEngineTrait;
+            let text = msg.get("text").and_then(|v| v.as_str()).map(str::trim).filter(|t| !t.is_empty())?;
+            let registry = SearchEngineRegistry::new(state.app.settings_engine.get_settings().search.clone());
+            let url = registry.search_url(text)?;
+            let id = state.app.tab_manager.create_tab(Some(&url), true);
+            let _ = state.app.tab_manager.update_tab_title(&id, &extract_title(&url));
+            Some(UserEvent::ShowActiveTab)
+        }
 
-fn url_to_event(url: &str) -> Option<UserEvent> {
-    if url.starts_with("http://") || url.starts_with("https://") {
-        Some(UserEvent::LoadUrl(url.to_string()))
-    } else if url == "about:settings" {
-        Some(UserEvent::LoadUrl("gb://localhost/settings".to_string()))
-    } else {
-        Some(UserEvent::LoadUrl("gb://localhost/newtab".to_string()))
-    }
-}
+        "save_image" => {
+            let url = msg.get("url").and_then(|v| v.as_str())?;
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return None;
+            }
+            let ask = msg.get("ask").and_then(|v| v.as_bool()).unwrap_or(false);
+            Some(UserEvent::SaveImage(url.to_string(), ask))
+        }
 
-fn navigate_to_active(state: &mut BrowserState) -> Option<UserEvent> {
-    use crate::managers::tab_manager::TabManagerTrait;
-    let url = state.app.tab_manager.get_active_tab()
-        .map(|t| t.url.clone()).unwrap_or_else(|| "about:newtab".into());
-    url_to_event(&url)
-}
+        "choose_file" => {
+            // A page may only ask for the paths it has a use for
+            let purpose = msg.get("purpose").and_then(|v| v.as_str())?;
+            let page = purpose.split('_').next().map(|page| format!("/{}", page));
+            if internal_page_path(page_url) != page.as_deref() {
+                eprintln!("[FILES] ignored {} from {}", purpose, page_url);
+                return None;
+            }
+            Some(UserEvent::ChooseFile(purpose.to_string()))
+        }
 
-fn build_tabs_update(state: &BrowserState) -> String {
-    use crate::managers::tab_manager::TabManagerTrait;
-    let tabs: Vec<serde_json::Value> = state.app.tab_manager.get_all_tabs().iter().map(|t| {
-        serde_json::json!({"id": t.id, "title": t.title, "url": t.url, "pinned": t.pinned})
+        "ask_ai" => {
+            let text = msg.get("text").and_then(|v| v.as_str()).map(str::trim).filter(|t| !t.is_empty())?;
+            let context = crate::types::ai::AIContext {
+                selected_text: Some(text.to_string()),
+                page_content: None,
+                page_url: msg.get("url").and_then(|v| v.as_str()).map(String::from),
+            };
+            // The assistant panel lives in an RPC client; without one there is no one to ask
+            let toast = if state.app.ask_ai(&context) {
+                "Sent to the AI assistant"
+            } else {
+                "Open the AI assistant to ask about a selection"
+            };
+            Some(UserEvent::EvalScript(format!("if(window.__gb_showToast)__gb_showToast('{}')", toast)))
+        }
+
+        cmd if cmd.starts_with("autofill_") => {
+            // Logins only ever go to pages of the origin they were saved for
+            let origin = crate::services::password_manager::PasswordManager::origin_of(page_url)?;
+            if internal_page_path(page_url).is_some() {
+                return None;
+            }
+            handle_autofill_ipc(state, cmd, &msg, &origin, tab_id, page_url)
+        }
+
+        cmd if cmd.starts_with("passwords_") => {
+            // Every page runs the toolbar's IPC bridge, so only the vault page may ask
+            if internal_page_path(page_url) != Some("/passwords") {
+                eprintln!("[PASSWORDS] ignored {} from {}", cmd, page_url);
+                return None;
+            }
+            let reply = handle_passwords_ipc(&mut state.app, cmd, &msg)
+                .unwrap_or_else(|e| serde_json::json!({"type": "error", "message": e}));
+            Some(UserEvent::EvalScript(passwords_reply_script(&reply)))
+        }
+
+        cmd if cmd.starts_with("extensions_") => {
+            // Installing an extension from any page but this one would be a way in
+            if internal_page_path(page_url) != Some("/extensions") {
+                eprintln!("[EXT] ignored {} from {}", cmd, page_url);
+                return None;
+            }
+            let reply = handle_extensions_ipc(&mut state.app, cmd, &msg)
+                .unwrap_or_else(|e| serde_json::json!({"type": "error", "message": e}));
+            Some(UserEvent::EvalScript(format!("if(window.__gb_extensions)__gb_extensions({})", reply)))
+        }
+
+        cmd if cmd.starts_with("privacy_") => {
+            if internal_page_path(page_url) != Some("/privacy") {
+                eprintln!("[PRIVACY] ignored {} from {}", cmd, page_url);
+                return None;
+            }
+            let js = match handle_privacy_ipc(&mut state.app, cmd, &msg) {
+                Ok(()) => format!(
+                    "if(window.__gb_privacy)__gb_privacy({})",
+                    crate::rpc_handler::privacy_dashboard_json(&state.app)
+                ),
+                Err(e) => format!("if(window.__gb_showToast)__gb_showToast({})", serde_json::json!(e)),
+            };
+            Some(UserEvent::EvalScript(js))
+        }
+
+        "add_bookmark" => {
+            if let (Some(url), Some(title)) = (
+                msg.get("url").and_then(|v| v.as_str()),
+                msg.get("title").and_then(|v| v.as_str()),
+            ) {
+                let conn = state.app.db.connection();
+                let mut mgr = crate::managers::bookmark_manager::BookmarkManager::new(conn);
+                use crate::managers::bookmark_manager::BookmarkManagerTrait;
+                let _ = mgr.add_bookmark(url, title, None);
+            }
+            Some(UserEvent::EvalScript("if(window.__gb_showToast)__gb_showToast('Bookmark added')".into()))
+        }
+
+        "reading_list_save" => {
+            if let (Some(url), Some(html)) = (
+                msg.get("url").and_then(|v| v.as_str()),
+                msg.get("html").and_then(|v| v.as_str()),
+            ) {
+                let images: std::collections::HashMap<String, String> = msg.get("images")
+                    .and_then(|v| v.as_object())
+                    .map(|m| m.iter().filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string()))).collect())
+                    .unwrap_or_default();
+                // Following pages are fetched off the UI thread
+                return Some(UserEvent::SaveArticle(url.to_string(), html.to_string(), images));
+            }
+            None
+        }
+
+        "reader_open" => {
+            use crate::services::reader_mode::ReaderModeTrait;
+            let url = msg.get("url").and_then(|v| v.as_str())?;
+            let html = msg.get("html").and_then(|v| v.as_str())?;
+            match state.app.reader_mode.extract_content(html, url) {
+                Ok(content) => {
+                    state.readers.insert(tab_id.to_string(), (url.to_string(), content));
+                    Some(UserEvent::LoadUrl(READER_PAGE_URL.to_string()))
+                }
+                Err(_) => Some(UserEvent::EvalScript(
+                    "if(window.__gb_showToast)__gb_showToast('This page has no readable article')".into(),
+                )),
+            }
+        }
+
+        // Reader view sits on top of its page in the webview's history
+        "reader_exit" => Some(UserEvent::EvalScript("history.back()".to_string())),
+
+        "reading_list_mark_read" => {
+            if let Some(id) = msg.get("id").and_then(|v| v.as_str()) {
+                use crate::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+                let read = msg.get("read").and_then(|v| v.as_bool()).unwrap_or(true);
+                let mut mgr = ReadingListManager::new(state.app.db.connection());
+                let _ = mgr.mark_read(id, read);
+            }
+            Some(UserEvent::LoadUrl("gb://localhost/reading-list".to_string()))
+        }
+
+        "reading_list_delete" => {
+            if let Some(id) = msg.get("id").and_then(|v| v.as_str()) {
+                use crate::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+                let mut mgr = ReadingListManager::new(state.app.db.connection());
+                let _ = mgr.remove_item(id);
+            }
+            Some(UserEvent::LoadUrl("gb://localhost/reading-list".to_string()))
+        }
+
+        "annotation_add" => {
+            if let (Some(url), Some(text)) = (
+                msg.get("url").and_then(|v| v.as_str()),
+                msg.get("text").and_then(|v| v.as_str()),
+            ) {
+                use crate::managers::annotation_manager::{AnnotationManager, AnnotationManagerTrait};
+                let prefix = msg.get("prefix").and_then(|v| v.as_str()).unwrap_or("");
+                let suffix = msg.get("suffix").and_then(|v| v.as_str()).unwrap_or("");
+                let note = msg.get("note").and_then(|v| v.as_str()).filter(|n| !n.is_empty());
+                let mut mgr = AnnotationManager::new(state.app.db.connection());
+                let _ = mgr.add_annotation(url, text, prefix, suffix, note, None);
+            }
+            Some(UserEvent::EvalScript("location.reload()".into()))
+        }
+
+        "annotation_delete" => {
+            if let Some(id) = msg.get("id").and_then(|v| v.as_str()) {
+                use crate::managers::annotation_manager::{AnnotationManager, AnnotationManagerTrait};
+                let mut mgr = AnnotationManager::new(state.app.db.connection());
+                let _ = mgr.remove_annotation(id);
+            }
+            Some(UserEvent::EvalScript("location.reload()".into()))
+        }
+
+        "get_settings" => {
+            use crate::services::settings_engine::SettingsEngineTrait;
+            let json = serde_json::to_string(state.app.settings_engine.get_settings()).unwrap_or_default();
+            Some(UserEvent::EvalScript(format!("if(typeof applySettingsData==='function')applySettingsData({})", json)))
+        }
+
+        "set_setting" => {
+            if let (Some(key), Some(value)) = (msg.get("key").and_then(|v| v.as_str()), msg.get("value")) {
+                use crate::services::settings_engine::SettingsEngineTrait;
+                let _ = state.app.settings_engine.set_value(key, value.clone());
+                let changes = state.app.process_settings_changes();
+                if !changes.is_empty() {
+                    let json = serde_json::to_string(&changes).unwrap_or_default();
+                    return Some(UserEvent::EvalScript(format!("if(typeof onSettingsChanged==='function')onSettingsChanged({})", json)));
+                }
+            }
+            None
+        }
+
+        "settings_export" | "settings_import" => {
+            use crate::services::settings_engine::SettingsEngineTrait;
+            if internal_page_path(page_url) != Some("/settings") {
+                eprintln!("[SETTINGS] ignored {} from {}", cmd, page_url);
+                return None;
+            }
+            let path = msg.get("path").and_then(|v| v.as_str())?;
+            let mut js = String::new();
+            let toast = if cmd == "settings_export" {
+                match state.app.settings_engine.export_to(path) {
+                    Ok(_) => format!("Settings exported to {}", path),
+                    Err(e) => e.to_string(),
+                }
+            } else {
+                match state.app.settings_engine.import_from(path) {
+                    Ok(changes) => {
+                        state.app.process_settings_changes();
+                        let json = serde_json::to_string(state.app.settings_engine.get_settings()).unwrap_or_default();
+                        js.push_str(&format!("if(typeof applySettingsData==='function')applySettingsData({});", json));
+                        format!("Imported {} setting{}", changes.len(), if changes.len() == 1 { "" } else { "s" })
+                    }
+                    Err(e) => e.to_string(),
+                }
+            };
+            js.push_str(&format!("if(window.__gb_showToast)__gb_showToast({})", serde_json::json!(toast)));
+            Some(UserEvent::EvalScript(js))
+        }
+
+        "reset_settings" => {
+            use crate::services::settings_engine::SettingsEngineTrait;
+            let _ = state.app.settings_engine.reset();
+            state.app.process_settings_changes();
+            let json = serde_json::to_string(state.app.settings_engine.get_settings()).unwrap_or_default();
+            Some(UserEvent::EvalScript(format!("if(typeof applySettingsData==='function')applySettingsData({})", json)))
+        }
+
+        "reload_crashed" => {
+            use crate::managers::tab_manager::TabManagerTrait;
+            if let Some(tab) = state.app.tab_manager.get_active_tab() {
+                let id = tab.id.clone();
+                let _ = state.app.tab_manager.reload_crashed_tab(&id);
+            }
+            navigate_to_active(state)
+        }
+
+        "retry_load" => {
+            // The tab kept the URL of the page that failed
+            use crate::managers::tab_manager::TabManagerTrait;
+            let url = state.app.tab_manager.get_tab(tab_id).map(|t| t.url.clone())?;
+            url_to_event(&url)
+        }
+
+        "crash_restore" => {
+            let restore = msg.get("restore").and_then(|v| v.as_bool()).unwrap_or(false);
+            match state.app.resolve_crash_restore(restore) {
+                // The restored tabs replace the one showing the prompt, and get webviews of their own
+                Ok(Some(_)) => Some(UserEvent::ShowActiveTab),
+                Ok(None) => navigate_to_active(state),
+                Err(e) => {
+                    eprintln!("[CRASH] {}", e);
+                    navigate_to_active(state)
+                }
+            }
+        }
+
+        "url_changed" => {
+            // JS detected a URL change (SPA navigation, redirect, etc.)
+            if let Some(url) = msg.get("url").and_then(|v| v.as_str()) {
+                let title = msg.get("title").and_then(|v| v.as_str()).unwrap_or("");
+                use crate::managers::tab_manager::TabManagerTrait;
+                let _ = state.app.tab_manager.record_navigation(tab_id, url);
+                let _ = state.app.tab_manager.update_tab_url(tab_id, url);
+                if !title.is_empty() {
+                    let _ = state.app.tab_manager.update_tab_title(tab_id, title);
+                }
+            }
+            Some(UserEvent::EvalScript(build_tabs_update(state)))
+        }
+
+        _ => None,
+    }
+}
+
+fn url_to_event(url: &str) -> Option<UserEvent> {
+    Some(UserEvent::LoadUrl(page_url_for(url)))
+}
+
+/// The URL a webview loads to show tab URL `url`. An internal page keeps
+/// its `#section`.
+fn page_url_for(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return url.to_string();
+    }
+    let (url, section) = match url.split_once('#') {
+        Some((url, section)) => (url, format!("#{}", section)),
+        None => (url, String::new()),
+    };
+    let page = match url {
+        "about:settings" => "settings",
+        "about:reading-list" => "reading-list",
+        "about:downloads" => "downloads",
+        "about:passwords" => "passwords",
+        "about:extensions" => "extensions",
+        "about:privacy" => "privacy",
+        _ => "newtab",
+    };
+    format!("gb://localhost/{}{}", page, section)
+}
+
+/// What a tab's webview shows: its page, or the crashed page.
+fn tab_page_url(tab: &crate::types::tab::Tab) -> String {
+    if tab.crashed {
+        "gb://localhost/crashed".to_string()
+    } else {
+        page_url_for(&tab.url)
+    }
+}
+
+/// The path of an internal `gb://localhost` page, as WebView2 reports it too.
+fn internal_page_path(url: &str) -> Option<&str> {
+    let rest = ["gb://localhost", "http://gb.localhost", "https://gb.localhost"]
+        .iter()
+        .find_map(|prefix| url.strip_prefix(prefix))?;
+    let path = rest.split(['?', '#']).next()?;
+    Some(if path.is_empty() { "/" } else { path })
+}
+
+/// Answers an `autofill_*` message from a page of `origin` in tab `tab_id`.
+/// Nothing is offered while the vault is locked, and no password ever goes
+/// to the page from here: logins are filled only from the autofill prompt
+/// and saved only from the save prompt, neither of which the page can reach.
+fn handle_autofill_ipc(
+    state: &mut BrowserState,
+    cmd: &str,
+    msg: &serde_json::Value,
+    origin: &str,
+    tab_id: &str,
+    page_url: &str,
+) -> Option<UserEvent> {
+    use crate::services::password_manager::PasswordManagerTrait;
+    let arg = |name: &str| msg.get(name).and_then(|v| v.as_str()).unwrap_or("");
+    let mgr = &state.app.password_manager;
+    if !mgr.is_unlocked() {
+        return None;
+    }
+    match cmd {
+        // The page has a login form: offer the logins saved for it
+        "autofill_query" => {
+            let logins: Vec<(String, String)> = mgr
+                .credentials_for_origin(origin)
+                .ok()?
+                .into_iter()
+                .map(|c| (c.id, c.username))
+                .collect();
+            if logins.is_empty() {
+                return None;
+            }
+            Some(UserEvent::AutofillOffer(origin.to_string(), logins))
+        }
+        // A login form was submitted
+        "autofill_capture" => {
+            let (username, password) = (arg("username").trim(), arg("password"));
+            if password.is_empty() || mgr.is_login_saved(origin, username, password) {
+                return None;
+            }
+            let login = PendingLogin {
+                origin: origin.to_string(),
+                username: username.to_string(),
+                password: password.to_string(),
+            };
+            state.pending_logins.insert(tab_id.to_string(), login);
+            save_login_offer(state, tab_id, page_url)
+        }
+        _ => None,
+    }
+}
+
+/// Script filling `username` and `password` into the login form of the
+/// page. Self-contained, so it calls nothing the page could have defined.
+fn autofill_script(username: &str, password: &str) -> String {
+    format!(
+        "(function(u,p){{\
+         var a=document.activeElement,root=a&&a.form||document,pass=a&&a.type==='password'?a:root.querySelector('input[type=password]');\
+         if(!pass)return;\
+         var inputs=Array.prototype.slice.call((pass.form||document).querySelectorAll('input'));\
+         var before=inputs.slice(0,inputs.indexOf(pass)).filter(function(i){{return /^(text|email|tel)$/.test(i.type)&&i.offsetParent!==null}});\
+         var set=Object.getOwnPropertyDescriptor(HTMLInputElement.prototype,'value').set;\
+         function fill(input,value){{set.call(input,value);\
+         input.dispatchEvent(new Event('input',{{bubbles:true}}));input.dispatchEvent(new Event('change',{{bubbles:true}}))}}\
+         if(before.length)fill(before[before.length-1],u);fill(pass,p)}})({},{})",
+        serde_json::Value::from(username),
+        serde_json::Value::from(password)
+    )
+}
+
+/// The login submitted in tab `tab_id`, if it came from the origin of
+/// `page_url`; one from elsewhere is dropped.
+fn kept_login<'a>(state: &'a mut BrowserState, tab_id: &str, page_url: &str) -> Option<&'a PendingLogin> {
+    let origin = crate::services::password_manager::PasswordManager::origin_of(page_url);
+    if origin.as_deref() != Some(state.pending_logins.get(tab_id)?.origin.as_str()) {
+        state.pending_logins.remove(tab_id);
+        return None;
+    }
+    state.pending_logins.get(tab_id)
+}
+
+/// Asks whether to save the login submitted in tab `tab_id`, if it came
+/// from the origin of `page_url`.
+fn save_login_offer(state: &mut BrowserState, tab_id: &str, page_url: &str) -> Option<UserEvent> {
+    let (origin, username) = kept_login(state, tab_id, page_url).map(|l| (l.origin.clone(), l.username.clone()))?;
+    let update = state
+        .app
+        .password_manager
+        .credentials_for_origin(&origin)
+        .is_ok_and(|saved| saved.iter().any(|c| c.username == username));
+    Some(UserEvent::SaveLoginOffer(origin, username, update))
+}
+
+/// Answers a `passwords_*` message from the vault page with the reply for
+/// its `__gb_passwords`.
+fn handle_passwords_ipc(app: &mut App, cmd: &str, msg: &serde_json::Value) -> Result<serde_json::Value, String> {
+    use crate::services::password_manager::PasswordManagerTrait;
+    let arg = |name: &str| msg.get(name).and_then(|v| v.as_str()).unwrap_or("");
+    let mgr = &mut app.password_manager;
+
+    let notice = match cmd {
+        "passwords_state" => None,
+        "passwords_unlock" => {
+            if !app.unlock_vault(arg("master_password")).map_err(|e| e.to_string())? {
+                return Err("Wrong master password".to_string());
+            }
+            None
+        }
+        "passwords_lock" => {
+            mgr.lock();
+            None
+        }
+        "passwords_reveal" => {
+            let id = arg("id");
+            let creds = mgr.list_all_credentials().map_err(|e| e.to_string())?;
+            let entry = creds.iter().find(|c| c.id == id).ok_or("credential not found")?;
+            let password = mgr.decrypt_password(entry).map_err(|e| e.to_string())?;
+            return Ok(serde_json::json!({"type": "revealed", "id": id, "password": password}));
+        }
+        "passwords_copy" => {
+            if !app.copy_password(arg("id")).map_err(|e| e.to_string())? {
+                return Err("Could not reach the clipboard".to_string());
+            }
+            let seconds = {
+                use crate::services::settings_engine::SettingsEngineTrait;
+                app.settings_engine.get_settings().privacy.clipboard_clear_seconds
+            };
+            Some(match seconds {
+                0 => "Password copied".to_string(),
+                s => format!("Password copied; the clipboard is cleared in {} seconds", s),
+            })
+        }
+        "passwords_save" => {
+            if arg("url").is_empty() || arg("username").is_empty() || arg("password").is_empty() {
+                return Err("URL, username and password are all needed".to_string());
+            }
+            mgr.save_credential(arg("url"), arg("username"), arg("password")).map_err(|e| e.to_string())?;
+            Some("Login saved".to_string())
+        }
+        "passwords_delete" => {
+            mgr.delete_credential(arg("id")).map_err(|e| e.to_string())?;
+            None
+        }
+        "passwords_generate" => {
+            let flag = |name: &str| msg.get(name).and_then(|v| v.as_bool()).unwrap_or(true);
+            let options = crate::types::credential::PasswordGenOptions {
+                length: msg.get("length").and_then(|v| v.as_u64()).unwrap_or(20).clamp(4, 128) as usize,
+                uppercase: flag("uppercase"),
+                lowercase: flag("lowercase"),
+                numbers: flag("numbers"),
+                symbols: flag("symbols"),
+            };
+            if !(options.uppercase || options.lowercase || options.numbers || options.symbols) {
+                return Err("Pick at least one kind of character".to_string());
+            }
+            return Ok(serde_json::json!({"type": "generated", "password": mgr.generate_password(&options)}));
+        }
+        "passwords_export" => {
+            if arg("path").is_empty() || arg("master_password").is_empty() {
+                return Err("A file path and a file password are needed".to_string());
+            }
+            mgr.export_encrypted(arg("master_password"), arg("path")).map_err(|e| e.to_string())?;
+            Some(format!("Exported to {}", arg("path")))
+        }
+        "passwords_import" => {
+            let count = mgr.import_encrypted(arg("master_password"), arg("path")).map_err(|e| e.to_string())?;
+            Some(format!("Imported {} login{}", count, if count == 1 { "" } else { "s" }))
+        }
+        _ => return Err(format!("unknown command: {}", cmd)),
+    };
+    Ok(passwords_state(app, notice))
+}
+
+/// Answers an `extensions_*` message from the extensions page with the
+/// reply for its `__gb_extensions`.
+fn handle_extensions_ipc(app: &mut App, cmd: &str, msg: &serde_json::Value) -> Result<serde_json::Value, String> {
+    use crate::services::extension_framework::ExtensionFrameworkTrait;
+    let arg = |name: &str| msg.get(name).and_then(|v| v.as_str()).unwrap_or("");
+    let ext = &mut app.extension_framework;
+
+    let notice = match cmd {
+        "extensions_list" => None,
+        "extensions_toggle" => {
+            if msg.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true) {
+                ext.enable(arg("id")).map_err(|e| e.to_string())?;
+            } else {
+                ext.disable(arg("id")).map_err(|e| e.to_string())?;
+            }
+            app.refresh_extension_rules();
+            None
+        }
+        "extensions_uninstall" => {
+            ext.uninstall(arg("id")).map_err(|e| e.to_string())?;
+            app.refresh_extension_rules();
+            Some("Extension removed".to_string())
+        }
+        "extensions_install" => {
+            let pending = ext.request_install(arg("path")).map_err(|e| e.to_string())?;
+            if !pending.permissions.is_empty() {
+                // Shown to the user, who answers with extensions_confirm
+                return Ok(serde_json::json!({"type": "pending", "install": pending}));
+            }
+            ext.confirm_install(&pending.install_id, &[]).map_err(|e| e.to_string())?;
+            app.refresh_extension_rules();
+            Some(format!("Installed {}", pending.name))
+        }
+        "extensions_confirm" => {
+            let granted: Vec<crate::types::extension::ExtensionPermission> =
+                serde_json::from_value(msg.get("permissions").cloned().unwrap_or_default()).map_err(|e| e.to_string())?;
+            let id = ext.confirm_install(arg("install_id"), &granted).map_err(|e| e.to_string())?;
+            let name = ext.get_extension(&id).map(|e| e.name.clone()).unwrap_or(id);
+            app.refresh_extension_rules();
+            Some(format!("Installed {}", name))
+        }
+        "extensions_cancel" => {
+            ext.cancel_install(arg("install_id")).map_err(|e| e.to_string())?;
+            None
+        }
+        _ => return Err(format!("unknown command: {}", cmd)),
+    };
+    let extensions: Vec<serde_json::Value> = app
+        .extension_framework
+        .list_extensions()
+        .into_iter()
+        .map(|e| crate::rpc_handler::extension_json(app, e))
+        .collect();
+    Ok(serde_json::json!({"type": "list", "extensions": extensions, "notice": notice}))
+}
+
+/// Applies a `privacy_*` message from the privacy dashboard; the page is
+/// then sent the dashboard afresh.
+fn handle_privacy_ipc(app: &mut App, cmd: &str, msg: &serde_json::Value) -> Result<(), String> {
+    use crate::services::settings_engine::SettingsEngineTrait;
+    match cmd {
+        "privacy_state" => Ok(()),
+        "privacy_set" => {
+            // The dashboard's switches, written through the settings like the settings page
+            let key = msg.get("key").and_then(|v| v.as_str()).unwrap_or("");
+            if !matches!(
+                key,
+                "tracker_blocking" | "ad_blocking" | "https_enforcement" | "dns_over_https" | "anti_fingerprinting"
+            ) {
+                return Err(format!("unknown privacy setting: {}", key));
+            }
+            let value = msg.get("value").and_then(|v| v.as_bool()).ok_or("missing value")?;
+            app.settings_engine
+                .set_value(&format!("privacy.{}", key), serde_json::Value::Bool(value))
+                .map_err(|e| e.to_string())?;
+            app.process_settings_changes();
+            Ok(())
+        }
+        "privacy_site" => {
+            let site = msg.get("site").and_then(|v| v.as_str()).ok_or("missing site")?;
+            let shields = msg.get("shields").and_then(|v| v.as_bool()).ok_or("missing shields")?;
+            app.set_site_shields(&format!("https://{}/", site), shields).map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        _ => Err(format!("unknown command: {}", cmd)),
+    }
+}
+
+/// The vault's lock state and saved logins, without their passwords.
+fn passwords_state(app: &App, notice: Option<String>) -> serde_json::Value {
+    use crate::services::password_manager::PasswordManagerTrait;
+    let credentials: Vec<serde_json::Value> = app
+        .password_manager
+        .list_all_credentials()
+        .map(|creds| creds.iter().map(crate::rpc_handler::credential_json).collect())
+        .unwrap_or_default();
+    serde_json::json!({
+        "type": "state",
+        "unlocked": app.password_manager.is_unlocked(),
+        "credentials": credentials,
+        "notice": notice,
+    })
+}
+
+fn passwords_reply_script(reply: &serde_json::Value) -> String {
+    format!("if(window.__gb_passwords)__gb_passwords({})", reply)
+}
+
+/// Saves the image at `url` to `path`, or into the downloads folder, as a
+/// download, so it shows on the downloads page. The fetch runs on its own
+/// thread.
+fn save_image(state: Arc<Mutex<BrowserState>>, url: String, path: Option<std::path::PathBuf>) -> Result<(), String> {
+    use crate::managers::download_manager::{DownloadManager, DownloadManagerTrait};
+    let path = path.unwrap_or_else(|| DownloadManager::free_path(&crate::platform::get_downloads_dir(), &image_file_name(&url)));
+    let dir = path.parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+    let id = state.lock().unwrap().app.download_manager
+        .start_download(&url, &path.to_string_lossy())
+        .map_err(|e| e.to_string())?;
+    std::thread::spawn(move || {
+        let saved = fetch_resource(&url, MAX_SAVED_IMAGE_BYTES, 60).and_then(|(bytes, _)| {
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            std::fs::write(&path, &bytes).map_err(|e| e.to_string())?;
+            Ok(bytes.len() as u64)
+        });
+        let mut s = state.lock().unwrap();
+        let mgr = &mut s.app.download_manager;
+        let recorded = match saved {
+            Ok(size) => mgr.update_progress(&id, size, Some(size)),
+            Err(e) => {
+                eprintln!("[DOWNLOAD] {}: {}", url, e);
+                mgr.cancel_download(&id)
+            }
+        };
+        if let Err(e) = recorded {
+            eprintln!("[DOWNLOAD] {}: {}", url, e);
+        }
+    });
+    Ok(())
+}
+
+/// Blocking fetch of `url`, refused past `max_bytes`. Returns the body and
+/// its content type.
+fn fetch_resource(url: &str, max_bytes: usize, timeout_secs: u64) -> Result<(Vec<u8>, Option<String>), String> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+    rt.block_on(async {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(timeout_secs))
+            .user_agent(concat!("GitBrowser/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status().as_u16()));
+        }
+        if resp.content_length().is_some_and(|len| len as usize > max_bytes) {
+            return Err("file too large".to_string());
+        }
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+        if bytes.len() > max_bytes {
+            return Err("file too large".to_string());
+        }
+        Ok((bytes.to_vec(), content_type))
+    })
+}
+
+/// Fetches the first of `candidates` that is an image, off the UI thread,
+/// then caches it as `site`'s icon and shows it on tab `tab_id` if the tab
+/// is still on that site.
+fn fetch_favicon(
+    state: Arc<Mutex<BrowserState>>,
+    proxy: EventLoopProxy<UserEvent>,
+    tab_id: String,
+    site: String,
+    candidates: Vec<String>,
+) {
+    use crate::managers::favicon_manager::{FaviconManager, FaviconManagerTrait};
+    use crate::managers::site_settings_manager::SiteSettingsManager;
+    use crate::managers::tab_manager::TabManagerTrait;
+    std::thread::spawn(move || {
+        let found = candidates.iter().take(MAX_FAVICON_TRIES).find_map(|url| {
+            let (bytes, content_type) = fetch_resource(url, MAX_FAVICON_BYTES, 10).ok()?;
+            FaviconManager::data_url(&bytes, content_type.as_deref()).map(|data_url| (url, data_url))
+        });
+        let Some((icon_url, data_url)) = found else {
+            return;
+        };
+        let mut s = state.lock().unwrap();
+        if let Err(e) = FaviconManager::new(s.app.db.connection()).save_favicon(&site, icon_url, &data_url) {
+            eprintln!("[FAVICON] {}: {}", site, e);
+            return;
+        }
+        let tabs = &mut s.app.tab_manager;
+        let same_site = tabs.get_tab(&tab_id).and_then(|t| SiteSettingsManager::site_for_url(&t.url)) == Some(site);
+        if same_site && tabs.update_tab_favicon(&tab_id, Some(&data_url)).is_ok() {
+            drop(s);
+            let _ = proxy.send_event(UserEvent::ShowActiveTab);
+        }
+    });
+}
+
+/// PDFs kept for viewer pages; older ones are fetched again if reopened.
+const MAX_CACHED_PDFS: usize = 4;
+
+/// Fetches the PDF at `url` for the viewer page of tab `tab_id`, then tells
+/// the page whether it can show it.
+fn fetch_pdf(state: Arc<Mutex<BrowserState>>, proxy: EventLoopProxy<UserEvent>, tab_id: String, url: String) {
+    use crate::services::pdf_viewer::{has_pdf_signature, MAX_PDF_BYTES};
+    std::thread::spawn(move || {
+        let fetched = fetch_resource(&url, MAX_PDF_BYTES, 120).and_then(|(bytes, _)| {
+            if has_pdf_signature(&bytes) { Ok(bytes) } else { Err("not a PDF file".to_string()) }
+        });
+        let js = match fetched {
+            Ok(bytes) => {
+                let mut s = state.lock().unwrap();
+                s.pdfs.retain(|(cached, _)| *cached != url);
+                if s.pdfs.len() >= MAX_CACHED_PDFS {
+                    s.pdfs.remove(0);
+                }
+                s.pdfs.push((url, bytes));
+                "if(window.__gb_pdfReady)__gb_pdfReady()".to_string()
+            }
+            Err(e) => {
+                eprintln!("[PDF] {}: {}", url, e);
+                format!("if(window.__gb_pdfFailed)__gb_pdfFailed({})", serde_json::to_string(&e).unwrap_or_default())
+            }
+        };
+        let _ = proxy.send_event(UserEvent::ForTab(tab_id, Box::new(UserEvent::EvalScript(js))));
+    });
+}
+
+/// Saves the article in `html` to the reading list, stitched with its
+/// following pages like `reading_list.add` does, then tells tab `tab_id`.
+fn save_article(
+    state: Arc<Mutex<BrowserState>>,
+    proxy: EventLoopProxy<UserEvent>,
+    tab_id: String,
+    url: String,
+    html: String,
+    images: std::collections::HashMap<String, String>,
+) {
+    use crate::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+    use crate::services::reader_mode::{ReaderMode, MAX_STITCHED_PAGES};
+    std::thread::spawn(move || {
+        // Fetched before taking the lock, so the browser stays responsive meanwhile
+        let toast = match ReaderMode::new().extract_stitched(&html, &url, MAX_STITCHED_PAGES) {
+            Ok(content) => {
+                let s = state.lock().unwrap();
+                match ReadingListManager::new(s.app.db.connection()).save_article(&url, &content, Some(&images)) {
+                    Ok(_) => "Saved to Reading List",
+                    Err(_) => "Could not save article",
+                }
+            }
+            Err(_) => "This page has no readable article",
+        };
+        let js = format!("if(window.__gb_showToast)__gb_showToast('{}')", toast);
+        let _ = proxy.send_event(UserEvent::ForTab(tab_id, Box::new(UserEvent::EvalScript(js))));
+    });
+}
+
+/// Asks for the path an internal page wants for `purpose`, named after
+/// the page and what the path is for; `None` if cancelled or unknown.
+fn choose_file(purpose: &str) -> Option<std::path::PathBuf> {
+    use crate::platform::{pick_file, pick_folder, save_dialog, FileFilter};
+    const JSON: FileFilter = FileFilter { name: "JSON files", extensions: &["json"] };
+    const EXTENSIONS: FileFilter = FileFilter { name: "Extensions", extensions: &["zip", "crx"] };
+    match purpose {
+        "passwords_export" => save_dialog("Export Logins", "gitbrowser-logins.json"),
+        "passwords_import" => pick_file("Import Logins", &[JSON]),
+        "extensions_file" => pick_file("Install Extension", &[EXTENSIONS]),
+        "extensions_folder" => pick_folder("Install Unpacked Extension"),
+        "settings_export" => save_dialog("Export Settings", "gitbrowser-settings.json"),
+        "settings_import" => pick_file("Import Settings", &[JSON]),
+        _ => None,
+    }
+}
+
+/// Saves the viewer's PDF from `url` into the downloads folder.
+fn save_pdf(state: &mut BrowserState, url: &str) -> Result<String, String> {
+    use crate::managers::download_manager::{DownloadManager, DownloadManagerTrait};
+    let bytes = state.pdfs.iter().find(|(cached, _)| cached == url).map(|(_, bytes)| bytes.clone())
+        .ok_or("the PDF is not loaded")?;
+    let mut name = image_file_name(url);
+    if !name.to_ascii_lowercase().ends_with(".pdf") {
+        name.push_str(".pdf");
+    }
+    let dir = crate::platform::get_downloads_dir();
+    let path = DownloadManager::free_path(&dir, &name);
+    let mgr = &mut state.app.download_manager;
+    let id = mgr.start_download(url, &path.to_string_lossy()).map_err(|e| e.to_string())?;
+    let written = std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, &bytes));
+    match written {
+        Ok(()) => {
+            let size = bytes.len() as u64;
+            mgr.update_progress(&id, size, Some(size)).map_err(|e| e.to_string())?;
+            Ok(path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or(name))
+        }
+        Err(e) => {
+            let _ = mgr.cancel_download(&id);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Tab `tab_id` moves to the PDF at `url`, which its viewer page shows.
+fn open_pdf_viewer(state: &mut BrowserState, tab_id: &str, url: &str) {
+    use crate::managers::tab_manager::TabManagerTrait;
+    let _ = state.app.tab_manager.record_navigation(tab_id, url);
+    let _ = state.app.tab_manager.update_tab_url(tab_id, url);
+}
+
+/// The built-in PDF viewer for the PDF named in `query`.
+fn pdf_viewer_html(query: Option<&str>) -> String {
+    use crate::services::pdf_viewer::{source_from_query, PdfJs};
+    let src = query.and_then(source_from_query).unwrap_or_default();
+    let name = image_file_name(&src);
+    let installed = PdfJs::locate().is_some();
+    let extra_css = "body{overflow:hidden}\
+#pdf-viewer{position:fixed;top:72px;bottom:22px;left:0;right:0;display:flex;flex-direction:column;background:var(--bg-canvas)}\
+.pdf-bar{display:flex;align-items:center;gap:6px;padding:6px 12px;background:var(--bg-default);border-bottom:1px solid var(--border-default);font-size:13px}\
+.pdf-bar button{background:none;border:1px solid var(--border-default);color:var(--fg-default);border-radius:var(--radius-sm);padding:2px 10px;cursor:pointer}\
+.pdf-bar button:hover{background:var(--bg-subtle)}\
+.pdf-name{flex:1;color:var(--fg-muted);overflow:hidden;text-overflow:ellipsis;white-space:nowrap}\
+#pdf-page{width:48px;background:var(--bg-canvas);color:var(--fg-default);border:1px solid var(--border-default);border-radius:var(--radius-sm);padding:2px 4px;text-align:right}\
+#pdf-zoom{min-width:44px;text-align:center;color:var(--fg-muted)}\
+#pdf-pages{flex:1;overflow:auto;display:flex;flex-direction:column;align-items:center;gap:12px;padding:16px 24px}\
+.pdf-page{background:#fff;box-shadow:var(--shadow-md);flex-shrink:0}\
+#pdf-status{color:var(--fg-muted);font-size:14px;padding:24px;text-align:center}";
+    let body = format!(
+        "<div id=\"pdf-viewer\" data-src=\"{}\"{}>\
+         <div class=\"pdf-bar\"><span class=\"pdf-name\" title=\"{}\">{}</span>\
+         <button id=\"pdf-prev\" title=\"Previous page\">&#9650;</button>\
+         <input id=\"pdf-page\" type=\"number\" min=\"1\" value=\"1\"/><span id=\"pdf-count\"></span>\
+         <button id=\"pdf-next\" title=\"Next page\">&#9660;</button>\
+         <button id=\"pdf-zoom-out\" title=\"Zoom out\">&minus;</button><span id=\"pdf-zoom\"></span>\
+         <button id=\"pdf-zoom-in\" title=\"Zoom in\">+</button>\
+         <button id=\"pdf-fit\" title=\"Fit to width\">Fit</button>\
+         <button id=\"pdf-download\" title=\"Save to Downloads\">Download</button></div>\
+         <div id=\"pdf-status\"></div><div id=\"pdf-pages\"></div></div>",
+        escape_html(&src),
+        if installed { " data-viewer=\"pdfjs\"" } else { "" },
+        escape_html(&src),
+        escape_html(&name)
+    );
+    internal_page(&body, extra_css, include_str!("../../resources/ui/pdf_viewer.js"))
+}
+
+/// A pdf.js file, or a PDF fetched for a viewer page, for `gb://localhost`.
+fn pdf_resource_response(
+    state: &BrowserState,
+    path: &str,
+    query: Option<&str>,
+) -> Option<wry::http::Response<std::borrow::Cow<'static, [u8]>>> {
+    use crate::services::pdf_viewer::{source_from_query, PdfJs};
+    let found = if let Some(name) = path.strip_prefix("/pdfjs/") {
+        PdfJs::locate().and_then(|pdfjs| pdfjs.asset(name))
+    } else if path == "/pdf-data" {
+        let src = query.and_then(source_from_query).unwrap_or_default();
+        state.pdfs.iter().find(|(url, _)| *url == src).map(|(_, bytes)| (bytes.clone(), "application/pdf"))
+    } else {
+        return None;
+    };
+    let response = match found {
+        Some((body, content_type)) => wry::http::Response::builder()
+            .header("Content-Type", content_type)
+            .body(body.into()),
+        None => wry::http::Response::builder()
+            .status(404)
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(b"not found".to_vec().into()),
+    };
+    Some(response.unwrap())
+}
+
+/// File name for an image saved from `url`: the last path segment, with
+/// characters no file system takes replaced.
+fn image_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name: String = path
+        .rsplit('/')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .map(|c| if c.is_control() || "\\/:*?\"<>|".contains(c) { '_' } else { c })
+        .collect();
+    if name.trim_matches('.').is_empty() {
+        "image".to_string()
+    } else {
+        name
+    }
+}
+
+/// Script updating download `id` on the downloads page, if that is the page
+/// showing; other pages are not told about downloads.
+fn download_update(app: &App, id: &str) -> Option<String> {
+    use crate::managers::download_manager::DownloadManagerTrait;
+    if !active_url_is(app, "about:downloads") {
+        return None;
+    }
+    let item = app.download_manager.get_download(id)?;
+    let json = crate::rpc_handler::download_json(app, item);
+    Some(format!("if(window.__gb_downloadUpdate)__gb_downloadUpdate({})", json))
+}
+
+fn active_url_is(app: &App, url: &str) -> bool {
+    use crate::managers::tab_manager::TabManagerTrait;
+    app.tab_manager.get_active_tab().is_some_and(|t| t.url == url)
+}
+
+/// Reloads the active tab's page in its webview.
+fn navigate_to_active(state: &mut BrowserState) -> Option<UserEvent> {
+    use crate::managers::tab_manager::TabManagerTrait;
+    let url = state.app.tab_manager.get_active_tab().map_or_else(|| page_url_for("about:newtab"), tab_page_url);
+    Some(UserEvent::LoadUrl(url))
+}
+
+/// Applies or removes forced dark mode on the current page and updates the toolbar button.
+fn force_dark_update(state: &BrowserState, enabled: bool) -> String {
+    use crate::services::theme_engine::ThemeEngineTrait;
+    format!(
+        "{};if(window.__gb_setForceDark)__gb_setForceDark({})",
+        state.app.theme_engine.force_dark_script(enabled),
+        enabled
+    )
+}
+
+/// Script resolving a content script's pending `runtime.sendMessage` promise.
+fn ext_reply_script(request_id: u64, response: &serde_json::Value, error: Option<&str>) -> String {
+    format!(
+        "if(window.__gb_ext_reply)__gb_ext_reply({},{},{})",
+        request_id,
+        response,
+        serde_json::to_string(&error).unwrap_or_else(|_| "null".to_string())
+    )
+}
+
+/// Identifies who sent an extension API request from the main webview: one of
+/// the extension's own `gb://extension/` pages, or a content script on the page.
+/// `page_url` is the URL the webview reports; a URL in the message itself is
+/// the page's own claim and never used.
+fn page_sender(page_url: &str, request_id: Option<u64>) -> crate::types::extension::MessageSender {
+    use crate::types::extension::MessageSender;
+    let url = page_url.to_string();
+    if crate::services::extension_framework::extension_page_id(page_url).is_some() {
+        MessageSender::ExtensionPage { url, request_id }
+    } else {
+        MessageSender::ContentScript { url, request_id }
+    }
+}
+
+/// Serves `gb://extension/<id>/options` and the files that page loads.
+fn extension_page_response(app: &App, path: &str) -> wry::http::Response<std::borrow::Cow<'static, [u8]>> {
+    use crate::services::extension_framework::ExtensionFrameworkTrait;
+    let path = path.trim_start_matches('/');
+    let (id, file) = path.split_once('/').unwrap_or((path, ""));
+    let result = if file == "options" {
+        app.extension_framework.options_page(id).map(|mut html| {
+            // Toolbar inlined as on other gb:// pages
+            html.push_str("<script>");
+            html.push_str(TOOLBAR_JS);
+            html.push_str("</script>");
+            ("text/html; charset=utf-8", html.into_bytes())
+        })
+    } else {
+        app.extension_framework.extension_resource(id, file).map(|bytes| (content_type_for(file), bytes))
+    };
+    match result {
+        Ok((content_type, body)) => wry::http::Response::builder()
+            .header("Content-Type", content_type)
+            .body(body.into())
+            .unwrap(),
+        Err(e) => wry::http::Response::builder()
+            .status(404)
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(e.to_string().into_bytes().into())
+            .unwrap(),
+    }
+}
+
+fn content_type_for(file: &str) -> &'static str {
+    match file.rsplit('.').next().unwrap_or("").to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Script resolving a pending extension API request with its result.
+fn ext_result_script(request_id: u64, result: Result<serde_json::Value, crate::types::errors::ExtensionError>) -> String {
+    match result {
+        Ok(value) => ext_reply_script(request_id, &value, None),
+        Err(e) => ext_reply_script(request_id, &serde_json::Value::Null, Some(&e.to_string())),
+    }
+}
+
+/// Runs a `chrome.storage.local` request for an extension.
+fn ext_storage_op(
+    app: &mut App,
+    extension_id: &str,
+    msg: &serde_json::Value,
+) -> Result<serde_json::Value, crate::types::errors::ExtensionError> {
+    use crate::services::extension_framework::ExtensionFrameworkTrait;
+    let keys = msg.get("keys").and_then(|v| v.as_array()).map(|a| {
+        a.iter().filter_map(|k| k.as_str().map(String::from)).collect::<Vec<_>>()
+    });
+    let fw = &mut app.extension_framework;
+    match msg.get("op").and_then(|v| v.as_str()).unwrap_or("") {
+        "get" => fw.storage_get(extension_id, keys.as_deref()).map(serde_json::Value::Object),
+        "set" => {
+            let items = msg.get("items").and_then(|v| v.as_object()).cloned().unwrap_or_default();
+            fw.storage_set(extension_id, &items).map(|_| serde_json::Value::Null)
+        }
+        "remove" => fw.storage_remove(extension_id, &keys.unwrap_or_default()).map(|_| serde_json::Value::Null),
+        op => Err(crate::types::errors::ExtensionError::InvalidMessage(format!("unknown storage operation '{}'", op))),
+    }
+}
+
+/// Handles IPC from an extension's background page. The extension ID comes
+/// from the page's handler, never from the message.
+fn handle_background_ipc(state: &mut BrowserState, extension_id: &str, message: &str) -> Option<UserEvent> {
+    use crate::services::extension_framework::ExtensionFrameworkTrait;
+    use crate::types::extension::MessageSender;
+    let msg: serde_json::Value = serde_json::from_str(message).ok()?;
+    let cmd = msg.get("cmd")?.as_str()?;
+
+    match cmd {
+        "bg_ready" => {
+            state.app.extension_framework.mark_background_ready(extension_id);
+            None
+        }
+        "ext_reply" => {
+            let message_id = msg.get("id").and_then(|v| v.as_u64())?;
+            let response = msg.get("response").cloned().unwrap_or(serde_json::Value::Null);
+            match state.app.extension_framework.reply(extension_id, message_id, &response) {
+                Ok(original) => match original.sender {
+                    MessageSender::ContentScript { request_id: Some(request_id), .. }
+                    | MessageSender::ExtensionPage { request_id: Some(request_id), .. } => {
+                        Some(UserEvent::EvalScript(ext_reply_script(request_id, &response, None)))
+                    }
+                    _ => None,
+                },
+                Err(e) => {
+                    eprintln!("[EXT] {}: {}", extension_id, e);
+                    None
+                }
+            }
+        }
+        "ext_message" => {
+            // The background page is the only receiver, so its own messages have nowhere to go
+            let request_id = msg.get("id").and_then(|v| v.as_u64())?;
+            let error = "Could not establish connection. Receiving end does not exist.";
+            Some(UserEvent::EvalBackground(
+                extension_id.to_string(),
+                ext_reply_script(request_id, &serde_json::Value::Null, Some(error)),
+            ))
+        }
+        "ext_storage" => {
+            let request_id = msg.get("id").and_then(|v| v.as_u64())?;
+            let result = ext_storage_op(&mut state.app, extension_id, &msg);
+            Some(UserEvent::EvalBackground(extension_id.to_string(), ext_result_script(request_id, result)))
+        }
+        _ => None,
+    }
+}
+
+/// Starts/stops background pages to match enabled extensions and delivers
+/// queued messages to the pages that have loaded.
+fn extension_background_events(app: &mut App) -> Vec<UserEvent> {
+    use crate::services::extension_framework::{BackgroundChange, ExtensionFrameworkTrait};
+    use crate::types::extension::MessageSender;
+    let fw = &mut app.extension_framework;
+    let mut events: Vec<UserEvent> = fw.poll_background_changes().into_iter().map(|change| match change {
+        BackgroundChange::Start(script) => UserEvent::StartBackground(script),
+        BackgroundChange::Stop(id) => UserEvent::StopBackground(id),
+    }).collect();
+
+    for id in fw.ready_backgrounds() {
+        let scripts: Vec<String> = fw.take_messages(&id).into_iter().map(|m| {
+            let sender = match &m.sender {
+                MessageSender::ContentScript { url, .. } | MessageSender::ExtensionPage { url, .. } => {
+                    serde_json::json!({"id": m.extension_id, "url": url})
+                }
+                MessageSender::Background => serde_json::json!({"id": m.extension_id}),
+            };
+            format!(
+                "if(window.__gb_ext_deliver)__gb_ext_deliver({},{},{},{})",
+                serde_json::to_string(&m.extension_id).unwrap_or_default(),
+                m.id,
+                m.payload,
+                sender
+            )
+        }).collect();
+        if !scripts.is_empty() {
+            events.push(UserEvent::EvalBackground(id, scripts.join(";")));
+        }
+    }
+    events
+}
+
+/// Builds the hidden webview hosting an extension's background script.
+fn build_background_page(
+    window: &tao::window::Window,
+    script: &BackgroundScript,
+    state: Arc<Mutex<BrowserState>>,
+    proxy: EventLoopProxy<UserEvent>,
+) -> wry::Result<wry::WebView> {
+    let extension_id = script.extension_id.clone();
+    let builder = WebViewBuilder::new()
+        .with_html(crate::services::extension_framework::background_page_html(script))
+        .with_visible(false)
+        .with_ipc_handler(move |msg: wry::http::Request<String>| {
+            let mut s = state.lock().unwrap();
+            if let Some(event) = handle_background_ipc(&mut s, &extension_id, msg.body()) {
+                let _ = proxy.send_event(event);
+            }
+        })
+        .with_devtools(cfg!(debug_assertions));
+    attach_child_webview(builder, window)
+}
+
+/// Strip asking whether to wait for the hung page at `url` or close it.
+/// It is a webview of its own: the toolbar lives in the hung page.
+fn build_hang_prompt(
+    window: &tao::window::Window,
+    url: &str,
+    proxy: EventLoopProxy<UserEvent>,
+) -> wry::Result<wry::WebView> {
+    let width = window.inner_size().to_logical::<f64>(window.scale_factor()).width;
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"><style>\
+         body{{margin:0;height:100vh;display:flex;align-items:center;gap:12px;padding:0 16px;box-sizing:border-box;\
+         font:13px -apple-system,BlinkMacSystemFont,\"Segoe UI\",\"Noto Sans\",Helvetica,Arial,sans-serif;\
+         background:#161b22;color:#e6edf3;border-bottom:1px solid #30363d}}\
+         .msg{{flex:1;overflow:hidden;text-overflow:ellipsis;white-space:nowrap}}.url{{color:#7d8590}}\
+         button{{border:1px solid #30363d;border-radius:6px;padding:4px 12px;cursor:pointer;background:#21262d;color:#e6edf3}}\
+         #kill{{background:#da3633;border-color:#da3633;color:#fff}}</style></head><body>\
+         <div class=\"msg\">This page is not responding <span class=\"url\">{}</span></div>\
+         <button id=\"wait\">Wait</button><button id=\"kill\">Close page</button>\
+         <script>['wait','kill'].forEach(function(id){{document.getElementById(id).onclick=function(){{window.ipc.postMessage(id)}}}})</script>\
+         </body></html>",
+        escape_html(url)
+    );
+    let builder = WebViewBuilder::new()
+        .with_html(html)
+        .with_bounds(wry::Rect {
+            position: tao::dpi::LogicalPosition::new(0.0, 0.0).into(),
+            size: tao::dpi::LogicalSize::new(width, HANG_PROMPT_HEIGHT).into(),
+        })
+        .with_ipc_handler(move |msg: wry::http::Request<String>| {
+            let kill = match msg.body().as_str() {
+                "kill" => true,
+                "wait" => false,
+                _ => return,
+            };
+            let _ = proxy.send_event(UserEvent::HangChoice(kill));
+        });
+    attach_child_webview(builder, window)
+}
+
+/// Strip asking for the username and password of HTTP authentication
+/// challenge `id`, answered through its IPC. Saving is offered while the
+/// password vault is unlocked.
+fn build_auth_prompt(
+    window: &tao::window::Window,
+    id: u64,
+    challenge: &AuthChallenge,
+    can_save: bool,
+    proxy: EventLoopProxy<UserEvent>,
+) -> wry::Result<wry::WebView> {
+    let width = window.inner_size().to_logical::<f64>(window.scale_factor()).width;
+    let message = if challenge.is_retry { "Wrong username or password for" } else { "Sign in to" };
+    let realm = if challenge.realm.is_empty() {
+        String::new()
+    } else {
+        format!(" <span class=\"realm\">\u{201c}{}\u{201d}</span>", escape_html(&challenge.realm))
+    };
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"><style>\
+         body{{margin:0;height:100vh;display:flex;align-items:center;gap:8px;padding:0 16px;box-sizing:border-box;\
+         font:13px -apple-system,BlinkMacSystemFont,\"Segoe UI\",\"Noto Sans\",Helvetica,Arial,sans-serif;\
+         background:#161b22;color:#e6edf3;border-bottom:1px solid #30363d}}\
+         .msg{{flex:1;overflow:hidden;text-overflow:ellipsis;white-space:nowrap}}.realm{{color:#7d8590}}\
+         input[type=text],input[type=password]{{width:140px;border:1px solid #30363d;border-radius:6px;padding:4px 8px;background:#0d1117;color:#e6edf3}}\
+         label{{display:{};align-items:center;gap:4px;color:#7d8590}}\
+         button{{border:1px solid #30363d;border-radius:6px;padding:4px 12px;cursor:pointer;background:#21262d;color:#e6edf3}}\
+         #sign-in{{background:#238636;border-color:#238636;color:#fff}}</style></head><body>\
+         <div class=\"msg\">{} <b>{}</b>{}</div>\
+         <input id=\"user\" type=\"text\" placeholder=\"Username\" autocomplete=\"off\" autofocus>\
+         <input id=\"pass\" type=\"password\" placeholder=\"Password\">\
+         <label><input id=\"save\" type=\"checkbox\">Save</label>\
+         <button id=\"cancel\">Cancel</button><button id=\"sign-in\">Sign in</button>\
+         <script>\
+         function $(id){{return document.getElementById(id)}}\
+         function signIn(){{window.ipc.postMessage(JSON.stringify({{username:$('user').value,password:$('pass').value,save:$('save').checked}}))}}\
+         $('sign-in').onclick=signIn;$('cancel').onclick=function(){{window.ipc.postMessage('cancel')}};\
+         document.addEventListener('keydown',function(e){{\
+         if(e.key==='Enter')signIn();else if(e.key==='Escape')window.ipc.postMessage('cancel')}});\
+         </script></body></html>",
+        if can_save { "flex" } else { "none" },
+        message,
+        escape_html(&challenge.origin),
+        realm
+    );
+    let builder = WebViewBuilder::new()
+        .with_html(html)
+        .with_bounds(wry::Rect {
+            position: tao::dpi::LogicalPosition::new(0.0, 0.0).into(),
+            size: tao::dpi::LogicalSize::new(width, AUTH_PROMPT_HEIGHT).into(),
+        })
+        .with_ipc_handler(move |msg: wry::http::Request<String>| {
+            let login = match msg.body().as_str() {
+                "cancel" => None,
+                body => match serde_json::from_str::<AuthLogin>(body) {
+                    Ok(login) => Some(login),
+                    Err(_) => return,
+                },
+            };
+            let _ = proxy.send_event(UserEvent::AuthAnswered(id, login));
+        });
+    attach_child_webview(builder, window)
+}
+
+/// Strip asking whether to open `url`, a link with the non-web `scheme`,
+/// in the application the OS has for it.
+fn build_external_prompt(
+    window: &tao::window::Window,
+    url: &str,
+    scheme: &str,
+    proxy: EventLoopProxy<UserEvent>,
+) -> wry::Result<wry::WebView> {
+    let width = window.inner_size().to_logical::<f64>(window.scale_factor()).width;
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"><style>\
+         body{{margin:0;height:100vh;display:flex;align-items:center;gap:12px;padding:0 16px;box-sizing:border-box;\
+         font:13px -apple-system,BlinkMacSystemFont,\"Segoe UI\",\"Noto Sans\",Helvetica,Arial,sans-serif;\
+         background:#161b22;color:#e6edf3;border-bottom:1px solid #30363d}}\
+         .msg{{flex:1;overflow:hidden;text-overflow:ellipsis;white-space:nowrap}}.url{{color:#7d8590}}\
+         label{{display:flex;align-items:center;gap:4px;color:#7d8590;white-space:nowrap}}\
+         button{{border:1px solid #30363d;border-radius:6px;padding:4px 12px;cursor:pointer;background:#21262d;color:#e6edf3}}\
+         #open{{background:#238636;border-color:#238636;color:#fff}}</style></head><body>\
+         <div class=\"msg\">Open this link in another app? <span class=\"url\">{}</span></div>\
+         <label><input id=\"always\" type=\"checkbox\">Always open {}: links</label>\
+         <button id=\"cancel\">Cancel</button><button id=\"open\" autofocus>Open</button>\
+         <script>\
+         document.getElementById('cancel').onclick=function(){{window.ipc.postMessage('cancel')}};\
+         document.getElementById('open').onclick=function(){{\
+         window.ipc.postMessage(document.getElementById('always').checked?'always':'open')}};\
+         document.addEventListener('keydown',function(e){{if(e.key==='Escape')window.ipc.postMessage('cancel')}});\
+         </script></body></html>",
+        escape_html(url),
+        escape_html(scheme)
+    );
+    let builder = WebViewBuilder::new()
+        .with_html(html)
+        .with_bounds(wry::Rect {
+            position: tao::dpi::LogicalPosition::new(0.0, 0.0).into(),
+            size: tao::dpi::LogicalSize::new(width, EXTERNAL_PROMPT_HEIGHT).into(),
+        })
+        .with_ipc_handler(move |msg: wry::http::Request<String>| {
+            let choice = match msg.body().as_str() {
+                "cancel" => None,
+                "open" => Some(false),
+                "always" => Some(true),
+                _ => return,
+            };
+            let _ = proxy.send_event(UserEvent::ExternalLinkChoice(choice));
+        });
+    attach_child_webview(builder, window)
+}
+
+/// Strip offering the logins saved for `origin`. Picking one there is what
+/// fills it into the page, so a page cannot get a password by asking.
+fn build_autofill_prompt(
+    window: &tao::window::Window,
+    origin: &str,
+    logins: &[(String, String)],
+    proxy: EventLoopProxy<UserEvent>,
+) -> wry::Result<wry::WebView> {
+    let width = window.inner_size().to_logical::<f64>(window.scale_factor()).width;
+    let buttons: String = logins
+        .iter()
+        .enumerate()
+        .map(|(i, (_, username))| {
+            let username = if username.is_empty() { "(no username)" } else { username };
+            format!("<button class=\"login\" data-i=\"{}\">{}</button>", i, escape_html(username))
+        })
+        .collect();
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"><style>\
+         body{{margin:0;height:100vh;display:flex;align-items:center;gap:8px;padding:0 16px;box-sizing:border-box;\
+         font:13px -apple-system,BlinkMacSystemFont,\"Segoe UI\",\"Noto Sans\",Helvetica,Arial,sans-serif;\
+         background:#161b22;color:#e6edf3;border-bottom:1px solid #30363d}}\
+         .msg{{flex:1;overflow:hidden;text-overflow:ellipsis;white-space:nowrap}}.origin{{color:#7d8590}}\
+         .logins{{display:flex;gap:8px;overflow-x:auto}}\
+         button{{border:1px solid #30363d;border-radius:6px;padding:4px 12px;cursor:pointer;background:#21262d;color:#e6edf3;white-space:nowrap}}\
+         .login{{background:#238636;border-color:#238636;color:#fff}}</style></head><body>\
+         <div class=\"msg\">Sign in with a saved login? <span class=\"origin\">{}</span></div>\
+         <div class=\"logins\">{}</div><button id=\"cancel\">Not now</button>\
+         <script>\
+         document.querySelectorAll('.login').forEach(function(b){{\
+         b.onclick=function(){{window.ipc.postMessage('fill:'+b.dataset.i)}}}});\
+         document.getElementById('cancel').onclick=function(){{window.ipc.postMessage('cancel')}};\
+         document.addEventListener('keydown',function(e){{if(e.key==='Escape')window.ipc.postMessage('cancel')}});\
+         </script></body></html>",
+        escape_html(origin),
+        buttons
+    );
+    let ids: Vec<String> = logins.iter().map(|(id, _)| id.clone()).collect();
+    let builder = WebViewBuilder::new()
+        .with_html(html)
+        .with_bounds(wry::Rect {
+            position: tao::dpi::LogicalPosition::new(0.0, 0.0).into(),
+            size: tao::dpi::LogicalSize::new(width, AUTOFILL_PROMPT_HEIGHT).into(),
+        })
+        .with_ipc_handler(move |msg: wry::http::Request<String>| {
+            let choice = match msg.body().as_str() {
+                "cancel" => None,
+                body => match body.strip_prefix("fill:").and_then(|i| i.parse::<usize>().ok()).and_then(|i| ids.get(i)) {
+                    Some(id) => Some(id.clone()),
+                    None => return,
+                },
+            };
+            let _ = proxy.send_event(UserEvent::AutofillChoice(choice));
+        });
+    attach_child_webview(builder, window)
+}
+
+/// Strip asking whether to save the password just submitted for `username`
+/// on `origin`. Only this strip saves it, so a page cannot overwrite a saved
+/// password by posting a login of its own.
+fn build_save_login_prompt(
+    window: &tao::window::Window,
+    origin: &str,
+    username: &str,
+    update: bool,
+    proxy: EventLoopProxy<UserEvent>,
+) -> wry::Result<wry::WebView> {
+    let width = window.inner_size().to_logical::<f64>(window.scale_factor()).width;
+    let message = if update { "Update the saved password for" } else { "Save the password for" };
+    let username = if username.is_empty() { "(no username)" } else { username };
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"><style>\
+         body{{margin:0;height:100vh;display:flex;align-items:center;gap:8px;padding:0 16px;box-sizing:border-box;\
+         font:13px -apple-system,BlinkMacSystemFont,\"Segoe UI\",\"Noto Sans\",Helvetica,Arial,sans-serif;\
+         background:#161b22;color:#e6edf3;border-bottom:1px solid #30363d}}\
+         .msg{{flex:1;overflow:hidden;text-overflow:ellipsis;white-space:nowrap}}.origin{{color:#7d8590}}\
+         button{{border:1px solid #30363d;border-radius:6px;padding:4px 12px;cursor:pointer;background:#21262d;color:#e6edf3}}\
+         #save{{background:#238636;border-color:#238636;color:#fff}}</style></head><body>\
+         <div class=\"msg\">{} <b>{}</b>? <span class=\"origin\">{}</span></div>\
+         <button id=\"cancel\">Not now</button><button id=\"save\" autofocus>Save</button>\
+         <script>\
+         document.getElementById('cancel').onclick=function(){{window.ipc.postMessage('cancel')}};\
+         document.getElementById('save').onclick=function(){{window.ipc.postMessage('save')}};\
+         document.addEventListener('keydown',function(e){{if(e.key==='Escape')window.ipc.postMessage('cancel')}});\
+         </script></body></html>",
+        message,
+        escape_html(username),
+        escape_html(origin)
+    );
+    let builder = WebViewBuilder::new()
+        .with_html(html)
+        .with_bounds(wry::Rect {
+            position: tao::dpi::LogicalPosition::new(0.0, 0.0).into(),
+            size: tao::dpi::LogicalSize::new(width, SAVE_LOGIN_PROMPT_HEIGHT).into(),
+        })
+        .with_ipc_handler(move |msg: wry::http::Request<String>| {
+            let save = match msg.body().as_str() {
+                "save" => true,
+                "cancel" => false,
+                _ => return,
+            };
+            let _ = proxy.send_event(UserEvent::SaveLoginChoice(save));
+        });
+    attach_child_webview(builder, window)
+}
+
+/// Hands `url` to its application, telling the page when there is none.
+fn open_external_link(url: &str, view: Option<&wry::WebView>) {
+    if crate::platform::open_external(url) {
+        return;
+    }
+    eprintln!("[EXTERNAL] no application opened {}", url);
+    if let Some(view) = view {
+        let _ = view.evaluate_script("if(window.__gb_showToast)__gb_showToast('No app could open this link')");
+    }
+}
+
+/// Shows the credential prompt for the next challenge in `queue`. Those
+/// it cannot be shown for are cancelled.
+fn next_auth_prompt(
+    window: &tao::window::Window,
+    state: &Arc<Mutex<BrowserState>>,
+    queue: &mut std::collections::VecDeque<(u64, AuthChallenge)>,
+    proxy: &EventLoopProxy<UserEvent>,
+) -> Option<(u64, AuthChallenge, wry::WebView)> {
+    use crate::services::password_manager::PasswordManagerTrait;
+    let can_save = state.lock().unwrap().app.password_manager.is_unlocked();
+    while let Some((id, challenge)) = queue.pop_front() {
+        match build_auth_prompt(window, id, &challenge, can_save, proxy.clone()) {
+            Ok(prompt) => return Some((id, challenge, prompt)),
+            Err(e) => {
+                eprintln!("[AUTH] cannot show prompt: {}", e);
+                answer_auth(id, None);
+            }
+        }
+    }
+    None
+}
+
+/// Kills the hung renderer where the webview allows it; elsewhere loading
+/// another page replaces it.
+#[cfg(target_os = "linux")]
+fn terminate_renderer(webview: &wry::WebView) {
+    use webkit2gtk::WebViewExt;
+    use wry::WebViewExtUnix;
+    webview.webview().terminate_web_process();
+}
+
+#[cfg(not(target_os = "linux"))]
+fn terminate_renderer(_webview: &wry::WebView) {}
+
+#[cfg(target_os = "linux")]
+fn attach_child_webview(builder: WebViewBuilder<'_>, window: &tao::window::Window) -> wry::Result<wry::WebView> {
+    use tao::platform::unix::WindowExtUnix;
+    use wry::WebViewBuilderExtUnix;
+    let vbox = window.default_vbox().expect("Failed to get GTK vbox");
+    builder.build_gtk(vbox)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn attach_child_webview(builder: WebViewBuilder<'_>, window: &tao::window::Window) -> wry::Result<wry::WebView> {
+    builder.build_as_child(window)
+}
+
+fn build_tabs_update(state: &BrowserState) -> String {
+    use crate::managers::tab_manager::TabManagerTrait;
+    let tabs: Vec<serde_json::Value> = state.app.tab_manager.get_all_tabs().iter().map(|t| {
+        serde_json::json!({
+            "id": t.id, "title": t.title, "url": t.url, "pinned": t.pinned, "crashed": t.crashed,
+            "blocked": t.blocked_requests, "favicon": t.favicon, "loading": t.loading,
+        })
     }).collect();
-    let aid = state.app.tab_manager.get_active_tab().map(|t| t.id.clone()).unwrap_or_default();
-    format!("if(window.__gb_updateTabs)__gb_updateTabs({})", serde_json::json!({"tabs":tabs,"activeId":aid}))
+    let tab_manager = &state.app.tab_manager;
+    let aid = tab_manager.get_active_tab().map(|t| t.id.clone()).unwrap_or_default();
+    let update = serde_json::json!({
+        "tabs": tabs,
+        "activeId": aid,
+        "canGoBack": tab_manager.can_go_back(&aid),
+        "canGoForward": tab_manager.can_go_forward(&aid),
+        "fullscreen": state.fullscreen,
+    });
+    format!("if(window.__gb_updateTabs)__gb_updateTabs({})", update)
+}
+
+/// Script showing the count of requests blocked on tab `tab_id`'s page.
+fn blocked_count_update(state: &BrowserState, tab_id: &str) -> String {
+    use crate::managers::tab_manager::TabManagerTrait;
+    let count = state.app.tab_manager.get_tab(tab_id).map_or(0, |t| t.blocked_requests);
+    format!("if(window.__gb_setBlocked)__gb_setBlocked({})", count)
+}
+
+// ─── Helpers ───
+
+fn escape_html(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn extract_title(url: &str) -> String {
+    if url.starts_with("about:") {
+        return match url {
+            "about:newtab" => "New Tab".to_string(),
+            "about:settings" => "Settings".to_string(),
+            "about:reading-list" => "Reading List".to_string(),
+            "about:downloads" => "Downloads".to_string(),
+            "about:passwords" => "Passwords".to_string(),
+            "about:extensions" => "Extensions".to_string(),
+            "about:privacy" => "Privacy".to_string(),
+            _ => "New Tab".to_string(),
+        };
+    }
+    url.trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.")
+        .split('/')
+        .next()
+        .unwrap_or(url)
+        .to_string()
+}
+
+// ─── Renderer crashes ───
+
+/// Calls `on_crash` with a reason when the webview's renderer process dies.
+/// wry does not forward this, so it is taken from the native webview:
+/// WebKitGTK's `web-process-terminated` and WebView2's `ProcessFailed`.
+/// WKWebView only reports it to wry's own navigation delegate, so macOS
+/// has no hook.
+#[cfg(target_os = "linux")]
+fn watch_renderer_crashes(webview: &wry::WebView, on_crash: impl Fn(String) + 'static) {
+    use webkit2gtk::{WebProcessTerminationReason, WebViewExt};
+    use wry::WebViewExtUnix;
+    webview.webview().connect_web_process_terminated(move |_, reason| {
+        match reason {
+            WebProcessTerminationReason::Crashed => on_crash("crashed".to_string()),
+            WebProcessTerminationReason::ExceededMemoryLimit => on_crash("exceeded the memory limit".to_string()),
+            // Terminated on purpose through the API
+            _ => {}
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn watch_renderer_crashes(webview: &wry::WebView, on_crash: impl Fn(String) + 'static) {
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        COREWEBVIEW2_PROCESS_FAILED_KIND, COREWEBVIEW2_PROCESS_FAILED_KIND_RENDER_PROCESS_EXITED,
+        COREWEBVIEW2_PROCESS_FAILED_KIND_RENDER_PROCESS_UNRESPONSIVE,
+    };
+    use webview2_com::ProcessFailedEventHandler;
+    use wry::WebViewExtWindows;
+
+    let handler = ProcessFailedEventHandler::create(Box::new(move |_, args| {
+        let Some(args) = args else { return Ok(()) };
+        let mut kind = COREWEBVIEW2_PROCESS_FAILED_KIND::default();
+        unsafe { args.ProcessFailedKind(&mut kind)? };
+        // Browser and GPU process failures are handled by WebView2 itself
+        if kind == COREWEBVIEW2_PROCESS_FAILED_KIND_RENDER_PROCESS_EXITED {
+            on_crash("render process exited".to_string());
+        } else if kind == COREWEBVIEW2_PROCESS_FAILED_KIND_RENDER_PROCESS_UNRESPONSIVE {
+            on_crash("render process unresponsive".to_string());
+        }
+        Ok(())
+    }));
+    let mut token = Default::default();
+    let registered = unsafe {
+        webview.controller().CoreWebView2().and_then(|core| core.add_ProcessFailed(&handler, &mut token))
+    };
+    if let Err(e) = registered {
+        eprintln!("[CRASH] cannot watch the renderer: {}", e);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn watch_renderer_crashes(_webview: &wry::WebView, _on_crash: impl Fn(String) + 'static) {}
+
+/// Calls `on_progress` as the page loads and `on_failure` when it cannot,
+/// with the engine's own error page suppressed. Loads stopped on purpose
+/// (by the navigation handler, a download, or a new navigation) are not
+/// failures.
+#[cfg(target_os = "linux")]
+fn watch_page_loads(
+    webview: &wry::WebView,
+    on_progress: impl Fn(f64) + 'static,
+    on_failure: impl Fn(LoadError) + 'static,
+) {
+    use crate::types::tab::LoadErrorKind;
+    use webkit2gtk::{NetworkError, PolicyError, WebViewExt};
+    use wry::WebViewExtUnix;
+    let on_failure = std::rc::Rc::new(on_failure);
+    let view = webview.webview();
+    view.connect_estimated_load_progress_notify(move |view| on_progress(view.estimated_load_progress()));
+    let failed = on_failure.clone();
+    view.connect_load_failed(move |_, _, url, error| {
+        if error.kind::<PolicyError>().is_some() || error.matches(NetworkError::Cancelled) {
+            return false;
+        }
+        let detail = error.message().to_string();
+        failed(LoadError { url: url.to_string(), kind: LoadErrorKind::from_message(&detail), detail });
+        true
+    });
+    view.connect_load_failed_with_tls_errors(move |_, url, _, errors| {
+        on_failure(LoadError {
+            url: url.to_string(),
+            kind: LoadErrorKind::Tls,
+            detail: format!("certificate not trusted ({:?})", errors),
+        });
+        true
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn watch_page_loads(
+    webview: &wry::WebView,
+    on_progress: impl Fn(f64) + 'static,
+    on_failure: impl Fn(LoadError) + 'static,
+) {
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        COREWEBVIEW2_WEB_ERROR_STATUS, COREWEBVIEW2_WEB_ERROR_STATUS_CANNOT_CONNECT,
+        COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_COMMON_NAME_IS_INCORRECT,
+        COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_EXPIRED, COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_IS_INVALID,
+        COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_REVOKED,
+        COREWEBVIEW2_WEB_ERROR_STATUS_CLIENT_CERTIFICATE_CONTAINS_ERRORS,
+        COREWEBVIEW2_WEB_ERROR_STATUS_CONNECTION_ABORTED, COREWEBVIEW2_WEB_ERROR_STATUS_CONNECTION_RESET,
+        COREWEBVIEW2_WEB_ERROR_STATUS_DISCONNECTED, COREWEBVIEW2_WEB_ERROR_STATUS_HOST_NAME_NOT_RESOLVED,
+        COREWEBVIEW2_WEB_ERROR_STATUS_OPERATION_CANCELED, COREWEBVIEW2_WEB_ERROR_STATUS_SERVER_UNREACHABLE,
+        COREWEBVIEW2_WEB_ERROR_STATUS_TIMEOUT,
+    };
+    use crate::types::tab::LoadErrorKind;
+    use webview2_com::{ContentLoadingEventHandler, NavigationCompletedEventHandler};
+    use wry::WebViewExtWindows;
+
+    // WebView2 reports no progress in between, only the document arriving
+    let loading = ContentLoadingEventHandler::create(Box::new(move |_, _| {
+        on_progress(0.5);
+        Ok(())
+    }));
+    let completed = NavigationCompletedEventHandler::create(Box::new(move |_, args| {
+        let Some(args) = args else { return Ok(()) };
+        let mut success = Default::default();
+        let mut status = COREWEBVIEW2_WEB_ERROR_STATUS::default();
+        unsafe {
+            args.IsSuccess(&mut success)?;
+            args.WebErrorStatus(&mut status)?;
+        }
+        if success.as_bool() {
+            return Ok(());
+        }
+        let kind = match status {
+            // Cancelled by the navigation handler or a new navigation, or turned into a download
+            COREWEBVIEW2_WEB_ERROR_STATUS_OPERATION_CANCELED | COREWEBVIEW2_WEB_ERROR_STATUS_CONNECTION_ABORTED => {
+                return Ok(());
+            }
+            COREWEBVIEW2_WEB_ERROR_STATUS_HOST_NAME_NOT_RESOLVED => LoadErrorKind::Dns,
+            COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_COMMON_NAME_IS_INCORRECT
+            | COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_EXPIRED
+            | COREWEBVIEW2_WEB_ERROR_STATUS_CLIENT_CERTIFICATE_CONTAINS_ERRORS
+            | COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_REVOKED
+            | COREWEBVIEW2_WEB_ERROR_STATUS_CERTIFICATE_IS_INVALID => LoadErrorKind::Tls,
+            COREWEBVIEW2_WEB_ERROR_STATUS_TIMEOUT => LoadErrorKind::Timeout,
+            COREWEBVIEW2_WEB_ERROR_STATUS_SERVER_UNREACHABLE
+            | COREWEBVIEW2_WEB_ERROR_STATUS_CONNECTION_RESET
+            | COREWEBVIEW2_WEB_ERROR_STATUS_DISCONNECTED
+            | COREWEBVIEW2_WEB_ERROR_STATUS_CANNOT_CONNECT => LoadErrorKind::Connection,
+            _ => LoadErrorKind::Other,
+        };
+        // The URL is the webview's own once the load has failed
+        on_failure(LoadError { url: String::new(), kind, detail: format!("web error status {}", status.0) });
+        Ok(())
+    }));
+    let mut token = Default::default();
+    let registered = unsafe {
+        webview.controller().CoreWebView2().and_then(|core| {
+            core.add_ContentLoading(&loading, &mut token)?;
+            core.add_NavigationCompleted(&completed, &mut token)
+        })
+    };
+    if let Err(e) = registered {
+        eprintln!("[LOAD] cannot watch page loads: {}", e);
+    }
+}
+
+/// WKWebView reports progress and failures only to wry's own delegates,
+/// so macOS keeps the engine's behaviour and the toolbar's own estimate.
+#[cfg(target_os = "macos")]
+fn watch_page_loads(
+    _webview: &wry::WebView,
+    _on_progress: impl Fn(f64) + 'static,
+    _on_failure: impl Fn(LoadError) + 'static,
+) {
+}
+
+// ─── HTTP authentication ───
+
+/// Answers an HTTP authentication challenge, once: with a username and
+/// password, or `None` to cancel it.
+type AuthResponder = Box<dyn FnOnce(Option<(String, String)>)>;
+
+thread_local! {
+    /// Challenges waiting on the credential prompt, by ID. The native
+    /// requests behind them stay on the UI thread.
+    static PENDING_AUTH: std::cell::RefCell<HashMap<u64, AuthResponder>> = Default::default();
+}
+
+/// Keeps `respond` until the prompt for its challenge is answered, and
+/// returns the challenge's ID.
+fn defer_auth(respond: AuthResponder) -> u64 {
+    static NEXT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+    let id = NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    PENDING_AUTH.with(|pending| pending.borrow_mut().insert(id, respond));
+    id
+}
+
+/// Answers challenge `id`, if it is still waiting.
+fn answer_auth(id: u64, login: Option<(String, String)>) {
+    if let Some(respond) = PENDING_AUTH.with(|pending| pending.borrow_mut().remove(&id)) {
+        respond(login);
+    }
+}
+
+/// Calls `on_challenge` when a site asks for HTTP (Basic or Digest)
+/// credentials, with the way to answer it; the engine's own dialog is
+/// not shown. Taken from WebKitGTK's `authenticate` and WebView2's
+/// `BasicAuthenticationRequested`.
+#[cfg(target_os = "linux")]
+fn watch_auth_challenges(webview: &wry::WebView, on_challenge: impl Fn(AuthChallenge, AuthResponder) + 'static) {
+    use gtk::glib::translate::{ToGlibPtr, ToGlibPtrMut};
+    use webkit2gtk::{AuthenticationRequestExt, AuthenticationScheme, Credential, CredentialPersistence, WebViewExt};
+    use wry::WebViewExtUnix;
+    webview.webview().connect_authenticate(move |_, request| {
+        // Client certificates, NTLM and the like keep WebKit's handling
+        if !matches!(
+            request.scheme(),
+            AuthenticationScheme::Default | AuthenticationScheme::HttpBasic | AuthenticationScheme::HttpDigest
+        ) {
+            return false;
+        }
+        let Some(origin) = request.security_origin().and_then(|o| o.to_str()).map(|o| o.to_string()) else {
+            return false;
+        };
+        let challenge = AuthChallenge {
+            origin: crate::services::password_manager::PasswordManager::origin_of(&origin).unwrap_or(origin),
+            realm: request.realm().map(|r| r.to_string()).unwrap_or_default(),
+            is_retry: request.is_retry(),
+        };
+        let request = request.clone();
+        on_challenge(
+            challenge,
+            Box::new(move |login| match login {
+                Some((username, password)) => {
+                    let mut credential = Credential::new(&username, &password, CredentialPersistence::ForSession);
+                    // Not wrapped by the webkit2gtk crate
+                    unsafe {
+                        webkit2gtk::ffi::webkit_authentication_request_authenticate(
+                            request.to_glib_none().0,
+                            credential.to_glib_none_mut().0,
+                        );
+                    }
+                }
+                None => request.cancel(),
+            }),
+        );
+        true
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn watch_auth_challenges(webview: &wry::WebView, on_challenge: impl Fn(AuthChallenge, AuthResponder) + 'static) {
+    use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2_10;
+    use webview2_com::{take_pwstr, BasicAuthenticationRequestedEventHandler};
+    use windows_core::{Interface, HSTRING, PWSTR};
+    use wry::WebViewExtWindows;
+
+    let handler = BasicAuthenticationRequestedEventHandler::create(Box::new(move |_, args| {
+        let Some(args) = args else { return Ok(()) };
+        let (mut uri, mut header) = (PWSTR::null(), PWSTR::null());
+        let deferral = unsafe {
+            args.Uri(&mut uri)?;
+            args.Challenge(&mut header)?;
+            args.GetDeferral()?
+        };
+        let (uri, header) = (take_pwstr(uri), take_pwstr(header));
+        let challenge = AuthChallenge {
+            origin: crate::services::password_manager::PasswordManager::origin_of(&uri).unwrap_or(uri),
+            realm: auth_realm(&header),
+            // WebView2 does not say; a refused saved login is caught by the caller
+            is_retry: false,
+        };
+        on_challenge(
+            challenge,
+            Box::new(move |login| unsafe {
+                match login {
+                    Some((username, password)) => {
+                        if let Ok(response) = args.Response() {
+                            let _ = response.SetUserName(&HSTRING::from(username));
+                            let _ = response.SetPassword(&HSTRING::from(password));
+                        }
+                    }
+                    None => {
+                        let _ = args.SetCancel(true);
+                    }
+                }
+                let _ = deferral.Complete();
+            }),
+        );
+        Ok(())
+    }));
+    let mut token = Default::default();
+    let registered = unsafe {
+        webview
+            .controller()
+            .CoreWebView2()
+            .and_then(|core| core.cast::<ICoreWebView2_10>())
+            .and_then(|core| core.add_BasicAuthenticationRequested(&handler, &mut token))
+    };
+    if let Err(e) = registered {
+        eprintln!("[AUTH] cannot watch authentication challenges: {}", e);
+    }
+}
+
+/// The `realm` of a `WWW-Authenticate` header value, or empty.
+#[cfg(target_os = "windows")]
+fn auth_realm(header: &str) -> String {
+    let Some(start) = header.to_ascii_lowercase().find("realm=") else {
+        return String::new();
+    };
+    let value = &header[start + "realm=".len()..];
+    match value.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or("").to_string(),
+        None => value.split([',', ' ']).next().unwrap_or("").to_string(),
+    }
+}
+
+/// WKWebView answers challenges through wry's own navigation delegate,
+/// so macOS keeps the engine's behaviour.
+#[cfg(target_os = "macos")]
+fn watch_auth_challenges(_webview: &wry::WebView, _on_challenge: impl Fn(AuthChallenge, AuthResponder) + 'static) {}
+
+// ─── Downloads ───
+
+/// How a page's download is going, by the ID `on_start` gave it.
+enum DownloadEvent {
+    /// Bytes received so far
+    Progress(u64),
+    /// It is saved, or failed (why)
+    Finished(Result<(), String>),
+}
+
+/// Takes over the downloads the page in `webview` starts: `on_start`
+/// gets what the response says of each and returns its ID and where to
+/// save it, or `None` to cancel it, and `on_event` hears how it goes.
+/// The engine's own download UI is not shown.
+#[cfg(target_os = "linux")]
+fn watch_downloads(
+    webview: &wry::WebView,
+    on_start: impl Fn(crate::types::download::DownloadRequest) -> Option<(String, std::path::PathBuf)> + 'static,
+    on_event: impl Fn(&str, DownloadEvent) + 'static,
+) {
+    use gtk::glib::ObjectExt;
+    use gtk::prelude::WidgetExt;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use webkit2gtk::{DownloadExt, URIRequestExt, URIResponseExt, WebContextExt, WebViewExt};
+    use wry::WebViewExtUnix;
+
+    let view = webview.webview();
+    let Some(context) = view.context() else { return };
+    let own = view.downgrade();
+    let (on_start, on_event) = (Rc::new(on_start), Rc::new(on_event));
+    let handler = context.connect_download_started(move |_, download| {
+        // The web context is shared, so every tab hears every download
+        let Some(view) = own.upgrade() else { return };
+        if download.web_view().as_ref() != Some(&view) {
+            return;
+        }
+        let id: Rc<RefCell<Option<String>>> = Default::default();
+        let (start, started) = (on_start.clone(), id.clone());
+        download.connect_decide_destination(move |download, suggested_name| {
+            let response = download.response();
+            let request = crate::types::download::DownloadRequest {
+                url: download.request().and_then(|r| r.uri()).map(|u| u.to_string()).unwrap_or_default(),
+                suggested_name: suggested_name.to_string(),
+                referrer: None,
+                size: response.as_ref().map(|r| r.content_length()).filter(|&size| size > 0),
+                mime_type: response.as_ref().and_then(|r| r.mime_type()).map(|m| m.to_string()),
+            };
+            let destination = start(request).and_then(|(id, path)| {
+                let uri = gtk::glib::filename_to_uri(&path, None).ok()?;
+                Some((id, uri))
+            });
+            match destination {
+                Some((id, uri)) => {
+                    download.set_destination(&uri);
+                    *started.borrow_mut() = Some(id);
+                }
+                None => download.cancel(),
+            }
+            true
+        });
+        let (events, progress_id) = (on_event.clone(), id.clone());
+        let last_progress = Cell::new(std::time::Instant::now());
+        download.connect_received_data(move |download, _| {
+            if last_progress.get().elapsed() < DOWNLOAD_PROGRESS_INTERVAL {
+                return;
+            }
+            last_progress.set(std::time::Instant::now());
+            if let Some(id) = progress_id.borrow().as_deref() {
+                events(id, DownloadEvent::Progress(download.received_data_length()));
+            }
+        });
+        // `finished` follows `failed`, which has already told
+        let failed = Rc::new(Cell::new(false));
+        let (events, failed_id, failed_flag) = (on_event.clone(), id.clone(), failed.clone());
+        download.connect_failed(move |_, error| {
+            failed_flag.set(true);
+            if let Some(id) = failed_id.borrow().as_deref() {
+                events(id, DownloadEvent::Finished(Err(error.message().to_string())));
+            }
+        });
+        let events = on_event.clone();
+        download.connect_finished(move |_| {
+            if let (false, Some(id)) = (failed.get(), id.borrow().as_deref()) {
+                events(id, DownloadEvent::Finished(Ok(())));
+            }
+        });
+    });
+    // Closed tabs stop listening
+    let handler = RefCell::new(Some(handler));
+    view.connect_destroy(move |_| {
+        if let Some(handler) = handler.take() {
+            context.disconnect(handler);
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn watch_downloads(
+    webview: &wry::WebView,
+    on_start: impl Fn(crate::types::download::DownloadRequest) -> Option<(String, std::path::PathBuf)> + 'static,
+    on_event: impl Fn(&str, DownloadEvent) + 'static,
+) {
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        ICoreWebView2_4, COREWEBVIEW2_DOWNLOAD_INTERRUPT_REASON, COREWEBVIEW2_DOWNLOAD_STATE,
+        COREWEBVIEW2_DOWNLOAD_STATE_COMPLETED, COREWEBVIEW2_DOWNLOAD_STATE_INTERRUPTED,
+    };
+    use webview2_com::{take_pwstr, BytesReceivedChangedEventHandler, DownloadStartingEventHandler, StateChangedEventHandler};
+    use windows_core::{Interface, HSTRING, PWSTR};
+    use wry::WebViewExtWindows;
+
+    let on_event = std::rc::Rc::new(on_event);
+    let handler = DownloadStartingEventHandler::create(Box::new(move |_, args| {
+        let Some(args) = args else { return Ok(()) };
+        unsafe {
+            let operation = args.DownloadOperation()?;
+            let (mut uri, mut mime_type, mut default_path) = (PWSTR::null(), PWSTR::null(), PWSTR::null());
+            let mut total = 0i64;
+            operation.Uri(&mut uri)?;
+            operation.MimeType(&mut mime_type)?;
+            operation.TotalBytesToReceive(&mut total)?;
+            args.ResultFilePath(&mut default_path)?;
+            let default_path = std::path::PathBuf::from(take_pwstr(default_path));
+            let request = crate::types::download::DownloadRequest {
+                url: take_pwstr(uri),
+                suggested_name: default_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+                referrer: None,
+                // Unknown sizes are reported as -1
+                size: u64::try_from(total).ok().filter(|&size| size > 0),
+                mime_type: Some(take_pwstr(mime_type)).filter(|m| !m.is_empty()),
+            };
+            let Some((id, path)) = on_start(request) else {
+                args.SetCancel(true)?;
+                return Ok(());
+            };
+            args.SetResultFilePath(&HSTRING::from(path.to_string_lossy().as_ref()))?;
+            // The downloads page shows it, not WebView2's flyout
+            args.SetHandled(true)?;
+
+            let (events, progress_id) = (on_event.clone(), id.clone());
+            let progress = BytesReceivedChangedEventHandler::create(Box::new(move |operation, _| {
+                let Some(operation) = operation else { return Ok(()) };
+                let mut received = 0i64;
+                operation.BytesReceived(&mut received)?;
+                events(&progress_id, DownloadEvent::Progress(received.max(0) as u64));
+                Ok(())
+            }));
+            let events = on_event.clone();
+            let state_changed = StateChangedEventHandler::create(Box::new(move |operation, _| {
+                let Some(operation) = operation else { return Ok(()) };
+                let mut state = COREWEBVIEW2_DOWNLOAD_STATE::default();
+                operation.State(&mut state)?;
+                if state == COREWEBVIEW2_DOWNLOAD_STATE_COMPLETED {
+                    events(&id, DownloadEvent::Finished(Ok(())));
+                } else if state == COREWEBVIEW2_DOWNLOAD_STATE_INTERRUPTED {
+                    let mut reason = COREWEBVIEW2_DOWNLOAD_INTERRUPT_REASON::default();
+                    operation.InterruptReason(&mut reason)?;
+                    events(&id, DownloadEvent::Finished(Err(format!("Interrupted (reason {})", reason.0))));
+                }
+                Ok(())
+            }));
+            let mut token = Default::default();
+            operation.add_BytesReceivedChanged(&progress, &mut token)?;
+            operation.add_StateChanged(&state_changed, &mut token)?;
+        }
+        Ok(())
+    }));
+    let mut token = Default::default();
+    let registered = unsafe {
+        webview
+            .controller()
+            .CoreWebView2()
+            .and_then(|core| core.cast::<ICoreWebView2_4>())
+            .and_then(|core| core.add_DownloadStarting(&handler, &mut token))
+    };
+    if let Err(e) = registered {
+        eprintln!("[DOWNLOAD] cannot watch downloads: {}", e);
+    }
+}
+
+/// Most a full-page screenshot is tall, in CSS pixels; longer pages are cut.
+#[cfg(not(target_os = "macos"))]
+const MAX_CAPTURE_HEIGHT: f64 = 16384.0;
+
+/// Largest screenshot handed to a page to put on the clipboard.
+#[cfg(not(target_os = "linux"))]
+const MAX_CLIPBOARD_IMAGE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Finishes the download recording a file made from a page: complete at
+/// its size on disk, or cancelled when it could not be written.
+fn record_saved_file(state: &Arc<Mutex<BrowserState>>, id: &str, result: Result<(), String>) {
+    use crate::managers::download_manager::DownloadManagerTrait;
+    let mut s = state.lock().unwrap();
+    let mgr = &mut s.app.download_manager;
+    let path = mgr.get_download(id).map(|d| d.filepath.clone()).unwrap_or_default();
+    let recorded = match result {
+        Ok(()) => {
+            let size = std::fs::metadata(&path).map_or(0, |m| m.len());
+            mgr.update_progress(id, size, Some(size))
+        }
+        Err(e) => {
+            eprintln!("[SAVE] {}: {}", path, e);
+            mgr.cancel_download(id)
+        }
+    };
+    if let Err(e) = recorded {
+        eprintln!("[SAVE] {}: {}", path, e);
+    }
+}
+
+/// Takes a PNG of the page, the whole of it with `full_page` (up to
+/// `MAX_CAPTURE_HEIGHT`), then calls `on_done` with it.
+#[cfg(target_os = "linux")]
+fn capture_page(webview: &wry::WebView, full_page: bool, on_done: impl FnOnce(Result<Vec<u8>, String>) + 'static) {
+    use gtk::cairo::ImageSurface;
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+    use wry::WebViewExtUnix;
+    let region = if full_page { SnapshotRegion::FullDocument } else { SnapshotRegion::Visible };
+    let no_cancel: Option<&gtk::gio::Cancellable> = None;
+    webview.webview().snapshot(region, SnapshotOptions::empty(), no_cancel, move |result| {
+        let png = result.map_err(|e| e.message().to_string()).and_then(|surface| {
+            let image = ImageSurface::try_from(surface.clone()).map_err(|_| "not an image".to_string())?;
+            let height = image.height().min(MAX_CAPTURE_HEIGHT as i32);
+            let pixbuf = gtk::gdk::pixbuf_get_from_surface(&surface, 0, 0, image.width(), height)
+                .ok_or("cannot read the snapshot")?;
+            pixbuf.save_to_bufferv("png", &[]).map_err(|e| e.message().to_string())
+        });
+        on_done(png);
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn capture_page(webview: &wry::WebView, full_page: bool, on_done: impl FnOnce(Result<Vec<u8>, String>) + 'static) {
+    use base64::Engine;
+    use webview2_com::CallDevToolsProtocolMethodCompletedHandler;
+    use windows_core::HSTRING;
+    use wry::WebViewExtWindows;
+
+    let core = match unsafe { webview.controller().CoreWebView2() } {
+        Ok(core) => core,
+        Err(e) => return on_done(Err(e.to_string())),
+    };
+    let metrics_core = core.clone();
+    // Chromium's own capture, through DevTools, can reach past the viewport
+    let capture = move |params: serde_json::Value| {
+        let handler = CallDevToolsProtocolMethodCompletedHandler::create(Box::new(move |result, json: String| {
+            let png = result.map_err(|e| e.to_string()).and_then(|()| {
+                let reply: serde_json::Value = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+                let data = reply.get("data").and_then(|d| d.as_str()).ok_or("no screenshot data")?;
+                base64::engine::general_purpose::STANDARD.decode(data).map_err(|e| e.to_string())
+            });
+            on_done(png);
+            Ok(())
+        }));
+        let params = HSTRING::from(params.to_string());
+        unsafe { core.CallDevToolsProtocolMethod(&HSTRING::from("Page.captureScreenshot"), &params, &handler) }
+    };
+    let started = if full_page {
+        let handler = CallDevToolsProtocolMethodCompletedHandler::create(Box::new(move |result, json: String| {
+            let size = result.ok().and_then(|()| serde_json::from_str::<serde_json::Value>(&json).ok())
+                .and_then(|m| m.get("cssContentSize").cloned())
+                .unwrap_or_default();
+            let width = size.get("width").and_then(|w| w.as_f64()).unwrap_or(0.0);
+            let height = size.get("height").and_then(|h| h.as_f64()).unwrap_or(0.0).min(MAX_CAPTURE_HEIGHT);
+            let clip = serde_json::json!({"x": 0, "y": 0, "width": width, "height": height, "scale": 1});
+            capture(serde_json::json!({"format": "png", "captureBeyondViewport": true, "clip": clip}))
+        }));
+        unsafe {
+            let method = HSTRING::from("Page.getLayoutMetrics");
+            metrics_core.CallDevToolsProtocolMethod(&method, &HSTRING::from("{}"), &handler)
+        }
+    } else {
+        capture(serde_json::json!({"format": "png"}))
+    };
+    if let Err(e) = started {
+        eprintln!("[CAPTURE] {}", e);
+    }
+}
+
+/// WKWebView's snapshots are not reachable through wry.
+#[cfg(target_os = "macos")]
+fn capture_page(_webview: &wry::WebView, _full_page: bool, on_done: impl FnOnce(Result<Vec<u8>, String>) + 'static) {
+    on_done(Err("capturing pages is not supported on macOS".to_string()));
+}
+
+/// Puts the PNG `png` on the clipboard and shows `toast` saying so.
+/// Returns false when it could not, leaving the toast to the caller.
+#[cfg(target_os = "linux")]
+fn copy_png_to_clipboard(webview: &wry::WebView, png: &[u8], toast: &str) -> bool {
+    use gtk::gdk_pixbuf::PixbufLoader;
+    let loader = PixbufLoader::new();
+    let loaded = loader.write(png).and_then(|()| loader.close());
+    let Some(pixbuf) = loaded.ok().and_then(|()| loader.pixbuf()) else { return false };
+    gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD).set_image(&pixbuf);
+    let toast = serde_json::to_string(&format!("{} and copied to the clipboard", toast)).unwrap_or_default();
+    let _ = webview.evaluate_script(&format!("if(window.__gb_showToast)__gb_showToast({})", toast));
+    true
+}
+
+/// The page puts it on the clipboard through the async clipboard API.
+#[cfg(not(target_os = "linux"))]
+fn copy_png_to_clipboard(webview: &wry::WebView, png: &[u8], toast: &str) -> bool {
+    use base64::Engine;
+    if png.len() > MAX_CLIPBOARD_IMAGE_BYTES {
+        return false;
+    }
+    let data_url = format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png));
+    let js = format!(
+        "if(window.__gb_copyImage)__gb_copyImage({},{})",
+        serde_json::to_string(&data_url).unwrap_or_default(),
+        serde_json::to_string(toast).unwrap_or_default()
+    );
+    webview.evaluate_script(&js).is_ok()
+}
+
+/// Makes the webview send `user_agent` from its next request on, or its
+/// own User-Agent again with `None`.
+#[cfg(target_os = "linux")]
+fn set_user_agent(webview: &wry::WebView, user_agent: Option<&str>) -> Result<(), String> {
+    use webkit2gtk::{SettingsExt, WebViewExt};
+    use wry::WebViewExtUnix;
+    let settings = webview.webview().settings().ok_or("the webview has no settings")?;
+    settings.set_user_agent(user_agent);
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn set_user_agent(webview: &wry::WebView, user_agent: Option<&str>) -> Result<(), String> {
+    use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2Settings2;
+    use windows_core::{Interface, HSTRING, PWSTR};
+    use wry::WebViewExtWindows;
+    // WebView2 has no way back to its own, so it is kept from before the first change
+    static DEFAULT_USER_AGENT: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+    unsafe {
+        let settings = webview
+            .controller()
+            .CoreWebView2()
+            .and_then(|core| core.Settings())
+            .and_then(|settings| settings.cast::<ICoreWebView2Settings2>())
+            .map_err(|e| e.to_string())?;
+        let default = DEFAULT_USER_AGENT.get_or_init(|| {
+            let mut current = PWSTR::null();
+            match settings.UserAgent(&mut current) {
+                Ok(()) => webview2_com::take_pwstr(current),
+                Err(_) => String::new(),
+            }
+        });
+        settings
+            .SetUserAgent(&HSTRING::from(user_agent.unwrap_or(default)))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// WKWebView's User-Agent is fixed once made through wry; new tabs get it.
+#[cfg(target_os = "macos")]
+fn set_user_agent(_webview: &wry::WebView, _user_agent: Option<&str>) -> Result<(), String> {
+    Err("changing the User-Agent of an open page is not supported on macOS".to_string())
+}
+
+/// Prints the page to a PDF at `path`, without a dialog, then calls
+/// `on_done`.
+#[cfg(target_os = "linux")]
+fn print_to_pdf(webview: &wry::WebView, path: &str, on_done: impl Fn(Result<(), String>) + 'static) {
+    use webkit2gtk::{PrintOperation, PrintOperationExt};
+    use wry::WebViewExtUnix;
+    let uri = match gtk::glib::filename_to_uri(path, None) {
+        Ok(uri) => uri,
+        Err(e) => return on_done(Err(e.to_string())),
+    };
+    let settings = gtk::PrintSettings::new();
+    settings.set("printer", Some("Print to File"));
+    settings.set("output-file-format", Some("pdf"));
+    settings.set("output-uri", Some(uri.as_str()));
+    let operation = PrintOperation::new(&webview.webview());
+    operation.set_print_settings(&settings);
+    let on_done = std::rc::Rc::new(on_done);
+    let failed = on_done.clone();
+    // `failed` is followed by `finished`; only the first counts
+    let reported = std::rc::Rc::new(std::cell::Cell::new(false));
+    let failed_reported = reported.clone();
+    operation.connect_failed(move |_, error| {
+        failed_reported.set(true);
+        failed(Err(error.message().to_string()));
+    });
+    operation.connect_finished(move |_| {
+        if !reported.replace(true) {
+            on_done(Ok(()));
+        }
+    });
+    operation.print();
+}
+
+#[cfg(target_os = "windows")]
+fn print_to_pdf(webview: &wry::WebView, path: &str, on_done: impl Fn(Result<(), String>) + 'static) {
+    use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2_7;
+    use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2PrintSettings;
+    use webview2_com::PrintToPdfCompletedHandler;
+    use windows_core::{Interface, HSTRING};
+    use wry::WebViewExtWindows;
+
+    let on_done = std::rc::Rc::new(on_done);
+    let completed_done = on_done.clone();
+    let handler = PrintToPdfCompletedHandler::create(Box::new(move |result, success| {
+        completed_done(match result {
+            Ok(()) if success => Ok(()),
+            Ok(()) => Err("printing failed".to_string()),
+            Err(e) => Err(e.to_string()),
+        });
+        Ok(())
+    }));
+    let path = HSTRING::from(path);
+    let started = unsafe {
+        webview.controller().CoreWebView2().and_then(|core| core.cast::<ICoreWebView2_7>()).and_then(|core| {
+            core.PrintToPdf(&path, None::<&ICoreWebView2PrintSettings>, &handler)
+        })
+    };
+    if let Err(e) = started {
+        on_done(Err(e.to_string()));
+    }
+}
+
+/// WKWebView's PDF export is not reachable through wry.
+#[cfg(target_os = "macos")]
+fn print_to_pdf(_webview: &wry::WebView, _path: &str, on_done: impl Fn(Result<(), String>) + 'static) {
+    on_done(Err("saving as PDF is not supported on macOS; use Print instead".to_string()));
+}
+
+/// Marks tab `tab` (or the active tab) as loading or done, in its toolbar
+/// and in the tab strip.
+fn show_loading(state: &Arc<Mutex<BrowserState>>, views: &TabViews, tab: Option<&str>, loading: bool) {
+    use crate::managers::tab_manager::TabManagerTrait;
+    let Some(tab) = tab.or(views.active.as_deref()) else { return };
+    let tabs_update = {
+        let mut s = state.lock().unwrap();
+        let _ = s.app.tab_manager.set_tab_loading(tab, loading);
+        build_tabs_update(&s)
+    };
+    if let Some(view) = views.views.get(tab) {
+        let _ = view.evaluate_script(&format!("if(window.__gb_setLoading)__gb_setLoading({})", loading));
+    }
+    if let Some(view) = views.active() {
+        let _ = view.evaluate_script(&tabs_update);
+    }
 }
 
-// ─── Helpers ───
+/// One webview per tab, so a tab switched away from keeps its page as it
+/// was. A tab gets its webview when first shown and loses it when closed
+/// or suspended.
+#[derive(Default)]
+struct TabViews {
+    views: HashMap<String, wry::WebView>,
+    /// The tab whose webview is showing
+    active: Option<String>,
+}
 
-fn normalize_url(input: &str) -> String {
-    let trimmed = input.trim();
-    if trimmed.is_empty() {
-        return "about:newtab".to_string();
+impl TabViews {
+    fn active(&self) -> Option<&wry::WebView> {
+        self.active.as_ref().and_then(|id| self.views.get(id))
     }
-    if trimmed == "about:newtab" || trimmed == "about:settings" || trimmed == "about:blank" {
-        return trimmed.to_string();
+
+    /// The webview of `tab`, or of the active tab.
+    fn view_for(&self, tab: Option<&str>) -> Option<&wry::WebView> {
+        match tab {
+            Some(id) => self.views.get(id),
+            None => self.active(),
+        }
     }
-    if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
-        return trimmed.to_string();
+
+    /// Brings the webviews in line with the tabs: drops those of closed and
+    /// suspended tabs, then shows the active tab's, made first if it has
+    /// none (loading `first_url` rather than the tab's page, if given).
+    /// Returns whether another webview is now showing.
+    fn sync(&mut self, factory: &mut TabViewFactory, window: &tao::window::Window, first_url: Option<String>) -> bool {
+        use crate::managers::tab_manager::TabManagerTrait;
+        let (live, active) = {
+            let mut s = factory.state.lock().unwrap();
+            let tabs = &mut s.app.tab_manager;
+            // Switching to a suspended tab wakes it
+            if let Some(id) = tabs.get_active_tab().map(|t| t.id.clone()).filter(|id| tabs.is_suspended(id)) {
+                let _ = tabs.resume_tab(&id);
+            }
+            let live: std::collections::HashSet<String> = tabs.get_all_tabs().into_iter()
+                .filter(|t| !tabs.is_suspended(&t.id))
+                .map(|t| t.id.clone())
+                .collect();
+            let active = tabs.get_active_tab().map(|t| (t.id.clone(), tab_page_url(t)));
+            s.user_agents.retain(|id, _| live.contains(id));
+            s.readers.retain(|id, _| live.contains(id));
+            s.pending_logins.retain(|id, _| live.contains(id));
+            (live, active)
+        };
+        self.views.retain(|id, _| live.contains(id));
+        let Some((id, url)) = active else {
+            return false;
+        };
+        if !self.views.contains_key(&id) {
+            match factory.build(window, &id, &first_url.unwrap_or(url)) {
+                Ok(view) => {
+                    use crate::managers::tab_manager::TabManagerTrait;
+                    let _ = factory.state.lock().unwrap().app.tab_manager.restart_webview_history(&id);
+                    self.views.insert(id.clone(), view);
+                }
+                Err(e) => {
+                    eprintln!("[TABS] cannot create the webview of {}: {}", id, e);
+                    return false;
+                }
+            }
+        } else if self.active.as_ref() == Some(&id) {
+            return false;
+        }
+        for (view_id, view) in &self.views {
+            let _ = view.set_visible(*view_id == id);
+        }
+        if let Some(view) = self.views.get(&id) {
+            let _ = view.focus();
+        }
+        self.active = Some(id);
+        true
     }
-    if trimmed.contains('.') && !trimmed.contains(' ') {
-        return format!("https://{}", trimmed);
+
+    /// Sizes the webviews to the window. In GTK the window's box does it.
+    fn fit_to(&self, window: &tao::window::Window) {
+        #[cfg(not(target_os = "linux"))]
+        for view in self.views.values() {
+            let _ = view.set_bounds(window_bounds(window));
+        }
+        #[cfg(target_os = "linux")]
+        let _ = window;
     }
-    format!("https://www.google.com/search?q={}", urlencoding(trimmed))
 }
 
-fn urlencoding(s: &str) -> String {
-    let mut out = String::with_capacity(s.len() * 3);
-    for b in s.bytes() {
-        match b {
-            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
-                out.push(b as char);
+/// Builds the tabs' webviews. They share one web context, so every tab
+/// sees the profile's cookies and storage.
+struct TabViewFactory {
+    state: Arc<Mutex<BrowserState>>,
+    proxy: EventLoopProxy<UserEvent>,
+    web_context: WebContext,
+}
+
+impl TabViewFactory {
+    /// A webview for tab `tab_id` loading `url`. Its IPC and page events
+    /// come back tagged with the tab.
+    fn build(&mut self, window: &tao::window::Window, tab_id: &str, url: &str) -> wry::Result<wry::WebView> {
+        let for_tab = {
+            let tab_id = tab_id.to_string();
+            move |event: UserEvent| UserEvent::ForTab(tab_id.clone(), Box::new(event))
+        };
+        let ipc_tab = tab_id.to_string();
+        let proto_state = self.state.clone();
+        let proto_tab = tab_id.to_string();
+        let ipc_state = self.state.clone();
+        let ipc_proxy = self.proxy.clone();
+        let ipc_for_tab = for_tab.clone();
+        let nw_proxy = self.proxy.clone();
+        let load_proxy = self.proxy.clone();
+        let load_for_tab = for_tab.clone();
+        let nav_tab = tab_id.to_string();
+        let nav_state = self.state.clone();
+        let nav_proxy = self.proxy.clone();
+        let nav_for_tab = for_tab.clone();
+
+        let builder = WebViewBuilder::new_with_web_context(&mut self.web_context)
+            .with_custom_protocol("gb".into(), move |_wv_id, request| {
+                // WebView2 reports custom-scheme hosts as `gb.<host>`
+                if matches!(request.uri().host(), Some("extension") | Some("gb.extension")) {
+                    return extension_page_response(&proto_state.lock().unwrap().app, request.uri().path());
+                }
+                let path = request.uri().path();
+                let query = request.uri().query();
+                if let Some(response) = pdf_resource_response(&proto_state.lock().unwrap(), path, query) {
+                    return response;
+                }
+                let html = match path {
+                    "/newtab" | "/" => newtab_html(),
+                    "/settings" => settings_html(),
+                    "/reading-list" => reading_list_html(&proto_state.lock().unwrap().app),
+                    "/downloads" => downloads_html(&proto_state.lock().unwrap().app),
+                    "/passwords" => passwords_html(),
+                    "/extensions" => extensions_html(),
+                    "/privacy" => privacy_html(),
+                    "/crashed" => crashed_html(&proto_state.lock().unwrap().app),
+                    "/error" => load_error_html(&proto_state.lock().unwrap().app, &proto_tab),
+                    "/pdf" => pdf_viewer_html(query),
+                    "/reader" => reader_html(&proto_state.lock().unwrap(), &proto_tab),
+                    "/restore" => restore_html(&proto_state.lock().unwrap().app),
+                    p if p.starts_with("/reading-list/") => {
+                        let id = &p["/reading-list/".len()..];
+                        reading_list_article_html(&proto_state.lock().unwrap().app, id)
+                    }
+                    _ => newtab_html(),
+                };
+                wry::http::Response::builder()
+                    .header("Content-Type", "text/html; charset=utf-8")
+                    .body(html.into_bytes().into())
+                    .unwrap()
+            })
+            // with_initialization_script uses AddScriptToExecuteOnDocumentCreatedAsync on Windows.
+            // It runs on every http/https navigation automatically.
+            // For gb:// custom protocol pages it does NOT run on Windows,
+            // so those pages have toolbar inlined in their HTML via internal_page().
+            .with_initialization_script(TOOLBAR_JS)
+            .with_url(url)
+            .with_ipc_handler(move |msg: wry::http::Request<String>| {
+                let body = msg.body().as_str();
+                eprintln!("[IPC] {}", &body[..body.len().min(200)]);
+                let mut s = ipc_state.lock().unwrap();
+                if let Some(event) = handle_ipc(&mut s, body, &msg.uri().to_string(), &ipc_tab) {
+                    let _ = ipc_proxy.send_event(ipc_for_tab(event));
+                }
+            })
+            .with_navigation_handler(move |url| {
+                // Top-level loads: blocked ones leave the page as it is, and
+                // http:// ones are loaded again as https://
+                use crate::types::privacy::RequestDecision;
+                // Links for other applications never load in the tab
+                if crate::platform::external_scheme(&url).is_some() {
+                    let _ = nav_proxy.send_event(UserEvent::ExternalLink(url));
+                    return false;
+                }
+                let mut s = nav_state.lock().unwrap();
+                match s.app.filter_request(Some(&nav_tab), &url, "main_frame", Some(&url)) {
+                    // PDFs open in the built-in viewer, the tab showing the PDF's own URL
+                    RequestDecision::Allow if crate::services::pdf_viewer::is_pdf(&url, None) => {
+                        open_pdf_viewer(&mut s, &nav_tab, &url);
+                        let viewer = crate::services::pdf_viewer::viewer_url(&url);
+                        let _ = nav_proxy.send_event(nav_for_tab(UserEvent::LoadUrl(viewer)));
+                        false
+                    }
+                    // A page wanting another User-Agent is loaded again once the webview sends it
+                    RequestDecision::Allow => {
+                        let user_agent = s.app.user_agent_for(&url);
+                        if s.user_agents.get(&nav_tab) == Some(&user_agent) {
+                            return true;
+                        }
+                        s.user_agents.insert(nav_tab.clone(), user_agent.clone());
+                        let _ = nav_proxy.send_event(nav_for_tab(UserEvent::LoadWithUserAgent(user_agent, url)));
+                        false
+                    }
+                    RequestDecision::Block => {
+                        eprintln!("[PRIVACY] blocked {}", url);
+                        let js = format!(
+                            "{};if(window.__gb_showToast)__gb_showToast('Blocked a page on the blocklists')",
+                            blocked_count_update(&s, &nav_tab)
+                        );
+                        let _ = nav_proxy.send_event(nav_for_tab(UserEvent::EvalScript(js)));
+                        false
+                    }
+                    RequestDecision::Upgrade(https_url) => {
+                        let _ = nav_proxy.send_event(nav_for_tab(UserEvent::LoadUrl(https_url)));
+                        false
+                    }
+                }
+            })
+            .with_new_window_req_handler(move |url, _features| {
+                eprintln!("[NW] {}", url);
+                if url.starts_with("http://") || url.starts_with("https://") {
+                    let _ = nw_proxy.send_event(UserEvent::NavigateUrl(url));
+                } else if crate::platform::external_scheme(&url).is_some() {
+                    let _ = nw_proxy.send_event(UserEvent::ExternalLink(url));
+                }
+                wry::NewWindowResponse::Deny
+            })
+            .with_on_page_load_handler(move |event, _url| {
+                let event = match event {
+                    wry::PageLoadEvent::Started => UserEvent::PageLoadStarted,
+                    wry::PageLoadEvent::Finished => UserEvent::PageLoadFinished,
+                };
+                let _ = load_proxy.send_event(load_for_tab(event));
+            })
+            .with_devtools(cfg!(debug_assertions));
+        #[cfg(not(target_os = "linux"))]
+        let builder = builder.with_bounds(window_bounds(window));
+        // WKWebView's downloads are only reachable through wry, which gives
+        // the URL and a path; elsewhere `watch_downloads` sees the response
+        #[cfg(target_os = "macos")]
+        let builder = {
+            let started = self.download_handler(tab_id, &for_tab);
+            let (done_state, done_events) = (self.state.clone(), self.download_events());
+            builder
+                .with_download_started_handler(move |url, path| {
+                    let suggested_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    let request = crate::types::download::DownloadRequest { url, suggested_name, ..Default::default() };
+                    match started(request) {
+                        Some((_, chosen)) => {
+                            *path = chosen;
+                            true
+                        }
+                        None => false,
+                    }
+                })
+                .with_download_completed_handler(move |url, _path, success| {
+                    use crate::managers::download_manager::DownloadManagerTrait;
+                    use crate::types::download::DownloadStatus;
+                    // The newest unfinished download of `url` is the one that ended
+                    let id = done_state.lock().unwrap().app.download_manager.list_downloads().into_iter().find_map(|d| {
+                        let unfinished = !matches!(d.status, DownloadStatus::Completed | DownloadStatus::Failed(_));
+                        (d.url == url && unfinished).then(|| d.id.clone())
+                    });
+                    // The webview does not say why, and a cancelled download ends the same way
+                    let result = if success { Ok(()) } else { Err("Cancelled".to_string()) };
+                    if let Some(id) = id {
+                        done_events(&id, DownloadEvent::Finished(result));
+                    }
+                })
+        };
+        let user_agent = {
+            let mut s = self.state.lock().unwrap();
+            let user_agent = s.app.user_agent_for(url);
+            s.user_agents.insert(tab_id.to_string(), user_agent.clone());
+            user_agent
+        };
+        let builder = match user_agent {
+            Some(user_agent) => builder.with_user_agent(user_agent),
+            None => builder,
+        };
+
+        let view = attach_child_webview(builder, window)?;
+        let crash_proxy = self.proxy.clone();
+        let crash_for_tab = for_tab.clone();
+        watch_renderer_crashes(&view, move |reason| {
+            let _ = crash_proxy.send_event(crash_for_tab(UserEvent::RendererCrashed(reason)));
+        });
+        let auth_state = self.state.clone();
+        let auth_proxy = self.proxy.clone();
+        let auth_for_tab = for_tab.clone();
+        // Saved logins answer a challenge once; asked again, the user answers
+        let autofilled: std::rc::Rc<std::cell::RefCell<Option<String>>> = Default::default();
+        watch_auth_challenges(&view, move |challenge, respond| {
+            let refused = autofilled.borrow_mut().take().is_some_and(|origin| origin == challenge.origin);
+            if !challenge.is_retry && !refused {
+                let saved = auth_state.lock().unwrap().app.password_manager.login_for_origin(&challenge.origin);
+                if let Some(login) = saved {
+                    *autofilled.borrow_mut() = Some(challenge.origin);
+                    return respond(Some(login));
+                }
+            }
+            let id = defer_auth(respond);
+            let _ = auth_proxy.send_event(auth_for_tab(UserEvent::AuthRequested(id, challenge)));
+        });
+        #[cfg(not(target_os = "macos"))]
+        watch_downloads(&view, self.download_handler(tab_id, &for_tab), self.download_events());
+        let progress_proxy = self.proxy.clone();
+        let progress_for_tab = for_tab.clone();
+        let failed_proxy = self.proxy.clone();
+        watch_page_loads(
+            &view,
+            move |progress| {
+                let _ = progress_proxy.send_event(progress_for_tab(UserEvent::LoadProgress(progress)));
+            },
+            move |error| {
+                let _ = failed_proxy.send_event(for_tab(UserEvent::LoadFailed(error)));
+            },
+        );
+        Ok(view)
+    }
+
+    /// Decides where a download started by tab `tab_id` goes: PDFs open in
+    /// the viewer instead, everything else is recorded and saved into the
+    /// downloads folder. Returns the download's ID and path, or `None` to
+    /// cancel it.
+    fn download_handler(
+        &self,
+        tab_id: &str,
+        for_tab: &(impl Fn(UserEvent) -> UserEvent + Clone + 'static),
+    ) -> impl Fn(crate::types::download::DownloadRequest) -> Option<(String, std::path::PathBuf)> + 'static {
+        let (state, proxy, tab_id) = (self.state.clone(), self.proxy.clone(), tab_id.to_string());
+        let viewer_for_tab = for_tab.clone();
+        move |request| {
+            let mut s = state.lock().unwrap();
+            // Served as a PDF without looking like one: shown rather than saved
+            let named_pdf = crate::managers::download_manager::DownloadManager::file_name_for(&request, "")
+                .to_ascii_lowercase()
+                .ends_with(".pdf");
+            let is_pdf = named_pdf || crate::services::pdf_viewer::is_pdf(&request.url, request.mime_type.as_deref());
+            if is_pdf && (request.url.starts_with("http://") || request.url.starts_with("https://")) {
+                open_pdf_viewer(&mut s, &tab_id, &request.url);
+                let viewer = crate::services::pdf_viewer::viewer_url(&request.url);
+                let _ = proxy.send_event(viewer_for_tab(UserEvent::LoadUrl(viewer)));
+                return None;
             }
-            b' ' => out.push('+'),
-            _ => {
-                out.push('%');
-                out.push(char::from(b"0123456789ABCDEF"[(b >> 4) as usize]));
-                out.push(char::from(b"0123456789ABCDEF"[(b & 0xf) as usize]));
+            let url = request.url.clone();
+            match s.app.start_link_download(&tab_id, request) {
+                Ok((id, path)) => {
+                    eprintln!("[DOWNLOAD] {} -> {}", url, path);
+                    Some((id, std::path::PathBuf::from(path)))
+                }
+                Err(e) => {
+                    eprintln!("[DOWNLOAD] {}: {}", url, e);
+                    None
+                }
             }
         }
     }
-    out
+
+    /// Records how a download started by `download_handler` is going.
+    fn download_events(&self) -> impl Fn(&str, DownloadEvent) + 'static {
+        let state = self.state.clone();
+        move |id, event| {
+            use crate::managers::download_manager::DownloadManagerTrait;
+            let mut s = state.lock().unwrap();
+            let mgr = &mut s.app.download_manager;
+            let result = match event {
+                DownloadEvent::Progress(received) => mgr.update_progress(id, received, None),
+                DownloadEvent::Finished(Ok(())) => {
+                    let Some(item) = mgr.get_download(id) else { return };
+                    let size = std::fs::metadata(&item.filepath).map_or(item.downloaded, |m| m.len());
+                    mgr.update_progress(id, size, Some(size))
+                }
+                DownloadEvent::Finished(Err(reason)) => mgr.fail_download(id, &reason),
+            };
+            if let Err(e) = result {
+                eprintln!("[DOWNLOAD] {}: {}", id, e);
+                return;
+            }
+            if s.window_focused {
+                return;
+            }
+            use crate::platform::{notify, NotificationAction};
+            use crate::types::download::DownloadStatus;
+            let Some(item) = s.app.download_manager.get_download(id) else { return };
+            let (title, body, actions) = match &item.status {
+                DownloadStatus::Completed => {
+                    let path = std::path::PathBuf::from(&item.filepath);
+                    let show = NotificationAction::new("Show in folder", move || {
+                        crate::platform::reveal_in_folder(&path);
+                    });
+                    ("Download complete", item.filename.clone(), vec![show])
+                }
+                DownloadStatus::Failed(reason) => ("Download failed", format!("{}: {}", item.filename, reason), Vec::new()),
+                _ => return,
+            };
+            drop(s);
+            // The platform tools take a moment to start; keep them off the event loop
+            std::thread::spawn(move || notify(title, &body, actions));
+        }
+    }
 }
 
-fn extract_title(url: &str) -> String {
-    if url.starts_with("about:") {
-        return match url {
-            "about:newtab" => "New Tab".to_string(),
-            "about:settings" => "Settings".to_string(),
-            _ => "New Tab".to_string(),
-        };
+/// Fills the taskbar jump list or dock menu: a new tab, a new private
+/// window, then the recently closed tabs. Each item names the profile, so
+/// it reaches this browser; a private browser leaves the menu alone.
+fn refresh_launcher_menu(state: &Arc<Mutex<BrowserState>>) {
+    use crate::managers::tab_manager::TabManagerTrait;
+    use crate::platform::LauncherItem;
+    let (tasks, recent) = {
+        let Ok(s) = state.lock() else { return };
+        if s.app.context().private {
+            return;
+        }
+        let profile = s.app.profile().map(|p| vec!["--profile".to_string(), p.name.clone()]);
+        let args = |arg: &str| profile.iter().flatten().cloned().chain([arg.to_string()]).collect::<Vec<_>>();
+        let tasks = vec![
+            LauncherItem::new("New Tab", args("about:newtab")),
+            LauncherItem::new("New Private Window", args("--private")),
+        ];
+        let recent: Vec<LauncherItem> = s
+            .app
+            .tab_manager
+            .recently_closed()
+            .into_iter()
+            .take(LAUNCHER_RECENT_TABS)
+            .map(|tab| {
+                let title = if tab.title.trim().is_empty() { &tab.url } else { &tab.title };
+                LauncherItem::new(title.as_str(), args(&tab.url))
+            })
+            .collect();
+        (tasks, recent)
+    };
+    crate::platform::set_launcher_menu(&tasks, &recent);
+}
+
+/// Bounds filling the window's client area, for webviews added as children.
+#[cfg(not(target_os = "linux"))]
+fn window_bounds(window: &tao::window::Window) -> wry::Rect {
+    let size = window.inner_size().to_logical::<f64>(window.scale_factor());
+    wry::Rect {
+        position: tao::dpi::LogicalPosition::new(0.0, 0.0).into(),
+        size: tao::dpi::LogicalSize::new(size.width, size.height).into(),
     }
-    url.trim_start_matches("https://")
-        .trim_start_matches("http://")
-        .trim_start_matches("www.")
-        .split('/')
-        .next()
-        .unwrap_or(url)
-        .to_string()
 }
 
 // ─── Main entry point ───
 
-pub fn run() {
-    let app = App::new("gitbrowser.db").expect("Failed to initialize GitBrowser");
-    let state = Arc::new(Mutex::new(BrowserState { app, navigating: false }));
-
+pub fn run(args: CliArgs) {
     {
+        // Swap in a staged update, or roll back one that never came up, before opening the profile
+        use crate::services::update_installer::{relaunch, UpdateInstaller, UpdateInstallerTrait};
+        use crate::types::update::StartupAction;
+        let mut installer = UpdateInstaller::for_current_exe();
+        match installer.on_startup(env!("CARGO_PKG_VERSION")) {
+            Ok(StartupAction::Continue) => {}
+            Ok(action) => {
+                eprintln!("[UPDATE] {:?}, restarting", action);
+                match relaunch(installer.executable()) {
+                    Ok(()) => return,
+                    Err(e) => eprintln!("[UPDATE] {}", e),
+                }
+            }
+            Err(e) => eprintln!("[UPDATE] {}", e),
+        }
+    }
+
+    // A browser already open on the profile takes the URL arguments instead
+    let instance_dir = args.instance_dir("gitbrowser.db");
+    if let Some(dir) = &instance_dir {
+        if crate::single_instance::hand_off(dir, &args.urls).is_ok() {
+            eprintln!("[INSTANCE] opened in the running browser");
+            return;
+        }
+    }
+
+    // `--profile <name>` runs an isolated profile instead of the default database
+    let app = match args.open_app("gitbrowser.db", None) {
+        Ok(app) => app,
+        Err(e) if matches!(
+            e.downcast_ref::<crate::types::errors::ProfileError>(),
+            Some(crate::types::errors::ProfileError::InUse(_))
+        ) => {
+            eprintln!("[PROFILE] {}", e);
+            std::process::exit(1);
+        }
+        Err(e) if matches!(
+            e.downcast_ref::<crate::types::errors::EncryptionError>(),
+            Some(crate::types::errors::EncryptionError::PasswordRequired)
+        ) => {
+            // No unlock screen in this UI yet; the Electron frontend asks for the password
+            eprintln!("[DB] {}; open it from the GitBrowser desktop app or switch privacy.database_encryption to keystore", e);
+            std::process::exit(1);
+        }
+        Err(e) => panic!("Failed to initialize GitBrowser: {}", e),
+    };
+    let reports_dir = app.crash_recovery.reports_dir().to_path_buf();
+    // Cookies, cache and web storage stay inside the profile
+    let web_context = WebContext::new(app.profile().map(|p| p.paths.cache.clone()));
+    let state = Arc::new(Mutex::new(BrowserState { app, navigating: false, find: HashMap::new(), pdfs: Vec::new(), fullscreen: None, user_agents: HashMap::new(), readers: HashMap::new(), pending_logins: HashMap::new(), window_focused: true }));
+
+    // Panics are written as crash reports; the state is left alone if the panicking thread holds it
+    let crash_state = state.clone();
+    crate::services::crash_recovery::install_panic_hook(reports_dir, move || {
+        use crate::managers::tab_manager::TabManagerTrait;
+        let s = crash_state.try_lock().ok()?;
+        s.app.tab_manager.get_active_tab().map(|t| t.url.clone())
+    });
+
+    // After a crash the first page asks whether to restore the last session; otherwise the
+    // URL arguments open in tabs of their own
+    let restore_url = {
         let mut s = state.lock().unwrap();
         use crate::managers::tab_manager::TabManagerTrait;
-        s.app.tab_manager.create_tab(Some("about:newtab"), true);
+        if args.open_urls(&mut s.app).is_empty() {
+            s.app.tab_manager.create_tab(Some("about:newtab"), true);
+        }
         s.app.startup();
-    }
+        // After startup has loaded the settings file, so a private app keeps them
+        if let Err(e) = args.apply_settings(&mut s.app) {
+            eprintln!("[SETTINGS] {}", e);
+            std::process::exit(2);
+        }
+        s.app.crash_restore_prompt().map(|_| "gb://localhost/restore".to_string())
+    };
 
     let event_loop: EventLoop<UserEvent> = EventLoopBuilder::with_user_event().build();
     let proxy = event_loop.create_proxy();
 
+    // Repaint every tab whenever the theme changes, whichever component changed it, keep
+    // the downloads page current, and follow tab changes made over RPC
+    let bus_events = state.lock().unwrap().app.bus.subscribe();
+    let bus_state = state.clone();
+    let bus_proxy = proxy.clone();
+    std::thread::spawn(move || {
+        for event in bus_events {
+            let event = match event {
+                AppEvent::ThemeChanged { variables } => {
+                    UserEvent::EvalAll(crate::services::theme_engine::css_variables_script(&variables))
+                }
+                AppEvent::DownloadProgress { id, .. } => match bus_state.lock() {
+                    Ok(s) => match download_update(&s.app, &id) {
+                        Some(js) => UserEvent::EvalScript(js),
+                        None => continue,
+                    },
+                    Err(_) => break,
+                },
+                AppEvent::VaultLocked => match bus_state.lock() {
+                    Ok(s) if active_url_is(&s.app, "about:passwords") => {
+                        UserEvent::EvalScript(passwords_reply_script(&passwords_state(&s.app, None)))
+                    }
+                    Ok(_) => continue,
+                    Err(_) => break,
+                },
+                AppEvent::TabChanged { action, .. } if action != "updated" => {
+                    if action.starts_with("closed") {
+                        refresh_launcher_menu(&bus_state);
+                    }
+                    UserEvent::ShowActiveTab
+                }
+                AppEvent::PageActionRequested { tab_id, action } => {
+                    UserEvent::ForTab(tab_id, Box::new(UserEvent::Page(action)))
+                }
+                _ => continue,
+            };
+            if bus_proxy.send_event(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Links handed over by another `gitbrowser` on this profile, including `gitbrowser://`
+    // links the OS opens with the browser registered below
+    let _instance_listener = instance_dir.as_deref().and_then(|dir| {
+        let instance_proxy = proxy.clone();
+        crate::single_instance::listen(dir, move |urls| {
+            let _ = instance_proxy.send_event(UserEvent::OpenUrls(urls));
+        })
+        .map_err(|e| eprintln!("[INSTANCE] not taking links from other windows: {}", e))
+        .ok()
+    });
+    std::thread::spawn(|| {
+        if !crate::platform::register_url_scheme() {
+            eprintln!("[INSTANCE] could not register {}:// links", crate::platform::URL_SCHEME);
+        }
+    });
+    // The jump list / dock menu starts GitBrowser for this profile, which hands off to us
+    let launcher_state = state.clone();
+    std::thread::spawn(move || refresh_launcher_menu(&launcher_state));
+
+    // Follow OS dark/light changes while the theme is set to System
+    let theme_state = state.clone();
+    crate::platform::watch_system_theme(SYSTEM_THEME_POLL, move |dark| match theme_state.lock() {
+        Ok(mut s) => {
+            if s.app.set_system_dark_mode(dark).is_some() {
+                eprintln!("[THEME] system preference changed (dark={})", dark);
+            }
+            true
+        }
+        Err(_) => false,
+    });
+
+    let accent_state = state.clone();
+    crate::platform::watch_system_accent(SYSTEM_THEME_POLL, move |color| match accent_state.lock() {
+        Ok(mut s) => {
+            s.app.set_system_accent_color(&color);
+            true
+        }
+        Err(_) => false,
+    });
+
+    // Hold back background work on battery while performance.battery_saver is on
+    let power_state = state.clone();
+    crate::platform::watch_power_status(POWER_POLL, move |power| match power_state.lock() {
+        Ok(mut s) => {
+            if s.app.set_power_status(power) {
+                eprintln!("[POWER] battery saver {}", if s.app.saving_power() { "on" } else { "off" });
+            }
+            true
+        }
+        Err(_) => false,
+    });
+
+    // Drive the theme schedule; the UTC offset is refreshed each tick to follow DST
+    let schedule_state = state.clone();
+    std::thread::spawn(move || loop {
+        let offset = crate::platform::local_utc_offset_minutes();
+        match schedule_state.lock() {
+            Ok(mut s) if !s.app.is_shutting_down() => {
+                use crate::services::theme_scheduler::ThemeSchedulerTrait;
+                if let Some(offset) = offset {
+                    s.app.theme_scheduler.set_utc_offset_minutes(offset);
+                }
+                if s.app.tick_theme_schedule().is_some() {
+                    eprintln!("[THEME] scheduled theme change");
+                }
+            }
+            _ => break,
+        }
+        std::thread::sleep(THEME_SCHEDULE_TICK);
+    });
+
+    // Run extension background pages for enabled extensions and feed them messages
+    let ext_state = state.clone();
+    let ext_proxy = proxy.clone();
+    std::thread::spawn(move || loop {
+        let events = match ext_state.lock() {
+            Ok(mut s) if !s.app.is_shutting_down() => extension_background_events(&mut s.app),
+            _ => break,
+        };
+        for event in events {
+            if ext_proxy.send_event(event).is_err() {
+                return;
+            }
+        }
+        std::thread::sleep(EXTENSION_BACKGROUND_TICK);
+    });
+
+    // Keep the session current so it can be offered after a crash; less often on battery
+    let session_state = state.clone();
+    std::thread::spawn(move || {
+        let mut interval = SESSION_SAVE_INTERVAL;
+        loop {
+            std::thread::sleep(interval);
+            let saved = match session_state.lock() {
+                Ok(s) if !s.app.is_shutting_down() => {
+                    interval = s.app.background_interval(SESSION_SAVE_INTERVAL);
+                    s.app.save_session()
+                }
+                _ => break,
+            };
+            if let Err(e) = saved {
+                eprintln!("[SESSION] {}", e);
+            }
+        }
+    });
+
+    // Keep rotating database backups and run maintenance; maintenance waits
+    // for mains power while the battery saver is on
+    let backup_state = state.clone();
+    std::thread::spawn(move || loop {
+        let maintenance = match backup_state.lock() {
+            Ok(s) if !s.app.is_shutting_down() => {
+                s.app.run_scheduled_backup();
+                (!s.app.saving_power()).then(|| s.app.run_scheduled_maintenance())
+            }
+            _ => break,
+        };
+        // Waited for outside the state lock; it runs on the database thread
+        if let Some(task) = maintenance {
+            let _ = task.wait();
+        }
+        std::thread::sleep(BACKUP_CHECK_INTERVAL);
+    });
+
     let window = WindowBuilder::new()
         .with_title("GitBrowser")
         .with_inner_size(tao::dpi::LogicalSize::new(1280.0, 800.0))
         .build(&event_loop)
         .expect("Failed to create window");
 
-    let ipc_state = state.clone();
-    let ipc_proxy = proxy.clone();
-    let nw_proxy = proxy.clone();
+    // Drive the hang watchdog; the pings themselves go out from the event loop
+    let tick_proxy = proxy.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(WATCHDOG_TICK);
+        if tick_proxy.send_event(UserEvent::WatchdogTick).is_err() {
+            break;
+        }
+    });
 
-    let builder = WebViewBuilder::new()
-        .with_custom_protocol("gb".into(), move |_wv_id, request| {
-            let path = request.uri().path();
-            let html = match path {
-                "/newtab" | "/" => newtab_html(),
-                "/settings" => settings_html(),
-                _ => newtab_html(),
-            };
-            wry::http::Response::builder()
-                .header("Content-Type", "text/html; charset=utf-8")
-                .body(html.into_bytes().into())
-                .unwrap()
-        })
-        // with_initialization_script uses AddScriptToExecuteOnDocumentCreatedAsync on Windows.
-        // It runs on every http/https navigation automatically.
-        // For gb:// custom protocol pages it does NOT run on Windows,
-        // so those pages have toolbar inlined in their HTML via internal_page().
-        .with_initialization_script(TOOLBAR_JS)
-        .with_url("gb://localhost/newtab")
-        .with_ipc_handler(move |msg: wry::http::Request<String>| {
-            let body = msg.body().as_str();
-            eprintln!("[IPC] {}", &body[..body.len().min(200)]);
-            let mut s = ipc_state.lock().unwrap();
-            if let Some(event) = handle_ipc(&mut s, body) {
-                let _ = ipc_proxy.send_event(event);
-            }
-        })
-        .with_new_window_req_handler(move |url, _features| {
-            eprintln!("[NW] {}", url);
-            if url.starts_with("http://") || url.starts_with("https://") {
-                let _ = nw_proxy.send_event(UserEvent::NavigateUrl(url));
-            }
-            wry::NewWindowResponse::Deny
-        })
-        .with_devtools(cfg!(debug_assertions));
+    // Suspend idle background tabs, sooner when memory runs low or the battery saver is on
+    let suspend_proxy = proxy.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TAB_SUSPEND_TICK);
+        // Sampling can start a process (PowerShell, vm_stat), so it stays off the UI loop
+        let low_memory = crate::platform::memory_status().is_some_and(|m| m.is_low(MEMORY_PRESSURE_FRACTION));
+        if suspend_proxy.send_event(UserEvent::SuspendTick(low_memory)).is_err() {
+            break;
+        }
+    });
 
-    #[cfg(target_os = "linux")]
-    let webview = {
-        use tao::platform::unix::WindowExtUnix;
-        use wry::WebViewBuilderExtUnix;
-        let vbox = window.default_vbox().expect("Failed to get GTK vbox");
-        builder.build_gtk(vbox).expect("Failed to create WebView")
-    };
+    let watchdog_proxy = proxy.clone();
+    let mut watchdog = crate::services::hang_watchdog::HangWatchdog::default();
+    let mut hang_prompt: Option<wry::WebView> = None;
+    // The credential prompt showing, and the challenges waiting their turn
+    let mut auth_prompt: Option<(u64, AuthChallenge, wry::WebView)> = None;
+    let mut auth_queue: std::collections::VecDeque<(u64, AuthChallenge)> = std::collections::VecDeque::new();
+    // The external link being asked about, and its prompt
+    let mut external_prompt: Option<(String, wry::WebView)> = None;
+    // The autofill prompt showing: the tab and origin it fills, and its strip
+    let mut autofill_prompt: Option<(String, String, wry::WebView)> = None;
+    // The save login prompt showing: the tab whose login it saves, and its strip
+    let mut save_login_prompt: Option<(String, wry::WebView)> = None;
+    let bg_state = state.clone();
+    let bg_proxy = proxy.clone();
+    let mut background_pages: HashMap<String, wry::WebView> = HashMap::new();
 
-    #[cfg(not(target_os = "linux"))]
-    let webview = builder.build(&window).expect("Failed to create WebView");
+    let mut factory = TabViewFactory { state: state.clone(), proxy: proxy.clone(), web_context };
+    let mut views = TabViews::default();
+    views.sync(&mut factory, &window, restore_url);
+    assert!(views.active().is_some(), "Failed to create WebView");
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Wait;
@@ -408,11 +4436,34 @@ pub fn run() {
                 ..
             } => {
                 let mut s = state.lock().unwrap();
-                s.app.shutdown();
+                if !s.app.shutdown().is_clean() {
+                    eprintln!("[SHUTDOWN] finished with errors; see above");
+                }
                 *control_flow = ControlFlow::Exit;
             }
 
+            Event::WindowEvent {
+                event: WindowEvent::Resized(_),
+                ..
+            } => views.fit_to(&window),
+
+            Event::WindowEvent {
+                event: WindowEvent::Focused(focused),
+                ..
+            } => state.lock().unwrap().window_focused = focused,
+
+            // macOS delivers `gitbrowser://` links to the running app instead of starting another
+            Event::Opened { urls } => {
+                let urls: Vec<String> = urls.into_iter().map(String::from).collect();
+                let _ = proxy.send_event(UserEvent::OpenUrls(urls));
+            }
+
             Event::UserEvent(user_event) => {
+                // Scripts and loads answer the tab that asked; everything else is about the active tab
+                let (target, user_event) = match user_event {
+                    UserEvent::ForTab(id, event) => (Some(id), *event),
+                    event => (None, event),
+                };
                 match user_event {
                     UserEvent::NavigateUrl(url) => {
                         eprintln!("[NAV] {}", url);
@@ -426,15 +4477,470 @@ pub fn run() {
                                 let _ = s.app.tab_manager.update_tab_title(&tid, &title);
                             }
                         }
-                        let _ = webview.load_url(&url);
+                        views.sync(&mut factory, &window, None);
+                        if let Some(view) = views.active() {
+                            let _ = view.load_url(&url);
+                        }
                     }
                     UserEvent::LoadUrl(url) => {
                         eprintln!("[LOAD] {}", url);
-                        let _ = webview.load_url(&url);
+                        views.sync(&mut factory, &window, None);
+                        if let Some(view) = views.view_for(target.as_deref()) {
+                            let _ = view.load_url(&url);
+                        }
+                    }
+                    UserEvent::LoadWithUserAgent(user_agent, url) => {
+                        if let Some(view) = views.view_for(target.as_deref()) {
+                            if let Err(e) = set_user_agent(view, user_agent.as_deref()) {
+                                eprintln!("[UA] {}", e);
+                            }
+                            let _ = view.load_url(&url);
+                        }
                     }
                     UserEvent::EvalScript(js) => {
-                        let _ = webview.evaluate_script(&js);
+                        if let Some(view) = views.view_for(target.as_deref()) {
+                            let _ = view.evaluate_script(&js);
+                        }
+                    }
+                    UserEvent::EvalAll(js) => {
+                        for view in views.views.values() {
+                            let _ = view.evaluate_script(&js);
+                        }
+                    }
+                    UserEvent::Zoom(zoom, js) => {
+                        if let Some(view) = views.view_for(target.as_deref()) {
+                            if let Err(e) = view.zoom(zoom) {
+                                eprintln!("[ZOOM] {}", e);
+                            }
+                            let _ = view.evaluate_script(&js);
+                        }
+                    }
+                    UserEvent::ShowActiveTab => {
+                        if views.sync(&mut factory, &window, None) {
+                            // The watchdog follows the page on show
+                            watchdog.reset();
+                            hang_prompt = None;
+                        }
+                        if let Some(view) = views.active() {
+                            let _ = view.evaluate_script(&build_tabs_update(&state.lock().unwrap()));
+                        }
+                    }
+                    UserEvent::OpenUrls(urls) => {
+                        crate::cli::open_urls(&mut state.lock().unwrap().app, &urls);
+                        window.set_minimized(false);
+                        window.set_focus();
+                        let _ = proxy.send_event(UserEvent::ShowActiveTab);
+                    }
+                    UserEvent::SuspendTick(low_memory) => {
+                        let (suspended, saving_power) = {
+                            let mut s = state.lock().unwrap();
+                            let saving_power = s.app.saving_power();
+                            (s.app.suspend_idle_tabs(low_memory || saving_power), saving_power)
+                        };
+                        if !suspended.is_empty() {
+                            let why = match (low_memory, saving_power) {
+                                (true, _) => " (memory low)",
+                                (false, true) => " (battery saver)",
+                                (false, false) => "",
+                            };
+                            eprintln!("[TABS] suspended {} idle tab(s){}", suspended.len(), why);
+                            views.sync(&mut factory, &window, None);
+                        }
+                    }
+                    UserEvent::Page(action) => {
+                        let view = views.view_for(target.as_deref());
+                        match action {
+                            PageAction::Print => {
+                                if let Some(Err(e)) = view.map(|v| v.print()) {
+                                    eprintln!("[PRINT] {}", e);
+                                }
+                            }
+                            PageAction::PrintToPdf { download_id, path } => {
+                                let done_proxy = proxy.clone();
+                                let on_done = move |result| {
+                                    let _ = done_proxy.send_event(UserEvent::PageSaved(download_id.clone(), result));
+                                };
+                                match view {
+                                    Some(view) => print_to_pdf(view, &path, on_done),
+                                    // Suspended tabs have no page to print
+                                    None => on_done(Err("the tab has no page loaded".to_string())),
+                                }
+                            }
+                            PageAction::Screenshot { download_id, full_page, .. } => {
+                                let Some(view) = view else {
+                                    let unloaded = Err("the tab has no page loaded".to_string());
+                                    record_saved_file(&state, &download_id, unloaded);
+                                    return;
+                                };
+                                // The toolbar is part of the page; it is hidden for the capture
+                                let capture_proxy = proxy.clone();
+                                let tab = target.clone();
+                                let _ = view.evaluate_script_with_callback(
+                                    "if(window.__gb_setChromeHidden)__gb_setChromeHidden(true)",
+                                    move |_| {
+                                        let event = UserEvent::Capture(download_id.clone(), full_page);
+                                        let event = match tab.clone() {
+                                            Some(tab) => UserEvent::ForTab(tab, Box::new(event)),
+                                            None => event,
+                                        };
+                                        let _ = capture_proxy.send_event(event);
+                                    },
+                                );
+                            }
+                        }
+                    }
+                    UserEvent::Capture(download_id, full_page) => {
+                        let done_proxy = proxy.clone();
+                        let tab = target.clone();
+                        let on_done = move |result| {
+                            let event = UserEvent::PageCaptured(download_id, result);
+                            let event = match tab {
+                                Some(tab) => UserEvent::ForTab(tab, Box::new(event)),
+                                None => event,
+                            };
+                            let _ = done_proxy.send_event(event);
+                        };
+                        match views.view_for(target.as_deref()) {
+                            Some(view) => capture_page(view, full_page, on_done),
+                            None => on_done(Err("the tab has no page loaded".to_string())),
+                        }
+                    }
+                    UserEvent::PageSaved(id, result) => {
+                        record_saved_file(&state, &id, result);
+                    }
+                    UserEvent::PageCaptured(id, png) => {
+                        let path = {
+                            use crate::managers::download_manager::DownloadManagerTrait;
+                            let s = state.lock().unwrap();
+                            s.app.download_manager.get_download(&id).map(|d| d.filepath.clone()).unwrap_or_default()
+                        };
+                        let written = png.and_then(|png| {
+                            std::fs::write(&path, &png).map_err(|e| e.to_string())?;
+                            Ok(png)
+                        });
+                        let toast = match &written {
+                            Ok(_) => "Screenshot saved to Downloads",
+                            Err(_) => "Could not capture the page",
+                        };
+                        let view = views.view_for(target.as_deref());
+                        if let Some(view) = view {
+                            let _ = view.evaluate_script("if(window.__gb_setChromeHidden)__gb_setChromeHidden(false)");
+                        }
+                        let copied = match (&written, view) {
+                            (Ok(png), Some(view)) => copy_png_to_clipboard(view, png, toast),
+                            _ => false,
+                        };
+                        if let (false, Some(view)) = (copied, view) {
+                            let _ = view.evaluate_script(&format!(
+                                "if(window.__gb_showToast)__gb_showToast({})",
+                                serde_json::to_string(toast).unwrap_or_default()
+                            ));
+                        }
+                        record_saved_file(&state, &id, written.map(|_| ()));
+                    }
+                    UserEvent::FetchFavicon(site, candidates) => {
+                        if let Some(tab_id) = target {
+                            fetch_favicon(state.clone(), proxy.clone(), tab_id, site, candidates);
+                        }
+                    }
+                    UserEvent::Fullscreen(mode) => {
+                        window.set_fullscreen(mode.map(|_| tao::window::Fullscreen::Borderless(None)));
+                        let js = format!(
+                            "if(window.__gb_setFullscreen)__gb_setFullscreen({})",
+                            serde_json::to_string(&mode).unwrap_or_default()
+                        );
+                        for view in views.views.values() {
+                            let _ = view.evaluate_script(&js);
+                        }
+                    }
+                    UserEvent::FetchPdf(url) => {
+                        if let Some(tab_id) = target {
+                            fetch_pdf(state.clone(), proxy.clone(), tab_id, url);
+                        }
+                    }
+                    UserEvent::SaveArticle(url, html, images) => {
+                        if let Some(tab_id) = target {
+                            save_article(state.clone(), proxy.clone(), tab_id, url, html, images);
+                        }
+                    }
+                    UserEvent::SaveImage(url, ask) => {
+                        let save_state = state.clone();
+                        let save_proxy = proxy.clone();
+                        let tab = target.clone();
+                        let save = move |path: Option<std::path::PathBuf>| {
+                            eprintln!("[DOWNLOAD] saving image {}", url);
+                            let toast = match save_image(save_state, url, path.clone()) {
+                                Ok(()) if path.is_some() => "Saving image".to_string(),
+                                Ok(()) => "Saving image to Downloads".to_string(),
+                                Err(e) => e,
+                            };
+                            let toast = serde_json::to_string(&toast).unwrap_or_default();
+                            let event = UserEvent::EvalScript(format!("if(window.__gb_showToast)__gb_showToast({})", toast));
+                            let event = match tab {
+                                Some(tab) => UserEvent::ForTab(tab, Box::new(event)),
+                                None => event,
+                            };
+                            let _ = save_proxy.send_event(event);
+                        };
+                        if ask {
+                            // The dialog waits for the user; keep it off the event loop
+                            let name = image_file_name(&url);
+                            std::thread::spawn(move || {
+                                if let Some(path) = crate::platform::save_dialog("Save Image As", &name) {
+                                    save(Some(path));
+                                }
+                            });
+                        } else {
+                            save(None);
+                        }
+                    }
+                    UserEvent::ChooseFile(purpose) => {
+                        let chosen_proxy = proxy.clone();
+                        let tab = target.clone();
+                        std::thread::spawn(move || {
+                            let Some(path) = choose_file(&purpose) else { return };
+                            let event = UserEvent::EvalScript(format!(
+                                "if(window.__gb_fileChosen)__gb_fileChosen({},{})",
+                                serde_json::json!(purpose),
+                                serde_json::json!(path.to_string_lossy()),
+                            ));
+                            let event = match tab {
+                                Some(tab) => UserEvent::ForTab(tab, Box::new(event)),
+                                None => event,
+                            };
+                            let _ = chosen_proxy.send_event(event);
+                        });
+                    }
+                    UserEvent::StartBackground(script) => {
+                        eprintln!("[EXT] starting background page of {}", script.extension_id);
+                        match build_background_page(&window, &script, bg_state.clone(), bg_proxy.clone()) {
+                            Ok(page) => {
+                                background_pages.insert(script.extension_id.clone(), page);
+                            }
+                            Err(e) => eprintln!("[EXT] background page of {} failed: {}", script.extension_id, e),
+                        }
+                    }
+                    UserEvent::StopBackground(id) => {
+                        eprintln!("[EXT] stopping background page of {}", id);
+                        background_pages.remove(&id);
+                    }
+                    UserEvent::EvalBackground(id, js) => {
+                        if let Some(page) = background_pages.get(&id) {
+                            let _ = page.evaluate_script(&js);
+                        }
+                    }
+                    UserEvent::RendererCrashed(reason) => {
+                        eprintln!("[CRASH] renderer {}", reason);
+                        let Some(tab) = target.or_else(|| views.active.clone()) else {
+                            return;
+                        };
+                        state.lock().unwrap().app.handle_tab_renderer_crash(&tab, &reason);
+                        if views.active.as_ref() == Some(&tab) {
+                            watchdog.reset();
+                            hang_prompt = None;
+                            // Loading a page starts a fresh renderer; the window stays up
+                            if let Some(view) = views.active() {
+                                let _ = view.load_url("gb://localhost/crashed");
+                            }
+                        } else {
+                            // Shown again with the crashed page when switched to
+                            views.views.remove(&tab);
+                        }
+                    }
+                    UserEvent::WatchdogTick => {
+                        use crate::services::hang_watchdog::WatchdogEvent;
+                        let now = std::time::Instant::now();
+                        if let (Some(seq), Some(view)) = (watchdog.next_ping(now), views.active()) {
+                            let pong_proxy = watchdog_proxy.clone();
+                            let _ = view.evaluate_script_with_callback("0", move |_| {
+                                let _ = pong_proxy.send_event(UserEvent::WatchdogPong(seq));
+                            });
+                        }
+                        if let Some(WatchdogEvent::Hung { unresponsive_for }) = watchdog.check(now) {
+                            let url = state.lock().unwrap().app.record_hang(unresponsive_for).unwrap_or_default();
+                            eprintln!("[HANG] {} unresponsive for {:?}", url, unresponsive_for);
+                            match build_hang_prompt(&window, &url, watchdog_proxy.clone()) {
+                                Ok(prompt) => hang_prompt = Some(prompt),
+                                Err(e) => eprintln!("[HANG] cannot show prompt: {}", e),
+                            }
+                        }
+                    }
+                    UserEvent::WatchdogPong(seq) => {
+                        if let Some(event) = watchdog.pong(seq, std::time::Instant::now()) {
+                            eprintln!("[HANG] {:?}", event);
+                            hang_prompt = None;
+                        }
+                    }
+                    UserEvent::PageLoadStarted => {
+                        // Background tabs loading say nothing about the page on show
+                        if target.is_none() || target == views.active {
+                            watchdog.reset();
+                            hang_prompt = None;
+                        }
+                        show_loading(&state, &views, target.as_deref(), true);
+                    }
+                    UserEvent::LoadProgress(progress) => {
+                        if let Some(view) = views.view_for(target.as_deref()) {
+                            let _ = view.evaluate_script(&format!(
+                                "if(window.__gb_setLoadProgress)__gb_setLoadProgress({})",
+                                progress.clamp(0.0, 1.0)
+                            ));
+                        }
+                    }
+                    UserEvent::PageLoadFinished => {
+                        show_loading(&state, &views, target.as_deref(), false);
+                    }
+                    UserEvent::LoadFailed(mut error) => {
+                        use crate::managers::tab_manager::TabManagerTrait;
+                        let Some(tab) = target.or_else(|| views.active.clone()) else {
+                            return;
+                        };
+                        let Some(view) = views.views.get(&tab) else { return };
+                        if error.url.is_empty() {
+                            error.url = view.url().unwrap_or_default();
+                        }
+                        eprintln!("[LOAD] {} failed ({:?}): {}", error.url, error.kind, error.detail);
+                        let _ = state.lock().unwrap().app.tab_manager.fail_tab_load(&tab, error);
+                        let _ = view.load_url("gb://localhost/error");
+                        show_loading(&state, &views, Some(&tab), false);
+                    }
+                    UserEvent::AuthRequested(id, challenge) => {
+                        eprintln!("[AUTH] {} asks for credentials", challenge.origin);
+                        auth_queue.push_back((id, challenge));
+                        if auth_prompt.is_none() {
+                            auth_prompt = next_auth_prompt(&window, &state, &mut auth_queue, &proxy);
+                        }
+                    }
+                    UserEvent::AuthAnswered(id, login) => {
+                        if auth_prompt.as_ref().map(|(pending, ..)| *pending) != Some(id) {
+                            return;
+                        }
+                        let Some((_, challenge, _)) = auth_prompt.take() else { return };
+                        if let Some(login) = login.as_ref().filter(|login| login.save) {
+                            let mut s = state.lock().unwrap();
+                            let saved = s.app.password_manager.save_or_update_credential(
+                                &challenge.origin,
+                                &login.username,
+                                &login.password,
+                            );
+                            if let Err(e) = saved {
+                                eprintln!("[AUTH] cannot save the login: {}", e);
+                            }
+                        }
+                        answer_auth(id, login.map(|login| (login.username, login.password)));
+                        auth_prompt = next_auth_prompt(&window, &state, &mut auth_queue, &proxy);
+                    }
+                    UserEvent::ExternalLink(url) => {
+                        let Some(scheme) = crate::platform::external_scheme(&url) else { return };
+                        if state.lock().unwrap().app.is_protocol_allowed(&scheme) {
+                            open_external_link(&url, views.active());
+                            return;
+                        }
+                        // A newer link replaces the one being asked about
+                        external_prompt = None;
+                        match build_external_prompt(&window, &url, &scheme, proxy.clone()) {
+                            Ok(prompt) => external_prompt = Some((url, prompt)),
+                            Err(e) => eprintln!("[EXTERNAL] cannot show prompt: {}", e),
+                        }
+                    }
+                    UserEvent::ExternalLinkChoice(choice) => {
+                        let Some((url, _)) = external_prompt.take() else { return };
+                        let Some(always) = choice else { return };
+                        if always {
+                            let scheme = crate::platform::external_scheme(&url).unwrap_or_default();
+                            if let Err(e) = state.lock().unwrap().app.set_protocol_allowed(&scheme, true) {
+                                eprintln!("[EXTERNAL] cannot remember {}: {}", scheme, e);
+                            }
+                        }
+                        open_external_link(&url, views.active());
+                    }
+                    UserEvent::AutofillOffer(origin, logins) => {
+                        // Only the page showing gets to offer, and a newer offer replaces one
+                        let Some(tab) = target.filter(|tab| views.active.as_ref() == Some(tab)) else { return };
+                        autofill_prompt = None;
+                        match build_autofill_prompt(&window, &origin, &logins, proxy.clone()) {
+                            Ok(prompt) => autofill_prompt = Some((tab, origin, prompt)),
+                            Err(e) => eprintln!("[AUTOFILL] cannot show prompt: {}", e),
+                        }
+                    }
+                    UserEvent::AutofillChoice(choice) => {
+                        use crate::services::password_manager::{PasswordManager, PasswordManagerTrait};
+                        let Some((tab, origin, _)) = autofill_prompt.take() else { return };
+                        let Some(id) = choice else { return };
+                        // The tab may have moved on while the prompt showed
+                        let Some(view) = views.views.get(&tab).filter(|_| views.active.as_ref() == Some(&tab)) else {
+                            return;
+                        };
+                        let page_origin = view.url().ok().and_then(|url| PasswordManager::origin_of(&url));
+                        if page_origin.as_deref() != Some(origin.as_str()) {
+                            return;
+                        }
+                        let s = state.lock().unwrap();
+                        let mgr = &s.app.password_manager;
+                        let entry = mgr.credentials_for_origin(&origin).ok().and_then(|saved| saved.into_iter().find(|c| c.id == id));
+                        let Some(entry) = entry else { return };
+                        match mgr.decrypt_password(&entry) {
+                            Ok(password) => {
+                                let password = zeroize::Zeroizing::new(password);
+                                let script = zeroize::Zeroizing::new(autofill_script(&entry.username, &password));
+                                let _ = view.evaluate_script(&script);
+                            }
+                            Err(e) => eprintln!("[AUTOFILL] cannot decrypt the login: {}", e),
+                        }
+                    }
+                    UserEvent::SaveLoginOffer(origin, username, update) => {
+                        let Some(tab) = target.filter(|tab| views.active.as_ref() == Some(tab)) else { return };
+                        save_login_prompt = None;
+                        match build_save_login_prompt(&window, &origin, &username, update, proxy.clone()) {
+                            Ok(prompt) => save_login_prompt = Some((tab, prompt)),
+                            Err(e) => eprintln!("[AUTOFILL] cannot show prompt: {}", e),
+                        }
+                    }
+                    UserEvent::SaveLoginChoice(save) => {
+                        use crate::services::password_manager::PasswordManager;
+                        let Some((tab, _)) = save_login_prompt.take() else { return };
+                        let mut s = state.lock().unwrap();
+                        let Some(login) = s.pending_logins.remove(&tab) else { return };
+                        let Some(view) = views.views.get(&tab).filter(|_| save) else { return };
+                        // The tab may have left the login's origin while the prompt showed
+                        let page_origin = view.url().ok().and_then(|url| PasswordManager::origin_of(&url));
+                        if page_origin.as_deref() != Some(login.origin.as_str()) {
+                            return;
+                        }
+                        let toast = match s.app.password_manager.save_or_update_credential(
+                            &login.origin,
+                            &login.username,
+                            &login.password,
+                        ) {
+                            Ok(_) => "Password saved".to_string(),
+                            Err(e) => format!("Cannot save the password: {}", e),
+                        };
+                        let _ = view.evaluate_script(&format!(
+                            "if(window.__gb_showToast)__gb_showToast({})",
+                            serde_json::Value::from(toast)
+                        ));
+                    }
+                    UserEvent::HangChoice(kill) => {
+                        hang_prompt = None;
+                        if kill {
+                            {
+                                use crate::managers::tab_manager::TabManagerTrait;
+                                let mut s = state.lock().unwrap();
+                                if let Some(id) = s.app.tab_manager.get_active_tab().map(|t| t.id.clone()) {
+                                    let _ = s.app.tab_manager.mark_tab_crashed(&id);
+                                }
+                            }
+                            watchdog.reset();
+                            if let Some(view) = views.active() {
+                                terminate_renderer(view);
+                                let _ = view.load_url("gb://localhost/crashed");
+                            }
+                        } else {
+                            watchdog.wait(std::time::Instant::now());
+                        }
                     }
+                    // Unwrapped above
+                    UserEvent::ForTab(..) => {}
                 }
             }
 
@@ -6,8 +6,13 @@
 //! and deserialized back without data loss for arbitrary valid inputs.
 
 use gitbrowser::types::ai::AIProviderName;
+use gitbrowser::types::github::GitHubSettings;
+use gitbrowser::types::reader::{FontFamily, ReaderPreset, ReaderSettings};
+use gitbrowser::types::search::SearchSettings;
+use gitbrowser::types::theme::ThemeScheduleSettings;
+use gitbrowser::types::update::UpdateSettings;
 use gitbrowser::types::settings::{
-    AISettings, AppearanceSettings, BrowserSettings, GeneralSettings, PerformanceSettings,
+    AISettings, AppearanceSettings, BrowserSettings, DatabaseEncryption, GeneralSettings, PerformanceSettings,
     PrivacySettings, StartupBehavior, ThemeMode,
 };
 use proptest::prelude::*;
@@ -45,39 +50,63 @@ fn arb_general_settings() -> impl Strategy<Value = GeneralSettings> {
         "[a-z]{2,5}",
         arb_startup_behavior(),
         "[a-zA-Z0-9:/._-]{1,50}",
-        "[a-z]{3,10}",
+        "[a-zA-Z0-9 ()/.;_-]{0,60}",
+        proptest::collection::vec("[a-z]{3,10}", 0..3),
     )
         .prop_map(
-            |(language, startup_behavior, homepage, default_search_engine)| GeneralSettings {
+            |(language, startup_behavior, homepage, user_agent, allowed_protocols)| GeneralSettings {
                 language,
                 startup_behavior,
                 homepage,
-                default_search_engine,
+                user_agent,
+                allowed_protocols,
             },
         )
 }
 
+fn arb_database_encryption() -> impl Strategy<Value = DatabaseEncryption> {
+    prop_oneof![
+        Just(DatabaseEncryption::Off),
+        Just(DatabaseEncryption::Keystore),
+        Just(DatabaseEncryption::MasterPassword),
+    ]
+}
+
 fn arb_privacy_settings() -> impl Strategy<Value = PrivacySettings> {
     (
-        any::<bool>(),
-        any::<bool>(),
-        any::<bool>(),
-        any::<bool>(),
-        "[a-zA-Z0-9:/._-]{5,60}",
-        any::<bool>(),
-        any::<bool>(),
-        any::<bool>(),
+        (
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            "[a-zA-Z0-9:/._-]{5,60}",
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            any::<bool>(),
+            "(https://[a-z]{3,12}\\.example/[a-z]{0,8})?",
+            arb_database_encryption(),
+            proptest::collection::vec("\\|\\|[a-z]{3,10}\\.example\\^", 0..3),
+        ),
+        0u32..=300u32,
     )
         .prop_map(
             |(
-                tracker_blocking,
-                ad_blocking,
-                https_enforcement,
-                dns_over_https,
-                dns_provider,
-                anti_fingerprinting,
-                clear_data_on_exit,
-                telemetry_consent,
+                (
+                    tracker_blocking,
+                    ad_blocking,
+                    https_enforcement,
+                    dns_over_https,
+                    dns_provider,
+                    anti_fingerprinting,
+                    clear_data_on_exit,
+                    telemetry_consent,
+                    crash_report_urls,
+                    crash_report_endpoint,
+                    database_encryption,
+                    custom_filters,
+                ),
+                clipboard_clear_seconds,
             )| PrivacySettings {
                 tracker_blocking,
                 ad_blocking,
@@ -87,19 +116,35 @@ fn arb_privacy_settings() -> impl Strategy<Value = PrivacySettings> {
                 anti_fingerprinting,
                 clear_data_on_exit,
                 telemetry_consent,
+                crash_report_urls,
+                crash_report_endpoint,
+                database_encryption,
+                custom_filters,
+                clipboard_clear_seconds,
             },
         )
 }
 
 fn arb_appearance_settings() -> impl Strategy<Value = AppearanceSettings> {
-    (arb_theme_mode(), "#[0-9a-f]{6}", 8u32..=72u32, proptest::bool::ANY, proptest::bool::ANY).prop_map(
-        |(theme, accent_color, font_size, show_telegram, show_github)| AppearanceSettings {
-            theme,
-            accent_color,
-            font_size,
-            show_telegram,
-            show_github,
-        },
+    (
+        arb_theme_mode(),
+        "#[0-9a-f]{6}",
+        8u32..=72u32,
+        proptest::bool::ANY,
+        proptest::bool::ANY,
+        proptest::option::of("[a-z0-9-]{1,20}"),
+        proptest::bool::ANY,
+    )
+        .prop_map(
+            |(theme, accent_color, font_size, show_telegram, show_github, custom_theme, force_dark)| AppearanceSettings {
+                theme,
+                accent_color,
+                font_size,
+                show_telegram,
+                show_github,
+                custom_theme,
+                force_dark,
+            },
     )
 }
 
@@ -119,14 +164,47 @@ fn arb_ai_settings() -> impl Strategy<Value = AISettings> {
 }
 
 fn arb_performance_settings() -> impl Strategy<Value = PerformanceSettings> {
-    (1u32..=120u32, any::<bool>()).prop_map(|(tab_suspend_timeout_minutes, lazy_load_images)| {
+    (1u32..=120u32, any::<bool>(), any::<bool>()).prop_map(|(tab_suspend_timeout_minutes, lazy_load_images, battery_saver)| {
         PerformanceSettings {
             tab_suspend_timeout_minutes,
             lazy_load_images,
+            battery_saver,
         }
     })
 }
 
+fn arb_reader_settings() -> impl Strategy<Value = ReaderSettings> {
+    (
+        10u32..=40u32,
+        prop_oneof![Just(FontFamily::Serif), Just(FontFamily::SansSerif), Just(FontFamily::Monospace)],
+        "#[0-9a-f]{6}",
+        10u32..=30u32,
+        400u32..=1200u32,
+        prop_oneof![
+            Just(ReaderPreset::Default),
+            Just(ReaderPreset::Sepia),
+            Just(ReaderPreset::Dark),
+            Just(ReaderPreset::HighContrast),
+        ],
+        40u32..=120u32,
+        any::<bool>(),
+    )
+        .prop_map(
+            |(font_size, font_family, background_color, lh_tenths, max_width, preset, column_width, justify)| {
+                ReaderSettings {
+                    font_size,
+                    font_family,
+                    background_color,
+                    line_height: lh_tenths as f32 / 10.0,
+                    max_width,
+                    preset,
+                    column_width,
+                    justify,
+                }
+            },
+        )
+}
+
 fn arb_browser_settings() -> impl Strategy<Value = BrowserSettings> {
     (
         arb_general_settings(),
@@ -135,15 +213,21 @@ fn arb_browser_settings() -> impl Strategy<Value = BrowserSettings> {
         arb_shortcuts(),
         arb_ai_settings(),
         arb_performance_settings(),
+        arb_reader_settings(),
     )
         .prop_map(
-            |(general, privacy, appearance, shortcuts, ai, performance)| BrowserSettings {
+            |(general, privacy, appearance, shortcuts, ai, performance, reader)| BrowserSettings {
                 general,
                 privacy,
                 appearance,
                 shortcuts,
                 ai,
                 performance,
+                reader,
+                search: SearchSettings::default(),
+                theme_schedule: ThemeScheduleSettings::default(),
+                github: GitHubSettings::default(),
+                updates: UpdateSettings::default(),
             },
         )
 }
@@ -0,0 +1,80 @@
+//! Unit tests for the AnnotationManager public API.
+//!
+//! These tests exercise highlight/note CRUD through the
+//! `AnnotationManagerTrait` interface and re-application of stored
+//! highlights to reader HTML, using an in-memory SQLite database.
+
+use gitbrowser::database::Database;
+use gitbrowser::managers::annotation_manager::{AnnotationManager, AnnotationManagerTrait, DEFAULT_HIGHLIGHT_COLOR};
+
+#[test]
+fn test_add_and_list_annotations_by_url() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = AnnotationManager::new(db.connection());
+
+    mgr.add_annotation("https://example.com/a", "first", "", "", Some("note"), None).unwrap();
+    mgr.add_annotation("https://example.com/a#section", "second", "", "", None, Some("#a0e0ff")).unwrap();
+    mgr.add_annotation("https://example.com/b", "other", "", "", None, None).unwrap();
+
+    let list = mgr.list_annotations("https://example.com/a").unwrap();
+    assert_eq!(list.len(), 2, "Fragment should be ignored when keying by URL");
+    assert_eq!(list[0].anchor_text, "first");
+    assert_eq!(list[0].note.as_deref(), Some("note"));
+    assert_eq!(list[0].color, DEFAULT_HIGHLIGHT_COLOR);
+    assert_eq!(list[1].color, "#a0e0ff");
+}
+
+#[test]
+fn test_add_rejects_empty_anchor_and_bad_color() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = AnnotationManager::new(db.connection());
+
+    assert!(mgr.add_annotation("https://example.com", "   ", "", "", None, None).is_err());
+    assert!(mgr.add_annotation("https://example.com", "text", "", "", None, Some("red")).is_err());
+    assert!(mgr.add_annotation("https://example.com", "text", "", "", None, Some("#12345g")).is_err());
+}
+
+#[test]
+fn test_update_and_remove_annotation() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = AnnotationManager::new(db.connection());
+
+    let id = mgr.add_annotation("https://example.com", "text", "", "", None, None).unwrap();
+    mgr.update_annotation(&id, Some("added later"), None).unwrap();
+
+    let list = mgr.list_annotations("https://example.com").unwrap();
+    assert_eq!(list[0].note.as_deref(), Some("added later"));
+    assert_eq!(list[0].color, DEFAULT_HIGHLIGHT_COLOR, "Color is kept when not provided");
+
+    mgr.remove_annotation(&id).unwrap();
+    assert!(mgr.list_annotations("https://example.com").unwrap().is_empty());
+    assert!(mgr.remove_annotation(&id).is_err());
+    assert!(mgr.update_annotation(&id, Some("x"), None).is_err());
+}
+
+#[test]
+fn test_apply_annotations_uses_context_to_pick_occurrence() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = AnnotationManager::new(db.connection());
+
+    mgr.add_annotation("https://example.com", "the cat", "sat. ", " ran", None, None).unwrap();
+    let list = mgr.list_annotations("https://example.com").unwrap();
+
+    let html = "<p>the cat sat. the cat ran.</p>";
+    let applied = AnnotationManager::apply_annotations(html, &list);
+    assert!(applied.starts_with("<p>the cat sat. <mark class=\"gb-annotation\""));
+    assert!(applied.ends_with(">the cat</mark> ran.</p>"));
+}
+
+#[test]
+fn test_apply_annotations_ignores_markup_and_escapes_notes() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = AnnotationManager::new(db.connection());
+
+    mgr.add_annotation("https://example.com", "cat", "", "", Some("<b>\"hi\"</b>"), None).unwrap();
+    let list = mgr.list_annotations("https://example.com").unwrap();
+
+    let applied = AnnotationManager::apply_annotations("<p class=\"cat\">a cat</p>", &list);
+    assert!(applied.starts_with("<p class=\"cat\">a <mark"), "Attribute values must not be matched");
+    assert!(applied.contains("title=\"&lt;b&gt;&quot;hi&quot;&lt;/b&gt;\""));
+}
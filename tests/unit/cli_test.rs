@@ -0,0 +1,99 @@
+//! Unit tests for command-line argument parsing.
+
+use gitbrowser::app::App;
+use gitbrowser::cli::CliArgs;
+use gitbrowser::managers::profile_manager::ProfileManager;
+use gitbrowser::managers::tab_manager::TabManagerTrait;
+use gitbrowser::services::settings_engine::SettingsEngineTrait;
+use gitbrowser::types::errors::CliError;
+use serde_json::json;
+use tempfile::TempDir;
+
+fn parse(list: &[&str]) -> Result<CliArgs, CliError> {
+    CliArgs::parse(list.iter().map(|s| s.to_string()))
+}
+
+#[test]
+fn test_no_arguments() {
+    assert_eq!(parse(&[]).unwrap(), CliArgs::default());
+}
+
+#[test]
+fn test_flags_and_urls() {
+    let args = parse(&["https://example.com", "--private", "--headless", "--rpc", "github.com"]).unwrap();
+    assert_eq!(args.urls, vec!["https://example.com", "github.com"]);
+    assert!(args.private && args.headless && args.rpc);
+    assert!(args.wants_backend());
+    assert!(!parse(&["github.com"]).unwrap().wants_backend());
+}
+
+#[test]
+fn test_profile_forms() {
+    assert_eq!(parse(&["--profile", "work"]).unwrap().profile.as_deref(), Some("work"));
+    assert_eq!(parse(&["--profile=work"]).unwrap().profile.as_deref(), Some("work"));
+    assert_eq!(parse(&["--profile"]), Err(CliError::MissingValue("--profile".to_string())));
+    for name in ["../../x", "a/b", ""] {
+        assert_eq!(parse(&["--profile", name]), Err(CliError::InvalidProfile(name.to_string())));
+    }
+}
+
+#[test]
+fn test_settings_values() {
+    let args = parse(&[
+        "--settings",
+        "privacy.ad_blocking=false",
+        "--settings=general.language=de",
+        "--settings",
+        "performance.tab_suspend_timeout_minutes=5",
+    ])
+    .unwrap();
+    assert_eq!(
+        args.settings,
+        vec![
+            ("privacy.ad_blocking".to_string(), json!(false)),
+            ("general.language".to_string(), json!("de")),
+            ("performance.tab_suspend_timeout_minutes".to_string(), json!(5)),
+        ]
+    );
+    assert_eq!(parse(&["--settings", "=1"]), Err(CliError::InvalidSetting("=1".to_string())));
+    assert_eq!(parse(&["--settings", "nokey"]), Err(CliError::InvalidSetting("nokey".to_string())));
+}
+
+#[test]
+fn test_unknown_option_and_separator() {
+    assert_eq!(parse(&["--verbose"]), Err(CliError::UnknownOption("--verbose".to_string())));
+    assert_eq!(parse(&["--", "--private"]).unwrap().urls, vec!["--private"]);
+    assert!(parse(&["-h"]).unwrap().help);
+    assert!(parse(&["--version"]).unwrap().version);
+}
+
+#[test]
+fn test_apply_to_private_app() {
+    let tmp = TempDir::new().unwrap();
+    let profiles = ProfileManager::new(tmp.path().to_path_buf());
+    let base = App::open_profile(&profiles, "test", None).unwrap();
+    let mut app = base.open_private().unwrap();
+
+    let args = parse(&["--settings", "general.language=de", "example.com", "https://github.com"]).unwrap();
+    args.apply_settings(&mut app).unwrap();
+    assert_eq!(app.settings_engine.get_settings().general.language, "de");
+    assert_eq!(base.settings_engine.get_settings().general.language, "en");
+
+    let tabs = args.open_urls(&mut app);
+    assert_eq!(tabs.len(), 2);
+    assert_eq!(app.tab_manager.get_active_tab().unwrap().url, "https://example.com");
+    assert_eq!(app.tab_manager.get_tab(&tabs[1]).unwrap().url, "https://github.com");
+
+    let rejected = parse(&["--settings", "no.such.key=1"]).unwrap().apply_settings(&mut app);
+    assert!(rejected.is_err());
+}
+
+#[test]
+fn test_instance_dir() {
+    assert_eq!(parse(&["--private"]).unwrap().instance_dir("gitbrowser.db"), None);
+
+    let dir = parse(&["--profile", "work", "gitbrowser://settings"]).unwrap().instance_dir("gitbrowser.db").unwrap();
+    assert_eq!(dir, ProfileManager::with_default_root().paths("work").dir);
+    let default = parse(&[]).unwrap().instance_dir("gitbrowser.db").unwrap();
+    assert_eq!(default, ProfileManager::default_context("gitbrowser.db").paths.dir);
+}
@@ -0,0 +1,135 @@
+//! Unit tests for crash logging, report collection and recovery.
+
+use std::sync::Arc;
+
+use tempfile::TempDir;
+
+use gitbrowser::database::connection::Database;
+use gitbrowser::services::crash_recovery::{
+    crash_entry, panic_message, write_report, CrashRecovery, CrashRecoveryTrait, MAX_CRASH_LOGS,
+};
+
+fn setup() -> (CrashRecovery, TempDir) {
+    let tmp = TempDir::new().unwrap();
+    let db = Arc::new(Database::open_in_memory().unwrap());
+    let recovery = CrashRecovery::new(db).with_reports_dir(tmp.path().join("crashes"));
+    (recovery, tmp)
+}
+
+// ─── Logging ───
+
+#[test]
+fn test_log_crash_keeps_context() {
+    let (mut recovery, _tmp) = setup();
+    assert!(!recovery.has_unrecovered_crash());
+    let entry = crash_entry("panic", "boom", Some("0: main".to_string()), Some("https://example.com".to_string()));
+    recovery.log_crash(entry).unwrap();
+
+    let logs = recovery.get_crash_logs().unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].tab_url.as_deref(), Some("https://example.com"));
+    assert_eq!(logs[0].backtrace.as_deref(), Some("0: main"));
+    assert_eq!(logs[0].os.as_deref(), Some(format!("{} {}", std::env::consts::OS, std::env::consts::ARCH).as_str()));
+    assert_eq!(logs[0].app_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+    assert!(recovery.has_unrecovered_crash());
+}
+
+#[test]
+fn test_mark_recovered_keeps_logs() {
+    let (mut recovery, _tmp) = setup();
+    recovery.log_crash(crash_entry("panic", "boom", None, None)).unwrap();
+    recovery.mark_crash_recovered().unwrap();
+    assert!(!recovery.has_unrecovered_crash());
+    assert_eq!(recovery.get_crash_logs().unwrap().len(), 1);
+}
+
+#[test]
+fn test_mark_logs_recovered_by_id_and_clear() {
+    let (mut recovery, _tmp) = setup();
+    let first = crash_entry("panic", "one", None, None);
+    let id = first.id.clone();
+    recovery.log_crash(first).unwrap();
+    recovery.log_crash(crash_entry("panic", "two", None, None)).unwrap();
+
+    recovery.mark_logs_recovered(std::slice::from_ref(&id)).unwrap();
+    assert!(recovery.has_unrecovered_crash());
+    let logs = recovery.get_crash_logs().unwrap();
+    assert!(logs.iter().all(|log| log.recovered == (log.id == id)));
+    assert!(recovery.mark_logs_recovered(&["missing".to_string()]).is_err());
+
+    assert_eq!(recovery.clear_crash_logs().unwrap(), 2);
+    assert!(recovery.get_crash_logs().unwrap().is_empty());
+    assert!(!recovery.has_unrecovered_crash());
+}
+
+#[test]
+fn test_old_logs_are_pruned() {
+    let (mut recovery, _tmp) = setup();
+    for i in 0..MAX_CRASH_LOGS + 5 {
+        let mut entry = crash_entry("panic", &format!("crash {}", i), None, None);
+        entry.timestamp = 1_700_000_000 + i;
+        recovery.log_crash(entry).unwrap();
+    }
+    let logs = recovery.get_crash_logs().unwrap();
+    assert_eq!(logs.len() as i64, MAX_CRASH_LOGS);
+    assert_eq!(logs[0].error_message.as_deref(), Some(format!("crash {}", MAX_CRASH_LOGS + 4).as_str()));
+}
+
+// ─── Reports ───
+
+#[test]
+fn test_collect_imports_and_removes_reports() {
+    let (mut recovery, _tmp) = setup();
+    let path = write_report(recovery.reports_dir(), &crash_entry("panic", "boom at src/app.rs:1:1", None, None)).unwrap();
+
+    assert_eq!(recovery.collect_reports().unwrap(), 1);
+    assert!(!path.exists());
+    let logs = recovery.get_crash_logs().unwrap();
+    assert_eq!(logs[0].error_message.as_deref(), Some("boom at src/app.rs:1:1"));
+    assert!(recovery.has_unrecovered_crash());
+}
+
+#[test]
+fn test_unclean_exit_is_logged_once() {
+    let (mut recovery, _tmp) = setup();
+    assert_eq!(recovery.collect_reports().unwrap(), 0);
+
+    // Never shut down: the next start finds the marker
+    assert_eq!(recovery.collect_reports().unwrap(), 1);
+    assert_eq!(recovery.get_crash_logs().unwrap()[0].error_type, "unclean_exit");
+
+    recovery.mark_clean_exit();
+    assert_eq!(recovery.collect_reports().unwrap(), 0);
+    assert_eq!(recovery.get_crash_logs().unwrap().len(), 1);
+}
+
+#[test]
+fn test_panic_report_replaces_unclean_exit() {
+    let (mut recovery, _tmp) = setup();
+    recovery.collect_reports().unwrap();
+    write_report(recovery.reports_dir(), &crash_entry("panic", "boom", None, None)).unwrap();
+
+    assert_eq!(recovery.collect_reports().unwrap(), 1);
+    let logs = recovery.get_crash_logs().unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].error_type, "panic");
+}
+
+#[test]
+fn test_unreadable_reports_are_dropped() {
+    let (mut recovery, _tmp) = setup();
+    std::fs::create_dir_all(recovery.reports_dir()).unwrap();
+    let path = recovery.reports_dir().join("crash-1-garbage.json");
+    std::fs::write(&path, "{not json").unwrap();
+    assert_eq!(recovery.collect_reports().unwrap(), 0);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_panic_message_formats_payloads() {
+    let location = std::panic::Location::caller();
+    let message = panic_message(&"index out of bounds", Some(location));
+    assert_eq!(message, format!("index out of bounds at {}:{}:{}", location.file(), location.line(), location.column()));
+    assert_eq!(panic_message(&String::from("owned"), None), "owned");
+    assert_eq!(panic_message(&42, None), "non-string panic payload");
+}
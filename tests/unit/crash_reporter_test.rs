@@ -0,0 +1,109 @@
+//! Unit tests for crash report sanitizing and issue drafts.
+
+use reqwest::Url;
+
+use gitbrowser::services::crash_recovery::crash_entry;
+use gitbrowser::services::crash_reporter::{
+    issue_draft_url, page_url, redact, sanitize_report, upload, UploadTarget, REPORT_FORMAT,
+};
+use gitbrowser::types::errors::CrashError;
+use gitbrowser::types::settings::PrivacySettings;
+
+// ─── Redaction ───
+
+#[test]
+fn test_redact_scrubs_user_names_from_paths() {
+    assert_eq!(
+        redact("at /home/alice/.cargo/registry/src/lib.rs:10", false),
+        "at /home/<user>/.cargo/registry/src/lib.rs:10"
+    );
+    assert_eq!(redact("/Users/bob/Library/x", false), "/Users/<user>/Library/x");
+    assert_eq!(redact(r"C:\Users\carol\AppData\gb.exe", false), r"C:\Users\<user>\AppData\gb.exe");
+    assert_eq!(redact("src/app.rs:1:1", false), "src/app.rs:1:1");
+}
+
+#[test]
+fn test_redact_scrubs_emails_and_urls() {
+    let text = "login failed for dev@example.com on https://example.com/p?token=abc (retry)";
+    assert_eq!(redact(text, false), "login failed for <email> on <url> (retry)");
+    assert_eq!(redact(text, true), "login failed for <email> on https://example.com/p (retry)");
+    assert_eq!(redact("file:///home/alice/x.html", true), "<url>");
+}
+
+#[test]
+fn test_redact_scrubs_urls_without_scheme() {
+    assert_eq!(redact("GET example.com/path?token=abc failed", false), "GET <url> failed");
+    assert_eq!(redact("GET example.com/path?token=abc failed", true), "GET example.com/path failed");
+    assert_eq!(redact("see www.example.org", false), "see <url>");
+    assert_eq!(redact("api.example.com:8443/v1#frag", true), "api.example.com:8443/v1");
+    // Source locations and module paths are not hosts
+    assert_eq!(redact("at src/app.rs:1:1 in gitbrowser::app::App::new", false), "at src/app.rs:1:1 in gitbrowser::app::App::new");
+    assert_eq!(redact("main.rs", false), "main.rs");
+}
+
+#[test]
+fn test_page_url_drops_credentials_query_and_fragment() {
+    assert_eq!(page_url("https://user:pw@example.com:8443/a/b?q=1#top").as_deref(), Some("https://example.com:8443/a/b"));
+    assert_eq!(page_url("gb://localhost/settings"), None);
+    assert_eq!(page_url("not a url"), None);
+}
+
+#[test]
+fn test_sanitize_report_keeps_url_only_on_opt_in() {
+    let entry = crash_entry(
+        "panic",
+        "boom at /home/alice/src/main.rs:1:1",
+        Some("0: /home/alice/gb".to_string()),
+        Some("https://example.com/inbox?id=1".to_string()),
+    );
+    let report = sanitize_report(&entry, false);
+    assert_eq!(report.id, entry.id);
+    assert_eq!(report.message.as_deref(), Some("boom at /home/<user>/src/main.rs:1:1"));
+    assert_eq!(report.backtrace.as_deref(), Some("0: /home/<user>/gb"));
+    assert_eq!(report.os, entry.os);
+    assert_eq!(report.url, None);
+    assert_eq!(sanitize_report(&entry, true).url.as_deref(), Some("https://example.com/inbox"));
+}
+
+// ─── Targets ───
+
+#[test]
+fn test_upload_target_from_settings() {
+    let mut privacy = PrivacySettings::default();
+    assert_eq!(UploadTarget::from_settings(&privacy), UploadTarget::IssueDraft);
+    privacy.crash_report_endpoint = " https://crash.example/reports ".to_string();
+    assert_eq!(
+        UploadTarget::from_settings(&privacy),
+        UploadTarget::Endpoint("https://crash.example/reports".to_string())
+    );
+}
+
+#[test]
+fn test_upload_requires_https() {
+    let report = sanitize_report(&crash_entry("panic", "boom", None, None), false);
+    assert!(matches!(upload("http://crash.example/reports", std::slice::from_ref(&report)), Err(CrashError::UploadFailed(_))));
+    assert!(matches!(upload("not a url", &[report]), Err(CrashError::UploadFailed(_))));
+}
+
+#[test]
+fn test_issue_draft_carries_reports() {
+    let report = sanitize_report(&crash_entry("panic", "boom", Some("0: main".to_string()), None), false);
+    let url = Url::parse(&issue_draft_url(&[report])).unwrap();
+    let pairs: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+    assert_eq!(pairs["title"], format!("Crash: panic in GitBrowser {}", env!("CARGO_PKG_VERSION")));
+    assert!(pairs["body"].contains(REPORT_FORMAT));
+    assert!(pairs["body"].contains("0: main"));
+}
+
+#[test]
+fn test_issue_draft_shortens_long_backtraces() {
+    let backtrace: String = (0..2000).map(|i| format!("{}: frame_{}\n", i, i)).collect();
+    let reports: Vec<_> = (0..3)
+        .map(|_| sanitize_report(&crash_entry("panic", "boom", Some(backtrace.clone()), None), false))
+        .collect();
+    let url = Url::parse(&issue_draft_url(&reports)).unwrap();
+    let body = url.query_pairs().find(|(k, _)| k == "body").unwrap().1.into_owned();
+    assert!(body.len() <= 6000, "{}", body.len());
+    assert!(body.contains("0: frame_0"));
+    assert!(body.contains("[…]"));
+}
@@ -0,0 +1,142 @@
+//! Unit tests for online database backups and their rotation.
+
+use std::time::Duration;
+
+use gitbrowser::database::backup::BackupRotation;
+use gitbrowser::database::Database;
+use gitbrowser::types::errors::BackupError;
+use tempfile::TempDir;
+
+fn setup() -> (Database, BackupRotation, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("gitbrowser.db")).unwrap();
+    let rotation = BackupRotation::new(dir.path().join("backups"));
+    (db, rotation, dir)
+}
+
+fn crash_count(db: &Database) -> i64 {
+    db.connection().query_row("SELECT COUNT(*) FROM crash_logs", [], |r| r.get(0)).unwrap()
+}
+
+fn insert_crash(db: &Database, id: &str) {
+    db.connection()
+        .execute("INSERT INTO crash_logs (id, error_type, timestamp) VALUES (?1, 'panic', 1)", [id])
+        .unwrap();
+}
+
+#[test]
+fn test_backup_to_copies_the_database() {
+    let (db, _rotation, dir) = setup();
+    insert_crash(&db, "c1");
+    let copy = dir.path().join("copy.db");
+    db.backup_to(&copy).unwrap();
+
+    let restored = Database::open(&copy).unwrap();
+    assert_eq!(crash_count(&restored), 1);
+}
+
+#[test]
+fn test_rotation_keeps_newest_backups() {
+    let (db, rotation, _dir) = setup();
+    let rotation = rotation.with_keep(2);
+    for i in 0..4 {
+        rotation.backup_now(&db, 1_700_000_000_000 + i * 1000).unwrap();
+    }
+    let backups = rotation.list().unwrap();
+    assert_eq!(backups.len(), 2);
+    assert_eq!(backups[0].name, "gitbrowser-1700000003000.db");
+    assert_eq!(backups[0].created_at, 1_700_000_003);
+    assert_eq!(backups[1].name, "gitbrowser-1700000002000.db");
+    assert!(backups[0].size > 0);
+}
+
+#[test]
+fn test_backups_in_the_same_millisecond_get_distinct_names() {
+    let (db, rotation, _dir) = setup();
+    let first = rotation.backup_now(&db, 1000).unwrap();
+    let second = rotation.backup_now(&db, 1000).unwrap();
+    assert_ne!(first.name, second.name);
+    assert_eq!(rotation.list().unwrap()[0].name, second.name);
+}
+
+#[test]
+fn test_backup_is_due_after_interval() {
+    let (db, rotation, _dir) = setup();
+    let rotation = rotation.with_interval(Duration::from_secs(60));
+    assert!(rotation.is_due(0).unwrap());
+    assert!(rotation.run_if_due(&db, 100_000).unwrap().is_some());
+    assert!(!rotation.is_due(130).unwrap());
+    assert!(rotation.run_if_due(&db, 130_000).unwrap().is_none());
+    assert!(rotation.is_due(160).unwrap());
+}
+
+#[test]
+fn test_restore_replaces_contents_and_keeps_a_safety_backup() {
+    let (db, rotation, _dir) = setup();
+    let rotation = rotation.with_keep(1);
+    insert_crash(&db, "c1");
+    let before = rotation.backup_now(&db, 1000).unwrap();
+    insert_crash(&db, "c2");
+    assert_eq!(crash_count(&db), 2);
+
+    // The restored backup is the oldest beyond `keep`, yet still restorable
+    let safety = rotation.restore(&db, &before.name, 2000).unwrap();
+    assert_eq!(crash_count(&db), 1);
+    assert_eq!(rotation.list().unwrap(), vec![safety.clone()]);
+
+    rotation.restore(&db, &safety.name, 3000).unwrap();
+    assert_eq!(crash_count(&db), 2);
+}
+
+#[test]
+fn test_restore_only_accepts_listed_backups() {
+    let (db, rotation, _dir) = setup();
+    rotation.backup_now(&db, 1000).unwrap();
+    for name in ["../gitbrowser.db", "gitbrowser-9.db", "/etc/passwd"] {
+        assert!(matches!(rotation.restore(&db, name, 2000), Err(BackupError::NotFound(_))));
+    }
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+#[test]
+fn test_backups_that_cannot_be_rekeyed_are_deleted() {
+    use gitbrowser::database::encryption::{DatabaseKey, KEY_LENGTH};
+
+    let (db, rotation, _dir) = setup();
+    rotation.backup_now(&db, 1000).unwrap();
+    // Without SQLCipher a plaintext backup can never be keyed, so none is left behind
+    let key = DatabaseKey::from_bytes(vec![7; KEY_LENGTH]).unwrap();
+    rotation.rekey(None, Some(&key)).unwrap();
+    assert!(rotation.list().unwrap().is_empty());
+}
+
+#[cfg(feature = "sqlcipher")]
+#[test]
+fn test_backups_follow_database_encryption() {
+    use gitbrowser::database::encryption::{is_plaintext, open, prepare_with_backups};
+    use gitbrowser::types::settings::DatabaseEncryption;
+
+    let (db, rotation, dir) = setup();
+    let path = dir.path().join("gitbrowser.db");
+    insert_crash(&db, "c1");
+    let before = rotation.backup_now(&db, 1000).unwrap();
+    db.checkpoint().unwrap();
+    drop(db);
+
+    let key = prepare_with_backups(&path, Some(&rotation), DatabaseEncryption::MasterPassword, Some("hunter2"))
+        .unwrap();
+    assert!(!is_plaintext(&before.path));
+    let db = open(path.to_str().unwrap(), key.as_ref()).unwrap();
+    insert_crash(&db, "c2");
+    rotation.restore(&db, &before.name, 2000).unwrap();
+    assert_eq!(crash_count(&db), 1);
+    drop(db);
+
+    // Turning encryption off again leaves plaintext backups the plain database restores
+    let key = prepare_with_backups(&path, Some(&rotation), DatabaseEncryption::Off, Some("hunter2")).unwrap();
+    assert!(key.is_none());
+    assert!(rotation.list().unwrap().iter().all(|b| is_plaintext(&b.path)));
+    let db = open(path.to_str().unwrap(), None).unwrap();
+    rotation.restore(&db, &before.name, 3000).unwrap();
+    assert_eq!(crash_count(&db), 1);
+}
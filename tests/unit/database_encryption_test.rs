@@ -0,0 +1,101 @@
+//! Unit tests for at-rest database encryption (key files and migrations).
+
+use gitbrowser::database::encryption::{
+    current_encryption, derive_password_key, is_plaintext, key_file_path, prepare, DatabaseKey, KEY_LENGTH,
+};
+use gitbrowser::database::Database;
+#[cfg(feature = "sqlcipher")]
+use gitbrowser::database::encryption::open;
+use gitbrowser::types::errors::EncryptionError;
+use gitbrowser::types::settings::DatabaseEncryption;
+use tempfile::TempDir;
+
+fn plaintext_db(dir: &TempDir) -> std::path::PathBuf {
+    let path = dir.path().join("gitbrowser.db");
+    let db = Database::open(&path).unwrap();
+    db.connection()
+        .execute("INSERT INTO crash_logs (id, error_type, timestamp) VALUES ('c1', 'panic', 1)", [])
+        .unwrap();
+    db.checkpoint().unwrap();
+    path
+}
+
+#[test]
+fn test_plaintext_database_is_detected() {
+    let dir = TempDir::new().unwrap();
+    assert!(is_plaintext(&dir.path().join("missing.db")));
+    let path = plaintext_db(&dir);
+    assert!(is_plaintext(&path));
+
+    std::fs::write(dir.path().join("random.db"), [0x5a; 64]).unwrap();
+    assert!(!is_plaintext(&dir.path().join("random.db")));
+}
+
+#[test]
+fn test_no_key_file_means_unencrypted() {
+    let dir = TempDir::new().unwrap();
+    let path = plaintext_db(&dir);
+    assert_eq!(current_encryption(&path).unwrap(), DatabaseEncryption::Off);
+    assert!(prepare(&path, DatabaseEncryption::Off, None).unwrap().is_none());
+    assert!(!key_file_path(&path).exists());
+}
+
+#[test]
+fn test_in_memory_database_is_never_encrypted() {
+    let key = prepare(std::path::Path::new(":memory:"), DatabaseEncryption::Keystore, None).unwrap();
+    assert!(key.is_none());
+}
+
+#[test]
+fn test_keys_must_be_256_bits() {
+    assert!(derive_password_key("hunter2", b"salt-one-16bytes").is_ok());
+    assert!(matches!(DatabaseKey::from_bytes(vec![1; 16]), Err(EncryptionError::KeystoreError(_))));
+    assert!(DatabaseKey::from_bytes(vec![1; KEY_LENGTH]).is_ok());
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+#[test]
+fn test_without_sqlcipher_database_stays_unencrypted() {
+    let dir = TempDir::new().unwrap();
+    let path = plaintext_db(&dir);
+    let key = prepare(&path, DatabaseEncryption::MasterPassword, Some("hunter2")).unwrap();
+    assert!(key.is_none());
+    assert!(is_plaintext(&path));
+    assert_eq!(current_encryption(&path).unwrap(), DatabaseEncryption::Off);
+}
+
+#[cfg(feature = "sqlcipher")]
+#[test]
+fn test_master_password_migration_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let path = plaintext_db(&dir);
+    let path_str = path.to_str().unwrap();
+
+    assert!(matches!(
+        prepare(&path, DatabaseEncryption::MasterPassword, None),
+        Err(EncryptionError::PasswordRequired)
+    ));
+    let key = prepare(&path, DatabaseEncryption::MasterPassword, Some("hunter2")).unwrap();
+    assert!(key.is_some());
+    assert!(!is_plaintext(&path));
+    assert_eq!(current_encryption(&path).unwrap(), DatabaseEncryption::MasterPassword);
+
+    // Existing rows survive the migration
+    let db = open(path_str, key.as_ref()).unwrap();
+    let count: i64 = db.connection().query_row("SELECT COUNT(*) FROM crash_logs", [], |r| r.get(0)).unwrap();
+    assert_eq!(count, 1);
+    drop(db);
+
+    // A wrong password does not open it
+    let wrong = prepare(&path, DatabaseEncryption::MasterPassword, Some("wrong")).unwrap();
+    assert!(matches!(open(path_str, wrong.as_ref()), Err(EncryptionError::WrongKey)));
+    assert!(matches!(open(path_str, None), Err(EncryptionError::WrongKey)));
+
+    // Turning it off decrypts the file again
+    assert!(prepare(&path, DatabaseEncryption::Off, Some("hunter2")).unwrap().is_none());
+    assert!(is_plaintext(&path));
+    assert!(!key_file_path(&path).exists());
+    let db = open(path_str, None).unwrap();
+    let count: i64 = db.connection().query_row("SELECT COUNT(*) FROM crash_logs", [], |r| r.get(0)).unwrap();
+    assert_eq!(count, 1);
+}
@@ -0,0 +1,71 @@
+//! Unit tests for scheduled database maintenance and size reporting.
+
+use std::time::Duration;
+
+use gitbrowser::database::maintenance;
+use gitbrowser::database::Database;
+use tempfile::TempDir;
+
+fn setup() -> (Database, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("gitbrowser.db")).unwrap();
+    (db, dir)
+}
+
+fn fill_and_empty(db: &Database) {
+    let conn = db.connection();
+    for i in 0..500 {
+        conn.execute(
+            "INSERT INTO history (id, url, title, visit_time) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![i.to_string(), format!("https://example.com/{}", i), "x".repeat(500), i],
+        )
+        .unwrap();
+    }
+    conn.execute("DELETE FROM history", []).unwrap();
+}
+
+#[test]
+fn test_new_database_uses_incremental_vacuum() {
+    let (db, _dir) = setup();
+    let stats = maintenance::stats(db.connection()).unwrap();
+    assert!(stats.incremental_vacuum);
+    assert_eq!(stats.size, stats.page_size * stats.page_count);
+}
+
+#[test]
+fn test_run_reclaims_free_pages_and_records_the_run() {
+    let (db, _dir) = setup();
+    fill_and_empty(&db);
+    assert!(maintenance::stats(db.connection()).unwrap().free > 0);
+
+    let report = maintenance::run(db.connection(), 1_000).unwrap();
+    assert!(report.integrity_errors.is_empty());
+    assert!(report.reclaimed > 0);
+    assert_eq!(maintenance::stats(db.connection()).unwrap().free, 0);
+    assert_eq!(maintenance::last_run(db.connection()).unwrap(), Some(report));
+}
+
+#[test]
+fn test_run_if_due_honours_the_interval() {
+    let (db, _dir) = setup();
+    let day = Duration::from_secs(24 * 60 * 60);
+    assert!(maintenance::run_if_due(db.connection(), 1_000, day).unwrap().is_some());
+    assert!(maintenance::run_if_due(db.connection(), 2_000, day).unwrap().is_none());
+    assert!(maintenance::run_if_due(db.connection(), 1_000 + 86_400, day).unwrap().is_some());
+}
+
+#[test]
+fn test_compact_switches_old_databases_to_incremental_vacuum() {
+    let (db, _dir) = setup();
+    db.connection().execute_batch("PRAGMA auto_vacuum = NONE; VACUUM;").unwrap();
+    fill_and_empty(&db);
+    let before = maintenance::stats(db.connection()).unwrap();
+    assert!(!before.incremental_vacuum);
+    assert!(before.free > 0);
+
+    let after = maintenance::compact(db.connection()).unwrap();
+    assert!(after.incremental_vacuum);
+    assert_eq!(after.free, 0);
+    assert!(after.size < before.size);
+    assert_eq!(after.wal_size, 0);
+}
@@ -26,6 +26,17 @@ fn test_migrations_create_all_tables() {
         "sessions",
         "github_auth",
         "github_sync",
+        "reading_list",
+        "annotations",
+        "site_settings",
+        "extension_storage",
+        "sync_state",
+        "sync_device",
+        "github_notifications",
+        "github_repos",
+        "sync_conflicts",
+        "maintenance_runs",
+        "favicons",
     ];
 
     for table in &expected_tables {
@@ -92,6 +103,31 @@ fn test_open_file_database() {
     let _ = std::fs::remove_dir(&dir);
 }
 
+#[test]
+fn test_file_database_uses_wal_with_busy_timeout() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("wal.db")).expect("open failed");
+    let conn = db.connection();
+
+    let mode: String = conn.query_row("PRAGMA journal_mode", [], |r| r.get(0)).unwrap();
+    assert_eq!(mode, "wal");
+    let synchronous: i64 = conn.query_row("PRAGMA synchronous", [], |r| r.get(0)).unwrap();
+    assert_eq!(synchronous, 1, "synchronous should be NORMAL");
+    let timeout: i64 = conn.query_row("PRAGMA busy_timeout", [], |r| r.get(0)).unwrap();
+    assert_eq!(timeout, 5000);
+
+    conn.execute("INSERT INTO crash_logs (id, error_type, timestamp) VALUES ('c1', 'panic', 1)", []).unwrap();
+    db.checkpoint().expect("checkpoint failed");
+    let wal_len = std::fs::metadata(dir.path().join("wal.db-wal")).map(|m| m.len()).unwrap_or(0);
+    assert_eq!(wal_len, 0, "checkpoint should truncate the WAL");
+}
+
+#[test]
+fn test_checkpoint_in_memory_is_noop() {
+    let db = Database::open_in_memory().expect("open_in_memory failed");
+    assert!(db.checkpoint().is_ok());
+}
+
 #[test]
 fn test_bookmarks_table_schema() {
     let db = Database::open_in_memory().expect("open_in_memory failed");
@@ -229,6 +265,8 @@ fn test_downloads_table_schema() {
         .expect("Should query downloads");
 
     assert_eq!(status, "in_progress");
+    conn.execute("UPDATE downloads SET referrer = 'https://example.com/' WHERE id = 'd-1'", [])
+        .expect("Should record the referrer");
 }
 
 #[test]
@@ -289,6 +327,17 @@ fn test_crash_logs_table_schema() {
         [],
     )
     .expect("Should insert into crash_logs");
+
+    conn.execute(
+        "INSERT INTO crash_logs (id, error_type, timestamp, backtrace, os, app_version)
+         VALUES ('crash-2', 'panic', 1700000001, '0: main', 'linux x86_64', '0.3.0')",
+        [],
+    )
+    .expect("Should insert crash context");
+    let recovered: i64 = conn
+        .query_row("SELECT recovered FROM crash_logs WHERE id = 'crash-2'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(recovered, 0);
 }
 
 #[test]
@@ -329,3 +378,51 @@ fn test_github_sync_table_schema() {
     )
     .expect("Should insert into github_sync");
 }
+
+#[test]
+fn test_migration_registry_is_contiguous() {
+    use gitbrowser::database::migrations::{CURRENT_SCHEMA_VERSION, MIGRATIONS};
+    for (i, m) in MIGRATIONS.iter().enumerate() {
+        assert_eq!(m.version, i as i32 + 1, "migration '{}' is out of sequence", m.description);
+    }
+    assert_eq!(CURRENT_SCHEMA_VERSION, MIGRATIONS.last().unwrap().version);
+}
+
+#[test]
+fn test_newer_schema_is_refused() {
+    use gitbrowser::database::migrations;
+    let db = Database::open_in_memory().expect("open_in_memory failed");
+    db.connection()
+        .execute("INSERT INTO schema_version (version, applied_at, description) VALUES (99, 0, 'future')", [])
+        .unwrap();
+    assert!(migrations::run_all(db.connection()).is_err(), "a newer schema must not be migrated");
+}
+
+fn create_then_fail(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch("CREATE TABLE half_done (id INTEGER)")?;
+    conn.execute_batch("THIS IS NOT SQL")
+}
+
+#[test]
+fn test_failing_migration_rolls_back() {
+    use gitbrowser::database::migrations::{self, Migration, CURRENT_SCHEMA_VERSION, MIGRATIONS};
+    let db = Database::open_in_memory().expect("open_in_memory failed");
+    let mut steps: Vec<Migration> = MIGRATIONS.to_vec();
+    steps.push(Migration { version: CURRENT_SCHEMA_VERSION + 1, description: "broken", up: create_then_fail });
+
+    assert!(migrations::apply(db.connection(), &steps).is_err());
+    assert_eq!(migrations::get_schema_version(db.connection()), CURRENT_SCHEMA_VERSION);
+    let exists: bool = db
+        .connection()
+        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name='half_done'")
+        .and_then(|mut s| s.exists([]))
+        .unwrap();
+    assert!(!exists, "a failed step must leave no trace");
+}
+
+#[test]
+fn test_foreign_keys_enabled() {
+    let db = Database::open_in_memory().expect("open_in_memory failed");
+    let on: i32 = db.connection().query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+    assert_eq!(on, 1);
+}
@@ -0,0 +1,69 @@
+//! Unit tests for the database worker thread.
+
+use std::time::Duration;
+
+use gitbrowser::database::{Database, DbWorker};
+use gitbrowser::types::errors::DbWorkerError;
+use tempfile::TempDir;
+
+fn setup() -> (Database, DbWorker, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("worker.db");
+    let db = Database::open(&path).unwrap();
+    let worker = DbWorker::spawn(path.to_str().unwrap(), None).unwrap();
+    (db, worker, dir)
+}
+
+#[test]
+fn test_worker_reads_what_the_main_connection_wrote() {
+    let (db, worker, _dir) = setup();
+    db.connection()
+        .execute("INSERT INTO crash_logs (id, error_type, timestamp) VALUES ('c1', 'panic', 1)", [])
+        .unwrap();
+    let count = worker
+        .run(|conn| conn.query_row("SELECT COUNT(*) FROM crash_logs", [], |r| r.get::<_, i64>(0)))
+        .wait()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn test_jobs_run_in_order() {
+    let (db, worker, _dir) = setup();
+    let first = worker.run(|conn| {
+        conn.execute("INSERT INTO crash_logs (id, error_type, timestamp) VALUES ('w1', 'hang', 2)", [])
+    });
+    let second = worker.run(|conn| conn.query_row("SELECT COUNT(*) FROM crash_logs", [], |r| r.get::<_, i64>(0)));
+    assert_eq!(first.wait().unwrap().unwrap(), 1);
+    assert_eq!(second.wait().unwrap().unwrap(), 1);
+
+    // Writes from the worker are visible to the main connection too
+    let count: i64 = db.connection().query_row("SELECT COUNT(*) FROM crash_logs", [], |r| r.get(0)).unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn test_task_can_be_awaited() {
+    let (_db, worker, _dir) = setup();
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let value = runtime.block_on(worker.run(|_| 42)).unwrap();
+    assert_eq!(value, 42);
+}
+
+#[test]
+fn test_panicking_job_does_not_stop_the_worker() {
+    let (_db, worker, _dir) = setup();
+    let failed = worker.run(|_| -> i32 { panic!("job failed") }).wait();
+    assert!(matches!(failed, Err(DbWorkerError::Stopped)));
+    assert_eq!(worker.run(|_| 7).wait().unwrap(), 7);
+}
+
+#[test]
+fn test_wait_timeout_gives_up_on_slow_job() {
+    let (_db, worker, _dir) = setup();
+    let slow = worker.run(|_| std::thread::sleep(Duration::from_millis(300)));
+    assert!(matches!(slow.wait_timeout(Duration::from_millis(20)), Err(DbWorkerError::TimedOut)));
+    // The slow job still runs first; later jobs answer once it is done
+    assert_eq!(worker.run(|_| 3).wait_timeout(Duration::from_secs(5)).unwrap(), 3);
+}
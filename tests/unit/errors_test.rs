@@ -50,6 +50,10 @@ fn crypto_error_display_variants() {
         CryptoError::InvalidKey("wrong length".to_string()).to_string(),
         "Invalid key: wrong length"
     );
+    assert_eq!(
+        CryptoError::CredentialNotFound("c1".to_string()).to_string(),
+        "Credential not found: c1"
+    );
 }
 
 // === BookmarkError Tests ===
@@ -88,6 +92,64 @@ fn history_error_display_variants() {
     );
 }
 
+// === ReadingListError Tests ===
+
+#[test]
+fn reading_list_error_display_variants() {
+    assert_eq!(
+        ReadingListError::NotFound("rl-1".to_string()).to_string(),
+        "Reading list item not found: rl-1"
+    );
+    assert_eq!(
+        ReadingListError::DatabaseError("disk full".to_string()).to_string(),
+        "Reading list database error: disk full"
+    );
+}
+
+// === AnnotationError Tests ===
+
+#[test]
+fn annotation_error_display_variants() {
+    assert_eq!(
+        AnnotationError::NotFound("an-1".to_string()).to_string(),
+        "Annotation not found: an-1"
+    );
+    assert_eq!(
+        AnnotationError::InvalidAnchor("empty".to_string()).to_string(),
+        "Invalid annotation anchor: empty"
+    );
+    assert_eq!(
+        AnnotationError::InvalidColor("red".to_string()).to_string(),
+        "Invalid annotation color: red"
+    );
+    assert_eq!(
+        AnnotationError::DatabaseError("disk full".to_string()).to_string(),
+        "Annotation database error: disk full"
+    );
+}
+
+// === SiteSettingsError Tests ===
+
+#[test]
+fn site_settings_error_display_variants() {
+    assert_eq!(
+        SiteSettingsError::InvalidSite("a b".to_string()).to_string(),
+        "Invalid site: a b"
+    );
+    assert_eq!(
+        SiteSettingsError::InvalidKey("Bad-Key".to_string()).to_string(),
+        "Invalid site setting key: Bad-Key"
+    );
+    assert_eq!(
+        SiteSettingsError::InvalidValue("user_agent must be a string".to_string()).to_string(),
+        "Invalid site setting value: user_agent must be a string"
+    );
+    assert_eq!(
+        SiteSettingsError::DatabaseError("locked".to_string()).to_string(),
+        "Site settings database error: locked"
+    );
+}
+
 // === DownloadError Tests ===
 
 #[test]
@@ -184,6 +246,14 @@ fn settings_error_display_variants() {
         SettingsError::InvalidValue("negative number".to_string()).to_string(),
         "Invalid settings value: negative number"
     );
+    assert_eq!(
+        SettingsError::ProfileNotFound("Work".to_string()).to_string(),
+        "Settings profile not found: Work"
+    );
+    assert_eq!(
+        SettingsError::ProfileExists("Work".to_string()).to_string(),
+        "Settings profile already exists: Work"
+    );
 }
 
 // === AIError Tests ===
@@ -207,6 +277,7 @@ fn ai_error_display_variants() {
         AIError::ProviderError("internal server error".to_string()).to_string(),
         "AI provider error: internal server error"
     );
+    assert_eq!(AIError::NoAnswer.to_string(), "The assistant did not answer in time");
 }
 
 // === PrivacyError Tests ===
@@ -247,6 +318,18 @@ fn extension_error_display_variants() {
         ExtensionError::LoadError("file corrupt".to_string()).to_string(),
         "Extension load error: file corrupt"
     );
+    assert_eq!(
+        ExtensionError::InvalidMessage("inbox full".to_string()).to_string(),
+        "Extension message rejected: inbox full"
+    );
+    assert_eq!(
+        ExtensionError::QuotaExceeded("6 MB used".to_string()).to_string(),
+        "Extension storage quota exceeded: 6 MB used"
+    );
+    assert_eq!(
+        ExtensionError::InvalidArchive("bad crx".to_string()).to_string(),
+        "Invalid extension archive: bad crx"
+    );
 }
 
 // === ReaderError Tests ===
@@ -272,6 +355,18 @@ fn theme_error_display_variants() {
         ThemeError::CssError("unexpected token".to_string()).to_string(),
         "CSS error: unexpected token"
     );
+    assert_eq!(
+        ThemeError::InvalidTheme("unknown variable '--x'".to_string()).to_string(),
+        "Invalid theme: unknown variable '--x'"
+    );
+    assert_eq!(
+        ThemeError::NotFound("nord".to_string()).to_string(),
+        "Theme not found: nord"
+    );
+    assert_eq!(
+        ThemeError::IoError("denied".to_string()).to_string(),
+        "Theme I/O error: denied"
+    );
 }
 
 // === LocaleError Tests ===
@@ -290,6 +385,10 @@ fn locale_error_display_variants() {
         LocaleError::FileNotFound("locales/fr.json".to_string()).to_string(),
         "Locale file not found: locales/fr.json"
     );
+    assert_eq!(
+        LocaleError::InvalidMessage("missing 'other' case".to_string()).to_string(),
+        "Invalid locale message: missing 'other' case"
+    );
 }
 
 // === CrashError Tests ===
@@ -304,6 +403,18 @@ fn crash_error_display_variants() {
         CrashError::RecoveryFailed("session corrupt".to_string()).to_string(),
         "Crash recovery failed: session corrupt"
     );
+    assert_eq!(
+        CrashError::ReportFailed("disk full".to_string()).to_string(),
+        "Crash report error: disk full"
+    );
+    assert_eq!(
+        CrashError::ConsentRequired.to_string(),
+        "Crash reports are only sent with consent (privacy.telemetry_consent)"
+    );
+    assert_eq!(
+        CrashError::UploadFailed("HTTP 500".to_string()).to_string(),
+        "Crash report upload failed: HTTP 500"
+    );
 }
 
 // === UpdateError Tests ===
@@ -318,6 +429,10 @@ fn update_error_display_variants() {
         UpdateError::ChecksumMismatch("expected abc, got def".to_string()).to_string(),
         "Update checksum mismatch: expected abc, got def"
     );
+    assert_eq!(
+        UpdateError::SignatureInvalid("signed with a different key".to_string()).to_string(),
+        "Update signature verification failed: signed with a different key"
+    );
     assert_eq!(
         UpdateError::InstallFailed("permission denied".to_string()).to_string(),
         "Update installation failed: permission denied"
@@ -328,6 +443,40 @@ fn update_error_display_variants() {
     );
 }
 
+// === SearchEngineError Tests ===
+
+#[test]
+fn search_engine_error_display_variants() {
+    assert_eq!(
+        SearchEngineError::NotFound("ddg".to_string()).to_string(),
+        "Search engine not found: ddg"
+    );
+    assert_eq!(
+        SearchEngineError::AlreadyExists("ddg".to_string()).to_string(),
+        "Search engine already exists: ddg"
+    );
+    assert_eq!(
+        SearchEngineError::InvalidId("a b".to_string()).to_string(),
+        "Invalid search engine ID: a b"
+    );
+    assert_eq!(
+        SearchEngineError::InvalidName("ddg".to_string()).to_string(),
+        "Search engine ddg must have a name"
+    );
+    assert_eq!(
+        SearchEngineError::InvalidTemplate("no placeholder".to_string()).to_string(),
+        "Invalid search URL template: no placeholder"
+    );
+    assert_eq!(
+        SearchEngineError::InvalidKeyword("g g".to_string()).to_string(),
+        "Invalid search keyword: g g"
+    );
+    assert_eq!(
+        SearchEngineError::LastEngine.to_string(),
+        "Cannot remove the last search engine"
+    );
+}
+
 // === GitHubError Tests ===
 
 #[test]
@@ -352,6 +501,132 @@ fn github_error_display_variants() {
         GitHubError::NotAuthenticated.to_string(),
         "Not authenticated with GitHub"
     );
+    assert_eq!(
+        GitHubError::DeviceCodeExpired.to_string(),
+        "GitHub device code expired"
+    );
+    assert_eq!(
+        GitHubError::RateLimited(42).to_string(),
+        "GitHub rate limit exceeded, retry in 42s"
+    );
+    assert_eq!(
+        GitHubError::InvalidConfig("no web URL".to_string()).to_string(),
+        "Invalid GitHub configuration: no web URL"
+    );
+}
+
+// === SyncError Tests ===
+
+#[test]
+fn sync_error_display_variants() {
+    assert_eq!(SyncError::NotAuthenticated.to_string(), "Sync requires a GitHub login");
+    assert_eq!(
+        SyncError::DatabaseError("locked".to_string()).to_string(),
+        "Sync database error: locked"
+    );
+    assert_eq!(
+        SyncError::InvalidPayload("bad json".to_string()).to_string(),
+        "Invalid sync payload: bad json"
+    );
+    assert_eq!(
+        SyncError::RemoteError("HTTP 500".to_string()).to_string(),
+        "Sync remote error: HTTP 500"
+    );
+    assert_eq!(
+        SyncError::ConflictNotFound("settings".to_string()).to_string(),
+        "No sync conflict for settings"
+    );
+    assert_eq!(SyncError::PassphraseRequired.to_string(), "Sync requires the sync passphrase");
+    assert_eq!(
+        SyncError::WrongPassphrase.to_string(),
+        "The sync passphrase does not match the synced data"
+    );
+    assert_eq!(SyncError::RateLimited(30).to_string(), "Sync is rate limited by GitHub, retry in 30s");
+}
+
+// === EncryptionError Tests ===
+
+#[test]
+fn encryption_error_display_variants() {
+    assert_eq!(
+        EncryptionError::Unsupported.to_string(),
+        "Database encryption is not supported by this build"
+    );
+    assert_eq!(
+        EncryptionError::PasswordRequired.to_string(),
+        "The database is locked with the master password"
+    );
+    assert_eq!(EncryptionError::WrongKey.to_string(), "Wrong database key");
+    assert_eq!(
+        EncryptionError::KeystoreError("no backend".to_string()).to_string(),
+        "Keystore error: no backend"
+    );
+    assert_eq!(
+        EncryptionError::MigrationFailed("disk full".to_string()).to_string(),
+        "Database encryption migration failed: disk full"
+    );
+    assert_eq!(
+        EncryptionError::DatabaseError("locked".to_string()).to_string(),
+        "Encrypted database error: locked"
+    );
+}
+
+// === KeystoreError Tests ===
+
+#[test]
+fn keystore_error_display_variants() {
+    assert_eq!(
+        KeystoreError::Unavailable.to_string(),
+        "The OS keystore is not supported by this build"
+    );
+    assert_eq!(
+        KeystoreError::AccessFailed("no session bus".to_string()).to_string(),
+        "OS keystore access failed: no session bus"
+    );
+    assert_eq!(
+        KeystoreError::InvalidSecret("data-key".to_string()).to_string(),
+        "Keystore entry data-key is not a valid secret"
+    );
+}
+
+// === DbWorkerError Tests ===
+
+#[test]
+fn db_worker_error_display() {
+    assert_eq!(
+        DbWorkerError::Stopped.to_string(),
+        "Database worker stopped before finishing the job"
+    );
+    assert_eq!(DbWorkerError::TimedOut.to_string(), "Database job did not finish in time");
+}
+
+// === BackupError Tests ===
+
+#[test]
+fn backup_error_display_variants() {
+    assert_eq!(
+        BackupError::NotFound("gitbrowser-1.db".to_string()).to_string(),
+        "Backup not found: gitbrowser-1.db"
+    );
+    assert_eq!(BackupError::IoError("denied".to_string()).to_string(), "Backup I/O error: denied");
+    assert_eq!(
+        BackupError::DatabaseError("busy".to_string()).to_string(),
+        "Backup database error: busy"
+    );
+}
+
+// === ProfileError Tests ===
+
+#[test]
+fn profile_error_display_variants() {
+    assert_eq!(ProfileError::InvalidName("a/b".to_string()).to_string(), "Invalid profile name: a/b");
+    assert_eq!(ProfileError::AlreadyExists("work".to_string()).to_string(), "Profile already exists: work");
+    assert_eq!(ProfileError::NotFound("work".to_string()).to_string(), "Profile not found: work");
+    assert_eq!(
+        ProfileError::InUse("work".to_string()).to_string(),
+        "Profile is open in another GitBrowser window: work"
+    );
+    assert_eq!(ProfileError::IoError("denied".to_string()).to_string(), "Profile I/O error: denied");
 }
 
 // === Cross-cutting: all errors implement std::error::Error ===
@@ -378,10 +653,16 @@ fn all_errors_implement_std_error() {
         Box::new(CrashError::DatabaseError("msg".to_string())),
         Box::new(UpdateError::NetworkError("msg".to_string())),
         Box::new(GitHubError::NotAuthenticated),
+        Box::new(SyncError::NotAuthenticated),
+        Box::new(EncryptionError::WrongKey),
+        Box::new(KeystoreError::Unavailable),
+        Box::new(DbWorkerError::Stopped),
+        Box::new(BackupError::NotFound("name".to_string())),
+        Box::new(ProfileError::NotFound("name".to_string())),
     ];
 
-    // All 18 error types should be present
-    assert_eq!(errors.len(), 18);
+    // All 24 error types should be present
+    assert_eq!(errors.len(), 24);
 
     // Each error should have a non-empty display string
     for err in &errors {
@@ -409,3 +690,42 @@ fn all_errors_implement_debug() {
     let debug_str = format!("{:?}", ReaderError::NotAnArticle);
     assert!(debug_str.contains("NotAnArticle"));
 }
+
+// === GitBrowserError Tests ===
+
+#[test]
+fn gitbrowser_error_keeps_module_messages() {
+    let err = GitBrowserError::from(TabError::NotFound("tab-123".to_string()));
+    assert_eq!(err.to_string(), "Tab not found: tab-123");
+    assert_eq!(err.code(), "tab.not_found");
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let err = GitBrowserError::from(EncryptionError::PasswordRequired);
+    assert_eq!(err.to_string(), EncryptionError::PasswordRequired.to_string());
+    assert_eq!(err.code(), "encryption.password_required");
+    assert_eq!(err.kind().as_str(), "locked");
+}
+
+#[test]
+fn gitbrowser_error_typed_variants() {
+    let err = GitBrowserError::MethodNotFound("nope".to_string());
+    assert_eq!(err.to_string(), "unknown method: nope");
+    assert_eq!(err.code(), "method_not_found");
+
+    let err = GitBrowserError::InvalidParams("missing url".to_string());
+    assert_eq!(err.code(), "invalid_params");
+    assert_eq!(err.to_string(), "missing url");
+
+    assert_eq!(GitBrowserError::RateLimited.kind(), ErrorKind::RateLimited);
+    assert_eq!(GitBrowserError::Unauthorized.code(), "unauthorized");
+    assert_eq!(GitBrowserError::TooLarge("response").to_string(), "response too large");
+
+    let lock = std::sync::Mutex::new(());
+    let _ = std::panic::catch_unwind(|| {
+        let _guard = lock.lock().unwrap();
+        panic!("poison");
+    });
+    let err = GitBrowserError::from(lock.lock().unwrap_err());
+    assert_eq!(err.code(), "poisoned");
+    assert_eq!(err.kind(), ErrorKind::Failed);
+}
@@ -0,0 +1,84 @@
+//! Unit tests for the EventBus and the events components emit on it.
+
+use std::sync::Arc;
+
+use gitbrowser::app::App;
+use gitbrowser::database::Database;
+use gitbrowser::managers::download_manager::{DownloadManager, DownloadManagerTrait};
+use gitbrowser::managers::profile_manager::ProfileManager;
+use gitbrowser::managers::tab_manager::{TabManager, TabManagerTrait};
+use gitbrowser::services::event_bus::{AppEvent, EventBus};
+use gitbrowser::services::password_manager::{PasswordManager, PasswordManagerTrait};
+use gitbrowser::services::settings_engine::SettingsEngineTrait;
+use serde_json::json;
+use tempfile::TempDir;
+
+#[test]
+fn test_every_subscriber_gets_events_in_order() {
+    let bus = EventBus::new();
+    let first = bus.subscribe();
+    let second = bus.subscribe();
+
+    bus.emit(AppEvent::VaultLocked);
+    bus.emit(AppEvent::TabChanged { action: "created".into(), tab_id: "t1".into() });
+
+    for rx in [first, second] {
+        let events: Vec<AppEvent> = rx.try_iter().collect();
+        assert_eq!(
+            events,
+            vec![AppEvent::VaultLocked, AppEvent::TabChanged { action: "created".into(), tab_id: "t1".into() }]
+        );
+    }
+}
+
+#[test]
+fn test_dropped_receiver_unsubscribes() {
+    let bus = EventBus::new();
+    let kept = bus.subscribe();
+    drop(bus.subscribe());
+    assert_eq!(bus.subscriber_count(), 2);
+
+    bus.emit(AppEvent::VaultLocked);
+    assert_eq!(bus.subscriber_count(), 1);
+    assert_eq!(kept.try_recv(), Ok(AppEvent::VaultLocked));
+}
+
+#[test]
+fn test_managers_emit_on_bus() {
+    let bus = EventBus::new();
+    let rx = bus.subscribe();
+
+    let mut tabs = TabManager::new().with_bus(bus.clone());
+    let tab_id = tabs.create_tab(Some("https://example.com"), true);
+    assert!(rx.try_iter().any(|e| e == AppEvent::TabChanged { action: "created".into(), tab_id: tab_id.clone() }));
+
+    let db = Arc::new(Database::open_in_memory().unwrap());
+    let mut downloads = DownloadManager::new(db.clone()).with_bus(bus.clone());
+    let id = downloads.start_download("https://example.com/a.zip", "/tmp/a.zip").unwrap();
+    downloads.update_progress(&id, 50, Some(100)).unwrap();
+    let progress = AppEvent::DownloadProgress { id, downloaded: 50, size: Some(100) };
+    assert!(rx.try_iter().any(|e| e == progress));
+
+    let mut vault = PasswordManager::new(db).with_bus(bus);
+    vault.unlock("master").unwrap();
+    assert!(rx.try_iter().next().is_none());
+    vault.lock();
+    assert_eq!(rx.try_recv(), Ok(AppEvent::VaultLocked));
+}
+
+#[test]
+fn test_app_emits_setting_and_theme_changes() {
+    let tmp = TempDir::new().unwrap();
+    let mut app = App::open_profile(&ProfileManager::new(tmp.path().to_path_buf()), "test", None).unwrap();
+    let rx = app.bus.subscribe();
+
+    // One of the two differs from whatever System resolved to
+    for mode in ["Dark", "Light"] {
+        app.settings_engine.set_value("appearance.theme", json!(mode)).unwrap();
+        app.process_settings_changes();
+    }
+
+    let events: Vec<AppEvent> = rx.try_iter().collect();
+    assert!(events.iter().any(|e| matches!(e, AppEvent::SettingChanged(c) if c.key == "appearance.theme")));
+    assert!(events.iter().any(|e| matches!(e, AppEvent::ThemeChanged { .. })));
+}
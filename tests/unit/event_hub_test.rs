@@ -0,0 +1,114 @@
+//! Unit tests for the EventHub and the events managers publish to it.
+
+use std::sync::{Arc, Mutex};
+
+use gitbrowser::database::Database;
+use gitbrowser::managers::download_manager::{DownloadManager, DownloadManagerTrait};
+use gitbrowser::managers::permission_manager::{PermissionManager, PermissionManagerTrait};
+use gitbrowser::services::event_hub::{Deliver, EventHub, EventTopic};
+use gitbrowser::types::download::DownloadStatus;
+use gitbrowser::types::permission::{PermissionType, PermissionValue};
+use serde_json::{json, Value};
+
+/// A `Deliver` that keeps what it is given.
+fn collector() -> (Deliver, Arc<Mutex<Vec<Value>>>) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    (Arc::new(move |value: &Value| sink.lock().unwrap().push(value.clone())), received)
+}
+
+#[test]
+fn test_topic_names_round_trip() {
+    for topic in EventTopic::ALL {
+        assert_eq!(EventTopic::parse(topic.as_str()), Some(topic));
+    }
+    assert_eq!(EventTopic::parse("weather"), None);
+}
+
+#[test]
+fn test_only_subscribed_topics_are_delivered() {
+    let hub = EventHub::new();
+    let (deliver, received) = collector();
+    let id = hub.subscribe(1, &[EventTopic::Tabs], deliver);
+
+    hub.publish(EventTopic::Downloads, json!({"ignored": true}));
+    hub.publish(EventTopic::Tabs, json!({"action": "created"}));
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(
+        received[0],
+        json!({
+            "jsonrpc": "2.0",
+            "method": "event",
+            "params": {"subscription": id, "topic": "tabs", "data": {"action": "created"}},
+        })
+    );
+}
+
+#[test]
+fn test_unsubscribe_needs_the_owning_client() {
+    let hub = EventHub::new();
+    let (deliver, received) = collector();
+    let id = hub.subscribe(1, &EventTopic::ALL, deliver);
+
+    assert!(!hub.unsubscribe(2, id));
+    assert!(hub.unsubscribe(1, id));
+    assert!(!hub.unsubscribe(1, id));
+
+    hub.publish(EventTopic::Sync, json!({}));
+    assert!(received.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_remove_client_ends_its_subscriptions() {
+    let hub = EventHub::new();
+    let (first, _) = collector();
+    let (second, _) = collector();
+    let (other, _) = collector();
+    hub.subscribe(1, &[EventTopic::Tabs], first);
+    hub.subscribe(1, &[EventTopic::Sync], second);
+    hub.subscribe(2, &[EventTopic::Tabs], other);
+
+    hub.remove_client(1);
+    assert_eq!(hub.subscription_count(), 1);
+}
+
+#[test]
+fn test_download_progress_is_published() {
+    let db = Arc::new(Database::open_in_memory().unwrap());
+    let hub = EventHub::new();
+    let (deliver, received) = collector();
+    hub.subscribe(1, &[EventTopic::Downloads], deliver);
+    let mut mgr = DownloadManager::new(db).with_events(hub);
+
+    let id = mgr.start_download("https://example.com/a.zip", "/tmp/a.zip").unwrap();
+    mgr.update_progress(&id, 50, Some(100)).unwrap();
+    assert!(matches!(mgr.get_download(&id).unwrap().status, DownloadStatus::InProgress));
+    mgr.update_progress(&id, 100, None).unwrap();
+    assert!(matches!(mgr.get_download(&id).unwrap().status, DownloadStatus::Completed));
+    assert!(mgr.update_progress(&id, 100, None).is_err());
+
+    let received = received.lock().unwrap();
+    let last = &received.last().unwrap()["params"]["data"]["download"];
+    assert_eq!(last["downloaded"], 100);
+    assert_eq!(last["size"], 100);
+    assert!(received.len() >= 3);
+}
+
+#[test]
+fn test_permission_prompt_is_published_only_when_undecided() {
+    let db = Arc::new(Database::open_in_memory().unwrap());
+    let hub = EventHub::new();
+    let (deliver, received) = collector();
+    hub.subscribe(1, &[EventTopic::Permissions], deliver);
+    let mut mgr = PermissionManager::new(db).with_events(hub);
+
+    assert_eq!(mgr.request_permission("https://a.example", &PermissionType::Camera), PermissionValue::Ask);
+    assert_eq!(received.lock().unwrap().last().unwrap()["params"]["data"]["action"], "prompt");
+
+    mgr.set_permission("https://a.example", PermissionType::Camera, PermissionValue::Allow).unwrap();
+    let count = received.lock().unwrap().len();
+    assert_eq!(mgr.request_permission("https://a.example", &PermissionType::Camera), PermissionValue::Allow);
+    assert_eq!(received.lock().unwrap().len(), count);
+}
@@ -1,15 +1,24 @@
 //! Unit tests for the Extension Framework.
 //!
 //! Tests install, uninstall, enable/disable, content script URL matching,
-//! and path traversal protection.
+//! path traversal protection, permission confirmation at install time,
+//! .zip/.crx installs, runtime messaging, background pages, storage,
+//! blocking rule compilation, and options pages.
 //!
 //! Covers: TEST-03 from AUDIT.md Phase 3.
 
 use std::sync::Arc;
+use serde_json::json;
 use tempfile::TempDir;
 
 use gitbrowser::database::Database;
-use gitbrowser::services::extension_framework::{ExtensionFramework, ExtensionFrameworkTrait};
+use gitbrowser::services::extension_framework::{
+    background_page_html, content_script_injection, extension_page_id, options_url, BackgroundChange,
+    ExtensionFramework, ExtensionFrameworkTrait, MatchedContentScript,
+};
+use gitbrowser::types::errors::ExtensionError;
+use gitbrowser::types::extension::{ExtensionPermission, MessageSender};
+use gitbrowser::types::privacy::NetworkRuleAction;
 
 /// Create a temp extension directory with a valid manifest.json.
 fn create_test_extension(tmp: &TempDir, name: &str, content_scripts_json: &str) -> String {
@@ -318,3 +327,609 @@ fn test_has_permission_check() {
     assert!(!fw.has_permission("perm-check-ext", &gitbrowser::types::extension::ExtensionPermission::Network));
     assert!(!fw.has_permission("nonexistent", &gitbrowser::types::extension::ExtensionPermission::PageContent));
 }
+
+// ─── Runtime Messaging ───
+
+fn github_sender() -> MessageSender {
+    MessageSender::ContentScript { url: "https://github.com/gothtr".to_string(), request_id: Some(7) }
+}
+
+#[test]
+fn test_send_take_and_reply() {
+    let (mut fw, tmp) = setup();
+    let ext_path = create_test_extension(&tmp, "msg-ext", r#"[{"matches": ["*://github.com/*"], "js": []}]"#);
+    fw.install(&ext_path).unwrap();
+
+    let id = fw.send_message("msg-ext", github_sender(), json!({"type": "ping"})).unwrap();
+    let messages = fw.take_messages("msg-ext");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].id, id);
+    assert_eq!(messages[0].payload["type"], "ping");
+    assert!(fw.take_messages("msg-ext").is_empty());
+
+    let original = fw.reply("msg-ext", id, &json!({"type": "pong"})).unwrap();
+    assert_eq!(original.sender, github_sender());
+    // A message is answered only once
+    assert!(fw.reply("msg-ext", id, &json!(null)).is_err());
+}
+
+#[test]
+fn test_content_script_sender_must_match_patterns() {
+    let (mut fw, tmp) = setup();
+    let ext_path = create_test_extension(&tmp, "scoped-ext", r#"[{"matches": ["*://github.com/*"], "js": []}]"#);
+    fw.install(&ext_path).unwrap();
+
+    let other_page = MessageSender::ContentScript { url: "https://evil.example/".to_string(), request_id: None };
+    assert!(matches!(
+        fw.send_message("scoped-ext", other_page, json!({})),
+        Err(ExtensionError::PermissionDenied(_))
+    ));
+    // The background context is not tied to a page
+    assert!(fw.send_message("scoped-ext", MessageSender::Background, json!({})).is_ok());
+}
+
+#[test]
+fn test_messages_rejected_for_disabled_or_unknown_extensions() {
+    let (mut fw, tmp) = setup();
+    let ext_path = create_test_extension(&tmp, "off-ext", r#"[{"matches": ["<all_urls>"], "js": []}]"#);
+    fw.install(&ext_path).unwrap();
+    fw.send_message("off-ext", github_sender(), json!(1)).unwrap();
+
+    fw.disable("off-ext").unwrap();
+    assert!(fw.send_message("off-ext", github_sender(), json!(1)).is_err());
+    // Disabling drops queued messages
+    assert!(fw.take_messages("off-ext").is_empty());
+    assert!(matches!(
+        fw.send_message("missing", MessageSender::Background, json!(1)),
+        Err(ExtensionError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_reply_only_by_target_extension() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_test_extension(&tmp, "ext-a", "[]")).unwrap();
+    fw.install(&create_test_extension(&tmp, "ext-b", "[]")).unwrap();
+
+    let id = fw.send_message("ext-a", MessageSender::Background, json!("hi")).unwrap();
+    fw.take_messages("ext-a");
+    assert!(fw.reply("ext-b", id, &json!("spoofed")).is_err());
+    assert!(fw.reply("ext-a", id, &json!("ok")).is_ok());
+}
+
+#[test]
+fn test_oversized_message_rejected() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_test_extension(&tmp, "big-ext", "[]")).unwrap();
+    let big = "x".repeat(70 * 1024);
+    assert!(matches!(
+        fw.send_message("big-ext", MessageSender::Background, json!(big)),
+        Err(ExtensionError::InvalidMessage(_))
+    ));
+}
+
+#[test]
+fn test_content_script_injection_wraps_each_extension() {
+    let scripts = vec![MatchedContentScript {
+        extension_id: "wrap-ext".to_string(),
+        extension_name: "Wrap".to_string(),
+        js: vec!["chrome.runtime.sendMessage('hi');".to_string()],
+        css: vec!["body{color:red}".to_string()],
+        run_at: "document_idle".to_string(),
+    }];
+    let js = content_script_injection(&scripts);
+    assert!(js.contains("window.__gb_ext_api"));
+    assert!(js.contains("__gb_ext_api(\"wrap-ext\")"));
+    assert!(js.contains("chrome.runtime.sendMessage('hi');"));
+    assert!(js.contains("\"body{color:red}\""));
+}
+
+// ─── Background Pages ───
+
+/// Create an extension with a background script and the given permissions.
+fn create_background_extension(tmp: &TempDir, name: &str, permissions: &str) -> String {
+    let ext_dir = tmp.path().join(name);
+    std::fs::create_dir_all(&ext_dir).unwrap();
+    std::fs::write(ext_dir.join("bg.js"), "chrome.runtime.onMessage.addListener(function(){});").unwrap();
+    let manifest = format!(r#"{{
+        "id": "{name}",
+        "name": "{name}",
+        "version": "1.0.0",
+        "permissions": {permissions},
+        "background": "bg.js"
+    }}"#);
+    std::fs::write(ext_dir.join("manifest.json"), manifest).unwrap();
+    ext_dir.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_background_started_and_stopped_with_extension() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_background_extension(&tmp, "bg-ext", "[]")).unwrap();
+    fw.install(&create_test_extension(&tmp, "plain-ext", "[]")).unwrap();
+
+    let changes = fw.poll_background_changes();
+    assert_eq!(changes.len(), 1);
+    match &changes[0] {
+        BackgroundChange::Start(script) => {
+            assert_eq!(script.extension_id, "bg-ext");
+            assert!(script.source.contains("onMessage"));
+        }
+        other => panic!("expected start, got {:?}", other),
+    }
+    // Nothing changes until the extension does
+    assert!(fw.poll_background_changes().is_empty());
+
+    fw.disable("bg-ext").unwrap();
+    assert!(matches!(fw.poll_background_changes().as_slice(), [BackgroundChange::Stop(id)] if id == "bg-ext"));
+    fw.enable("bg-ext").unwrap();
+    assert!(matches!(fw.poll_background_changes().as_slice(), [BackgroundChange::Start(_)]));
+    fw.uninstall("bg-ext").unwrap();
+    assert!(matches!(fw.poll_background_changes().as_slice(), [BackgroundChange::Stop(_)]));
+}
+
+#[test]
+fn test_background_ready_only_after_load() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_background_extension(&tmp, "ready-ext", "[]")).unwrap();
+    fw.poll_background_changes();
+    assert!(fw.ready_backgrounds().is_empty());
+
+    fw.mark_background_ready("ready-ext");
+    assert_eq!(fw.ready_backgrounds(), vec!["ready-ext".to_string()]);
+
+    // A restarted page must report again
+    fw.disable("ready-ext").unwrap();
+    fw.poll_background_changes();
+    fw.enable("ready-ext").unwrap();
+    fw.poll_background_changes();
+    assert!(fw.ready_backgrounds().is_empty());
+}
+
+#[test]
+fn test_background_script_path_traversal_not_started() {
+    let (mut fw, tmp) = setup();
+    let ext_dir = tmp.path().join("escape-ext");
+    std::fs::create_dir_all(&ext_dir).unwrap();
+    std::fs::write(tmp.path().join("outside.js"), "// secret").unwrap();
+    let manifest = r#"{
+        "id": "escape-ext", "name": "Escape", "version": "1.0.0",
+        "permissions": [], "background": "../outside.js"
+    }"#;
+    std::fs::write(ext_dir.join("manifest.json"), manifest).unwrap();
+    fw.install(ext_dir.to_str().unwrap()).unwrap();
+
+    assert!(fw.poll_background_changes().is_empty());
+}
+
+#[test]
+fn test_background_page_html_escapes_script() {
+    let (mut fw, tmp) = setup();
+    let ext_dir = create_background_extension(&tmp, "html-ext", "[]");
+    std::fs::write(std::path::Path::new(&ext_dir).join("bg.js"), "var s = '</script><b>';").unwrap();
+    fw.install(&ext_dir).unwrap();
+
+    let Some(BackgroundChange::Start(script)) = fw.poll_background_changes().into_iter().next() else {
+        panic!("background not started");
+    };
+    let html = background_page_html(&script);
+    assert!(html.contains("__gb_ext_api(\"html-ext\")"));
+    assert!(html.contains("bg_ready"));
+    assert_eq!(html.matches("</script>").count(), 1);
+}
+
+// ─── Storage ───
+
+#[test]
+fn test_storage_set_get_remove() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_background_extension(&tmp, "store-ext", r#"["storage"]"#)).unwrap();
+
+    let items = json!({"count": 3, "theme": {"dark": true}});
+    fw.storage_set("store-ext", items.as_object().unwrap()).unwrap();
+    let all = fw.storage_get("store-ext", None).unwrap();
+    assert_eq!(all.get("count"), Some(&json!(3)));
+    assert_eq!(all.get("theme"), Some(&json!({"dark": true})));
+
+    let some = fw.storage_get("store-ext", Some(&["count".to_string(), "absent".to_string()])).unwrap();
+    assert_eq!(some.len(), 1);
+
+    fw.storage_set("store-ext", json!({"count": 4}).as_object().unwrap()).unwrap();
+    assert_eq!(fw.storage_get("store-ext", None).unwrap().get("count"), Some(&json!(4)));
+
+    fw.storage_remove("store-ext", &["count".to_string()]).unwrap();
+    let left = fw.storage_get("store-ext", None).unwrap();
+    assert!(left.get("count").is_none());
+    assert!(left.get("theme").is_some());
+}
+
+#[test]
+fn test_storage_requires_permission() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_background_extension(&tmp, "nostore-ext", "[]")).unwrap();
+    assert!(matches!(
+        fw.storage_get("nostore-ext", None),
+        Err(ExtensionError::PermissionDenied(_))
+    ));
+    assert!(fw.storage_set("nostore-ext", json!({"a": 1}).as_object().unwrap()).is_err());
+}
+
+#[test]
+fn test_storage_sender_scoped_to_content_script_pages() {
+    // chrome.storage from a page goes through check_sender with the webview's URL
+    let (mut fw, tmp) = setup();
+    let ext_path = create_test_extension(&tmp, "page-store-ext", r#"[{"matches": ["*://github.com/*"], "js": []}]"#);
+    fw.install(&ext_path).unwrap();
+
+    assert!(fw.check_sender("page-store-ext", &github_sender()).is_ok());
+    let other_page = MessageSender::ContentScript { url: "https://evil.example/".to_string(), request_id: Some(1) };
+    assert!(matches!(
+        fw.check_sender("page-store-ext", &other_page),
+        Err(ExtensionError::PermissionDenied(_))
+    ));
+}
+
+#[test]
+fn test_storage_quota_and_isolation() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_background_extension(&tmp, "quota-ext", r#"["storage"]"#)).unwrap();
+    fw.install(&create_background_extension(&tmp, "other-ext", r#"["storage"]"#)).unwrap();
+
+    let big = "x".repeat(6 * 1024 * 1024);
+    assert!(matches!(
+        fw.storage_set("quota-ext", json!({"big": big}).as_object().unwrap()),
+        Err(ExtensionError::QuotaExceeded(_))
+    ));
+
+    fw.storage_set("quota-ext", json!({"k": "mine"}).as_object().unwrap()).unwrap();
+    assert!(fw.storage_get("other-ext", None).unwrap().is_empty());
+
+    // Uninstalling drops the extension's data
+    fw.uninstall("quota-ext").unwrap();
+    fw.install(&create_background_extension(&tmp, "quota-ext", r#"["storage"]"#)).unwrap();
+    assert!(fw.storage_get("quota-ext", None).unwrap().is_empty());
+}
+
+// ─── Install Confirmation ───
+
+#[test]
+fn test_request_install_waits_for_confirmation() {
+    let (mut fw, tmp) = setup();
+    let ext_path = create_test_extension(&tmp, "pending-ext", "[]");
+
+    let pending = fw.request_install(&ext_path).unwrap();
+    assert_eq!(pending.extension_id, "pending-ext");
+    assert_eq!(pending.permissions, vec![ExtensionPermission::PageContent]);
+    assert!(fw.get_extension("pending-ext").is_none());
+
+    let id = fw.confirm_install(&pending.install_id, &[ExtensionPermission::PageContent]).unwrap();
+    assert_eq!(id, "pending-ext");
+    assert!(fw.get_extension("pending-ext").is_some());
+    // A handle is single-use
+    assert!(fw.confirm_install(&pending.install_id, &[ExtensionPermission::PageContent]).is_err());
+}
+
+#[test]
+fn test_confirm_install_rejects_partial_acknowledgement() {
+    let (mut fw, tmp) = setup();
+    let pending = fw.request_install(&create_test_extension(&tmp, "partial-ext", "[]")).unwrap();
+
+    assert!(matches!(
+        fw.confirm_install(&pending.install_id, &[]),
+        Err(ExtensionError::PermissionDenied(_))
+    ));
+    assert!(fw.get_extension("partial-ext").is_none());
+}
+
+#[test]
+fn test_confirm_install_rejects_changed_manifest() {
+    let (mut fw, tmp) = setup();
+    let ext_path = create_test_extension(&tmp, "swap-ext", "[]");
+    let pending = fw.request_install(&ext_path).unwrap();
+
+    let manifest = r#"{"id": "swap-ext", "name": "swap-ext", "version": "1.0.0", "permissions": ["pagecontent", "network"]}"#;
+    std::fs::write(std::path::Path::new(&ext_path).join("manifest.json"), manifest).unwrap();
+
+    assert!(matches!(
+        fw.confirm_install(&pending.install_id, &[ExtensionPermission::PageContent]),
+        Err(ExtensionError::InvalidManifest(_))
+    ));
+    assert!(fw.get_extension("swap-ext").is_none());
+}
+
+#[test]
+fn test_cancel_and_invalid_install_requests() {
+    let (mut fw, tmp) = setup();
+    let pending = fw.request_install(&create_test_extension(&tmp, "cancel-ext", "[]")).unwrap();
+    fw.cancel_install(&pending.install_id).unwrap();
+    assert!(fw.confirm_install(&pending.install_id, &[ExtensionPermission::PageContent]).is_err());
+
+    // Unlike install(), a prompt needs a readable manifest
+    let empty = tmp.path().join("no-manifest");
+    std::fs::create_dir_all(&empty).unwrap();
+    assert!(matches!(
+        fw.request_install(empty.to_str().unwrap()),
+        Err(ExtensionError::InvalidManifest(_))
+    ));
+}
+
+// ─── Archive Installs ───
+
+const ARCHIVE_MANIFEST: &str = r#"{"id": "zipped-ext", "name": "Zipped", "version": "2.0.0", "permissions": ["pagecontent"]}"#;
+
+/// Write a zip archive with the given (name, contents) entries.
+fn write_zip(path: &std::path::Path, entries: &[(&str, &str)]) {
+    use std::io::Write;
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for (name, contents) in entries {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+fn setup_managed() -> (ExtensionFramework, TempDir) {
+    let db = Arc::new(Database::open_in_memory().unwrap());
+    let tmp = TempDir::new().unwrap();
+    let fw = ExtensionFramework::new(db).with_extensions_dir(tmp.path().join("managed"));
+    (fw, tmp)
+}
+
+#[test]
+fn test_install_from_zip() {
+    let (mut fw, tmp) = setup_managed();
+    let archive = tmp.path().join("ext.zip");
+    write_zip(&archive, &[("manifest.json", ARCHIVE_MANIFEST), ("js/content.js", "// hi")]);
+
+    // Unpacked into staging until the install is confirmed
+    let pending = fw.request_install(archive.to_str().unwrap()).unwrap();
+    let managed = tmp.path().join("managed").join("zipped-ext");
+    assert!(std::path::Path::new(&pending.path).starts_with(tmp.path().join("managed/.staging")));
+    assert!(std::path::Path::new(&pending.path).join("js/content.js").is_file());
+    assert!(!managed.exists());
+
+    fw.confirm_install(&pending.install_id, &[ExtensionPermission::PageContent]).unwrap();
+    let installed = fw.get_extension("zipped-ext").unwrap();
+    assert_eq!(installed.version, "2.0.0");
+    assert_eq!(std::path::Path::new(&installed.install_path), managed);
+    assert!(managed.join("js/content.js").is_file());
+    assert!(!std::path::Path::new(&pending.path).exists());
+
+    // Uninstalling removes the files we unpacked
+    fw.uninstall("zipped-ext").unwrap();
+    assert!(!managed.exists());
+}
+
+#[test]
+fn test_install_from_crx_with_wrapping_folder() {
+    let (mut fw, tmp) = setup_managed();
+    let zip_path = tmp.path().join("inner.zip");
+    write_zip(&zip_path, &[("zipped/manifest.json", ARCHIVE_MANIFEST)]);
+    let mut crx = b"Cr24".to_vec();
+    crx.extend_from_slice(&3u32.to_le_bytes());
+    crx.extend_from_slice(&4u32.to_le_bytes());
+    crx.extend_from_slice(b"sig!");
+    crx.extend(std::fs::read(&zip_path).unwrap());
+    let archive = tmp.path().join("ext.crx");
+    std::fs::write(&archive, crx).unwrap();
+
+    let pending = fw.request_install(archive.to_str().unwrap()).unwrap();
+    assert_eq!(pending.extension_id, "zipped-ext");
+    fw.confirm_install(&pending.install_id, &[ExtensionPermission::PageContent]).unwrap();
+    assert!(tmp.path().join("managed/zipped-ext/manifest.json").is_file());
+}
+
+#[test]
+fn test_pending_archive_installs_do_not_share_files() {
+    let (mut fw, tmp) = setup_managed();
+    let archive = tmp.path().join("ext.zip");
+    write_zip(&archive, &[("manifest.json", ARCHIVE_MANIFEST)]);
+
+    let first = fw.request_install(archive.to_str().unwrap()).unwrap();
+    let second = fw.request_install(archive.to_str().unwrap()).unwrap();
+    assert_ne!(first.path, second.path);
+
+    fw.cancel_install(&second.install_id).unwrap();
+    assert!(std::path::Path::new(&first.path).join("manifest.json").is_file());
+
+    fw.confirm_install(&first.install_id, &[ExtensionPermission::PageContent]).unwrap();
+    assert!(tmp.path().join("managed/zipped-ext/manifest.json").is_file());
+}
+
+#[test]
+fn test_refused_archive_install_removes_files() {
+    let (mut fw, tmp) = setup_managed();
+    let archive = tmp.path().join("ext.zip");
+    write_zip(&archive, &[("manifest.json", ARCHIVE_MANIFEST)]);
+
+    let pending = fw.request_install(archive.to_str().unwrap()).unwrap();
+    assert!(fw.confirm_install(&pending.install_id, &[]).is_err());
+    assert!(!std::path::Path::new(&pending.path).exists());
+    assert!(!tmp.path().join("managed/zipped-ext").exists());
+}
+
+#[test]
+fn test_archive_with_traversal_rejected() {
+    let (mut fw, tmp) = setup_managed();
+    let archive = tmp.path().join("evil.zip");
+    write_zip(&archive, &[("manifest.json", ARCHIVE_MANIFEST), ("../escaped.js", "// nope")]);
+
+    assert!(matches!(
+        fw.request_install(archive.to_str().unwrap()),
+        Err(ExtensionError::InvalidArchive(_))
+    ));
+    assert!(!tmp.path().join("escaped.js").exists());
+    assert!(!tmp.path().join("managed/zipped-ext").exists());
+}
+
+#[test]
+fn test_archive_without_manifest_rejected() {
+    let (mut fw, tmp) = setup_managed();
+    let archive = tmp.path().join("empty.zip");
+    write_zip(&archive, &[("readme.txt", "no manifest here")]);
+    assert!(matches!(
+        fw.request_install(archive.to_str().unwrap()),
+        Err(ExtensionError::InvalidArchive(_))
+    ));
+
+    std::fs::write(tmp.path().join("garbage.zip"), "not a zip").unwrap();
+    assert!(fw.request_install(tmp.path().join("garbage.zip").to_str().unwrap()).is_err());
+}
+
+#[test]
+fn test_cancelled_archive_install_removes_files() {
+    let (mut fw, tmp) = setup_managed();
+    let archive = tmp.path().join("ext.zip");
+    write_zip(&archive, &[("manifest.json", ARCHIVE_MANIFEST)]);
+
+    let pending = fw.request_install(archive.to_str().unwrap()).unwrap();
+    fw.cancel_install(&pending.install_id).unwrap();
+    assert!(!std::path::Path::new(&pending.path).exists());
+    // The source archive itself is untouched
+    assert!(archive.exists());
+}
+
+// ─── Blocking Rules ───
+
+/// Create an extension shipping `rules.json` (enabled) and `extra.json` (disabled).
+fn create_rules_extension(tmp: &TempDir, name: &str, permissions: &str) -> String {
+    let ext_dir = tmp.path().join(name);
+    std::fs::create_dir_all(&ext_dir).unwrap();
+    let rules = r#"[
+        {"id": 1, "action": {"type": "block"}, "condition": {"urlFilter": "||ads.example.com^", "resourceTypes": ["script"]}},
+        {"id": 2, "priority": 3, "action": {"type": "allow"}, "condition": {"requestDomains": ["ok.example.com"]}},
+        {"id": 3, "action": {"type": "redirect"}, "condition": {"urlFilter": "tracker"}}
+    ]"#;
+    std::fs::write(ext_dir.join("rules.json"), rules).unwrap();
+    std::fs::write(ext_dir.join("extra.json"), r#"[{"id": 9, "action": {"type": "block"}, "condition": {}}]"#).unwrap();
+    let manifest = format!(r#"{{
+        "id": "{name}",
+        "name": "{name}",
+        "version": "1.0.0",
+        "permissions": {permissions},
+        "declarative_net_request": {{
+            "rule_resources": [
+                {{"id": "main", "path": "rules.json"}},
+                {{"id": "extra", "enabled": false, "path": "extra.json"}}
+            ]
+        }}
+    }}"#);
+    std::fs::write(ext_dir.join("manifest.json"), manifest).unwrap();
+    ext_dir.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_compile_blocking_rules() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_rules_extension(&tmp, "dnr-ext", r#"["declarativeNetRequest"]"#)).unwrap();
+
+    let rules = fw.compile_blocking_rules();
+    // The redirect rule and the disabled ruleset are skipped
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].extension_id, "dnr-ext");
+    assert_eq!(rules[0].action, NetworkRuleAction::Block);
+    assert_eq!(rules[0].priority, 1);
+    assert_eq!(rules[0].url_filter.as_deref(), Some("||ads.example.com^"));
+    assert_eq!(rules[0].resource_types, vec!["script".to_string()]);
+    assert_eq!(rules[1].action, NetworkRuleAction::Allow);
+    assert_eq!(rules[1].priority, 3);
+    assert_eq!(rules[1].request_domains, vec!["ok.example.com".to_string()]);
+
+    fw.disable("dnr-ext").unwrap();
+    assert!(fw.compile_blocking_rules().is_empty());
+}
+
+#[test]
+fn test_blocking_rules_require_permission() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_rules_extension(&tmp, "noperm-ext", "[]")).unwrap();
+    assert!(fw.compile_blocking_rules().is_empty());
+}
+
+#[test]
+fn test_malformed_ruleset_skipped() {
+    let (mut fw, tmp) = setup();
+    let ext_path = create_rules_extension(&tmp, "broken-ext", r#"["declarativenetrequest"]"#);
+    std::fs::write(std::path::Path::new(&ext_path).join("rules.json"), "{not json").unwrap();
+    fw.install(&ext_path).unwrap();
+    assert!(fw.compile_blocking_rules().is_empty());
+}
+
+// ─── Options Pages ───
+
+/// Create an extension with an options page declared via `options_ui`.
+fn create_options_extension(tmp: &TempDir, name: &str) -> String {
+    let ext_dir = tmp.path().join(name);
+    std::fs::create_dir_all(ext_dir.join("ui")).unwrap();
+    std::fs::write(
+        ext_dir.join("ui/options.html"),
+        "<html><HEAD><title>Options</title></HEAD><body><script src=\"options.js\"></script></body></html>",
+    ).unwrap();
+    std::fs::write(ext_dir.join("ui/options.js"), "chrome.storage.local.get(null);").unwrap();
+    let manifest = format!(r#"{{
+        "id": "{name}", "name": "{name}", "version": "1.0.0",
+        "permissions": ["storage"],
+        "options_ui": {{"page": "ui/options.html"}}
+    }}"#);
+    std::fs::write(ext_dir.join("manifest.json"), manifest).unwrap();
+    ext_dir.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_options_page_served_with_api() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_options_extension(&tmp, "opt-ext")).unwrap();
+
+    assert_eq!(fw.options_page_path("opt-ext").as_deref(), Some("ui/options.html"));
+    let html = fw.options_page("opt-ext").unwrap();
+    // The API is bound inside <head>, before the page's own scripts
+    let api_at = html.find("__gb_ext_api(\"opt-ext\")").unwrap();
+    assert!(html.find("<HEAD>").unwrap() < api_at);
+    assert!(api_at < html.find("options.js").unwrap());
+
+    assert_eq!(fw.extension_resource("opt-ext", "ui/options.js").unwrap(), b"chrome.storage.local.get(null);");
+}
+
+#[test]
+fn test_options_page_unavailable() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_options_extension(&tmp, "off-opt-ext")).unwrap();
+    fw.install(&create_test_extension(&tmp, "no-opt-ext", "[]")).unwrap();
+
+    assert!(fw.options_page_path("no-opt-ext").is_none());
+    assert!(matches!(fw.options_page("no-opt-ext"), Err(ExtensionError::NotFound(_))));
+    fw.disable("off-opt-ext").unwrap();
+    assert!(fw.options_page("off-opt-ext").is_err());
+    assert!(fw.extension_resource("off-opt-ext", "ui/options.js").is_err());
+}
+
+#[test]
+fn test_extension_resource_stays_inside_extension() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_options_extension(&tmp, "safe-opt-ext")).unwrap();
+    std::fs::write(tmp.path().join("secret.txt"), "secret").unwrap();
+
+    assert!(fw.extension_resource("safe-opt-ext", "../secret.txt").is_err());
+    assert!(fw.extension_resource("safe-opt-ext", "ui/../../secret.txt").is_err());
+    assert!(fw.extension_resource("safe-opt-ext", "manifest.json").is_err());
+}
+
+#[test]
+fn test_extension_page_urls() {
+    assert_eq!(options_url("opt-ext"), "gb://extension/opt-ext/options");
+    assert_eq!(extension_page_id("gb://extension/opt-ext/options"), Some("opt-ext"));
+    assert_eq!(extension_page_id("http://gb.extension/opt-ext/options?x=1"), Some("opt-ext"));
+    assert_eq!(extension_page_id("gb://localhost/settings"), None);
+    assert_eq!(extension_page_id("https://evil.example/gb://extension/opt-ext/"), None);
+}
+
+#[test]
+fn test_extension_page_sender_must_belong_to_extension() {
+    let (mut fw, tmp) = setup();
+    fw.install(&create_options_extension(&tmp, "page-ext")).unwrap();
+
+    let own = MessageSender::ExtensionPage { url: options_url("page-ext"), request_id: Some(1) };
+    let foreign = MessageSender::ExtensionPage { url: options_url("other-ext"), request_id: Some(1) };
+    assert!(fw.check_sender("page-ext", &own).is_ok());
+    assert!(matches!(fw.check_sender("page-ext", &foreign), Err(ExtensionError::PermissionDenied(_))));
+}
@@ -0,0 +1,88 @@
+//! Unit tests for the FaviconManager public API.
+//!
+//! These tests exercise the favicon cache through the `FaviconManagerTrait`
+//! interface, using an in-memory SQLite database, and the choice of icon
+//! links to fetch.
+
+use gitbrowser::database::Database;
+use gitbrowser::managers::favicon_manager::{FaviconManager, FaviconManagerTrait, MAX_FAVICON_DATA_URL_LEN};
+use gitbrowser::types::favicon::IconLink;
+
+fn link(rel: &str, href: &str, sizes: &str) -> IconLink {
+    IconLink { rel: rel.into(), href: href.into(), sizes: sizes.into(), mime: String::new() }
+}
+
+#[test]
+fn test_save_get_and_replace() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = FaviconManager::new(db.connection());
+
+    assert_eq!(mgr.get_favicon("example.com").unwrap(), None);
+    mgr.save_favicon("Example.com", "https://example.com/a.png", "data:image/png;base64,AAAA").unwrap();
+    let icon = mgr.get_favicon("example.com").unwrap().unwrap();
+    assert_eq!(icon.icon_url, "https://example.com/a.png");
+    assert!(!FaviconManager::is_stale(icon.fetched_at));
+
+    mgr.save_favicon("example.com", "https://example.com/b.ico", "data:image/x-icon;base64,BBBB").unwrap();
+    assert_eq!(mgr.get_favicon("example.com").unwrap().unwrap().data_url, "data:image/x-icon;base64,BBBB");
+    assert!(mgr.remove_favicon("example.com").unwrap());
+    assert!(!mgr.remove_favicon("example.com").unwrap());
+}
+
+#[test]
+fn test_rejects_non_images_and_oversized_icons() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = FaviconManager::new(db.connection());
+    assert!(mgr.save_favicon("a.com", "https://a.com/x", "data:text/html;base64,AAAA").is_err());
+    let huge = format!("data:image/png;base64,{}", "A".repeat(MAX_FAVICON_DATA_URL_LEN));
+    assert!(mgr.save_favicon("a.com", "https://a.com/x", &huge).is_err());
+}
+
+#[test]
+fn test_favicons_for_urls_by_site() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = FaviconManager::new(db.connection());
+    mgr.save_favicon("a.com", "https://a.com/i.png", "data:image/png;base64,AAAA").unwrap();
+
+    let icons = mgr.favicons_for_urls(&["https://a.com/one", "https://A.com/two", "https://b.com/", "about:x"]).unwrap();
+    assert_eq!(icons.len(), 1);
+    assert_eq!(icons["a.com"], "data:image/png;base64,AAAA");
+    mgr.clear().unwrap();
+    assert!(mgr.favicons_for_urls(&["https://a.com/"]).unwrap().is_empty());
+}
+
+#[test]
+fn test_icon_candidates_prefer_sized_icons_then_fall_back() {
+    let links = [
+        link("apple-touch-icon", "https://a.com/touch.png", "180x180"),
+        link("mask-icon", "https://a.com/mask.svg", ""),
+        link("icon", "https://a.com/plain.ico", ""),
+        link("icon", "https://a.com/16.png", "16x16"),
+        link("shortcut icon", "https://a.com/32.png", "32x32"),
+        link("icon", "https://a.com/any.svg", "any"),
+        link("icon", "data:image/png;base64,AAAA", "32x32"),
+    ];
+    assert_eq!(
+        FaviconManager::icon_candidates(&links, "https://a.com/page?q=1"),
+        vec![
+            "https://a.com/32.png",
+            "https://a.com/16.png",
+            "https://a.com/any.svg",
+            "https://a.com/plain.ico",
+            "https://a.com/touch.png",
+            "https://a.com/favicon.ico",
+        ]
+    );
+    assert_eq!(FaviconManager::icon_candidates(&[], "http://b.com:8080/x"), vec!["http://b.com:8080/favicon.ico"]);
+    assert!(FaviconManager::icon_candidates(&[], "about:blank").is_empty());
+}
+
+#[test]
+fn test_data_url_uses_or_sniffs_the_image_type() {
+    let png = b"\x89PNG\r\n\x1a\nrest";
+    let url = FaviconManager::data_url(png, Some("application/octet-stream")).unwrap();
+    assert!(url.starts_with("data:image/png;base64,"));
+    let ico = FaviconManager::data_url(&[0, 0, 1, 0, 1], Some("image/vnd.microsoft.icon; charset=binary")).unwrap();
+    assert!(ico.starts_with("data:image/vnd.microsoft.icon;base64,"));
+    assert_eq!(FaviconManager::data_url(b"<html>not found</html>", Some("text/html")), None);
+}
@@ -0,0 +1,212 @@
+//! Unit tests for the GitHub REST client.
+//!
+//! A scripted transport stands in for the network and records every request,
+//! so conditional requests and rate-limit handling can be checked directly.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use gitbrowser::services::github_client::{next_page_url, GitHubClient};
+use gitbrowser::services::github_integration::ApiResponse;
+use gitbrowser::types::errors::GitHubError;
+
+/// One request as the transport saw it.
+#[derive(Debug, Clone)]
+struct Sent {
+    method: String,
+    url: String,
+    token: Option<String>,
+    headers: HashMap<String, String>,
+}
+
+fn response(status: u16, headers: &[(&str, &str)], body: Value) -> ApiResponse {
+    ApiResponse {
+        status,
+        headers: headers.iter().map(|(k, v)| (k.to_ascii_lowercase(), v.to_string())).collect(),
+        body,
+    }
+}
+
+/// Runs one request against `answers`, returning the result, the requests
+/// sent and the sleeps taken.
+fn run(
+    client: &GitHubClient,
+    method: &str,
+    path: &str,
+    now: i64,
+    answers: Vec<ApiResponse>,
+) -> (Result<ApiResponse, GitHubError>, Vec<Sent>, Vec<Duration>) {
+    let answers = RefCell::new(VecDeque::from(answers));
+    let sent = RefCell::new(Vec::new());
+    let mut slept = Vec::new();
+    let result = client.request_with(
+        method,
+        path,
+        None,
+        now,
+        |method, url, token, _body, headers| {
+            sent.borrow_mut().push(Sent {
+                method: method.to_string(),
+                url: url.to_string(),
+                token: token.map(String::from),
+                headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            });
+            answers.borrow_mut().pop_front().ok_or_else(|| GitHubError::NetworkError("no answer".to_string()))
+        },
+        |d| slept.push(d),
+    );
+    (result, sent.into_inner(), slept)
+}
+
+// ─── Requests ───
+
+#[test]
+fn test_paths_resolve_against_api_base() {
+    let client = GitHubClient::new("tok");
+    assert_eq!(client.url("/user"), "https://api.github.com/user");
+    assert_eq!(client.url("gists"), "https://api.github.com/gists");
+    assert_eq!(client.url("https://gist.githubusercontent.com/raw/1"), "https://gist.githubusercontent.com/raw/1");
+}
+
+#[test]
+fn test_requests_carry_token() {
+    let client = GitHubClient::new("tok");
+    let (result, sent, _) = run(&client, "GET", "/user", 0, vec![response(200, &[], json!({"login": "octo"}))]);
+    assert_eq!(result.unwrap().body["login"], "octo");
+    assert_eq!(sent[0].method, "GET");
+    assert_eq!(sent[0].url, "https://api.github.com/user");
+    assert_eq!(sent[0].token.as_deref(), Some("tok"));
+    assert!(sent[0].headers.is_empty());
+}
+
+// ─── Conditional requests ───
+
+#[test]
+fn test_etag_revalidation_serves_cached_body() {
+    let client = GitHubClient::new("tok");
+    let first = response(200, &[("ETag", "\"abc\""), ("Last-Modified", "Thu, 15 Oct 2026 10:00:00 GMT")], json!([1, 2]));
+    let _ = run(&client, "GET", "/notifications", 0, vec![first]);
+
+    let (result, sent, _) = run(&client, "GET", "/notifications", 10, vec![response(304, &[], Value::Null)]);
+    let result = result.unwrap();
+    assert_eq!(result.status, 304);
+    assert_eq!(result.body, json!([1, 2]));
+    assert_eq!(sent[0].headers["If-None-Match"], "\"abc\"");
+    assert_eq!(sent[0].headers["If-Modified-Since"], "Thu, 15 Oct 2026 10:00:00 GMT");
+}
+
+#[test]
+fn test_clones_share_cache() {
+    let client = GitHubClient::new("tok");
+    let _ = run(&client, "GET", "/user", 0, vec![response(200, &[("ETag", "\"v1\"")], json!({}))]);
+    let (_, sent, _) = run(&client.clone(), "GET", "/user", 0, vec![response(304, &[], Value::Null)]);
+    assert_eq!(sent[0].headers["If-None-Match"], "\"v1\"");
+}
+
+#[test]
+fn test_writes_are_not_conditional() {
+    let client = GitHubClient::new("tok");
+    let _ = run(&client, "GET", "/gists", 0, vec![response(200, &[("ETag", "\"v1\"")], json!([]))]);
+    let (_, sent, _) = run(&client, "POST", "/gists", 0, vec![response(201, &[("ETag", "\"v2\"")], json!({}))]);
+    assert!(sent[0].headers.is_empty());
+}
+
+// ─── Rate limits ───
+
+#[test]
+fn test_rate_limit_is_tracked() {
+    let client = GitHubClient::new("tok");
+    assert!(client.rate_limit().is_none());
+    let headers = [("X-RateLimit-Limit", "5000"), ("X-RateLimit-Remaining", "4999"), ("X-RateLimit-Reset", "1000")];
+    let _ = run(&client, "GET", "/user", 0, vec![response(200, &headers, json!({}))]);
+    let limit = client.rate_limit().unwrap();
+    assert_eq!((limit.limit, limit.remaining, limit.reset_at), (5000, 4999, 1000));
+}
+
+#[test]
+fn test_exhausted_quota_fails_without_request() {
+    let client = GitHubClient::new("tok");
+    let headers = [("X-RateLimit-Limit", "60"), ("X-RateLimit-Remaining", "0"), ("X-RateLimit-Reset", "1000")];
+    let _ = run(&client, "GET", "/user", 0, vec![response(200, &headers, json!({}))]);
+
+    let (result, sent, _) = run(&client, "GET", "/user", 400, vec![]);
+    assert!(matches!(result, Err(GitHubError::RateLimited(600))));
+    assert!(sent.is_empty());
+
+    // Once the reset time has passed requests go out again
+    let (result, sent, _) = run(&client, "GET", "/user", 1000, vec![response(200, &[], json!({}))]);
+    assert!(result.is_ok());
+    assert_eq!(sent.len(), 1);
+}
+
+#[test]
+fn test_retry_after_is_waited_out() {
+    let client = GitHubClient::new("tok");
+    let limited = response(429, &[("Retry-After", "5")], json!({"message": "slow down"}));
+    let (result, sent, slept) = run(&client, "GET", "/user", 0, vec![limited, response(200, &[], json!({"ok": true}))]);
+    assert_eq!(result.unwrap().body["ok"], true);
+    assert_eq!(sent.len(), 2);
+    assert_eq!(slept, vec![Duration::from_secs(5)]);
+}
+
+#[test]
+fn test_secondary_limit_without_retry_after() {
+    let client = GitHubClient::new("tok");
+    let limited = response(403, &[], json!({"message": "You have exceeded a secondary rate limit."}));
+    let (result, _, slept) = run(&client, "GET", "/user", 0, vec![limited, response(200, &[], json!({}))]);
+    assert!(result.is_ok());
+    assert_eq!(slept, vec![Duration::from_secs(60)]);
+}
+
+#[test]
+fn test_long_waits_fail_fast() {
+    let client = GitHubClient::new("tok");
+    let limited = response(403, &[("Retry-After", "3600")], json!({}));
+    let (result, sent, slept) = run(&client, "GET", "/user", 0, vec![limited]);
+    assert!(matches!(result, Err(GitHubError::RateLimited(3600))));
+    assert_eq!(sent.len(), 1);
+    assert!(slept.is_empty());
+}
+
+#[test]
+fn test_retries_are_bounded() {
+    let client = GitHubClient::new("tok");
+    let limited = || response(429, &[("Retry-After", "1")], json!({}));
+    let (result, sent, slept) = run(&client, "GET", "/user", 0, vec![limited(), limited(), limited(), limited()]);
+    assert!(matches!(result, Err(GitHubError::RateLimited(1))));
+    assert_eq!(sent.len(), 3);
+    assert_eq!(slept.len(), 2);
+}
+
+#[test]
+fn test_client_without_waiting_fails_on_rate_limit() {
+    let client = GitHubClient::new("tok").without_waiting();
+    let limited = response(429, &[("Retry-After", "5")], json!({}));
+    let (result, sent, slept) = run(&client, "GET", "/user", 0, vec![limited, response(200, &[], json!({}))]);
+    assert!(matches!(result, Err(GitHubError::RateLimited(5))));
+    assert_eq!(sent.len(), 1);
+    assert!(slept.is_empty());
+}
+
+#[test]
+fn test_plain_forbidden_is_not_retried() {
+    let client = GitHubClient::new("tok");
+    let (result, sent, _) = run(&client, "GET", "/repos/o/r", 0, vec![response(403, &[], json!({"message": "Resource not accessible"}))]);
+    assert_eq!(result.unwrap().status, 403);
+    assert_eq!(sent.len(), 1);
+}
+
+#[test]
+fn test_next_page_url_from_link_header() {
+    let link = "<https://api.github.com/user/repos?page=2>; rel=\"next\", <https://api.github.com/user/repos?page=5>; rel=\"last\"";
+    assert_eq!(
+        next_page_url(&response(200, &[("Link", link)], json!([]))).as_deref(),
+        Some("https://api.github.com/user/repos?page=2")
+    );
+    let last = "<https://api.github.com/user/repos?page=1>; rel=\"prev\"";
+    assert_eq!(next_page_url(&response(200, &[("Link", last)], json!([]))), None);
+    assert_eq!(next_page_url(&response(200, &[], json!([]))), None);
+}
@@ -1,14 +1,22 @@
 //! Unit tests for GitHub Integration.
 //!
-//! Tests token storage/retrieval, encryption, logout, and rekey with master password.
+//! Tests token storage/retrieval, encryption, logout, rekey with master password,
+//! and the device flow login.
 //!
 //! Covers: TEST-04 from AUDIT.md Phase 3.
 
 use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
 
 use gitbrowser::database::Database;
 use gitbrowser::services::crypto_service::{CryptoService, CryptoServiceTrait};
-use gitbrowser::services::github_integration::{GitHubIntegration, GitHubIntegrationTrait};
+use gitbrowser::services::github_integration::{
+    parse_device_code, parse_profile, parse_token_poll, GitHubEndpoints, GitHubIntegration, GitHubIntegrationTrait,
+};
+use gitbrowser::types::errors::GitHubError;
+use gitbrowser::types::github::{DeviceFlowProgress, DeviceTokenPoll, GitHubSettings, OAuthDeviceCode};
 
 fn setup() -> GitHubIntegration {
     let db = Arc::new(Database::open_in_memory().unwrap());
@@ -153,3 +161,207 @@ fn test_is_authenticated_reflects_stored_token() {
     let gh2 = GitHubIntegration::new(db).unwrap();
     assert!(gh2.is_authenticated());
 }
+
+#[test]
+fn test_client_requires_stored_token() {
+    let mut gh = setup();
+    assert!(matches!(gh.client(), Err(GitHubError::NotAuthenticated)));
+
+    gh.store_token("ghp_x", "user", None).unwrap();
+    assert_eq!(gh.client().unwrap().url("/user"), "https://api.github.com/user");
+
+    gh.logout().unwrap();
+    assert!(matches!(gh.client(), Err(GitHubError::NotAuthenticated)));
+}
+
+// ─── Device Flow ───
+
+fn device_code(interval: u32, expires_in: u32) -> OAuthDeviceCode {
+    OAuthDeviceCode {
+        device_code: "dc".to_string(),
+        user_code: "ABCD-1234".to_string(),
+        verification_uri: "https://github.com/login/device".to_string(),
+        expires_in,
+        interval,
+    }
+}
+
+#[test]
+fn test_parse_device_code() {
+    let code = parse_device_code(&json!({
+        "device_code": "dc", "user_code": "ABCD-1234",
+        "verification_uri": "https://github.com/login/device", "expires_in": 900, "interval": 5
+    })).unwrap();
+    assert_eq!(code.user_code, "ABCD-1234");
+    assert_eq!(code.interval, 5);
+
+    let err = parse_device_code(&json!({"error": "unauthorized_client", "error_description": "bad client"}));
+    assert!(matches!(err, Err(GitHubError::AuthFailed(msg)) if msg == "bad client"));
+}
+
+#[test]
+fn test_parse_token_poll_answers() {
+    assert_eq!(parse_token_poll(&json!({"access_token": "gho_x"})).unwrap(), DeviceTokenPoll::Granted("gho_x".to_string()));
+    assert_eq!(parse_token_poll(&json!({"error": "authorization_pending"})).unwrap(), DeviceTokenPoll::Pending);
+    assert_eq!(parse_token_poll(&json!({"error": "slow_down", "interval": 10})).unwrap(), DeviceTokenPoll::SlowDown(Some(10)));
+    assert!(matches!(parse_token_poll(&json!({"error": "expired_token"})), Err(GitHubError::DeviceCodeExpired)));
+    assert!(matches!(parse_token_poll(&json!({"error": "access_denied"})), Err(GitHubError::AuthFailed(_))));
+}
+
+#[test]
+fn test_parse_profile() {
+    let profile = parse_profile(&json!({
+        "login": "octocat", "name": null, "id": 1,
+        "avatar_url": "https://avatars.githubusercontent.com/u/1", "html_url": "https://github.com/octocat"
+    })).unwrap();
+    assert_eq!(profile.login, "octocat");
+    assert!(profile.name.is_none());
+}
+
+#[test]
+fn test_wait_for_device_token_follows_slow_down() {
+    let mut answers = vec![
+        Ok(DeviceTokenPoll::Pending),
+        Ok(DeviceTokenPoll::SlowDown(None)),
+        Err(GitHubError::NetworkError("reset".to_string())),
+        Ok(DeviceTokenPoll::Granted("gho_token".to_string())),
+    ].into_iter();
+    let mut slept = Vec::new();
+    let mut progress = Vec::new();
+    let token = GitHubIntegration::wait_for_device_token(
+        &device_code(5, 900),
+        || answers.next().unwrap(),
+        |d| slept.push(d),
+        |p| progress.push(p),
+    ).unwrap();
+
+    assert_eq!(token, "gho_token");
+    assert_eq!(slept, vec![Duration::from_secs(5), Duration::from_secs(5), Duration::from_secs(10), Duration::from_secs(10)]);
+    assert!(matches!(progress[0], DeviceFlowProgress::Pending));
+    assert!(matches!(progress[1], DeviceFlowProgress::SlowDown { interval: 10 }));
+}
+
+#[test]
+fn test_wait_for_device_token_expires() {
+    let mut polls = 0;
+    let result = GitHubIntegration::wait_for_device_token(
+        &device_code(5, 12),
+        || { polls += 1; Ok(DeviceTokenPoll::Pending) },
+        |_| {},
+        |_| {},
+    );
+    assert!(matches!(result, Err(GitHubError::DeviceCodeExpired)));
+    assert_eq!(polls, 3);
+}
+
+#[test]
+fn test_wait_for_device_token_stops_on_denial() {
+    let result = GitHubIntegration::wait_for_device_token(
+        &device_code(1, 60),
+        || Err(GitHubError::AuthFailed("access denied".to_string())),
+        |_| {},
+        |_| {},
+    );
+    assert!(matches!(result, Err(GitHubError::AuthFailed(_))));
+}
+
+#[test]
+fn test_complete_login_authenticates() {
+    let mut gh = setup();
+    let profile = parse_profile(&json!({
+        "login": "octocat", "avatar_url": "https://avatars.githubusercontent.com/u/1",
+        "html_url": "https://github.com/octocat"
+    })).unwrap();
+    gh.complete_login("gho_device", &profile).unwrap();
+    assert!(gh.is_authenticated());
+    assert_eq!(gh.get_token().unwrap(), Some("gho_device".to_string()));
+}
+
+// ─── Enterprise ───
+
+fn enterprise(web_url: &str) -> GitHubSettings {
+    GitHubSettings {
+        use_enterprise: true,
+        enterprise_web_url: Some(web_url.to_string()),
+        enterprise_api_url: None,
+        enterprise_client_id: Some("Iv1.enterprise".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_endpoints_default_to_github_com() {
+    let endpoints = GitHubEndpoints::from_settings(&GitHubSettings::default()).unwrap();
+    assert_eq!(endpoints, GitHubEndpoints::github_com());
+    assert_eq!(endpoints.api_url, "https://api.github.com");
+    assert_eq!(endpoints.credential_slot, "default");
+}
+
+#[test]
+fn test_enterprise_endpoints_from_settings() {
+    let endpoints = GitHubEndpoints::from_settings(&enterprise("https://ghe.example.com/")).unwrap();
+    assert_eq!(endpoints.web_url, "https://ghe.example.com");
+    assert_eq!(endpoints.api_url, "https://ghe.example.com/api/v3");
+    assert_eq!(endpoints.credential_slot, "ghe.example.com");
+    assert_eq!(endpoints.device_client_id(None).unwrap(), "Iv1.enterprise");
+    assert_eq!(endpoints.device_client_id(Some("other")).unwrap(), "other");
+
+    let mut settings = enterprise("https://ghe.example.com:8443");
+    settings.enterprise_api_url = Some("https://api.ghe.example.com".to_string());
+    let endpoints = GitHubEndpoints::from_settings(&settings).unwrap();
+    assert_eq!(endpoints.api_url, "https://api.ghe.example.com");
+    assert_eq!(endpoints.credential_slot, "ghe.example.com:8443");
+}
+
+#[test]
+fn test_invalid_enterprise_settings_rejected() {
+    let missing = GitHubSettings { use_enterprise: true, ..Default::default() };
+    assert!(matches!(GitHubEndpoints::from_settings(&missing), Err(GitHubError::InvalidConfig(_))));
+    assert!(matches!(
+        GitHubEndpoints::from_settings(&enterprise("http://ghe.example.com")),
+        Err(GitHubError::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        GitHubEndpoints::from_settings(&enterprise("not a url")),
+        Err(GitHubError::InvalidConfig(_))
+    ));
+
+    let mut no_client = enterprise("https://ghe.example.com");
+    no_client.enterprise_client_id = None;
+    let endpoints = GitHubEndpoints::from_settings(&no_client).unwrap();
+    assert!(matches!(endpoints.device_client_id(None), Err(GitHubError::InvalidConfig(_))));
+}
+
+#[test]
+fn test_hosts_keep_separate_tokens() {
+    let mut gh = setup();
+    gh.store_token("ghp_public", "octocat", None).unwrap();
+
+    let ghe = GitHubEndpoints::from_settings(&enterprise("https://ghe.example.com")).unwrap();
+    gh.set_endpoints(ghe.clone());
+    assert!(!gh.is_authenticated());
+    assert_eq!(gh.get_token().unwrap(), None);
+    gh.store_token("ghp_enterprise", "octocat", None).unwrap();
+    assert_eq!(gh.client().unwrap().url("/user"), "https://ghe.example.com/api/v3/user");
+
+    // Logging out of the enterprise host leaves the github.com login alone
+    gh.logout().unwrap();
+    gh.set_endpoints(GitHubEndpoints::github_com());
+    assert!(gh.is_authenticated());
+    assert_eq!(gh.get_token().unwrap(), Some("ghp_public".to_string()));
+}
+
+#[test]
+fn test_rekey_covers_every_host() {
+    let mut gh = setup();
+    gh.store_token("ghp_public", "octocat", None).unwrap();
+    gh.set_endpoints(GitHubEndpoints::from_settings(&enterprise("https://ghe.example.com")).unwrap());
+    gh.store_token("ghp_enterprise", "octocat", None).unwrap();
+
+    let crypto = CryptoService::new();
+    let master_key = crypto.derive_key("master_password", &crypto.generate_salt()).unwrap();
+    gh.rekey_with_master(&master_key).unwrap();
+    assert_eq!(gh.get_token().unwrap(), Some("ghp_enterprise".to_string()));
+    gh.set_endpoints(GitHubEndpoints::github_com());
+    assert_eq!(gh.get_token().unwrap(), Some("ghp_public".to_string()));
+}
@@ -0,0 +1,180 @@
+//! Unit tests for the GitHub notifications inbox.
+//!
+//! Responses are built by hand and fed to `record_response`, so polling
+//! rules, persistence and alerting run without a network.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use gitbrowser::database::Database;
+use gitbrowser::services::github_integration::ApiResponse;
+use gitbrowser::services::github_notifications::{
+    alert_texts, html_url, parse_notification, GitHubNotifications, GitHubNotificationsTrait,
+    DEFAULT_POLL_INTERVAL_SECS,
+};
+use gitbrowser::types::errors::GitHubError;
+
+fn inbox() -> GitHubNotifications {
+    GitHubNotifications::new(Arc::new(Database::open_in_memory().unwrap()))
+}
+
+fn thread(id: &str, reason: &str, kind: &str, number: u32) -> Value {
+    let path = if kind == "PullRequest" { "pulls" } else { "issues" };
+    json!({
+        "id": id,
+        "unread": true,
+        "reason": reason,
+        "updated_at": format!("2026-10-0{}T10:00:00Z", number % 9 + 1),
+        "subject": {
+            "title": format!("Thread {}", id),
+            "url": format!("https://api.github.com/repos/octo/repo/{}/{}", path, number),
+            "type": kind
+        },
+        "repository": {"full_name": "octo/repo", "html_url": "https://github.com/octo/repo"}
+    })
+}
+
+fn response(status: u16, headers: &[(&str, &str)], body: Value) -> ApiResponse {
+    ApiResponse {
+        status,
+        headers: headers.iter().map(|(k, v)| (k.to_ascii_lowercase(), v.to_string())).collect::<HashMap<_, _>>(),
+        body,
+    }
+}
+
+// ─── Parsing ───
+
+#[test]
+fn test_parse_notification_maps_subject_to_web_url() {
+    let n = parse_notification(&thread("1", "mention", "PullRequest", 7)).unwrap();
+    assert_eq!(n.id, "1");
+    assert_eq!(n.title, "Thread 1");
+    assert_eq!(n.repo_full_name, "octo/repo");
+    assert_eq!(n.notification_type, "PullRequest");
+    assert_eq!(n.reason, "mention");
+    assert!(n.unread);
+    assert_eq!(n.url, "https://github.com/octo/repo/pull/7");
+}
+
+#[test]
+fn test_parse_notification_requires_id() {
+    assert!(parse_notification(&json!({"subject": {"title": "x"}})).is_none());
+}
+
+#[test]
+fn test_html_url_variants() {
+    let repo = "https://github.com/octo/repo";
+    let api = |rest: &str| format!("https://api.github.com/repos/octo/repo/{}", rest);
+    assert_eq!(html_url(Some(&api("issues/3")), repo), "https://github.com/octo/repo/issues/3");
+    assert_eq!(html_url(Some(&api("commits/abc123")), repo), "https://github.com/octo/repo/commit/abc123");
+    assert_eq!(html_url(Some(&api("releases/99")), repo), "https://github.com/octo/repo/releases");
+    assert_eq!(html_url(None, repo), repo);
+    assert_eq!(html_url(Some("https://example.com/other"), repo), repo);
+}
+
+// ─── Polling ───
+
+#[test]
+fn test_poll_interval_follows_header() {
+    let mut inbox = inbox();
+    assert!(inbox.poll_due(0));
+    inbox.record_response(&response(200, &[("X-Poll-Interval", "120")], json!([])), 1_000).unwrap();
+    assert!(!inbox.poll_due(1_119));
+    assert!(inbox.poll_due(1_120));
+}
+
+#[test]
+fn test_poll_interval_defaults() {
+    let mut inbox = inbox();
+    inbox.record_response(&response(200, &[], json!([])), 1_000).unwrap();
+    assert!(!inbox.poll_due(1_000 + DEFAULT_POLL_INTERVAL_SECS as i64 - 1));
+    assert!(inbox.poll_due(1_000 + DEFAULT_POLL_INTERVAL_SECS as i64));
+}
+
+#[test]
+fn test_failed_poll_waits_before_retrying() {
+    let mut inbox = inbox();
+    inbox.record_failure(&GitHubError::NetworkError("offline".to_string()), 1_000);
+    assert!(!inbox.poll_due(1_001));
+    assert!(inbox.poll_due(1_000 + DEFAULT_POLL_INTERVAL_SECS as i64));
+
+    // A rate limit longer than the interval is waited out
+    inbox.record_failure(&GitHubError::RateLimited(600), 2_000);
+    assert!(!inbox.poll_due(2_000 + DEFAULT_POLL_INTERVAL_SECS as i64));
+    assert!(inbox.poll_due(2_600));
+}
+
+#[test]
+fn test_not_modified_keeps_stored_threads() {
+    let mut inbox = inbox();
+    inbox.record_response(&response(200, &[], json!([thread("1", "mention", "Issue", 1)])), 0).unwrap();
+
+    let alerts = inbox.record_response(&response(304, &[], Value::Null), 100).unwrap();
+    assert!(alerts.is_empty());
+    assert_eq!(inbox.unread_count().unwrap(), 1);
+}
+
+#[test]
+fn test_unauthorized_response_is_auth_error() {
+    let mut inbox = inbox();
+    let err = inbox.record_response(&response(401, &[], json!({"message": "Bad credentials"})), 0).unwrap_err();
+    assert!(matches!(err, GitHubError::AuthFailed(_)));
+}
+
+// ─── Persistence & alerts ───
+
+#[test]
+fn test_mentions_and_review_requests_alert_once() {
+    let mut inbox = inbox();
+    let body = json!([
+        thread("1", "mention", "Issue", 1),
+        thread("2", "review_requested", "PullRequest", 2),
+        thread("3", "subscribed", "Issue", 3),
+    ]);
+    let alerts = inbox.record_response(&response(200, &[], body.clone()), 0).unwrap();
+    let mut ids: Vec<&str> = alerts.iter().map(|n| n.id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["1", "2"]);
+
+    let again = inbox.record_response(&response(200, &[], body), 100).unwrap();
+    assert!(again.is_empty());
+    assert_eq!(inbox.list_notifications(false).unwrap().len(), 3);
+}
+
+#[test]
+fn test_threads_missing_from_full_list_become_read() {
+    let mut inbox = inbox();
+    let both = json!([thread("1", "mention", "Issue", 1), thread("2", "subscribed", "Issue", 2)]);
+    inbox.record_response(&response(200, &[], both), 0).unwrap();
+
+    inbox.record_response(&response(200, &[], json!([thread("2", "subscribed", "Issue", 2)])), 100).unwrap();
+    assert_eq!(inbox.unread_count().unwrap(), 1);
+    let unread = inbox.list_notifications(true).unwrap();
+    assert_eq!(unread[0].id, "2");
+    assert_eq!(inbox.list_notifications(false).unwrap().len(), 2);
+}
+
+#[test]
+fn test_paged_response_does_not_mark_read() {
+    let mut inbox = inbox();
+    let both = json!([thread("1", "mention", "Issue", 1), thread("2", "subscribed", "Issue", 2)]);
+    inbox.record_response(&response(200, &[], both), 0).unwrap();
+
+    let link = "<https://api.github.com/notifications?page=2>; rel=\"next\"";
+    inbox.record_response(&response(200, &[("Link", link)], json!([thread("2", "subscribed", "Issue", 2)])), 100).unwrap();
+    assert_eq!(inbox.unread_count().unwrap(), 2);
+}
+
+#[test]
+fn test_alert_texts_summarise_overflow() {
+    let threads: Vec<_> = (1..=5)
+        .map(|i| parse_notification(&thread(&i.to_string(), "mention", "Issue", i)).unwrap())
+        .collect();
+    assert_eq!(alert_texts(&threads[..1]), vec![("Mentioned in octo/repo".to_string(), "Thread 1".to_string())]);
+
+    let texts = alert_texts(&threads);
+    assert_eq!(texts.len(), 3);
+    assert_eq!(texts[2].1, "3 more mentions and review requests");
+}
@@ -0,0 +1,162 @@
+//! Unit tests for the `gh` omnibox command.
+//!
+//! Shorthand resolution is checked directly; suggestions run against a
+//! repository cache filled through `store_repos`.
+
+use std::sync::Arc;
+
+use gitbrowser::database::Database;
+use gitbrowser::services::github_quick_open::{
+    command_query, resolve_with, GitHubQuickOpen, GitHubQuickOpenTrait, ListedRepo, REPO_CACHE_TTL_SECS,
+};
+use gitbrowser::types::github::GitHubRepo;
+
+const WEB: &str = "https://github.com";
+
+fn resolve(query: &str) -> Option<String> {
+    resolve_with(WEB, query, |_| None)
+}
+
+fn listed(full_name: &str, stars: u32, owned: bool, starred: bool) -> ListedRepo {
+    ListedRepo {
+        repo: GitHubRepo {
+            full_name: full_name.to_string(),
+            description: Some(format!("About {}", full_name)),
+            html_url: format!("{}/{}", WEB, full_name),
+            stargazers_count: stars,
+            language: None,
+            updated_at: "2026-10-01T00:00:00Z".to_string(),
+        },
+        owned,
+        starred,
+    }
+}
+
+fn cached() -> GitHubQuickOpen {
+    let mut quick_open = GitHubQuickOpen::new(Arc::new(Database::open_in_memory().unwrap()));
+    quick_open.store_repos(&[
+        listed("rust-lang/rust", 90_000, false, true),
+        listed("octocat/rusty_tools", 10, true, false),
+        listed("octocat/hello-world", 5, true, false),
+        listed("someone/trusty", 500, false, true),
+    ], 1_000).unwrap();
+    quick_open
+}
+
+// ─── Command parsing ───
+
+#[test]
+fn test_command_query() {
+    assert_eq!(command_query("gh rust-lang/rust issues"), Some("rust-lang/rust issues"));
+    assert_eq!(command_query("  GH   octocat "), Some("octocat"));
+    assert_eq!(command_query("gh"), Some(""));
+    assert_eq!(command_query("ghost town"), None);
+    assert_eq!(command_query("g rust"), None);
+}
+
+// ─── Resolution ───
+
+#[test]
+fn test_resolve_repo_and_sections() {
+    assert_eq!(resolve("").as_deref(), Some("https://github.com"));
+    assert_eq!(resolve("rust-lang/rust").as_deref(), Some("https://github.com/rust-lang/rust"));
+    assert_eq!(resolve("rust-lang/rust issues").as_deref(), Some("https://github.com/rust-lang/rust/issues"));
+    assert_eq!(resolve("rust-lang/rust prs").as_deref(), Some("https://github.com/rust-lang/rust/pulls"));
+    assert_eq!(resolve("rust-lang/rust ci").as_deref(), Some("https://github.com/rust-lang/rust/actions"));
+    assert_eq!(resolve("rust-lang/rust releases").as_deref(), Some("https://github.com/rust-lang/rust/releases"));
+}
+
+#[test]
+fn test_resolve_numbers_and_searches() {
+    assert_eq!(resolve("rust-lang/rust#123").as_deref(), Some("https://github.com/rust-lang/rust/issues/123"));
+    assert_eq!(resolve("rust-lang/rust 456").as_deref(), Some("https://github.com/rust-lang/rust/issues/456"));
+    assert_eq!(
+        resolve("rust-lang/rust issues borrow checker").as_deref(),
+        Some("https://github.com/rust-lang/rust/issues?q=is%3Aissue+borrow+checker")
+    );
+    assert_eq!(
+        resolve("rust-lang/rust pr const").as_deref(),
+        Some("https://github.com/rust-lang/rust/pulls?q=is%3Apr+const")
+    );
+    assert_eq!(
+        resolve("rust-lang/rust HashMap entry").as_deref(),
+        Some("https://github.com/rust-lang/rust/search?q=HashMap+entry")
+    );
+}
+
+#[test]
+fn test_resolve_users() {
+    assert_eq!(resolve("octocat").as_deref(), Some("https://github.com/octocat"));
+    assert_eq!(resolve("octocat stars").as_deref(), Some("https://github.com/octocat?tab=stars"));
+    assert_eq!(resolve("octocat repos").as_deref(), Some("https://github.com/octocat?tab=repositories"));
+}
+
+#[test]
+fn test_resolve_rejects_malformed() {
+    assert_eq!(resolve("rust-lang/rust#abc"), None);
+    assert_eq!(resolve("owner!/repo"), None);
+    assert_eq!(resolve("owner/re?po"), None);
+    assert_eq!(resolve("unknown#12"), None);
+}
+
+#[test]
+fn test_resolve_uses_enterprise_host() {
+    assert_eq!(
+        resolve_with("https://ghe.example.com/", "team/app issues", |_| None).as_deref(),
+        Some("https://ghe.example.com/team/app/issues")
+    );
+}
+
+#[test]
+fn test_bare_name_resolved_from_cache() {
+    let quick_open = cached();
+    assert_eq!(quick_open.resolve(WEB, "rust issues").as_deref(), Some("https://github.com/rust-lang/rust/issues"));
+    assert_eq!(quick_open.resolve(WEB, "hello-world#3").as_deref(), Some("https://github.com/octocat/hello-world/issues/3"));
+    // No cached repo has this name, so it is taken as a user
+    assert_eq!(quick_open.resolve(WEB, "torvalds").as_deref(), Some("https://github.com/torvalds"));
+}
+
+// ─── Suggestions ───
+
+#[test]
+fn test_suggestions_rank_prefix_and_owned_first() {
+    let quick_open = cached();
+    let names: Vec<String> = quick_open.suggest(WEB, "rust", 10).unwrap().into_iter().map(|s| s.full_name).collect();
+    assert_eq!(names, vec!["octocat/rusty_tools", "rust-lang/rust", "someone/trusty"]);
+
+    let limited = quick_open.suggest(WEB, "rust", 1).unwrap();
+    assert_eq!(limited.len(), 1);
+}
+
+#[test]
+fn test_suggestions_carry_section_url() {
+    let quick_open = cached();
+    let suggestions = quick_open.suggest(WEB, "rust-lang/ru prs", 5).unwrap();
+    assert_eq!(suggestions[0].full_name, "rust-lang/rust");
+    assert_eq!(suggestions[0].url, "https://github.com/rust-lang/rust/pulls");
+    assert!(suggestions[0].starred);
+    assert!(!suggestions[0].owned);
+}
+
+#[test]
+fn test_suggestions_treat_like_wildcards_literally() {
+    let quick_open = cached();
+    assert!(quick_open.suggest(WEB, "%", 10).unwrap().is_empty());
+    let underscore: Vec<String> = quick_open.suggest(WEB, "y_t", 10).unwrap().into_iter().map(|s| s.full_name).collect();
+    assert_eq!(underscore, vec!["octocat/rusty_tools"]);
+}
+
+// ─── Cache ───
+
+#[test]
+fn test_cache_staleness_and_clear() {
+    let mut quick_open = GitHubQuickOpen::new(Arc::new(Database::open_in_memory().unwrap()));
+    assert!(quick_open.cache_is_stale(0));
+    quick_open.store_repos(&[listed("octocat/hello-world", 1, true, false)], 1_000).unwrap();
+    assert!(!quick_open.cache_is_stale(1_000 + REPO_CACHE_TTL_SECS - 1));
+    assert!(quick_open.cache_is_stale(1_000 + REPO_CACHE_TTL_SECS));
+
+    quick_open.clear_cache().unwrap();
+    assert!(quick_open.cache_is_stale(1_000));
+    assert!(quick_open.suggest(WEB, "hello", 5).unwrap().is_empty());
+}
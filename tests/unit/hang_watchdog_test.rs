@@ -0,0 +1,62 @@
+//! Unit tests for the renderer hang watchdog.
+
+use std::time::{Duration, Instant};
+
+use gitbrowser::services::hang_watchdog::{HangWatchdog, WatchdogEvent, PING_INTERVAL};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn secs(s: u64) -> Duration {
+    Duration::from_secs(s)
+}
+
+#[test]
+fn test_answered_pings_never_report() {
+    let start = Instant::now();
+    let mut wd = HangWatchdog::new(TIMEOUT);
+    let seq = wd.next_ping(start).unwrap();
+    assert_eq!(wd.next_ping(start + secs(1)), None, "one ping at a time");
+    assert_eq!(wd.pong(seq, start + secs(1)), None);
+    assert_eq!(wd.check(start + secs(30)), None);
+
+    assert_eq!(wd.next_ping(start + secs(1)), None, "interval not over");
+    assert!(wd.next_ping(start + PING_INTERVAL).unwrap() > seq);
+}
+
+#[test]
+fn test_unanswered_ping_reports_hang_once() {
+    let start = Instant::now();
+    let mut wd = HangWatchdog::new(TIMEOUT);
+    let seq = wd.next_ping(start).unwrap();
+    assert_eq!(wd.check(start + secs(9)), None);
+    assert_eq!(wd.check(start + secs(10)), Some(WatchdogEvent::Hung { unresponsive_for: secs(10) }));
+    assert!(wd.is_hung());
+    assert_eq!(wd.check(start + secs(20)), None);
+
+    assert_eq!(wd.pong(seq, start + secs(25)), Some(WatchdogEvent::Recovered { after: secs(25) }));
+    assert!(!wd.is_hung());
+}
+
+#[test]
+fn test_wait_asks_again_after_another_timeout() {
+    let start = Instant::now();
+    let mut wd = HangWatchdog::new(TIMEOUT);
+    wd.next_ping(start).unwrap();
+    assert!(wd.check(start + secs(10)).is_some());
+    wd.wait(start + secs(12));
+    assert_eq!(wd.check(start + secs(21)), None);
+    assert_eq!(wd.check(start + secs(22)), Some(WatchdogEvent::Hung { unresponsive_for: secs(22) }));
+}
+
+#[test]
+fn test_reset_drops_stale_pings() {
+    let start = Instant::now();
+    let mut wd = HangWatchdog::new(TIMEOUT);
+    let stale = wd.next_ping(start).unwrap();
+    wd.reset();
+    assert_eq!(wd.check(start + secs(30)), None);
+    let seq = wd.next_ping(start + secs(30)).unwrap();
+    assert_eq!(wd.pong(stale, start + secs(31)), None);
+    assert_eq!(wd.check(start + secs(40)), Some(WatchdogEvent::Hung { unresponsive_for: secs(10) }));
+    assert!(wd.pong(seq, start + secs(41)).is_some());
+}
@@ -252,3 +252,17 @@ fn test_missing_key_returns_key() {
         "Missing key should return the key string itself"
     );
 }
+
+/// A reload that hits a broken file keeps the translations already loaded.
+#[test]
+fn test_failed_reload_keeps_loaded_locales() {
+    let dir = TempDir::new().unwrap();
+    setup_locales(dir.path());
+    let mut engine = initialized_engine(&dir);
+    engine.set_locale("ru").unwrap();
+
+    fs::write(dir.path().join("ru.json"), "{ not json").unwrap();
+    assert!(engine.reload().is_err());
+    assert_eq!(engine.t("tabs.new_tab", None), "Новая вкладка");
+    assert_eq!(engine.get_available_locales().len(), 2);
+}
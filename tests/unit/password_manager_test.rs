@@ -5,6 +5,7 @@
 //! Covers: TEST-07 from AUDIT.md Phase 3.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use gitbrowser::database::Database;
 use gitbrowser::services::password_manager::{PasswordManager, PasswordManagerTrait};
@@ -176,6 +177,30 @@ fn test_delete_credential() {
     assert_eq!(mgr.list_all_credentials().unwrap().len(), 0);
 }
 
+#[test]
+fn test_copy_password_needs_unlocked_vault_and_known_login() {
+    let mut mgr = setup();
+    mgr.unlock("master").unwrap();
+    let id = mgr.save_credential("https://x.com", "u", "p").unwrap();
+
+    assert!(mgr.copy_password("no-such-login", Duration::from_secs(30)).is_err());
+    mgr.lock();
+    assert!(mgr.copy_password(&id, Duration::from_secs(30)).is_err());
+}
+
+#[test]
+fn test_copy_unknown_login_is_not_found() {
+    use gitbrowser::types::errors::{CryptoError, ErrorKind, GitBrowserError};
+
+    let mut mgr = setup();
+    mgr.unlock("master").unwrap();
+    let err = mgr.copy_password("no-such-login", Duration::from_secs(30)).unwrap_err();
+    assert!(matches!(err, CryptoError::CredentialNotFound(ref id) if id == "no-such-login"));
+    let err = GitBrowserError::from(err);
+    assert_eq!(err.code(), "crypto.credential_not_found");
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
 // ─── Password Generation ───
 
 #[test]
@@ -242,3 +267,61 @@ fn test_generate_password_uniqueness() {
     // Two random passwords should almost certainly differ
     assert_ne!(pw1, pw2);
 }
+
+// ─── HTTP authentication logins ───
+
+#[test]
+fn test_origin_of() {
+    assert_eq!(PasswordManager::origin_of("https://User@Intranet.Example:443/a?b").as_deref(), Some("https://intranet.example"));
+    assert_eq!(PasswordManager::origin_of("http://host:8080/").as_deref(), Some("http://host:8080"));
+    assert_eq!(PasswordManager::origin_of("HTTP://host:80").as_deref(), Some("http://host"));
+    assert_eq!(PasswordManager::origin_of("gb://localhost/newtab"), None);
+    assert_eq!(PasswordManager::origin_of("https:///path"), None);
+}
+
+#[test]
+fn test_login_for_origin_saves_and_updates() {
+    let mut mgr = setup();
+    let origin = "https://intranet.example";
+    assert_eq!(mgr.login_for_origin(origin), None);
+
+    mgr.unlock("master").unwrap();
+    let id = mgr.save_or_update_credential(origin, "alice", "first").unwrap();
+    assert_eq!(mgr.login_for_origin(origin), Some(("alice".to_string(), "first".to_string())));
+
+    // Same username: the password changes in place
+    assert_eq!(mgr.save_or_update_credential(origin, "alice", "second").unwrap(), id);
+    assert_eq!(mgr.get_credentials(origin).unwrap().len(), 1);
+    assert_eq!(mgr.login_for_origin(origin), Some(("alice".to_string(), "second".to_string())));
+    assert_eq!(mgr.login_for_origin("https://other.example"), None);
+
+    mgr.lock();
+    assert_eq!(mgr.login_for_origin(origin), None);
+    assert!(mgr.save_or_update_credential(origin, "bob", "x").is_err());
+}
+
+// ─── Page autofill ───
+
+#[test]
+fn test_credentials_match_by_origin() {
+    let mut mgr = setup();
+    mgr.unlock("master").unwrap();
+    mgr.save_credential("https://example.com/login", "alice", "a-pass").unwrap();
+    mgr.save_credential("https://example.com:443/", "bob", "b-pass").unwrap();
+    mgr.save_credential("https://other.example/login", "carol", "c-pass").unwrap();
+
+    let users: Vec<String> = mgr.credentials_for_origin("https://example.com").unwrap().into_iter().map(|c| c.username).collect();
+    assert_eq!(users.len(), 2);
+    assert!(users.contains(&"alice".to_string()) && users.contains(&"bob".to_string()));
+    assert!(mgr.is_login_saved("https://example.com", "bob", "b-pass"));
+    assert!(!mgr.is_login_saved("https://example.com", "bob", "changed"));
+
+    // Saved from a form on another page of the site, the login is updated in place
+    mgr.save_or_update_credential("https://example.com/account", "alice", "new-pass").unwrap();
+    assert_eq!(mgr.credentials_for_origin("https://example.com").unwrap().len(), 2);
+    assert!(mgr.is_login_saved("https://example.com", "alice", "new-pass"));
+
+    mgr.lock();
+    assert!(mgr.credentials_for_origin("https://example.com").is_err());
+    assert!(!mgr.is_login_saved("https://example.com", "bob", "b-pass"));
+}
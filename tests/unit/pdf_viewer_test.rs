@@ -0,0 +1,65 @@
+//! Unit tests for the built-in PDF viewer's helpers: spotting PDFs, the
+//! viewer page URL, and serving pdf.js files.
+
+use gitbrowser::services::pdf_viewer::{has_pdf_signature, is_pdf, source_from_query, viewer_url, PdfJs, PDFJS_FILES};
+
+#[test]
+fn test_pdfs_are_spotted_by_content_type_or_extension() {
+    assert!(is_pdf("https://example.com/paper.PDF", None));
+    assert!(is_pdf("https://example.com/paper.pdf?download=0#page=2", None));
+    assert!(!is_pdf("https://example.com/pdf/", None));
+    assert!(!is_pdf("https://example.com/paper.pdf.html", None));
+    assert!(!is_pdf("file:///home/me/paper.pdf", None));
+    // The content type decides when there is one
+    assert!(is_pdf("https://example.com/get?id=7", Some("application/pdf; qs=0.001")));
+    assert!(!is_pdf("https://example.com/paper.pdf", Some("text/html")));
+
+    assert!(has_pdf_signature(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3"));
+    assert!(has_pdf_signature(b"\xef\xbb\xbf%PDF-1.4"));
+    assert!(!has_pdf_signature(b"<!DOCTYPE html>"));
+}
+
+#[test]
+fn test_viewer_url_round_trips_the_source() {
+    let src = "https://example.com/a b/report.pdf?x=1&y=\u{e9}";
+    let url = viewer_url(src);
+    assert!(url.starts_with("gb://localhost/pdf?src="));
+    let query = url.split_once('?').unwrap().1;
+    assert_eq!(source_from_query(query).as_deref(), Some(src));
+    // Only web PDFs are fetched for the viewer
+    assert_eq!(source_from_query("src=file%3A%2F%2F%2Fetc%2Fpasswd"), None);
+    assert_eq!(source_from_query("page=2"), None);
+}
+
+#[test]
+fn test_pdfjs_serves_only_its_own_files() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let pdfjs = PdfJs::new(tmp.path().join("pdfjs"));
+    assert!(!pdfjs.is_installed());
+
+    std::fs::create_dir_all(pdfjs.dir()).unwrap();
+    for name in PDFJS_FILES {
+        std::fs::write(pdfjs.dir().join(name), "// pdf.js").unwrap();
+    }
+    std::fs::write(tmp.path().join("secret.js"), "nope").unwrap();
+    assert!(pdfjs.is_installed());
+
+    let (bytes, content_type) = pdfjs.asset("pdf.min.js").unwrap();
+    assert_eq!(bytes, b"// pdf.js");
+    assert_eq!(content_type, "text/javascript");
+    assert!(pdfjs.asset("../secret.js").is_none());
+    assert!(pdfjs.asset("..\\secret.js").is_none());
+    assert!(pdfjs.asset("missing.js").is_none());
+    assert!(pdfjs.asset("README.md").is_none());
+}
+
+#[test]
+fn test_bundled_pdfjs_matches_the_feature() {
+    assert_eq!(PdfJs::bundled().is_some(), cfg!(feature = "bundled-pdfjs"));
+    let Some(pdfjs) = PdfJs::bundled() else { return };
+    assert!(pdfjs.is_installed());
+    for name in PDFJS_FILES {
+        assert!(!pdfjs.asset(name).unwrap().0.is_empty());
+    }
+    assert!(pdfjs.asset("../Cargo.toml").is_none());
+}
@@ -1,10 +1,12 @@
 //! Unit tests for the Privacy Engine.
 //!
-//! Tests tracker blocking, HTTPS upgrade, private mode, and DNS-over-HTTPS config.
+//! Tests tracker blocking, extension blocking rules, custom filters, site
+//! exceptions, HTTPS upgrade, private mode, and DNS-over-HTTPS config.
 //!
 //! Covers: TEST-06 from AUDIT.md Phase 3.
 
-use gitbrowser::services::privacy_engine::{PrivacyEngine, PrivacyEngineTrait};
+use gitbrowser::services::privacy_engine::{url_filter_matches, PrivacyEngine, PrivacyEngineTrait};
+use gitbrowser::types::privacy::{NetworkRule, NetworkRuleAction};
 
 fn setup() -> PrivacyEngine {
     let mut engine = PrivacyEngine::new();
@@ -132,3 +134,203 @@ fn test_initial_stats_zero() {
     assert_eq!(stats.trackers_blocked, 0);
     assert_eq!(stats.https_upgrades, 0);
 }
+
+// ─── Settings ───
+
+#[test]
+fn test_apply_settings_disables_blocking_and_https() {
+    use gitbrowser::types::settings::PrivacySettings;
+    let mut engine = setup();
+    let settings = PrivacySettings {
+        tracker_blocking: false,
+        ad_blocking: false,
+        https_enforcement: false,
+        ..PrivacySettings::default()
+    };
+    engine.apply_settings(&settings);
+    assert!(!engine.should_block_request("https://www.google-analytics.com/analytics.js", "script"));
+    assert!(!engine.should_block_request("https://example.com/ads/banner.js", "script"));
+    assert!(engine.upgrade_to_https("http://example.com").is_none());
+}
+
+#[test]
+fn test_apply_settings_ad_blocking_independent_of_trackers() {
+    use gitbrowser::types::settings::PrivacySettings;
+    let mut engine = setup();
+    let settings = PrivacySettings { tracker_blocking: false, ..PrivacySettings::default() };
+    engine.apply_settings(&settings);
+    assert!(!engine.should_block_request("https://www.google-analytics.com/analytics.js", "script"));
+    assert!(engine.should_block_request("https://example.com/ads/banner.js", "script"));
+}
+
+// ─── Extension Rules ───
+
+fn rule(id: u32, priority: u32, action: NetworkRuleAction, url_filter: &str) -> NetworkRule {
+    NetworkRule {
+        extension_id: "blocker".to_string(),
+        rule_id: id,
+        priority,
+        action,
+        url_filter: Some(url_filter.to_string()),
+        resource_types: Vec::new(),
+        excluded_resource_types: Vec::new(),
+        request_domains: Vec::new(),
+        excluded_request_domains: Vec::new(),
+    }
+}
+
+#[test]
+fn test_url_filter_syntax() {
+    assert!(url_filter_matches("||ads.example.com^", "https://ads.example.com/x.js"));
+    assert!(url_filter_matches("||example.com^", "https://cdn.example.com/"));
+    assert!(!url_filter_matches("||example.com^", "https://notexample.com/"));
+    assert!(!url_filter_matches("||example.com", "https://a.com/?u=example.com"));
+    assert!(url_filter_matches("|https://a.com/x|", "https://a.com/x"));
+    assert!(!url_filter_matches("|https://a.com/x|", "https://a.com/xy"));
+    assert!(url_filter_matches("/banner/*.gif", "https://x.org/banner/big.gif"));
+    assert!(!url_filter_matches("ads^", "https://x.org/adsy"));
+    assert!(url_filter_matches("ADS^", "https://x.org/ads?id=1"));
+    assert!(url_filter_matches("||example.com/*.js|", "https://cdn.example.com/a/b.js"));
+    assert!(!url_filter_matches("||example.com/*.js|", "https://cdn.example.com/a/b.json"));
+    assert!(url_filter_matches("banner^*^", "https://x.org/banner/"));
+}
+
+#[test]
+fn test_url_filter_wildcards_do_not_backtrack_exponentially() {
+    let mut engine = setup();
+    engine.set_extension_rules(vec![rule(1, 1, NetworkRuleAction::Block, "*a*a*a*a*a*a*a*a*a*a*b")]);
+    let url = format!("https://x.org/{}", "a".repeat(4000));
+    let started = std::time::Instant::now();
+    assert!(!engine.should_block_request(&url, "script"));
+    assert!(engine.should_block_request(&format!("{}b", url), "script"));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+fn test_extension_block_rule_applies() {
+    let mut engine = setup();
+    assert!(!engine.should_block_request("https://cdn.example.com/widget.js", "script"));
+    engine.set_extension_rules(vec![rule(1, 1, NetworkRuleAction::Block, "||cdn.example.com^")]);
+    assert_eq!(engine.extension_rule_count(), 1);
+    assert!(engine.should_block_request("https://cdn.example.com/widget.js", "script"));
+    // Without resource types a rule does not apply to top-level navigations
+    assert!(!engine.should_block_request("https://cdn.example.com/", "main_frame"));
+}
+
+#[test]
+fn test_extension_allow_rule_overrides_block_and_lists() {
+    let mut engine = setup();
+    engine.set_extension_rules(vec![
+        rule(1, 1, NetworkRuleAction::Block, "||example.com^"),
+        rule(2, 1, NetworkRuleAction::Allow, "||example.com/ads/"),
+        rule(3, 2, NetworkRuleAction::Allow, "||google-analytics.com^"),
+    ]);
+    assert!(engine.should_block_request("https://example.com/app.js", "script"));
+    // Allow wins at equal priority
+    assert!(!engine.should_block_request("https://example.com/ads/banner.js", "script"));
+    // Allow also exempts requests the built-in tracker list would block
+    assert!(!engine.should_block_request("https://www.google-analytics.com/analytics.js", "script"));
+
+    engine.set_extension_rules(vec![
+        rule(1, 5, NetworkRuleAction::Block, "||example.com^"),
+        rule(2, 1, NetworkRuleAction::Allow, "||example.com/ads/"),
+    ]);
+    assert!(engine.should_block_request("https://example.com/ads/banner.js", "script"));
+}
+
+#[test]
+fn test_extension_rule_conditions() {
+    let mut engine = setup();
+    let mut scripts_only = rule(1, 1, NetworkRuleAction::Block, "/widget");
+    scripts_only.resource_types = vec!["script".to_string()];
+    scripts_only.request_domains = vec!["example.com".to_string()];
+    scripts_only.excluded_request_domains = vec!["safe.example.com".to_string()];
+    engine.set_extension_rules(vec![scripts_only]);
+
+    assert!(engine.should_block_request("https://www.example.com/widget.js", "script"));
+    assert!(!engine.should_block_request("https://www.example.com/widget.png", "image"));
+    assert!(!engine.should_block_request("https://other.org/widget.js", "script"));
+    assert!(!engine.should_block_request("https://safe.example.com/widget.js", "script"));
+}
+
+#[test]
+fn test_extension_rules_ignore_ad_blocking_setting() {
+    use gitbrowser::types::settings::PrivacySettings;
+    let mut engine = setup();
+    let settings = PrivacySettings { ad_blocking: false, tracker_blocking: false, ..PrivacySettings::default() };
+    engine.apply_settings(&settings);
+    engine.set_extension_rules(vec![rule(1, 1, NetworkRuleAction::Block, "||cdn.example.com^")]);
+    assert!(engine.should_block_request("https://cdn.example.com/a.js", "script"));
+
+    engine.record_blocked("https://cdn.example.com/a.js");
+    assert_eq!(engine.get_stats().extension_rules_blocked, 1);
+    assert_eq!(engine.get_stats().ads_blocked, 0);
+}
+
+#[test]
+fn test_blocks_are_counted_per_site() {
+    let mut engine = setup();
+    engine.record_blocked_on_site("https://doubleclick.net/ad.js", Some("News.example"));
+    engine.record_blocked_on_site("https://google-analytics.com/ga.js", Some("news.example"));
+    engine.record_blocked_on_site("https://doubleclick.net/ad.js", None);
+    let stats = engine.get_stats();
+    assert_eq!(stats.trackers_blocked, 3);
+    assert_eq!(stats.blocked_by_site.get("news.example"), Some(&2));
+    assert_eq!(stats.blocked_by_site.len(), 1);
+}
+
+#[test]
+fn test_builtin_filter_lists_report_their_revision() {
+    let engine = setup();
+    let lists = engine.filter_lists();
+    let trackers = lists.iter().find(|l| l.id == "trackers").unwrap();
+    assert_eq!(trackers.updated_at, Some(gitbrowser::services::privacy_engine::BUILTIN_LISTS_UPDATED_AT));
+    assert_eq!(lists.iter().find(|l| l.id == "custom").unwrap().updated_at, None);
+}
+
+// ─── Custom filters and site exceptions ───
+
+#[test]
+fn test_custom_filters_block_and_are_counted() {
+    use gitbrowser::types::settings::PrivacySettings;
+    let mut engine = setup();
+    let settings = PrivacySettings {
+        custom_filters: vec!["||cdn.example.org^".to_string()],
+        ..PrivacySettings::default()
+    };
+    engine.apply_settings(&settings);
+    assert!(engine.should_block_request("https://cdn.example.org/lib.js", "script"));
+    assert!(!engine.should_block_request("https://example.org/lib.js", "script"));
+
+    engine.record_blocked("https://cdn.example.org/lib.js");
+    assert_eq!(engine.get_stats().custom_filters_blocked, 1);
+    assert_eq!(engine.get_stats().extension_rules_blocked, 0);
+
+    let lists = engine.filter_lists();
+    let custom = lists.iter().find(|l| l.id == "custom").unwrap();
+    assert_eq!(custom.entries, 1);
+}
+
+#[test]
+fn test_disabled_sites_block_nothing() {
+    let mut engine = setup();
+    let tracker = "https://www.google-analytics.com/analytics.js";
+    engine.set_disabled_sites(vec!["News.Example".to_string()]);
+    assert!(engine.is_site_disabled("news.example"));
+
+    assert!(!engine.should_block_on_site(tracker, "script", Some("news.example")));
+    assert!(engine.should_block_on_site(tracker, "script", Some("other.example")));
+    assert!(engine.should_block_on_site(tracker, "script", None));
+}
+
+#[test]
+fn test_filter_lists_follow_settings() {
+    use gitbrowser::types::settings::PrivacySettings;
+    let mut engine = setup();
+    engine.apply_settings(&PrivacySettings { ad_blocking: false, ..PrivacySettings::default() });
+    let lists = engine.filter_lists();
+    let enabled = |id: &str| lists.iter().find(|l| l.id == id).unwrap().enabled;
+    assert!(enabled("trackers"));
+    assert!(!enabled("ads"));
+    assert!(lists.iter().all(|l| l.id == "custom" || l.entries > 0));
+}
@@ -0,0 +1,106 @@
+//! Unit tests for user profiles and their locking.
+
+use gitbrowser::app::App;
+use gitbrowser::managers::profile_manager::{ProfileManager, ProfileManagerTrait};
+use gitbrowser::types::errors::ProfileError;
+use tempfile::TempDir;
+
+fn setup() -> (ProfileManager, TempDir) {
+    let dir = TempDir::new().unwrap();
+    (ProfileManager::new(dir.path().join("profiles")), dir)
+}
+
+#[test]
+fn test_create_and_list_profiles() {
+    let (profiles, _dir) = setup();
+    assert!(profiles.list_profiles().unwrap().is_empty());
+
+    let work = profiles.create_profile("work").unwrap();
+    profiles.create_profile("personal").unwrap();
+    assert!(work.paths.dir.is_dir());
+    assert!(work.paths.cache.is_dir());
+    assert!(!work.in_use);
+
+    let names: Vec<String> = profiles.list_profiles().unwrap().into_iter().map(|p| p.name).collect();
+    assert_eq!(names, vec!["personal", "work"]);
+    assert!(matches!(profiles.create_profile("work"), Err(ProfileError::AlreadyExists(_))));
+}
+
+#[test]
+fn test_profile_names_are_restricted() {
+    let (profiles, _dir) = setup();
+    for name in ["", "../escape", "a/b", ".hidden", &"x".repeat(65)] {
+        assert!(matches!(profiles.create_profile(name), Err(ProfileError::InvalidName(_))), "{:?}", name);
+    }
+}
+
+#[test]
+fn test_locked_profile_is_in_use_until_released() {
+    let (profiles, _dir) = setup();
+    profiles.create_profile("work").unwrap();
+
+    let lock = profiles.lock_profile("work").unwrap();
+    assert!(profiles.get_profile("work").unwrap().in_use);
+    assert!(matches!(profiles.lock_profile("work"), Err(ProfileError::InUse(_))));
+    assert!(matches!(profiles.delete_profile("work"), Err(ProfileError::InUse(_))));
+
+    drop(lock);
+    assert!(!profiles.get_profile("work").unwrap().in_use);
+    profiles.delete_profile("work").unwrap();
+    assert!(matches!(profiles.get_profile("work"), Err(ProfileError::NotFound(_))));
+}
+
+#[test]
+fn test_app_opens_profile_with_its_own_data_and_lock() {
+    let (profiles, _dir) = setup();
+    let app = App::open_profile(&profiles, "work", None).expect("open_profile failed");
+    let profile = app.profile().unwrap().clone();
+    assert_eq!(profile.name, "work");
+    assert!(profile.paths.database.exists());
+
+    // A second instance is kept out while the first has the profile open
+    let second = App::open_profile(&profiles, "work", None);
+    let err = second.err().expect("second open must fail");
+    assert!(matches!(err.downcast_ref::<ProfileError>(), Some(ProfileError::InUse(_))));
+
+    drop(app);
+    assert!(App::open_profile(&profiles, "work", None).is_ok());
+}
+
+#[test]
+fn test_profiles_open_side_by_side() {
+    use gitbrowser::managers::bookmark_manager::{BookmarkManager, BookmarkManagerTrait};
+    let (profiles, _dir) = setup();
+    let work = App::open_profile(&profiles, "work", None).unwrap();
+    let home = App::open_profile(&profiles, "home", None).unwrap();
+    assert_eq!(work.context().name, "work");
+    assert_eq!(work.context().paths.crashes, profiles.paths("work").crashes);
+    assert_eq!(home.context().paths.themes, profiles.paths("home").themes);
+
+    BookmarkManager::new(work.db.connection()).add_bookmark("https://example.com", "Example", None).unwrap();
+    assert!(BookmarkManager::new(home.db.connection()).search_bookmarks("Example").unwrap().is_empty());
+}
+
+#[test]
+fn test_private_app_writes_nothing() {
+    use gitbrowser::managers::bookmark_manager::{BookmarkManager, BookmarkManagerTrait};
+    use gitbrowser::services::privacy_engine::PrivacyEngineTrait;
+    use gitbrowser::services::settings_engine::SettingsEngineTrait;
+    let (profiles, _dir) = setup();
+    let mut work = App::open_profile(&profiles, "work", None).unwrap();
+    work.settings_engine.set_value("general.language", serde_json::json!("de")).unwrap();
+
+    let mut private = work.open_private().unwrap();
+    assert!(private.context().private);
+    assert!(private.privacy_engine.is_private_mode());
+    assert!(!work.privacy_engine.is_private_mode());
+    assert_eq!(private.settings_engine.get_settings().general.language, "de");
+
+    let settings = std::fs::read_to_string(&profiles.paths("work").settings).unwrap();
+    private.settings_engine.set_value("general.language", serde_json::json!("fr")).unwrap();
+    assert_eq!(std::fs::read_to_string(&profiles.paths("work").settings).unwrap(), settings);
+
+    BookmarkManager::new(private.db.connection()).add_bookmark("https://example.com", "Example", None).unwrap();
+    assert!(BookmarkManager::new(work.db.connection()).search_bookmarks("Example").unwrap().is_empty());
+    assert!(private.run_scheduled_backup().is_none());
+}
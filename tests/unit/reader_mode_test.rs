@@ -0,0 +1,156 @@
+//! Unit tests for ReaderMode display formatting and multi-page stitching.
+//!
+//! These tests check that `format_for_display` honors the reader appearance
+//! settings (presets, column width, justification) and clamps bad values, and
+//! that paginated articles are followed and concatenated within the page cap.
+
+use gitbrowser::services::reader_mode::{ReaderMode, ReaderModeTrait};
+use gitbrowser::types::reader::{ReaderContent, ReaderPreset, ReaderSettings};
+
+fn content() -> ReaderContent {
+    ReaderContent {
+        title: "Title".to_string(),
+        content: "<p>Body</p>".to_string(),
+        text_content: "Body".to_string(),
+        author: None,
+        publish_date: None,
+        site_name: None,
+        estimated_read_time_minutes: 1,
+        page_count: 1,
+    }
+}
+
+#[test]
+fn test_default_preset_uses_background_color() {
+    let mode = ReaderMode::new();
+    let settings = ReaderSettings { background_color: "#fafafa".to_string(), ..ReaderSettings::default() };
+    let html = mode.format_for_display(&content(), &settings);
+    assert!(html.contains("background: #fafafa"));
+    assert!(html.contains("text-align: left"));
+}
+
+#[test]
+fn test_presets_override_background() {
+    let mode = ReaderMode::new();
+    let sepia = ReaderSettings { preset: ReaderPreset::Sepia, ..ReaderSettings::default() };
+    assert!(mode.format_for_display(&content(), &sepia).contains("background: #f4ecd8"));
+
+    let contrast = ReaderSettings { preset: ReaderPreset::HighContrast, ..ReaderSettings::default() };
+    let html = mode.format_for_display(&content(), &contrast);
+    assert!(html.contains("background: #000000"));
+    assert!(html.contains("color: #ffffff"));
+}
+
+#[test]
+fn test_column_width_and_justify() {
+    let mode = ReaderMode::new();
+    let settings = ReaderSettings { column_width: 55, justify: true, ..ReaderSettings::default() };
+    let html = mode.format_for_display(&content(), &settings);
+    assert!(html.contains("max-width: min(680px, 55ch)"));
+    assert!(html.contains("text-align: justify"));
+}
+
+#[test]
+fn test_invalid_values_are_sanitized() {
+    let mode = ReaderMode::new();
+    let settings = ReaderSettings {
+        background_color: "red;} body{display:none".to_string(),
+        font_size: 500,
+        column_width: 5,
+        ..ReaderSettings::default()
+    };
+    let html = mode.format_for_display(&content(), &settings);
+    assert!(!html.contains("display:none"));
+    assert!(html.contains("font-size: 40px"));
+    assert!(html.contains("40ch"));
+}
+
+#[test]
+fn test_with_settings_uses_persisted_values() {
+    let settings = ReaderSettings { preset: ReaderPreset::Dark, ..ReaderSettings::default() };
+    let mode = ReaderMode::with_settings(settings.clone());
+    assert_eq!(mode.get_settings(), &settings);
+}
+
+// ─── Multi-page stitching ───
+
+fn page(body: &str, next: Option<&str>) -> String {
+    let link = next.map(|n| format!("<link rel=\"next\" href=\"{}\">", n)).unwrap_or_default();
+    format!(
+        "<html><head><title>Paged</title>{}</head><body><article><p>{} {}</p></article></body></html>",
+        link,
+        body,
+        "filler text that makes this page long enough to count as an article body for reader mode. ".repeat(2)
+    )
+}
+
+#[test]
+fn test_find_next_page_url_rel_next_and_link_text() {
+    let mode = ReaderMode::new();
+    let html = "<head><link rel=\"next\" href=\"/post?page=2\"></head>";
+    assert_eq!(
+        mode.find_next_page_url(html, "https://example.com/post").as_deref(),
+        Some("https://example.com/post?page=2")
+    );
+
+    let html = "<body><a href=\"page3.html\">Next page »</a></body>";
+    assert_eq!(
+        mode.find_next_page_url(html, "https://example.com/a/page2.html").as_deref(),
+        Some("https://example.com/a/page3.html")
+    );
+
+    assert!(mode.find_next_page_url("<a href=\"javascript:next()\" rel=\"next\">x</a>", "https://example.com/").is_none());
+    assert!(mode.find_next_page_url("<a href=\"/about\">About</a>", "https://example.com/").is_none());
+}
+
+#[test]
+fn test_extract_multipage_stitches_pages() {
+    let mode = ReaderMode::new();
+    let first = page("First", Some("/p2"));
+    let mut fetched = Vec::new();
+    let content = mode
+        .extract_multipage(&first, "https://example.com/p1", 10, |url| {
+            fetched.push(url.to_string());
+            match url {
+                "https://example.com/p2" => Some(page("Second", Some("/p3"))),
+                "https://example.com/p3" => Some(page("Third", None)),
+                _ => None,
+            }
+        })
+        .unwrap();
+
+    assert_eq!(content.page_count, 3);
+    assert_eq!(fetched, vec!["https://example.com/p2", "https://example.com/p3"]);
+    assert!(content.content.contains("Second") && content.content.contains("Third"));
+    assert!(content.content.contains("Page 3 of 3"));
+    assert!(mode.format_for_display(&content, mode.get_settings()).contains("3 pages"));
+}
+
+#[test]
+fn test_extract_multipage_respects_cap_loops_and_hosts() {
+    let mode = ReaderMode::new();
+
+    // Cap: only two pages even though every page links onward
+    let content = mode
+        .extract_multipage(&page("1", Some("/next")), "https://example.com/1", 2, |u| {
+            Some(page(u, Some(&format!("{}x", u))))
+        })
+        .unwrap();
+    assert_eq!(content.page_count, 2);
+
+    // Loop: page 2 points back to page 1
+    let content = mode
+        .extract_multipage(&page("1", Some("/2")), "https://example.com/1", 10, |_| {
+            Some(page("2", Some("/1")))
+        })
+        .unwrap();
+    assert_eq!(content.page_count, 2);
+
+    // Cross-host next links are never fetched
+    let content = mode
+        .extract_multipage(&page("1", Some("https://other.example/2")), "https://example.com/1", 10, |_| {
+            panic!("must not fetch another host")
+        })
+        .unwrap();
+    assert_eq!(content.page_count, 1);
+}
@@ -0,0 +1,126 @@
+//! Unit tests for the ReadingListManager public API.
+//!
+//! These tests exercise saving, listing, read-state tracking and image
+//! inlining through the `ReadingListManagerTrait` interface, using an
+//! in-memory SQLite database.
+
+use std::collections::HashMap;
+
+use gitbrowser::database::Database;
+use gitbrowser::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+use gitbrowser::types::reader::ReaderContent;
+
+fn article(title: &str, html: &str) -> ReaderContent {
+    ReaderContent {
+        title: title.to_string(),
+        content: html.to_string(),
+        text_content: "Some article text".to_string(),
+        author: None,
+        publish_date: None,
+        site_name: Some("Example".to_string()),
+        estimated_read_time_minutes: 3,
+        page_count: 1,
+    }
+}
+
+#[test]
+fn test_save_and_get_article() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = ReadingListManager::new(db.connection());
+
+    let id = mgr.save_article("https://example.com/a", &article("A", "<p>Hello</p>"), None).unwrap();
+    let item = mgr.get_item(&id).unwrap();
+
+    assert_eq!(item.url, "https://example.com/a");
+    assert_eq!(item.title, "A");
+    assert_eq!(item.content_html, "<p>Hello</p>");
+    assert_eq!(item.site_name.as_deref(), Some("Example"));
+    assert!(!item.images_inlined);
+    assert!(item.read_at.is_none());
+}
+
+#[test]
+fn test_resaving_url_updates_existing_item() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = ReadingListManager::new(db.connection());
+
+    let first = mgr.save_article("https://example.com/a", &article("Old", "<p>1</p>"), None).unwrap();
+    mgr.mark_read(&first, true).unwrap();
+    let second = mgr.save_article("https://example.com/a", &article("New", "<p>2</p>"), None).unwrap();
+
+    assert_eq!(first, second);
+    let (items, total) = mgr.list_items(false, 100, 0).unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(items[0].title, "New");
+    assert!(items[0].read_at.is_none(), "Re-saving should mark the article unread");
+}
+
+#[test]
+fn test_stitched_page_count_is_kept() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = ReadingListManager::new(db.connection());
+
+    let mut stitched = article("A", "<p>1</p><p>2</p><p>3</p>");
+    stitched.page_count = 3;
+    let id = mgr.save_article("https://example.com/a", &stitched, None).unwrap();
+    assert_eq!(mgr.get_item(&id).unwrap().page_count, 3);
+
+    mgr.save_article("https://example.com/a", &article("A", "<p>1</p>"), None).unwrap();
+    assert_eq!(mgr.get_item(&id).unwrap().page_count, 1);
+}
+
+#[test]
+fn test_mark_read_and_unread_filter() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = ReadingListManager::new(db.connection());
+
+    let a = mgr.save_article("https://example.com/a", &article("A", "<p>a</p>"), None).unwrap();
+    mgr.save_article("https://example.com/b", &article("B", "<p>b</p>"), None).unwrap();
+    mgr.mark_read(&a, true).unwrap();
+
+    let (unread, total) = mgr.list_items(true, 100, 0).unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(unread[0].title, "B");
+
+    mgr.mark_read(&a, false).unwrap();
+    let (_, total) = mgr.list_items(true, 100, 0).unwrap();
+    assert_eq!(total, 2);
+}
+
+#[test]
+fn test_remove_item() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = ReadingListManager::new(db.connection());
+
+    let id = mgr.save_article("https://example.com/a", &article("A", "<p>a</p>"), None).unwrap();
+    mgr.remove_item(&id).unwrap();
+
+    assert!(mgr.get_item(&id).is_err());
+    assert!(mgr.remove_item(&id).is_err(), "Removing twice should report NotFound");
+}
+
+#[test]
+fn test_images_are_inlined_when_provided() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = ReadingListManager::new(db.connection());
+
+    let mut images = HashMap::new();
+    images.insert("/img/a.png".to_string(), "data:image/png;base64,AAAA".to_string());
+    let html = r#"<p>x</p><img src="/img/a.png"><img src="/img/b.png">"#;
+    let id = mgr.save_article("https://example.com/a", &article("A", html), Some(&images)).unwrap();
+
+    let item = mgr.get_item(&id).unwrap();
+    assert!(item.images_inlined);
+    assert!(item.content_html.contains(r#"src="data:image/png;base64,AAAA""#));
+    assert!(item.content_html.contains(r#"src="/img/b.png""#), "Unmapped images keep their source");
+}
+
+#[test]
+fn test_inline_images_ignores_non_image_data_uris() {
+    let mut images = HashMap::new();
+    images.insert("a.png".to_string(), "data:text/html,<script>alert(1)</script>".to_string());
+
+    let (html, inlined) = ReadingListManager::inline_images(r#"<img src="a.png">"#, &images);
+    assert!(!inlined);
+    assert_eq!(html, r#"<img src="a.png">"#);
+}
@@ -0,0 +1,61 @@
+//! Unit tests for the RPC socket authentication token and handshake.
+
+use gitbrowser::rpc_auth::{handshake, AuthToken};
+use gitbrowser::rpc_protocol::UNAUTHORIZED;
+use serde_json::json;
+use tempfile::TempDir;
+
+#[test]
+fn test_tokens_are_fresh_and_verified_exactly() {
+    let token = AuthToken::generate();
+    assert_eq!(token.as_str().len(), 64);
+    assert_ne!(token.as_str(), AuthToken::generate().as_str());
+
+    assert!(token.verify(token.as_str()));
+    assert!(!token.verify(""));
+    assert!(!token.verify(&token.as_str()[..63]));
+    assert!(!token.verify(&token.as_str().to_uppercase()));
+}
+
+#[test]
+fn test_handshake_accepts_the_token() {
+    let token = AuthToken::generate();
+    let line = json!({"jsonrpc": "2.0", "id": 1, "method": "auth", "params": {"token": token.as_str()}}).to_string();
+    assert_eq!(handshake(&token, &line), Ok(Some(json!({"jsonrpc": "2.0", "id": 1, "result": {"ok": true}}))));
+
+    let legacy = json!({"id": 2, "method": "auth", "params": {"token": token.as_str()}}).to_string();
+    assert_eq!(handshake(&token, &legacy), Ok(Some(json!({"id": 2, "result": {"ok": true}}))));
+}
+
+#[test]
+fn test_handshake_rejects_everything_else() {
+    let token = AuthToken::generate();
+    let wrong = handshake(&token, r#"{"jsonrpc":"2.0","id":1,"method":"auth","params":{"token":"guess"}}"#);
+    assert_eq!(wrong.unwrap_err().unwrap()["error"]["code"], UNAUTHORIZED);
+
+    // A method before authenticating is not dispatched
+    let early = json!({"jsonrpc": "2.0", "id": 1, "method": "password.decrypt", "params": {"token": token.as_str()}});
+    assert_eq!(handshake(&token, &early.to_string()).unwrap_err().unwrap()["error"]["code"], UNAUTHORIZED);
+
+    let batch = json!([{"jsonrpc": "2.0", "id": 1, "method": "auth", "params": {"token": token.as_str()}}]);
+    assert!(handshake(&token, &batch.to_string()).is_err());
+    assert!(handshake(&token, "not json").is_err());
+}
+
+#[test]
+fn test_token_file_is_private_and_removed_on_drop() {
+    let tmp = TempDir::new().unwrap();
+    let token = AuthToken::generate();
+    let file = token.write_file(tmp.path()).unwrap();
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), token.as_str());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(file.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let path = file.path().to_path_buf();
+    drop(file);
+    assert!(!path.exists());
+}
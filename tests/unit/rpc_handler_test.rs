@@ -11,7 +11,7 @@ use tempfile::TempDir;
 
 use gitbrowser::app::App;
 use gitbrowser::managers::bookmark_manager::BookmarkManagerTrait;
-use gitbrowser::rpc_handler::handle_method;
+use gitbrowser::rpc_handler::{complete_device_login, handle_method};
 
 /// Create a fresh App backed by a temp directory DB.
 fn setup() -> (Mutex<App>, TempDir) {
@@ -21,6 +21,38 @@ fn setup() -> (Mutex<App>, TempDir) {
     (Mutex::new(app), tmp)
 }
 
+/// Like `setup`, with settings and extensions kept in the temp directory
+/// too, for tests that change settings.
+fn setup_isolated() -> (Mutex<App>, TempDir) {
+    use gitbrowser::managers::profile_manager::ProfileManager;
+    let tmp = TempDir::new().expect("Failed to create temp dir");
+    let app = App::open_profile(&ProfileManager::new(tmp.path().to_path_buf()), "test", None)
+        .expect("Failed to init App");
+    (Mutex::new(app), tmp)
+}
+
+/// Runs streaming `method` as request 1 and returns its result and the
+/// `params` of each part it sent.
+fn call_streaming(
+    app: &Mutex<App>,
+    method: &str,
+    params: serde_json::Value,
+) -> (Result<serde_json::Value, gitbrowser::types::errors::GitBrowserError>, Vec<serde_json::Value>) {
+    use gitbrowser::rpc_handler::handle_streaming;
+    use gitbrowser::rpc_protocol::{parse_line, Incoming};
+    use gitbrowser::rpc_stream::Stream;
+    use std::sync::Arc;
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let deliver: gitbrowser::services::event_hub::Deliver =
+        Arc::new(move |value: &serde_json::Value| sink.lock().unwrap().push(value["params"].clone()));
+    let line = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}).to_string();
+    let Ok(Incoming::Single(Ok(call))) = parse_line(&line) else { panic!("expected a call") };
+    let result = handle_streaming(app, method, &call.params, &mut Stream::new(&call, deliver)).expect("not streaming");
+    let parts = received.lock().unwrap().clone();
+    (result, parts)
+}
+
 // ─── Ping ───
 
 #[test]
@@ -30,6 +62,13 @@ fn test_ping() {
     assert_eq!(res, json!({"pong": true}));
 }
 
+#[test]
+fn test_notify_show_needs_title() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "notify.show", &json!({"body": "Done"}));
+    assert!(res.unwrap_err().to_string().contains("title"));
+}
+
 // ─── Unknown method ───
 
 #[test]
@@ -37,7 +76,9 @@ fn test_unknown_method_returns_error() {
     let (app, _tmp) = setup();
     let res = handle_method(&app, "nonexistent.method", &json!({}));
     assert!(res.is_err());
-    assert!(res.unwrap_err().contains("unknown method"));
+    let err = res.unwrap_err();
+    assert!(err.to_string().contains("unknown method"));
+    assert_eq!(err.code(), "method_not_found");
 }
 
 // ─── Bookmarks ───
@@ -67,7 +108,7 @@ fn test_bookmark_add_invalid_url() {
         "title": "Bad"
     }));
     assert!(res.is_err());
-    assert!(res.unwrap_err().contains("invalid url"));
+    assert!(res.unwrap_err().to_string().contains("invalid url"));
 }
 
 #[test]
@@ -128,6 +169,30 @@ fn test_bookmark_list_with_folder() {
     assert_eq!(root["items"].as_array().unwrap()[0]["title"], "Root");
 }
 
+#[test]
+fn test_bookmark_and_history_listings_carry_cached_favicons() {
+    use gitbrowser::managers::favicon_manager::{FaviconManager, FaviconManagerTrait};
+    let (app, _tmp) = setup();
+    handle_method(&app, "bookmark.add", &json!({"url": "https://icons.example/a", "title": "A"})).unwrap();
+    handle_method(&app, "bookmark.add", &json!({"url": "https://plain.example/", "title": "B"})).unwrap();
+    handle_method(&app, "history.record", &json!({"url": "https://icons.example/b", "title": "B"})).unwrap();
+    {
+        let a = app.lock().unwrap();
+        FaviconManager::new(a.db.connection())
+            .save_favicon("icons.example", "https://icons.example/favicon.ico", "data:image/x-icon;base64,AAAA")
+            .unwrap();
+    }
+
+    let listed = handle_method(&app, "bookmark.list", &json!({})).unwrap();
+    let favicon = |url: &str| {
+        listed["items"].as_array().unwrap().iter().find(|b| b["url"] == url).unwrap()["favicon"].clone()
+    };
+    assert_eq!(favicon("https://icons.example/a"), "data:image/x-icon;base64,AAAA");
+    assert!(favicon("https://plain.example/").is_null());
+    let recent = handle_method(&app, "history.recent", &json!({})).unwrap();
+    assert_eq!(recent["items"][0]["favicon"], "data:image/x-icon;base64,AAAA");
+}
+
 // ─── History ───
 
 #[test]
@@ -152,7 +217,7 @@ fn test_history_record_invalid_url() {
         "url": "ftp://bad.com", "title": "Bad"
     }));
     assert!(res.is_err());
-    assert!(res.unwrap_err().contains("invalid url"));
+    assert!(res.unwrap_err().to_string().contains("invalid url"));
 }
 
 #[test]
@@ -200,6 +265,174 @@ fn test_history_clear() {
     assert_eq!(recent["items"].as_array().unwrap().len(), 0);
 }
 
+#[test]
+fn test_history_export_streams_in_chunks() {
+    use gitbrowser::rpc_handler::handle_streaming;
+    use gitbrowser::rpc_protocol::{parse_line, Incoming};
+    use gitbrowser::rpc_stream::Stream;
+    use std::sync::Arc;
+    let (app, _tmp) = setup();
+    for i in 0..5 {
+        let url = format!("https://example.com/{}", i);
+        handle_method(&app, "history.record", &json!({"url": url, "title": "Page"})).unwrap();
+    }
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let deliver: gitbrowser::services::event_hub::Deliver =
+        Arc::new(move |value: &serde_json::Value| sink.lock().unwrap().push(value.clone()));
+    let line = r#"{"jsonrpc":"2.0","id":9,"method":"history.export","params":{"chunk_size":2}}"#;
+    let Ok(Incoming::Single(Ok(call))) = parse_line(line) else { panic!("expected a call") };
+    let mut stream = Stream::new(&call, deliver.clone());
+
+    let res = handle_streaming(&app, &call.method, &call.params, &mut stream).unwrap().unwrap();
+    assert_eq!(res, json!({"exported": 5, "chunks": 3}));
+    let parts = received.lock().unwrap();
+    assert_eq!(parts.len(), 3);
+    assert!(parts.iter().all(|p| p["method"] == "stream" && p["params"]["id"] == 9));
+    assert_eq!(parts[0]["params"]["seq"], 0);
+    assert_eq!(parts[0]["params"]["progress"], json!({"done": 2, "total": 5}));
+    assert_eq!(parts[0]["params"]["data"]["entries"].as_array().unwrap().len(), 2);
+    assert_eq!(parts[2]["params"]["data"]["entries"].as_array().unwrap().len(), 1);
+    drop(parts);
+
+    // The original format gets events, and a notification gets no parts
+    let legacy = r#"{"id":"x","method":"history.export","params":{"chunk_size":5}}"#;
+    let Ok(Incoming::Single(Ok(call))) = parse_line(legacy) else { panic!("expected a call") };
+    handle_streaming(&app, &call.method, &call.params, &mut Stream::new(&call, deliver.clone())).unwrap().unwrap();
+    let last = received.lock().unwrap().last().cloned().unwrap();
+    assert_eq!(last["event"], "stream");
+    assert_eq!(last["id"], "x");
+    let notification = r#"{"jsonrpc":"2.0","method":"history.export"}"#;
+    let Ok(Incoming::Single(Ok(call))) = parse_line(notification) else { panic!("expected a call") };
+    handle_streaming(&app, &call.method, &call.params, &mut Stream::new(&call, deliver)).unwrap().unwrap();
+    assert_eq!(received.lock().unwrap().len(), 4);
+
+    assert!(handle_streaming(&app, "history.recent", &json!({}), &mut stream).is_none());
+}
+
+// ─── Reading list ───
+
+const ARTICLE_HTML: &str = "<html><head><title>Offline Article</title></head><body><article>\
+<p>This is a long enough article body to be recognised by reader mode as real content.</p>\
+<p>It keeps going for a little while so the text passes the minimum length check.</p>\
+</article></body></html>";
+
+#[test]
+fn test_reading_list_add_get_and_list() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "reading_list.add", &json!({
+        "url": "https://example.com/post", "html": ARTICLE_HTML
+    })).unwrap();
+    let id = res["id"].as_str().unwrap().to_string();
+    assert_eq!(res["title"], "Offline Article");
+
+    let item = handle_method(&app, "reading_list.get", &json!({"id": id})).unwrap();
+    assert!(item["content_html"].as_str().unwrap().contains("long enough article"));
+
+    let list = handle_method(&app, "reading_list.list", &json!({})).unwrap();
+    assert_eq!(list["total"], 1);
+    assert_eq!(list["items"][0]["url"], "https://example.com/post");
+    assert!(list["items"][0]["read_at"].is_null());
+}
+
+#[test]
+fn test_reader_detects_extracts_and_renders() {
+    let (app, _tmp) = setup();
+    let page = json!({"url": "https://example.com/post", "html": ARTICLE_HTML});
+    assert_eq!(handle_method(&app, "reader.is_article", &page).unwrap()["is_article"], true);
+    let plain = json!({"url": "https://example.com", "html": "<html><body>hi</body></html>"});
+    assert_eq!(handle_method(&app, "reader.is_article", &plain).unwrap()["is_article"], false);
+    assert!(handle_method(&app, "reader.extract", &plain).is_err());
+
+    let res = handle_method(&app, "reader.extract", &page).unwrap();
+    assert_eq!(res["content"]["title"], "Offline Article");
+    assert_eq!(res["content"]["page_count"], 1);
+
+    let res = handle_method(&app, "reader.render", &page).unwrap();
+    assert_eq!(res["title"], "Offline Article");
+    assert!(res["html"].as_str().unwrap().contains("<h1>Offline Article</h1>"));
+
+    let settings = json!({
+        "font_size": 20, "font_family": "Serif", "background_color": "#ffffff",
+        "line_height": 1.5, "max_width": 700, "justify": true,
+    });
+    let res = handle_method(&app, "reader.render", &json!({
+        "url": "https://example.com/post", "html": ARTICLE_HTML, "settings": settings,
+    })).unwrap();
+    assert!(res["html"].as_str().unwrap().contains("text-align: justify"));
+    assert!(handle_method(&app, "reader.render", &json!({
+        "url": "https://example.com/post", "html": ARTICLE_HTML, "settings": {"font_size": "big"},
+    })).unwrap_err().to_string().contains("invalid settings"));
+}
+
+#[test]
+fn test_reading_list_add_rejects_non_article() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "reading_list.add", &json!({
+        "url": "https://example.com", "html": "<html><body>hi</body></html>"
+    }));
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_reading_list_mark_read_and_delete() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "reading_list.add", &json!({
+        "url": "https://example.com/post", "html": ARTICLE_HTML
+    })).unwrap();
+    let id = res["id"].as_str().unwrap().to_string();
+
+    handle_method(&app, "reading_list.mark_read", &json!({"id": id})).unwrap();
+    let unread = handle_method(&app, "reading_list.list", &json!({"unread_only": true})).unwrap();
+    assert_eq!(unread["total"], 0);
+
+    handle_method(&app, "reading_list.delete", &json!({"id": id})).unwrap();
+    assert!(handle_method(&app, "reading_list.get", &json!({"id": id})).is_err());
+}
+
+// ─── Annotations ───
+
+#[test]
+fn test_annotations_add_list_and_apply() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "annotations.add", &json!({
+        "url": "https://example.com/post", "text": "reader mode", "note": "key point"
+    })).unwrap();
+    let id = res["id"].as_str().unwrap().to_string();
+
+    let list = handle_method(&app, "annotations.list", &json!({"url": "https://example.com/post"})).unwrap();
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert_eq!(list[0]["id"], id.as_str());
+
+    let applied = handle_method(&app, "annotations.apply", &json!({
+        "url": "https://example.com/post", "html": ARTICLE_HTML
+    })).unwrap();
+    assert_eq!(applied["count"], 1);
+    assert!(applied["html"].as_str().unwrap().contains(">reader mode</mark>"));
+}
+
+#[test]
+fn test_annotations_update_and_delete() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "annotations.add", &json!({
+        "url": "https://example.com/post", "text": "passage"
+    })).unwrap();
+    let id = res["id"].as_str().unwrap().to_string();
+
+    handle_method(&app, "annotations.update", &json!({"id": id, "note": "later"})).unwrap();
+    let list = handle_method(&app, "annotations.list", &json!({"url": "https://example.com/post"})).unwrap();
+    assert_eq!(list[0]["note"], "later");
+
+    handle_method(&app, "annotations.delete", &json!({"id": id})).unwrap();
+    assert!(handle_method(&app, "annotations.delete", &json!({"id": id})).is_err());
+}
+
+#[test]
+fn test_annotations_add_missing_text() {
+    let (app, _tmp) = setup();
+    assert!(handle_method(&app, "annotations.add", &json!({"url": "https://example.com"})).is_err());
+}
+
 // ─── Settings ───
 
 #[test]
@@ -212,7 +445,7 @@ fn test_settings_get() {
 
 #[test]
 fn test_settings_set_and_get() {
-    let (app, _tmp) = setup();
+    let (app, _tmp) = setup_isolated();
     handle_method(&app, "settings.set", &json!({
         "key": "general.homepage",
         "value": "https://custom.home"
@@ -227,6 +460,79 @@ fn test_settings_set_and_get() {
     }
 }
 
+#[test]
+fn test_settings_import_streams_each_change() {
+    let (app, tmp) = setup_isolated();
+    let path = tmp.path().join("settings-export.json");
+    let path = path.to_str().unwrap();
+    handle_method(&app, "settings.set", &json!({"key": "general.homepage", "value": "https://exported.home"})).unwrap();
+    handle_method(&app, "settings.set", &json!({"key": "shortcuts.new_tab", "value": "Ctrl+Shift+T"})).unwrap();
+    handle_method(&app, "settings.export", &json!({"path": path})).unwrap();
+    handle_method(&app, "settings.set", &json!({"key": "general.homepage", "value": "https://local.home"})).unwrap();
+    handle_method(&app, "settings.set", &json!({"key": "shortcuts.new_tab", "value": "Ctrl+T"})).unwrap();
+
+    let (res, parts) = call_streaming(&app, "settings.import", json!({"path": path}));
+    assert_eq!(res.unwrap(), json!({"applied": false, "changes": 2, "chunks": 2}));
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0]["seq"], 0);
+    assert_eq!(parts[1]["progress"], json!({"done": 2, "total": 2}));
+    let homepage = parts.iter().find(|p| p["data"]["change"]["key"] == "general.homepage").unwrap();
+    assert_eq!(homepage["data"]["change"]["new_value"], "https://exported.home");
+    let settings = handle_method(&app, "settings.get", &json!({})).unwrap();
+    assert_eq!(settings["general"]["homepage"], "https://local.home");
+
+    let (res, parts) = call_streaming(&app, "settings.import", json!({"path": path, "apply": true}));
+    assert_eq!(res.unwrap(), json!({"applied": true, "changes": 2, "chunks": 2}));
+    assert_eq!(parts.len(), 2);
+    let settings = handle_method(&app, "settings.get", &json!({})).unwrap();
+    assert_eq!(settings["general"]["homepage"], "https://exported.home");
+
+    // Nothing left to change
+    let (res, parts) = call_streaming(&app, "settings.import", json!({"path": path}));
+    assert_eq!(res.unwrap(), json!({"applied": false, "changes": 0, "chunks": 0}));
+    assert!(parts.is_empty());
+}
+
+#[test]
+fn test_external_protocols_always_allowed() {
+    let (app, _tmp) = setup_isolated();
+    assert!(!app.lock().unwrap().is_protocol_allowed("mailto"));
+
+    app.lock().unwrap().set_protocol_allowed("MailTo", true).unwrap();
+    app.lock().unwrap().set_protocol_allowed("mailto", true).unwrap();
+    assert!(app.lock().unwrap().is_protocol_allowed("mailto"));
+    let settings = handle_method(&app, "settings.get", &json!({})).unwrap();
+    assert_eq!(settings["general"]["allowed_protocols"], json!(["mailto"]));
+
+    app.lock().unwrap().set_protocol_allowed("mailto", false).unwrap();
+    assert!(!app.lock().unwrap().is_protocol_allowed("mailto"));
+}
+
+#[test]
+fn test_settings_schema() {
+    let (app, _dir) = setup();
+    let res = handle_method(&app, "settings.schema", &json!({})).unwrap();
+    let keys = res["keys"].as_array().unwrap();
+    let theme = keys.iter().find(|k| k["key"] == "appearance.theme").unwrap();
+    assert_eq!(theme["type"], "enum");
+    assert_eq!(theme["default"], "System");
+    assert!(theme["allowed_values"].as_array().unwrap().contains(&json!("Dark")));
+    assert!(keys.iter().any(|k| k["key"] == "shortcuts.new_tab"));
+}
+
+#[test]
+fn test_search_list_and_resolve() {
+    let (app, _dir) = setup_isolated();
+    let res = handle_method(&app, "search.list", &json!({})).unwrap();
+    assert!(res["engines"].as_array().unwrap().iter().any(|e| e["id"] == "duckduckgo"));
+    assert!(res["default"].is_string());
+
+    let res = handle_method(&app, "search.resolve", &json!({"query": "ddg rust lang"})).unwrap();
+    assert_eq!(res["url"], "https://duckduckgo.com/?q=rust+lang");
+    assert!(handle_method(&app, "search.resolve", &json!({})).is_err());
+    assert!(handle_method(&app, "search.add", &json!({"engine": {"id": "x"}})).is_err());
+}
+
 #[test]
 fn test_settings_set_missing_params() {
     let (app, _tmp) = setup();
@@ -234,6 +540,174 @@ fn test_settings_set_missing_params() {
     assert!(handle_method(&app, "settings.set", &json!({"value": "x"})).is_err());
 }
 
+// ─── Shortcuts ───
+
+#[test]
+fn test_shortcuts_set_reset_and_persist() {
+    use gitbrowser::services::settings_engine::SettingsEngineTrait;
+    let (app, _tmp) = setup_isolated();
+    let res = handle_method(&app, "shortcuts.get", &json!({"action": "fullscreen"})).unwrap();
+    assert_eq!(res["keys"], "F11");
+    assert_eq!(res["customized"], false);
+    assert!(handle_method(&app, "shortcuts.get", &json!({"action": "fly"})).is_err());
+
+    handle_method(&app, "shortcuts.set", &json!({"action": "fullscreen", "keys": "F7"})).unwrap();
+    let res = handle_method(&app, "shortcuts.get", &json!({"action": "fullscreen"})).unwrap();
+    assert_eq!(res["keys"], "F7");
+    assert_eq!(res["default"], "F11");
+    assert_eq!(res["customized"], true);
+    let stored = app.lock().unwrap().settings_engine.get_settings().shortcuts.get("fullscreen").cloned();
+    assert_eq!(stored.as_deref(), Some("F7"));
+
+    let list = handle_method(&app, "shortcuts.list", &json!({})).unwrap();
+    let list = list["shortcuts"].as_array().unwrap();
+    assert!(list.windows(2).all(|w| w[0]["action"].as_str() < w[1]["action"].as_str()));
+    assert!(list.iter().any(|s| s["action"] == "fullscreen" && s["keys"] == "F7"));
+
+    handle_method(&app, "shortcuts.reset", &json!({"action": "fullscreen"})).unwrap();
+    let res = handle_method(&app, "shortcuts.get", &json!({"action": "fullscreen"})).unwrap();
+    assert_eq!(res["keys"], "F11");
+    handle_method(&app, "shortcuts.set", &json!({"action": "fullscreen", "keys": "F7"})).unwrap();
+    handle_method(&app, "shortcuts.reset", &json!({})).unwrap();
+    let res = handle_method(&app, "shortcuts.get", &json!({"action": "fullscreen"})).unwrap();
+    assert_eq!(res["keys"], "F11");
+}
+
+#[test]
+fn test_shortcuts_conflicts() {
+    let (app, _tmp) = setup_isolated();
+    let res = handle_method(&app, "shortcuts.has_conflict", &json!({"keys": "F12"})).unwrap();
+    assert_eq!(res["conflict"], true);
+    assert_eq!(res["action"], "dev_tools");
+    let res = handle_method(&app, "shortcuts.has_conflict", &json!({"keys": "F12", "action": "dev_tools"})).unwrap();
+    assert_eq!(res["conflict"], false);
+    let res = handle_method(&app, "shortcuts.has_conflict", &json!({"keys": "F7"})).unwrap();
+    assert_eq!(res["action"], serde_json::Value::Null);
+
+    let err = handle_method(&app, "shortcuts.set", &json!({"action": "fullscreen", "keys": "F12"})).unwrap_err().to_string();
+    assert!(err.contains("dev_tools"));
+    let res = handle_method(&app, "shortcuts.get", &json!({"action": "fullscreen"})).unwrap();
+    assert_eq!(res["keys"], "F11");
+}
+
+// ─── Themes ───
+
+#[test]
+fn test_theme_list_and_invalid_install() {
+    let (app, _tmp) = setup_isolated();
+    let res = handle_method(&app, "theme.list", &json!({})).unwrap();
+    assert!(res["themes"].is_array());
+
+    let bad = json!({"theme": {"name": "Bad", "variables": {"--bg-primary": "red;}"}}});
+    assert!(handle_method(&app, "theme.install", &bad).is_err());
+    assert!(handle_method(&app, "theme.install", &json!({})).is_err());
+    assert!(handle_method(&app, "theme.apply", &json!({"id": "does-not-exist"})).is_err());
+}
+
+#[test]
+fn test_theme_set_mode_and_accent() {
+    let (app, _tmp) = setup_isolated();
+    let res = handle_method(&app, "theme.set", &json!({"mode": "Light"})).unwrap();
+    assert_eq!(res["mode"], "Light");
+    assert_eq!(res["active_mode"], "Light");
+    let vars = handle_method(&app, "theme.css_variables", &json!({})).unwrap();
+    assert_eq!(vars["variables"], res["variables"]);
+
+    let res = handle_method(&app, "theme.set_accent", &json!({"color": "#ff0000"})).unwrap();
+    assert_eq!(res["accent"], "#ff0000");
+    assert_eq!(res["variables"]["--accent-color"], "#ff0000");
+    let res = handle_method(&app, "theme.get", &json!({})).unwrap();
+    assert_eq!(res["accent_color"], "#ff0000");
+    assert_eq!(res["custom_theme"], serde_json::Value::Null);
+
+    assert!(handle_method(&app, "theme.set", &json!({"mode": "Purple"})).unwrap_err().to_string().contains("invalid mode"));
+    assert!(handle_method(&app, "theme.set_accent", &json!({"color": "red"})).unwrap_err().to_string().contains("invalid color"));
+}
+
+#[test]
+fn test_theme_switch_is_pushed_to_subscribers() {
+    use gitbrowser::rpc_handler::handle_subscription;
+    use gitbrowser::services::event_hub::Deliver;
+    use std::sync::Arc;
+    let (app, _tmp) = setup_isolated();
+    handle_method(&app, "theme.set", &json!({"mode": "Dark"})).unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let deliver: Deliver = Arc::new(move |value: &serde_json::Value| sink.lock().unwrap().push(value.clone()));
+    handle_subscription(&app, 1, "subscribe", &json!({"topics": ["theme"]}), &deliver).unwrap().unwrap();
+
+    handle_method(&app, "theme.set", &json!({"mode": "Light"})).unwrap();
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    let data = &received[0]["params"]["data"];
+    assert_eq!(data["mode"], "Light");
+    assert!(data["variables"]["--accent-color"].is_string());
+}
+
+#[test]
+fn test_theme_schedule_status() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "theme.schedule", &json!({})).unwrap();
+    assert!(res["settings"]["mode"].is_string());
+    assert!(res.get("next_transition").is_some());
+}
+
+#[test]
+fn test_force_dark_toggle_per_site() {
+    let (app, _tmp) = setup();
+    let url = json!({"url": "https://Example.com/page"});
+    let res = handle_method(&app, "theme.force_dark", &url).unwrap();
+    assert_eq!(res["enabled"], false);
+
+    let res = handle_method(&app, "theme.force_dark.toggle", &url).unwrap();
+    assert_eq!(res["enabled"], true);
+    assert!(res["script"].as_str().unwrap().contains("data-gb-force-dark"));
+
+    let res = handle_method(&app, "site_settings.get", &json!({"site": "example.com"})).unwrap();
+    assert_eq!(res["settings"][0]["key"], "force_dark");
+    assert_eq!(res["settings"][0]["value"], true);
+
+    // Toggling back to the global default drops the override
+    let res = handle_method(&app, "theme.force_dark.toggle", &url).unwrap();
+    assert_eq!(res["enabled"], false);
+    let res = handle_method(&app, "site_settings.get", &json!({"site": "example.com"})).unwrap();
+    assert!(res["settings"].as_array().unwrap().is_empty());
+
+    assert!(handle_method(&app, "theme.force_dark.toggle", &json!({"url": "gb://localhost/newtab"})).is_err());
+}
+
+#[test]
+fn test_user_agent_overrides_per_site() {
+    let (app, _tmp) = setup_isolated();
+    let ua = |url: &str| handle_method(&app, "site_settings.user_agent", &json!({"url": url})).unwrap()["user_agent"].clone();
+    assert!(ua("https://example.com/").is_null());
+
+    handle_method(&app, "settings.set", &json!({"key": "general.user_agent", "value": "Global/1.0"})).unwrap();
+    assert_eq!(ua("https://example.com/"), "Global/1.0");
+
+    let res = handle_method(
+        &app,
+        "site_settings.user_agent.set",
+        &json!({"url": "https://Legacy.example/app", "user_agent": " Mozilla/5.0 (X11) Firefox/128.0 "}),
+    )
+    .unwrap();
+    assert_eq!(res["site"], "legacy.example");
+    assert_eq!(ua("https://legacy.example/other"), "Mozilla/5.0 (X11) Firefox/128.0");
+    assert_eq!(ua("https://example.com/"), "Global/1.0");
+
+    // Headers cannot carry control characters, and blank is no User-Agent
+    let bad = json!({"url": "https://legacy.example/", "user_agent": "a\r\nX-Injected: 1"});
+    assert!(handle_method(&app, "site_settings.user_agent.set", &bad).is_err());
+    let bad = json!({"site": "legacy.example", "key": "user_agent", "value": "  "});
+    assert!(handle_method(&app, "site_settings.set", &bad).is_err());
+
+    handle_method(&app, "site_settings.user_agent.set", &json!({"url": "https://legacy.example/"})).unwrap();
+    assert_eq!(ua("https://legacy.example/"), "Global/1.0");
+    handle_method(&app, "settings.set", &json!({"key": "general.user_agent", "value": ""})).unwrap();
+    assert!(ua("https://legacy.example/").is_null());
+    assert!(handle_method(&app, "site_settings.user_agent.set", &json!({"url": "gb://localhost/newtab", "user_agent": "X"})).is_err());
+}
+
 // ─── Localization ───
 
 #[test]
@@ -242,6 +716,7 @@ fn test_i18n_locale() {
     let res = handle_method(&app, "i18n.locale", &json!({})).unwrap();
     // Should return a locale string
     assert!(res.get("locale").is_some());
+    assert!(matches!(res["direction"].as_str(), Some("ltr") | Some("rtl")));
 }
 
 #[test]
@@ -258,6 +733,107 @@ fn test_i18n_t_missing_key() {
     assert!(handle_method(&app, "i18n.t", &json!({})).is_err());
 }
 
+#[test]
+fn test_i18n_missing_keys_in_debug_mode() {
+    let (app, _tmp) = setup();
+    handle_method(&app, "i18n.debug", &json!({"enabled": true})).unwrap();
+    handle_method(&app, "i18n.t", &json!({"key": "no.such.key"})).unwrap();
+
+    let res = handle_method(&app, "i18n.missing", &json!({"clear": true})).unwrap();
+    assert_eq!(res["debug"], true);
+    let missing = res["missing"].as_array().unwrap();
+    assert!(missing.iter().any(|m| m["key"] == "no.such.key" && m["resolved_from"].is_null()));
+
+    let res = handle_method(&app, "i18n.missing", &json!({})).unwrap();
+    assert!(res["missing"].as_array().unwrap().is_empty());
+    assert!(handle_method(&app, "i18n.debug", &json!({})).is_err());
+}
+
+#[test]
+fn test_history_entries_include_relative_time() {
+    let (app, _tmp) = setup();
+    handle_method(&app, "history.record", &json!({"url": "https://example.com", "title": "Example"})).unwrap();
+    let res = handle_method(&app, "history.recent", &json!({})).unwrap();
+    let items = res["items"].as_array().unwrap();
+    assert!(items.iter().all(|h| h["visited"].as_str().is_some_and(|s| !s.is_empty())));
+}
+
+#[test]
+fn test_ai_usage_is_localized() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "ai.usage", &json!({})).unwrap();
+    assert!(res["total_tokens"].is_u64());
+    assert!(res["cost_display"].as_str().unwrap().contains('0'));
+}
+
+#[test]
+fn test_ai_ask_streams_the_panel_answer() {
+    use gitbrowser::services::event_hub::EventTopic;
+    use std::sync::Arc;
+    let (app, _tmp) = setup();
+    let ask = json!({"selected_text": "What is a borrow checker?", "page_url": "https://doc.rust-lang.org/"});
+
+    // Nobody shows an assistant panel yet
+    let (res, parts) = call_streaming(&app, "ai.ask", ask.clone());
+    assert_eq!(res.unwrap(), json!({"delivered": false}));
+    assert!(parts.is_empty());
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    app.lock().unwrap().events.subscribe(
+        1,
+        &[EventTopic::Ai],
+        Arc::new(move |value: &serde_json::Value| sink.lock().unwrap().push(value["params"]["data"].clone())),
+    );
+    let (res, parts) = std::thread::scope(|scope| {
+        let asking = scope.spawn(|| call_streaming(&app, "ai.ask", ask));
+        // The panel answers in pieces once the question reaches it
+        let question = loop {
+            if let Some(event) = events.lock().unwrap().first().cloned() {
+                break event;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        };
+        assert_eq!(question["action"], "ask");
+        assert_eq!(question["context"]["selected_text"], "What is a borrow checker?");
+        assert_eq!(question["context"]["page_url"], "https://doc.rust-lang.org/");
+        let id = question["ask"].as_u64().unwrap();
+        handle_method(&app, "ai.answer", &json!({"ask": id, "text": "It checks "})).unwrap();
+        handle_method(&app, "ai.answer", &json!({"ask": id, "text": "references."})).unwrap();
+        handle_method(&app, "ai.answer", &json!({"ask": id, "done": true})).unwrap();
+        assert!(handle_method(&app, "ai.answer", &json!({"ask": id, "text": "late"})).is_err());
+        asking.join().unwrap()
+    });
+    assert_eq!(res.unwrap(), json!({"delivered": true, "answer": "It checks references.", "chunks": 2}));
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0]["data"], json!({"text": "It checks "}));
+    assert_eq!(parts[0]["progress"], json!({"done": 10, "total": null}));
+    assert_eq!(parts[1]["seq"], 1);
+    assert_eq!(parts[1]["data"], json!({"text": "references."}));
+    assert_eq!(parts[1]["progress"]["done"], 21);
+}
+
+#[test]
+fn test_i18n_reload() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "i18n.reload", &json!({})).unwrap();
+    assert!(res["locales"].as_array().unwrap().iter().any(|l| l == "en"));
+}
+
+#[test]
+fn test_i18n_locales_and_stats() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "i18n.locales", &json!({})).unwrap();
+    assert!(res["locales"].is_array());
+    assert!(res["fallback"].is_array());
+
+    let res = handle_method(&app, "i18n.stats", &json!({})).unwrap();
+    for entry in res["stats"].as_array().unwrap() {
+        assert!(entry["missing"].is_array());
+        assert!(entry["completion"].as_f64().unwrap() <= 100.0);
+    }
+}
+
 // ─── Session save/restore ───
 
 #[test]
@@ -294,6 +870,276 @@ fn test_session_save_missing_tabs() {
     assert!(handle_method(&app, "session.save", &json!({})).is_err());
 }
 
+// ─── Database backups ───
+
+#[test]
+fn test_db_restore_needs_confirmation() {
+    let (app, _tmp) = setup();
+    handle_method(&app, "bookmark.add", &json!({"url": "https://a.example", "title": "A"})).unwrap();
+    let backup = handle_method(&app, "db.backup", &json!({})).unwrap()["backup"].clone();
+    let name = backup["name"].as_str().unwrap();
+    handle_method(&app, "bookmark.add", &json!({"url": "https://b.example", "title": "B"})).unwrap();
+
+    let pending = handle_method(&app, "db.restore", &json!({"name": name})).unwrap();
+    assert_eq!(pending["pending"], true);
+    assert_eq!(pending["backup"]["name"], name);
+    assert_eq!(handle_method(&app, "bookmark.list", &json!({})).unwrap()["total"], 2);
+
+    let res = handle_method(&app, "db.restore", &json!({"name": name, "confirm": true})).unwrap();
+    assert_eq!(res["ok"], true);
+    assert_eq!(handle_method(&app, "bookmark.list", &json!({})).unwrap()["total"], 1);
+    let backups = handle_method(&app, "db.backups", &json!({})).unwrap();
+    assert_eq!(backups[0]["name"], res["safety_backup"]["name"]);
+
+    assert!(handle_method(&app, "db.restore", &json!({"name": "../test.db", "confirm": true})).is_err());
+}
+
+#[test]
+fn test_profiles_create_list_and_delete() {
+    use gitbrowser::managers::profile_manager::ProfileManager;
+    let (app, tmp) = setup();
+    app.lock().unwrap().profile_manager = ProfileManager::new(tmp.path().join("profiles"));
+
+    let created = handle_method(&app, "profiles.create", &json!({"name": "work"})).unwrap();
+    assert_eq!(created["profile"]["name"], "work");
+    assert!(handle_method(&app, "profiles.create", &json!({"name": "../work"})).is_err());
+    let list = handle_method(&app, "profiles.list", &json!({})).unwrap();
+    assert_eq!(list["profiles"].as_array().unwrap().len(), 1);
+    assert_eq!(list["active"], serde_json::Value::Null);
+
+    let pending = handle_method(&app, "profiles.delete", &json!({"name": "work"})).unwrap();
+    assert_eq!(pending["pending"], true);
+    handle_method(&app, "profiles.delete", &json!({"name": "work", "confirm": true})).unwrap();
+    let list = handle_method(&app, "profiles.list", &json!({})).unwrap();
+    assert!(list["profiles"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_db_stats_and_compact() {
+    let (app, _tmp) = setup();
+    handle_method(&app, "db.backup", &json!({})).unwrap();
+    let stats = handle_method(&app, "db.stats", &json!({})).unwrap();
+    assert!(stats["stats"]["size"].as_u64().unwrap() > 0);
+    assert!(stats["backups_size"].as_u64().unwrap() > 0);
+    assert!(stats["total"].as_u64().unwrap() >= stats["stats"]["size"].as_u64().unwrap());
+    assert_eq!(stats["last_maintenance"], serde_json::Value::Null);
+
+    let res = handle_method(&app, "db.compact", &json!({})).unwrap();
+    assert_eq!(res["ok"], true);
+    assert_eq!(res["stats"]["free"], 0);
+    assert_eq!(res["stats"]["incremental_vacuum"], true);
+}
+
+// ─── Crash logs ───
+
+#[test]
+fn test_crash_logs_list_logged_crashes() {
+    use gitbrowser::services::crash_recovery::{crash_entry, CrashRecoveryTrait};
+    let (app, _tmp) = setup();
+    assert_eq!(handle_method(&app, "crash.logs", &json!({})).unwrap(), json!([]));
+
+    let entry = crash_entry("panic", "boom at src/app.rs:1:1", Some("0: main".to_string()), None);
+    app.lock().unwrap().crash_recovery.log_crash(entry).unwrap();
+    let logs = handle_method(&app, "crash.logs", &json!({})).unwrap();
+    assert_eq!(logs[0]["error_type"], "panic");
+    assert_eq!(logs[0]["backtrace"], "0: main");
+    assert_eq!(logs[0]["app_version"], env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_crash_list_mark_recovered_and_clear() {
+    use gitbrowser::services::crash_recovery::{crash_entry, CrashRecoveryTrait};
+    let (app, _tmp) = setup();
+    let mut ids = Vec::new();
+    for i in 0..2 {
+        let mut entry = crash_entry("panic", "boom", None, None);
+        entry.timestamp = 1_700_000_000 + i;
+        ids.push(entry.id.clone());
+        app.lock().unwrap().crash_recovery.log_crash(entry).unwrap();
+    }
+    let res = handle_method(&app, "crash.list", &json!({})).unwrap();
+    assert_eq!(res["crashes"].as_array().unwrap().len(), 2);
+    assert_eq!(res["unrecovered"], true);
+    assert_eq!(res["restore_pending"], false);
+
+    let res = handle_method(&app, "crash.mark_recovered", &json!({"ids": [ids[0]]})).unwrap();
+    assert_eq!(res["unrecovered"], true);
+    let res = handle_method(&app, "crash.list", &json!({"unrecovered_only": true})).unwrap();
+    assert_eq!(res["crashes"].as_array().unwrap().len(), 1);
+    assert_eq!(res["crashes"][0]["id"], ids[1].as_str());
+    assert!(handle_method(&app, "crash.mark_recovered", &json!({"ids": ["nope"]})).unwrap_err().to_string().contains("not found"));
+
+    let res = handle_method(&app, "crash.mark_recovered", &json!({})).unwrap();
+    assert_eq!(res["unrecovered"], false);
+    let res = handle_method(&app, "crash.list", &json!({})).unwrap();
+    assert!(res["crashes"].as_array().unwrap().iter().all(|c| c["recovered"] == true));
+
+    assert_eq!(handle_method(&app, "crash.clear", &json!({})).unwrap()["removed"], 2);
+    assert_eq!(handle_method(&app, "crash.list", &json!({})).unwrap()["crashes"], json!([]));
+}
+
+#[test]
+fn test_crash_mark_recovered_declines_pending_restore() {
+    use gitbrowser::managers::tab_manager::TabManagerTrait;
+    use gitbrowser::services::crash_recovery::{crash_entry, CrashRecoveryTrait};
+    let (app, _tmp) = setup();
+    {
+        let mut a = app.lock().unwrap();
+        a.tab_manager.create_tab(Some("https://example.com"), true);
+        a.save_session().unwrap();
+        a.crash_recovery.log_crash(crash_entry("panic", "boom", None, None)).unwrap();
+        a.check_crash_recovery();
+    }
+    assert_eq!(handle_method(&app, "crash.list", &json!({})).unwrap()["restore_pending"], true);
+    handle_method(&app, "crash.mark_recovered", &json!({})).unwrap();
+    assert_eq!(handle_method(&app, "crash.restore_prompt", &json!({})).unwrap(), serde_json::Value::Null);
+    assert!(!app.lock().unwrap().crash_recovery.has_unrecovered_crash());
+}
+
+#[test]
+fn test_crash_renderer_logs_tab_url() {
+    let (app, _tmp) = setup();
+    handle_method(&app, "crash.renderer", &json!({"reason": "oom", "url": "https://example.com"})).unwrap();
+    let logs = handle_method(&app, "crash.logs", &json!({})).unwrap();
+    assert_eq!(logs[0]["error_type"], "renderer_crash");
+    assert_eq!(logs[0]["error_message"], "oom");
+    assert_eq!(logs[0]["tab_url"], "https://example.com");
+    assert!(handle_method(&app, "crash.renderer", &json!({})).is_err());
+}
+
+#[test]
+fn test_hang_is_logged_with_page_url() {
+    use gitbrowser::managers::tab_manager::TabManagerTrait;
+    let (app, _tmp) = setup();
+    {
+        let mut a = app.lock().unwrap();
+        a.tab_manager.create_tab(Some("https://slow.example"), true);
+        let url = a.record_hang(std::time::Duration::from_secs(12));
+        assert_eq!(url.as_deref(), Some("https://slow.example"));
+    }
+    let logs = handle_method(&app, "crash.logs", &json!({})).unwrap();
+    assert_eq!(logs[0]["error_type"], "hang");
+    assert_eq!(logs[0]["error_message"], "Page unresponsive for 12 s");
+    assert_eq!(logs[0]["tab_url"], "https://slow.example");
+}
+
+#[test]
+fn test_crash_restore_prompt_waits_for_the_user() {
+    use gitbrowser::managers::tab_manager::TabManagerTrait;
+    use gitbrowser::services::crash_recovery::CrashRecoveryTrait;
+    use gitbrowser::types::session::SessionTab;
+    let (app, _tmp) = setup();
+    assert_eq!(handle_method(&app, "crash.restore_prompt", &json!({})).unwrap(), serde_json::Value::Null);
+    assert!(handle_method(&app, "crash.restore", &json!({"restore": true})).is_err());
+
+    {
+        let mut a = app.lock().unwrap();
+        let tab = |id: &str, url: &str, pinned| SessionTab {
+            id: id.to_string(),
+            url: url.to_string(),
+            title: url.to_string(),
+            pinned,
+            scroll_position: Default::default(),
+        };
+        let tabs = vec![tab("a", "https://github.com", true), tab("b", "https://example.com", false)];
+        a.store_session(tabs, Some("b".to_string())).unwrap();
+        a.tab_manager.create_tab(Some("about:newtab"), true);
+        a.crash_recovery.log_crash(gitbrowser::services::crash_recovery::crash_entry("panic", "boom", None, None)).unwrap();
+        a.check_crash_recovery();
+        // Not recovered until the user answers
+        assert!(a.crash_recovery.has_unrecovered_crash());
+    }
+
+    let prompt = handle_method(&app, "crash.restore_prompt", &json!({})).unwrap();
+    assert_eq!(prompt["event"], "crash.restore_prompt");
+    assert_eq!(prompt["tabs"][0]["url"], "https://github.com");
+    assert_eq!(prompt["tabs"][0]["pinned"], true);
+    assert_eq!(prompt["active_index"], 1);
+
+    let res = handle_method(&app, "crash.restore", &json!({"restore": true})).unwrap();
+    assert_eq!(res["tabs"].as_array().unwrap().len(), 2);
+    let a = app.lock().unwrap();
+    assert!(!a.crash_recovery.has_unrecovered_crash());
+    assert!(a.crash_restore_prompt().is_none());
+    let urls: Vec<&str> = a.tab_manager.get_all_tabs().iter().map(|t| t.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://github.com", "https://example.com"]);
+    assert_eq!(a.tab_manager.get_active_tab().unwrap().url, "https://example.com");
+}
+
+#[test]
+fn test_crash_restore_start_fresh_keeps_tabs() {
+    use gitbrowser::managers::tab_manager::TabManagerTrait;
+    use gitbrowser::services::crash_recovery::CrashRecoveryTrait;
+    let (app, _tmp) = setup();
+    {
+        let mut a = app.lock().unwrap();
+        a.tab_manager.create_tab(Some("https://example.com"), true);
+        a.save_session().unwrap();
+        a.crash_recovery.log_crash(gitbrowser::services::crash_recovery::crash_entry("panic", "boom", None, None)).unwrap();
+        a.check_crash_recovery();
+        a.tab_manager.create_tab(Some("https://other.example"), true);
+    }
+    let res = handle_method(&app, "crash.restore", &json!({"restore": false})).unwrap();
+    assert_eq!(res["tabs"], json!([]));
+    let a = app.lock().unwrap();
+    assert!(!a.crash_recovery.has_unrecovered_crash());
+    assert_eq!(a.tab_manager.tab_count(), 2);
+}
+
+#[test]
+fn test_crash_upload_needs_consent_and_drafts_issue() {
+    let (app, _tmp) = setup_isolated();
+    handle_method(&app, "crash.renderer", &json!({"reason": "crashed", "url": "https://example.com/a?token=1"})).unwrap();
+    let err = handle_method(&app, "crash.upload", &json!({})).unwrap_err();
+    assert_eq!(err.code(), "crash.consent_required");
+    assert!(err.to_string().contains("consent"), "{}", err);
+
+    handle_method(&app, "settings.set", &json!({"key": "privacy.telemetry_consent", "value": true})).unwrap();
+    let res = handle_method(&app, "crash.upload", &json!({})).unwrap();
+    assert_eq!(res["uploaded"], 0);
+    assert!(res["issue_url"].as_str().unwrap().starts_with("https://github.com/gothtr/gitbrowser/issues/new?"));
+    assert_eq!(res["reports"][0]["error_type"], "renderer_crash");
+    assert!(res["reports"][0]["url"].is_null());
+
+    handle_method(&app, "settings.set", &json!({"key": "privacy.crash_report_urls", "value": true})).unwrap();
+    let res = handle_method(&app, "crash.upload", &json!({"ids": ["other"]})).unwrap();
+    assert_eq!(res["reports"], json!([]));
+    let res = handle_method(&app, "crash.upload", &json!({})).unwrap();
+    assert_eq!(res["reports"][0]["url"], "https://example.com/a");
+}
+
+#[test]
+fn test_renderer_crash_marks_active_tab() {
+    use gitbrowser::managers::tab_manager::TabManagerTrait;
+    let (app, _tmp) = setup();
+    let mut a = app.lock().unwrap();
+    assert!(a.handle_renderer_crash("crashed").is_none());
+
+    let id = a.tab_manager.create_tab(Some("https://example.com"), true);
+    assert_eq!(a.handle_renderer_crash("crashed").as_deref(), Some(id.as_str()));
+    assert!(a.tab_manager.get_tab(&id).unwrap().crashed);
+    drop(a);
+    let logs = handle_method(&app, "crash.logs", &json!({})).unwrap();
+    assert_eq!(logs[0]["tab_url"], "https://example.com");
+}
+
+#[test]
+fn test_renderer_crash_of_a_background_tab() {
+    use gitbrowser::managers::tab_manager::TabManagerTrait;
+    let (app, _tmp) = setup();
+    let mut a = app.lock().unwrap();
+    let active = a.tab_manager.create_tab(Some("https://example.com"), true);
+    let background = a.tab_manager.create_tab(Some("https://example.org"), false);
+    assert!(!a.handle_tab_renderer_crash("gone", "crashed"));
+
+    assert!(a.handle_tab_renderer_crash(&background, "crashed"));
+    assert!(a.tab_manager.get_tab(&background).unwrap().crashed);
+    assert!(!a.tab_manager.get_tab(&active).unwrap().crashed);
+    drop(a);
+    let logs = handle_method(&app, "crash.logs", &json!({})).unwrap();
+    assert_eq!(logs[0]["tab_url"], "https://example.org");
+}
+
 // ─── Password Manager ───
 
 #[test]
@@ -319,6 +1165,17 @@ fn test_password_unlock_and_lock() {
     assert_eq!(locked["unlocked"], false);
 }
 
+#[test]
+fn test_quick_unlock_needs_unlocked_vault() {
+    let (app, _tmp) = setup();
+    // Nothing to put in the keystore while the vault is locked
+    assert!(handle_method(&app, "password.set_quick_unlock", &json!({"enabled": true})).is_err());
+    assert!(handle_method(&app, "password.set_quick_unlock", &json!({})).is_err());
+
+    let unlocked = handle_method(&app, "password.is_unlocked", &json!({})).unwrap();
+    assert_eq!(unlocked["unlocked"], false);
+}
+
 #[test]
 fn test_password_save_list_decrypt_delete() {
     let (app, _tmp) = setup();
@@ -389,6 +1246,36 @@ fn test_password_update() {
     assert_eq!(dec["password"], "new_pass");
 }
 
+#[test]
+fn test_password_export_and_import() {
+    let (app, tmp) = setup();
+    handle_method(&app, "password.unlock", &json!({"master_password": "master1"})).unwrap();
+    let save_res = handle_method(&app, "password.save", &json!({
+        "url": "https://example.com", "username": "user", "password": "secret"
+    })).unwrap();
+    let id = save_res["id"].as_str().unwrap().to_string();
+
+    let path = tmp.path().join("vault.export");
+    let path = path.to_str().unwrap();
+    handle_method(&app, "password.export", &json!({"master_password": "export-pw", "path": path})).unwrap();
+    assert!(!std::fs::read_to_string(path).unwrap().contains("secret"));
+
+    handle_method(&app, "password.delete", &json!({"id": id})).unwrap();
+    let (wrong, parts) = call_streaming(&app, "password.import", json!({"master_password": "nope", "path": path}));
+    assert!(wrong.is_err());
+    assert!(parts.is_empty());
+    let (res, parts) = call_streaming(&app, "password.import", json!({"master_password": "export-pw", "path": path}));
+    assert_eq!(res.unwrap(), json!({"imported": 1, "chunks": 1}));
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0]["progress"], json!({"done": 1, "total": 1}));
+    assert_eq!(parts[0]["data"]["credential"], json!({"id": id, "url": "https://example.com", "username": "user"}));
+    let dec = handle_method(&app, "password.decrypt", &json!({"id": id})).unwrap();
+    assert_eq!(dec["password"], "secret");
+
+    handle_method(&app, "password.lock", &json!({})).unwrap();
+    assert!(handle_method(&app, "password.export", &json!({"master_password": "x", "path": path})).is_err());
+}
+
 #[test]
 fn test_password_generate() {
     let (app, _tmp) = setup();
@@ -411,7 +1298,7 @@ fn test_password_decrypt_not_found() {
     handle_method(&app, "password.unlock", &json!({"master_password": "m"})).unwrap();
     let res = handle_method(&app, "password.decrypt", &json!({"id": "nonexistent"}));
     assert!(res.is_err());
-    assert!(res.unwrap_err().contains("credential not found"));
+    assert!(res.unwrap_err().to_string().contains("credential not found"));
 }
 
 // ─── GitHub Integration ───
@@ -429,6 +1316,120 @@ fn test_github_store_and_get_token() {
     assert_eq!(res["token"], "ghp_test123");
 }
 
+#[test]
+fn test_github_device_login_reports_failure() {
+    let (app, _tmp) = setup();
+    let mut events = Vec::new();
+    complete_device_login(&app, &json!({}), &json!({"error": "bad"}), |e| events.push(e));
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["event"], "github.login");
+    assert_eq!(events[0]["status"], "failed");
+    let res = handle_method(&app, "github.get_token", &json!({})).unwrap();
+    assert!(res["token"].is_null());
+}
+
+#[test]
+fn test_github_notifications_empty_without_refresh() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "github.notifications", &json!({"unread_only": true})).unwrap();
+    assert_eq!(res, json!({"notifications": [], "unread": 0}));
+}
+
+#[test]
+fn test_github_notifications_refresh_requires_login() {
+    let (app, _tmp) = setup();
+    let err = handle_method(&app, "github.notifications", &json!({"refresh": true})).unwrap_err();
+    assert_eq!(err.to_string(), "Not authenticated with GitHub");
+    assert_eq!(err.code(), "github.not_authenticated");
+}
+
+#[test]
+fn test_github_endpoints_follow_enterprise_settings() {
+    let (app, _tmp) = setup_isolated();
+    let res = handle_method(&app, "github.endpoints", &json!({})).unwrap();
+    assert_eq!(res["web_url"], "https://github.com");
+    assert_eq!(res["enterprise"], false);
+
+    handle_method(&app, "settings.set", &json!({"key": "github.enterprise_web_url", "value": "https://ghe.example.com"})).unwrap();
+    handle_method(&app, "settings.set", &json!({"key": "github.use_enterprise", "value": true})).unwrap();
+    let res = handle_method(&app, "github.endpoints", &json!({})).unwrap();
+    assert_eq!(res["web_url"], "https://ghe.example.com");
+    assert_eq!(res["api_url"], "https://ghe.example.com/api/v3");
+    assert_eq!(res["enterprise"], true);
+    assert_eq!(res["authenticated"], false);
+
+    // Enterprise login needs the server's own OAuth app
+    let err = handle_method(&app, "github.login_device", &json!({})).unwrap_err().to_string();
+    assert!(err.contains("no OAuth client ID"));
+}
+
+#[test]
+fn test_gh_command_resolves_in_address_bar() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "search.resolve", &json!({"query": "gh rust-lang/rust issues"})).unwrap();
+    assert_eq!(res["url"], "https://github.com/rust-lang/rust/issues");
+
+    let res = handle_method(&app, "github.quick_open", &json!({"query": "gh rust-lang/rust#7"})).unwrap();
+    assert_eq!(res["url"], "https://github.com/rust-lang/rust/issues/7");
+    assert_eq!(res["suggestions"], json!([]));
+}
+
+#[test]
+fn test_sync_now_requires_login() {
+    let (app, _tmp) = setup();
+    let err = handle_method(&app, "sync.now", &json!({})).unwrap_err();
+    assert_eq!(err.to_string(), "Sync requires a GitHub login");
+    assert_eq!(err.code(), "sync.not_authenticated");
+}
+
+#[test]
+fn test_sync_set_passphrase() {
+    let (app, _tmp) = setup();
+    let err = handle_method(&app, "sync.set_passphrase", &json!({"passphrase": ""})).unwrap_err();
+    assert_eq!(err.code(), "sync.passphrase_required");
+    let res = handle_method(&app, "sync.set_passphrase", &json!({"passphrase": "shared secret"})).unwrap();
+    assert_eq!(res["ok"], true);
+}
+
+#[test]
+fn test_update_channel_follows_settings() {
+    let (app, _tmp) = setup_isolated();
+    let status = handle_method(&app, "update.status", &json!({})).unwrap();
+    assert_eq!(status["channel"], "stable");
+    assert!(status["ready"].is_null());
+
+    let res = handle_method(&app, "update.set_channel", &json!({"channel": "beta"})).unwrap();
+    assert_eq!(res["channel"], "beta");
+    let status = handle_method(&app, "update.status", &json!({})).unwrap();
+    assert_eq!(status["channel"], "beta");
+
+    let err = handle_method(&app, "update.set_channel", &json!({"channel": "canary"})).unwrap_err().to_string();
+    assert_eq!(err, "invalid channel");
+}
+
+#[test]
+fn test_update_install_requires_verified_download() {
+    let (app, _tmp) = setup();
+    let err = handle_method(&app, "update.install", &json!({})).unwrap_err().to_string();
+    assert_eq!(err, "Update installation failed: no verified update is ready to install");
+}
+
+#[test]
+fn test_sync_conflicts_empty_by_default() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "sync.conflicts", &json!({})).unwrap();
+    assert_eq!(res["conflicts"], json!([]));
+
+    let err = handle_method(&app, "sync.resolve_conflict", &json!({
+        "collection": "settings", "resolution": "keep_local"
+    })).unwrap_err().to_string();
+    assert_eq!(err, "No sync conflict for settings");
+    let err = handle_method(&app, "sync.resolve_conflict", &json!({
+        "collection": "settings", "resolution": "merge"
+    })).unwrap_err().to_string();
+    assert_eq!(err, "invalid resolution");
+}
+
 #[test]
 fn test_github_get_token_when_none() {
     let (app, _tmp) = setup();
@@ -498,6 +1499,84 @@ fn test_extension_install_missing_path() {
     assert!(handle_method(&app, "extension.install", &json!({})).is_err());
 }
 
+#[test]
+fn test_extension_install_requires_permission_confirmation() {
+    let (app, tmp) = setup();
+    let ext_dir = tmp.path().join("prompt-ext");
+    std::fs::create_dir_all(&ext_dir).unwrap();
+    std::fs::write(ext_dir.join("manifest.json"), r#"{
+        "id": "prompt-ext", "name": "Prompt", "version": "1.0.0", "permissions": ["storage", "tabs"]
+    }"#).unwrap();
+
+    let res = handle_method(&app, "extension.install", &json!({"path": ext_dir.to_str().unwrap()})).unwrap();
+    assert_eq!(res["pending"], true);
+    assert_eq!(res["install"]["permissions"], json!(["storage", "tabs"]));
+    let list = handle_method(&app, "extension.list", &json!({})).unwrap();
+    assert_eq!(list.as_array().unwrap().len(), 0);
+
+    let install_id = res["install"]["install_id"].as_str().unwrap();
+    let res = handle_method(&app, "extension.confirm_install", &json!({
+        "install_id": install_id, "permissions": ["tabs", "storage"]
+    })).unwrap();
+    assert_eq!(res["id"], "prompt-ext");
+    let list = handle_method(&app, "extension.list", &json!({})).unwrap();
+    assert_eq!(list.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_extension_install_without_permissions_is_immediate() {
+    let (app, tmp) = setup();
+    let ext_dir = tmp.path().join("quiet-ext");
+    std::fs::create_dir_all(&ext_dir).unwrap();
+    std::fs::write(ext_dir.join("manifest.json"), r#"{
+        "id": "quiet-ext", "name": "Quiet", "version": "1.0.0", "permissions": []
+    }"#).unwrap();
+
+    let res = handle_method(&app, "extension.install", &json!({"path": ext_dir.to_str().unwrap()})).unwrap();
+    assert_eq!(res["id"], "quiet-ext");
+}
+
+#[test]
+fn test_extension_blocking_rules_reach_privacy_engine() {
+    let (app, tmp) = setup();
+    let ext_dir = tmp.path().join("dnr-ext");
+    std::fs::create_dir_all(&ext_dir).unwrap();
+    std::fs::write(ext_dir.join("rules.json"), r#"[
+        {"id": 1, "action": {"type": "block"}, "condition": {"urlFilter": "||cdn.example.com^"}}
+    ]"#).unwrap();
+    std::fs::write(ext_dir.join("manifest.json"), r#"{
+        "id": "dnr-ext", "name": "Blocker", "version": "1.0.0",
+        "permissions": ["declarativeNetRequest"],
+        "declarative_net_request": {"rule_resources": [{"id": "r", "path": "rules.json"}]}
+    }"#).unwrap();
+    let check = json!({"url": "https://cdn.example.com/lib.js", "resource_type": "script"});
+    assert_eq!(handle_method(&app, "privacy.check_request", &check).unwrap()["blocked"], false);
+
+    let res = handle_method(&app, "extension.install", &json!({"path": ext_dir.to_str().unwrap()})).unwrap();
+    let install_id = res["install"]["install_id"].as_str().unwrap();
+    handle_method(&app, "extension.confirm_install", &json!({
+        "install_id": install_id, "permissions": ["declarativenetrequest"]
+    })).unwrap();
+    assert_eq!(handle_method(&app, "privacy.check_request", &check).unwrap()["blocked"], true);
+
+    handle_method(&app, "extension.disable", &json!({"id": "dnr-ext"})).unwrap();
+    assert_eq!(handle_method(&app, "privacy.check_request", &check).unwrap()["blocked"], false);
+}
+
+#[test]
+fn test_privacy_check_request_missing_url() {
+    let (app, _tmp) = setup();
+    assert!(handle_method(&app, "privacy.check_request", &json!({})).is_err());
+}
+
+#[test]
+fn test_extension_confirm_install_unknown_handle() {
+    let (app, _tmp) = setup();
+    assert!(handle_method(&app, "extension.confirm_install", &json!({"install_id": "nope", "permissions": []})).is_err());
+    assert!(handle_method(&app, "extension.confirm_install", &json!({"install_id": "nope"})).is_err());
+    assert!(handle_method(&app, "extension.cancel_install", &json!({"install_id": "nope"})).is_err());
+}
+
 #[test]
 fn test_extension_enable_disable_missing_id() {
     let (app, _tmp) = setup();
@@ -519,6 +1598,24 @@ fn test_extension_content_scripts_missing_url() {
     assert!(handle_method(&app, "extension.content_scripts", &json!({})).is_err());
 }
 
+#[test]
+fn test_extension_messaging_unknown_extension() {
+    let (app, _tmp) = setup();
+    assert!(handle_method(&app, "extension.send_message", &json!({"id": "nope", "message": {"a": 1}})).is_err());
+    let res = handle_method(&app, "extension.messages", &json!({"id": "nope"})).unwrap();
+    assert_eq!(res["messages"].as_array().unwrap().len(), 0);
+    assert!(handle_method(&app, "extension.reply", &json!({"id": "nope", "message_id": 1, "response": null})).is_err());
+}
+
+#[test]
+fn test_extension_storage_unknown_extension() {
+    let (app, _tmp) = setup();
+    assert!(handle_method(&app, "extension.storage_get", &json!({"id": "nope"})).is_err());
+    assert!(handle_method(&app, "extension.storage_set", &json!({"id": "nope", "items": {"a": 1}})).is_err());
+    assert!(handle_method(&app, "extension.storage_set", &json!({"id": "nope"})).is_err());
+    assert!(handle_method(&app, "extension.storage_remove", &json!({"id": "nope"})).is_err());
+}
+
 // ─── Secure Secret Storage ───
 
 #[test]
@@ -580,7 +1677,7 @@ fn test_secret_get_master_required_after_lock() {
     // Trying to get should fail because master is required
     let res = handle_method(&app, "secret.get", &json!({"key": "locked_secret"}));
     assert!(res.is_err());
-    assert!(res.unwrap_err().contains("master password required"));
+    assert!(res.unwrap_err().to_string().contains("master password required"));
 }
 
 // ─── Base64 helpers ───
@@ -600,3 +1697,524 @@ fn test_base64_decode_invalid() {
     let res = base64_decode("!!!not-base64!!!");
     assert!(res.is_err());
 }
+
+// ─── Tabs ───
+
+#[test]
+fn test_tab_create_list_switch_close() {
+    let (app, _tmp) = setup();
+    let first = handle_method(&app, "tab.create", &json!({"url": "https://a.example"})).unwrap();
+    let first_id = first["tab"]["id"].as_str().unwrap().to_string();
+    assert_eq!(first["tab"]["url"], "https://a.example");
+    assert_eq!(first["tab"]["suspended"], false);
+    let second = handle_method(&app, "tab.create", &json!({"url": "https://b.example", "active": false})).unwrap();
+    let second_id = second["tab"]["id"].as_str().unwrap().to_string();
+
+    let list = handle_method(&app, "tab.list", &json!({})).unwrap();
+    assert_eq!(list["tabs"].as_array().unwrap().len(), 2);
+    assert_eq!(list["active_tab_id"], json!(first_id));
+
+    handle_method(&app, "tab.switch", &json!({"tab_id": second_id})).unwrap();
+    handle_method(&app, "tab.suspend", &json!({"tab_id": first_id})).unwrap();
+    let list = handle_method(&app, "tab.list", &json!({})).unwrap();
+    assert_eq!(list["active_tab_id"], json!(second_id));
+    assert_eq!(list["tabs"][0]["suspended"], true);
+
+    let closed = handle_method(&app, "tab.close", &json!({"tab_id": second_id})).unwrap();
+    assert_eq!(closed["active_tab_id"], json!(first_id));
+    assert!(handle_method(&app, "tab.close", &json!({"tab_id": second_id})).is_err());
+    assert!(handle_method(&app, "tab.switch", &json!({})).unwrap_err().to_string().contains("missing tab_id"));
+}
+
+#[test]
+fn test_tab_pin_and_mute() {
+    let (app, _tmp) = setup();
+    let tab = |url: &str| {
+        let res = handle_method(&app, "tab.create", &json!({"url": url})).unwrap();
+        res["tab"]["id"].as_str().unwrap().to_string()
+    };
+    let (a, b) = (tab("https://a.example"), tab("https://b.example"));
+
+    let pinned = handle_method(&app, "tab.pin", &json!({"tab_id": b})).unwrap();
+    assert_eq!(pinned["order"], json!([b, a]));
+    let unpinned = handle_method(&app, "tab.pin", &json!({"tab_id": b, "pinned": false})).unwrap();
+    assert_eq!(unpinned["order"][0], json!(b));
+    let list = handle_method(&app, "tab.list", &json!({})).unwrap();
+    assert_eq!(list["tabs"][0]["pinned"], false);
+
+    let muted = |params| handle_method(&app, "tab.mute", &params).unwrap()["muted"].clone();
+    assert_eq!(muted(json!({"tab_id": a})), true);
+    assert_eq!(muted(json!({"tab_id": a, "muted": true})), true);
+    assert_eq!(muted(json!({"tab_id": a})), false);
+    assert_eq!(muted(json!({"tab_id": a, "muted": false})), false);
+    assert!(handle_method(&app, "tab.mute", &json!({"tab_id": "nope"})).is_err());
+}
+
+#[test]
+fn test_tab_zoom_is_remembered_per_site() {
+    let (app, _tmp) = setup();
+    let tab = |url: &str| {
+        let res = handle_method(&app, "tab.create", &json!({"url": url})).unwrap();
+        res["tab"]["id"].as_str().unwrap().to_string()
+    };
+    let (a, b) = (tab("https://docs.example.com/a"), tab("https://other.example/"));
+    let zoom = |tab: &str, step: &str| {
+        handle_method(&app, "tab.zoom", &json!({"tab_id": tab, "step": step})).unwrap()["zoom"].clone()
+    };
+
+    assert_eq!(zoom(&a, "in"), 1.1);
+    assert_eq!(zoom(&a, "in"), 1.25);
+    assert_eq!(zoom(&b, "out"), 0.9);
+    let read = handle_method(&app, "tab.zoom", &json!({"tab_id": a})).unwrap();
+    assert_eq!(read["zoom"], 1.25);
+
+    // Another page of the same site opens at the remembered zoom
+    let c = tab("https://docs.example.com/b");
+    let mut locked = app.lock().unwrap();
+    assert_eq!(locked.zoom_for("https://docs.example.com/c"), Some(1.25));
+    assert_eq!(locked.apply_site_zoom(&c, "https://docs.example.com/b").unwrap(), 1.25);
+    assert_eq!(locked.apply_site_zoom(&c, "https://fresh.example/").unwrap(), 1.0);
+    drop(locked);
+
+    assert_eq!(zoom(&a, "reset"), 1.0);
+    assert_eq!(app.lock().unwrap().zoom_for("https://docs.example.com/"), None);
+    assert!(handle_method(&app, "tab.zoom", &json!({"tab_id": a, "step": "sideways"})).is_err());
+    assert!(handle_method(&app, "tab.zoom", &json!({"tab_id": "nope", "step": "in"})).is_err());
+}
+
+#[test]
+fn test_page_print_asks_the_webview_and_records_pdfs_as_downloads() {
+    use gitbrowser::services::event_bus::AppEvent;
+    use gitbrowser::types::tab::PageAction;
+
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "tab.create", &json!({"url": "https://example.com/report"})).unwrap();
+    let tab = res["tab"]["id"].as_str().unwrap().to_string();
+    let events = app.lock().unwrap().bus.subscribe();
+    let page_actions = || -> Vec<PageAction> {
+        events.try_iter().filter_map(|e| match e {
+            AppEvent::PageActionRequested { tab_id, action } if tab_id == tab => Some(action),
+            _ => None,
+        }).collect()
+    };
+
+    let res = handle_method(&app, "page.print", &json!({"tab_id": tab})).unwrap();
+    assert!(res["download_id"].is_null());
+    assert_eq!(page_actions(), vec![PageAction::Print]);
+
+    let res = handle_method(&app, "page.print", &json!({"tab_id": tab, "pdf": true})).unwrap();
+    let id = res["download_id"].as_str().unwrap().to_string();
+    let downloads = handle_method(&app, "download.list", &json!({})).unwrap();
+    let item = downloads["downloads"].as_array().unwrap().iter().find(|d| d["id"] == id.as_str()).unwrap().clone();
+    assert_eq!(item["url"], "https://example.com/report");
+    assert!(item["filename"].as_str().unwrap().ends_with(".pdf"));
+    match page_actions().as_slice() {
+        [PageAction::PrintToPdf { download_id, path }] => {
+            assert_eq!(download_id, &id);
+            assert_eq!(path.as_str(), item["filepath"].as_str().unwrap());
+        }
+        other => panic!("unexpected page actions: {:?}", other),
+    }
+
+    assert!(handle_method(&app, "page.print", &json!({"tab_id": "nope"})).is_err());
+}
+
+#[test]
+fn test_link_downloads_keep_name_referrer_and_size() {
+    use gitbrowser::managers::download_manager::DownloadManagerTrait;
+    use gitbrowser::types::download::{DownloadRequest, DownloadStatus};
+
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "tab.create", &json!({"url": "https://example.com/releases"})).unwrap();
+    let tab = res["tab"]["id"].as_str().unwrap().to_string();
+    let request = DownloadRequest {
+        url: "https://cdn.example.com/get?id=7".to_string(),
+        suggested_name: "../tool v1.2.tar.gz".to_string(),
+        size: Some(2048),
+        mime_type: Some("application/gzip".to_string()),
+        ..Default::default()
+    };
+    let (id, path) = app.lock().unwrap().start_link_download(&tab, request.clone()).unwrap();
+    let (_, second) = app.lock().unwrap().start_link_download(&tab, request).unwrap();
+    assert!(path.ends_with("tool v1.2.tar.gz"));
+    assert!(second.ends_with("tool v1.2.tar (1).gz"));
+
+    let downloads = handle_method(&app, "download.list", &json!({})).unwrap();
+    let item = downloads["downloads"].as_array().unwrap().iter().find(|d| d["id"] == id.as_str()).unwrap().clone();
+    assert_eq!(item["referrer"], "https://example.com/releases");
+    assert_eq!(item["size"], 2048);
+    assert_eq!(item["mime_type"], "application/gzip");
+
+    let mut a = app.lock().unwrap();
+    a.download_manager.fail_download(&id, "Network error").unwrap();
+    a.download_manager.update_progress(&id, 2048, None).unwrap();
+    assert!(matches!(a.download_manager.get_download(&id).unwrap().status, DownloadStatus::Failed(ref r) if r == "Network error"));
+}
+
+#[test]
+fn test_page_screenshot_defaults_to_the_full_page() {
+    use gitbrowser::services::event_bus::AppEvent;
+    use gitbrowser::types::tab::PageAction;
+
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "tab.create", &json!({"url": "https://example.com/"})).unwrap();
+    let tab = res["tab"]["id"].as_str().unwrap().to_string();
+    let events = app.lock().unwrap().bus.subscribe();
+    let screenshots = || -> Vec<(String, String, bool)> {
+        events.try_iter().filter_map(|e| match e {
+            AppEvent::PageActionRequested { action: PageAction::Screenshot { download_id, path, full_page }, .. } => {
+                Some((download_id, path, full_page))
+            }
+            _ => None,
+        }).collect()
+    };
+
+    let full = handle_method(&app, "page.screenshot", &json!({"tab_id": tab})).unwrap();
+    let visible = handle_method(&app, "page.screenshot", &json!({"tab_id": tab, "full_page": false})).unwrap();
+    let full = full["download_id"].as_str().unwrap().to_string();
+    let visible = visible["download_id"].as_str().unwrap().to_string();
+    let taken = screenshots();
+    let summary: Vec<(String, bool)> = taken.iter().map(|(id, _, full_page)| (id.clone(), *full_page)).collect();
+    assert_eq!(summary, vec![(full, true), (visible, false)]);
+    // Both are named after the page, yet get files of their own
+    assert!(taken.iter().all(|(_, path, _)| path.ends_with(".png")));
+    assert_ne!(taken[0].1, taken[1].1);
+    assert!(handle_method(&app, "page.screenshot", &json!({"tab_id": "nope"})).is_err());
+}
+
+// ─── Downloads ───
+
+#[test]
+fn test_download_lifecycle_with_progress() {
+    let (app, _tmp) = setup();
+    let params = json!({"url": "https://example.com/a.zip", "filepath": "/tmp/a.zip"});
+    let started = handle_method(&app, "download.start", &params).unwrap();
+    let id = started["download"]["id"].as_str().unwrap().to_string();
+    assert_eq!(started["download"]["filename"], "a.zip");
+    assert_eq!(started["download"]["progress"], serde_json::Value::Null);
+
+    let res = handle_method(&app, "download.progress", &json!({"id": id, "downloaded": 1536, "size": 16384})).unwrap();
+    assert_eq!(res["download"]["status"], "InProgress");
+    assert_eq!(res["download"]["progress"], 0.09375);
+    assert_eq!(res["download"]["downloaded_text"], "1.5 KB");
+    assert_eq!(res["download"]["size_text"], "16 KB");
+
+    let paused = handle_method(&app, "download.pause", &json!({"id": id})).unwrap();
+    assert_eq!(paused["download"]["status"], "Paused");
+    handle_method(&app, "download.resume", &json!({"id": id})).unwrap();
+    let done = handle_method(&app, "download.progress", &json!({"id": id, "downloaded": 16384})).unwrap();
+    assert_eq!(done["download"]["status"], "Completed");
+    assert_eq!(done["download"]["progress"], 1.0);
+    assert!(handle_method(&app, "download.pause", &json!({"id": id})).is_err());
+
+    let list = handle_method(&app, "download.list", &json!({})).unwrap();
+    assert_eq!(list["downloads"].as_array().unwrap().len(), 1);
+    assert_eq!(handle_method(&app, "download.get", &json!({"id": id})).unwrap()["download"]["id"], json!(id));
+}
+
+#[test]
+fn test_download_cancel_retry_and_errors() {
+    let (app, _tmp) = setup();
+    let params = json!({"url": "https://example.com/b.bin", "filepath": "/tmp/b.bin"});
+    let started = handle_method(&app, "download.start", &params).unwrap();
+    let id = started["download"]["id"].as_str().unwrap().to_string();
+
+    let cancelled = handle_method(&app, "download.cancel", &json!({"id": id})).unwrap();
+    assert_eq!(cancelled["download"]["status"], json!({"Failed": "Cancelled"}));
+    let retried = handle_method(&app, "download.retry", &json!({"id": id})).unwrap();
+    assert_eq!(retried["download"]["status"], "Pending");
+
+    let err = handle_method(&app, "download.get", &json!({"id": "nope"})).unwrap_err();
+    assert!(err.to_string().contains("not found"));
+    assert_eq!(err.code(), "download.not_found");
+    assert!(handle_method(&app, "download.start", &json!({"url": "file:///etc/passwd", "filepath": "/tmp/x"}))
+        .unwrap_err().to_string()
+        .contains("invalid url"));
+    assert!(handle_method(&app, "download.start", &json!({"url": "https://example.com/c"})).is_err());
+}
+
+// ─── Site permissions ───
+
+#[test]
+fn test_permission_set_get_list_revoke_reset() {
+    let (app, _tmp) = setup();
+    let site = "https://a.example";
+    let get = |permission: &str| {
+        let params = json!({"origin": site, "permission": permission});
+        handle_method(&app, "permission.get", &params).unwrap()["value"].clone()
+    };
+    assert_eq!(get("Camera"), "Ask");
+
+    let set = |origin: &str, permission: &str, value: &str| {
+        let params = json!({"origin": origin, "permission": permission, "value": value});
+        handle_method(&app, "permission.set", &params)
+    };
+    set(site, "Camera", "Allow").unwrap();
+    set(site, "Microphone", "Deny").unwrap();
+    set("https://b.example", "Geolocation", "Allow").unwrap();
+    assert_eq!(get("Camera"), "Allow");
+
+    let all = handle_method(&app, "permission.list", &json!({})).unwrap();
+    assert_eq!(all["permissions"].as_array().unwrap().len(), 3);
+    let site_only = handle_method(&app, "permission.list", &json!({"origin": site})).unwrap();
+    assert_eq!(site_only["permissions"].as_array().unwrap().len(), 2);
+
+    handle_method(&app, "permission.revoke", &json!({"origin": site, "permission": "Camera"})).unwrap();
+    assert_eq!(get("Camera"), "Ask");
+    handle_method(&app, "permission.reset_site", &json!({"origin": site})).unwrap();
+    assert_eq!(get("Microphone"), "Ask");
+
+    assert_eq!(set(site, "Telepathy", "Allow").unwrap_err().to_string(), "invalid permission");
+    assert_eq!(set(site, "Camera", "Maybe").unwrap_err().to_string(), "invalid value");
+}
+
+#[test]
+fn test_permission_prompt_flow() {
+    use gitbrowser::services::event_hub::EventTopic;
+    use std::sync::Arc;
+    let (app, _tmp) = setup();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    app.lock().unwrap().events.subscribe(
+        1,
+        &[EventTopic::Permissions],
+        Arc::new(move |value: &serde_json::Value| sink.lock().unwrap().push(value["params"]["data"].clone())),
+    );
+    let site = "https://cam.example";
+    let request = json!({"origin": site, "permission": "Camera"});
+
+    let asked = handle_method(&app, "permission.request", &request).unwrap();
+    assert_eq!(asked, json!({"prompt": true, "value": "Ask"}));
+    assert_eq!(events.lock().unwrap().last().unwrap()["action"], "prompt");
+
+    // Answered once: nothing stored, so the next request prompts again
+    let once = json!({"origin": site, "permission": "Camera", "value": "Allow", "remember": false});
+    handle_method(&app, "permission.respond", &once).unwrap();
+    assert_eq!(events.lock().unwrap().last().unwrap()["action"], "answered");
+    assert_eq!(handle_method(&app, "permission.request", &request).unwrap()["prompt"], true);
+
+    let always = json!({"origin": site, "permission": "Camera", "value": "Deny"});
+    handle_method(&app, "permission.respond", &always).unwrap();
+    let decided = handle_method(&app, "permission.request", &request).unwrap();
+    assert_eq!(decided, json!({"prompt": false, "value": "Deny"}));
+
+    let ask = json!({"origin": site, "permission": "Camera", "value": "Ask"});
+    assert!(handle_method(&app, "permission.respond", &ask).unwrap_err().to_string().starts_with("invalid value"));
+}
+
+// ─── Privacy ───
+
+#[test]
+fn test_privacy_should_block_respects_site_shields() {
+    let (app, _tmp) = setup_isolated();
+    let tracker = "https://www.google-analytics.com/analytics.js";
+    let check = |page: &str| {
+        let params = json!({"url": tracker, "resource_type": "script", "page_url": page, "record": true});
+        handle_method(&app, "privacy.should_block", &params).unwrap()["blocked"].clone()
+    };
+    assert_eq!(check("https://news.example/story"), true);
+
+    let shields = |on: bool| {
+        handle_method(&app, "privacy.set_site", &json!({"url": "https://news.example/", "shields": on})).unwrap()
+    };
+    assert_eq!(shields(false), json!({"site": "news.example", "shields": false}));
+    assert_eq!(check("https://news.example/other"), false);
+    assert_eq!(check("https://blog.example/"), true);
+    let site = handle_method(&app, "privacy.site", &json!({"url": "https://news.example/a"})).unwrap();
+    assert_eq!(site["shields"], false);
+
+    shields(true);
+    assert_eq!(check("https://news.example/other"), true);
+
+    let stats = handle_method(&app, "privacy.stats", &json!({})).unwrap();
+    assert_eq!(stats["stats"]["trackers_blocked"], 3);
+    assert_eq!(stats["private_mode"], false);
+}
+
+#[test]
+fn test_filter_request_blocks_upgrades_and_counts_per_tab() {
+    use gitbrowser::managers::tab_manager::TabManagerTrait;
+    use gitbrowser::services::privacy_engine::PrivacyEngineTrait;
+    use gitbrowser::types::privacy::RequestDecision;
+
+    let (app, _tmp) = setup_isolated();
+    let mut a = app.lock().unwrap();
+    let tab = a.tab_manager.create_tab(Some("https://news.example/"), true);
+    let page = Some("https://news.example/story");
+    let tracker = "https://www.google-analytics.com/analytics.js";
+    assert_eq!(a.filter_request(Some(&tab), tracker, "script", page), RequestDecision::Block);
+    assert_eq!(a.filter_request(Some(&tab), "https://doubleclick.net/x.js", "script", page), RequestDecision::Block);
+    assert_eq!(a.tab_manager.get_tab(&tab).unwrap().blocked_requests, 2);
+    assert_eq!(
+        a.filter_request(Some(&tab), "http://cdn.example/app.js", "script", page),
+        RequestDecision::Upgrade("https://cdn.example/app.js".to_string())
+    );
+    assert_eq!(a.filter_request(Some(&tab), "http://localhost:8080/", "main_frame", None), RequestDecision::Allow);
+    assert_eq!(a.filter_request(Some(&tab), "http://127.0.0.1/api", "xmlhttprequest", page), RequestDecision::Allow);
+    assert_eq!(a.filter_request(Some(&tab), "gb://localhost/newtab", "main_frame", None), RequestDecision::Allow);
+    assert_eq!(a.privacy_engine.get_stats().https_upgrades, 1);
+    assert_eq!(a.privacy_engine.get_stats().blocked_by_site.get("news.example"), Some(&2));
+
+    // Nothing is blocked or upgraded where protections are off
+    drop(a);
+    handle_method(&app, "privacy.set_site", &json!({"url": "https://news.example/", "shields": false})).unwrap();
+    let mut a = app.lock().unwrap();
+    assert_eq!(a.filter_request(Some(&tab), tracker, "script", page), RequestDecision::Allow);
+    assert_eq!(a.filter_request(Some(&tab), "http://cdn.example/a.js", "script", page), RequestDecision::Allow);
+
+    // A new page starts the tab's count over
+    let next = "https://blog.example/";
+    assert_eq!(a.filter_request(Some(&tab), next, "main_frame", Some(next)), RequestDecision::Allow);
+    assert_eq!(a.tab_manager.get_tab(&tab).unwrap().blocked_requests, 0);
+    drop(a);
+    let tabs = handle_method(&app, "tab.list", &json!({})).unwrap();
+    let listed = tabs["tabs"].as_array().unwrap().iter().find(|t| t["id"] == tab.as_str()).unwrap();
+    assert_eq!(listed["blocked_requests"], 0);
+}
+
+#[test]
+fn test_privacy_dashboard_counts_blocks_per_site() {
+    let (app, _tmp) = setup_isolated();
+    let block = |page: &str| {
+        let params = json!({"url": "https://doubleclick.net/ad.js", "page_url": page, "record": true});
+        handle_method(&app, "privacy.should_block", &params).unwrap();
+    };
+    block("https://news.example/a");
+    block("https://news.example/b");
+    block("https://blog.example/");
+    handle_method(&app, "privacy.set_site", &json!({"url": "https://shop.example/", "shields": false})).unwrap();
+
+    let dash = handle_method(&app, "privacy.dashboard", &json!({})).unwrap();
+    assert_eq!(dash["total_blocked"], 3);
+    assert_eq!(dash["top_sites"][0], json!({"site": "news.example", "blocked": 2, "shields": true}));
+    assert_eq!(dash["top_sites"][1]["site"], "blog.example");
+    assert_eq!(dash["disabled_sites"], json!(["shop.example"]));
+    assert_eq!(dash["toggles"]["tracker_blocking"], true);
+    assert!(dash["lists"][0]["updated_at"].is_i64());
+}
+
+#[test]
+fn test_privacy_filter_lists_and_custom_filters() {
+    let (app, _tmp) = setup_isolated();
+    let ad = json!({"url": "https://example.com/ads/banner.js", "resource_type": "script"});
+    assert_eq!(handle_method(&app, "privacy.should_block", &ad).unwrap()["blocked"], true);
+    let enable = |list: &str, enabled: bool| {
+        handle_method(&app, "privacy.filters.enable", &json!({"list": list, "enabled": enabled}))
+    };
+    let lists = enable("ads", false).unwrap();
+    assert_eq!(lists["lists"][1]["id"], "ads");
+    assert_eq!(lists["lists"][1]["enabled"], false);
+    assert_eq!(handle_method(&app, "privacy.should_block", &ad).unwrap()["blocked"], false);
+    assert!(enable("cookies", false).is_err());
+
+    let custom = json!({"url": "https://cdn.example.org/x.js"});
+    let added = handle_method(&app, "privacy.filters.add", &json!({"filter": "||cdn.example.org^"})).unwrap();
+    assert_eq!(added, json!({"changed": true, "custom": ["||cdn.example.org^"]}));
+    let again = handle_method(&app, "privacy.filters.add", &json!({"filter": "||cdn.example.org^"})).unwrap();
+    assert_eq!(again["changed"], false);
+    assert_eq!(handle_method(&app, "privacy.should_block", &custom).unwrap()["blocked"], true);
+    let listed = handle_method(&app, "privacy.filters.list", &json!({})).unwrap();
+    assert_eq!(listed["custom"], json!(["||cdn.example.org^"]));
+
+    handle_method(&app, "privacy.filters.remove", &json!({"filter": "||cdn.example.org^"})).unwrap();
+    assert_eq!(handle_method(&app, "privacy.should_block", &custom).unwrap()["blocked"], false);
+}
+
+#[test]
+fn test_privacy_private_mode_and_doh() {
+    let (app, _tmp) = setup_isolated();
+    let on = handle_method(&app, "privacy.set_private_mode", &json!({"enabled": true})).unwrap();
+    assert_eq!(on["private_mode"], true);
+    let off = handle_method(&app, "privacy.set_private_mode", &json!({"enabled": false})).unwrap();
+    assert_eq!(off["private_mode"], false);
+
+    let doh = json!({"provider": "https://dns.quad9.net/dns-query"});
+    let res = handle_method(&app, "privacy.configure_doh", &doh).unwrap();
+    assert_eq!(res["dns_over_https"], "https://dns.quad9.net/dns-query");
+    let settings = handle_method(&app, "settings.get", &json!({})).unwrap();
+    assert_eq!(settings["privacy"]["dns_provider"], "https://dns.quad9.net/dns-query");
+
+    let res = handle_method(&app, "privacy.configure_doh", &json!({"enabled": false})).unwrap();
+    assert_eq!(res["dns_over_https"], serde_json::Value::Null);
+    let plain = json!({"provider": "http://dns.example/dns-query"});
+    assert!(handle_method(&app, "privacy.configure_doh", &plain).unwrap_err().to_string().starts_with("invalid provider"));
+}
+
+// ─── Event subscriptions ───
+
+#[test]
+fn test_subscribe_pushes_tab_events() {
+    use gitbrowser::managers::tab_manager::TabManagerTrait;
+    use gitbrowser::rpc_handler::handle_subscription;
+    use gitbrowser::services::event_hub::Deliver;
+    use std::sync::Arc;
+    let (app, _tmp) = setup();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let deliver: Deliver = Arc::new(move |value: &serde_json::Value| sink.lock().unwrap().push(value.clone()));
+
+    let err = handle_subscription(&app, 1, "subscribe", &json!({"topics": ["weather"]}), &deliver).unwrap();
+    assert!(err.unwrap_err().to_string().contains("invalid topic"));
+    assert!(handle_subscription(&app, 1, "ping", &json!({}), &deliver).is_none());
+
+    let sub = handle_subscription(&app, 1, "subscribe", &json!({"topics": ["tabs"]}), &deliver).unwrap().unwrap();
+    assert_eq!(sub["topics"], json!(["tabs"]));
+    let tab_id = app.lock().unwrap().tab_manager.create_tab(Some("https://example.com"), false);
+    handle_method(&app, "tab.suspend", &json!({"tab_id": tab_id})).unwrap();
+    {
+        let received = received.lock().unwrap();
+        let last = &received.last().unwrap()["params"];
+        assert_eq!(last["subscription"], sub["subscription"]);
+        assert_eq!(last["data"]["action"], "suspended");
+        assert_eq!(last["data"]["tab_id"], json!(tab_id));
+    }
+
+    let id = sub["subscription"].clone();
+    assert!(handle_subscription(&app, 2, "unsubscribe", &json!({"subscription": id}), &deliver).unwrap().is_err());
+    handle_subscription(&app, 1, "unsubscribe", &json!({"subscription": id}), &deliver).unwrap().unwrap();
+    let count = received.lock().unwrap().len();
+    handle_method(&app, "tab.resume", &json!({"tab_id": tab_id})).unwrap();
+    assert_eq!(received.lock().unwrap().len(), count);
+}
+
+// ─── Introspection ───
+
+#[test]
+fn test_rpc_discover_lists_methods_and_params() {
+    let (app, _tmp) = setup();
+    let res = handle_method(&app, "rpc.discover", &json!({})).unwrap();
+    let methods = res["methods"].as_array().unwrap();
+    let add = methods.iter().find(|m| m["name"] == "bookmark.add").unwrap();
+    assert_eq!(add["params"][0], json!({"name": "url", "type": "string", "required": true}));
+    assert_eq!(add["params"][2], json!({"name": "folder_id", "type": "string", "required": false}));
+    assert!(methods.iter().any(|m| m["name"] == "subscribe"));
+    assert!(methods.iter().any(|m| m["name"] == "rpc.discover"));
+    assert_eq!(add["streaming"], false);
+    let export = methods.iter().find(|m| m["name"] == "history.export").unwrap();
+    assert_eq!(export["streaming"], true);
+}
+
+#[test]
+fn test_method_registry_matches_dispatcher() {
+    use gitbrowser::rpc_methods::{find, METHODS};
+    // Method names are the top-level arms of `handle_method`
+    let source = include_str!("../../src/rpc_handler.rs");
+    let body = &source[source.find("pub fn handle_method").unwrap()..];
+    let body = &body[..body.find("\n}\n").unwrap()];
+    let dispatched: Vec<&str> = body
+        .lines()
+        .filter(|line| line.starts_with("        \"") && line.contains("=>"))
+        .flat_map(|line| line[..line.find("=>").unwrap()].split('|'))
+        .map(|name| name.trim().trim_matches('"'))
+        .collect();
+    assert!(dispatched.len() > 100);
+
+    for name in &dispatched {
+        assert!(find(name).is_some(), "{} is dispatched but not registered", name);
+    }
+    for spec in METHODS {
+        let elsewhere = ["subscribe", "unsubscribe"].contains(&spec.name) || spec.streaming;
+        assert!(elsewhere || dispatched.contains(&spec.name), "{} is registered but not dispatched", spec.name);
+        assert_eq!(METHODS.iter().filter(|m| m.name == spec.name).count(), 1, "{} is registered twice", spec.name);
+    }
+}
@@ -0,0 +1,87 @@
+//! Unit tests for the RPC request/response size and rate limits.
+
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use gitbrowser::rpc_limits::{
+    bounded_lines, oversized_response, LineSplitter, LineTooLong, RateLimiter, MAX_RESPONSE_BYTES,
+};
+use gitbrowser::rpc_protocol::{error_codes, parse_line, Incoming, TOO_LARGE};
+use gitbrowser::types::errors::GitBrowserError;
+
+// ─── Request lines ───
+
+#[test]
+fn test_lines_split_across_reads() {
+    let mut splitter = LineSplitter::new(64);
+    assert!(splitter.feed(b"{\"id\":").is_empty());
+    let lines = splitter.feed(b"1}\r\n{\"id\":2}\n{\"id\"");
+    assert_eq!(lines, vec![Ok("{\"id\":1}".to_string()), Ok("{\"id\":2}".to_string())]);
+    assert_eq!(splitter.finish(), Some(Ok("{\"id\"".to_string())));
+    assert_eq!(splitter.finish(), None);
+}
+
+#[test]
+fn test_long_line_is_dropped_and_the_next_one_read() {
+    let mut splitter = LineSplitter::new(8);
+    assert!(splitter.feed(b"0123456").is_empty());
+    assert!(splitter.feed(b"789abc").is_empty());
+    let lines = splitter.feed(b"def\nshort\n");
+    assert_eq!(lines, vec![Err(LineTooLong { size: 16 }), Ok("short".to_string())]);
+}
+
+#[test]
+fn test_bounded_lines_reads_to_the_end() {
+    let input = format!("ping\n{}\nlast", "x".repeat(100));
+    let lines: Vec<_> = bounded_lines(Cursor::new(input), 10).collect();
+    assert_eq!(lines, vec![Ok("ping".to_string()), Err(LineTooLong { size: 100 }), Ok("last".to_string())]);
+}
+
+#[test]
+fn test_too_long_response_is_structured() {
+    let response = LineTooLong { size: 100 }.response(10);
+    assert_eq!(response["id"], serde_json::Value::Null);
+    assert_eq!(response["error"]["code"], TOO_LARGE);
+    assert_eq!(response["error"]["message"], "request too large");
+    assert_eq!(response["error"]["data"], json!({"size": 100, "limit": 10}));
+}
+
+// ─── Responses ───
+
+#[test]
+fn test_oversized_response() {
+    assert_eq!(oversized_response(&json!({"ok": true})), None);
+    let big = json!("x".repeat(MAX_RESPONSE_BYTES));
+    assert_eq!(oversized_response(&big), Some(MAX_RESPONSE_BYTES + 2));
+}
+
+#[test]
+fn test_limit_errors_carry_data_in_v2_only() {
+    let Ok(Incoming::Single(Ok(call))) = parse_line(r#"{"jsonrpc":"2.0","id":7,"method":"ping"}"#) else {
+        panic!("expected a call");
+    };
+    let response = call.error_with_data(GitBrowserError::RateLimited, json!({"limit_per_second": 200})).unwrap();
+    assert_eq!(response["id"], 7);
+    assert_eq!(response["error"]["data"]["limit_per_second"], 200);
+    assert_eq!(response["error"]["data"]["code"], "rate_limited");
+
+    let Ok(Incoming::Single(Ok(call))) = parse_line(r#"{"id":8,"method":"ping"}"#) else {
+        panic!("expected a call");
+    };
+    let response = call.error_with_data(GitBrowserError::TooLarge("response"), json!({})).unwrap();
+    assert_eq!(response, json!({"id": 8, "error": "response too large", "error_code": "too_large"}));
+    assert_eq!(error_codes(&GitBrowserError::TooLarge("response")).0, TOO_LARGE);
+}
+
+// ─── Rate ───
+
+#[test]
+fn test_rate_limiter_resets_each_second() {
+    let mut limiter = RateLimiter::new(3);
+    let start = Instant::now();
+    assert!((0..3).all(|_| limiter.check_at(start)));
+    assert!(!limiter.check_at(start + Duration::from_millis(500)));
+    assert!(limiter.check_at(start + Duration::from_secs(1)));
+}
@@ -0,0 +1,112 @@
+//! Unit tests for JSON-RPC 2.0 framing and the original-format shim.
+
+use gitbrowser::rpc_protocol::*;
+use gitbrowser::types::errors::{BookmarkError, EncryptionError, GitBrowserError, GitHubError, TabError};
+use serde_json::{json, Value};
+
+fn single(line: &str) -> Result<Call, Value> {
+    match parse_line(line).expect("line should parse") {
+        Incoming::Single(call) => call,
+        Incoming::Batch(_) => panic!("expected a single request"),
+    }
+}
+
+#[test]
+fn test_v2_request_and_responses() {
+    let call = single(r#"{"jsonrpc":"2.0","id":7,"method":"ping","params":{"a":1}}"#).unwrap();
+    assert_eq!(call.protocol, Protocol::V2);
+    assert_eq!(call.method, "ping");
+    assert_eq!(call.params, json!({"a": 1}));
+
+    assert_eq!(call.response(&Ok(json!("pong"))), Some(json!({"jsonrpc": "2.0", "id": 7, "result": "pong"})));
+    let err = call.response(&Err(GitBrowserError::InvalidParams("missing url".to_string()))).unwrap();
+    assert_eq!(err["error"]["code"], INVALID_PARAMS);
+    assert_eq!(err["error"]["message"], "missing url");
+    assert_eq!(err["error"]["data"]["code"], "invalid_params");
+    let err = call.response(&Err(GitBrowserError::MethodNotFound("nope".to_string()))).unwrap();
+    assert_eq!(err["error"]["code"], METHOD_NOT_FOUND);
+    assert_eq!(err["error"]["message"], "unknown method: nope");
+}
+
+#[test]
+fn test_v2_notification_gets_no_response() {
+    let call = single(r#"{"jsonrpc":"2.0","method":"session.save"}"#).unwrap();
+    assert!(call.is_notification());
+    assert_eq!(call.params, json!({}));
+    assert_eq!(call.response(&Ok(json!({"ok": true}))), None);
+    assert_eq!(call.response(&Err(GitBrowserError::Unauthorized)), None);
+}
+
+#[test]
+fn test_legacy_format_is_answered_in_kind() {
+    let call = single(r#"{"id":3,"method":"ping"}"#).unwrap();
+    assert_eq!(call.protocol, Protocol::Legacy);
+    assert_eq!(call.response(&Ok(json!("pong"))), Some(json!({"id": 3, "result": "pong"})));
+    assert_eq!(
+        call.response(&Err(GitBrowserError::InvalidParams("missing url".to_string()))),
+        Some(json!({"id": 3, "error": "missing url", "error_code": "invalid_params"}))
+    );
+
+    // Without id the original server still answered, with id null
+    let call = single(r#"{"method":"ping"}"#).unwrap();
+    assert!(!call.is_notification());
+    assert_eq!(call.response(&Ok(json!("pong"))), Some(json!({"id": null, "result": "pong"})));
+}
+
+#[test]
+fn test_batch_parses_each_request() {
+    let line = r#"[{"jsonrpc":"2.0","id":1,"method":"a"},{"jsonrpc":"2.0","method":"b"},42]"#;
+    let Incoming::Batch(items) = parse_line(line).unwrap() else { panic!("expected a batch") };
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].as_ref().unwrap().method, "a");
+    assert!(items[1].as_ref().unwrap().is_notification());
+    assert_eq!(items[2].as_ref().unwrap_err()["error"]["code"], INVALID_REQUEST);
+}
+
+#[test]
+fn test_malformed_lines() {
+    assert_eq!(parse_line("{not json").unwrap_err()["error"]["code"], PARSE_ERROR);
+    assert_eq!(parse_line("[]").unwrap_err()["error"]["code"], INVALID_REQUEST);
+
+    let err = single(r#"{"jsonrpc":"1.0","id":1,"method":"ping"}"#).unwrap_err();
+    assert_eq!(err["error"]["code"], INVALID_REQUEST);
+    assert_eq!(err["id"], 1);
+    let err = single(r#"{"jsonrpc":"2.0","id":2}"#).unwrap_err();
+    assert_eq!(err["error"]["code"], INVALID_REQUEST);
+    let err = single(r#"{"jsonrpc":"2.0","id":3,"method":"ping","params":5}"#).unwrap_err();
+    assert_eq!(err["error"]["code"], INVALID_PARAMS);
+}
+
+#[test]
+fn test_module_errors_carry_stable_codes() {
+    let call = single(r#"{"jsonrpc":"2.0","id":1,"method":"bookmark.add"}"#).unwrap();
+    let err = call.response(&Err(BookmarkError::DuplicateUrl("https://a.example".to_string()).into())).unwrap();
+    assert_eq!(err["error"]["code"], ALREADY_EXISTS);
+    assert_eq!(err["error"]["message"], "Duplicate bookmark URL: https://a.example");
+    assert_eq!(err["error"]["data"]["code"], "bookmark.duplicate_url");
+
+    let locked = GitBrowserError::from(EncryptionError::PasswordRequired);
+    assert_eq!(error_codes(&locked), (LOCKED, "encryption.password_required"));
+    let missing = GitBrowserError::from(TabError::NotFound("t1".to_string()));
+    assert_eq!(error_codes(&missing), (NOT_FOUND, "tab.not_found"));
+    let limited = GitBrowserError::from(GitHubError::RateLimited(60));
+    assert_eq!(error_codes(&limited), (RATE_LIMITED, "github.rate_limited"));
+    assert_eq!(error_codes(&GitBrowserError::RateLimited), (RATE_LIMITED, "rate_limited"));
+
+    let call = single(r#"{"id":2,"method":"tab.close"}"#).unwrap();
+    assert_eq!(
+        call.response(&Err(missing)),
+        Some(json!({"id": 2, "error": "Tab not found: t1", "error_code": "tab.not_found"}))
+    );
+}
+
+#[test]
+fn test_error_codes() {
+    assert_eq!(error_codes(&GitBrowserError::MethodNotFound("x".to_string())), (METHOD_NOT_FOUND, "method_not_found"));
+    assert_eq!(error_codes(&GitBrowserError::InvalidParams("x".to_string())), (INVALID_PARAMS, "invalid_params"));
+    assert_eq!(error_codes(&GitBrowserError::Unauthorized), (UNAUTHORIZED, "unauthorized"));
+    assert_eq!(error_codes(&GitBrowserError::TooLarge("request")), (TOO_LARGE, "too_large"));
+    // A message that merely looks like a parameter error stays an application error
+    let missing = GitBrowserError::from(BookmarkError::NotFound("invalid url".to_string()));
+    assert_eq!(error_codes(&missing).0, NOT_FOUND);
+}
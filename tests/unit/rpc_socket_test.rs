@@ -0,0 +1,96 @@
+//! Unit tests for the local socket RPC transport and the single-instance
+//! hand-off over it.
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+
+use gitbrowser::rpc_socket::{endpoint_for, endpoint_named, listen, OnConnect};
+use gitbrowser::single_instance;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+/// Answers every line with `{"echo": <line>}`.
+fn echo() -> OnConnect {
+    Arc::new(|lines, sink| {
+        for line in lines {
+            match line {
+                Ok(line) => sink(&json!({"echo": line})),
+                Err(too_long) => sink(&json!({"too_long": too_long.size})),
+            }
+        }
+    })
+}
+
+fn ask(stream: &mut UnixStream, line: &str) -> Value {
+    writeln!(stream, "{}", line).unwrap();
+    let mut answer = String::new();
+    BufReader::new(stream.try_clone().unwrap()).read_line(&mut answer).unwrap();
+    serde_json::from_str(&answer).unwrap()
+}
+
+#[test]
+fn test_clients_are_served_concurrently() {
+    let dir = TempDir::new().unwrap();
+    let endpoint = endpoint_for(dir.path());
+    let _listener = listen(&endpoint, echo()).unwrap();
+
+    let mut first = UnixStream::connect(&endpoint).unwrap();
+    let mut second = UnixStream::connect(&endpoint).unwrap();
+    assert_eq!(ask(&mut second, "b"), json!({"echo": "b"}));
+    assert_eq!(ask(&mut first, "a"), json!({"echo": "a"}));
+}
+
+#[test]
+fn test_socket_is_private_and_removed_on_drop() {
+    let dir = TempDir::new().unwrap();
+    let endpoint = endpoint_for(dir.path());
+    let listener = listen(&endpoint, echo()).unwrap();
+    let mode = std::fs::metadata(&endpoint).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    // The private directory it was bound in is gone
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    drop(listener);
+    assert!(!std::path::Path::new(&endpoint).exists());
+}
+
+#[test]
+fn test_live_socket_is_not_taken_over() {
+    let dir = TempDir::new().unwrap();
+    let endpoint = endpoint_for(dir.path());
+    let _listener = listen(&endpoint, echo()).unwrap();
+    let err = listen(&endpoint, echo()).err().expect("second listener must fail");
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+}
+
+#[test]
+fn test_stale_socket_is_replaced() {
+    let dir = TempDir::new().unwrap();
+    let endpoint = endpoint_for(dir.path());
+    // A socket file nobody listens on, as left by a crashed backend
+    drop(std::os::unix::net::UnixListener::bind(&endpoint).unwrap());
+    let _listener = listen(&endpoint, echo()).unwrap();
+    let mut client = UnixStream::connect(&endpoint).unwrap();
+    assert_eq!(ask(&mut client, "x"), json!({"echo": "x"}));
+}
+
+#[test]
+fn test_hand_off_reaches_running_instance() {
+    let dir = TempDir::new().unwrap();
+    assert!(single_instance::hand_off(dir.path(), &[]).is_err());
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let tx = std::sync::Mutex::new(tx);
+    let _listener = single_instance::listen(dir.path(), move |urls| {
+        let _ = tx.lock().unwrap().send(urls);
+    })
+    .unwrap();
+    assert_ne!(endpoint_for(dir.path()), endpoint_named(dir.path(), "instance"));
+
+    let urls = vec!["gitbrowser://settings/privacy".to_string()];
+    single_instance::hand_off(dir.path(), &urls).unwrap();
+    assert_eq!(rx.recv().unwrap(), urls);
+}
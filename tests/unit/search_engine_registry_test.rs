@@ -0,0 +1,120 @@
+//! Unit tests for the SearchEngineRegistry.
+//!
+//! Covers engine CRUD and validation, default selection, keyword triggers,
+//! and query/suggestion URL expansion.
+
+use gitbrowser::services::search_engine_registry::{
+    encode_query, url_for_input, SearchEngineRegistry, SearchEngineRegistryTrait,
+};
+use gitbrowser::types::errors::SearchEngineError;
+use gitbrowser::types::search::{SearchEngine, SearchSettings};
+
+fn setup() -> SearchEngineRegistry {
+    SearchEngineRegistry::new(SearchSettings::default())
+}
+
+fn engine(id: &str, keyword: Option<&str>) -> SearchEngine {
+    SearchEngine {
+        id: id.to_string(),
+        name: id.to_uppercase(),
+        url_template: format!("https://{}.example/search?q=%s", id),
+        keyword: keyword.map(String::from),
+        suggest_url: None,
+    }
+}
+
+// ─── Queries ───
+
+#[test]
+fn test_default_engine_search_url() {
+    let registry = setup();
+    assert_eq!(
+        registry.search_url("rust borrow checker").unwrap(),
+        "https://www.google.com/search?q=rust+borrow+checker"
+    );
+}
+
+#[test]
+fn test_keyword_trigger_selects_engine() {
+    let registry = setup();
+    assert_eq!(registry.search_url("ddg  c++ & rust").unwrap(), "https://duckduckgo.com/?q=c%2B%2B+%26+rust");
+    // A bare keyword with no query is searched for literally
+    assert_eq!(registry.search_url("ddg").unwrap(), "https://www.google.com/search?q=ddg");
+}
+
+#[test]
+fn test_suggestion_url() {
+    let mut registry = setup();
+    registry.set_default("bing").unwrap();
+    assert_eq!(
+        registry.suggestion_url("rust").unwrap(),
+        "https://api.bing.com/osjson.aspx?query=rust"
+    );
+    registry.add_engine(engine("plain", None)).unwrap();
+    registry.set_default("plain").unwrap();
+    assert!(registry.suggestion_url("rust").is_none());
+}
+
+#[test]
+fn test_encode_query() {
+    assert_eq!(encode_query("a b/c?d=é"), "a+b%2Fc%3Fd%3D%C3%A9");
+}
+
+// ─── Management ───
+
+#[test]
+fn test_add_update_remove_engine() {
+    let mut registry = setup();
+    registry.add_engine(engine("wiki", Some("w"))).unwrap();
+    assert_eq!(registry.search_url("w Rust").unwrap(), "https://wiki.example/search?q=Rust");
+
+    let mut updated = engine("wiki", Some("wp"));
+    updated.name = "Wikipedia".into();
+    registry.update_engine(updated).unwrap();
+    assert_eq!(registry.get_engine("wiki").unwrap().name, "Wikipedia");
+
+    registry.remove_engine("wiki").unwrap();
+    assert!(registry.get_engine("wiki").is_none());
+    assert!(matches!(registry.remove_engine("wiki"), Err(SearchEngineError::NotFound(_))));
+}
+
+#[test]
+fn test_validation() {
+    let mut registry = setup();
+    assert!(matches!(registry.add_engine(engine("google", None)), Err(SearchEngineError::AlreadyExists(_))));
+    assert!(matches!(registry.add_engine(engine("bad id", None)), Err(SearchEngineError::InvalidId(_))));
+    assert!(matches!(registry.add_engine(engine("dup", Some("G"))), Err(SearchEngineError::InvalidKeyword(_))));
+
+    let mut no_placeholder = engine("np", None);
+    no_placeholder.url_template = "https://np.example/search".into();
+    assert!(matches!(registry.add_engine(no_placeholder), Err(SearchEngineError::InvalidTemplate(_))));
+
+    let mut js = engine("js", None);
+    js.url_template = "javascript:alert(%s)".into();
+    assert!(matches!(registry.add_engine(js), Err(SearchEngineError::InvalidTemplate(_))));
+}
+
+#[test]
+fn test_removing_default_falls_back_and_last_engine_is_kept() {
+    let mut registry = setup();
+    registry.remove_engine("google").unwrap();
+    assert_eq!(registry.settings().default_engine, "duckduckgo");
+    registry.remove_engine("bing").unwrap();
+    assert!(matches!(registry.remove_engine("duckduckgo"), Err(SearchEngineError::LastEngine)));
+    assert!(matches!(registry.set_default("google"), Err(SearchEngineError::NotFound(_))));
+}
+
+#[test]
+fn test_url_for_input() {
+    let search = SearchSettings::default();
+    assert_eq!(url_for_input("  ", &search), "about:newtab");
+    assert_eq!(url_for_input("about:settings", &search), "about:settings");
+    assert_eq!(url_for_input("about:downloads", &search), "about:downloads");
+    assert_eq!(url_for_input("http://example.com", &search), "http://example.com");
+    assert_eq!(url_for_input("example.com", &search), "https://example.com");
+    let searched = url_for_input("rust borrow checker", &search);
+    assert_eq!(Some(searched), setup().search_url("rust borrow checker"));
+    assert_eq!(url_for_input("gitbrowser://settings/privacy", &search), "about:settings#privacy");
+    assert_eq!(url_for_input("gitbrowser://nowhere", &search), "about:newtab");
+}
+
@@ -117,3 +117,346 @@ fn test_reset_restores_defaults() {
         );
     }
 }
+
+/// Reader appearance is persisted through the regular `reader.*` keys, and
+/// enum presets are validated on the way in.
+#[test]
+fn test_reader_settings_persist_via_dot_keys() {
+    use gitbrowser::types::reader::ReaderPreset;
+    let dir = TempDir::new().unwrap();
+
+    {
+        let mut engine = engine_in_temp(&dir);
+        engine.load().unwrap();
+        engine.set_value("reader.preset", serde_json::json!("Sepia")).unwrap();
+        engine.set_value("reader.justify", serde_json::json!(true)).unwrap();
+        engine.set_value("reader.column_width", serde_json::json!(60)).unwrap();
+        assert!(engine.set_value("reader.preset", serde_json::json!("Neon")).is_err());
+    }
+
+    let mut engine2 = engine_in_temp(&dir);
+    let loaded = engine2.load().unwrap();
+    assert_eq!(loaded.reader.preset, ReaderPreset::Sepia);
+    assert!(loaded.reader.justify);
+    assert_eq!(loaded.reader.column_width, 60);
+}
+
+/// Config files written before the reader section existed still load, with
+/// reader defaults filled in.
+#[test]
+fn test_load_config_without_reader_section() {
+    let dir = TempDir::new().unwrap();
+    let mut value = serde_json::to_value(BrowserSettings::default()).unwrap();
+    value.as_object_mut().unwrap().remove("reader");
+    std::fs::write(dir.path().join("settings.json"), value.to_string()).unwrap();
+
+    let mut engine = engine_in_temp(&dir);
+    let loaded = engine.load().unwrap();
+    assert_eq!(loaded.reader, gitbrowser::types::reader::ReaderSettings::default());
+}
+
+/// Subscribers receive one event per changed leaf, with old and new values.
+#[test]
+fn test_subscribe_receives_changes() {
+    let dir = TempDir::new().unwrap();
+    let mut engine = engine_in_temp(&dir);
+    engine.load().unwrap();
+    let rx = engine.subscribe();
+
+    engine.set_value("general.language", serde_json::json!("ru")).unwrap();
+    let change = rx.try_recv().unwrap();
+    assert_eq!(change.key, "general.language");
+    assert_eq!(change.old_value, serde_json::json!("en"));
+    assert_eq!(change.new_value, serde_json::json!("ru"));
+    assert!(rx.try_recv().is_err(), "Only the changed key is reported");
+
+    // Setting the same value again is not a change
+    engine.set_value("general.language", serde_json::json!("ru")).unwrap();
+    assert!(rx.try_recv().is_err());
+}
+
+/// Resetting reports every key that differed from the defaults, and a
+/// dropped receiver does not break later updates.
+#[test]
+fn test_reset_notifies_and_dropped_subscribers_are_pruned() {
+    let dir = TempDir::new().unwrap();
+    let mut engine = engine_in_temp(&dir);
+    engine.load().unwrap();
+    drop(engine.subscribe());
+    let rx = engine.subscribe();
+
+    engine.set_value("privacy.tracker_blocking", serde_json::json!(false)).unwrap();
+    engine.set_value("shortcuts.new_tab", serde_json::json!("Ctrl+Shift+T")).unwrap();
+    let _ = rx.try_iter().count();
+
+    engine.reset().unwrap();
+    let mut keys: Vec<String> = rx.try_iter().map(|c| c.key).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["privacy.tracker_blocking", "shortcuts.new_tab"]);
+}
+
+/// Every schema key is documented and accepts its default and each allowed
+/// value through `set_value`.
+#[test]
+fn test_schema_keys_are_settable() {
+    use gitbrowser::services::settings_engine::settings_schema;
+    let dir = TempDir::new().unwrap();
+    let mut engine = engine_in_temp(&dir);
+    engine.load().unwrap();
+
+    let schema = settings_schema();
+    assert!(schema.iter().any(|s| s.key == "general.language" && s.value_type == "string"));
+    assert!(schema.iter().any(|s| s.key == "reader.line_height" && s.value_type == "number"));
+    for entry in &schema {
+        assert!(!entry.description.is_empty(), "{} has no description", entry.key);
+        engine.set_value(&entry.key, entry.default.clone()).unwrap();
+        for allowed in entry.allowed_values.iter().flatten() {
+            engine.set_value(&entry.key, allowed.clone()).unwrap();
+        }
+    }
+
+    let theme = schema.iter().find(|s| s.key == "appearance.theme").unwrap();
+    assert_eq!(theme.value_type, "enum");
+    assert_eq!(theme.allowed_values.as_ref().unwrap().len(), 3);
+    assert!(schema.iter().find(|s| s.key == "ai.active_model").unwrap().nullable);
+}
+
+/// Profiles can be created (empty or cloned), switched and deleted, and the
+/// active profile survives a reload.
+#[test]
+fn test_settings_profiles_lifecycle() {
+    use gitbrowser::types::errors::SettingsError;
+    let dir = TempDir::new().unwrap();
+    let mut engine = engine_in_temp(&dir);
+    engine.load().unwrap();
+    assert_eq!(engine.list_profiles(), vec!["Default"]);
+    assert_eq!(engine.active_profile(), "Default");
+
+    engine.set_value("privacy.tracker_blocking", serde_json::json!(false)).unwrap();
+    engine.create_profile("Work", Some("Default")).unwrap();
+    engine.create_profile("Hardened", None).unwrap();
+    assert!(matches!(engine.create_profile("work", None), Err(SettingsError::ProfileExists(_))));
+    assert!(engine.create_profile("../etc", None).is_err());
+    assert_eq!(engine.list_profiles(), vec!["Default", "Work", "Hardened"]);
+
+    let work = engine.switch_profile("Work").unwrap();
+    assert!(!work.privacy.tracker_blocking, "Cloned profile copies settings");
+    engine.set_value("general.language", serde_json::json!("ru")).unwrap();
+
+    let hardened = engine.switch_profile("Hardened").unwrap();
+    assert_eq!(hardened, BrowserSettings::default());
+
+    // Active profile and its values persist across engines
+    let mut engine2 = engine_in_temp(&dir);
+    engine2.load().unwrap();
+    assert_eq!(engine2.active_profile(), "Hardened");
+    let work = engine2.switch_profile("Work").unwrap();
+    assert_eq!(work.general.language, "ru");
+
+    assert!(engine2.delete_profile("Work").is_err(), "Active profile cannot be deleted");
+    assert!(engine2.delete_profile("Default").is_err());
+    engine2.delete_profile("Hardened").unwrap();
+    assert!(matches!(engine2.switch_profile("Hardened"), Err(SettingsError::ProfileNotFound(_))));
+    assert_eq!(engine2.list_profiles(), vec!["Default", "Work"]);
+}
+
+/// Switching profiles notifies subscribers of every differing key.
+#[test]
+fn test_switch_profile_notifies_subscribers() {
+    let dir = TempDir::new().unwrap();
+    let mut engine = engine_in_temp(&dir);
+    engine.load().unwrap();
+    engine.create_profile("Dark", None).unwrap();
+    engine.switch_profile("Dark").unwrap();
+    engine.set_value("appearance.theme", serde_json::json!("Dark")).unwrap();
+    engine.switch_profile("Default").unwrap();
+
+    let rx = engine.subscribe();
+    engine.switch_profile("Dark").unwrap();
+    let keys: Vec<String> = rx.try_iter().map(|c| c.key).collect();
+    assert_eq!(keys, vec!["appearance.theme"]);
+}
+
+/// Saved files carry the current `schema_version`.
+#[test]
+fn test_saved_file_is_versioned() {
+    use gitbrowser::services::settings_migrations::SETTINGS_SCHEMA_VERSION;
+    let dir = TempDir::new().unwrap();
+    let mut engine = engine_in_temp(&dir);
+    engine.load().unwrap();
+    engine.save().unwrap();
+
+    let raw: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("settings.json")).unwrap()).unwrap();
+    assert_eq!(raw["schema_version"], serde_json::json!(SETTINGS_SCHEMA_VERSION));
+}
+
+/// An unversioned legacy file is upgraded on load: renamed keys move,
+/// enum spellings are normalized, missing sections get defaults, removed
+/// options are dropped, and the upgraded file is written back.
+#[test]
+fn test_legacy_settings_file_is_migrated() {
+    use gitbrowser::services::settings_migrations::SETTINGS_SCHEMA_VERSION;
+    use gitbrowser::types::settings::ThemeMode;
+    let dir = TempDir::new().unwrap();
+    let mut legacy = serde_json::to_value(BrowserSettings::default()).unwrap();
+    {
+        let map = legacy.as_object_mut().unwrap();
+        map.remove("performance");
+        map.insert("language".into(), serde_json::json!("ru"));
+        map.insert("legacy_sidebar".into(), serde_json::json!(true));
+        map["general"].as_object_mut().unwrap().remove("language");
+        map["appearance"]["theme"] = serde_json::json!("dark");
+        map["shortcuts"].as_object_mut().unwrap().insert("custom".into(), serde_json::json!("Ctrl+K"));
+    }
+    let path = dir.path().join("settings.json");
+    std::fs::write(&path, legacy.to_string()).unwrap();
+
+    let mut engine = engine_in_temp(&dir);
+    let loaded = engine.load().unwrap();
+    assert_eq!(loaded.general.language, "ru");
+    assert_eq!(loaded.appearance.theme, ThemeMode::Dark);
+    assert_eq!(loaded.performance, BrowserSettings::default().performance);
+    assert_eq!(loaded.shortcuts.get("custom").unwrap(), "Ctrl+K");
+
+    let raw: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(raw["schema_version"], serde_json::json!(SETTINGS_SCHEMA_VERSION));
+    assert!(raw.get("language").is_none());
+    assert!(raw.get("legacy_sidebar").is_none());
+}
+
+/// A pre-registry file's `general.default_search_engine` becomes the
+/// registry's default engine.
+#[test]
+fn test_default_search_engine_migrates_to_registry() {
+    let dir = TempDir::new().unwrap();
+    let mut legacy = serde_json::to_value(BrowserSettings::default()).unwrap();
+    {
+        let map = legacy.as_object_mut().unwrap();
+        map.remove("search");
+        map.insert("schema_version".into(), serde_json::json!(2));
+        map["general"].as_object_mut().unwrap().insert("default_search_engine".into(), serde_json::json!("bing"));
+    }
+    std::fs::write(dir.path().join("settings.json"), legacy.to_string()).unwrap();
+
+    let mut engine = engine_in_temp(&dir);
+    let loaded = engine.load().unwrap();
+    assert_eq!(loaded.search.default_engine, "bing");
+    assert_eq!(loaded.search.engines.len(), 3);
+}
+
+/// Exported settings can be previewed and imported into another engine;
+/// previews list changes without applying them.
+#[test]
+fn test_export_preview_and_import() {
+    let src_dir = TempDir::new().unwrap();
+    let mut source = engine_in_temp(&src_dir);
+    source.load().unwrap();
+    source.set_value("general.language", serde_json::json!("ru")).unwrap();
+    source.set_value("appearance.font_size", serde_json::json!(16)).unwrap();
+    let export_path = src_dir.path().join("export").join("settings-export.json");
+    let export_path = export_path.to_string_lossy().to_string();
+    let stripped = source.export_to(&export_path).unwrap();
+    assert!(stripped.is_empty());
+
+    let dst_dir = TempDir::new().unwrap();
+    let mut target = engine_in_temp(&dst_dir);
+    target.load().unwrap();
+
+    let mut keys: Vec<String> = target.preview_import(&export_path).unwrap().into_iter().map(|c| c.key).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["appearance.font_size", "general.language"]);
+    assert_eq!(target.get_settings().general.language, "en", "Preview must not apply");
+
+    let changes = target.import_from(&export_path).unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(target.get_settings().general.language, "ru");
+    let mut reloaded = engine_in_temp(&dst_dir);
+    assert_eq!(reloaded.load().unwrap().appearance.font_size, 16);
+}
+
+/// Secrets are stripped from exports and never taken from an import, and
+/// invalid files are rejected without touching the current settings.
+#[test]
+fn test_import_ignores_secrets_and_rejects_invalid_files() {
+    let dir = TempDir::new().unwrap();
+    let mut engine = engine_in_temp(&dir);
+    engine.load().unwrap();
+
+    let mut doc = serde_json::to_value(BrowserSettings::default()).unwrap();
+    doc["ai"]["api_key"] = serde_json::json!("sk-secret");
+    doc["github_token"] = serde_json::json!("ghp_secret");
+    doc["general"]["homepage"] = serde_json::json!("https://example.com");
+    let path = dir.path().join("with-secrets.json");
+    std::fs::write(&path, doc.to_string()).unwrap();
+    let path = path.to_string_lossy().to_string();
+
+    let keys: Vec<String> = engine.preview_import(&path).unwrap().into_iter().map(|c| c.key).collect();
+    assert_eq!(keys, vec!["general.homepage"]);
+    engine.import_from(&path).unwrap();
+    let saved = std::fs::read_to_string(dir.path().join("settings.json")).unwrap();
+    assert!(!saved.contains("secret"));
+
+    let bad = dir.path().join("bad.json");
+    std::fs::write(&bad, r#"{"appearance": {"theme": 42}, "schema_version": 3}"#).unwrap();
+    assert!(engine.import_from(&bad.to_string_lossy()).is_err());
+    std::fs::write(&bad, "[1, 2, 3]").unwrap();
+    assert!(engine.import_from(&bad.to_string_lossy()).is_err());
+    assert_eq!(engine.get_settings().general.homepage, "https://example.com");
+}
+
+/// Saves keep the previous good file as `settings.json.bak` and leave no
+/// temp file behind.
+#[test]
+fn test_save_keeps_backup_of_previous_version() {
+    let dir = TempDir::new().unwrap();
+    let mut engine = engine_in_temp(&dir);
+    engine.load().unwrap();
+    engine.set_value("general.language", serde_json::json!("ru")).unwrap();
+    engine.set_value("general.language", serde_json::json!("de")).unwrap();
+
+    let backup = std::fs::read_to_string(dir.path().join("settings.json.bak")).unwrap();
+    let backup: BrowserSettings = serde_json::from_str(&backup).unwrap();
+    assert_eq!(backup.general.language, "ru");
+    assert!(!dir.path().join("settings.json.tmp").exists());
+}
+
+/// A corrupted settings file is restored from the backup on load, and the
+/// recovery is reported.
+#[test]
+fn test_load_recovers_from_backup() {
+    let dir = TempDir::new().unwrap();
+    {
+        let mut engine = engine_in_temp(&dir);
+        engine.load().unwrap();
+        engine.set_value("general.language", serde_json::json!("ru")).unwrap();
+        engine.set_value("appearance.font_size", serde_json::json!(16)).unwrap();
+    }
+    std::fs::write(dir.path().join("settings.json"), "{ truncated").unwrap();
+
+    let mut engine = engine_in_temp(&dir);
+    let loaded = engine.load().unwrap();
+    assert_eq!(loaded.general.language, "ru");
+    assert!(engine.recovery_notice().unwrap().contains("settings.json.bak"));
+
+    // The main file is rewritten from the backup
+    let mut engine2 = engine_in_temp(&dir);
+    assert_eq!(engine2.load().unwrap().general.language, "ru");
+    assert!(engine2.recovery_notice().is_none());
+}
+
+#[test]
+fn test_sync_snapshot_round_trip_keeps_local_shortcuts() {
+    let (dir_a, dir_b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let mut a = engine_in_temp(&dir_a);
+    a.set_value("general.language", serde_json::json!("ru")).unwrap();
+    let snapshot = a.sync_snapshot().unwrap();
+    assert!(snapshot.get("shortcuts").is_none());
+
+    let mut b = engine_in_temp(&dir_b);
+    b.set_value("shortcuts.new_tab", serde_json::json!("Ctrl+N")).unwrap();
+    let changes = b.apply_synced(snapshot).unwrap();
+    assert!(changes.iter().any(|c| c.key == "general.language"));
+    assert_eq!(b.get_settings().general.language, "ru");
+    assert_eq!(b.get_settings().shortcuts["new_tab"], "Ctrl+N");
+}
@@ -0,0 +1,69 @@
+//! Unit tests for the app's shutdown pipeline.
+
+use gitbrowser::app::App;
+use gitbrowser::managers::history_manager::{HistoryManager, HistoryManagerTrait};
+use gitbrowser::managers::profile_manager::ProfileManager;
+use gitbrowser::managers::session_manager::SessionManagerTrait;
+use gitbrowser::managers::tab_manager::TabManagerTrait;
+use gitbrowser::services::password_manager::PasswordManagerTrait;
+use gitbrowser::services::settings_engine::SettingsEngineTrait;
+use gitbrowser::types::shutdown::{ShutdownStep, StepStatus};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn setup() -> (App, TempDir) {
+    let tmp = TempDir::new().unwrap();
+    let app = App::open_profile(&ProfileManager::new(tmp.path().to_path_buf()), "test", None).unwrap();
+    (app, tmp)
+}
+
+#[test]
+fn test_steps_run_in_order_once() {
+    let (mut app, _tmp) = setup();
+    app.tab_manager.create_tab(Some("https://example.com"), true);
+    app.password_manager.unlock("master").unwrap();
+
+    let report = app.shutdown();
+    let steps: Vec<ShutdownStep> = report.steps.iter().map(|s| s.step).collect();
+    assert_eq!(steps, ShutdownStep::ALL);
+    assert!(report.is_clean());
+    assert_eq!(report.status(ShutdownStep::SaveSession), Some(&StepStatus::Done));
+    assert_eq!(report.status(ShutdownStep::ClearOnExit), Some(&StepStatus::Skipped));
+    assert_eq!(report.status(ShutdownStep::CheckpointWal), Some(&StepStatus::Done));
+
+    assert!(app.is_shutting_down());
+    assert!(app.session_manager.has_session());
+    assert!(!app.password_manager.is_unlocked());
+    assert!(!app.session_manager.is_periodic_save_running());
+
+    assert!(app.shutdown().steps.is_empty());
+}
+
+#[test]
+fn test_clear_data_on_exit() {
+    let (mut app, _tmp) = setup();
+    app.tab_manager.create_tab(Some("https://example.com"), true);
+    HistoryManager::new(app.db.connection()).record_visit("https://example.com", "Example").unwrap();
+    app.settings_engine.set_value("privacy.clear_data_on_exit", json!(true)).unwrap();
+    app.process_settings_changes();
+
+    let report = app.shutdown();
+    assert!(report.is_clean());
+    assert_eq!(report.status(ShutdownStep::SaveSession), Some(&StepStatus::Skipped));
+    assert_eq!(report.status(ShutdownStep::ClearOnExit), Some(&StepStatus::Done));
+    assert!(HistoryManager::new(app.db.connection()).list_history(None).unwrap().is_empty());
+    assert!(!app.session_manager.has_session());
+}
+
+#[test]
+fn test_private_app_skips_disk_steps() {
+    let (app, _tmp) = setup();
+    let mut private = app.open_private().unwrap();
+
+    let report = private.shutdown();
+    assert!(report.is_clean());
+    for step in [ShutdownStep::SaveSession, ShutdownStep::MarkCleanExit, ShutdownStep::CheckpointWal] {
+        assert_eq!(report.status(step), Some(&StepStatus::Skipped));
+    }
+    assert_eq!(report.status(ShutdownStep::ClearOnExit), Some(&StepStatus::Done));
+}
@@ -0,0 +1,62 @@
+//! Unit tests for the SiteSettingsManager public API.
+//!
+//! These tests exercise per-site overrides through the
+//! `SiteSettingsManagerTrait` interface, using an in-memory SQLite database.
+
+use gitbrowser::database::Database;
+use gitbrowser::managers::site_settings_manager::{SiteSettingsManager, SiteSettingsManagerTrait, FORCE_DARK_KEY, USER_AGENT_KEY};
+use serde_json::json;
+
+#[test]
+fn test_set_get_and_replace() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = SiteSettingsManager::new(db.connection());
+
+    assert_eq!(mgr.get_setting("example.com", FORCE_DARK_KEY).unwrap(), None);
+    mgr.set_setting("example.com", FORCE_DARK_KEY, &json!(true)).unwrap();
+    assert_eq!(mgr.get_setting("example.com", FORCE_DARK_KEY).unwrap(), Some(json!(true)));
+
+    mgr.set_setting("example.com", FORCE_DARK_KEY, &json!(false)).unwrap();
+    assert_eq!(mgr.get_setting("example.com", FORCE_DARK_KEY).unwrap(), Some(json!(false)));
+    assert_eq!(mgr.get_site_settings("example.com").unwrap().len(), 1);
+}
+
+#[test]
+fn test_list_remove_and_clear() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = SiteSettingsManager::new(db.connection());
+
+    mgr.set_setting("b.com", FORCE_DARK_KEY, &json!(true)).unwrap();
+    mgr.set_setting("a.com", FORCE_DARK_KEY, &json!(false)).unwrap();
+    mgr.set_setting("a.com", "zoom", &json!(1.25)).unwrap();
+
+    let sites: Vec<String> = mgr.list_sites(FORCE_DARK_KEY).unwrap().into_iter().map(|s| s.site).collect();
+    assert_eq!(sites, vec!["a.com", "b.com"]);
+
+    assert!(mgr.remove_setting("b.com", FORCE_DARK_KEY).unwrap());
+    assert!(!mgr.remove_setting("b.com", FORCE_DARK_KEY).unwrap());
+
+    mgr.clear_site("a.com").unwrap();
+    assert!(mgr.get_site_settings("a.com").unwrap().is_empty());
+}
+
+#[test]
+fn test_invalid_site_and_key_rejected() {
+    let db = Database::open_in_memory().unwrap();
+    let mut mgr = SiteSettingsManager::new(db.connection());
+
+    assert!(mgr.set_setting("", FORCE_DARK_KEY, &json!(true)).is_err());
+    assert!(mgr.set_setting("a b.com", FORCE_DARK_KEY, &json!(true)).is_err());
+    assert!(mgr.set_setting("example.com", "Bad-Key", &json!(true)).is_err());
+    assert!(mgr.set_setting("example.com", USER_AGENT_KEY, &json!(true)).is_err());
+    assert!(mgr.set_setting("example.com", USER_AGENT_KEY, &json!("Bad\nAgent")).is_err());
+    mgr.set_setting("example.com", USER_AGENT_KEY, &json!("Mozilla/5.0 (X11; Linux x86_64)")).unwrap();
+}
+
+#[test]
+fn test_site_for_url() {
+    assert_eq!(SiteSettingsManager::site_for_url("https://Example.com/a?b#c").as_deref(), Some("example.com"));
+    assert_eq!(SiteSettingsManager::site_for_url("http://user@host:8080/").as_deref(), Some("host:8080"));
+    assert_eq!(SiteSettingsManager::site_for_url("gb://localhost/newtab"), None);
+    assert_eq!(SiteSettingsManager::site_for_url("https://"), None);
+}
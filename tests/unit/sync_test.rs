@@ -0,0 +1,423 @@
+//! Unit tests for the gist-backed sync service.
+//!
+//! Two devices with their own databases and settings share an in-memory
+//! remote, so pull, push and merge rules run end to end without a network.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde_json::json;
+use tempfile::TempDir;
+
+use gitbrowser::database::Database;
+use gitbrowser::managers::bookmark_manager::{BookmarkManager, BookmarkManagerTrait};
+use gitbrowser::managers::reading_list_manager::{ReadingListManager, ReadingListManagerTrait};
+use gitbrowser::services::settings_engine::{SettingsEngine, SettingsEngineTrait};
+use gitbrowser::services::sync::{merge_data, merge_payloads, SyncRemote, SyncService, SyncServiceTrait};
+use gitbrowser::types::errors::SyncError;
+use gitbrowser::types::reader::ReaderContent;
+use gitbrowser::types::sync::{
+    CollectionState, ConflictResolution, ConflictStrategy, SyncCollection, SyncPayload, SyncReport, VersionOrder,
+    VersionVector,
+};
+
+struct Device {
+    db: Arc<Database>,
+    settings: SettingsEngine,
+    sync: SyncService,
+    _dir: TempDir,
+}
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+fn device() -> Device {
+    let d = device_without_passphrase();
+    d.sync.set_passphrase(PASSPHRASE).unwrap();
+    d
+}
+
+fn device_without_passphrase() -> Device {
+    let dir = TempDir::new().unwrap();
+    let db = Arc::new(Database::open_in_memory().unwrap());
+    let settings = SettingsEngine::new(Some(dir.path().join("settings.json").to_string_lossy().to_string()));
+    Device {
+        sync: SyncService::new(db.clone()),
+        db,
+        settings,
+        _dir: dir,
+    }
+}
+
+#[derive(Default)]
+struct MemoryRemote {
+    content: Option<String>,
+    stores: usize,
+}
+
+impl SyncRemote for MemoryRemote {
+    fn fetch(&mut self) -> Result<Option<String>, SyncError> {
+        Ok(self.content.clone())
+    }
+
+    fn store(&mut self, content: &str) -> Result<(), SyncError> {
+        self.content = Some(content.to_string());
+        self.stores += 1;
+        Ok(())
+    }
+}
+
+fn sync(d: &mut Device, remote: &mut MemoryRemote) -> SyncReport {
+    d.sync.sync_now(&mut d.settings, remote).unwrap()
+}
+
+fn add_bookmark(d: &Device, url: &str) -> String {
+    BookmarkManager::new(d.db.connection()).add_bookmark(url, "Title", None).unwrap()
+}
+
+fn bookmark_urls(d: &Device) -> Vec<String> {
+    let mut urls: Vec<String> = BookmarkManager::new(d.db.connection())
+        .list_all_bookmarks()
+        .unwrap()
+        .into_iter()
+        .map(|b| b.url)
+        .collect();
+    urls.sort();
+    urls
+}
+
+fn versions(entries: &[(&str, u64)]) -> VersionVector {
+    VersionVector(entries.iter().map(|(d, n)| (d.to_string(), *n)).collect())
+}
+
+// ─── Version Vectors ───
+
+#[test]
+fn test_version_vector_compare() {
+    let a1 = versions(&[("a", 1)]);
+    let a2 = versions(&[("a", 2)]);
+    let a1b1 = versions(&[("a", 1), ("b", 1)]);
+    assert_eq!(a1.compare(&a1), VersionOrder::Equal);
+    assert_eq!(a2.compare(&a1), VersionOrder::Ahead);
+    assert_eq!(a1.compare(&a1b1), VersionOrder::Behind);
+    assert_eq!(a2.compare(&a1b1), VersionOrder::Concurrent);
+    assert_eq!(a2.merged(&a1b1), versions(&[("a", 2), ("b", 1)]));
+}
+
+#[test]
+fn test_merge_prefers_newer_side() {
+    let local = shortcuts_payload(versions(&[("a", 1)]), json!({"new_tab": "Ctrl+T"}), 0);
+    let remote = shortcuts_payload(versions(&[("a", 1), ("b", 1)]), json!({"new_tab": "Ctrl+N"}), 0);
+
+    let outcome = merge_payloads(&local, Some(&remote), "a", ConflictStrategy::Merge);
+    assert_eq!(outcome.apply, vec![SyncCollection::Shortcuts]);
+    assert_eq!(outcome.report.pulled, vec![SyncCollection::Shortcuts]);
+    assert!(!outcome.push);
+
+    let outcome = merge_payloads(&remote, Some(&local), "b", ConflictStrategy::Merge);
+    assert!(outcome.apply.is_empty());
+    assert!(outcome.push);
+}
+
+fn shortcuts_payload(versions: VersionVector, data: serde_json::Value, modified_at: i64) -> SyncPayload {
+    SyncPayload {
+        format: 1,
+        device_id: "x".to_string(),
+        collections: BTreeMap::from([(SyncCollection::Shortcuts, CollectionState { versions, data, modified_at })]),
+    }
+}
+
+#[test]
+fn test_concurrent_change_follows_strategy() {
+    // Local changed earlier than remote
+    let local = shortcuts_payload(versions(&[("a", 2), ("b", 1)]), json!({"new_tab": "Ctrl+T"}), 100);
+    let remote = shortcuts_payload(versions(&[("a", 1), ("b", 2)]), json!({"new_tab": "Ctrl+N"}), 200);
+    let data_after = |strategy| {
+        let outcome = merge_payloads(&local, Some(&remote), "a", strategy);
+        assert!(outcome.report.conflicts.is_empty());
+        assert_eq!(outcome.report.merged, vec![SyncCollection::Shortcuts]);
+        assert!(outcome.push);
+        outcome.payload.collections[&SyncCollection::Shortcuts].data.clone()
+    };
+    assert_eq!(data_after(ConflictStrategy::LastWriterWins), json!({"new_tab": "Ctrl+N"}));
+    assert_eq!(data_after(ConflictStrategy::LocalWins), json!({"new_tab": "Ctrl+T"}));
+    assert_eq!(data_after(ConflictStrategy::RemoteWins), json!({"new_tab": "Ctrl+N"}));
+
+    // Merge leaves a document for the user: nothing is applied or pushed
+    let outcome = merge_payloads(&local, Some(&remote), "a", ConflictStrategy::Merge);
+    assert_eq!(outcome.report.conflicts, vec![SyncCollection::Shortcuts]);
+    assert!(outcome.apply.is_empty());
+    assert!(!outcome.push);
+}
+
+#[test]
+fn test_merge_dedupes_bookmarks() {
+    let entry = |url: &str| json!({"url": url, "title": "T", "folder": ["Work"]});
+    let local = json!([entry("https://a.example"), entry("https://a.example")]);
+    let remote = json!([entry("https://b.example"), entry("https://a.example")]);
+    let merged = merge_data(SyncCollection::Bookmarks, &local, &remote);
+    assert_eq!(merged, json!([entry("https://a.example"), entry("https://b.example")]));
+}
+
+// ─── Sync Runs ───
+
+#[test]
+fn test_first_sync_pushes_encrypted_payload() {
+    let mut a = device();
+    let mut remote = MemoryRemote::default();
+    add_bookmark(&a, "https://example.com/secret-page");
+
+    let report = sync(&mut a, &mut remote);
+    assert!(report.pushed);
+    let content = remote.content.clone().unwrap();
+    assert!(!content.contains("secret-page"));
+
+    // Nothing changed since: no second upload
+    let report = sync(&mut a, &mut remote);
+    assert!(!report.pushed);
+    assert_eq!(remote.stores, 1);
+}
+
+#[test]
+fn test_second_device_receives_data() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    {
+        let mut mgr = BookmarkManager::new(a.db.connection());
+        let work = mgr.create_folder("Work", None).unwrap();
+        mgr.add_bookmark("https://docs.rs", "Docs", Some(&work)).unwrap();
+    }
+    a.settings.set_value("general.homepage", json!("https://start.example")).unwrap();
+    a.settings.set_value("shortcuts.new_tab", json!("Ctrl+Shift+T")).unwrap();
+    sync(&mut a, &mut remote);
+
+    let report = sync(&mut b, &mut remote);
+    assert!(report.pulled.contains(&SyncCollection::Settings));
+    assert!(report.pulled.contains(&SyncCollection::Shortcuts));
+    assert_eq!(b.settings.get_settings().general.homepage, "https://start.example");
+    assert_eq!(b.settings.get_settings().shortcuts["new_tab"], "Ctrl+Shift+T");
+
+    let mgr = BookmarkManager::new(b.db.connection());
+    let folders = mgr.list_folders().unwrap();
+    assert_eq!(folders.len(), 1);
+    assert_eq!(folders[0].name, "Work");
+    let docs = mgr.list_bookmarks(Some(&folders[0].id)).unwrap();
+    assert_eq!(docs[0].url, "https://docs.rs");
+}
+
+#[test]
+fn test_payload_key_comes_from_the_passphrase() {
+    let mut a = device();
+    let mut remote = MemoryRemote::default();
+    add_bookmark(&a, "https://example.com/shared");
+    sync(&mut a, &mut remote);
+
+    // The envelope carries what another device needs to derive the key
+    let envelope: serde_json::Value = serde_json::from_str(remote.content.as_deref().unwrap()).unwrap();
+    assert_eq!(envelope["kdf"]["algorithm"], "pbkdf2-sha256");
+    assert!(envelope["kdf"]["iterations"].as_u64().unwrap() >= 100_000);
+    assert!(!envelope["kdf"]["salt"].as_str().unwrap().is_empty());
+
+    let mut b = device_without_passphrase();
+    assert!(matches!(b.sync.sync_now(&mut b.settings, &mut remote), Err(SyncError::PassphraseRequired)));
+    b.sync.set_passphrase("a different passphrase").unwrap();
+    assert!(matches!(b.sync.sync_now(&mut b.settings, &mut remote), Err(SyncError::WrongPassphrase)));
+    assert!(bookmark_urls(&b).is_empty());
+
+    b.sync.set_passphrase(PASSPHRASE).unwrap();
+    sync(&mut b, &mut remote);
+    assert_eq!(bookmark_urls(&b), vec!["https://example.com/shared"]);
+    // Both sides hold the same data, so nothing is pushed back
+    assert_eq!(remote.stores, 1);
+}
+
+#[test]
+fn test_format_1_payload_is_replaced() {
+    let mut a = device();
+    add_bookmark(&a, "https://example.com/local");
+    let mut remote = MemoryRemote {
+        content: Some(json!({"format": 1, "ciphertext": "AAAA", "iv": "AAAA", "auth_tag": "AAAA"}).to_string()),
+        stores: 0,
+    };
+
+    let report = sync(&mut a, &mut remote);
+    assert!(report.pushed);
+    let envelope: serde_json::Value = serde_json::from_str(remote.content.as_deref().unwrap()).unwrap();
+    assert_eq!(envelope["format"], 2);
+    assert!(envelope["kdf"].is_object());
+}
+
+#[test]
+fn test_first_sync_keeps_local_bookmarks() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    add_bookmark(&a, "https://a.example");
+    add_bookmark(&b, "https://b.example");
+    sync(&mut a, &mut remote);
+
+    let report = sync(&mut b, &mut remote);
+    assert!(report.merged.contains(&SyncCollection::Bookmarks));
+    assert_eq!(bookmark_urls(&b), vec!["https://a.example", "https://b.example"]);
+
+    sync(&mut a, &mut remote);
+    assert_eq!(bookmark_urls(&a), bookmark_urls(&b));
+}
+
+#[test]
+fn test_deletion_propagates() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    let gone = add_bookmark(&a, "https://gone.example");
+    add_bookmark(&a, "https://kept.example");
+    sync(&mut a, &mut remote);
+    sync(&mut b, &mut remote);
+
+    BookmarkManager::new(a.db.connection()).remove_bookmark(&gone).unwrap();
+    sync(&mut a, &mut remote);
+    let report = sync(&mut b, &mut remote);
+    assert_eq!(report.pulled, vec![SyncCollection::Bookmarks]);
+    assert_eq!(bookmark_urls(&b), vec!["https://kept.example"]);
+}
+
+#[test]
+fn test_concurrent_changes_are_merged() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    sync(&mut a, &mut remote);
+    sync(&mut b, &mut remote);
+
+    add_bookmark(&a, "https://from-a.example");
+    add_bookmark(&b, "https://from-b.example");
+    sync(&mut a, &mut remote);
+    let report = sync(&mut b, &mut remote);
+    assert_eq!(report.merged, vec![SyncCollection::Bookmarks]);
+    assert!(report.pushed);
+
+    sync(&mut a, &mut remote);
+    let both = vec!["https://from-a.example", "https://from-b.example"];
+    assert_eq!(bookmark_urls(&a), both);
+    assert_eq!(bookmark_urls(&b), both);
+}
+
+#[test]
+fn test_change_during_exchange_is_kept() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    add_bookmark(&a, "https://from-a.example");
+    sync(&mut a, &mut remote);
+
+    // The exchange runs without the app lock, so the user can edit meanwhile
+    let run = b.sync.begin_sync(&b.settings).unwrap();
+    add_bookmark(&b, "https://during.example");
+    let merged = run.exchange(&mut remote).unwrap();
+    let report = b.sync.finish_sync(merged, &mut b.settings).unwrap();
+    assert!(!report.pulled.contains(&SyncCollection::Bookmarks));
+    assert_eq!(bookmark_urls(&b), vec!["https://during.example"]);
+
+    sync(&mut b, &mut remote);
+    assert_eq!(bookmark_urls(&b), vec!["https://during.example", "https://from-a.example"]);
+}
+
+#[test]
+fn test_reading_list_syncs_read_state() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    {
+        let mut mgr = ReadingListManager::new(a.db.connection());
+        let content = ReaderContent {
+            title: "Article".to_string(),
+            content: "<p>Body</p>".to_string(),
+            text_content: "Body".to_string(),
+            author: None,
+            publish_date: None,
+            site_name: None,
+            estimated_read_time_minutes: 1,
+            page_count: 1,
+        };
+        let id = mgr.save_article("https://news.example/a", &content, None).unwrap();
+        mgr.mark_read(&id, true).unwrap();
+    }
+    sync(&mut a, &mut remote);
+    sync(&mut b, &mut remote);
+
+    let (items, total) = ReadingListManager::new(b.db.connection()).list_items(false, 10, 0).unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(items[0].content_html, "<p>Body</p>");
+    assert!(items[0].read_at.is_some());
+}
+
+// ─── Conflicts ───
+
+/// Both devices change the homepage after a common sync; `b` syncs last.
+fn conflicting_homepages(a: &mut Device, b: &mut Device, remote: &mut MemoryRemote) -> SyncReport {
+    sync(a, remote);
+    sync(b, remote);
+    a.settings.set_value("general.homepage", json!("https://a.example")).unwrap();
+    b.settings.set_value("general.homepage", json!("https://b.example")).unwrap();
+    sync(a, remote);
+    sync(b, remote)
+}
+
+#[test]
+fn test_settings_conflict_waits_for_user() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    let report = conflicting_homepages(&mut a, &mut b, &mut remote);
+    assert_eq!(report.conflicts, vec![SyncCollection::Settings]);
+    assert_eq!(b.settings.get_settings().general.homepage, "https://b.example");
+
+    let conflicts = b.sync.conflicts().unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].collection, SyncCollection::Settings);
+    assert_eq!(conflicts[0].local["general"]["homepage"], "https://b.example");
+    assert_eq!(conflicts[0].remote["general"]["homepage"], "https://a.example");
+
+    // Still unresolved on the next run, and `a` keeps its value
+    assert_eq!(sync(&mut b, &mut remote).conflicts, vec![SyncCollection::Settings]);
+    sync(&mut a, &mut remote);
+    assert_eq!(a.settings.get_settings().general.homepage, "https://a.example");
+}
+
+#[test]
+fn test_keep_local_resolution_is_pushed() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    conflicting_homepages(&mut a, &mut b, &mut remote);
+
+    b.sync.resolve_conflict(SyncCollection::Settings, ConflictResolution::KeepLocal, &mut b.settings).unwrap();
+    assert!(b.sync.conflicts().unwrap().is_empty());
+    let report = sync(&mut b, &mut remote);
+    assert!(report.conflicts.is_empty());
+    assert!(report.pushed);
+
+    let report = sync(&mut a, &mut remote);
+    assert_eq!(report.pulled, vec![SyncCollection::Settings]);
+    assert_eq!(a.settings.get_settings().general.homepage, "https://b.example");
+}
+
+#[test]
+fn test_keep_remote_resolution_applies_remote() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    conflicting_homepages(&mut a, &mut b, &mut remote);
+
+    b.sync.resolve_conflict(SyncCollection::Settings, ConflictResolution::KeepRemote, &mut b.settings).unwrap();
+    assert_eq!(b.settings.get_settings().general.homepage, "https://a.example");
+    let report = sync(&mut b, &mut remote);
+    assert!(report.conflicts.is_empty());
+    assert!(report.pulled.is_empty());
+
+    let err = b.sync.resolve_conflict(SyncCollection::Settings, ConflictResolution::KeepRemote, &mut b.settings);
+    assert!(matches!(err, Err(SyncError::ConflictNotFound(_))));
+}
+
+#[test]
+fn test_strategy_setting_settles_conflicts() {
+    let (mut a, mut b) = (device(), device());
+    let mut remote = MemoryRemote::default();
+    // The strategy syncs like any other setting
+    a.settings.set_value("github.sync_conflict_strategy", json!("remote_wins")).unwrap();
+    let report = conflicting_homepages(&mut a, &mut b, &mut remote);
+    assert!(report.conflicts.is_empty());
+    assert_eq!(report.merged, vec![SyncCollection::Settings]);
+    assert_eq!(b.settings.get_settings().general.homepage, "https://a.example");
+}
@@ -1,4 +1,8 @@
-use gitbrowser::managers::tab_manager::{TabManager, TabManagerTrait};
+use gitbrowser::managers::tab_manager::{TabManager, TabManagerTrait, MAX_CLOSED_TABS, MEMORY_PRESSURE_IDLE_SECS};
+
+fn now() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
+}
 
 #[test]
 fn test_create_tab_returns_unique_ids() {
@@ -227,13 +231,36 @@ fn test_close_tabs_to_right() {
 
     mgr.close_tabs_to_right(&id1).unwrap();
     assert_eq!(mgr.tab_count(), 1);
-    assert_eq!(mgr.get_tab_order(), &[id1.clone()]);
+    assert_eq!(mgr.get_tab_order(), std::slice::from_ref(&id1));
 
     // Active should be id1 since id2 and id3 were removed
     // (id1 was already active)
     let _ = id2;
 }
 
+#[test]
+fn test_recently_closed_keeps_web_pages_newest_first() {
+    let mut mgr = TabManager::new();
+    let a = mgr.create_tab(Some("https://a.example/"), true);
+    let b = mgr.create_tab(Some("https://b.example/"), false);
+    let newtab = mgr.create_tab(None, false);
+    let again = mgr.create_tab(Some("https://a.example/"), false);
+
+    mgr.close_tab(&a).unwrap();
+    mgr.close_tab(&newtab).unwrap();
+    mgr.close_tab(&b).unwrap();
+    mgr.close_tab(&again).unwrap();
+    let urls: Vec<&str> = mgr.recently_closed().iter().map(|t| t.url.as_str()).collect();
+    assert_eq!(urls, ["https://a.example/", "https://b.example/"]);
+
+    for i in 0..MAX_CLOSED_TABS + 5 {
+        let id = mgr.create_tab(Some(&format!("https://{}.example/", i)), false);
+        mgr.close_tab(&id).unwrap();
+    }
+    assert_eq!(mgr.recently_closed().len(), MAX_CLOSED_TABS);
+    assert_eq!(mgr.recently_closed()[0].url, format!("https://{}.example/", MAX_CLOSED_TABS + 4));
+}
+
 #[test]
 fn test_suspend_and_resume_tab() {
     let mut mgr = TabManager::new();
@@ -291,3 +318,197 @@ fn test_close_active_tab_at_end_switches_to_previous() {
     assert_eq!(mgr.get_active_tab().unwrap().id, id2);
     let _ = id1;
 }
+
+#[test]
+fn test_suspend_timeout_defaults_and_updates() {
+    let mut mgr = TabManager::new();
+    assert_eq!(mgr.suspend_timeout_minutes(), 30);
+    mgr.set_suspend_timeout_minutes(0);
+    assert_eq!(mgr.suspend_timeout_minutes(), 0);
+}
+
+#[test]
+fn test_idle_background_tabs_are_suspended() {
+    let mut mgr = TabManager::new();
+    let active = mgr.create_tab(Some("https://a.com"), true);
+    let idle = mgr.create_tab(Some("https://b.com"), false);
+    let pinned = mgr.create_tab(Some("https://c.com"), false);
+    mgr.pin_tab(&pinned).unwrap();
+
+    assert!(mgr.suspend_idle_tabs(now(), false).is_empty());
+    let later = now() + 31 * 60;
+    assert_eq!(mgr.suspend_idle_tabs(later, false), vec![idle.clone()]);
+    assert!(mgr.is_suspended(&idle));
+    assert!(!mgr.is_suspended(&active));
+    assert!(!mgr.is_suspended(&pinned));
+    // Already suspended tabs are not reported twice
+    assert!(mgr.suspend_idle_tabs(later, false).is_empty());
+}
+
+#[test]
+fn test_memory_pressure_suspends_sooner_and_pinned_tabs_too() {
+    let mut mgr = TabManager::new();
+    let first = mgr.create_tab(Some("https://a.com"), true);
+    let pinned = mgr.create_tab(Some("https://b.com"), false);
+    mgr.pin_tab(&pinned).unwrap();
+    mgr.switch_tab(&pinned).unwrap();
+    mgr.create_tab(Some("https://c.com"), true);
+
+    assert!(mgr.suspend_idle_tabs(now(), true).is_empty());
+    let suspended = mgr.suspend_idle_tabs(now() + MEMORY_PRESSURE_IDLE_SECS, true);
+    assert_eq!(suspended.len(), 2);
+    assert!(suspended.contains(&first) && suspended.contains(&pinned));
+
+    // A timeout of 0 turns suspension off, memory pressure or not
+    let mut off = TabManager::new();
+    off.create_tab(None, true);
+    off.create_tab(None, false);
+    off.set_suspend_timeout_minutes(0);
+    assert!(off.suspend_idle_tabs(now() + 24 * 3600, true).is_empty());
+}
+
+#[test]
+fn test_crashed_tab_recovers_on_reload_or_navigation() {
+    let mut mgr = TabManager::new();
+    let id = mgr.create_tab(Some("https://example.com"), true);
+    mgr.mark_tab_crashed(&id).unwrap();
+    assert!(mgr.get_tab(&id).unwrap().crashed);
+    mgr.reload_crashed_tab(&id).unwrap();
+    assert!(!mgr.get_tab(&id).unwrap().crashed);
+    assert_eq!(mgr.get_tab(&id).unwrap().url, "https://example.com");
+
+    mgr.mark_tab_crashed(&id).unwrap();
+    mgr.update_tab_url(&id, "https://example.org").unwrap();
+    assert!(!mgr.get_tab(&id).unwrap().crashed);
+    assert!(mgr.mark_tab_crashed("missing").is_err());
+}
+
+#[test]
+fn test_blocked_requests_are_counted_per_page() {
+    let mut mgr = TabManager::new();
+    let id = mgr.create_tab(Some("https://example.com"), true);
+    let other = mgr.create_tab(Some("https://example.org"), false);
+    assert_eq!(mgr.count_blocked_request(&id).unwrap(), 1);
+    assert_eq!(mgr.count_blocked_request(&id).unwrap(), 2);
+    assert_eq!(mgr.get_tab(&other).unwrap().blocked_requests, 0);
+    let copy = mgr.duplicate_tab(&id).unwrap();
+    assert_eq!(mgr.get_tab(&copy).unwrap().blocked_requests, 0);
+
+    mgr.reset_blocked_requests(&id).unwrap();
+    assert_eq!(mgr.get_tab(&id).unwrap().blocked_requests, 0);
+    assert!(mgr.count_blocked_request("missing").is_err());
+}
+
+#[test]
+fn test_back_and_forward_follow_the_pages_shown() {
+    use gitbrowser::types::tab::HistoryStep;
+
+    let mut mgr = TabManager::new();
+    let id = mgr.create_tab(Some("about:newtab"), true);
+    assert!(!mgr.can_go_back(&id));
+    mgr.record_navigation(&id, "https://a.example/").unwrap();
+    mgr.record_navigation(&id, "https://b.example/").unwrap();
+    assert!(mgr.can_go_back(&id));
+    assert!(!mgr.can_go_forward(&id));
+
+    assert_eq!(mgr.go_back(&id).unwrap(), Some(HistoryStep::Traverse("https://a.example/".into())));
+    // The page coming back confirms the step rather than adding a page
+    mgr.record_navigation(&id, "https://a.example/").unwrap();
+    assert!(mgr.can_go_forward(&id));
+    // The page itself going forward is seen as such
+    mgr.record_navigation(&id, "https://b.example/").unwrap();
+    assert!(!mgr.can_go_forward(&id));
+    mgr.record_navigation(&id, "https://a.example/").unwrap();
+
+    // A new page drops the ones ahead
+    mgr.record_navigation(&id, "https://c.example/").unwrap();
+    assert!(!mgr.can_go_forward(&id));
+    assert_eq!(mgr.go_back(&id).unwrap(), Some(HistoryStep::Traverse("https://a.example/".into())));
+    assert_eq!(mgr.go_back(&id).unwrap(), Some(HistoryStep::Traverse("about:newtab".into())));
+    assert_eq!(mgr.go_back(&id).unwrap(), None);
+    assert!(mgr.go_back("missing").is_err());
+}
+
+#[test]
+fn test_history_from_before_a_new_webview_is_loaded() {
+    use gitbrowser::types::tab::HistoryStep;
+
+    let mut mgr = TabManager::new();
+    let id = mgr.create_tab(Some("https://a.example/"), true);
+    mgr.record_navigation(&id, "https://b.example/").unwrap();
+    mgr.record_navigation(&id, "https://c.example/").unwrap();
+    mgr.go_back(&id).unwrap();
+    // e.g. the tab was suspended and woken
+    mgr.restart_webview_history(&id).unwrap();
+
+    assert_eq!(mgr.go_back(&id).unwrap(), Some(HistoryStep::Load("https://a.example/".into())));
+    assert_eq!(mgr.go_forward(&id).unwrap(), Some(HistoryStep::Load("https://b.example/".into())));
+    assert_eq!(mgr.go_forward(&id).unwrap(), Some(HistoryStep::Load("https://c.example/".into())));
+    assert_eq!(mgr.go_forward(&id).unwrap(), None);
+
+    // A duplicate has the history but a webview of its own
+    let copy = mgr.duplicate_tab(&id).unwrap();
+    assert_eq!(mgr.go_back(&copy).unwrap(), Some(HistoryStep::Load("https://b.example/".into())));
+}
+
+#[test]
+fn test_tab_zoom_steps_and_clamps() {
+    use gitbrowser::types::tab::ZoomStep;
+
+    let mut mgr = TabManager::new();
+    let id = mgr.create_tab(Some("https://example.com"), true);
+    assert_eq!(mgr.get_tab(&id).unwrap().zoom, 1.0);
+    assert_eq!(ZoomStep::In.apply(1.0), 1.1);
+    assert_eq!(ZoomStep::Out.apply(1.0), 0.9);
+    assert_eq!(ZoomStep::In.apply(1.2), 1.25);
+    assert_eq!(ZoomStep::In.apply(5.0), 5.0);
+    assert_eq!(ZoomStep::Out.apply(0.25), 0.25);
+    assert_eq!(ZoomStep::Reset.apply(3.0), 1.0);
+
+    mgr.set_tab_zoom(&id, 1.25).unwrap();
+    assert_eq!(mgr.get_tab(&id).unwrap().zoom, 1.25);
+    let copy = mgr.duplicate_tab(&id).unwrap();
+    assert_eq!(mgr.get_tab(&copy).unwrap().zoom, 1.25);
+    mgr.set_tab_zoom(&id, 40.0).unwrap();
+    assert_eq!(mgr.get_tab(&id).unwrap().zoom, 5.0);
+    assert!(mgr.set_tab_zoom("missing", 1.0).is_err());
+}
+
+#[test]
+fn test_failed_load_is_kept_until_the_next_page() {
+    use gitbrowser::types::tab::{LoadError, LoadErrorKind};
+
+    let mut mgr = TabManager::new();
+    let id = mgr.create_tab(Some("https://a.example/"), true);
+    mgr.set_tab_loading(&id, true).unwrap();
+    assert!(mgr.get_tab(&id).unwrap().loading);
+
+    let error = LoadError {
+        url: "https://missing.example/".into(),
+        kind: LoadErrorKind::Dns,
+        detail: "Error resolving \u{201c}missing.example\u{201d}: Name or service not known".into(),
+    };
+    mgr.fail_tab_load(&id, error.clone()).unwrap();
+    let tab = mgr.get_tab(&id).unwrap();
+    assert_eq!(tab.url, "https://missing.example/");
+    assert!(!tab.loading);
+    assert_eq!(mgr.load_error(&id), Some(&error));
+    // The failed page is in the history, so back leaves it
+    assert!(mgr.can_go_back(&id));
+
+    mgr.update_tab_url(&id, "https://missing.example/").unwrap();
+    assert_eq!(mgr.load_error(&id), None);
+    assert!(mgr.fail_tab_load("missing", error).is_err());
+}
+
+#[test]
+fn test_load_error_kind_from_engine_messages() {
+    use gitbrowser::types::tab::LoadErrorKind;
+
+    let dns = "Error resolving \u{201c}x.invalid\u{201d}: Name or service not known";
+    assert_eq!(LoadErrorKind::from_message(dns), LoadErrorKind::Dns);
+    assert_eq!(LoadErrorKind::from_message("Unacceptable TLS certificate"), LoadErrorKind::Tls);
+    assert_eq!(LoadErrorKind::from_message("Socket I/O timed out"), LoadErrorKind::Timeout);
+    assert_eq!(LoadErrorKind::from_message("Could not connect: Connection refused"), LoadErrorKind::Connection);
+    assert_eq!(LoadErrorKind::from_message("Load request cancelled"), LoadErrorKind::Other);
+}
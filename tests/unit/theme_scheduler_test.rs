@@ -0,0 +1,110 @@
+//! Unit tests for the ThemeScheduler public API.
+//!
+//! These tests exercise fixed-time and sunrise/sunset scheduling, next
+//! transition lookup and tick de-duplication through `ThemeSchedulerTrait`.
+
+use gitbrowser::services::theme_scheduler::{sun_times, SunEvent, ThemeScheduler, ThemeSchedulerTrait};
+use gitbrowser::types::settings::ThemeMode;
+use gitbrowser::types::theme::{ScheduleMode, ThemeScheduleSettings};
+
+/// 2024-06-21 00:00 UTC.
+const SOLSTICE: i64 = 19_895 * 86_400;
+
+fn at(hours: i64, minutes: i64) -> i64 {
+    SOLSTICE + hours * 3600 + minutes * 60
+}
+
+fn schedule(mode: ScheduleMode) -> ThemeScheduleSettings {
+    ThemeScheduleSettings {
+        mode,
+        ..ThemeScheduleSettings::default()
+    }
+}
+
+#[test]
+fn test_parse_time() {
+    assert_eq!(ThemeScheduler::parse_time("07:30"), Some(450));
+    assert_eq!(ThemeScheduler::parse_time("0:00"), Some(0));
+    assert_eq!(ThemeScheduler::parse_time("24:00"), None);
+    assert_eq!(ThemeScheduler::parse_time("7"), None);
+}
+
+#[test]
+fn test_off_schedules_nothing() {
+    let scheduler = ThemeScheduler::new(ThemeScheduleSettings::default());
+    assert_eq!(scheduler.scheduled_mode(at(12, 0)), None);
+    assert_eq!(scheduler.next_transition(at(12, 0)), None);
+}
+
+#[test]
+fn test_fixed_times_use_local_offset() {
+    let mut scheduler = ThemeScheduler::new(schedule(ScheduleMode::FixedTimes));
+    assert_eq!(scheduler.scheduled_mode(at(6, 59)), Some(ThemeMode::Dark));
+    assert_eq!(scheduler.scheduled_mode(at(7, 0)), Some(ThemeMode::Light));
+    assert_eq!(scheduler.scheduled_mode(at(19, 0)), Some(ThemeMode::Dark));
+    assert_eq!(scheduler.next_transition(at(12, 0)), Some(at(19, 0)));
+    assert_eq!(scheduler.next_transition(at(20, 0)), Some(at(31, 0)));
+
+    // UTC+2: 06:00 UTC is 08:00 local
+    scheduler.set_utc_offset_minutes(120);
+    assert_eq!(scheduler.scheduled_mode(at(6, 0)), Some(ThemeMode::Light));
+    assert_eq!(scheduler.next_transition(at(12, 0)), Some(at(17, 0)));
+}
+
+#[test]
+fn test_fixed_times_wrapping_midnight_and_invalid() {
+    let mut scheduler = ThemeScheduler::new(ThemeScheduleSettings {
+        light_start: "22:00".to_string(),
+        dark_start: "06:00".to_string(),
+        ..schedule(ScheduleMode::FixedTimes)
+    });
+    assert_eq!(scheduler.scheduled_mode(at(23, 0)), Some(ThemeMode::Light));
+    assert_eq!(scheduler.scheduled_mode(at(12, 0)), Some(ThemeMode::Dark));
+
+    scheduler.update_settings(ThemeScheduleSettings {
+        light_start: "noon".to_string(),
+        ..schedule(ScheduleMode::FixedTimes)
+    });
+    assert_eq!(scheduler.scheduled_mode(at(12, 0)), None);
+}
+
+#[test]
+fn test_sunrise_sunset_london() {
+    let scheduler = ThemeScheduler::new(ThemeScheduleSettings {
+        latitude: 51.5,
+        longitude: -0.13,
+        ..schedule(ScheduleMode::SunriseSunset)
+    });
+    assert_eq!(scheduler.scheduled_mode(at(12, 0)), Some(ThemeMode::Light));
+    assert_eq!(scheduler.scheduled_mode(at(23, 0)), Some(ThemeMode::Dark));
+    assert_eq!(scheduler.scheduled_mode(at(2, 0)), Some(ThemeMode::Dark));
+
+    // Sunset is around 20:21 UTC on the solstice
+    let sunset = scheduler.next_transition(at(12, 0)).unwrap();
+    assert!((at(20, 10)..at(20, 30)).contains(&sunset), "sunset at {}", sunset);
+}
+
+#[test]
+fn test_polar_day_and_night() {
+    assert_eq!(sun_times(19_895, 78.2, 15.6), SunEvent::PolarDay);
+    assert_eq!(sun_times(19_713, 78.2, 15.6), SunEvent::PolarNight);
+
+    let scheduler = ThemeScheduler::new(ThemeScheduleSettings {
+        latitude: 78.2,
+        longitude: 15.6,
+        ..schedule(ScheduleMode::SunriseSunset)
+    });
+    assert_eq!(scheduler.scheduled_mode(at(0, 30)), Some(ThemeMode::Light));
+}
+
+#[test]
+fn test_tick_reports_only_transitions() {
+    let mut scheduler = ThemeScheduler::new(schedule(ScheduleMode::FixedTimes));
+    assert_eq!(scheduler.tick(at(8, 0)), Some(ThemeMode::Light));
+    assert_eq!(scheduler.tick(at(9, 0)), None);
+    assert_eq!(scheduler.tick(at(19, 30)), Some(ThemeMode::Dark));
+
+    // New settings re-apply the current mode on the next tick
+    scheduler.update_settings(schedule(ScheduleMode::FixedTimes));
+    assert_eq!(scheduler.tick(at(19, 31)), Some(ThemeMode::Dark));
+}
@@ -0,0 +1,154 @@
+//! Unit tests for staged update installs.
+//!
+//! A fake executable in a temp directory stands in for the running build;
+//! each `UpdateInstaller::new` reads the state back from disk, like a restart.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use gitbrowser::services::update_installer::{UpdateInstaller, UpdateInstallerTrait, MAX_UNCONFIRMED_LAUNCHES};
+use gitbrowser::types::errors::UpdateError;
+use gitbrowser::types::update::{InstallState, ReadyUpdate, StartupAction, UpdateChannel, UpdateInfo};
+
+fn setup() -> (TempDir, PathBuf) {
+    let dir = TempDir::new().unwrap();
+    let exe = dir.path().join("gitbrowser");
+    std::fs::write(&exe, "build 0.3.0").unwrap();
+    (dir, exe)
+}
+
+fn ready(path: &Path, version: &str) -> ReadyUpdate {
+    ReadyUpdate {
+        info: UpdateInfo {
+            version: version.to_string(),
+            changelog: String::new(),
+            download_url: String::new(),
+            signature_url: String::new(),
+            sha256: String::new(),
+            published_at: String::new(),
+            file_size: 0,
+            channel: UpdateChannel::Stable,
+        },
+        path: path.to_path_buf(),
+    }
+}
+
+/// A zip package holding `gitbrowser` plus a readme.
+fn zip_package(dir: &TempDir, contents: &str) -> PathBuf {
+    let path = dir.path().join("gitbrowser-0.4.0-linux-x86_64.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("README.md", options).unwrap();
+    zip.write_all(b"readme").unwrap();
+    zip.start_file("gitbrowser-0.4.0/gitbrowser", options).unwrap();
+    zip.write_all(contents.as_bytes()).unwrap();
+    zip.finish().unwrap();
+    path
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+/// Stages and applies 0.4.0, as the 0.3.0 build would on restart.
+fn install_040(dir: &TempDir, exe: &Path) {
+    let package = zip_package(dir, "build 0.4.0");
+    UpdateInstaller::new(exe).stage(&ready(&package, "0.4.0")).unwrap();
+    assert_eq!(UpdateInstaller::new(exe).on_startup("0.3.0").unwrap(), StartupAction::Relaunch);
+}
+
+// ─── Staging ───
+
+#[test]
+fn test_stage_unpacks_executable_next_to_exe() {
+    let (dir, exe) = setup();
+    let package = zip_package(&dir, "build 0.4.0");
+    let mut installer = UpdateInstaller::new(&exe);
+    installer.stage(&ready(&package, "0.4.0")).unwrap();
+
+    assert_eq!(read(&dir.path().join("gitbrowser.new")), "build 0.4.0");
+    assert_eq!(read(&exe), "build 0.3.0");
+    assert_eq!(installer.state().staged.as_deref(), Some("0.4.0"));
+    assert_eq!(UpdateInstaller::new(&exe).state(), installer.state());
+}
+
+#[test]
+fn test_stage_takes_raw_executables() {
+    let (dir, exe) = setup();
+    let package = dir.path().join("gitbrowser-0.4.0-linux-x86_64");
+    std::fs::write(&package, "build 0.4.0").unwrap();
+    UpdateInstaller::new(&exe).stage(&ready(&package, "0.4.0")).unwrap();
+    assert_eq!(read(&dir.path().join("gitbrowser.new")), "build 0.4.0");
+}
+
+#[test]
+fn test_stage_rejects_unusable_packages() {
+    let (dir, exe) = setup();
+    let tarball = dir.path().join("gitbrowser-0.4.0.tar.gz");
+    std::fs::write(&tarball, "tar").unwrap();
+    let mut installer = UpdateInstaller::new(&exe);
+    assert!(matches!(installer.stage(&ready(&tarball, "0.4.0")), Err(UpdateError::InstallFailed(_))));
+
+    let path = dir.path().join("other.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    zip.start_file("README.md", zip::write::SimpleFileOptions::default()).unwrap();
+    zip.finish().unwrap();
+    let err = installer.stage(&ready(&path, "0.4.0")).unwrap_err();
+    assert!(err.to_string().contains("package has no gitbrowser"), "{}", err);
+    assert_eq!(installer.state(), &InstallState::default());
+}
+
+// ─── Startup ───
+
+#[test]
+fn test_startup_swaps_in_staged_update() {
+    let (dir, exe) = setup();
+    assert_eq!(UpdateInstaller::new(&exe).on_startup("0.3.0").unwrap(), StartupAction::Continue);
+    install_040(&dir, &exe);
+
+    assert_eq!(read(&exe), "build 0.4.0");
+    assert_eq!(read(&dir.path().join("gitbrowser.old")), "build 0.3.0");
+    let mut installer = UpdateInstaller::new(&exe);
+    assert_eq!(installer.state().pending.as_deref(), Some("0.4.0"));
+    assert_eq!(installer.state().previous.as_deref(), Some("0.3.0"));
+
+    // The new build starts and confirms
+    assert_eq!(installer.on_startup("0.4.0").unwrap(), StartupAction::Continue);
+    installer.confirm_started().unwrap();
+    let installer = UpdateInstaller::new(&exe);
+    assert!(installer.state().pending.is_none());
+    assert_eq!(installer.state().previous.as_deref(), Some("0.3.0"));
+}
+
+#[test]
+fn test_unconfirmed_build_is_rolled_back() {
+    let (dir, exe) = setup();
+    install_040(&dir, &exe);
+
+    for _ in 0..MAX_UNCONFIRMED_LAUNCHES {
+        assert_eq!(UpdateInstaller::new(&exe).on_startup("0.4.0").unwrap(), StartupAction::Continue);
+    }
+    let action = UpdateInstaller::new(&exe).on_startup("0.4.0").unwrap();
+    assert_eq!(action, StartupAction::RolledBack { from: "0.4.0".to_string() });
+    assert_eq!(read(&exe), "build 0.3.0");
+
+    // The old build cleans up after the failed one
+    assert_eq!(UpdateInstaller::new(&exe).on_startup("0.3.0").unwrap(), StartupAction::Continue);
+    assert!(!dir.path().join("gitbrowser.rollback").exists());
+}
+
+#[test]
+fn test_manual_rollback_restores_previous_build() {
+    let (dir, exe) = setup();
+    install_040(&dir, &exe);
+    let mut installer = UpdateInstaller::new(&exe);
+    installer.on_startup("0.4.0").unwrap();
+    installer.confirm_started().unwrap();
+
+    installer.rollback().unwrap();
+    assert_eq!(read(&exe), "build 0.3.0");
+    assert_eq!(installer.state(), &InstallState::default());
+    assert!(matches!(installer.rollback(), Err(UpdateError::InstallFailed(_))));
+}
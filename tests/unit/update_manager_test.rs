@@ -0,0 +1,312 @@
+//! Unit tests for update downloads.
+//!
+//! Releases are signed at test time with a fresh Ed25519 key in minisign's
+//! format, and served by an in-memory fetch.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ring::rand::SystemRandom;
+use serde_json::{json, Value};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use tempfile::TempDir;
+
+use gitbrowser::services::update_manager::{
+    channel_switch_warning, compare_versions, release_channel, select_update, verify_signature, UpdateManager,
+    UpdateManagerTrait, UPDATE_PUBLIC_KEY,
+};
+use gitbrowser::types::errors::UpdateError;
+use gitbrowser::types::update::{UpdateChannel, UpdateInfo};
+
+const KEY_ID: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+const BINARY_URL: &str = "https://example.com/releases/gitbrowser-0.4.0-linux.tar.gz";
+const SIGNATURE_URL: &str = "https://example.com/releases/gitbrowser-0.4.0-linux.tar.gz.minisig";
+
+struct Signer {
+    pair: Ed25519KeyPair,
+}
+
+impl Signer {
+    fn new() -> Self {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Self { pair: Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap() }
+    }
+
+    fn public_key(&self) -> String {
+        let key = [b"Ed".as_slice(), &KEY_ID, self.pair.public_key().as_ref()].concat();
+        format!("untrusted comment: minisign public key 0807060504030201\n{}\n", BASE64.encode(key))
+    }
+
+    /// A `minisign -S -l` signature file for `data`.
+    fn sign(&self, data: &[u8]) -> String {
+        self.sign_as(b"Ed", data)
+    }
+
+    /// A `minisign -S` signature file for `data`, over its BLAKE2b-512 hash.
+    fn sign_prehashed(&self, data: &[u8]) -> String {
+        self.sign_as(b"ED", blake2b_simd::Params::new().hash_length(64).hash(data).as_bytes())
+    }
+
+    /// A signature file claiming algorithm `alg`, signing `signed` as is.
+    fn sign_as(&self, alg: &[u8], signed: &[u8]) -> String {
+        let sig = self.pair.sign(signed);
+        let trusted = "timestamp:1791000000\tfile:gitbrowser-0.4.0-linux.tar.gz";
+        let global = self.pair.sign(&[sig.as_ref(), trusted.as_bytes()].concat());
+        format!(
+            "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {}\n{}\n",
+            BASE64.encode([alg, &KEY_ID, sig.as_ref()].concat()),
+            trusted,
+            BASE64.encode(global.as_ref()),
+        )
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn release(data: &[u8]) -> UpdateInfo {
+    UpdateInfo {
+        version: "0.4.0".to_string(),
+        changelog: "Fixes".to_string(),
+        download_url: BINARY_URL.to_string(),
+        signature_url: SIGNATURE_URL.to_string(),
+        sha256: sha256_hex(data),
+        published_at: "2026-10-01T00:00:00Z".to_string(),
+        file_size: data.len() as u64,
+        channel: UpdateChannel::Stable,
+    }
+}
+
+fn serve(files: HashMap<&'static str, Vec<u8>>) -> impl FnMut(&str) -> Result<Vec<u8>, UpdateError> {
+    move |url| files.get(url).cloned().ok_or_else(|| UpdateError::NetworkError(format!("HTTP 404 for {}", url)))
+}
+
+fn manager(dir: &TempDir, signer: &Signer) -> UpdateManager {
+    UpdateManager::new().with_download_dir(dir.path()).with_public_key(&signer.public_key())
+}
+
+// ─── Signatures ───
+
+#[test]
+fn test_signature_verifies() {
+    let signer = Signer::new();
+    let data = b"release archive".to_vec();
+    assert!(verify_signature(&data, &signer.sign(&data), &signer.public_key()).is_ok());
+}
+
+#[test]
+fn test_prehashed_signature_verifies() {
+    let signer = Signer::new();
+    let data = b"release archive".to_vec();
+    let key = signer.public_key();
+    assert!(verify_signature(&data, &signer.sign_prehashed(&data), &key).is_ok());
+
+    let err = verify_signature(b"release archivE", &signer.sign_prehashed(&data), &key).unwrap_err();
+    assert!(err.to_string().contains("file signature does not match"), "{}", err);
+    // The algorithm tag decides what was signed, so it cannot be swapped
+    assert!(verify_signature(&data, &signer.sign_as(b"ED", &data), &key).is_err());
+    assert!(verify_signature(&data, &signer.sign_prehashed(&data).replacen("RUQ", "RWQ", 1), &key).is_err());
+}
+
+#[test]
+fn test_signature_rejects_tampering() {
+    let signer = Signer::new();
+    let data = b"release archive".to_vec();
+    let signature = signer.sign(&data);
+
+    let err = verify_signature(b"release archivE", &signature, &signer.public_key()).unwrap_err();
+    assert!(err.to_string().contains("file signature does not match"), "{}", err);
+
+    let edited = signature.replace("timestamp:1791000000", "timestamp:1791000001");
+    let err = verify_signature(&data, &edited, &signer.public_key()).unwrap_err();
+    assert!(err.to_string().contains("trusted comment"), "{}", err);
+
+    let other = Signer::new();
+    assert!(verify_signature(&data, &signature, &other.public_key()).is_err());
+}
+
+#[test]
+fn test_signature_rejects_malformed_files() {
+    let signer = Signer::new();
+    let data = b"release archive".to_vec();
+    let key = signer.public_key();
+    assert!(matches!(verify_signature(&data, "", &key), Err(UpdateError::SignatureInvalid(_))));
+    let truncated: String = signer.sign(&data).lines().take(2).collect::<Vec<_>>().join("\n");
+    assert!(matches!(verify_signature(&data, &truncated, &key), Err(UpdateError::SignatureInvalid(_))));
+
+    let unknown = signer.sign_as(b"Ex", &data);
+    let err = verify_signature(&data, &unknown, &key).unwrap_err();
+    assert!(err.to_string().contains("unknown signature algorithm"), "{}", err);
+}
+
+// ─── Downloads ───
+
+#[test]
+fn test_download_needs_a_signing_key() {
+    if UPDATE_PUBLIC_KEY.is_some() {
+        return;
+    }
+    let (dir, signer) = (TempDir::new().unwrap(), Signer::new());
+    let data = b"new build".to_vec();
+    let mut mgr = UpdateManager::new().with_download_dir(dir.path());
+    let fetch = serve(HashMap::from([(BINARY_URL, data.clone()), (SIGNATURE_URL, signer.sign(&data).into_bytes())]));
+    let err = mgr.download_update_with(&release(&data), fetch).unwrap_err();
+    assert!(err.to_string().contains("no update signing key"), "{}", err);
+    assert!(mgr.ready_update().is_none());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_verified_download_becomes_ready() {
+    let (dir, signer) = (TempDir::new().unwrap(), Signer::new());
+    let data = b"new build".to_vec();
+    let mut mgr = manager(&dir, &signer);
+    assert!(mgr.ready_update().is_none());
+
+    let fetch = serve(HashMap::from([(BINARY_URL, data.clone()), (SIGNATURE_URL, signer.sign(&data).into_bytes())]));
+    let path = mgr.download_update_with(&release(&data), fetch).unwrap();
+    assert_eq!(path, dir.path().join("gitbrowser-0.4.0-linux.tar.gz"));
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+
+    let ready = mgr.ready_update().unwrap();
+    assert_eq!(ready.info.version, "0.4.0");
+    assert_eq!(ready.path, path);
+}
+
+#[test]
+fn test_checksum_mismatch_is_refused() {
+    let (dir, signer) = (TempDir::new().unwrap(), Signer::new());
+    let data = b"new build".to_vec();
+    let mut info = release(&data);
+    info.sha256 = sha256_hex(b"other build");
+    let mut mgr = manager(&dir, &signer);
+
+    let fetch = serve(HashMap::from([(BINARY_URL, data.clone()), (SIGNATURE_URL, signer.sign(&data).into_bytes())]));
+    let err = mgr.download_update_with(&info, fetch).unwrap_err();
+    assert!(matches!(err, UpdateError::ChecksumMismatch(_)));
+    assert!(mgr.ready_update().is_none());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_bad_signature_is_refused() {
+    let (dir, signer) = (TempDir::new().unwrap(), Signer::new());
+    let data = b"new build".to_vec();
+    let mut mgr = manager(&dir, &signer);
+
+    // Right checksum, but signed by someone else
+    let forged = Signer::new().sign(&data).into_bytes();
+    let fetch = serve(HashMap::from([(BINARY_URL, data.clone()), (SIGNATURE_URL, forged)]));
+    let err = mgr.download_update_with(&release(&data), fetch).unwrap_err();
+    assert!(matches!(err, UpdateError::SignatureInvalid(_)));
+    assert!(mgr.ready_update().is_none());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    // A missing signature fails the download too
+    let fetch = serve(HashMap::from([(BINARY_URL, data.clone())]));
+    assert!(matches!(mgr.download_update_with(&release(&data), fetch), Err(UpdateError::NetworkError(_))));
+}
+
+#[test]
+fn test_download_rejects_unsafe_file_names() {
+    let (dir, signer) = (TempDir::new().unwrap(), Signer::new());
+    let data = b"new build".to_vec();
+    let mut info = release(&data);
+    info.download_url = "https://example.com/releases/".to_string();
+    let mut mgr = manager(&dir, &signer);
+    let fetch = serve(HashMap::new());
+    assert!(matches!(mgr.download_update_with(&info, fetch), Err(UpdateError::ParseError(_))));
+}
+
+// ─── Channels ───
+
+#[test]
+fn test_versions_order_by_semver_precedence() {
+    let ordered = ["0.3.0", "0.4.0-beta.1", "0.4.0-beta.2", "0.4.0-beta.10", "0.4.0-rc.1", "0.4.0", "v0.4.1"];
+    for pair in ordered.windows(2) {
+        assert_eq!(compare_versions(pair[0], pair[1]), Ordering::Less, "{} < {}", pair[0], pair[1]);
+    }
+    assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+    assert!(UpdateManager::is_newer_version("0.1.0", "0.2.0"));
+    assert!(!UpdateManager::is_newer_version("0.4.0", "0.4.0-rc.1"));
+}
+
+#[test]
+fn test_release_channel_from_tag() {
+    assert_eq!(release_channel("v0.4.0", false), UpdateChannel::Stable);
+    assert_eq!(release_channel("v0.4.0", true), UpdateChannel::Beta);
+    assert_eq!(release_channel("v0.4.0-rc.1", true), UpdateChannel::Beta);
+    assert_eq!(release_channel("v0.4.0-nightly.20261015", true), UpdateChannel::Nightly);
+}
+
+fn github_release(tag: &str, prerelease: bool) -> Value {
+    let name = format!("gitbrowser-{}-linux-x86_64.tar.gz", tag.trim_start_matches('v'));
+    json!({
+        "tag_name": tag,
+        "prerelease": prerelease,
+        "draft": false,
+        "body": format!("Notes for {}", tag),
+        "published_at": "2026-10-01T00:00:00Z",
+        "assets": [
+            {
+                "name": name,
+                "browser_download_url": format!("https://example.com/{}", name),
+                "size": 1024,
+                "digest": "sha256:abc123",
+            },
+            {
+                "name": format!("{}.minisig", name),
+                "browser_download_url": format!("https://example.com/{}.minisig", name),
+                "size": 300,
+            },
+        ],
+    })
+}
+
+#[test]
+fn test_select_update_filters_by_channel() {
+    let releases = json!([
+        github_release("v0.5.0-nightly.20261015", true),
+        github_release("v0.5.0-beta.1", true),
+        github_release("v0.4.0", false),
+        github_release("v0.3.0", false),
+    ]);
+    let pick = |channel| select_update(&releases, channel, "0.3.0", "linux-x86_64").map(|u| u.version);
+    assert_eq!(pick(UpdateChannel::Stable).as_deref(), Some("0.4.0"));
+    assert_eq!(pick(UpdateChannel::Beta).as_deref(), Some("0.5.0-beta.1"));
+    assert_eq!(pick(UpdateChannel::Nightly).as_deref(), Some("0.5.0-nightly.20261015"));
+
+    let update = select_update(&releases, UpdateChannel::Stable, "0.3.0", "linux-x86_64").unwrap();
+    assert_eq!(update.sha256, "abc123");
+    assert_eq!(update.signature_url, "https://example.com/gitbrowser-0.4.0-linux-x86_64.tar.gz.minisig");
+    assert_eq!(update.channel, UpdateChannel::Stable);
+
+    // Never offers an older build, nor one without an asset for the platform
+    assert!(select_update(&releases, UpdateChannel::Stable, "0.5.0-beta.1", "linux-x86_64").is_none());
+    assert!(select_update(&releases, UpdateChannel::Nightly, "0.3.0", "windows-x86_64").is_none());
+}
+
+#[test]
+fn test_select_update_skips_unsigned_and_draft_releases() {
+    let mut unsigned = github_release("v0.4.0", false);
+    unsigned["assets"].as_array_mut().unwrap().pop();
+    let mut draft = github_release("v0.4.1", false);
+    draft["draft"] = json!(true);
+    let releases = json!([draft, unsigned]);
+    assert!(select_update(&releases, UpdateChannel::Stable, "0.3.0", "linux-x86_64").is_none());
+}
+
+#[test]
+fn test_channel_switch_warns_about_downgrade() {
+    assert!(channel_switch_warning("0.3.0", UpdateChannel::Nightly).is_none());
+    assert!(channel_switch_warning("0.4.0-beta.1", UpdateChannel::Beta).is_none());
+    let warning = channel_switch_warning("0.5.0-nightly.20261015", UpdateChannel::Stable).unwrap();
+    assert!(warning.contains("nightly build"), "{}", warning);
+    assert!(warning.contains("stable channel"), "{}", warning);
+}